@@ -0,0 +1,23 @@
+use std::{fs, process::Command};
+
+// Records the commit cedar is built from for `cedar version`.
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+
+    if let Ok(head) = fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{}", reference);
+        }
+    }
+
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_owned())
+        .unwrap_or_else(|| String::from("unknown"));
+
+    println!("cargo:rustc-env=CEDAR_GIT_HASH={}", hash);
+}
@@ -1,5 +1,67 @@
-use crate::structure::{build::build, init::init, manifest::Manifest};
-use std::{env, error::Error, fmt::Display, fs, path::PathBuf, process};
+use crate::{
+    error::CedarError,
+    structure::{
+        abi::{self, SymbolFilter},
+        amalgamate::amalgamate,
+        audit::audit,
+        bench::{BenchOptions, Change},
+        build::{detect_compiler, BuildOptions, Emit},
+        cache, changelog,
+        check::{analyze, cycle_path, include_cycles},
+        config::Config,
+        container,
+        deps::{self, Update},
+        diagnostics::{OutputFormat, OutputOrder},
+        dist, doc,
+        expand::expand,
+        export::{export, CiProvider, ExportFormat},
+        generate::generate_module,
+        graph::{Graph, GraphFormat},
+        hooks,
+        ide::{ide, Editor},
+        info::Info,
+        install::install,
+        intercept::intercept,
+        license::{self, scaffold_license},
+        limits,
+        lint::{self, LintTool},
+        lockfile::LockedPackage,
+        manifest::{set_version, ProjectKind},
+        matrix::{matrix, Cell},
+        metadata::Metadata,
+        migrate::{migrate, MigrateSource},
+        notify,
+        package::package,
+        profile::{profile, ProfileTool},
+        project::{Project, ProjectError},
+        release, remote,
+        sbom::{sbom, SbomFormat},
+        sign,
+        stats::Stats,
+        subproject::{self, Changes},
+        sysdeps,
+        target::{TargetFilter, TargetKind},
+        task,
+        test::{export_ctest, ReportFormat, TestOptions, TestReport},
+        timeout,
+        timings::{self, TimingsFormat},
+        tooling::{scaffold_tooling, Tooling},
+        trace,
+        version::{set_header_version, Bump, Version},
+    },
+    style::{self, ColorChoice},
+};
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
 
 /// Custom error type for command line related errors.
 ///
@@ -7,23 +69,54 @@ use std::{env, error::Error, fmt::Display, fs, path::PathBuf, process};
 ///
 /// * 'InvalidCommand' - Raised when a command is given but is invalid.
 /// * 'MissingArgument' - Raised when a command is given that expects an
-///         argument but no argument is given.
-///         
+///   argument but no argument is given.
+/// * 'InvalidArgument' - Raised when an argument is given but is invalid.
+/// * 'UnknownCommand' - Raised when the command is not known, holds the
+///   closest known command if one is similar enough.
+/// * 'UnknownFlag' - Raised when a flag is not known, holds the closest known
+///   flag if one is similar enough.
+/// * 'Aborted' - Raised when the user declines a confirmation prompt, holds
+///   the question.
+///
 #[derive(Debug)]
 pub enum CliError {
     InvalidCommand,
     MissingArgument(&'static str),
+    InvalidArgument(String),
+    UnknownCommand(String, Option<&'static str>),
+    UnknownFlag(String, Option<&'static str>),
+    Aborted(String),
 }
 
 impl Display for CliError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CliError::InvalidCommand => {
-                write!(f, "Error: Invalid command was given.")
+                writeln!(f, "Error: Invalid command was given.")
             }
             CliError::MissingArgument(arg) => {
                 writeln!(f, "Error: Missing argument {}", arg)
             }
+            CliError::InvalidArgument(arg) => {
+                writeln!(f, "Error: Invalid argument {}", arg)
+            }
+            CliError::UnknownCommand(command, suggestion) => {
+                writeln!(f, "Error: Unknown command {:?}.", command)?;
+                match suggestion {
+                    Some(suggestion) => writeln!(f, " Did you mean `{}`?", suggestion),
+                    None => writeln!(f, " Run `cedar help` for a list of commands."),
+                }
+            }
+            CliError::Aborted(question) => {
+                writeln!(f, "Error: Aborted. \n {}", question)
+            }
+            CliError::UnknownFlag(flag, suggestion) => {
+                writeln!(f, "Error: Unknown flag {:?}.", flag)?;
+                match suggestion {
+                    Some(suggestion) => writeln!(f, " Did you mean `{}`?", suggestion),
+                    None => writeln!(f, " Run `cedar help` for a list of options."),
+                }
+            }
         }
     }
 }
@@ -35,15 +128,35 @@ impl Error for CliError {}
 /// # Fields
 ///
 /// * 'command' - An instance of the Command enum representing what part of the
-///         program to execute.
+///   program to execute.
 /// * 'path' - An optional PathBuf pointing to the project directory. It is
-///         optional because only the new command requires a path, the rest
-///         work in the current working directory.
+///   optional because only the new command requires a path, the rest
+///   work in the current working directory. The file of verify and
+///   of build --emit.
+/// * 'module' - The module path given to the generate command.
+/// * 'topic' - The command to print the help of (help).
+/// * 'pattern' - The pattern picking the tests to run (test).
+/// * 'export' - The build system to export to (export).
+/// * 'command_line' - The command to run and its arguments (intercept).
+/// * 'editor' - The editor to set the project up for (ide).
+/// * 'plugin' - The executable of a command cedar does not have, cedar-<name>
+///   on the PATH, run with command_line as its arguments.
+/// * 'bump' - How to change the version of the project (version, release,
+///   changelog).
+/// * 'flags' - The flags given after the command.
 ///
 #[derive(Clone)]
 pub struct Args {
     pub command: Commands,
     pub path: Option<PathBuf>,
+    pub module: Option<String>,
+    pub topic: Option<Commands>,
+    pub pattern: Option<String>,
+    pub export: Option<ExportFormat>,
+    pub command_line: Vec<String>,
+    pub editor: Option<Editor>,
+    pub plugin: Option<PathBuf>,
+    pub bump: Option<Bump>,
     pub flags: Vec<Flags>,
 }
 
@@ -55,14 +168,90 @@ pub struct Args {
 /// * 'New' - Intializes a project in the given relative or absolute path.
 /// * 'Build' - Compiles and links all the fiels in src and include.
 /// * 'Run' - Compiles/links and runs the program.
+/// * 'Task' - Runs a task of the manifest.
+/// * 'Generate' - Scaffolds a module (source, header, and test stub).
+/// * 'Amalgamate' - Combines the project into a single source and header.
+/// * 'Expand' - Prints a source of the project after the preprocessor.
+/// * 'Check' - Runs static analysis over the sources.
+/// * 'Lint' - Runs a linter over the sources.
+/// * 'AbiDiff' - Compares the exports of the shared library with an old
+///   build of it.
+/// * 'Symbols' - Lists the symbols of the artifacts.
+/// * 'Export' - Writes a build definition for another build system.
+/// * 'Migrate' - Writes a cedar.toml from the build definition of another
+///   build system.
+/// * 'Intercept' - Runs a build command, writing the compiles it runs to
+///   compile_commands.json.
+/// * 'Ide' - Writes the editor configuration of the project.
+/// * 'Install' - Builds the project and installs its artifacts under a prefix.
+/// * 'Package' - Builds the project into an archive, optionally signed.
+/// * 'Release' - Tests, tags, and packages a release of the project.
+/// * 'Changelog' - Adds the commits since the last tag to CHANGELOG.md.
+/// * 'Hooks' - Installs the git hooks of the manifest.
+/// * 'Verify' - Checks the signature of a file.
+/// * 'Doc' - Generates HTML documentation with Doxygen.
+/// * 'Metadata' - Prints a machine readable description of the project.
+/// * 'Info' - Prints an overview of the project.
+/// * 'Stats' - Prints statistics of the code and builds of the project.
+/// * 'Graph' - Prints the build graph of the project.
+/// * 'Test' - Builds and runs the tests in tests/.
+/// * 'Cov' - Runs the tests and reports the line coverage of the sources.
+/// * 'Bench' - Builds and times the benchmarks in benches/.
+/// * 'Profile' - Runs the program under a profiler.
+/// * 'Audit' - Checks the dependencies against known vulnerabilities.
+/// * 'License' - Reports the licenses of the dependencies.
+/// * 'Sbom' - Prints a software bill of materials.
+/// * 'Notices' - Writes the license notices of the dependencies.
+/// * 'Add' - Adds a git submodule as a dependency.
+/// * 'Update' - Updates the locked versions of the dependencies.
+/// * 'Outdated' - Lists the dependencies with newer versions.
+/// * 'Clean' - Removes the build directory.
+/// * 'Version' - Prints the version of cedar, or bumps the version of the
+///   project.
 /// * 'Help' - Displays the help message.
 ///
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Commands {
     Init,
     New,
     Build,
     Run,
+    Task,
+    Generate,
+    Amalgamate,
+    Expand,
+    Check,
+    Lint,
+    AbiDiff,
+    Symbols,
+    Export,
+    Migrate,
+    Intercept,
+    Ide,
+    Install,
+    Package,
+    Release,
+    Changelog,
+    Hooks,
+    Verify,
+    Doc,
+    Metadata,
+    Info,
+    Stats,
+    Graph,
+    Test,
+    Cov,
+    Bench,
+    Profile,
+    Audit,
+    License,
+    Sbom,
+    Notices,
+    Add,
+    Update,
+    Outdated,
+    Clean,
+    Version,
     Help,
 }
 
@@ -71,56 +260,1903 @@ pub enum Commands {
 /// # Members
 ///
 /// * 'Git' - Initalizes a git repositiory in the project.
+/// * 'Tooling' - Generates a tooling configuration file in the project.
+/// * 'HeaderOnly' - Creates a header-only library project.
+/// * 'License' - Scaffolds a LICENSE file from the given SPDX identifier.
+/// * 'Test' - Also generates a test stub (generate).
+/// * 'Prefix' - The directory to install into (install).
+/// * 'Open' - Opens the generated documentation in a browser (doc).
+/// * 'Builtin' - Uses the built in doc extractor instead of Doxygen (doc).
+/// * 'Format' - The output format (metadata, sbom, graph).
+/// * 'Color' - When to color the output (global).
+/// * 'Yes' - Confirms prompts without asking (global).
+/// * 'Offline' - Forbids network access (global).
+/// * 'Locked' - Forbids changes to cedar.lock (global).
+/// * 'TraceOutput' - Where to write a trace of the command (global).
+/// * 'Bin' - The binary to build or run (build, run).
+/// * 'Example' - The example to build or run (build, run).
+/// * 'Lib' - Builds the library (build).
+/// * 'Examples' - Builds every example (build).
+/// * 'AllTargets' - Builds every target, examples included (build).
+/// * 'Skip' - Skips the tests matching the pattern (test).
+/// * 'Jobs' - How many tests run at once (test).
+/// * 'NoCapture' - Prints the output of every test (test).
+/// * 'UpdateSnapshots' - Rewrites the test snapshots (test).
+/// * 'Export' - Writes a test file for another runner instead of testing
+///   (test).
+/// * 'FailUnder' - The minimum line coverage in percent (cov).
+/// * 'Iterations' - How many times each benchmark is timed (bench).
+/// * 'SaveBaseline' - Saves the results under the name (bench).
+/// * 'Baseline' - Compares the results against the named baseline (bench).
+/// * 'Tool' - The profiler to run the program under (profile).
+/// * 'Report' - Writes a test report in the format to the path, or stdout
+///   when there is no path (test).
+/// * 'Db' - An extra advisory database to check against (audit).
+/// * 'Package' - A dependency to update, every one when not given (update).
+/// * 'Submodule' - The repository to add as a submodule (add).
+/// * 'From' - The build system to migrate from (migrate).
+/// * 'Log' - A build log to migrate from instead of running make -n
+///   (migrate).
+/// * 'InContainer' - Builds inside a container of the image, or of the one
+///   in the manifest (build).
+/// * 'Remote' - The remote of the config to build on (build).
+/// * 'Sign' - Signs the archive (package).
+/// * 'Key' - The key to sign with or verify against (package, verify).
+/// * 'Provider' - The CI service to export a pipeline for (export).
+/// * 'Capture' - Writes the output of the program to the file (run).
+/// * 'Tee' - Writes the output of the program to the file and the terminal
+///   (run).
+/// * 'Timeout' - How long the program, or each test, may run before it is
+///   killed (run, test).
+/// * 'Wrap' - A command the program, or each test, is run with (run, test).
+/// * 'Emit' - What the build stops at instead of linking (build).
+/// * 'SaveTemps' - Keeps the intermediate files of the compiles (build).
+/// * 'PrintGcSections' - Prints the sections the linker drops (build).
+/// * 'Matrix' - Builds every combination of the [matrix] table (build).
+/// * 'Features' - The features to turn on (build, run, test).
+/// * 'NoDefaultFeatures' - Leaves the default features off (build, run,
+///   test).
+/// * 'OwnLines' - Prints only the lines of the file itself (expand).
+/// * 'Analyzer' - Runs gcc's static analyzer (check).
+/// * 'IncludeCycles' - Looks for headers including each other (check).
+/// * 'LintTool' - The linter to run (lint).
+/// * 'Fix' - Applies the suggestions of the linter (lint).
+/// * 'Unused' - Looks for sources and headers nothing uses (lint).
+/// * 'Undefined' - Lists the symbols left undefined instead (symbols).
+/// * 'Dynamic' - Lists only the dynamic symbol table (symbols).
 ///
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Flags {
     Git,
+    Tooling(Tooling),
+    HeaderOnly,
+    License(String),
+    Test,
+    Prefix(PathBuf),
+    Open,
+    Builtin,
+    Format(String),
+    Color(ColorChoice),
+    Yes,
+    Offline,
+    Locked,
+    Bin(String),
+    Example(String),
+    Lib,
+    Examples,
+    AllTargets,
+    Skip(String),
+    Jobs(usize),
+    NoCapture,
+    UpdateSnapshots,
+    FailUnder(f64),
+    Export(String),
+    Iterations(usize),
+    SaveBaseline(String),
+    Baseline(String),
+    Tool(ProfileTool),
+    Report(ReportFormat, Option<PathBuf>),
+    Db(PathBuf),
+    Package(String),
+    Submodule(String),
+    From(String),
+    Log(PathBuf),
+    InContainer(Option<String>),
+    Remote(String),
+    Recursive,
+    Since(String),
+    Explain,
+    Sign,
+    Key(PathBuf),
+    TraceOutput(PathBuf),
+    Timings(TimingsFormat),
+    MaxErrors(usize),
+    OutputOrder(OutputOrder),
+    OutputFormat(OutputFormat),
+    Release,
+    Profile(String),
+    Header(PathBuf),
+    Conventional,
+    Provider(CiProvider),
+    Capture(PathBuf),
+    Tee(PathBuf),
+    Timeout(Duration),
+    Wrap(String),
+    Emit(Emit),
+    SaveTemps,
+    PrintGcSections,
+    Matrix,
+    Features(Vec<String>),
+    NoDefaultFeatures,
+    OwnLines,
+    Analyzer,
+    IncludeCycles,
+    LintTool(LintTool),
+    Fix,
+    Unused,
+    Undefined,
+    Dynamic,
+}
+
+/// The definition of a command, used both to parse it and to print its help.
+///
+/// # Fields
+///
+/// * 'command' - The command it defines.
+/// * 'name' - The name it is invoked with.
+/// * 'aliases' - Other names it can be invoked with.
+/// * 'summary' - A one line description for the command list.
+/// * 'description' - A longer description for the help of the command.
+/// * 'args' - The positional arguments, in order.
+/// * 'flags' - The flags the command accepts, besides the global ones.
+/// * 'examples' - Example invocations.
+///
+pub struct CommandSpec {
+    pub command: Commands,
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub summary: &'static str,
+    pub description: &'static str,
+    pub args: &'static [ArgSpec],
+    pub flags: &'static [FlagSpec],
+    pub examples: &'static [&'static str],
+}
+
+/// A positional argument of a command.
+///
+/// # Fields
+///
+/// * 'name' - The name shown in the usage line.
+/// * 'required' - Whether the command fails without it.
+/// * 'help' - A description of the argument.
+///
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub required: bool,
+    pub help: &'static str,
+}
+
+/// A flag accepted by a command.
+///
+/// # Fields
+///
+/// * 'long' - The long form, e.g. --prefix.
+/// * 'short' - The short form, e.g. -g, if it has one.
+/// * 'value' - The name of the value the flag takes, None for switches. The
+///   value is either the next argument or given as --flag=value.
+/// * 'optional' - Whether the value can be left out, the next argument is
+///   then only taken as the value when it is not a flag.
+/// * 'help' - A description of the flag.
+///
+pub struct FlagSpec {
+    pub long: &'static str,
+    pub short: Option<&'static str>,
+    pub value: Option<&'static str>,
+    pub optional: bool,
+    pub help: &'static str,
+}
+
+impl FlagSpec {
+    const fn switch(long: &'static str, short: Option<&'static str>, help: &'static str) -> Self {
+        Self {
+            long,
+            short,
+            value: None,
+            optional: false,
+            help,
+        }
+    }
+    const fn value(long: &'static str, value: &'static str, help: &'static str) -> Self {
+        Self {
+            long,
+            short: None,
+            value: Some(value),
+            optional: false,
+            help,
+        }
+    }
+    const fn optional(long: &'static str, value: &'static str, help: &'static str) -> Self {
+        Self {
+            long,
+            short: None,
+            value: Some(value),
+            optional: true,
+            help,
+        }
+    }
+}
+
+/// Flags every command accepts.
+pub const GLOBAL_FLAGS: &[FlagSpec] = &[
+    FlagSpec::switch("--help", Some("-h"), "Prints the help of the command."),
+    FlagSpec::switch("--version", Some("-V"), "Prints the version of cedar."),
+    FlagSpec::value(
+        "--color",
+        "WHEN",
+        "Colors the output: auto (default), always, or\nnever. NO_COLOR also disables colors.",
+    ),
+    FlagSpec::switch(
+        "--yes",
+        Some("-y"),
+        "Confirms prompts without asking, e.g. in scripts.",
+    ),
+    FlagSpec::switch(
+        "--offline",
+        None,
+        "Forbids network access, dependencies must already\nbe downloaded. Also offline = true in the config.",
+    ),
+    FlagSpec::switch(
+        "--locked",
+        None,
+        "Fails instead of changing cedar.lock, and verifies\nthe checksums of git dependencies.",
+    ),
+    FlagSpec::value(
+        "--trace-output",
+        "PATH",
+        "Writes where the time of the command went inside\ncedar as a Chrome trace (chrome://tracing, Perfetto).",
+    ),
+];
+
+/// Flags shared by the commands that create a project.
+const NEW_FLAGS: &[FlagSpec] = &[
+    FlagSpec::switch("--git", Some("-g"), "Initializes a git repository."),
+    FlagSpec::switch(
+        "--with-tooling",
+        Some("-t"),
+        "Generates .clang-format, .clang-tidy, and\n.editorconfig files.",
+    ),
+    FlagSpec {
+        long: "--license",
+        short: Some("-l"),
+        value: Some("ID"),
+        optional: false,
+        help: "Writes a LICENSE file for the SPDX identifier and\nrecords it in the manifest.",
+    },
+    FlagSpec::switch(
+        "--clang-format",
+        None,
+        "Generates only the .clang-format file.",
+    ),
+    FlagSpec::switch("--clang-tidy", None, "Generates only the .clang-tidy file."),
+    FlagSpec::switch(
+        "--header-only",
+        None,
+        "Creates a header-only library, include/ alone\nwithout src/ or a link step.",
+    ),
+    FlagSpec::switch(
+        "--editorconfig",
+        None,
+        "Generates only the .editorconfig file.",
+    ),
+];
+
+/// Every command cedar has, in the order they are listed in the help.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        command: Commands::New,
+        name: "new",
+        aliases: &[],
+        summary: "Creates a new directory with the name/path given and\ninitializes it as a project.",
+        description: "Creates a new directory with the name/path given and initializes it\nas a project with src/, include/, build/, and a cedar.toml.",
+        args: &[ArgSpec {
+            name: "PATH",
+            required: true,
+            help: "The directory to create, its name is the project name.",
+        }],
+        flags: NEW_FLAGS,
+        examples: &[
+            "cedar new hello",
+            "cedar new hello --git --license MIT",
+            "cedar new vec --header-only",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Init,
+        name: "init",
+        aliases: &[],
+        summary: "Creates a new project in the current working directory.",
+        description: "Creates a new project in the current working directory, which must be\nempty.",
+        args: &[],
+        flags: NEW_FLAGS,
+        examples: &["cedar init", "cedar init --with-tooling"],
+    },
+    CommandSpec {
+        command: Commands::Build,
+        name: "build",
+        aliases: &[],
+        summary: "Compiles the project.",
+        description: "Compiles the translation units that changed since the last build and\nrelinks the artifacts that depend on them. Every target except the\nexamples is built unless targets are picked with the options.",
+        args: &[ArgSpec {
+            name: "FILE",
+            required: false,
+            help: "The source to emit, with --emit asm or preprocessed.",
+        }],
+        flags: &[
+            FlagSpec::value("--bin", "NAME", "Builds the binary with the given name."),
+            FlagSpec::switch("--lib", None, "Builds the library."),
+            FlagSpec::value(
+                "--example",
+                "NAME",
+                "Builds the example with the given name.",
+            ),
+            FlagSpec::switch("--examples", None, "Builds every example."),
+            FlagSpec::switch(
+                "--all-targets",
+                None,
+                "Builds every target, examples included.",
+            ),
+            FlagSpec::optional(
+                "--in-container",
+                "IMAGE",
+                "Builds inside a container of the image with docker\nor podman, container in [build] by default.",
+            ),
+            FlagSpec::value(
+                "--remote",
+                "NAME",
+                "Builds on the host of [remote.<NAME>] in the config\nover ssh and copies the artifacts back.",
+            ),
+            FlagSpec::switch(
+                "--recursive",
+                None,
+                "Builds the subprojects first, in the order of\n[subprojects], each after its own. Projects without\nchanges since their last recursive build are skipped.",
+            ),
+            FlagSpec::switch(
+                "--explain",
+                None,
+                "Prints why every recompiled source and relinked\nartifact was out of date.",
+            ),
+            FlagSpec::value(
+                "--since",
+                "REF",
+                "Builds recursively, skipping the projects without\nchanges since the git ref instead.",
+            ),
+            FlagSpec::optional(
+                "--timings",
+                "FORMAT",
+                "Reports how long every compile and link took, text\n(the default) prints the slowest, html writes\nbuild/timings/cedar-timing.html with a chart, the\ncritical path, and the history of recent builds.",
+            ),
+            FlagSpec::value(
+                "--max-errors",
+                "N",
+                "Shows at most N errors of the compiler, the rest are\nonly counted in the summary.",
+            ),
+            FlagSpec::value(
+                "--output-order",
+                "ORDER",
+                "Prints the output of parallel compiles as each one\nfinishes (finish, the default) or in the order they\nstarted (start).",
+            ),
+            FlagSpec::value(
+                "--output-format",
+                "FORMAT",
+                "Prints diagnostics as the compiler does (human, the\ndefault), as file:line:col: lines for editor quickfix\nlists (quickfix), or as GitHub Actions annotations\n(github).",
+            ),
+            FlagSpec::value(
+                "--emit",
+                "KIND",
+                "Stops after compiling and prints the paths of the\nobject files of the targets (obj), for a link\noutside of cedar, or of the assembly (asm) or\npreprocessed output (preprocessed) of FILE or of\nevery source, compiled with the project's flags.",
+            ),
+            FlagSpec::switch(
+                "--save-temps",
+                None,
+                "Keeps the preprocessed source, assembly, and other\nintermediate files of every compile under\nbuild/temps/<source>/.",
+            ),
+            FlagSpec::switch(
+                "--print-gc-sections",
+                None,
+                "Prints the sections the linker drops as unused, with\ngc_sections = true in the profile. Links again even\nwhen up to date.",
+            ),
+            FlagSpec::switch(
+                "--matrix",
+                None,
+                "Builds every profile with every compiler and\nselection of targets of [matrix] in cedar.toml,\nand prints a table of which ones pass.",
+            ),
+            FlagSpec::value(
+                "--features",
+                "LIST",
+                "Turns on the features of [features], separated by\ncommas, along with the default ones.",
+            ),
+            FlagSpec::switch(
+                "--no-default-features",
+                None,
+                "Leaves the default features off.",
+            ),
+            FlagSpec::switch("--release", None, "Builds with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Builds with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar build",
+            "cedar build --in-container=gcc:14",
+            "cedar build --remote box --bin tool",
+            "cedar build --recursive",
+            "cedar build --since main",
+            "cedar build --explain",
+            "cedar build --timings=html",
+            "cedar build --max-errors 5",
+            "cedar build --output-order=start",
+            "cedar build --output-format=github",
+            "cedar build --emit obj --lib",
+            "cedar build --emit asm src/main.c --release",
+            "cedar build --save-temps",
+            "cedar build --release --print-gc-sections",
+            "cedar build --matrix",
+            "cedar build --features tls,metrics",
+            "cedar build --release",
+            "cedar build --bin tool",
+            "cedar build --lib --examples",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Run,
+        name: "run",
+        aliases: &[],
+        summary: "Compiles then runs the project.",
+        description: "Compiles the project if needed, then runs its executable, a binary\nfrom src/bin/, or an example from examples/, with the arguments\nafter --. It reads the stdin of cedar, and cedar exits with its exit\ncode.\n\nWhen stdout is not a terminal, e.g. in a pipeline, the build prints\nnothing but the warnings of the compiler, on stderr, so stdout only\ncarries the output of the program.\n\n--capture writes the output of the program, stdout and stderr, to a\nfile instead of the terminal, --tee to both.",
+        args: &[ArgSpec {
+            name: "ARGS...",
+            required: false,
+            help: "The arguments of the program, after --.",
+        }],
+        flags: &[
+            FlagSpec::value("--bin", "NAME", "Runs the binary with the given name."),
+            FlagSpec::value(
+                "--example",
+                "NAME",
+                "Builds and runs the example with the given name.",
+            ),
+            FlagSpec::value(
+                "--features",
+                "LIST",
+                "Turns on the features of [features], separated by\ncommas, along with the default ones.",
+            ),
+            FlagSpec::switch(
+                "--no-default-features",
+                None,
+                "Leaves the default features off.",
+            ),
+            FlagSpec::switch("--release", None, "Builds with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Builds with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+            FlagSpec::value(
+                "--capture",
+                "FILE",
+                "Writes the output of the program to FILE instead\nof the terminal.",
+            ),
+            FlagSpec::value(
+                "--tee",
+                "FILE",
+                "Writes the output of the program to FILE as well\nas the terminal.",
+            ),
+            FlagSpec::value(
+                "--timeout",
+                "DURATION",
+                "Kills the program once it has run for DURATION,\ne.g. 30s, 500ms or 2m.",
+            ),
+            FlagSpec::value(
+                "--wrap",
+                "COMMAND",
+                "Runs the program with COMMAND, e.g. \"strace -f\",\noverriding wrapper in [run].",
+            ),
+        ],
+        examples: &[
+            "cedar run",
+            "cedar run -- --port 8080",
+            "cedar run --capture run.log",
+            "cedar run --wrap \"strace -f\"",
+            "cedar run --tee run.log | grep error",
+            "cedar run --bin tool",
+            "cedar run --example demo",
+            "cedar run --release",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Task,
+        name: "task",
+        aliases: &["run-task"],
+        summary: "Runs a task of [tasks] in cedar.toml, or lists them.",
+        description: "Runs the shell commands of a task in the project root, after the tasks\nit depends on, stopping at the first that fails:\n\n    [tasks]\n    gen = \"python scripts/gen.py\"\n    check = [\"cedar build\", \"cedar test\"]\n    bundle = { run = [\"./bundle.sh\"], depends = [\"gen\"], build = true }\n\nA task with build = true builds the project first, the commands find\nits artifacts in $CEDAR_OUT_DIR, built with the profile in\n$CEDAR_PROFILE. Arguments after the name are appended to the commands\nof the task.\n\nWithout a name, lists the tasks.",
+        args: &[
+            ArgSpec {
+                name: "NAME",
+                required: false,
+                help: "The task to run.",
+            },
+            ArgSpec {
+                name: "ARGS...",
+                required: false,
+                help: "Appended to the commands of the task.",
+            },
+        ],
+        flags: &[
+            FlagSpec::switch("--release", None, "Builds with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Builds with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar task",
+            "cedar task gen",
+            "cedar task bundle --release",
+            "cedar task gen -- --verbose",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Test,
+        name: "test",
+        aliases: &["t"],
+        summary: "Compiles and runs the tests in tests/.",
+        description: "Compiles every tests/<name>.c (or tests/<name>/ directory) into its\nown binary linked against the project's sources except src/main.c,\nruns them, and reports which passed. A test passes when it exits\nwith 0.\n\nTests can #include <cedar/test.h> and define cases with TEST(name)\nand the ASSERT macros instead of a main function, cedar then generates\nthe main that runs every case.\n\nA test with a tests/snapshots/<name>.txt also fails when its stdout\ndiffers from the snapshot.\n\nEvery test gets a fresh temporary directory in $CEDAR_TEST_TMPDIR\nholding a copy of the fixtures listed in [test] fixtures of\ncedar.toml, removed afterwards unless the test failed.",
+        args: &[ArgSpec {
+            name: "PATTERN",
+            required: false,
+            help: "Only runs the tests and TEST cases whose name\ncontains the pattern.",
+        }],
+        flags: &[
+            FlagSpec::value(
+                "--skip",
+                "PATTERN",
+                "Skips the tests and TEST cases whose name contains\nthe pattern, can be given more than once.",
+            ),
+            FlagSpec {
+                long: "--jobs",
+                short: Some("-j"),
+                value: Some("N"),
+                optional: false,
+                help: "Runs N tests at once, defaults to the number of\ncores.",
+            },
+            FlagSpec::switch(
+                "--nocapture",
+                None,
+                "Prints the output of every test, not only of the\nfailed ones.",
+            ),
+            FlagSpec::switch(
+                "--update-snapshots",
+                None,
+                "Rewrites tests/snapshots/<name>.txt with the stdout\nof the test instead of comparing them.",
+            ),
+            FlagSpec::value(
+                "--timeout",
+                "DURATION",
+                "Kills and fails a test once it has run for DURATION,\ne.g. 30s, overriding timeout in [test].",
+            ),
+            FlagSpec::value(
+                "--wrap",
+                "COMMAND",
+                "Runs every test with COMMAND, e.g. \"valgrind -q\",\noverriding wrapper in [run].",
+            ),
+            FlagSpec::value(
+                "--export",
+                "ctest",
+                "Builds the tests and writes build/CTestTestfile.cmake\nto run them with ctest instead of running them.",
+            ),
+            FlagSpec::value(
+                "--report",
+                "FORMAT[:PATH]",
+                "Writes a junit or tap report of the results to PATH.\nJUnit defaults to build/test-results.xml, TAP to\nstdout in place of the usual output.",
+            ),
+            FlagSpec::value(
+                "--features",
+                "LIST",
+                "Turns on the features of [features], separated by\ncommas, along with the default ones.",
+            ),
+            FlagSpec::switch(
+                "--no-default-features",
+                None,
+                "Leaves the default features off.",
+            ),
+            FlagSpec::switch("--release", None, "Builds the tests with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Builds the tests with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar test",
+            "cedar test parser_",
+            "cedar test --skip slow_",
+            "cedar test --report junit:build/test-results.xml",
+            "cedar test --profile asan",
+            "cedar test --timeout 30s",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Cov,
+        name: "cov",
+        aliases: &[],
+        summary: "Runs the tests and reports the line coverage.",
+        description: "Builds the tests with gcov instrumentation into build/coverage/, runs\nthem, and prints the line coverage of every source except\nsrc/main.c.\n\nWith min_coverage in the [test] table of cedar.toml, or --fail-under,\nexits with an error when the total coverage is below it.",
+        args: &[ArgSpec {
+            name: "PATTERN",
+            required: false,
+            help: "Only runs the tests and TEST cases whose name\ncontains the pattern.",
+        }],
+        flags: &[
+            FlagSpec::value(
+                "--fail-under",
+                "PERCENT",
+                "Fails when the line coverage is below PERCENT,\noverriding min_coverage.",
+            ),
+            FlagSpec::value(
+                "--skip",
+                "PATTERN",
+                "Skips the tests and TEST cases whose name contains\nthe pattern, can be given more than once.",
+            ),
+            FlagSpec {
+                long: "--jobs",
+                short: Some("-j"),
+                value: Some("N"),
+                optional: false,
+                help: "Runs N tests at once, defaults to the number of\ncores.",
+            },
+        ],
+        examples: &["cedar cov", "cedar cov --fail-under 80"],
+    },
+    CommandSpec {
+        command: Commands::Bench,
+        name: "bench",
+        aliases: &[],
+        summary: "Compiles and times the benchmarks in benches/.",
+        description: "Compiles every benches/<name>.c (or benches/<name>/ directory) into\nits own binary linked against the project's sources except\nsrc/main.c, runs each one several times after a warm up run, and\nprints the mean, median, and standard deviation of its run time.\n\nResults can be saved as a named baseline in build/baselines/, later\nruns compared against it mark significant changes (Welch's t-test).",
+        args: &[ArgSpec {
+            name: "PATTERN",
+            required: false,
+            help: "Only runs the benchmarks whose name contains the\npattern.",
+        }],
+        flags: &[
+            FlagSpec::value(
+                "--iterations",
+                "N",
+                "Times each benchmark N times, 10 by default.",
+            ),
+            FlagSpec::value(
+                "--save-baseline",
+                "NAME",
+                "Saves the results as the baseline NAME.",
+            ),
+            FlagSpec::value(
+                "--baseline",
+                "NAME",
+                "Compares the results against the baseline NAME.",
+            ),
+        ],
+        examples: &[
+            "cedar bench",
+            "cedar bench --save-baseline main",
+            "cedar bench --baseline main",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Profile,
+        name: "profile",
+        aliases: &[],
+        summary: "Runs the program under a profiler and prints the hottest\nfunctions.",
+        description: "Builds the program with debug info and frame pointers into\nbuild/profile/, runs it under a profiler, and prints the functions it\nspent the most time in.\n\nWith perf (the default) the samples are rendered into\nbuild/profile/flamegraph.svg. When perf is missing or not permitted to\nrecord, the program is built with -pg and sampled by gprof instead,\nwhose flamegraph has no callers. With callgrind or cachegrind the\nvalgrind output is kept as build/profile/<tool>.out, e.g. for\nkcachegrind.\n\nWith massif (--heap) the peak heap usage is printed along with the\nallocation sites holding it.",
+        args: &[],
+        flags: &[
+            FlagSpec::value("--bin", "NAME", "Profiles the binary with the given name."),
+            FlagSpec::value(
+                "--example",
+                "NAME",
+                "Profiles the example with the given name.",
+            ),
+            FlagSpec::value(
+                "--tool",
+                "TOOL",
+                "The profiler: perf, callgrind, cachegrind, or\nmassif.",
+            ),
+            FlagSpec::switch(
+                "--heap",
+                None,
+                "Profiles the heap with massif, the same as\n--tool massif.",
+            ),
+        ],
+        examples: &[
+            "cedar profile",
+            "cedar profile --bin tool",
+            "cedar profile --tool callgrind",
+            "cedar profile --heap",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Audit,
+        name: "audit",
+        aliases: &[],
+        summary: "Checks the dependencies in cedar.lock against known\nvulnerabilities.",
+        description: "Resolves the dependencies into cedar.lock and checks each one against\nthe advisory database bundled with cedar, an offline snapshot of\nknown vulnerabilities in commonly vendored libraries. A dependency is\naffected when its name matches an advisory and its version, or its\nlocked git commit, is one the advisory lists.\n\nFails when any dependency is affected, so it can gate CI.",
+        args: &[],
+        flags: &[FlagSpec::value(
+            "--db",
+            "FILE",
+            "Also checks against the advisories in FILE, in the\nformat of the bundled database.",
+        )],
+        examples: &["cedar audit", "cedar audit --db advisories.toml"],
+    },
+    CommandSpec {
+        command: Commands::License,
+        name: "license",
+        aliases: &[],
+        summary: "Prints the licenses of the dependencies, failing on denied\nones.",
+        description: "Resolves the dependencies and prints the license of each one, taken\nfrom its manifest or, when it has none, detected from its LICENSE or\nCOPYING file.\n\nFails when a dependency uses a license on the deny list of the\nmanifest:\n\n  [licenses]\n  deny = [\"GPL-3.0\", \"AGPL-3.0\"]",
+        args: &[],
+        flags: &[],
+        examples: &["cedar license"],
+    },
+    CommandSpec {
+        command: Commands::Sbom,
+        name: "sbom",
+        aliases: &[],
+        summary: "Prints a software bill of materials of the project and its\ndependencies.",
+        description: "Resolves the dependencies and prints a software bill of materials:\nthe project and every dependency with its version, license, the\nrepository and commit it was fetched from, and the SHA-256 of its\nsources.\n\nThe timestamp is taken from SOURCE_DATE_EPOCH when it is set.",
+        args: &[],
+        flags: &[FlagSpec::value(
+            "--format",
+            "FORMAT",
+            "cyclonedx (CycloneDX 1.5, the default) or spdx\n(SPDX 2.3), both JSON.",
+        )],
+        examples: &["cedar sbom > bom.json", "cedar sbom --format spdx"],
+    },
+    CommandSpec {
+        command: Commands::Notices,
+        name: "notices",
+        aliases: &[],
+        summary: "Writes the license texts of the dependencies to\nbuild/NOTICES.",
+        description: "Resolves the dependencies and concatenates the LICENSE, COPYING, and\nNOTICE files of each one into build/NOTICES, to ship alongside the\nprogram for attribution.\n\nWith embed_notices = true under [licenses] in the manifest, every\nbuild also compiles the notices into the program as\n\n  extern const char cedar_notices[];",
+        args: &[],
+        flags: &[],
+        examples: &["cedar notices"],
+    },
+    CommandSpec {
+        command: Commands::Add,
+        name: "add",
+        aliases: &[],
+        summary: "Adds a git submodule to the project as a dependency.",
+        description: "Adds the repository as a git submodule at the path and registers it\nin [dependencies] as a path dependency, so it builds as part of the\nproject. A fresh clone checks the submodule out on its first build.\n\nA submodule without a cedar.toml is built as a plain static library:\nits include/ (or the submodule itself) goes on the include path and\nthe sources in its src/ (or the whole submodule) are compiled. Adjust\ninclude and sources in cedar.toml when the library is laid out\ndifferently.",
+        args: &[ArgSpec {
+            name: "PATH",
+            required: true,
+            help: "Where to check the submodule out, relative to the\nproject root.",
+        }],
+        flags: &[FlagSpec {
+            long: "--submodule",
+            short: None,
+            value: Some("URL"),
+            optional: false,
+            help: "The repository to add.",
+        }],
+        examples: &["cedar add --submodule https://github.com/madler/zlib third_party/zlib"],
+    },
+    CommandSpec {
+        command: Commands::Update,
+        name: "update",
+        aliases: &[],
+        summary: "Updates the dependencies in cedar.lock to their newest\ncompatible versions.",
+        description: "Re-resolves the dependencies without their locked commits, so git\ndependencies move to the head of their branch, or to the newest tag\ntheir version requirement allows, prints what changed, and rewrites\ncedar.lock after asking for confirmation.\n\nDependencies pinned to a rev or tag stay where they are.",
+        args: &[],
+        flags: &[FlagSpec {
+            long: "--package",
+            short: Some("-p"),
+            value: Some("NAME"),
+            optional: false,
+            help: "Only updates the given dependency, may be repeated.",
+        }],
+        examples: &["cedar update", "cedar update -p zlib", "cedar update --yes"],
+    },
+    CommandSpec {
+        command: Commands::Outdated,
+        name: "outdated",
+        aliases: &[],
+        summary: "Lists the dependencies with newer versions than cedar.lock.",
+        description: "Asks the remote of every git dependency for its version tags and\nlists the dependencies with newer versions than the locked ones.\n\nThe compatible column is the newest version the manifest allows, which\ncedar update moves to. The latest column is a newer version still,\nwhich needs the version requirement (or the pinned tag) in the\nmanifest changed.",
+        args: &[],
+        flags: &[],
+        examples: &["cedar outdated"],
+    },
+    CommandSpec {
+        command: Commands::Generate,
+        name: "generate",
+        aliases: &["g"],
+        summary: "Generates a module, e.g. generate module net/socket\ncreates src/net/socket.c and include/net/socket.h.",
+        description: "Generates a module, a source file in src/ and a header in include/,\nwith an optional test stub in tests/.",
+        args: &[
+            ArgSpec {
+                name: "KIND",
+                required: true,
+                help: "What to generate, only module.",
+            },
+            ArgSpec {
+                name: "PATH",
+                required: true,
+                help: "The path of the module, e.g. net/socket.",
+            },
+        ],
+        flags: &[FlagSpec::switch(
+            "--test",
+            None,
+            "Also generates a test stub in tests/.",
+        )],
+        examples: &["cedar generate module net/socket", "cedar g module util --test"],
+    },
+    CommandSpec {
+        command: Commands::Amalgamate,
+        name: "amalgamate",
+        aliases: &[],
+        summary: "Combines the library into one .c and one .h file in\nbuild/amalgamation/.",
+        description: "Combines the library into one .c and one .h file in\nbuild/amalgamation/, inlining local includes.\n\nOnly library projects can be amalgamated.",
+        args: &[],
+        flags: &[],
+        examples: &["cedar amalgamate"],
+    },
+    CommandSpec {
+        command: Commands::Expand,
+        name: "expand",
+        aliases: &[],
+        summary: "Prints a source after the preprocessor, with the\nproject's flags.",
+        description: "Runs only the preprocessor over a source of the project, with the\ndefines, include directories, and other flags it compiles with, and\nprints the result, for debugging macros. --own-lines leaves out what\nthe headers it includes expand to.",
+        args: &[ArgSpec {
+            name: "FILE",
+            required: true,
+            help: "The source to preprocess.",
+        }],
+        flags: &[
+            FlagSpec::switch(
+                "--own-lines",
+                None,
+                "Prints only the lines coming from the file itself.",
+            ),
+            FlagSpec::switch("--release", None, "Expands with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Expands with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar expand src/parser.c",
+            "cedar expand src/parser.c --own-lines --release",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Check,
+        name: "check",
+        aliases: &[],
+        summary: "Runs static analysis over the sources.",
+        description: "Runs static analysis over every translation unit of the project, tests\nand examples included, with the flags of the build, and prints the\nissues grouped by kind. It fails when there are any.\n\n--analyzer runs gcc's -fanalyzer, which follows the paths through\nthe code for leaks, double frees, use after free, null dereferences,\nand the like, printing the events leading up to each issue. It needs\ngcc 10 or later, nothing else.\n\n--include-cycles follows the quoted includes of the headers in include/\nand src/ and prints every cycle among them, a.h -> b.h -> a.h. Set\ninclude_cycles = \"warn\" or \"error\" in [build] to have builds look for\nthem too. Every check runs when none is picked.",
+        args: &[],
+        flags: &[
+            FlagSpec::switch("--analyzer", None, "Runs gcc's static analyzer."),
+            FlagSpec::switch(
+                "--include-cycles",
+                None,
+                "Looks for headers including each other.",
+            ),
+            FlagSpec::switch("--release", None, "Checks with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Checks with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar check",
+            "cedar check --analyzer --release",
+            "cedar check --include-cycles",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Lint,
+        name: "lint",
+        aliases: &[],
+        summary: "Runs include-what-you-use over the sources, or looks for dead\nfiles.",
+        description: "Runs a linter over every translation unit of the project, tests and\nexamples included, with the flags of the build, and prints what it\nsuggests per file. It fails when there are suggestions, unless they\nare applied with --fix.\n\niwyu, include-what-you-use, suggests the includes a file is missing\nand those it does not need. --fix only removes a line that still\nreads as iwyu saw it, and adds the missing ones after the last\ninclude.\n\n--unused lists the sources none of whose symbols another source\nreferences, as nm lists the objects, and the headers no depfile names.\nA library's API counts as used when a test or example calls it, or\nthe exports file of [build] names it. It runs alone unless --tool\npicks a linter too.",
+        args: &[],
+        flags: &[
+            FlagSpec::value("--tool", "TOOL", "The linter, iwyu (the default)."),
+            FlagSpec::switch("--fix", None, "Applies the suggestions to the files."),
+            FlagSpec::switch(
+                "--unused",
+                None,
+                "Lists sources and headers nothing uses.",
+            ),
+            FlagSpec::switch("--release", None, "Lints with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Lints with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar lint",
+            "cedar lint --tool iwyu --fix",
+            "cedar lint --unused",
+        ],
+    },
+    CommandSpec {
+        command: Commands::AbiDiff,
+        name: "abi-diff",
+        aliases: &[],
+        summary: "Compares the exports of the shared library with an old\nbuild of it.",
+        description: "Builds the shared library of the project and compares the symbols it\nexports with those of an old build, the last release, to catch a\nbreak of the ABI before releasing. It fails when a symbol was removed,\nturned from a function to data or the other way, or is data of\nanother size. Added symbols are listed and do not fail.\n\nWhen abidiff of libabigail is installed its report is printed too,\ncomparing the types of functions and structures from the debug info\nof both libraries.",
+        args: &[ArgSpec {
+            name: "OLD",
+            required: true,
+            help: "The old shared library, e.g. libparser.so.1.2.0.",
+        }],
+        flags: &[
+            FlagSpec::switch("--release", None, "Builds with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Builds with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar abi-diff /usr/lib/libparser.so.1",
+            "cedar abi-diff old/libparser.so --release",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Symbols,
+        name: "symbols",
+        aliases: &[],
+        summary: "Lists the symbols the artifacts define or leave undefined.",
+        description: "Builds the project and lists the external symbols of each artifact\nwith nm, the type letter of nm first: T for a function, D, B, or R for\ndata, U for undefined. The symbols it defines are listed, or with\n--undefined those it references and leaves to another object or\nlibrary, for tracking down an undefined reference. --dynamic only\nlists the dynamic symbol table, what a shared library exports.\n\nPATTERN keeps the symbols whose name contains it, or matches it when\nit has the wildcards *, ?, or [...].",
+        args: &[ArgSpec {
+            name: "PATTERN",
+            required: false,
+            help: "Only lists the symbols matching the pattern.",
+        }],
+        flags: &[
+            FlagSpec::switch(
+                "--undefined",
+                None,
+                "Lists the undefined symbols instead.",
+            ),
+            FlagSpec::switch(
+                "--dynamic",
+                None,
+                "Lists only the dynamic symbol table.",
+            ),
+            FlagSpec::value("--bin", "NAME", "Lists the binary with the given name."),
+            FlagSpec::switch("--lib", None, "Lists the library."),
+            FlagSpec::switch("--release", None, "Builds with the release profile."),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Builds with the profile of [profile.<NAME>] in\ncedar.toml, or dev, release, or asan.",
+            ),
+        ],
+        examples: &[
+            "cedar symbols",
+            "cedar symbols --undefined --bin tool",
+            "cedar symbols --dynamic --lib 'parser_*'",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Export,
+        name: "export",
+        aliases: &[],
+        summary: "Writes a CMakeLists.txt, Makefile, flake.nix, Dockerfile, or CI\npipeline building the project.",
+        description: "Writes a build definition of the project next to cedar.toml, for\nbuilding it without cedar.\n\ncmake writes a CMakeLists.txt that builds the project the way cedar\ndoes, for IDEs and for projects consuming it with add_subdirectory.\nThe library sources become an object library every target links,\nwith the flags, defines, include directories, and link libraries of\nthe manifest. Dependencies become static libraries, system\ndependencies are found with pkg-config, and tests are registered\nwith CTest.\n\nmake writes a self-contained Makefile following the build plan of\ncedar, a rule per source with depfiles and a link rule per target, so\nthe sources can be built where cedar is not installed. make builds\nthe binaries and libraries, make examples, tests, and benches the\nrest, make check runs the tests.\n\nnix writes a flake.nix whose package builds the project with that\nMakefile and installs it like cedar install, with the stdenv of the\ncompiler at the version installed here, system dependencies from\nnixpkgs, and git dependencies fetched at their commit in cedar.lock.\nnix develop opens a shell with the same compiler and dependencies.\n\ndocker writes a multi-stage Dockerfile building the executables with\nthat Makefile in debian:bookworm, with the Debian packages of the\nsystem dependencies, and copying them with the libraries they load\ninto debian:bookworm-slim. The [package.docker] table of cedar.toml\nsets the ports to expose and other images.\n\nci writes a CI pipeline, a GitHub Actions workflow in\n.github/workflows/ci.yml or with --provider gitlab a .gitlab-ci.yml,\ninstalling cedar and building every profile with every compiler of\nthe [ci] table, the compiler of [build] in dev and release by\ndefault. --all-targets is built when there are examples or benches,\nand the tests run when there are tests. A lint job checks the\nformatting when the project has a .clang-format and runs clang-tidy\nwhen it has a .clang-tidy.\n\nThe file is marked as generated and rewritten on every export, a file\ncedar did not write is never overwritten.",
+        args: &[ArgSpec {
+            name: "FORMAT",
+            required: true,
+            help: "The build system to export to: cmake, make, nix,\ndocker, or ci.",
+        }],
+        flags: &[FlagSpec::value(
+            "--provider",
+            "SERVICE",
+            "The CI service of ci: github (the default) or\ngitlab.",
+        )],
+        examples: &[
+            "cedar export cmake",
+            "cedar export make",
+            "cedar export nix",
+            "cedar export docker",
+            "cedar export ci --provider gitlab",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Migrate,
+        name: "migrate",
+        aliases: &[],
+        summary: "Writes a cedar.toml for a project built with CMake or make.",
+        description: "Reads the CMakeLists.txt of the project, and of the directories it\nadds, and writes a cedar.toml carrying over its name, version,\ndefinitions, compile options, include directories, and link\nlibraries. Packages found with find_package or pkg-config become\n[system-deps].\n\nFor a Makefile, the commands make -n -B prints are read instead: the\nflags of every compile, the objects of every link, and the libraries\narchived with ar. With --log, the commands are read from a saved build\nlog, e.g. of make V=1, instead of running make.\n\nSources are left in place. cedar builds every source in src/ into\none library linked into src/main.c and each binary in src/bin/, so\nthe migration prints where to move each file, and what it could not\ncarry over.",
+        args: &[],
+        flags: &[
+            FlagSpec::value(
+                "--from",
+                "SYSTEM",
+                "The build system to migrate from: cmake or make.\nDetected from the files in the directory by default.",
+            ),
+            FlagSpec::value(
+                "--log",
+                "FILE",
+                "Reads the compiler commands from a build log\ninstead of running make -n.",
+            ),
+        ],
+        examples: &[
+            "cedar migrate",
+            "cedar migrate --from make",
+            "cedar migrate --log build.log",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Intercept,
+        name: "intercept",
+        aliases: &[],
+        summary: "Runs a build command and writes the compiles it runs to\ncompile_commands.json.",
+        description: "Runs a build command of any build system with wrappers in front of the\ncompilers on the PATH (cc, gcc, clang, c++, g++, and clang++) and of\nthose $CC and $CXX name, and writes every compile they see to\ncompile_commands.json in the current directory, for clangd,\nclang-tidy, and other tools reading it. The database is written even\nwhen the command fails.\n\nCompilers called by their full path other than through $CC or $CXX,\nas in builds configured by CMake, are not seen. CMake writes the\ndatabase itself with -DCMAKE_EXPORT_COMPILE_COMMANDS=ON.\n\nArguments after -- are passed to the command as they are.",
+        args: &[ArgSpec {
+            name: "COMMAND...",
+            required: true,
+            help: "The build command to run and its arguments.",
+        }],
+        flags: &[],
+        examples: &["cedar intercept make", "cedar intercept -- make -j8 all"],
+    },
+    CommandSpec {
+        command: Commands::Ide,
+        name: "ide",
+        aliases: &[],
+        summary: "Sets the project up for VS Code or clangd.",
+        description: "vscode writes .vscode/tasks.json, with tasks building, testing, and\nrunning the project with cedar and the errors of the compiler in the\nproblems panel, and .vscode/launch.json, debugging every binary,\nexample, and test with gdb (lldb on macOS and with clang) after\nbuilding it.\n\nclangd builds every target, writing compile_commands.json to the\nbuild directory, and writes a .clangd pointing clangd at it. Run it\nagain after adding sources or changing flags.\n\nThe files are marked as generated and rewritten every time, a file\ncedar did not write is never overwritten.",
+        args: &[ArgSpec {
+            name: "EDITOR",
+            required: true,
+            help: "The editor to set up: vscode or clangd.",
+        }],
+        flags: &[],
+        examples: &["cedar ide vscode", "cedar ide clangd"],
+    },
+    CommandSpec {
+        command: Commands::Doc,
+        name: "doc",
+        aliases: &[],
+        summary: "Generates HTML documentation in build/doc/ with Doxygen.",
+        description: "Generates HTML documentation in build/doc/ with Doxygen, using the\nproject's Doxyfile if it has one.",
+        args: &[],
+        flags: &[
+            FlagSpec::switch("--open", None, "Opens the documentation in a browser."),
+            FlagSpec::switch(
+                "--builtin",
+                None,
+                "Extracts /// comments from include/ instead of\nrunning Doxygen.",
+            ),
+        ],
+        examples: &["cedar doc --open", "cedar doc --builtin"],
+    },
+    CommandSpec {
+        command: Commands::Metadata,
+        name: "metadata",
+        aliases: &[],
+        summary: "Prints the manifest, targets, sources, and artifacts as\nJSON for tools.",
+        description: "Prints the manifest, targets, sources, and artifacts of the project\nas JSON for editors and scripts.",
+        args: &[],
+        flags: &[FlagSpec::value(
+            "--format",
+            "FORMAT",
+            "The output format, only json.",
+        )],
+        examples: &["cedar metadata", "cedar metadata --format json"],
+    },
+    CommandSpec {
+        command: Commands::Info,
+        name: "info",
+        aliases: &[],
+        summary: "Prints an overview of the project.",
+        description: "Prints an overview of the project: its name, version, and type, the\ncompiler and its version, the targets, the declared dependencies, how\nmany sources and translation units it has, the last build, and where\nthe artifacts go. Nothing is built.",
+        args: &[],
+        flags: &[],
+        examples: &["cedar info"],
+    },
+    CommandSpec {
+        command: Commands::Stats,
+        name: "stats",
+        aliases: &[],
+        summary: "Prints statistics of the code and builds of the project.",
+        description: "Prints statistics of the project: the lines of code in each directory,\nhow many translation units and headers it has, how long its recent\nbuilds took, how many of the logged builds failed, the hit rate of the\nobject cache, and the largest objects of the profile. Nothing is built.",
+        args: &[],
+        flags: &[
+            FlagSpec::switch(
+                "--release",
+                None,
+                "Lists the largest objects of the release profile.",
+            ),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Lists the largest objects of the profile of\n[profile.<NAME>] in cedar.toml.",
+            ),
+        ],
+        examples: &["cedar stats", "cedar stats --profile release"],
+    },
+    CommandSpec {
+        command: Commands::Graph,
+        name: "graph",
+        aliases: &[],
+        summary: "Prints the packages, targets, and sources as a graph.",
+        description: "Prints the build graph of the project: the project and its\ndependencies, its targets, and the translation units they compile, with\nhow they depend on each other.",
+        args: &[],
+        flags: &[FlagSpec::value(
+            "--format",
+            "FORMAT",
+            "The output format, dot (default) or json.",
+        )],
+        examples: &["cedar graph | dot -Tsvg -o graph.svg", "cedar graph --format json"],
+    },
+    CommandSpec {
+        command: Commands::Install,
+        name: "install",
+        aliases: &[],
+        summary: "Compiles the project and installs it, along with a\npkg-config file for libraries.",
+        description: "Compiles the project and installs its executables, libraries,\nheaders, and a pkg-config file for libraries under a prefix.",
+        args: &[],
+        flags: &[FlagSpec::value(
+            "--prefix",
+            "DIR",
+            "The directory to install into, defaults to\n/usr/local.",
+        )],
+        examples: &["cedar install", "cedar install --prefix ~/.local"],
+    },
+    CommandSpec {
+        command: Commands::Package,
+        name: "package",
+        aliases: &[],
+        summary: "Compiles the project into a tar.gz archive to distribute.",
+        description: "Compiles the project and archives what install would install into\nbuild/package/<name>-<version>.tar.gz (artifact_name in [package]\nrenames it) and prints its SHA-256. The archive is reproducible:\nentries are sorted, owned by root, and dated SOURCE_DATE_EPOCH (or\nthe epoch), so the same files always package to the same bytes.\n\nWith --sign the archive is signed with the key of [sign] in the\nconfig, an OpenSSH key (ssh-keygen -Y) or a minisign key, into\n<archive>.sig.",
+        args: &[],
+        flags: &[
+            FlagSpec::switch("--sign", None, "Signs the archive."),
+            FlagSpec::value(
+                "--key",
+                "PATH",
+                "The secret key to sign with instead of the one\nof the config.",
+            ),
+        ],
+        examples: &[
+            "cedar package",
+            "cedar package --sign",
+            "cedar package --sign --key ~/.ssh/release",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Release,
+        name: "release",
+        aliases: &[],
+        summary: "Tests, tags, and packages a release of the project.",
+        description: "Cuts a release in one go, stopping at the first step that fails:\n\n  1. Checks that the git tree has no uncommitted changes.\n  2. Bumps the version as cedar version does, when asked to, and\n     checks that its tag v<version> does not exist yet. A CHANGELOG.md\n     gets a section for the version as cedar changelog writes it,\n     unless it has one already.\n  3. Builds and tests the project with the release profile. When they\n     fail the version bump and changelog are undone.\n  4. Commits the version bump and changelog and creates the annotated\n     tag.\n  5. Packages the release build as cedar package does.",
+        args: &[ArgSpec {
+            name: "BUMP",
+            required: false,
+            help: "patch, minor, or major to bump that part of the\nversion, or the new version. Without it the current\nversion is released.",
+        }],
+        flags: &[
+            FlagSpec::value(
+                "--header",
+                "FILE",
+                "Updates the version macros of the header as well.",
+            ),
+            FlagSpec::switch(
+                "--conventional",
+                None,
+                "Groups the changelog by conventional commit type.",
+            ),
+            FlagSpec::switch("--sign", None, "Signs the archive."),
+            FlagSpec::value(
+                "--key",
+                "PATH",
+                "The secret key to sign with instead of the one\nof the config.",
+            ),
+        ],
+        examples: &[
+            "cedar release",
+            "cedar release minor",
+            "cedar release 2.0.0 --header include/app/version.h",
+            "cedar release patch --sign",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Changelog,
+        name: "changelog",
+        aliases: &[],
+        summary: "Adds the commits since the last tag to CHANGELOG.md.",
+        description: "Writes a section for the upcoming version to CHANGELOG.md, listing\nthe commits since the last tag, and creates the file if needed. The\nsection of the same version is replaced, others are kept as they are.\n\nWith --conventional the commits are grouped by their conventional\ncommit type (feat:, fix:, perf:, docs:), breaking changes (feat!:)\nfirst and the rest under Other changes.\n\ncedar release adds the section as part of a release when the project\nhas a CHANGELOG.md.",
+        args: &[ArgSpec {
+            name: "VERSION",
+            required: false,
+            help: "The version of the section, or patch, minor, or\nmajor for the version after a bump. Defaults to\nthe version of the project.",
+        }],
+        flags: &[FlagSpec::switch(
+            "--conventional",
+            None,
+            "Groups the commits by conventional commit type.",
+        )],
+        examples: &[
+            "cedar changelog",
+            "cedar changelog minor --conventional",
+            "cedar changelog 2.0.0",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Hooks,
+        name: "hooks",
+        aliases: &[],
+        summary: "Installs the git hooks of [hooks.git] in cedar.toml.",
+        description: "Writes a script into the hooks directory of the git repository for\nevery hook in [hooks.git], running its command lines in the project\nroot and stopping at the first that fails:\n\n    [hooks.git]\n    pre-commit = [\"cedar build\", \"cedar test\"]\n    pre-push = [\"cedar test --release\"]\n\nHooks installed by cedar are replaced without asking, others only\nafter confirming.",
+        args: &[ArgSpec {
+            name: "ACTION",
+            required: true,
+            help: "What to do, only install.",
+        }],
+        flags: &[],
+        examples: &["cedar hooks install", "cedar hooks install --yes"],
+    },
+    CommandSpec {
+        command: Commands::Verify,
+        name: "verify",
+        aliases: &[],
+        summary: "Checks the signature of a package or any other file.",
+        description: "Checks <FILE>.sig, made by cedar package --sign, against the public\nkey of public_key in [package] of the manifest, or the one given.",
+        args: &[ArgSpec {
+            name: "FILE",
+            required: true,
+            help: "The signed file.",
+        }],
+        flags: &[FlagSpec::value(
+            "--key",
+            "PATH",
+            "The public key to check against, an OpenSSH or\nminisign public key.",
+        )],
+        examples: &[
+            "cedar verify build/package/app-1.0.0.tar.gz",
+            "cedar verify app-1.0.0.tar.gz --key release.pub",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Clean,
+        name: "clean",
+        aliases: &[],
+        summary: "Removes everything in build/.",
+        description: "Removes everything in build/ after asking for confirmation, so the\nnext build starts from scratch.\n\nThe objects, dependencies, and artifacts of a build are kept in\nbuild/<target>/<profile>/, with --release or --profile only those of\nthe profile are removed.",
+        args: &[],
+        flags: &[
+            FlagSpec::switch(
+                "--release",
+                None,
+                "Only removes the artifacts of the release profile.",
+            ),
+            FlagSpec::value(
+                "--profile",
+                "NAME",
+                "Only removes the artifacts of the profile.",
+            ),
+        ],
+        examples: &["cedar clean", "cedar clean --yes", "cedar clean --release"],
+    },
+    CommandSpec {
+        command: Commands::Version,
+        name: "version",
+        aliases: &[],
+        summary: "Prints the version of cedar, or bumps the project's.",
+        description: "Prints the version of cedar, the commit it was built from, and the C\ncompiler it detected.\n\nWith an argument, sets version in [meta] of cedar.toml instead,\nleaving the rest of the file as it is, and prints the old and new\nversion. With --header, the version macros of a header are updated\nalong with it: <PREFIX>VERSION \"x.y.z\" and <PREFIX>VERSION_MAJOR,\n_MINOR, and _PATCH.",
+        args: &[ArgSpec {
+            name: "BUMP",
+            required: false,
+            help: "patch, minor, or major to bump that part of the\nversion of the project, or the new version.",
+        }],
+        flags: &[FlagSpec::value(
+            "--header",
+            "FILE",
+            "Updates the version macros of the header as well.",
+        )],
+        examples: &[
+            "cedar version",
+            "cedar --version",
+            "cedar version patch",
+            "cedar version 2.0.0-rc.1",
+            "cedar version minor --header include/app/version.h",
+        ],
+    },
+    CommandSpec {
+        command: Commands::Help,
+        name: "help",
+        aliases: &[],
+        summary: "Prints this message or the help of a command.",
+        description: "Prints the list of commands, or the help of the given command.",
+        args: &[ArgSpec {
+            name: "COMMAND",
+            required: false,
+            help: "The command to print the help of.",
+        }],
+        flags: &[],
+        examples: &["cedar help", "cedar help build"],
+    },
+];
+
+impl Commands {
+    /// The definition of the command.
+    pub fn spec(self) -> &'static CommandSpec {
+        COMMANDS
+            .iter()
+            .find(|spec| spec.command == self)
+            .expect("every command has a spec")
+    }
 }
 
+impl CommandSpec {
+    /// Finds the command invoked by the given name or alias.
+    pub fn find(name: &str) -> Option<&'static Self> {
+        COMMANDS
+            .iter()
+            .find(|spec| spec.name == name || spec.aliases.contains(&name))
+    }
+}
+
+/// Files to write and their new contents.
+type Edits = Vec<(PathBuf, String)>;
+
 impl Args {
-    // Gets the environment arguments and returns an Args struct with them.
+    /// Gets the environment arguments and returns an Args struct with them,
+    /// with the command expanded when it is an alias.
     pub fn get() -> Result<Self, CliError> {
+        Self::parse(expand_aliases(env::args().skip(1).collect(), aliases)?)
+    }
+    /// Parses the arguments after the program name against the command
+    /// definitions in COMMANDS.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, CliError> {
         let mut cli = Self {
             command: Commands::Help,
             path: None,
+            module: None,
+            topic: None,
+            pattern: None,
+            export: None,
+            command_line: Vec::new(),
+            editor: None,
+            plugin: None,
+            bump: None,
             flags: Vec::new(),
         };
 
-        let mut args = env::args().skip(1).enumerate();
+        let mut args = args.into_iter().peekable();
+
+        // The command is the first argument that is not a flag, global flags
+        // may come before it, e.g. cedar --color=never build.
+        let mut spec: Option<&'static CommandSpec> = None;
+        let mut positionals = Vec::new();
+        let mut help = false;
+        let mut version = false;
+
+        while let Some(arg) = args.next() {
+            // Everything after -- is an argument, e.g. the command to
+            // intercept.
+            if arg == "--" && spec.is_some() {
+                positionals.extend(args.by_ref());
+                break;
+            }
+
+            if !arg.starts_with('-') || arg == "-" {
+                match spec {
+                    Some(_) => positionals.push(arg),
+                    None => match find_command(arg.trim()) {
+                        Ok(found) => spec = Some(found),
+                        // Commands cedar does not have may be plugins, which
+                        // get every argument after their name as it is.
+                        Err(err) => match plugin(arg.trim()) {
+                            Some(path) => {
+                                cli.plugin = Some(path);
+                                cli.command_line = args.collect();
+                                return Ok(cli);
+                            }
+                            None => return Err(err),
+                        },
+                    },
+                }
+                continue;
+            }
+
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (arg.as_str(), None),
+            };
+
+            let flags = spec.map_or(&[][..], |spec| spec.flags);
+
+            let flag = match find_flag(flags, name) {
+                Some(flag) => flag,
+                None => {
+                    let names = flag_names(flags.iter().chain(GLOBAL_FLAGS));
+
+                    return Err(CliError::UnknownFlag(
+                        name.to_owned(),
+                        suggest(name, &names),
+                    ));
+                }
+            };
+
+            let value = match (flag.value, inline) {
+                (None, None) => None,
+                (None, Some(_)) => {
+                    return Err(CliError::InvalidArgument(format!(
+                        "{:?}, {} does not take a value.",
+                        arg, flag.long
+                    )))
+                }
+                (Some(_), Some(value)) => Some(value),
+                (Some(_), None) if flag.optional => args.next_if(|next| !next.starts_with('-')),
+                (Some(_), None) => match args.next() {
+                    Some(value) => Some(value),
+                    None => return Err(CliError::MissingArgument(flag.long)),
+                },
+            };
+
+            match flag.long {
+                "--help" => help = true,
+                "--version" => version = true,
+                "--yes" => cli.flags.push(Flags::Yes),
+                "--offline" => cli.flags.push(Flags::Offline),
+                "--locked" => cli.flags.push(Flags::Locked),
+                "--bin" => cli.flags.push(Flags::Bin(value.unwrap_or_default())),
+                "--example" => cli.flags.push(Flags::Example(value.unwrap_or_default())),
+                "--lib" => cli.flags.push(Flags::Lib),
+                "--skip" => cli.flags.push(Flags::Skip(value.unwrap_or_default())),
+                "--nocapture" => cli.flags.push(Flags::NoCapture),
+                "--update-snapshots" => cli.flags.push(Flags::UpdateSnapshots),
+                "--export" => cli.flags.push(Flags::Export(value.unwrap_or_default())),
+                "--iterations" => {
+                    let value = value.unwrap_or_default();
+
+                    match value.parse() {
+                        Ok(iterations) if iterations > 0 => {
+                            cli.flags.push(Flags::Iterations(iterations))
+                        }
+                        _ => {
+                            return Err(CliError::InvalidArgument(format!(
+                                "{:?}, --iterations expects a positive number.",
+                                value
+                            )))
+                        }
+                    }
+                }
+                "--save-baseline" => cli
+                    .flags
+                    .push(Flags::SaveBaseline(value.unwrap_or_default())),
+                "--baseline" => cli.flags.push(Flags::Baseline(value.unwrap_or_default())),
+                "--tool" if spec.is_some_and(|spec| spec.command == Commands::Lint) => cli
+                    .flags
+                    .push(Flags::LintTool(value.unwrap_or_default().parse()?)),
+                "--tool" => cli
+                    .flags
+                    .push(Flags::Tool(value.unwrap_or_default().parse()?)),
+                "--heap" => cli.flags.push(Flags::Tool(ProfileTool::Massif)),
+                "--fail-under" => {
+                    let value = value.unwrap_or_default();
+
+                    match value.parse() {
+                        Ok(minimum) if (0.0..=100.0).contains(&minimum) => {
+                            cli.flags.push(Flags::FailUnder(minimum))
+                        }
+                        _ => {
+                            return Err(CliError::InvalidArgument(format!(
+                                "{:?}, --fail-under expects a percentage.",
+                                value
+                            )))
+                        }
+                    }
+                }
+                "--report" => {
+                    let value = value.unwrap_or_default();
+
+                    // Only the colon after the format separates the path,
+                    // which may hold colons of its own, e.g. junit:C:\out.xml.
+                    let (format, path) = ["junit", "tap"]
+                        .into_iter()
+                        .find_map(|format| match value.strip_prefix(format)? {
+                            "" => Some((format, None)),
+                            rest => Some((format, Some(PathBuf::from(rest.strip_prefix(':')?)))),
+                        })
+                        .unwrap_or((value.as_str(), None));
+
+                    cli.flags.push(Flags::Report(format.parse()?, path));
+                }
+                "--jobs" => {
+                    let value = value.unwrap_or_default();
+
+                    match value.parse() {
+                        Ok(jobs) if jobs > 0 => cli.flags.push(Flags::Jobs(jobs)),
+                        _ => {
+                            return Err(CliError::InvalidArgument(format!(
+                                "{:?}, --jobs expects a positive number.",
+                                value
+                            )))
+                        }
+                    }
+                }
+                "--max-errors" => {
+                    let value = value.unwrap_or_default();
 
-        while let Some((i, arg)) = args.next() {
-            match (i, arg.trim()) {
-                (0, "init") => cli.command = Commands::Init,
-                (0, "new") => {
-                    let name = args.next();
+                    match value.parse() {
+                        Ok(max_errors) => cli.flags.push(Flags::MaxErrors(max_errors)),
+                        _ => {
+                            return Err(CliError::InvalidArgument(format!(
+                                "{:?}, --max-errors expects a number.",
+                                value
+                            )))
+                        }
+                    }
+                }
+                "--output-format" => cli
+                    .flags
+                    .push(Flags::OutputFormat(value.unwrap_or_default().parse()?)),
+                "--output-order" => cli
+                    .flags
+                    .push(Flags::OutputOrder(value.unwrap_or_default().parse()?)),
+                "--emit" => cli
+                    .flags
+                    .push(Flags::Emit(value.unwrap_or_default().parse()?)),
+                "--examples" => cli.flags.push(Flags::Examples),
+                "--all-targets" => cli.flags.push(Flags::AllTargets),
+                "--color" => cli
+                    .flags
+                    .push(Flags::Color(value.unwrap_or_default().parse()?)),
+                "--with-tooling" => cli
+                    .flags
+                    .extend(Tooling::ALL.iter().map(|tool| Flags::Tooling(*tool))),
+                "--git" => cli.flags.push(Flags::Git),
+                "--license" => cli.flags.push(Flags::License(value.unwrap_or_default())),
+                "--clang-format" => cli.flags.push(Flags::Tooling(Tooling::ClangFormat)),
+                "--clang-tidy" => cli.flags.push(Flags::Tooling(Tooling::ClangTidy)),
+                "--editorconfig" => cli.flags.push(Flags::Tooling(Tooling::EditorConfig)),
+                "--header-only" => cli.flags.push(Flags::HeaderOnly),
+                "--test" => cli.flags.push(Flags::Test),
+                "--open" => cli.flags.push(Flags::Open),
+                "--builtin" => cli.flags.push(Flags::Builtin),
+                "--format" => cli.flags.push(Flags::Format(value.unwrap_or_default())),
+                "--package" => cli.flags.push(Flags::Package(value.unwrap_or_default())),
+                "--submodule" => cli.flags.push(Flags::Submodule(value.unwrap_or_default())),
+                "--from" => cli.flags.push(Flags::From(value.unwrap_or_default())),
+                "--in-container" => cli.flags.push(Flags::InContainer(value)),
+                "--remote" => cli.flags.push(Flags::Remote(value.unwrap_or_default())),
+                "--recursive" => cli.flags.push(Flags::Recursive),
+                "--explain" => cli.flags.push(Flags::Explain),
+                "--save-temps" => cli.flags.push(Flags::SaveTemps),
+                "--print-gc-sections" => cli.flags.push(Flags::PrintGcSections),
+                "--matrix" => cli.flags.push(Flags::Matrix),
+                "--features" => cli.flags.push(Flags::Features(
+                    value
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(String::from)
+                        .collect(),
+                )),
+                "--no-default-features" => cli.flags.push(Flags::NoDefaultFeatures),
+                "--own-lines" => cli.flags.push(Flags::OwnLines),
+                "--analyzer" => cli.flags.push(Flags::Analyzer),
+                "--include-cycles" => cli.flags.push(Flags::IncludeCycles),
+                "--fix" => cli.flags.push(Flags::Fix),
+                "--unused" => cli.flags.push(Flags::Unused),
+                "--undefined" => cli.flags.push(Flags::Undefined),
+                "--dynamic" => cli.flags.push(Flags::Dynamic),
+                "--release" => cli.flags.push(Flags::Release),
+                "--conventional" => cli.flags.push(Flags::Conventional),
+                "--capture" => cli
+                    .flags
+                    .push(Flags::Capture(PathBuf::from(value.unwrap_or_default()))),
+                "--tee" => cli
+                    .flags
+                    .push(Flags::Tee(PathBuf::from(value.unwrap_or_default()))),
+                "--wrap" => cli.flags.push(Flags::Wrap(value.unwrap_or_default())),
+                "--timeout" => {
+                    let value = value.unwrap_or_default();
 
-                    if let Some((_, name)) = name {
-                        cli.path = Some(
-                            env::current_dir()
-                                .expect("Error: Invalid current directory.")
-                                .join(name.trim_start_matches("/")),
-                        );
-                        cli.command = Commands::New
-                    } else {
-                        return Err(CliError::MissingArgument("name after command new."));
+                    match timeout::parse(&value) {
+                        Some(timeout) => cli.flags.push(Flags::Timeout(timeout)),
+                        None => {
+                            return Err(CliError::InvalidArgument(format!(
+                                "{:?}, --timeout expects a duration such as 30s, 500ms or 2m.",
+                                value
+                            )))
+                        }
                     }
                 }
-                (0, "build") => cli.command = Commands::Build,
-                (0, "run") => cli.command = Commands::Run,
-                (0, "help") => cli.command = Commands::Help,
-                (0, _) => {
-                    return Err(CliError::InvalidCommand);
+                "--provider" => cli
+                    .flags
+                    .push(Flags::Provider(value.unwrap_or_default().parse()?)),
+                "--header" => cli
+                    .flags
+                    .push(Flags::Header(value.unwrap_or_default().into())),
+                "--profile" => cli.flags.push(Flags::Profile(value.unwrap_or_default())),
+                "--timings" => cli.flags.push(Flags::Timings(match value {
+                    Some(value) => value.parse()?,
+                    None => TimingsFormat::Text,
+                })),
+                "--trace-output" => cli
+                    .flags
+                    .push(Flags::TraceOutput(PathBuf::from(value.unwrap_or_default()))),
+                "--since" => cli.flags.push(Flags::Since(value.unwrap_or_default())),
+                "--log" => cli
+                    .flags
+                    .push(Flags::Log(PathBuf::from(value.unwrap_or_default()))),
+                "--db" => cli
+                    .flags
+                    .push(Flags::Db(PathBuf::from(value.unwrap_or_default()))),
+                "--sign" => cli.flags.push(Flags::Sign),
+                "--key" => cli
+                    .flags
+                    .push(Flags::Key(PathBuf::from(value.unwrap_or_default()))),
+                "--prefix" => cli
+                    .flags
+                    .push(Flags::Prefix(PathBuf::from(value.unwrap_or_default()))),
+                _ => unreachable!("flag {} has no parser", flag.long),
+            }
+        }
+
+        if help {
+            cli.topic = spec.map(|spec| spec.command);
+            return Ok(cli);
+        }
+
+        if version {
+            cli.command = Commands::Version;
+            return Ok(cli);
+        }
+
+        let spec = spec.unwrap_or(Commands::Help.spec());
+
+        if let Some(missing) = spec
+            .args
+            .iter()
+            .skip(positionals.len())
+            .find(|arg| arg.required)
+        {
+            return Err(CliError::MissingArgument(missing.name));
+        }
+
+        // A last argument ending in ... takes every remaining one.
+        let variadic = spec
+            .args
+            .last()
+            .is_some_and(|arg| arg.name.ends_with("..."));
+
+        if !variadic && positionals.len() > spec.args.len() {
+            return Err(CliError::InvalidArgument(format!(
+                "{:?}, {} takes {} argument(s).",
+                positionals[spec.args.len()],
+                spec.name,
+                spec.args.len()
+            )));
+        }
+
+        let mut positionals = positionals.into_iter();
+
+        match spec.command {
+            Commands::New => {
+                let name = positionals.next().unwrap_or_default();
+                cli.path = Some(PathBuf::from(name));
+            }
+            Commands::Generate => {
+                let kind = positionals.next().unwrap_or_default();
+
+                if kind != "module" {
+                    return Err(CliError::InvalidArgument(format!(
+                        "{:?}, generate only supports module.",
+                        kind
+                    )));
+                }
+
+                cli.module = positionals.next();
+            }
+            Commands::Hooks => {
+                let action = positionals.next().unwrap_or_default();
+
+                if action != "install" {
+                    return Err(CliError::InvalidArgument(format!(
+                        "{:?}, hooks only supports install.",
+                        action
+                    )));
+                }
+            }
+            Commands::Test | Commands::Cov | Commands::Bench | Commands::Symbols => {
+                cli.pattern = positionals.next()
+            }
+            Commands::Export => cli.export = positionals.next().map(|s| s.parse()).transpose()?,
+            Commands::Intercept | Commands::Task | Commands::Run => {
+                cli.command_line = positionals.collect()
+            }
+            Commands::Ide => cli.editor = positionals.next().map(|s| s.parse()).transpose()?,
+            Commands::Verify | Commands::Build | Commands::Expand | Commands::AbiDiff => {
+                cli.path = positionals.next().map(PathBuf::from)
+            }
+            Commands::Version | Commands::Release | Commands::Changelog => {
+                cli.bump = positionals
+                    .next()
+                    .map(|bump| {
+                        bump.parse().map_err(|_| {
+                            CliError::InvalidArgument(format!(
+                                "{:?}, expected patch, minor, major, or a version.",
+                                bump
+                            ))
+                        })
+                    })
+                    .transpose()?
+            }
+            Commands::Add => {
+                if !cli
+                    .flags
+                    .iter()
+                    .any(|flag| matches!(flag, Flags::Submodule(_)))
+                {
+                    return Err(CliError::MissingArgument("--submodule"));
                 }
-                (_, "--git") | (_, "-g") => {
-                    cli.flags.push(Flags::Git);
+
+                cli.path = positionals.next().map(PathBuf::from);
+            }
+            Commands::Help => {
+                if let Some(topic) = positionals.next() {
+                    cli.topic = Some(find_command(&topic)?.command);
                 }
-                (_, _) => {}
             }
+            _ => {}
         }
 
+        cli.command = spec.command;
+
         Ok(cli)
     }
-    pub fn exec(&self) -> Result<(), Box<dyn Error>> {
+    pub fn exec(&self) -> Result<(), CedarError> {
+        for flag in &self.flags {
+            if let Flags::Color(choice) = flag {
+                style::set_color(*choice);
+            }
+        }
+
+        if let Some(plugin) = &self.plugin {
+            return self.run_plugin(plugin);
+        }
+
+        let mut offline = self.flags.contains(&Flags::Offline);
+        let mut notify = false;
+
+        if !matches!(self.command, Commands::Help | Commands::Version) {
+            let config = Config::load()?;
+
+            offline |= config.offline;
+            notify = config.notify;
+            deps::set_network(config.net);
+            dist::set_config(config.dist);
+            cache::set_config(config.cache);
+        }
+
+        deps::set_offline(offline);
+        deps::set_locked(self.flags.contains(&Flags::Locked));
+
+        let trace_output = self.flags.iter().find_map(|flag| match flag {
+            Flags::TraceOutput(path) => Some(path),
+            _ => None,
+        });
+
+        if trace_output.is_some() {
+            trace::enable();
+        }
+
+        let started = Instant::now();
+        let result = self.dispatch();
+
+        if notify
+            && matches!(
+                self.command,
+                Commands::Build | Commands::Test | Commands::Cov
+            )
+        {
+            self.notify(&result, started.elapsed());
+        }
+
+        // The trace is written even when the command failed, to show where.
+        if let Some(path) = trace_output {
+            trace::write(path).map_err(|err| CedarError::io(path, err))?;
+        }
+
+        result
+    }
+    /// Runs an external command, with the project in the current directory
+    /// described in its environment: CEDAR_ROOT, CEDAR_MANIFEST,
+    /// CEDAR_BUILD_DIR, CEDAR_OUT_DIR, and CEDAR_PROFILE. CEDAR is the path of
+    /// cedar itself, for plugins calling back into it.
+    fn run_plugin(&self, plugin: &Path) -> Result<(), CedarError> {
+        let mut command = process::Command::new(plugin);
+        command.args(&self.command_line);
+
+        if let Ok(cedar) = env::current_exe() {
+            command.env("CEDAR", cedar);
+        }
+
+        if let Ok(project) = env::current_dir()
+            .map_err(ProjectError::from)
+            .and_then(Project::find)
+        {
+            command
+                .env("CEDAR_ROOT", &project.root)
+                .env("CEDAR_MANIFEST", project.manifest_path())
+                .env("CEDAR_BUILD_DIR", &project.build_dir)
+                .env("CEDAR_OUT_DIR", project.out_dir())
+                .env("CEDAR_PROFILE", &project.profile);
+        }
+
+        let status = command
+            .status()
+            .map_err(|err| CedarError::command(&command, err))?;
+
+        match status.success() {
+            true => Ok(()),
+            false => Err(CedarError::Exited {
+                program: plugin.to_path_buf(),
+                code: status.code(),
+            }),
+        }
+    }
+    /// Sends a desktop notification saying how a build or test run ended.
+    fn notify(&self, result: &Result<(), CedarError>, elapsed: Duration) {
+        let name = self.command.spec().name;
+        let project = env::current_dir()
+            .ok()
+            .and_then(|cwd| Project::find(cwd).ok())
+            .map(|project| format!("{} ", project.manifest.meta.name))
+            .unwrap_or_default();
+
+        let (title, body) = match result {
+            Ok(()) => (
+                format!("cedar {} {}succeeded", name, project),
+                format!("Finished in {:.1?}", elapsed),
+            ),
+            Err(err) => (
+                format!("cedar {} {}failed", name, project),
+                format!(
+                    "{} after {:.1?}",
+                    err.to_string()
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .trim_start_matches("Error: "),
+                    elapsed
+                ),
+            ),
+        };
+
+        notify::send(&title, &body);
+    }
+    /// Runs the command.
+    fn dispatch(&self) -> Result<(), CedarError> {
         match self.command {
             Commands::Init => {
                 self.init()?;
@@ -138,112 +2174,2913 @@ impl Args {
                 self.run()?;
                 Ok(())
             }
+            Commands::Task => {
+                self.task()?;
+                Ok(())
+            }
+            Commands::Generate => {
+                self.generate()?;
+                Ok(())
+            }
+            Commands::Amalgamate => {
+                self.amalgamate()?;
+                Ok(())
+            }
+            Commands::Expand => {
+                self.expand()?;
+                Ok(())
+            }
+            Commands::Check => {
+                self.check()?;
+                Ok(())
+            }
+            Commands::Lint => {
+                self.lint()?;
+                Ok(())
+            }
+            Commands::AbiDiff => {
+                self.abi_diff()?;
+                Ok(())
+            }
+            Commands::Symbols => {
+                self.symbols()?;
+                Ok(())
+            }
+            Commands::Export => {
+                self.export()?;
+                Ok(())
+            }
+            Commands::Migrate => {
+                self.migrate()?;
+                Ok(())
+            }
+            Commands::Intercept => {
+                self.intercept()?;
+                Ok(())
+            }
+            Commands::Ide => {
+                self.ide()?;
+                Ok(())
+            }
+            Commands::Install => {
+                self.install()?;
+                Ok(())
+            }
+            Commands::Package => {
+                self.package()?;
+                Ok(())
+            }
+            Commands::Release => {
+                self.release()?;
+                Ok(())
+            }
+            Commands::Changelog => {
+                self.changelog()?;
+                Ok(())
+            }
+            Commands::Hooks => {
+                self.hooks()?;
+                Ok(())
+            }
+            Commands::Verify => {
+                self.verify()?;
+                Ok(())
+            }
+            Commands::Doc => {
+                self.doc()?;
+                Ok(())
+            }
+            Commands::Metadata => {
+                self.metadata()?;
+                Ok(())
+            }
+            Commands::Info => {
+                self.info()?;
+                Ok(())
+            }
+            Commands::Stats => {
+                self.stats()?;
+                Ok(())
+            }
+            Commands::Graph => {
+                self.graph()?;
+                Ok(())
+            }
+            Commands::Test => {
+                self.test()?;
+                Ok(())
+            }
+            Commands::Cov => {
+                self.cov()?;
+                Ok(())
+            }
+            Commands::Bench => {
+                self.bench()?;
+                Ok(())
+            }
+            Commands::Profile => {
+                self.profile()?;
+                Ok(())
+            }
+            Commands::Audit => {
+                self.audit()?;
+                Ok(())
+            }
+            Commands::License => {
+                self.license_report()?;
+                Ok(())
+            }
+            Commands::Sbom => {
+                self.sbom()?;
+                Ok(())
+            }
+            Commands::Notices => {
+                self.notices()?;
+                Ok(())
+            }
+            Commands::Add => {
+                self.add()?;
+                Ok(())
+            }
+            Commands::Update => {
+                self.update()?;
+                Ok(())
+            }
+            Commands::Outdated => {
+                self.outdated()?;
+                Ok(())
+            }
+            Commands::Clean => {
+                self.clean()?;
+                Ok(())
+            }
+            Commands::Version => {
+                match &self.bump {
+                    Some(bump) => self.bump(bump)?,
+                    None => print!("{}", version()),
+                }
+                Ok(())
+            }
             Commands::Help => {
-                help();
+                match self.topic {
+                    Some(topic) => print!("{}", command_help(topic.spec())),
+                    None => print!("{}", help(&aliases(), &plugins())),
+                }
                 Ok(())
             }
         }
     }
     /// Initializes a new project in the current working directory.
-    fn init(&self) -> Result<(), Box<dyn Error>> {
+    fn init(&self) -> Result<(), CedarError> {
         let cwd = env::current_dir()?;
+        let non_empty = self.confirm_non_empty(&cwd)?;
+
+        let license = self.license(true)?;
 
-        println!("\n\t\x1b[32mCreating \x1b[0mCedar project here");
+        println!("\n\t{}Cedar project here", style::green("Creating "));
         println!("\t  -> Generating directories and manifest");
 
-        init(&cwd)?;
+        if non_empty {
+            Project::init_existing(&cwd, self.new_kind())?;
+        } else {
+            Project::init(&cwd, self.new_kind())?;
+        }
+
+        self.scaffold_tooling(&cwd)?;
+        Self::scaffold_license(&cwd, license)?;
 
         if self.flags.contains(&Flags::Git) {
             println!("\t  -> Initializing git \n");
 
-            process::Command::new("git")
-                .args(["init", "-b", "main"])
-                .stdout(process::Stdio::null())
-                .spawn()
-                .expect("Git failed to execute, is it installed?")
-                .wait()?;
+            let mut git = process::Command::new("git");
+            git.args(["init", "-b", "main"]);
+
+            git_init(git)?;
         }
 
-        println!("\t\x1b[1;32mFinished\x1b[0m");
+        println!("\t{}", style::header("Finished"));
         Ok(())
     }
+    /// The kind of project cedar new and cedar init create.
+    fn new_kind(&self) -> ProjectKind {
+        match self.flags.contains(&Flags::HeaderOnly) {
+            true => ProjectKind::HeaderOnly,
+            false => ProjectKind::Bin,
+        }
+    }
     /// Creates a new project at the given directory.
-    fn create_new(&self) -> Result<(), Box<dyn Error>> {
+    fn create_new(&self) -> Result<(), CedarError> {
+        let license = self.license(false)?;
+
+        let path = env::current_dir()?.join(self.path.as_ref().unwrap());
+        let non_empty = self.confirm_non_empty(&path)?;
+
         println!(
-            "\n\t\x1b[1;32mCreating \x1b[0m{:?} ({:?})",
-            self.path.as_ref().unwrap().file_name().unwrap(),
-            self.path.as_ref().unwrap()
+            "\n\t{}{:?} ({:?})",
+            style::header("Creating "),
+            path.file_name().unwrap_or_default(),
+            path
         );
         println!("\t  -> Generating directories and manifest.");
 
-        let path = self.path.clone().unwrap();
-
-        let path_str = path.clone().into_os_string();
-
-        if !path.is_dir() {
-            fs::create_dir_all(&path)?;
+        if non_empty {
+            Project::init_existing(&path, self.new_kind())?;
+        } else {
+            Project::create(&path, self.new_kind())?;
         }
 
-        init(&path)?;
+        self.scaffold_tooling(&path)?;
+        Self::scaffold_license(&path, license)?;
 
         if self.flags.contains(&Flags::Git) {
             println!("\t  -> Initializing git \n");
 
-            process::Command::new("git")
-                .args(["init", path_str.to_str().unwrap(), "-b", "main"])
-                .stdout(process::Stdio::null())
-                .spawn()
-                .expect("Git failed to execute, is it installed?")
-                .wait()?;
+            let mut git = process::Command::new("git");
+            git.arg("init").arg(&path).args(["-b", "main"]);
+
+            git_init(git)?;
         }
 
-        println!("\t\x1b[1;32mFinished\x1b[0m");
+        println!("\t{}", style::header("Finished"));
         Ok(())
     }
-    /// Compiles the project.
-    fn build(&self) -> Result<(), Box<dyn Error>> {
-        let cwd = env::current_dir()?;
-        build(cwd)?;
+    /// Generates the tooling configuration files requested by the flags.
+    fn scaffold_tooling(&self, path: &Path) -> Result<(), CedarError> {
+        let tools: Vec<Tooling> = self
+            .flags
+            .iter()
+            .filter_map(|flag| match flag {
+                Flags::Tooling(tool) => Some(*tool),
+                _ => None,
+            })
+            .collect();
+
+        if !tools.is_empty() {
+            println!("\t  -> Generating tooling configuration");
+            scaffold_tooling(path, &tools)?;
+        }
+
         Ok(())
     }
-    /// Compiles (if needed) and then runs the project.
-    fn run(&self) -> Result<(), Box<dyn Error>> {
-        let path = env::current_dir()?;
+    /// Resolves the SPDX identifier of the license for a new project from the
+    /// --license flag or the global config, optionally prompting the user when
+    /// neither is set. Returns None when no license should be scaffolded.
+    fn license(&self, prompt: bool) -> Result<Option<String>, CedarError> {
+        let config = Config::load()?;
+
+        let flag = self.flags.iter().find_map(|flag| match flag {
+            Flags::License(id) => Some(id.clone()),
+            _ => None,
+        });
 
-        let manifest_path = path.join("cedar.toml");
-        let build_path = path.join("build/");
+        let id = match flag.or(config.new.license) {
+            Some(id) => Some(id),
+            None if prompt && io::stdin().is_terminal() => prompt_license()?,
+            None => None,
+        };
 
-        let manifest_file = fs::read_to_string(manifest_path)?;
-        let manifest = Manifest::parse(&manifest_file)?;
+        match id {
+            Some(id)
+                if !license::supported()
+                    .iter()
+                    .any(|s| s.eq_ignore_ascii_case(&id)) =>
+            {
+                Err(ProjectError::UnknownLicense(id).into())
+            }
+            id => Ok(id),
+        }
+    }
+    /// Writes the license resolved by Args::license into the project, using
+    /// the author from the global config as the copyright holder.
+    fn scaffold_license(path: &Path, license: Option<String>) -> Result<(), CedarError> {
+        if let Some(id) = license {
+            println!("\t  -> Writing {} license", id);
+            let config = Config::load()?;
+            scaffold_license(path, &id, config.new.author.as_deref())?;
+        }
 
-        let output_path = build_path.join(manifest.meta.name);
+        Ok(())
+    }
+    /// Generates a module in the project in the current working directory.
+    fn generate(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let module = self.module.as_ref().unwrap();
 
-        build(&path)?;
+        println!("\n\t{}module {}", style::header("Generating "), module);
 
-        let output_str = output_path.to_str().unwrap();
+        let files = generate_module(&project, module, self.flags.contains(&Flags::Test))?;
 
-        process::Command::new(output_str)
-            .spawn()
-            .expect("Error: Could not run executable.")
-            .wait()?;
+        for file in files {
+            println!(
+                "\t  -> Created {}",
+                file.strip_prefix(&project.root).unwrap_or(&file).display()
+            );
+        }
 
+        println!("\t{}", style::header("Finished"));
         Ok(())
     }
-}
+    /// Combines the project in the current working directory into a single
+    /// source file and header.
+    fn amalgamate(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
 
-pub fn help() {
-    println!(
-        "
-  A C project manager.
-
-  \x1b[1;32mUsage:\x1b[0m cedar [COMMAND] [OPTIONS]
-
-  \x1b[1;32mCommands:\x1b[0m
-    \x1b[1m new      \x1b[0m Creates a new directory with the name/path given and 
-                    initializes it as a project.
-    \x1b[1m init     \x1b[0m Creates a new project in the current working directory.
-    \x1b[1m build    \x1b[0m Compiles the project.
-    \x1b[1m run      \x1b[0m Compiles then runs the project.
-"
-    );
+        println!(
+            "\n\t{}project ({:?})",
+            style::header("Amalgamating "),
+            &project.root
+        );
+
+        let (source, header) = amalgamate(&project)?;
+
+        for file in [source, header] {
+            println!(
+                "\t  -> Wrote {}",
+                file.strip_prefix(&project.root).unwrap_or(&file).display()
+            );
+        }
+
+        println!("\t{}", style::header("Finished"));
+        Ok(())
+    }
+    /// Prints a source of the project after the preprocessor.
+    fn expand(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let file = self.path.as_ref().expect("expand requires a file");
+
+        print!(
+            "{}",
+            expand(&project, file, self.flags.contains(&Flags::OwnLines))?
+        );
+        Ok(())
+    }
+    /// Runs the checks picked, or every one, printing the issues grouped by
+    /// kind and failing when there are any.
+    fn check(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let all = ![Flags::Analyzer, Flags::IncludeCycles]
+            .iter()
+            .any(|flag| self.flags.contains(flag));
+        let mut issues = Vec::new();
+
+        if all || self.flags.contains(&Flags::Analyzer) {
+            println!(
+                "\n\t{}{} v{} with gcc -fanalyzer\n",
+                style::header("Analyzing "),
+                project.manifest.meta.name,
+                project.manifest.meta.version
+            );
+
+            let findings = analyze(&project)?;
+
+            for (index, finding) in findings.iter().enumerate() {
+                if index == 0 || findings[index - 1].option != finding.option {
+                    let count = findings
+                        .iter()
+                        .filter(|other| other.option == finding.option)
+                        .count();
+                    let cwe = finding
+                        .cwe
+                        .map_or(String::new(), |cwe| format!(" (CWE-{})", cwe));
+
+                    if index > 0 {
+                        println!();
+                    }
+                    println!(
+                        "{}{}, {} issue(s)",
+                        style::bold(&finding.option),
+                        cwe,
+                        count
+                    );
+                }
+
+                println!("  {}: {}", finding.location, style::red(&finding.message));
+
+                for (number, (location, description)) in finding.path.iter().enumerate() {
+                    println!("      ({}) {}: {}", number + 1, location, description);
+                }
+            }
+
+            match findings.is_empty() {
+                true => println!("\t{}\n", style::green("No issues")),
+                false => {
+                    println!();
+                    issues.push(("gcc -fanalyzer", findings.len()));
+                }
+            }
+        }
+
+        if all || self.flags.contains(&Flags::IncludeCycles) {
+            println!(
+                "\n\t{}{} v{} for include cycles\n",
+                style::header("Checking "),
+                project.manifest.meta.name,
+                project.manifest.meta.version
+            );
+
+            let cycles = include_cycles(&project)?;
+
+            for cycle in &cycles {
+                println!("  {}", style::red(&cycle_path(cycle, &project.root)));
+            }
+
+            match cycles.is_empty() {
+                true => println!("\t{}\n", style::green("No issues")),
+                false => {
+                    println!();
+                    issues.push(("include cycles", cycles.len()));
+                }
+            }
+        }
+
+        match issues.as_slice() {
+            [] => Ok(()),
+            [(check, count)] => Err(CedarError::Issues {
+                check,
+                count: *count,
+            }),
+            _ => Err(CedarError::Issues {
+                check: "cedar check",
+                count: issues.iter().map(|(_, count)| count).sum(),
+            }),
+        }
+    }
+
+    /// Runs the linter of --tool, printing its suggestions per file and
+    /// applying them with --fix.
+    fn lint(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let unused = self.flags.contains(&Flags::Unused);
+        let tool = self
+            .flags
+            .iter()
+            .find_map(|flag| match flag {
+                Flags::LintTool(tool) => Some(*tool),
+                _ => None,
+            })
+            .or((!unused).then_some(LintTool::Iwyu));
+        let fix = self.flags.contains(&Flags::Fix);
+        let mut issues = Vec::new();
+
+        if unused {
+            println!(
+                "\n\t{}{} v{} for unused files\n",
+                style::header("Linting "),
+                project.manifest.meta.name,
+                project.manifest.meta.version
+            );
+
+            let found = lint::unused(&project)?;
+
+            for (kind, files) in [("Sources", &found.sources), ("Headers", &found.headers)] {
+                if files.is_empty() {
+                    continue;
+                }
+
+                println!("{}", style::bold(&format!("{} nothing uses", kind)));
+
+                for file in files {
+                    println!("  {}", style::red(&file.display().to_string()));
+                }
+            }
+
+            match found.sources.len() + found.headers.len() {
+                0 => println!("\t{}\n", style::green("No issues")),
+                count => {
+                    println!();
+                    issues.push(("cedar lint --unused", count));
+                }
+            }
+        }
+
+        match tool {
+            None => {}
+            Some(LintTool::Iwyu) => {
+                println!(
+                    "\n\t{}{} v{} with include-what-you-use\n",
+                    style::header("Linting "),
+                    project.manifest.meta.name,
+                    project.manifest.meta.version
+                );
+
+                let suggestions = lint::iwyu(&project)?;
+                let mut count = 0;
+
+                for suggestion in &suggestions {
+                    let file = suggestion
+                        .file
+                        .strip_prefix(&project.root)
+                        .unwrap_or(&suggestion.file);
+
+                    println!("{}", style::bold(&file.display().to_string()));
+
+                    for line in &suggestion.add {
+                        println!("  {}", style::green(&format!("+ {}", line)));
+                    }
+                    for (line, number) in &suggestion.remove {
+                        println!("  {} (line {})", style::red(&format!("- {}", line)), number);
+                    }
+
+                    count += suggestion.add.len() + suggestion.remove.len();
+
+                    if fix && lint::fix(suggestion)? {
+                        println!("  {}", style::header("Fixed"));
+                    }
+                }
+
+                if suggestions.is_empty() {
+                    println!("\t{}\n", style::green("No issues"));
+                } else if !fix {
+                    println!();
+                    issues.push(("include-what-you-use", count));
+                }
+            }
+        }
+
+        match issues.as_slice() {
+            [] => Ok(()),
+            [(check, count)] => Err(CedarError::Issues {
+                check,
+                count: *count,
+            }),
+            _ => Err(CedarError::Issues {
+                check: "cedar lint",
+                count: issues.iter().map(|(_, count)| count).sum(),
+            }),
+        }
+    }
+
+    /// Compares the exports of the shared library with those of the old
+    /// build given, failing on any change breaking programs linked against
+    /// it.
+    fn abi_diff(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let old = self.path.as_ref().expect("abi-diff requires a library");
+        let diff = abi::diff(
+            &project,
+            old,
+            &BuildOptions {
+                quiet: true,
+                ..Default::default()
+            },
+        )?;
+
+        println!(
+            "\n\t{}{} with {}\n",
+            style::header("Comparing "),
+            diff.library
+                .strip_prefix(&project.root)
+                .unwrap_or(&diff.library)
+                .display(),
+            old.display()
+        );
+
+        if !diff.removed.is_empty() {
+            println!("{}", style::bold("Removed"));
+        }
+        for symbol in &diff.removed {
+            println!("  {}", style::red(&format!("- {}", symbol)));
+        }
+
+        if !diff.changed.is_empty() {
+            println!("{}", style::bold("Changed"));
+        }
+        for (old, new) in &diff.changed {
+            println!("  {}", style::red(&format!("~ {}, was {}", new, old)));
+        }
+
+        if !diff.added.is_empty() {
+            println!("{}", style::bold("Added"));
+        }
+        for symbol in &diff.added {
+            println!("  {}", style::green(&format!("+ {}", symbol)));
+        }
+
+        if let Some((_, report)) = diff
+            .abidiff
+            .as_ref()
+            .filter(|(_, report)| !report.is_empty())
+        {
+            println!("{}", style::bold("abidiff"));
+            print!("{}", report);
+        }
+
+        match diff.breaks() {
+            0 => {
+                println!("\t{}\n", style::green("Compatible"));
+                Ok(())
+            }
+            count => {
+                println!();
+                Err(CedarError::Issues {
+                    check: "cedar abi-diff",
+                    count,
+                })
+            }
+        }
+    }
+    /// Lists the symbols of every artifact built, or of those of --bin and
+    /// --lib.
+    fn symbols(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let filter = SymbolFilter {
+            undefined: self.flags.contains(&Flags::Undefined),
+            dynamic: self.flags.contains(&Flags::Dynamic),
+            pattern: self.pattern.clone(),
+        };
+        let options = BuildOptions {
+            quiet: true,
+            targets: self.target_filters(),
+            ..Default::default()
+        };
+
+        for (artifact, symbols) in abi::symbols(&project, &options, &filter)? {
+            println!(
+                "{}",
+                style::bold(
+                    &artifact
+                        .strip_prefix(&project.root)
+                        .unwrap_or(&artifact)
+                        .display()
+                        .to_string()
+                )
+            );
+
+            for symbol in symbols {
+                println!("  {} {}", symbol.kind, symbol.name);
+            }
+        }
+
+        Ok(())
+    }
+    /// Writes the build definition of the project for another build system.
+    fn export(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let provider = self.flags.iter().find_map(|flag| match flag {
+            Flags::Provider(provider) => Some(*provider),
+            _ => None,
+        });
+        let format = match (self.export.unwrap_or(ExportFormat::Cmake), provider) {
+            (ExportFormat::Ci(_), Some(provider)) => ExportFormat::Ci(provider),
+            (_, Some(_)) => {
+                return Err(CliError::InvalidArgument(String::from(
+                    "--provider, only export ci has a provider.",
+                ))
+                .into())
+            }
+            (format, None) => format,
+        };
+
+        let path = export(&project, format)?;
+
+        println!(
+            "\n\t{}{}\n",
+            style::green("Exported "),
+            path.strip_prefix(&project.root).unwrap_or(&path).display()
+        );
+
+        Ok(())
+    }
+    /// Writes a cedar.toml from the build definition of another build
+    /// system, printing where to move the sources.
+    fn migrate(&self) -> Result<(), CedarError> {
+        let root = env::current_dir()?;
+
+        let log = self.flags.iter().find_map(|flag| match flag {
+            Flags::Log(log) => Some(log.as_path()),
+            _ => None,
+        });
+
+        let source = match self.flags.iter().find_map(|flag| match flag {
+            Flags::From(source) => Some(source.as_str()),
+            _ => None,
+        }) {
+            Some(source) => source.parse()?,
+            None if log.is_some() => MigrateSource::Make,
+            None => MigrateSource::detect(&root).ok_or_else(|| {
+                ProjectError::InvalidPath(String::from(
+                    "No CMakeLists.txt or Makefile to migrate from in the current directory.",
+                ))
+            })?,
+        };
+
+        let migration = migrate(&root, source, log)?;
+
+        println!(
+            "\n\t{}{} v{} to cedar.toml\n",
+            style::green("Migrated "),
+            migration.manifest.meta.name,
+            migration.manifest.meta.version
+        );
+
+        if !migration.suggestions.is_empty() {
+            println!("\tMove the sources into the layout cedar expects:\n");
+            for (from, to) in &migration.suggestions {
+                println!("\t  {} -> {}", from.display(), to.display());
+            }
+            println!();
+        }
+
+        for note in &migration.notes {
+            println!("\t{}{}", style::bold("note: "), note);
+        }
+
+        println!();
+        Ok(())
+    }
+    /// Runs a build command, writing the compiles it runs to
+    /// compile_commands.json.
+    fn intercept(&self) -> Result<(), CedarError> {
+        let root = env::current_dir()?;
+
+        let (path, count) = intercept(&root, &self.command_line)?;
+
+        println!(
+            "\n\t{}{} compile(s) to {}\n",
+            style::green("Intercepted "),
+            count,
+            path.strip_prefix(&root).unwrap_or(&path).display()
+        );
+
+        Ok(())
+    }
+    /// Runs a task of [tasks], or lists them without a name.
+    fn task(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+
+        let Some((name, args)) = self.command_line.split_first() else {
+            println!();
+            for (name, task) in &project.manifest.tasks {
+                println!(
+                    "\t{}{}",
+                    style::bold(&format!("{:<16}", name)),
+                    task.commands().join(" && ")
+                );
+            }
+            println!();
+            return Ok(());
+        };
+
+        task::run(&project, name, args)
+    }
+    /// Writes the editor configuration of the project.
+    fn ide(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let editor = self.editor.unwrap_or(Editor::Vscode);
+
+        let written = ide(&project, editor)?;
+
+        println!();
+        for path in written {
+            println!(
+                "\t{}{}",
+                style::green("Wrote "),
+                path.strip_prefix(&project.root).unwrap_or(&path).display()
+            );
+        }
+        println!();
+
+        Ok(())
+    }
+    /// Builds the project and installs it under the --prefix directory,
+    /// /usr/local by default.
+    fn install(&self) -> Result<(), CedarError> {
+        let cwd = env::current_dir()?;
+
+        let prefix = self
+            .flags
+            .iter()
+            .find_map(|flag| match flag {
+                Flags::Prefix(prefix) => Some(cwd.join(prefix)),
+                _ => None,
+            })
+            .unwrap_or_else(|| PathBuf::from("/usr/local"));
+
+        let project = Project::find(&cwd)?;
+        project.build(&BuildOptions::default())?;
+
+        println!("\t{}({:?})", style::header("Installing "), &prefix);
+
+        for file in install(&project, &prefix)? {
+            println!("\t  -> {}", file.display());
+        }
+
+        println!("\t{}", style::header("Finished"));
+        Ok(())
+    }
+    /// Builds the project into an archive, signing it with --sign.
+    fn package(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+
+        // The key is checked before building, so a missing one fails early.
+        let key = self.signing_key()?;
+
+        project.build(&BuildOptions::default())?;
+
+        Self::package_built(&project, key.as_deref())
+    }
+    /// Archives a built project, then signs the archive with the key if any.
+    fn package_built(project: &Project, key: Option<&Path>) -> Result<(), CedarError> {
+        let (archive, digest) = package(project)?;
+        println!("\t{}{}", style::header("Packaged "), archive.display());
+        println!("\t  sha256 {}", digest);
+
+        if let Some(key) = key {
+            let signature = sign::sign(&archive, key)?;
+            println!("\t{}{}", style::header("Signed "), signature.display());
+        }
+
+        Ok(())
+    }
+    /// The key of --key or of the config with --sign, None without --sign.
+    fn signing_key(&self) -> Result<Option<PathBuf>, CedarError> {
+        match self.flags.contains(&Flags::Sign) {
+            true => {
+                Ok(Some(self.key().or(Config::load()?.sign.key).ok_or(
+                    CliError::MissingArgument("--key, or key in [sign]"),
+                )?))
+            }
+            false => Ok(None),
+        }
+    }
+    /// Writes the section of the upcoming version to CHANGELOG.md.
+    fn changelog(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let current: Version = project.manifest.meta.version.parse()?;
+        let version = match &self.bump {
+            Some(bump) => current.bumped(bump),
+            None => current,
+        };
+
+        let (text, count) = changelog::update(
+            &project.root,
+            &version.to_string(),
+            self.flags.contains(&Flags::Conventional),
+        )?;
+
+        let path = project.root.join("CHANGELOG.md");
+        fs::write(&path, text).map_err(|err| CedarError::io(&path, err))?;
+
+        println!(
+            "\n\t{}{:?} with {} commit(s) for {}\n",
+            style::header("Updated "),
+            path,
+            count,
+            version
+        );
+        Ok(())
+    }
+    /// Installs the git hooks of [hooks.git].
+    fn hooks(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let hooks = &project.manifest.hooks.git;
+
+        if hooks.is_empty() {
+            println!("\n\tNo hooks in [hooks.git] of cedar.toml to install.\n");
+            return Ok(());
+        }
+
+        let written = hooks::install(&project.root, hooks, |path| {
+            self.confirm(&format!("{:?} exists, replace it?", path))
+        })?;
+
+        println!();
+        for path in written {
+            println!("\t{}{:?}", style::header("Installed "), path);
+        }
+        println!();
+        Ok(())
+    }
+    /// Cuts a release: checks the tree, bumps the version, builds and tests
+    /// with the release profile, commits and tags, then packages.
+    fn release(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let root = project.root.clone();
+
+        let files = release::uncommitted(&root)?;
+        if !files.is_empty() {
+            return Err(CedarError::UncommittedChanges { files });
+        }
+
+        let key = self.signing_key()?;
+        let (old, new, mut edits) = self.version_edits(&project, self.bump.as_ref())?;
+
+        let tag = release::tag_name(&new);
+        if release::tag_exists(&root, &tag)? {
+            return Err(CedarError::TagExists { tag });
+        }
+
+        // A changelog the project keeps gets the section of the release.
+        let changelog_path = root.join("CHANGELOG.md");
+        if changelog_path.is_file() {
+            let text = fs::read_to_string(&changelog_path)
+                .map_err(|err| CedarError::io(&changelog_path, err))?;
+
+            if !changelog::has_section(&text, &new.to_string()) {
+                let (text, _) = changelog::update(
+                    &root,
+                    &new.to_string(),
+                    self.flags.contains(&Flags::Conventional),
+                )?;
+                edits.push((changelog_path, text));
+            }
+        }
+
+        println!(
+            "\n\t{}{} {}",
+            style::header("Releasing "),
+            project.name(),
+            match old == new {
+                true => new.to_string(),
+                false => format!("{} -> {}", old, new),
+            }
+        );
+
+        // The bump is undone should the release fail.
+        let project = release::with_edits(&edits, || {
+            let project = Project::find(&root)?.with_profile("release")?;
+            project.build(&BuildOptions::default())?;
+
+            let options = self.test_options();
+            let report = project.test(&options)?;
+            print_tests(&report, &options);
+            tests_passed(&report)?;
+
+            Ok(project)
+        })?;
+
+        let files: Vec<PathBuf> = edits.into_iter().map(|(path, _)| path).collect();
+        let tag = release::commit_and_tag(&root, &files, &new)?;
+        println!("\t{}{}", style::header("Tagged "), tag);
+
+        Self::package_built(&project, key.as_deref())?;
+
+        println!();
+        Ok(())
+    }
+    /// Checks the signature of a file against the public key of the project
+    /// or --key.
+    fn verify(&self) -> Result<(), CedarError> {
+        let cwd = env::current_dir()?;
+        let file = cwd.join(self.path.as_ref().expect("verify requires a file"));
+
+        let public_key = match self.key() {
+            Some(key) => cwd.join(key),
+            None => {
+                let project = Project::find(&cwd)?;
+                let key = project
+                    .manifest
+                    .package
+                    .public_key
+                    .ok_or(CliError::MissingArgument(
+                        "--key, or public_key in [package]",
+                    ))?;
+                project.root.join(key)
+            }
+        };
+
+        sign::verify(&file, &public_key)?;
+        println!("\t{}{}", style::header("Verified "), file.display());
+
+        Ok(())
+    }
+    /// The key given with --key.
+    fn key(&self) -> Option<PathBuf> {
+        self.flags.iter().find_map(|flag| match flag {
+            Flags::Key(key) => Some(key.clone()),
+            _ => None,
+        })
+    }
+    /// Generates the documentation of the project in the current working
+    /// directory, opening it in a browser if --open was given.
+    fn doc(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+
+        println!(
+            "\n\t{}project ({:?})",
+            style::header("Documenting "),
+            &project.root
+        );
+
+        let index = if self.flags.contains(&Flags::Builtin) {
+            doc::builtin(&project)?
+        } else {
+            doc::doxygen(&project)?
+        };
+
+        println!(
+            "\t  -> Wrote {}",
+            index
+                .strip_prefix(&project.root)
+                .unwrap_or(&index)
+                .display()
+        );
+
+        if self.flags.contains(&Flags::Open) {
+            open_in_browser(&index)?;
+        }
+
+        println!("\t{}", style::header("Finished"));
+        Ok(())
+    }
+    /// Prints the metadata of the project in the current working directory,
+    /// JSON is currently the only format.
+    fn metadata(&self) -> Result<(), CedarError> {
+        let format = self.flags.iter().find_map(|flag| match flag {
+            Flags::Format(format) => Some(format.as_str()),
+            _ => None,
+        });
+
+        match format {
+            None | Some("json") => {}
+            Some(format) => {
+                return Err(CliError::InvalidArgument(format!(
+                    "{:?}, metadata only supports the json format.",
+                    format
+                ))
+                .into())
+            }
+        }
+
+        let project = Project::find(env::current_dir()?)?;
+        let metadata = Metadata::load(&project)?;
+        println!("{}", metadata.to_json().map_err(io::Error::from)?);
+
+        Ok(())
+    }
+    /// Prints an overview of the project, one field per line.
+    fn info(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let info = Info::load(&project)?;
+
+        let field = |label: &str, value: &str| {
+            println!("\t{}{}", style::bold(&format!("{:<14}", label)), value);
+        };
+        let list = |label: &str, values: &[String]| match values.split_first() {
+            None => field(label, "none"),
+            Some((first, rest)) => {
+                field(label, first);
+                for value in rest {
+                    field("", value);
+                }
+            }
+        };
+
+        println!();
+        field(
+            "name",
+            &format!("{} v{} ({})", info.name, info.version, info.kind.as_str()),
+        );
+        field(
+            "compiler",
+            &match &info.compiler {
+                (compiler, Some(version)) => format!("{} ({})", compiler, version),
+                (compiler, None) => format!("{} (not found)", compiler),
+            },
+        );
+        list(
+            "targets",
+            &info
+                .targets
+                .iter()
+                .map(|(name, kind)| format!("{} ({})", name, kind.as_str()))
+                .collect::<Vec<_>>(),
+        );
+        list(
+            "dependencies",
+            &info
+                .dependencies
+                .iter()
+                .map(|(name, source)| format!("{} ({})", name, source))
+                .collect::<Vec<_>>(),
+        );
+        field(
+            "sources",
+            &format!(
+                "{} in the library, {} translation unit(s) in all",
+                info.sources, info.translation_units
+            ),
+        );
+        field(
+            "last build",
+            &match &info.last_build {
+                Some(record) if record.profile.is_empty() => {
+                    format!("{}, in {:.2}s", record.started, record.elapsed)
+                }
+                Some(record) => format!(
+                    "{} at {}, in {:.2}s",
+                    record.profile, record.started, record.elapsed
+                ),
+                None => String::from("never"),
+            },
+        );
+        list(
+            "artifacts",
+            &info
+                .artifacts
+                .iter()
+                .map(|(path, exists)| match exists {
+                    true => path.display().to_string(),
+                    false => format!("{} (not built)", path.display()),
+                })
+                .collect::<Vec<_>>(),
+        );
+        println!();
+
+        Ok(())
+    }
+    /// Prints the statistics of the project, one section after the other.
+    fn stats(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+        let stats = Stats::load(&project)?;
+
+        let field = |label: &str, value: &str| {
+            println!("\t{}{}", style::bold(&format!("{:<14}", label)), value);
+        };
+        let section = |title: &str| println!("\n\t{}", style::header(title));
+
+        section("Code");
+        for (dir, files, lines) in &stats.directories {
+            field(
+                &dir.display().to_string(),
+                &format!("{} line(s) in {} file(s)", lines, files),
+            );
+        }
+        field(
+            "total",
+            &format!(
+                "{} line(s), {} translation unit(s), {} header(s)",
+                stats
+                    .directories
+                    .iter()
+                    .map(|(_, _, lines)| lines)
+                    .sum::<usize>(),
+                stats.translation_units,
+                stats.headers
+            ),
+        );
+
+        section("Builds");
+        match stats.builds.is_empty() {
+            true => field("recorded", "none"),
+            false => {
+                let times: Vec<f64> = stats.builds.iter().map(|record| record.elapsed).collect();
+                let average = times.iter().sum::<f64>() / times.len() as f64;
+                let fastest = times.iter().copied().fold(f64::INFINITY, f64::min);
+                let slowest = times.iter().copied().fold(0.0, f64::max);
+
+                field(
+                    "recorded",
+                    &format!(
+                        "{}, in {:.2}s on average, {:.2}s to {:.2}s",
+                        times.len(),
+                        average,
+                        fastest,
+                        slowest
+                    ),
+                );
+                for record in &stats.builds {
+                    let profile = match record.profile.is_empty() {
+                        true => String::new(),
+                        false => format!(" ({})", record.profile),
+                    };
+                    field(
+                        "",
+                        &format!("{}{}, {:.2}s", record.started, profile, record.elapsed),
+                    );
+                }
+            }
+        }
+        field(
+            "failed",
+            &format!("{} of the {} logged", stats.failed, stats.logged),
+        );
+        field(
+            "cache",
+            &match stats.cache {
+                Some((hits, misses)) if hits + misses > 0 => format!(
+                    "{:.1}% hit rate, {} hit(s), {} miss(es)",
+                    hits as f64 * 100.0 / (hits + misses) as f64,
+                    hits,
+                    misses
+                ),
+                Some(_) => String::from("no lookups"),
+                None => String::from("not enabled"),
+            },
+        );
+
+        section("Largest objects");
+        if stats.largest_objects.is_empty() {
+            field("none", "build the project first");
+        }
+        for (path, size) in &stats.largest_objects {
+            println!("\t{:>10}  {}", format_bytes(*size), path.display());
+        }
+        println!();
+
+        Ok(())
+    }
+    /// Prints the build graph of the project as dot or JSON.
+    fn graph(&self) -> Result<(), CedarError> {
+        let format = self
+            .flags
+            .iter()
+            .find_map(|flag| match flag {
+                Flags::Format(format) => Some(format.parse()),
+                _ => None,
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let project = Project::find(env::current_dir()?)?;
+        let graph = Graph::load(&project)?;
+
+        match format {
+            GraphFormat::Dot => print!("{}", graph.to_dot()),
+            GraphFormat::Json => println!("{}", graph.to_json().map_err(io::Error::from)?),
+        }
+
+        Ok(())
+    }
+    /// Builds and runs the tests of the project, printing the output of the
+    /// failed ones.
+    fn test(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+
+        let export = self.flags.iter().find_map(|flag| match flag {
+            Flags::Export(format) => Some(format.as_str()),
+            _ => None,
+        });
+
+        match export {
+            None => {}
+            Some("ctest") => {
+                let path = export_ctest(&project, &self.test_options())?;
+                println!("\t{} {:?}\n", style::header("Exported"), path);
+                return Ok(());
+            }
+            Some(format) => {
+                return Err(CliError::InvalidArgument(format!(
+                    "{:?}, --export only supports ctest.",
+                    format
+                ))
+                .into())
+            }
+        }
+
+        let reports: Vec<_> = self
+            .flags
+            .iter()
+            .filter_map(|flag| match flag {
+                Flags::Report(format, path) => Some((*format, path.clone())),
+                _ => None,
+            })
+            .collect();
+
+        // TAP on stdout replaces the usual output so it can be piped.
+        let tap = reports
+            .iter()
+            .any(|(format, path)| *format == ReportFormat::Tap && path.is_none());
+
+        let options = TestOptions {
+            quiet: tap,
+            ..self.test_options()
+        };
+
+        let report = project.test(&options)?;
+
+        for (format, path) in reports {
+            let rendered = report.render(format, &project.manifest.meta.name);
+
+            let path = match (format, path) {
+                (_, Some(path)) => path,
+                (ReportFormat::Junit, None) => project.build_dir.join("test-results.xml"),
+                (ReportFormat::Tap, None) => {
+                    print!("{}", rendered);
+                    continue;
+                }
+            };
+
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+            }
+
+            fs::write(&path, rendered).map_err(|err| CedarError::io(&path, err))?;
+        }
+
+        if !tap {
+            print_tests(&report, &options);
+        }
+
+        tests_passed(&report)
+    }
+    /// Runs the tests with coverage instrumentation and prints the line
+    /// coverage of every library source, failing below the minimum.
+    fn cov(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let options = self.test_options();
+
+        let (coverage, report) = project.coverage(&options)?;
+        print_tests(&report, &options);
+
+        let width = coverage
+            .files
+            .iter()
+            .map(|file| file.path.as_os_str().len())
+            .max()
+            .unwrap_or(0);
+
+        for file in &coverage.files {
+            println!(
+                "\t{:<width$}  {:>6.2}%  {}/{}",
+                file.path.display(),
+                file.percent(),
+                file.covered,
+                file.lines,
+                width = width
+            );
+        }
+
+        let percent = coverage.percent();
+        println!("\n\t{} {:.2}%\n", style::header("Coverage"), percent);
+
+        tests_passed(&report)?;
+
+        let minimum = self
+            .flags
+            .iter()
+            .find_map(|flag| match flag {
+                Flags::FailUnder(minimum) => Some(*minimum),
+                _ => None,
+            })
+            .or(project.manifest.test.min_coverage);
+
+        match minimum {
+            Some(minimum) if percent < minimum => {
+                Err(CedarError::CoverageTooLow { percent, minimum })
+            }
+            _ => Ok(()),
+        }
+    }
+    /// Builds and times the benchmarks, printing their statistics and how
+    /// they changed against the baseline.
+    fn bench(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+
+        let mut options = BenchOptions {
+            filter: self.pattern.clone(),
+            ..Default::default()
+        };
+
+        for flag in &self.flags {
+            match flag {
+                Flags::Iterations(iterations) => options.iterations = *iterations,
+                Flags::SaveBaseline(name) => options.save_baseline = Some(name.clone()),
+                Flags::Baseline(name) => options.baseline = Some(name.clone()),
+                _ => {}
+            }
+        }
+
+        let results = project.bench(&options)?;
+
+        let width = results
+            .iter()
+            .map(|result| result.name.len())
+            .max()
+            .unwrap_or(0);
+
+        for result in &results {
+            let stats = &result.stats;
+
+            let mut line = format!(
+                "\tbench {:<width$}  {} ± {}  (median {})",
+                result.name,
+                format_seconds(stats.mean),
+                format_seconds(stats.stddev),
+                format_seconds(stats.median),
+                width = width
+            );
+
+            if let Some(baseline) = &result.baseline {
+                let change = (stats.mean / baseline.mean - 1.0) * 100.0;
+
+                let verdict = match stats.compare(baseline) {
+                    Change::NoChange => String::from("no change"),
+                    Change::Improved => style::green("improved"),
+                    Change::Regressed => style::red("regressed"),
+                };
+
+                line.push_str(&format!("  {:+.2}% {}", change, verdict));
+            }
+
+            println!("{}", line);
+        }
+
+        if let Some(name) = &options.save_baseline {
+            println!("\n\t{} baseline {:?}", style::header("Saved"), name);
+        }
+
+        println!();
+        Ok(())
+    }
+    /// Profiles the program, printing the hottest functions.
+    fn profile(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let target = project.runnable(self.target_filters().first())?;
+
+        let tool = self
+            .flags
+            .iter()
+            .find_map(|flag| match flag {
+                Flags::Tool(tool) => Some(*tool),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        println!(
+            "\t{}{} under {}\n",
+            style::header("Profiling "),
+            target.name,
+            tool.as_str()
+        );
+
+        let report = profile(&project, target, tool)?;
+
+        let heading = match report.peak_heap {
+            Some(peak) => {
+                println!("\n\t{} {}", style::header("Peak heap"), format_bytes(peak));
+                "Top allocation sites"
+            }
+            None => "Hottest functions",
+        };
+
+        println!("\n\t{}\n", style::header(heading));
+
+        for (function, percent) in report.hottest.iter().take(10) {
+            println!("\t{:>6.2}%  {}", percent, function);
+        }
+
+        match &report.flamegraph {
+            Some(flamegraph) => {
+                println!("\n\t{} {:?}\n", style::header("Flamegraph"), flamegraph)
+            }
+            None => println!("\n\t{} {:?}\n", style::header("Profile"), report.data),
+        }
+
+        Ok(())
+    }
+    /// Audits the dependencies, printing every advisory that affects one.
+    fn audit(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+
+        let database = self.flags.iter().find_map(|flag| match flag {
+            Flags::Db(path) => Some(path.as_path()),
+            _ => None,
+        });
+
+        // Resolving keeps cedar.lock in step with the manifest.
+        let packages = project.dependencies(false)?;
+
+        println!(
+            "\n\t{}{} dependencies\n",
+            style::header("Auditing "),
+            packages.len()
+        );
+
+        let findings = audit(&project, database)?;
+
+        for finding in &findings {
+            match &finding.unknown {
+                Some(reason) => println!(
+                    "\t{} {} {}\n\t  not checked, {}",
+                    style::bold(&finding.advisory.id),
+                    finding.package.name,
+                    finding.package.version,
+                    reason
+                ),
+                None => println!(
+                    "\t{} {} {}\n\t  {}",
+                    style::red(&finding.advisory.id),
+                    finding.package.name,
+                    finding.package.version,
+                    finding.advisory.summary
+                ),
+            }
+        }
+
+        let count = findings
+            .iter()
+            .filter(|finding| finding.unknown.is_none())
+            .count();
+
+        if count == 0 {
+            println!("\t{}\n", style::green("No known vulnerabilities"));
+            return Ok(());
+        }
+
+        println!();
+
+        Err(CedarError::Vulnerable { count })
+    }
+    /// Prints the licenses of the dependencies, failing when any is denied.
+    fn license_report(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let licenses = license::report(&project)?;
+
+        let width = licenses
+            .iter()
+            .map(|license| license.name.len() + license.version.len() + 1)
+            .max()
+            .unwrap_or(0);
+
+        println!();
+
+        for dependency in &licenses {
+            let license = match &dependency.license {
+                Some(license) if dependency.denied => style::red(&format!("{} (denied)", license)),
+                Some(license) if dependency.detected => format!("{} (detected)", license),
+                Some(license) => license.clone(),
+                None => style::bold("unknown"),
+            };
+
+            println!(
+                "\t{:<width$}  {}",
+                format!("{} {}", dependency.name, dependency.version),
+                license,
+                width = width
+            );
+        }
+
+        if licenses.is_empty() {
+            println!("\tNo dependencies");
+        }
+
+        println!();
+
+        let denied: Vec<String> = licenses
+            .iter()
+            .filter(|license| license.denied)
+            .map(|license| license.name.clone())
+            .collect();
+
+        match denied.is_empty() {
+            true => Ok(()),
+            false => Err(CedarError::DeniedLicenses { names: denied }),
+        }
+    }
+    /// Prints the software bill of materials.
+    fn sbom(&self) -> Result<(), CedarError> {
+        let format = match self.flags.iter().find_map(|flag| match flag {
+            Flags::Format(format) => Some(format.as_str()),
+            _ => None,
+        }) {
+            Some(format) => format.parse()?,
+            None => SbomFormat::default(),
+        };
+
+        let project = Project::find(env::current_dir()?)?;
+        println!("{}", sbom(&project, format)?);
+
+        Ok(())
+    }
+    /// Writes the license notices of the dependencies to build/NOTICES.
+    fn notices(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let packages = project.dependencies(false)?;
+
+        let path = project.build_dir.join("NOTICES");
+        fs::create_dir_all(&project.build_dir)
+            .map_err(|err| CedarError::io(&project.build_dir, err))?;
+        fs::write(&path, license::notices(&packages)).map_err(|err| CedarError::io(&path, err))?;
+
+        println!(
+            "\n\t{}notices of {} dependencies to {:?}\n",
+            style::green("Wrote "),
+            packages.len(),
+            path
+        );
+
+        Ok(())
+    }
+    /// Adds a git submodule as a dependency.
+    fn add(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+
+        let url = self
+            .flags
+            .iter()
+            .find_map(|flag| match flag {
+                Flags::Submodule(url) => Some(url.as_str()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let path = self.path.clone().unwrap_or_default();
+
+        let (name, dependency) = deps::add_submodule(&project, url, &path.to_string_lossy())?;
+
+        println!(
+            "\n\t{}{} ({})",
+            style::green("Adding "),
+            name,
+            dependency.path.as_deref().unwrap_or_default()
+        );
+
+        if dependency.is_vendored() {
+            println!(
+                "\tNo cedar.toml, building it as a plain library (sources: {}, include: {}).",
+                dependency.sources.join(", "),
+                dependency.include.as_deref().unwrap_or("include")
+            );
+        }
+
+        println!();
+        Ok(())
+    }
+    /// Updates the dependencies, printing every change to cedar.lock.
+    fn update(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+
+        let names: Vec<String> = self
+            .flags
+            .iter()
+            .filter_map(|flag| match flag {
+                Flags::Package(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let (updates, lockfile) = deps::update(&project, &names, false)?;
+
+        println!();
+
+        for Update { name, old, new } in &updates {
+            let describe = |package: &LockedPackage| match package.commit() {
+                Some(commit) => format!("{} ({})", package.version, &commit[..commit.len().min(7)]),
+                None => package.version.clone(),
+            };
+
+            match (old, new) {
+                (Some(old), Some(new)) => println!(
+                    "\t{}{} {} -> {}",
+                    style::header("Updating "),
+                    name,
+                    describe(old),
+                    describe(new)
+                ),
+                (None, Some(new)) => {
+                    println!("\t{}{} {}", style::green("Adding "), name, describe(new))
+                }
+                (Some(old), None) => {
+                    println!("\t{}{} {}", style::red("Removing "), name, describe(old))
+                }
+                (None, None) => {}
+            }
+        }
+
+        if updates.is_empty() {
+            println!("\tDependencies are up to date\n");
+            return Ok(());
+        }
+
+        self.confirm("Write these changes to cedar.lock?")?;
+        lockfile.save(&project)?;
+
+        println!();
+        Ok(())
+    }
+    /// Lists the dependencies with newer versions.
+    fn outdated(&self) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let outdated = deps::outdated(&project)?;
+
+        println!();
+
+        if outdated.is_empty() {
+            println!("\tDependencies are up to date\n");
+            return Ok(());
+        }
+
+        let width = outdated
+            .iter()
+            .map(|dependency| dependency.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        println!(
+            "\t{}",
+            style::bold(&format!(
+                "{:<width$}  {:<10}  {:<10}  {:<10}",
+                "name",
+                "current",
+                "compatible",
+                "latest",
+                width = width
+            ))
+        );
+
+        let version = |version: &Option<Version>| {
+            version
+                .as_ref()
+                .map_or_else(|| String::from("-"), Version::to_string)
+        };
+
+        for dependency in &outdated {
+            println!(
+                "\t{:<width$}  {:<10}  {:<10}  {:<10}",
+                dependency.name,
+                dependency.current.to_string(),
+                version(&dependency.compatible),
+                version(&dependency.latest),
+                width = width
+            );
+        }
+
+        println!();
+
+        if outdated
+            .iter()
+            .any(|dependency| dependency.compatible.is_some())
+        {
+            println!("\tRun cedar update to move to the compatible versions.");
+        }
+
+        if outdated
+            .iter()
+            .any(|dependency| dependency.latest.is_some())
+        {
+            println!("\tThe latest versions need the requirements in cedar.toml changed.");
+        }
+
+        println!();
+        Ok(())
+    }
+    /// The test options given on the command line.
+    fn test_options(&self) -> TestOptions {
+        TestOptions {
+            filter: self.pattern.clone(),
+            skip: self
+                .flags
+                .iter()
+                .filter_map(|flag| match flag {
+                    Flags::Skip(pattern) => Some(pattern.clone()),
+                    _ => None,
+                })
+                .collect(),
+            jobs: self.flags.iter().find_map(|flag| match flag {
+                Flags::Jobs(jobs) => Some(*jobs),
+                _ => None,
+            }),
+            nocapture: self.flags.contains(&Flags::NoCapture),
+            update_snapshots: self.flags.contains(&Flags::UpdateSnapshots),
+            timeout: self.timeout(),
+            wrap: self.wrap().map(String::from),
+            ..Default::default()
+        }
+    }
+    /// Sets the version of the project in cedar.toml, and in the version
+    /// macros of the header of --header.
+    fn bump(&self, bump: &Bump) -> Result<(), CedarError> {
+        let project = Project::find(env::current_dir()?)?;
+        let (old, new, edits) = self.version_edits(&project, Some(bump))?;
+
+        for (path, contents) in &edits {
+            fs::write(path, contents).map_err(|err| CedarError::io(path, err))?;
+        }
+
+        println!(
+            "\n\t{}{} {} -> {}",
+            style::header("Bumping "),
+            project.name(),
+            old,
+            new
+        );
+
+        for (path, _) in edits.iter().skip(1) {
+            println!("\t{}{:?}", style::header("Updating "), path);
+        }
+
+        println!();
+        Ok(())
+    }
+    /// The new contents of the files a version bump changes, cedar.toml first
+    /// and then the header of --header, checked before anything is written.
+    ///
+    /// # Returns
+    ///
+    /// * The old and new version, and the files to write. Without a bump the
+    ///   version stays and nothing is written.
+    ///
+    fn version_edits(
+        &self,
+        project: &Project,
+        bump: Option<&Bump>,
+    ) -> Result<(Version, Version, Edits), CedarError> {
+        let old: Version = project.manifest.meta.version.parse()?;
+        let Some(bump) = bump else {
+            return Ok((old.clone(), old, Vec::new()));
+        };
+        let new = old.bumped(bump);
+
+        let manifest_path = project.manifest_path();
+        let text = fs::read_to_string(&manifest_path)
+            .map_err(|err| CedarError::io(&manifest_path, err))?;
+        let text = set_version(&text, &new.to_string()).ok_or(ProjectError::InvalidManifest)?;
+        let mut edits = vec![(manifest_path, text)];
+
+        if let Some(path) = self.flags.iter().find_map(|flag| match flag {
+            Flags::Header(path) => Some(project.root.join(path)),
+            _ => None,
+        }) {
+            let contents = fs::read_to_string(&path).map_err(|err| CedarError::io(&path, err))?;
+            let contents = set_header_version(&contents, &old, &new).ok_or_else(|| {
+                CliError::InvalidArgument(format!(
+                    "{:?}, the header defines no version macro.",
+                    path
+                ))
+            })?;
+            edits.push((path, contents));
+        }
+
+        Ok((old, new, edits))
+    }
+    /// Removes the build directory of the project after confirming.
+    fn clean(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+
+        if let Some(profile) = self.profile_name() {
+            let out_dir = project.out_dir();
+            self.confirm(&format!("Remove the {} build in {:?}?", profile, out_dir))?;
+
+            println!(
+                "\n\t{}{} profile ({:?})",
+                style::header("Cleaning "),
+                profile,
+                out_dir
+            );
+
+            project.clean_profile()?;
+
+            println!("\t{}", style::header("Finished"));
+            return Ok(());
+        }
+
+        self.confirm(&format!("Remove everything in {:?}?", project.build_dir))?;
+
+        println!(
+            "\n\t{}project ({:?})",
+            style::header("Cleaning "),
+            &project.root
+        );
+
+        project.clean()?;
+
+        println!("\t{}", style::header("Finished"));
+        Ok(())
+    }
+    /// Asks before initializing a project in a directory that already has
+    /// files in it.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the directory is non-empty (and the user agreed to use it).
+    ///
+    fn confirm_non_empty(&self, path: &Path) -> Result<bool, CedarError> {
+        let non_empty = path.is_dir() && path.read_dir()?.next().is_some();
+
+        if non_empty {
+            self.confirm(&format!(
+                "{:?} is not empty, create a project in it anyway?",
+                path
+            ))?;
+        }
+
+        Ok(non_empty)
+    }
+    /// Asks the user to confirm a destructive or surprising operation, --yes
+    /// confirms without asking. Without a terminal to ask on, the operation
+    /// is refused.
+    fn confirm(&self, question: &str) -> Result<(), CedarError> {
+        if self.flags.contains(&Flags::Yes) {
+            return Ok(());
+        }
+
+        if !io::stdin().is_terminal() {
+            return Err(CliError::Aborted(format!("{} Pass --yes to confirm.", question)).into());
+        }
+
+        print!("\n\t{} [y/N] ", question);
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => Ok(()),
+            _ => Err(CliError::Aborted(question.to_owned()).into()),
+        }
+    }
+    /// Compiles the project, or the targets picked by the flags.
+    fn build(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+
+        if let Some(image) = self.flags.iter().find_map(|flag| match flag {
+            Flags::InContainer(image) => Some(image),
+            _ => None,
+        }) {
+            let image = image
+                .as_ref()
+                .or(project.manifest.build.container.as_ref())
+                .ok_or(CliError::MissingArgument(
+                    "--in-container <IMAGE>, or container in [build]",
+                ))?;
+
+            // The same command, without the flag and its image, runs inside
+            // the container.
+            let mut args: Vec<String> = Vec::new();
+            let mut given = env::args().skip(1).peekable();
+
+            while let Some(arg) = given.next() {
+                match arg.as_str() {
+                    "--in-container" => {
+                        given.next_if(|next| !next.starts_with('-'));
+                    }
+                    _ if arg.starts_with("--in-container=") => {}
+                    _ => args.push(arg),
+                }
+            }
+
+            return container::run(&project.root, image, &args);
+        }
+
+        let emit = self.flags.iter().find_map(|flag| match flag {
+            Flags::Emit(emit) => Some(*emit),
+            _ => None,
+        });
+        let file = match (&self.path, emit) {
+            (None, _) => None,
+            (Some(path), Some(Emit::Asm | Emit::Preprocessed)) => Some(
+                path.canonicalize()
+                    .map_err(|err| CedarError::io(path, err))?,
+            ),
+            (Some(path), _) => {
+                return Err(CliError::InvalidArgument(format!(
+                    "{:?}, a file is only built with --emit asm or preprocessed.",
+                    path
+                ))
+                .into())
+            }
+        };
+
+        let options = BuildOptions {
+            targets: self.target_filters(),
+            explain: self.flags.contains(&Flags::Explain),
+            max_errors: self.flags.iter().find_map(|flag| match flag {
+                Flags::MaxErrors(max_errors) => Some(*max_errors),
+                _ => None,
+            }),
+            output_order: self
+                .flags
+                .iter()
+                .find_map(|flag| match flag {
+                    Flags::OutputOrder(order) => Some(*order),
+                    _ => None,
+                })
+                .unwrap_or_default(),
+            output_format: self
+                .flags
+                .iter()
+                .find_map(|flag| match flag {
+                    Flags::OutputFormat(format) => Some(*format),
+                    _ => None,
+                })
+                .unwrap_or_default(),
+            emit,
+            file,
+            save_temps: self.flags.contains(&Flags::SaveTemps),
+            print_gc_sections: self.flags.contains(&Flags::PrintGcSections),
+            ..Default::default()
+        };
+
+        if let Some(name) = self.flags.iter().find_map(|flag| match flag {
+            Flags::Remote(name) => Some(name),
+            _ => None,
+        }) {
+            let config = Config::load()?;
+            let remote = config.remote.get(name).ok_or_else(|| {
+                ProjectError::InvalidConfig(format!("There is no [remote.{}].", name))
+            })?;
+
+            // The same command, without the flag, runs on the remote.
+            let mut args = Vec::new();
+            let mut skip = false;
+            for arg in env::args().skip(1) {
+                match arg.as_str() {
+                    _ if skip => skip = false,
+                    "--remote" => skip = true,
+                    arg if arg.starts_with("--remote=") => {}
+                    _ => args.push(arg),
+                }
+            }
+
+            let artifacts = remote::build(&project, name, remote, &options, &args)?;
+            println!(
+                "\t{}{} artifact(s) from {}",
+                style::header("Copied "),
+                artifacts.len(),
+                name
+            );
+            return Ok(());
+        }
+
+        if self.flags.contains(&Flags::Matrix) {
+            return self.build_matrix(&project);
+        }
+
+        let since = self.flags.iter().find_map(|flag| match flag {
+            Flags::Since(since) => Some(since),
+            _ => None,
+        });
+
+        let report = match since {
+            Some(since) => {
+                subproject::build_recursive(&project, &options, &Changes::Since(since.clone()))?
+                    .pop()
+                    .filter(|(root, _)| *root == project.root)
+                    .map(|(_, report)| report)
+            }
+            None if self.flags.contains(&Flags::Recursive) => {
+                subproject::build_recursive(&project, &options, &Changes::LastBuild)?
+                    .pop()
+                    .filter(|(root, _)| *root == project.root)
+                    .map(|(_, report)| report)
+            }
+            None => Some(project.build(&options)?),
+        };
+
+        // The emitted files are printed last, one per line, for scripts.
+        if let Some(report) = report.as_ref().filter(|_| options.emit.is_some()) {
+            for path in &report.artifacts {
+                println!("{}", path.display());
+            }
+        }
+
+        let format = self.flags.iter().find_map(|flag| match flag {
+            Flags::Timings(format) => Some(*format),
+            _ => None,
+        });
+
+        match (format, report) {
+            (Some(TimingsFormat::Text), Some(report)) => timings::print(&report),
+            (Some(TimingsFormat::Html), Some(report)) => {
+                let history = timings::history(&project.build_dir);
+                let path = timings::write_html(&project, &report, &history)
+                    .map_err(|err| CedarError::io(&project.build_dir, err))?;
+
+                println!("\t{}{}\n", style::header("Timings "), path.display());
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+    /// Builds every combination of the [matrix] and prints whether each
+    /// passed, failing when any did not.
+    fn build_matrix(&self, project: &Project) -> Result<(), CedarError> {
+        println!(
+            "\n\t{}{} v{} for every combination of [matrix]\n",
+            style::header("Building "),
+            project.manifest.meta.name,
+            project.manifest.meta.version
+        );
+
+        let cells = matrix(project)?;
+
+        let width = |column: fn(&Cell) -> &str, heading: &str| {
+            cells
+                .iter()
+                .map(|cell| column(cell).len())
+                .chain([heading.len()])
+                .max()
+                .unwrap_or(0)
+        };
+        let profile = width(|cell| &cell.profile, "profile");
+        let compiler = width(|cell| &cell.compiler, "compiler");
+        let targets = width(|cell| &cell.targets, "targets");
+
+        println!(
+            "\t{}",
+            style::bold(&format!(
+                "{:<profile$}  {:<compiler$}  {:<targets$}  result",
+                "profile", "compiler", "targets"
+            ))
+        );
+
+        for cell in &cells {
+            let result = match &cell.result {
+                Ok(elapsed) => style::green(&format!("ok in {:.2?}", elapsed)),
+                Err(error) => style::red(&format!("failed, {}", error)),
+            };
+
+            println!(
+                "\t{:<profile$}  {:<compiler$}  {:<targets$}  {}",
+                cell.profile, cell.compiler, cell.targets, result
+            );
+        }
+
+        println!();
+
+        match cells.iter().filter(|cell| cell.result.is_err()).count() {
+            0 => Ok(()),
+            count => Err(CedarError::Issues {
+                check: "cedar build --matrix",
+                count,
+            }),
+        }
+    }
+    /// The project found from the current directory, set to build with the
+    /// profile of --release or --profile.
+    fn project(&self) -> Result<Project, CedarError> {
+        let mut project = Project::find(env::current_dir()?)?;
+
+        let features: Vec<String> = self
+            .flags
+            .iter()
+            .filter_map(|flag| match flag {
+                Flags::Features(names) => Some(names.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let defaults = !self.flags.contains(&Flags::NoDefaultFeatures);
+
+        if !features.is_empty() || !defaults {
+            project = project.with_features(&features, defaults)?;
+        }
+
+        match self.profile_name() {
+            Some(name) => Ok(project.with_profile(name)?),
+            None => Ok(project),
+        }
+    }
+    /// The profile picked with --release or --profile, the last one wins.
+    fn profile_name(&self) -> Option<&str> {
+        self.flags.iter().rev().find_map(|flag| match flag {
+            Flags::Release => Some("release"),
+            Flags::Profile(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+    /// The wrapper given with --wrap.
+    fn wrap(&self) -> Option<&str> {
+        self.flags.iter().find_map(|flag| match flag {
+            Flags::Wrap(wrapper) => Some(wrapper.as_str()),
+            _ => None,
+        })
+    }
+    /// The timeout given with --timeout.
+    fn timeout(&self) -> Option<Duration> {
+        self.flags.iter().find_map(|flag| match flag {
+            Flags::Timeout(timeout) => Some(*timeout),
+            _ => None,
+        })
+    }
+    /// The targets picked by --bin, --lib, --example, --examples, and
+    /// --all-targets.
+    fn target_filters(&self) -> Vec<TargetFilter> {
+        self.flags
+            .iter()
+            .filter_map(|flag| match flag {
+                Flags::Bin(name) => Some(TargetFilter::Bin(name.clone())),
+                Flags::Example(name) => Some(TargetFilter::Example(name.clone())),
+                Flags::Lib => Some(TargetFilter::Lib),
+                Flags::Examples => Some(TargetFilter::Examples),
+                Flags::AllTargets => Some(TargetFilter::All),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Compiles (if needed) and then runs the project.
+    fn run(&self) -> Result<(), CedarError> {
+        let project = self.project()?;
+
+        let target = project.runnable(self.target_filters().first())?;
+
+        // In a pipeline stdout is left to the program.
+        let piped = !io::stdout().is_terminal();
+        let options = BuildOptions {
+            quiet: piped,
+            targets: vec![match target.kind {
+                TargetKind::Example => TargetFilter::Example(target.name.clone()),
+                _ => TargetFilter::Bin(target.name.clone()),
+            }],
+            ..Default::default()
+        };
+
+        let report = project.build(&options)?;
+
+        if piped {
+            for (_, output) in &report.diagnostics {
+                eprint!("{}", output);
+            }
+        }
+
+        let program = project.output(target);
+        let run = &project.manifest.run;
+        let mut command = limits::command(&program, run.wrapper(self.wrap()), &run.limits)?;
+        command
+            .args(&self.command_line)
+            .stdin(process::Stdio::inherit());
+
+        let capture = self.flags.iter().find_map(|flag| match flag {
+            Flags::Capture(path) => Some((path, false)),
+            Flags::Tee(path) => Some((path, true)),
+            _ => None,
+        });
+
+        let timeout = self.timeout();
+
+        // A program reading the terminal has to stay in the foreground
+        // process group, so only then is it killed alone on a timeout.
+        if timeout.is_some() && !io::stdin().is_terminal() {
+            timeout::isolate(&mut command);
+        }
+
+        let status = match capture {
+            None => command
+                .spawn()
+                .and_then(|mut child| timeout::wait(&mut child, timeout)),
+            Some((path, tee)) => {
+                let file = fs::File::create(path).map_err(|err| CedarError::io(path, err))?;
+                run_captured(&mut command, file, tee, timeout)
+            }
+        }
+        .map_err(|err| CedarError::command(&command, err))?;
+
+        exited(program, status, timeout)
+    }
+}
+
+/// How a program run for the user ended, an error unless it succeeded.
+///
+/// # Arguments
+///
+/// * 'program' - The program run.
+/// * 'status' - How it exited, None when it ran past the timeout.
+/// * 'timeout' - The timeout it ran with.
+///
+fn exited(
+    program: PathBuf,
+    status: Option<process::ExitStatus>,
+    timeout: Option<Duration>,
+) -> Result<(), CedarError> {
+    match status {
+        Some(status) if status.success() => Ok(()),
+        Some(status) => Err(CedarError::Exited {
+            program,
+            code: status.code(),
+        }),
+        None => Err(CedarError::TimedOut {
+            program,
+            timeout: timeout.unwrap_or_default(),
+        }),
+    }
+}
+
+/// Runs a program with its stdout and stderr written to a file, and also to
+/// the terminal when teeing.
+///
+/// # Returns
+///
+/// * How the program exited, None when it ran past the timeout and was
+///   killed.
+///
+fn run_captured(
+    command: &mut process::Command,
+    file: fs::File,
+    tee: bool,
+    timeout: Option<Duration>,
+) -> io::Result<Option<process::ExitStatus>> {
+    if !tee {
+        let mut child = command.stdout(file.try_clone()?).stderr(file).spawn()?;
+        return timeout::wait(&mut child, timeout);
+    }
+
+    let mut child = command
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()?;
+
+    let file = std::sync::Mutex::new(file);
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+
+    // Both streams are copied as they come, each to its own terminal stream.
+    std::thread::scope(|scope| {
+        let copy = |mut from: Box<dyn io::Read + Send>, mut to: Box<dyn Write + Send>| {
+            let file = &file;
+            scope.spawn(move || {
+                let mut buffer = [0; 8192];
+                while let Ok(read) = from.read(&mut buffer) {
+                    if read == 0 {
+                        break;
+                    }
+                    let _ = to.write_all(&buffer[..read]).and_then(|_| to.flush());
+                    if let Ok(mut file) = file.lock() {
+                        let _ = file.write_all(&buffer[..read]);
+                    }
+                }
+            });
+        };
+
+        if let Some(stdout) = stdout {
+            copy(Box::new(stdout), Box::new(io::stdout()));
+        }
+        if let Some(stderr) = stderr {
+            copy(Box::new(stderr), Box::new(io::stderr()));
+        }
+
+        timeout::wait(&mut child, timeout)
+    })
+}
+
+/// Prints the output of the failed tests, unless it was printed already,
+/// and the summary of a test run.
+fn print_tests(report: &TestReport, options: &TestOptions) {
+    // Without capturing, the output was already printed with the status.
+    for failure in report.failures().filter(|_| !options.nocapture) {
+        println!(
+            "\n\t{}{}\n{}",
+            style::red("Output of "),
+            failure.name,
+            failure.output
+        );
+    }
+
+    let failed = report.failures().count();
+
+    println!(
+        "\n\t{} {} passed, {} failed\n",
+        style::header("Tests"),
+        report.results.len() - failed,
+        failed
+    );
+}
+
+/// Turns failed tests into an error.
+fn tests_passed(report: &TestReport) -> Result<(), CedarError> {
+    let failed = report.failures().count();
+
+    if failed > 0 {
+        return Err(CedarError::TestsFailed {
+            failed,
+            total: report.results.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Formats a duration in seconds with a fitting unit.
+fn format_seconds(seconds: f64) -> String {
+    match seconds {
+        s if s >= 1.0 => format!("{:.3} s", s),
+        s if s >= 1e-3 => format!("{:.3} ms", s * 1e3),
+        s => format!("{:.3} µs", s * 1e6),
+    }
+}
+
+/// Formats a size in bytes with a binary unit.
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.2} GiB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.2} MiB", b as f64 / (1u64 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.2} KiB", b as f64 / (1u64 << 10) as f64),
+        b => format!("{} B", b),
+    }
+}
+
+/// Opens a file with the platform's default handler, which for HTML files is
+/// the browser.
+fn open_in_browser(path: &Path) -> Result<(), io::Error> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        process::Command::new("open")
+    } else {
+        process::Command::new("xdg-open")
+    };
+
+    command
+        .arg(path)
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
+/// Finds the candidate closest to what was given, if it is close enough to
+/// plausibly be a typo of it.
+fn suggest(given: &str, candidates: &[&'static str]) -> Option<&'static str> {
+    let limit = (given.chars().count() / 3).max(1);
+
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(given, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between two strings, where transposing two
+/// adjacent characters also counts as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            rows[i][j] = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                rows[i][j] = rows[i][j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// Runs a git init command quietly.
+fn git_init(mut git: process::Command) -> Result<(), CedarError> {
+    let status = git
+        .stdout(process::Stdio::null())
+        .status()
+        .map_err(|err| CedarError::command(&git, err))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed("git").into());
+    }
+
+    Ok(())
+}
+
+/// Asks the user which license to use, an empty answer means no license.
+fn prompt_license() -> Result<Option<String>, io::Error> {
+    print!(
+        "\n\tLicense for the project ({}, empty for none): ",
+        license::supported().join(", ")
+    );
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    let answer = answer.trim();
+
+    if answer.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(answer.to_owned()))
+    }
+}
+
+/// The version of cedar, the commit it was built from, and the detected
+/// compiler.
+pub fn version() -> String {
+    format!(
+        "cedar {} ({})\ncompiler: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        env!("CEDAR_GIT_HASH"),
+        detect_compiler().unwrap_or_else(|| String::from("none found"))
+    )
+}
+
+/// The aliases of [alias] in the global config and in the manifest of the
+/// project in the current directory, whose aliases take precedence. A
+/// config that fails to load is reported by the command instead.
+pub fn aliases() -> BTreeMap<String, Vec<String>> {
+    let mut aliases: BTreeMap<String, Vec<String>> = Config::load()
+        .map(|config| config.alias)
+        .unwrap_or_default()
+        .iter()
+        .map(|(name, alias)| (name.clone(), alias.args()))
+        .collect();
+
+    if let Ok(project) = env::current_dir()
+        .map_err(ProjectError::from)
+        .and_then(Project::find)
+    {
+        aliases.extend(
+            project
+                .manifest
+                .alias
+                .iter()
+                .map(|(name, alias)| (name.clone(), alias.args())),
+        );
+    }
+
+    aliases
+}
+
+/// Replaces an alias in the position of the command with the arguments it
+/// expands to, again while they start with an alias. Commands of cedar
+/// cannot be aliased.
+///
+/// # Arguments
+///
+/// * 'args' - The arguments after the program name.
+/// * 'aliases' - Loads the aliases, only called when the command is not
+///   one of cedar.
+///
+fn expand_aliases(
+    mut args: Vec<String>,
+    aliases: impl FnOnce() -> BTreeMap<String, Vec<String>>,
+) -> Result<Vec<String>, CliError> {
+    // The command is the first argument that is not a flag or the value of
+    // a global flag.
+    let command = |args: &[String]| {
+        let mut index = 0;
+        while let Some(arg) = args.get(index) {
+            if arg == "--" {
+                return None;
+            }
+            if !arg.starts_with('-') || arg == "-" {
+                return Some(index);
+            }
+            let takes_value = GLOBAL_FLAGS.iter().any(|flag| {
+                (flag.long == arg || flag.short == Some(arg))
+                    && flag.value.is_some()
+                    && !flag.optional
+            });
+            index += if takes_value { 2 } else { 1 };
+        }
+        None
+    };
+
+    let Some(index) = command(&args).filter(|index| CommandSpec::find(&args[*index]).is_none())
+    else {
+        return Ok(args);
+    };
+
+    let aliases = aliases();
+    let mut expanded = vec![args[index].clone()];
+    let mut index = index;
+
+    while let Some(expansion) = aliases.get(&args[index]) {
+        args.splice(index..=index, expansion.iter().cloned());
+
+        match command(&args) {
+            Some(next) if CommandSpec::find(&args[next]).is_none() => {
+                if expanded.contains(&args[next]) {
+                    expanded.push(args[next].clone());
+                    return Err(CliError::InvalidArgument(format!(
+                        "{:?}, the alias expands to itself: {}.",
+                        expanded[0],
+                        expanded.join(" -> ")
+                    )));
+                }
+                expanded.push(args[next].clone());
+                index = next;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(args)
+}
+
+/// The executable of the plugin of a command, cedar-<name> on the PATH.
+fn plugin(name: &str) -> Option<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        return None;
+    }
+
+    sysdeps::which(&format!("cedar-{}", name))
+}
+
+/// The names of the plugins on the PATH, the executables named
+/// cedar-<name>, except ones shadowed by commands of cedar.
+pub fn plugins() -> Vec<String> {
+    let mut names: Vec<String> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let name = file_name.strip_prefix("cedar-")?;
+            let name = name.strip_suffix(env::consts::EXE_SUFFIX).unwrap_or(name);
+            Some(name.to_owned())
+        })
+        .filter(|name| CommandSpec::find(name).is_none() && plugin(name).is_some())
+        .collect();
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The help listing every command, the aliases and plugins of the user, and
+/// the global options.
+pub fn help(aliases: &BTreeMap<String, Vec<String>>, plugins: &[String]) -> String {
+    let mut help = format!(
+        "\n  A C project manager.\n\n  {} cedar [COMMAND] [OPTIONS]\n\n  {}\n",
+        style::header("Usage:"),
+        style::header("Commands:")
+    );
+
+    for spec in COMMANDS {
+        help.push_str(&help_entry(spec.name, 10, spec.summary));
+    }
+
+    if !aliases.is_empty() {
+        help.push_str(&format!("\n  {}\n", style::header("Aliases:")));
+
+        for (name, args) in aliases {
+            help.push_str(&help_entry(name, 10, &args.join(" ")));
+        }
+    }
+
+    if !plugins.is_empty() {
+        help.push_str(&format!("\n  {}\n", style::header("Plugins:")));
+
+        for name in plugins {
+            help.push_str(&help_entry(name, 10, &format!("Runs cedar-{}.", name)));
+        }
+    }
+
+    help.push_str(&format!("\n  {}\n", style::header("Options:")));
+    help.push_str(&flags_help(GLOBAL_FLAGS));
+    help.push_str("\n  Run `cedar help <COMMAND>` for the arguments and options of a command.\n");
+
+    help
+}
+
+/// The help of a single command: its usage, arguments, options, and
+/// examples.
+pub fn command_help(spec: &CommandSpec) -> String {
+    let mut usage = format!("cedar {}", spec.name);
+
+    for arg in spec.args {
+        if arg.required {
+            usage.push_str(&format!(" <{}>", arg.name));
+        } else {
+            usage.push_str(&format!(" [{}]", arg.name));
+        }
+    }
+
+    let mut help = format!(
+        "\n  {}\n\n  {} {} [OPTIONS]\n",
+        spec.description.replace('\n', "\n  "),
+        style::header("Usage:"),
+        usage
+    );
+
+    if !spec.aliases.is_empty() {
+        help.push_str(&format!(
+            "\n  {} {}\n",
+            style::header("Aliases:"),
+            spec.aliases.join(", ")
+        ));
+    }
+
+    if !spec.args.is_empty() {
+        help.push_str(&format!("\n  {}\n", style::header("Arguments:")));
+
+        for arg in spec.args {
+            help.push_str(&help_entry(&format!("<{}>", arg.name), 18, arg.help));
+        }
+    }
+
+    help.push_str(&format!("\n  {}\n", style::header("Options:")));
+    help.push_str(&flags_help(spec.flags));
+    help.push_str(&flags_help(GLOBAL_FLAGS));
+
+    help.push_str(&format!("\n  {}\n", style::header("Examples:")));
+
+    for example in spec.examples {
+        help.push_str(&format!("    {}\n", example));
+    }
+
+    help
+}
+
+/// The help entries of a list of flags.
+fn flags_help(flags: &[FlagSpec]) -> String {
+    flags
+        .iter()
+        .map(|flag| {
+            let mut name = match flag.short {
+                Some(short) => format!("{}, {}", short, flag.long),
+                None => flag.long.to_owned(),
+            };
+
+            match (flag.value, flag.optional) {
+                (Some(value), true) => name.push_str(&format!(" [<{}>]", value)),
+                (Some(value), false) => name.push_str(&format!(" <{}>", value)),
+                (None, _) => {}
+            }
+
+            help_entry(&name, 18, flag.help)
+        })
+        .collect()
+}
+
+/// A bold name padded to the given width followed by its description, with
+/// the continuation lines of the description aligned under its first line.
+/// Names longer than the width get the description on the next line.
+fn help_entry(name: &str, width: usize, description: &str) -> String {
+    let indent = format!("\n{}", " ".repeat(width + 6));
+    let separator = match name.len() > width {
+        true => indent.as_str(),
+        false => " ",
+    };
+
+    format!(
+        "    {}{}{}\n",
+        style::bold(&format!(" {:<width$}", name, width = width)),
+        separator,
+        description.replace('\n', &indent)
+    )
+}
+
+/// Finds the command invoked by the given name, suggesting the closest
+/// command if there is none.
+fn find_command(name: &str) -> Result<&'static CommandSpec, CliError> {
+    CommandSpec::find(name).ok_or_else(|| {
+        let names: Vec<&'static str> = COMMANDS.iter().map(|spec| spec.name).collect();
+        CliError::UnknownCommand(name.to_owned(), suggest(name, &names))
+    })
+}
+
+/// Finds the flag given by its long or short form among the flags of a
+/// command and the global flags.
+fn find_flag(flags: &'static [FlagSpec], name: &str) -> Option<&'static FlagSpec> {
+    flags
+        .iter()
+        .chain(GLOBAL_FLAGS)
+        .find(|flag| flag.long == name || flag.short == Some(name))
+}
+
+/// The long names of a list of flags, for suggestions.
+fn flag_names<'a, I: IntoIterator<Item = &'a FlagSpec>>(flags: I) -> Vec<&'static str> {
+    flags.into_iter().map(|flag| flag.long).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, CliError> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn expands_aliases() {
+        let aliases = || {
+            BTreeMap::from([
+                (
+                    String::from("b"),
+                    vec![String::from("build"), String::from("--release")],
+                ),
+                (
+                    String::from("rb"),
+                    vec![String::from("b"), String::from("--timings")],
+                ),
+                (String::from("loop"), vec![String::from("again")]),
+                (String::from("again"), vec![String::from("loop")]),
+            ])
+        };
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            expand_aliases(args(&["--color", "never", "rb", "--bin", "x"]), aliases).unwrap(),
+            args(&[
+                "--color",
+                "never",
+                "build",
+                "--release",
+                "--timings",
+                "--bin",
+                "x"
+            ])
+        );
+        assert_eq!(
+            expand_aliases(args(&["build"]), || unreachable!()).unwrap(),
+            args(&["build"])
+        );
+        assert!(expand_aliases(args(&["loop"]), aliases).is_err());
+    }
+
+    #[test]
+    fn suggestions() {
+        let commands: Vec<&'static str> = COMMANDS.iter().map(|spec| spec.name).collect();
+
+        assert_eq!(suggest("biuld", &commands), Some("build"));
+        assert_eq!(suggest("instal", &commands), Some("install"));
+        assert_eq!(suggest("deploy", &commands), None);
+        assert!(matches!(
+            parse(&["install", "--prefx", "/tmp"]),
+            Err(CliError::UnknownFlag(_, Some("--prefix")))
+        ));
+    }
+
+    #[test]
+    fn command_help_flags() {
+        let args = parse(&["build", "--help"]).unwrap();
+        assert_eq!(
+            (args.command, args.topic),
+            (Commands::Help, Some(Commands::Build))
+        );
+
+        let args = parse(&["help", "g"]).unwrap();
+        assert_eq!(args.topic, Some(Commands::Generate));
+
+        let args = parse(&["install", "--prefix=/opt"]).unwrap();
+        assert_eq!(args.flags, vec![Flags::Prefix(PathBuf::from("/opt"))]);
+
+        let args = parse(&["--color", "never", "build"]).unwrap();
+        assert_eq!(args.command, Commands::Build);
+        assert_eq!(args.flags, vec![Flags::Color(ColorChoice::Never)]);
+
+        let args = parse(&["intercept", "--", "make", "-j8", "--", "all"]).unwrap();
+        assert_eq!(args.command_line, ["make", "-j8", "--", "all"]);
+        assert!(parse(&["build", "--", "all", "src/main.c"]).is_err());
+
+        let args = parse(&["build", "--in-container", "--lib"]).unwrap();
+        assert_eq!(args.flags, vec![Flags::InContainer(None), Flags::Lib]);
+        let args = parse(&["build", "--remote", "box", "--lib"]).unwrap();
+        assert_eq!(
+            args.flags,
+            vec![Flags::Remote(String::from("box")), Flags::Lib]
+        );
+        let args = parse(&["build", "--in-container=gcc:14"]).unwrap();
+        assert_eq!(
+            args.flags,
+            vec![Flags::InContainer(Some(String::from("gcc:14")))]
+        );
+        let args = parse(&["test", "--report", r"junit:C:\out\report.xml"]).unwrap();
+        assert_eq!(
+            args.flags,
+            vec![Flags::Report(
+                ReportFormat::Junit,
+                Some(PathBuf::from(r"C:\out\report.xml"))
+            )]
+        );
+        let args = parse(&["test", "--report=tap"]).unwrap();
+        assert_eq!(args.flags, vec![Flags::Report(ReportFormat::Tap, None)]);
+        assert!(parse(&["test", "--report", "junitx:out.xml"]).is_err());
+
+        let args = parse(&["build", "--in-container", "gcc:14", "--lib"]).unwrap();
+        assert_eq!(
+            args.flags,
+            vec![Flags::InContainer(Some(String::from("gcc:14"))), Flags::Lib]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_timeouts() {
+        let path = env::temp_dir().join(format!("cedar-capture-{}", process::id()));
+        let file = fs::File::create(&path).unwrap();
+        let timeout = Some(Duration::from_millis(200));
+
+        let mut command = process::Command::new("sh");
+        command.args(["-c", "echo started; sleep 10"]);
+        timeout::isolate(&mut command);
+
+        let status = run_captured(&mut command, file, false, timeout).unwrap();
+
+        assert_eq!(status, None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "started\n");
+        assert!(matches!(
+            exited(PathBuf::from("app"), status, timeout),
+            Err(CedarError::TimedOut { timeout, .. }) if timeout == Duration::from_millis(200)
+        ));
+
+        fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,262 @@
+use std::{error::Error, fmt::Display, io, path::PathBuf, process, time::Duration};
+
+use crate::{
+    cli::CliError,
+    structure::{build::BuildError, project::ProjectError},
+};
+
+/// Exit code for errors that have no more specific code.
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit code for invalid command lines (unknown commands, missing or invalid
+/// arguments).
+pub const EXIT_USAGE: u8 = 2;
+/// Exit code for projects that cannot be used as they are: a missing or
+/// invalid manifest, config, or directory layout.
+pub const EXIT_PROJECT: u8 = 3;
+/// Exit code for failed filesystem operations.
+pub const EXIT_IO: u8 = 4;
+/// Exit code for external programs (git, doxygen, ...) that are missing or
+/// failed.
+pub const EXIT_TOOL: u8 = 5;
+/// Exit code for a translation unit that failed to compile.
+pub const EXIT_COMPILE: u8 = 101;
+/// Exit code for an artifact that failed to link or archive.
+pub const EXIT_LINK: u8 = 102;
+/// Exit code for a test run with failing tests, or coverage below the
+/// minimum.
+pub const EXIT_TEST: u8 = 103;
+
+/// The error type of every cedar operation, carrying enough context to
+/// report what went wrong and mapping to a stable process exit code.
+///
+/// # Members
+///
+/// * 'Cli' - The command line was invalid.
+/// * 'Project' - The project, manifest, or config was invalid.
+/// * 'Build' - Compiling or linking failed.
+/// * 'Io' - A filesystem operation on the given path failed.
+/// * 'Command' - The given program could not be started.
+/// * 'TestsFailed' - Tests failed, holds how many out of how many.
+/// * 'CoverageTooLow' - The line coverage is below the required minimum.
+/// * 'BenchFailed' - A benchmark exited unsuccessfully, holds its name and
+///   stderr.
+/// * 'Vulnerable' - Dependencies are affected by known vulnerabilities,
+///   holds how many advisories matched.
+/// * 'DeniedLicenses' - Dependencies use denied licenses, holds their names.
+/// * 'UncommittedChanges' - A release was cut from a tree with uncommitted
+///   changes, holds the files.
+/// * 'TagExists' - The tag of a release exists already.
+/// * 'TaskFailed' - A command of a task exited unsuccessfully, holds the
+///   task and the command.
+/// * 'Exited' - A program run for the user (by cedar run, or a plugin)
+///   exited unsuccessfully, holds it and its exit code, which cedar
+///   exits with.
+/// * 'TimedOut' - A program run for the user ran past its timeout and was
+///   killed, holds it and the timeout.
+/// * 'Issues' - A check of the sources found issues, holds the check and
+///   how many.
+///
+#[derive(Debug)]
+pub enum CedarError {
+    Cli(CliError),
+    Project(ProjectError),
+    Build(BuildError),
+    Io { path: PathBuf, source: io::Error },
+    Command { program: String, source: io::Error },
+    TestsFailed { failed: usize, total: usize },
+    CoverageTooLow { percent: f64, minimum: f64 },
+    BenchFailed { name: String, output: String },
+    Vulnerable { count: usize },
+    DeniedLicenses { names: Vec<String> },
+    UncommittedChanges { files: Vec<String> },
+    TagExists { tag: String },
+    TaskFailed { task: String, command: String },
+    Exited { program: PathBuf, code: Option<i32> },
+    TimedOut { program: PathBuf, timeout: Duration },
+    Issues { check: &'static str, count: usize },
+}
+
+impl CedarError {
+    /// Wraps an io::Error with the path it happened on.
+    pub fn io<P: Into<PathBuf>>(path: P, source: io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+    /// Wraps an io::Error from starting a command with the program's name.
+    pub fn command(command: &process::Command, source: io::Error) -> Self {
+        Self::Command {
+            program: command.get_program().to_string_lossy().into_owned(),
+            source,
+        }
+    }
+    /// The process exit code for the error, see the EXIT_* constants.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Cli(CliError::Aborted(_)) => EXIT_FAILURE,
+            Self::Cli(_) => EXIT_USAGE,
+            Self::Project(err) => match err {
+                ProjectError::IoError(_) => EXIT_IO,
+                ProjectError::MissingTool(_) | ProjectError::ToolFailed(_) => EXIT_TOOL,
+                _ => EXIT_PROJECT,
+            },
+            Self::Build(err) => match err {
+                BuildError::CompilationFailed(..) => EXIT_COMPILE,
+                BuildError::LinkingFailed(_) => EXIT_LINK,
+                BuildError::NotExecutable => EXIT_FAILURE,
+                BuildError::NotSharedLibrary => EXIT_FAILURE,
+                _ => EXIT_PROJECT,
+            },
+            Self::Io { .. } => EXIT_IO,
+            Self::Command { .. } => EXIT_TOOL,
+            Self::TestsFailed { .. } | Self::CoverageTooLow { .. } => EXIT_TEST,
+            Self::BenchFailed { .. }
+            | Self::Vulnerable { .. }
+            | Self::DeniedLicenses { .. }
+            | Self::UncommittedChanges { .. }
+            | Self::TagExists { .. }
+            | Self::TaskFailed { .. }
+            | Self::TimedOut { .. }
+            | Self::Issues { .. } => EXIT_FAILURE,
+            Self::Exited { code, .. } => code
+                .and_then(|code| u8::try_from(code).ok())
+                .filter(|code| *code != 0)
+                .unwrap_or(EXIT_FAILURE),
+        }
+    }
+}
+
+impl Display for CedarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cli(err) => write!(f, "{}", err),
+            Self::Project(err) => write!(f, "{}", err),
+            Self::Build(err) => write!(f, "{}", err),
+            Self::Io { path, source } => writeln!(f, "Error: {:?}: {}", path, source),
+            Self::Command { program, source } if source.kind() == io::ErrorKind::NotFound => {
+                writeln!(f, "Error: Failed to run {}, is it installed?", program)
+            }
+            Self::Command { program, source } => {
+                writeln!(f, "Error: Failed to run {}. \n {}", program, source)
+            }
+            Self::TestsFailed { failed, total } => {
+                writeln!(f, "Error: {} of {} tests failed.", failed, total)
+            }
+            Self::CoverageTooLow { percent, minimum } => writeln!(
+                f,
+                "Error: Line coverage {:.2}% is below the minimum of {}%.",
+                percent, minimum
+            ),
+            Self::BenchFailed { name, output } => {
+                writeln!(f, "Error: Benchmark {} exited unsuccessfully.", name)?;
+                write!(f, "{}", output)
+            }
+            Self::Vulnerable { count } => writeln!(
+                f,
+                "Error: Found {} known {} in the dependencies.",
+                count,
+                if *count == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                }
+            ),
+            Self::DeniedLicenses { names } => writeln!(
+                f,
+                "Error: Dependencies use denied licenses: {}.",
+                names.join(", ")
+            ),
+            Self::UncommittedChanges { files } => {
+                writeln!(
+                    f,
+                    "Error: The tree has uncommitted changes, commit or stash them first."
+                )?;
+                files.iter().try_for_each(|file| writeln!(f, " {}", file))
+            }
+            Self::TagExists { tag } => writeln!(
+                f,
+                "Error: Tag {} already exists, bump the version to release.",
+                tag
+            ),
+            Self::TaskFailed { task, command } => {
+                writeln!(f, "Error: Task {} failed running `{}`.", task, command)
+            }
+            Self::Exited { program, code } => match code {
+                Some(code) => writeln!(f, "Error: {:?} exited with {}.", program, code),
+                None => writeln!(f, "Error: {:?} was killed by a signal.", program),
+            },
+            Self::TimedOut { program, timeout } => writeln!(
+                f,
+                "Error: {:?} ran for longer than {} and was killed.",
+                program,
+                crate::structure::timeout::display(*timeout)
+            ),
+            Self::Issues { check, count } => {
+                writeln!(f, "Error: {} found {} issue(s).", check, count)
+            }
+        }
+    }
+}
+
+impl Error for CedarError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Cli(err) => Some(err),
+            Self::Project(err) => Some(err),
+            Self::Build(err) => Some(err),
+            Self::Io { source, .. } | Self::Command { source, .. } => Some(source),
+            Self::TestsFailed { .. }
+            | Self::CoverageTooLow { .. }
+            | Self::BenchFailed { .. }
+            | Self::Vulnerable { .. }
+            | Self::DeniedLicenses { .. }
+            | Self::UncommittedChanges { .. }
+            | Self::TagExists { .. }
+            | Self::TaskFailed { .. }
+            | Self::Exited { .. }
+            | Self::TimedOut { .. }
+            | Self::Issues { .. } => None,
+        }
+    }
+}
+
+impl From<CliError> for CedarError {
+    fn from(err: CliError) -> Self {
+        Self::Cli(err)
+    }
+}
+
+impl From<ProjectError> for CedarError {
+    fn from(err: ProjectError) -> Self {
+        Self::Project(err)
+    }
+}
+
+impl From<BuildError> for CedarError {
+    fn from(err: BuildError) -> Self {
+        Self::Build(err)
+    }
+}
+
+impl From<io::Error> for CedarError {
+    fn from(err: io::Error) -> Self {
+        Self::Project(ProjectError::IoError(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        let compile = CedarError::from(BuildError::CompilationFailed("a.c".into(), "".into()));
+        let usage = CedarError::from(CliError::InvalidCommand);
+        let manifest = CedarError::from(ProjectError::InvalidManifest);
+
+        assert_eq!(compile.exit_code(), EXIT_COMPILE);
+        assert_eq!(usage.exit_code(), EXIT_USAGE);
+        assert_eq!(manifest.exit_code(), EXIT_PROJECT);
+    }
+}
@@ -1,2 +1,32 @@
+//! Cedar, a C project manager.
+//!
+//! Besides the `cedar` binary, the crate can be used to drive builds
+//! programmatically instead of shelling out and scraping terminal output:
+//!
+//! ```no_run
+//! use cedar::{BuildOptions, Project};
+//!
+//! let project = Project::load("path/to/project").unwrap();
+//! let options = BuildOptions {
+//!     quiet: true,
+//!     ..Default::default()
+//! };
+//! let report = project.build(&options).unwrap();
+//!
+//! for artifact in report.artifacts {
+//!     println!("{}", artifact.display());
+//! }
+//! ```
+
 pub mod cli;
+pub mod error;
 pub mod structure;
+pub mod style;
+
+pub use error::CedarError;
+pub use structure::{
+    build::{BuildError, BuildOptions, BuildReport},
+    manifest::Manifest,
+    project::{Project, ProjectError},
+    test::{TestOptions, TestReport, TestResult},
+};
@@ -1,9 +1,16 @@
-use std::error::Error;
+use std::process::ExitCode;
 
-use cedar::cli::Args;
+use cedar::{cli::Args, CedarError};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::get()?;
-    args.exec()?;
-    Ok(())
+fn main() -> ExitCode {
+    match Args::get()
+        .map_err(CedarError::from)
+        .and_then(|args| args.exec())
+    {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprint!("{}", err);
+            ExitCode::from(err.exit_code())
+        }
+    }
 }
@@ -0,0 +1,347 @@
+use std::{
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::error::CedarError;
+
+use super::{
+    build::{BuildError, BuildOptions, Toolchain},
+    ignore,
+    project::{Project, ProjectError},
+};
+
+/// A symbol a shared library exports, as nm -D lists it.
+///
+/// # Fields
+///
+/// * 'name' - The name, with its version when the library has a version
+///   script, e.g. parser_new@@PARSER_1.
+/// * 'kind' - The type letter of nm, T for a function, D, B, or R for data.
+/// * 'size' - The size in bytes, when the library records it.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: char,
+    pub size: Option<u64>,
+}
+
+impl Symbol {
+    /// Whether the symbol is code rather than data.
+    pub fn is_function(&self) -> bool {
+        matches!(self.kind, 'T' | 't' | 'W' | 'w' | 'i')
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.is_function(), self.size) {
+            (true, _) => write!(f, "{} (function)", self.name),
+            (false, Some(size)) => write!(f, "{} (object, {} bytes)", self.name, size),
+            (false, None) => write!(f, "{} (object)", self.name),
+        }
+    }
+}
+
+/// How the ABI of a shared library changed between two builds.
+///
+/// # Fields
+///
+/// * 'library' - The shared library of the current build.
+/// * 'removed' - Symbols the old library exports and the current one does
+///   not, breaking every program linked against them.
+/// * 'changed' - Symbols exported by both that turned from code to data or
+///   the other way, or data whose size changed, old and current.
+/// * 'added' - Symbols only the current library exports.
+/// * 'abidiff' - What abidiff reports, when libabigail is installed, and
+///   whether it found an incompatible change.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbiDiff {
+    pub library: PathBuf,
+    pub removed: Vec<Symbol>,
+    pub changed: Vec<(Symbol, Symbol)>,
+    pub added: Vec<Symbol>,
+    pub abidiff: Option<(bool, String)>,
+}
+
+impl AbiDiff {
+    /// The number of changes breaking programs linked against the old
+    /// library.
+    pub fn breaks(&self) -> usize {
+        let abidiff = self.abidiff.as_ref().is_some_and(|(breaks, _)| *breaks);
+
+        match self.removed.len() + self.changed.len() {
+            0 => usize::from(abidiff),
+            count => count,
+        }
+    }
+}
+
+/// Builds the shared library of the project and compares what it exports
+/// with an old build of it, a release to stay compatible with.
+///
+/// The exported symbols are compared by name, kind, and size. abidiff of
+/// libabigail also compares the types of functions and structures when it
+/// is installed, from the debug info of both libraries.
+///
+/// # Arguments
+///
+/// * 'project' - The project, a library producing a shared library.
+/// * 'old' - The old shared library.
+/// * 'options' - How to build the current library.
+///
+pub fn diff(project: &Project, old: &Path, options: &BuildOptions) -> Result<AbiDiff, CedarError> {
+    if !old.is_file() {
+        return Err(CedarError::io(
+            old,
+            io::Error::from(io::ErrorKind::NotFound),
+        ));
+    }
+
+    let report = project.build(options)?;
+    let library = report
+        .artifacts
+        .into_iter()
+        .find(|artifact| {
+            artifact
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().contains(".so"))
+        })
+        .ok_or(BuildError::NotSharedLibrary)?;
+
+    let toolchain = Toolchain::from_compiler(&project.manifest.build.compiler)?;
+    let (removed, changed, added) = compare(
+        &exported(&toolchain.nm, old)?,
+        &exported(&toolchain.nm, &library)?,
+    );
+
+    // abidiff exits with bit 3 set for a change breaking the ABI.
+    let abidiff = match process::Command::new("abidiff")
+        .arg(old)
+        .arg(&library)
+        .output()
+    {
+        Ok(output) => Some((
+            output.status.code().is_some_and(|code| code & 8 != 0),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(source) => {
+            return Err(CedarError::Command {
+                program: String::from("abidiff"),
+                source,
+            })
+        }
+    };
+
+    Ok(AbiDiff {
+        library,
+        removed,
+        changed,
+        added,
+        abidiff,
+    })
+}
+
+/// Which symbols of an artifact cedar symbols lists.
+///
+/// # Fields
+///
+/// * 'undefined' - The symbols it references and leaves to others to
+///   define, instead of those it defines.
+/// * 'dynamic' - Only the symbols of the dynamic symbol table, those a
+///   shared library exports or an executable loads.
+/// * 'pattern' - Only the symbols whose name contains it, or matches it
+///   when it has the wildcards *, ?, or [...].
+///
+#[derive(Debug, Clone, Default)]
+pub struct SymbolFilter {
+    pub undefined: bool,
+    pub dynamic: bool,
+    pub pattern: Option<String>,
+}
+
+impl SymbolFilter {
+    fn matches(&self, symbol: &Symbol) -> bool {
+        match &self.pattern {
+            Some(pattern) if pattern.contains(['*', '?', '[']) => {
+                ignore::glob(pattern.as_bytes(), symbol.name.as_bytes())
+            }
+            Some(pattern) => symbol.name.contains(pattern.as_str()),
+            None => true,
+        }
+    }
+}
+
+/// Builds the project and lists the external symbols of its artifacts, as
+/// nm lists them.
+///
+/// # Arguments
+///
+/// * 'project' - The project.
+/// * 'options' - How to build, and which artifacts.
+/// * 'filter' - Which symbols to list.
+///
+/// # Returns
+///
+/// * The symbols of each artifact, in the order of their names.
+///
+pub fn symbols(
+    project: &Project,
+    options: &BuildOptions,
+    filter: &SymbolFilter,
+) -> Result<Vec<(PathBuf, Vec<Symbol>)>, CedarError> {
+    let report = project.build(options)?;
+    let toolchain = Toolchain::from_compiler(&project.manifest.build.compiler)?;
+
+    let mut args = vec!["-P", "-g"];
+    args.extend(filter.dynamic.then_some("-D"));
+    args.push(match filter.undefined {
+        true => "--undefined-only",
+        false => "--defined-only",
+    });
+
+    let mut listed = Vec::new();
+
+    for artifact in report.artifacts {
+        let mut symbols = nm(&toolchain.nm, &args, &artifact)?;
+
+        // The members of an archive define and reference the same names.
+        symbols.retain(|symbol| filter.matches(symbol));
+        symbols.dedup();
+
+        listed.push((artifact, symbols));
+    }
+
+    Ok(listed)
+}
+
+/// The dynamic symbols a shared library defines.
+fn exported(nm_program: &str, library: &Path) -> Result<Vec<Symbol>, CedarError> {
+    nm(nm_program, &["-D", "-P", "--defined-only"], library)
+}
+
+/// Runs nm over a file, an archive lists the symbols of every member.
+fn nm(program: &str, args: &[&str], file: &Path) -> Result<Vec<Symbol>, CedarError> {
+    let mut command = process::Command::new(program);
+    command.args(args).arg(file);
+
+    let output = command
+        .output()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !output.status.success() {
+        return Err(ProjectError::ToolFailed("nm").into());
+    }
+
+    Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Reads the POSIX output of nm, name type value [size] per line, sizes in
+/// hexadecimal. The member lines of an archive, lib.a[a.o]:, are skipped.
+fn parse(listing: &str) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = listing
+        .lines()
+        .filter(|line| !line.ends_with(':'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let kind = fields.next()?.chars().next()?;
+            let size = fields
+                .nth(1)
+                .and_then(|size| u64::from_str_radix(size, 16).ok());
+
+            Some(Symbol {
+                name: name.to_owned(),
+                kind,
+                size,
+            })
+        })
+        .collect();
+
+    symbols.sort_by(|a, b| (&a.name, a.kind).cmp(&(&b.name, b.kind)));
+    symbols
+}
+
+/// The removed, changed, and added symbols between two exports.
+fn compare(old: &[Symbol], new: &[Symbol]) -> (Vec<Symbol>, Vec<(Symbol, Symbol)>, Vec<Symbol>) {
+    let find =
+        |symbols: &[Symbol], name: &str| symbols.iter().find(|symbol| symbol.name == name).cloned();
+
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+
+    for symbol in old {
+        match find(new, &symbol.name) {
+            None => removed.push(symbol.clone()),
+            Some(current) => {
+                let resized = !current.is_function() && current.size != symbol.size;
+
+                if current.is_function() != symbol.is_function() || resized {
+                    changed.push((symbol.clone(), current));
+                }
+            }
+        }
+    }
+
+    let added = new
+        .iter()
+        .filter(|symbol| find(old, &symbol.name).is_none())
+        .cloned()
+        .collect();
+
+    (removed, changed, added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_exports() {
+        let old =
+            parse("parser_new T 1110 1e\nparser_free T 1130 b\ntable D 4010 8\nversion R 2000 4\n");
+        let new = parse(
+            "parser_new T 1110 2a\ntable D 4010 10\nversion T 1140 9\nparser_reset T 1150 c\n",
+        );
+
+        let (removed, changed, added) = compare(&old, &new);
+
+        assert_eq!(
+            removed
+                .iter()
+                .map(|symbol| symbol.to_string())
+                .collect::<Vec<_>>(),
+            ["parser_free (function)"]
+        );
+        assert_eq!(
+            changed
+                .iter()
+                .map(|(old, new)| format!("{} -> {}", old, new))
+                .collect::<Vec<_>>(),
+            [
+                "table (object, 8 bytes) -> table (object, 16 bytes)",
+                "version (object, 4 bytes) -> version (function)"
+            ]
+        );
+        assert_eq!(added[0].name, "parser_reset");
+
+        let filter = SymbolFilter {
+            pattern: Some(String::from("parser_*")),
+            ..Default::default()
+        };
+        let archive = parse("libparser.a[a.o]:\nparser_new T 0 1e\ntable D 0 8\n");
+        assert_eq!(
+            archive
+                .iter()
+                .filter(|symbol| filter.matches(symbol))
+                .map(|symbol| symbol.name.as_str())
+                .collect::<Vec<_>>(),
+            ["parser_new"]
+        );
+    }
+}
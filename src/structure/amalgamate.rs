@@ -0,0 +1,226 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    ignore,
+    manifest::Symlinks,
+    project::{Project, ProjectError},
+};
+
+/// Combines the project into a single source file and a single header in
+/// build/amalgamation/, SQLite style, so it can be dropped into another tree.
+///
+/// Public headers from include/ become `<name>.h` and every source file from
+/// src/ (along with private headers in src/) becomes `<name>.c`. Files are
+/// emitted in dependency order and quoted includes of project files are
+/// removed, since their contents are already inlined above.
+///
+/// # Arguments
+///
+/// * 'project' - The project to amalgamate.
+///
+/// # Returns
+///
+/// * The paths of the generated source and header, an error for a bin
+///   project, whose main would end up in the source.
+///
+pub fn amalgamate(project: &Project) -> Result<(PathBuf, PathBuf), ProjectError> {
+    let (src_path, include_path) = (&project.src_dir, &project.include_dir);
+
+    let manifest = &project.manifest;
+    if manifest.meta.kind.is_bin() {
+        return Err(ProjectError::NotALibrary("cedar amalgamate"));
+    }
+
+    let name = &manifest.meta.name;
+    let symlinks = manifest.build.symlinks;
+
+    let mut headers = files_with_extensions(include_path, &["h"], symlinks)?;
+    let mut private_headers = files_with_extensions(src_path, &["h"], symlinks)?;
+    let mut sources = files_with_extensions(src_path, &["c"], symlinks)?;
+    headers.sort();
+    private_headers.sort();
+    sources.sort();
+
+    let root = project.root.canonicalize()?;
+    let roots = [include_path.as_path(), src_path.as_path()];
+    let guard = format!("{}_AMALGAMATION_H", name)
+        .to_ascii_uppercase()
+        .replace(|ch: char| !ch.is_ascii_alphanumeric(), "_");
+
+    let mut header = format!(
+        "/* {} v{} amalgamated header, generated by cedar. */\n#ifndef {2}\n#define {2}\n",
+        name, manifest.meta.version, guard
+    );
+
+    let mut emitted = HashSet::new();
+    for file in &headers {
+        emit(file, &root, &roots, &mut emitted, &mut header)?;
+    }
+    header.push_str(&format!("\n#endif /* {} */\n", guard));
+
+    let mut source = format!(
+        "/* {} v{} amalgamated source, generated by cedar. */\n#include \"{}.h\"\n",
+        name, manifest.meta.version, name
+    );
+
+    // Public headers already live in the amalgamated header.
+    for file in private_headers.iter().chain(sources.iter()) {
+        emit(file, &root, &roots, &mut emitted, &mut source)?;
+    }
+
+    let output_path = project.build_dir.join("amalgamation");
+    fs::create_dir_all(&output_path)?;
+
+    let source_path = output_path.join(format!("{}.c", name));
+    let header_path = output_path.join(format!("{}.h", name));
+
+    fs::write(&source_path, source)?;
+    fs::write(&header_path, header)?;
+
+    Ok((source_path, header_path))
+}
+
+/// Appends a file to the output after first appending every project file it
+/// includes (depth first), skipping files that were already emitted. Include
+/// cycles are broken by marking files before visiting their includes.
+fn emit(
+    file: &Path,
+    root: &Path,
+    roots: &[&Path],
+    emitted: &mut HashSet<PathBuf>,
+    output: &mut String,
+) -> Result<(), ProjectError> {
+    let file = file.canonicalize()?;
+
+    if !emitted.insert(file.clone()) {
+        return Ok(());
+    }
+
+    let contents = fs::read_to_string(&file)?;
+    let mut body = String::new();
+
+    for line in contents.lines() {
+        match local_include(line).and_then(|include| resolve(&file, include, roots)) {
+            Some(dependency) => emit(&dependency, root, roots, emitted, output)?,
+            None => {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+    }
+
+    output.push_str(&format!(
+        "\n/* ---- {} ---- */\n",
+        file.strip_prefix(root).unwrap_or(&file).display()
+    ));
+    output.push_str(&body);
+
+    Ok(())
+}
+
+/// Resolves a quoted include the way the compiler would for a cedar project,
+/// relative to the including file first and then in each include root.
+pub(crate) fn resolve(file: &Path, include: &str, roots: &[&Path]) -> Option<PathBuf> {
+    let relative = file.parent().map(|dir| dir.join(include));
+
+    relative
+        .into_iter()
+        .chain(roots.iter().map(|root| root.join(include)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Returns the path of a quoted `#include "..."` directive, if the line is
+/// one. System includes using angle brackets are never project files.
+pub(crate) fn local_include(line: &str) -> Option<&str> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let rest = directive.strip_prefix("include")?.trim_start();
+    let rest = rest.strip_prefix('"')?;
+
+    rest.find('"').map(|end| &rest[..end])
+}
+
+/// Collects the files under a directory with one of the given extensions.
+pub(crate) fn files_with_extensions(
+    path: &Path,
+    extensions: &[&str],
+    symlinks: Symlinks,
+) -> Result<Vec<PathBuf>, ProjectError> {
+    if !path.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    Ok(ignore::files(path, symlinks)?
+        .into_iter()
+        .filter(|file| {
+            file.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.contains(&ext))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn test_local_include() {
+        assert_eq!(
+            local_include("#include \"net/socket.h\""),
+            Some("net/socket.h")
+        );
+        assert_eq!(local_include("  #  include \"a.h\" // note"), Some("a.h"));
+        assert_eq!(local_include("#include <stdio.h>"), None);
+        assert_eq!(local_include("int include = 0;"), None);
+    }
+
+    #[test]
+    fn amalgamates_only_libraries() {
+        let root = env::temp_dir().join(format!("cedar-amalgamate-{}", process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("include")).unwrap();
+        fs::write(root.join("include").join("api.h"), "int a(void);\n").unwrap();
+        fs::write(
+            root.join("src").join("a.c"),
+            "#include \"api.h\"\nint a(void) { return 1; }\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src").join("main.c"),
+            "int main(void) { return 0; }\n",
+        )
+        .unwrap();
+
+        let manifest = |kind: &str| {
+            fs::write(
+                root.join("cedar.toml"),
+                format!(
+                    "[meta]\nname = \"a\"\nversion = \"0.1.0\"\ntype = \"{}\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n",
+                    kind
+                ),
+            )
+            .unwrap();
+            Project::load(&root).unwrap()
+        };
+
+        assert!(matches!(
+            amalgamate(&manifest("bin")),
+            Err(ProjectError::NotALibrary(_))
+        ));
+
+        fs::remove_file(root.join("src").join("main.c")).unwrap();
+        let (source, header) = amalgamate(&manifest("lib")).unwrap();
+        assert!(fs::read_to_string(header).unwrap().contains("int a(void);"));
+        let source = fs::read_to_string(source).unwrap();
+        assert!(source.contains("int a(void) { return 1; }"));
+        assert!(!source.contains("#include \"api.h\""));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::error::CedarError;
+
+use super::{
+    lockfile::{LockedPackage, Lockfile},
+    project::{Project, ProjectError},
+    version::{Version, VersionReq},
+};
+
+/// The advisory database bundled with cedar, an offline snapshot of known
+/// vulnerabilities in commonly vendored libraries.
+const BUNDLED: &str = include_str!("advisories/advisories.toml");
+
+/// A known vulnerability.
+///
+/// # Fields
+///
+/// * 'id' - The identifier of the advisory, e.g. "CVE-2022-37434".
+/// * 'package' - The name of the affected dependency.
+/// * 'versions' - A requirement matching the affected versions, e.g.
+///   "<1.2.13".
+/// * 'revisions' - Git commits known to be affected, matched against the
+///   locked commits of git dependencies whatever their version.
+/// * 'summary' - A one line description of the vulnerability.
+///
+#[derive(Debug, Clone, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    #[serde(default)]
+    pub versions: Option<String>,
+    #[serde(default)]
+    pub revisions: Vec<String>,
+    #[serde(default)]
+    pub summary: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Database {
+    #[serde(default, rename = "advisory")]
+    advisories: Vec<Advisory>,
+}
+
+impl Advisory {
+    /// Whether the advisory affects a locked dependency.
+    pub fn affects(&self, package: &LockedPackage) -> Result<bool, ProjectError> {
+        if package.name != self.package {
+            return Ok(false);
+        }
+
+        if let Some(commit) = package.commit() {
+            // Revisions may be abbreviated.
+            if self
+                .revisions
+                .iter()
+                .any(|revision| commit.starts_with(revision.as_str()))
+            {
+                return Ok(true);
+            }
+        }
+
+        match &self.versions {
+            Some(versions) => Ok(versions
+                .parse::<VersionReq>()?
+                .matches(&package.version.parse::<Version>()?)),
+            None => Ok(false),
+        }
+    }
+}
+
+/// A dependency affected by an advisory.
+///
+/// # Fields
+///
+/// * 'package' - The locked dependency.
+/// * 'advisory' - The advisory of its name.
+/// * 'unknown' - Why the advisory could not be checked against the
+///   dependency, e.g. a version that does not parse. None when the
+///   dependency is affected.
+///
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub package: LockedPackage,
+    pub advisory: Advisory,
+    pub unknown: Option<String>,
+}
+
+/// Loads the bundled advisories, along with those of an extra database in the
+/// same format.
+pub fn advisories(database: Option<&Path>) -> Result<Vec<Advisory>, CedarError> {
+    let parse = |contents: &str, name: &str| {
+        toml::from_str::<Database>(contents)
+            .map(|database| database.advisories)
+            .map_err(|err| {
+                ProjectError::InvalidDependency(format!(
+                    "Invalid advisory database {}: {}",
+                    name, err
+                ))
+            })
+    };
+
+    let mut advisories = parse(BUNDLED, "(bundled)")?;
+
+    if let Some(path) = database {
+        let contents = fs::read_to_string(path).map_err(|err| CedarError::io(path, err))?;
+        advisories.extend(parse(&contents, &format!("{:?}", path))?);
+    }
+
+    Ok(advisories)
+}
+
+/// Checks every dependency in cedar.lock against the advisories.
+///
+/// # Arguments
+///
+/// * 'project' - The project to audit.
+/// * 'database' - An extra advisory database to check against.
+///
+/// # Returns
+///
+/// * The affected dependencies, in lockfile order, along with the advisories
+///   that could not be checked.
+///
+pub fn audit(project: &Project, database: Option<&Path>) -> Result<Vec<Finding>, CedarError> {
+    let lockfile = Lockfile::load(project)?;
+    check(&lockfile.packages, &advisories(database)?)
+}
+
+/// Checks locked dependencies against advisories. A version that does not
+/// parse only leaves its advisory unknown, the other advisories are still
+/// checked.
+fn check(packages: &[LockedPackage], advisories: &[Advisory]) -> Result<Vec<Finding>, CedarError> {
+    let mut findings = Vec::new();
+
+    for package in packages {
+        for advisory in advisories {
+            let unknown = match advisory.affects(package) {
+                Ok(false) => continue,
+                Ok(true) => None,
+                Err(ProjectError::InvalidVersion(version)) => {
+                    Some(format!("invalid version {:?}", version))
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            findings.push(Finding {
+                package: package.clone(),
+                advisory: advisory.clone(),
+                unknown,
+            });
+        }
+    }
+
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_versions_and_revisions() {
+        let advisories = advisories(None).unwrap();
+        let zlib = advisories
+            .iter()
+            .find(|advisory| advisory.id == "CVE-2022-37434")
+            .unwrap();
+
+        let mut package = LockedPackage {
+            name: "zlib".into(),
+            version: "1.2.12".into(),
+            source: "path+vendor/zlib".into(),
+            checksum: None,
+        };
+        assert!(zlib.affects(&package).unwrap());
+
+        package.version = "1.2.13".into();
+        assert!(!zlib.affects(&package).unwrap());
+
+        let pinned = Advisory {
+            revisions: vec!["0123abc".into()],
+            versions: None,
+            ..zlib.clone()
+        };
+        package.source = "git+https://example.com/zlib.git#0123abcdef".into();
+        assert!(pinned.affects(&package).unwrap());
+    }
+
+    #[test]
+    fn reports_unparseable_versions_as_unknown() {
+        let advisories = advisories(None).unwrap();
+        let packages = [
+            LockedPackage {
+                name: "zlib".into(),
+                version: "snapshot".into(),
+                source: "path+vendor/zlib".into(),
+                checksum: None,
+            },
+            LockedPackage {
+                name: "zlib".into(),
+                version: "1.2.12".into(),
+                source: "path+third_party/zlib".into(),
+                checksum: None,
+            },
+        ];
+
+        let findings = check(&packages, &advisories).unwrap();
+        let unknown: Vec<&Finding> = findings
+            .iter()
+            .filter(|finding| finding.unknown.is_some())
+            .collect();
+
+        assert!(!unknown.is_empty());
+        assert!(unknown
+            .iter()
+            .all(|finding| finding.package.version == "snapshot"));
+        assert!(findings.iter().any(|finding| finding.unknown.is_none()
+            && finding.package.version == "1.2.12"
+            && finding.advisory.id == "CVE-2022-37434"));
+    }
+}
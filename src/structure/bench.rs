@@ -0,0 +1,332 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::PathBuf,
+    process::{self, Stdio},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cli::CliError, error::CedarError};
+
+use super::{
+    build::{build, BuildOptions},
+    project::Project,
+    target::{Target, TargetFilter, TargetKind},
+};
+
+/// Options controlling a benchmark run.
+///
+/// # Fields
+///
+/// * 'quiet' - Suppresses the build output.
+/// * 'filter' - Only runs the benchmarks whose name contains the pattern.
+/// * 'iterations' - How many times each benchmark is timed, after one
+///   untimed warm up run.
+/// * 'save_baseline' - Saves the results as the baseline with this name.
+/// * 'baseline' - Compares the results against the baseline with this name.
+///
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub quiet: bool,
+    pub filter: Option<String>,
+    pub iterations: usize,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            quiet: false,
+            filter: None,
+            iterations: 10,
+            save_baseline: None,
+            baseline: None,
+        }
+    }
+}
+
+/// Summary statistics of the run times of a benchmark, in seconds.
+///
+/// # Fields
+///
+/// * 'samples' - How many runs were timed.
+/// * 'mean' - The mean run time.
+/// * 'median' - The median run time.
+/// * 'stddev' - The sample standard deviation of the run times.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub samples: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+}
+
+impl Stats {
+    /// Computes the statistics of a set of run times.
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let count = samples.len();
+
+        if count == 0 {
+            return Self {
+                samples: 0,
+                mean: 0.0,
+                median: 0.0,
+                stddev: 0.0,
+            };
+        }
+
+        let mean = samples.iter().sum::<f64>() / count as f64;
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let median = match count % 2 {
+            0 => (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0,
+            _ => sorted[count / 2],
+        };
+
+        let variance = match count {
+            1 => 0.0,
+            _ => {
+                samples
+                    .iter()
+                    .map(|sample| (sample - mean).powi(2))
+                    .sum::<f64>()
+                    / (count - 1) as f64
+            }
+        };
+
+        Self {
+            samples: count,
+            mean,
+            median,
+            stddev: variance.sqrt(),
+        }
+    }
+    /// Compares these statistics against a baseline with Welch's t-test, a
+    /// difference counts as significant when |t| exceeds 2 (about 95%
+    /// confidence for the sample sizes benchmarks use).
+    pub fn compare(&self, baseline: &Stats) -> Change {
+        let error = (self.stddev.powi(2) / self.samples.max(1) as f64
+            + baseline.stddev.powi(2) / baseline.samples.max(1) as f64)
+            .sqrt();
+
+        let difference = self.mean - baseline.mean;
+
+        let significant = if error == 0.0 {
+            difference != 0.0
+        } else {
+            (difference / error).abs() > 2.0
+        };
+
+        match (significant, difference > 0.0) {
+            (false, _) => Change::NoChange,
+            (true, true) => Change::Regressed,
+            (true, false) => Change::Improved,
+        }
+    }
+}
+
+/// How a benchmark changed against its baseline.
+///
+/// # Members
+///
+/// * 'NoChange' - The difference is within the noise.
+/// * 'Improved' - It got significantly faster.
+/// * 'Regressed' - It got significantly slower.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    NoChange,
+    Improved,
+    Regressed,
+}
+
+/// The outcome of a single benchmark.
+///
+/// # Fields
+///
+/// * 'name' - The name of the benchmark, its file or directory name in
+///   benches/.
+/// * 'stats' - The statistics of its run times.
+/// * 'baseline' - The statistics of the baseline it was compared against, if
+///   one was given and has the benchmark.
+///
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub name: String,
+    pub stats: Stats,
+    pub baseline: Option<Stats>,
+}
+
+/// A saved set of benchmark results, build/baselines/<name>.json.
+type Baseline = BTreeMap<String, Stats>;
+
+/// Builds every benchmark in benches/, each linked against the library
+/// sources of the project, then times them one after another so they do not
+/// compete for the machine.
+///
+/// # Arguments
+///
+/// * 'project' - The project to benchmark.
+/// * 'options' - How to benchmark.
+///
+pub fn bench(project: &Project, options: &BenchOptions) -> Result<Vec<BenchResult>, CedarError> {
+    let benches: Vec<&Target> = project
+        .targets
+        .iter()
+        .filter(|target| target.kind == TargetKind::Bench)
+        .filter(|target| {
+            options
+                .filter
+                .as_ref()
+                .is_none_or(|filter| target.name.contains(filter.as_str()))
+        })
+        .collect();
+
+    let baseline = match &options.baseline {
+        Some(name) => Some(load_baseline(project, name)?),
+        None => None,
+    };
+
+    if benches.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let build_options = BuildOptions {
+        quiet: options.quiet,
+        targets: benches
+            .iter()
+            .map(|target| TargetFilter::Bench(target.name.clone()))
+            .collect(),
+        ..Default::default()
+    };
+
+    build(project, &build_options)?;
+
+    let mut results = Vec::new();
+
+    for target in benches {
+        let mut samples = Vec::new();
+
+        // The first run warms up caches and is not timed.
+        for iteration in 0..=options.iterations {
+            let now = Instant::now();
+            run_bench(project, target)?;
+
+            if iteration > 0 {
+                samples.push(now.elapsed().as_secs_f64());
+            }
+        }
+
+        results.push(BenchResult {
+            name: target.name.clone(),
+            stats: Stats::from_samples(&samples),
+            baseline: baseline
+                .as_ref()
+                .and_then(|baseline| baseline.get(&target.name))
+                .copied(),
+        });
+    }
+
+    if let Some(name) = &options.save_baseline {
+        save_baseline(project, name, &results)?;
+    }
+
+    Ok(results)
+}
+
+/// Runs a benchmark once, its output is discarded.
+fn run_bench(project: &Project, target: &Target) -> Result<(), CedarError> {
+    let mut command = process::Command::new(project.output(target));
+    command
+        .current_dir(&project.root)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let output = command
+        .output()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !output.status.success() {
+        return Err(CedarError::BenchFailed {
+            name: target.name.clone(),
+            output: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+
+    Ok(())
+}
+
+/// The file a baseline is saved to.
+fn baseline_path(project: &Project, name: &str) -> PathBuf {
+    project
+        .build_dir
+        .join("baselines")
+        .join(format!("{}.json", name))
+}
+
+/// Loads a saved baseline.
+fn load_baseline(project: &Project, name: &str) -> Result<Baseline, CedarError> {
+    let path = baseline_path(project, name);
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(CliError::InvalidArgument(format!(
+                "{:?}, no baseline with that name was saved, use --save-baseline first.",
+                name
+            ))
+            .into())
+        }
+        Err(err) => return Err(CedarError::io(&path, err)),
+    };
+
+    serde_json::from_str(&contents)
+        .map_err(|err| CedarError::io(&path, io::Error::new(io::ErrorKind::InvalidData, err)))
+}
+
+/// Saves results as a baseline, merged into an existing baseline of the
+/// same name so saving a filtered run keeps the other benchmarks.
+fn save_baseline(project: &Project, name: &str, results: &[BenchResult]) -> Result<(), CedarError> {
+    let path = baseline_path(project, name);
+
+    let mut baseline = match path.exists() {
+        true => load_baseline(project, name)?,
+        false => Baseline::new(),
+    };
+
+    for result in results {
+        baseline.insert(result.name.clone(), result.stats);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+    }
+
+    let contents = serde_json::to_string_pretty(&baseline).map_err(io::Error::from)?;
+    fs::write(&path, contents).map_err(|err| CedarError::io(&path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_and_compares_stats() {
+        let stats = Stats::from_samples(&[3.0, 1.0, 2.0, 6.0]);
+
+        assert_eq!(stats.mean, 3.0);
+        assert_eq!(stats.median, 2.5);
+        assert!((stats.stddev - 2.160).abs() < 0.001);
+
+        let slower = Stats::from_samples(&[10.0, 11.0, 12.0, 10.5]);
+        assert_eq!(slower.compare(&stats), Change::Regressed);
+        assert_eq!(stats.compare(&slower), Change::Improved);
+        assert_eq!(stats.compare(&stats), Change::NoChange);
+    }
+}
@@ -1,12 +1,76 @@
-use std::time::Instant;
-use std::{error::Error, fmt::Display, fs, path::Path, process};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::Mutex,
+};
 
-use crate::structure::manifest::Manifest;
+use crate::{
+    cli::CliError,
+    error::CedarError,
+    structure::{
+        amalgamate::files_with_extensions,
+        buildlog::BuildLog,
+        buildscript,
+        cache::{Cache, CacheStats},
+        check, cmake, conan,
+        deps::{self, Package},
+        diagnostics::{self, Diagnostics, FixIt, OutputFormat, OutputOrder},
+        dist,
+        fingerprint::{self, Dirty},
+        flags, hooks, ignore,
+        intercept::{self, CompileCommand},
+        license,
+        manifest::{
+            Backend, Build, IncludeCycles, Profile, Symlinks, SystemDependency, Visibility,
+        },
+        ninja::{self, Edge},
+        pkgconfig::pkg_config,
+        probe,
+        project::Project,
+        sha256,
+        sysdeps::{self, MissingLibrary, SystemLibrary},
+        target::{Artifact, Target, TargetFilter, TargetKind},
+        test,
+        timings::{self, Unit, UnitKind},
+        trace, vcpkg,
+    },
+    style,
+};
 
 #[derive(Debug)]
 pub enum BuildError {
     InvalidDirectory,
     InvalidCompiler,
+    CompilationFailed(String, String),
+    LinkingFailed(String),
+    NotExecutable,
+    NotSharedLibrary,
+    InvalidAbiVersion(String),
+    UnknownTarget {
+        name: String,
+        kind: &'static str,
+        available: Vec<String>,
+    },
+    AmbiguousExecutable(Vec<String>),
+    MissingSystemDependencies(Vec<MissingLibrary>),
+    UnknownProfile(String, Vec<String>),
+    InvalidArtifactName(String, String),
+    HookFailed(String, String),
+    HooksWithNinja,
+    UnknownTargetTable(String, Vec<String>),
+    UnknownSource(PathBuf),
+    IncludeCycles(Vec<String>),
+    InvalidMatrixTargets(String),
+    BuildScriptFailed(String),
+    InvalidBuildScriptDirective(String),
 }
 
 impl Display for BuildError {
@@ -16,69 +80,1734 @@ impl Display for BuildError {
             BuildError::InvalidCompiler => {
                 writeln!(f, "Error: Compiler given in the manifest is invalid.")
             }
+            BuildError::CompilationFailed(file, output) => {
+                writeln!(f, "Error: Failed to compile {}.", file)?;
+                write!(f, "{}", output)
+            }
+            BuildError::LinkingFailed(output) => {
+                writeln!(f, "Error: Failed to link the project.")?;
+                write!(f, "{}", output)
+            }
+            BuildError::InvalidAbiVersion(version) => {
+                writeln!(
+                    f,
+                    "Error: Invalid abi_version {:?}, expected e.g. 1.2.3.",
+                    version
+                )
+            }
+            BuildError::NotExecutable => {
+                writeln!(f, "Error: Library projects do not produce an executable.")
+            }
+            BuildError::NotSharedLibrary => {
+                writeln!(f, "Error: The project does not produce a shared library.")
+            }
+            BuildError::UnknownTarget {
+                name,
+                kind,
+                available,
+            } => {
+                writeln!(f, "Error: No {} target named {:?}.", kind, name)?;
+                match available.is_empty() {
+                    true => writeln!(f, " The project has no {} targets.", kind),
+                    false => writeln!(f, " Available: {}", available.join(", ")),
+                }
+            }
+            BuildError::AmbiguousExecutable(available) => {
+                writeln!(
+                    f,
+                    "Error: The project has several binaries, pick one with --bin."
+                )?;
+                writeln!(f, " Available: {}", available.join(", "))
+            }
+            BuildError::MissingSystemDependencies(missing) => {
+                writeln!(f, "Error: Missing system dependencies.")?;
+                missing
+                    .iter()
+                    .try_for_each(|library| write!(f, "{}", library))
+            }
+            BuildError::UnknownProfile(name, available) => {
+                writeln!(f, "Error: No profile named {:?}.", name)?;
+                writeln!(f, " Available: {}", available.join(", "))
+            }
+            BuildError::InvalidArtifactName(template, placeholder) => {
+                writeln!(
+                    f,
+                    "Error: Unknown placeholder {{{}}} in artifact_name {:?}.",
+                    placeholder, template
+                )?;
+                writeln!(f, " Available: {{name}}, {{version}}, {{target}}")
+            }
+            BuildError::HookFailed(hook, output) => {
+                writeln!(f, "Error: The {} hook of [hooks.build] failed.", hook)?;
+                write!(f, "{}", output)
+            }
+            BuildError::HooksWithNinja => {
+                writeln!(
+                    f,
+                    "Error: The ninja backend cannot run the hooks of [hooks.build]."
+                )?;
+                writeln!(
+                    f,
+                    " Remove them or build with backend = \"cedar\" in [build]."
+                )
+            }
+            BuildError::UnknownTargetTable(name, available) => {
+                writeln!(
+                    f,
+                    "Error: [targets.{}] names no target of the project.",
+                    name
+                )?;
+                writeln!(f, " Available: {}", available.join(", "))
+            }
+            BuildError::UnknownSource(path) => {
+                writeln!(f, "Error: {:?} is not a source of the project.", path)
+            }
+            BuildError::InvalidMatrixTargets(selection) => {
+                writeln!(f, "Error: Unknown targets {:?} in [matrix].", selection)?;
+                writeln!(
+                    f,
+                    " Available: default, lib, examples, tests, all, bin:<name>, example:<name>"
+                )
+            }
+            BuildError::BuildScriptFailed(output) => {
+                writeln!(f, "Error: The build script build.c failed.")?;
+                write!(f, "{}", output)
+            }
+            BuildError::InvalidBuildScriptDirective(line) => {
+                writeln!(f, "Error: Invalid directive {:?} from build.c.", line)?;
+                writeln!(
+                    f,
+                    " Available: cedar:define=, cedar:source=, cedar:link-lib=, cedar:rerun-if-changed="
+                )
+            }
+            BuildError::IncludeCycles(cycles) => {
+                writeln!(f, "Error: Headers of the project include each other.")?;
+                cycles
+                    .iter()
+                    .try_for_each(|cycle| writeln!(f, " {}", cycle))
+            }
         }
     }
 }
 
 impl Error for BuildError {}
 
-pub fn build<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn Error>> {
+/// The languages of the translation units cedar can compile.
+///
+/// # Members
+///
+/// * 'C' - C sources (.c), compiled with the C driver and cflags.
+/// * 'Cxx' - C++ sources (.cpp, .cc, .cxx), compiled with the C++ driver and
+///   cxxflags. When any are present the C++ driver also links.
+/// * 'Asm' - Plain assembly (.s), assembled through the C driver with asflags.
+/// * 'AsmCpp' - Assembly that is run through the preprocessor first (.S), so
+///   it can use #include and #define like C sources.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    C,
+    Cxx,
+    Asm,
+    AsmCpp,
+}
+
+impl Language {
+    /// Gets the language of a source file from its extension, None if the
+    /// file is not a translation unit (e.g. a header).
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "c" => Some(Self::C),
+            "cpp" | "cc" | "cxx" | "c++" | "C" => Some(Self::Cxx),
+            "s" => Some(Self::Asm),
+            "S" | "sx" => Some(Self::AsmCpp),
+            _ => None,
+        }
+    }
+}
+
+/// The programs used to build a project with a given compiler.
+///
+/// # Fields
+///
+/// * 'cc' - The C driver, also used for assembly and linking C only projects.
+/// * 'cxx' - The C++ driver.
+/// * 'ar' - The archiver, the gcc-ar wrapper or llvm-ar is used so archives
+///   of LTO objects get a usable symbol index.
+/// * 'gcov' - The coverage tool matching the compiler, llvm-cov for clang.
+/// * 'nm' - The symbol lister, the gcc-nm wrapper or llvm-nm is used so the
+///   symbols of LTO objects are listed.
+///
+#[derive(Debug, Clone)]
+pub struct Toolchain {
+    pub cc: String,
+    pub cxx: String,
+    pub ar: String,
+    pub gcov: String,
+    pub nm: String,
+}
+
+impl Toolchain {
+    /// The toolchain of a compiler: gcc, clang, or a gcc cross compiler
+    /// named by its prefix, e.g. arm-none-eabi-gcc.
+    pub fn from_compiler(compiler: &str) -> Result<Self, BuildError> {
+        match compiler {
+            "GCC" | "gcc" => Ok(Self::gcc("")),
+            "CLANG" | "clang" | "Clang" => Ok(Self::clang()),
+            _ => match compiler.strip_suffix("gcc") {
+                Some(prefix) if prefix.ends_with('-') && prefix.len() > 1 => Ok(Self::gcc(prefix)),
+                _ => Err(BuildError::InvalidCompiler),
+            },
+        }
+    }
+    /// The gcc toolchain whose programs start with the prefix.
+    fn gcc(prefix: &str) -> Self {
+        Self {
+            cc: format!("{}gcc", prefix),
+            cxx: format!("{}g++", prefix),
+            ar: format!("{}gcc-ar", prefix),
+            gcov: format!("{}gcov", prefix),
+            nm: format!("{}gcc-nm", prefix),
+        }
+    }
+    /// The clang toolchain, with the LLVM binutils.
+    fn clang() -> Self {
+        Self {
+            cc: String::from("clang"),
+            cxx: String::from("clang++"),
+            ar: String::from("llvm-ar"),
+            gcov: String::from("llvm-cov"),
+            nm: String::from("llvm-nm"),
+        }
+    }
+    /// The command running gcov, llvm-cov runs it as its gcov subcommand.
+    pub fn gcov_command(&self) -> process::Command {
+        let mut command = process::Command::new(&self.gcov);
+        if self.gcov.ends_with("llvm-cov") {
+            command.arg("gcov");
+        }
+        command
+    }
+}
+
+/// Finds the C compiler new projects would use, $CC if set or else the first
+/// of gcc and clang that runs.
+///
+/// # Returns
+///
+/// * The first line of the compiler's --version output, None if no compiler
+///   was found.
+///
+pub fn detect_compiler() -> Option<String> {
+    host_compiler().and_then(|compiler| compiler_version(&compiler))
+}
+
+/// The program of the C compiler detect_compiler finds, None if no compiler
+/// runs.
+pub fn host_compiler() -> Option<String> {
+    std::env::var("CC")
+        .ok()
+        .into_iter()
+        .chain(["gcc", "clang"].map(String::from))
+        .find(|compiler| compiler_version(compiler).is_some())
+}
+
+/// The first line of the --version output of a compiler program, None when
+/// it does not run.
+pub fn compiler_version(program: &str) -> Option<String> {
+    probe::version(program)?.lines().next().map(str::to_owned)
+}
+
+/// The target the C compiler of the manifest builds for as it reports with
+/// -dumpmachine, e.g. x86_64-linux-gnu, probed once per compiler. It names
+/// the directory the artifacts of a build go to, "native" when the compiler
+/// does not say.
+pub fn target_triple(compiler: &str) -> &'static str {
+    // Every compiler a process builds with is looked up over and over, its
+    // triple is kept for the rest of the process.
+    static TRIPLES: Mutex<BTreeMap<String, &'static str>> = Mutex::new(BTreeMap::new());
+
+    let mut triples = TRIPLES.lock().unwrap_or_else(|err| err.into_inner());
+
+    triples.entry(compiler.to_owned()).or_insert_with(|| {
+        let triple = Toolchain::from_compiler(compiler)
+            .ok()
+            .and_then(|toolchain| {
+                probe::probe(&toolchain.cc, "dumpmachine", |cc| {
+                    process::Command::new(cc)
+                        .arg("-dumpmachine")
+                        .output()
+                        .ok()
+                        .filter(|output| output.status.success())
+                        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+                })
+            });
+
+        match triple.filter(|triple| !triple.is_empty() && !triple.contains(['/', '\\'])) {
+            Some(triple) => triple.leak(),
+            None => "native",
+        }
+    })
+}
+
+/// Finds every translation unit under a directory along with its language,
+/// skipping what .gitignore and .cedarignore files ignore and handling
+/// symlinks as the manifest asks.
+pub fn find_sources<P: AsRef<Path>>(
+    path: P,
+    symlinks: Symlinks,
+) -> Result<Vec<(PathBuf, Language)>, std::io::Error> {
+    let mut sources: Vec<(PathBuf, Language)> = ignore::files(path.as_ref(), symlinks)?
+        .into_iter()
+        .filter_map(|file| Language::from_path(&file).map(|lang| (file, lang)))
+        .collect();
+
+    sources.sort();
+
+    Ok(sources)
+}
+
+/// The object a source (relative to the project root) compiles to and its
+/// depfile. The source extension is kept in the name so main.c and main.cpp
+/// never collide.
+pub fn object_files(object_path: &Path, relative: &Path) -> (PathBuf, PathBuf) {
+    let relative = relative.to_str().unwrap();
+
+    (
+        object_path.join(format!("{}.o", relative)),
+        object_path.join(format!("{}.d", relative)),
+    )
+}
+
+/// The flags of a shared library link exporting only what the exports file
+/// of [build] names. A list of symbols is first written out in the form the
+/// linker takes, next to the objects.
+///
+/// # Returns
+///
+/// * The flags, and the exports file the link depends on.
+///
+fn export_flags(
+    root: &Path,
+    exports: &str,
+    object_path: &Path,
+) -> Result<(Vec<String>, PathBuf), CedarError> {
+    let path = root.join(exports);
+    let text = fs::read_to_string(&path).map_err(|err| CedarError::io(&path, err))?;
+
+    let (contents, flag, name) = match cfg!(target_os = "macos") {
+        true => (
+            flags::exported_symbols_list(&text),
+            "-Wl,-exported_symbols_list,",
+            "exports.txt",
+        ),
+        false if flags::is_version_script(&text) => {
+            return Ok((
+                vec![format!("-Wl,--version-script={}", path.display())],
+                path,
+            ))
+        }
+        false => (
+            flags::version_script(&text),
+            "-Wl,--version-script=",
+            "exports.map",
+        ),
+    };
+
+    let written = object_path.join(name);
+    fs::create_dir_all(object_path).map_err(|err| CedarError::io(object_path, err))?;
+    fs::write(&written, contents).map_err(|err| CedarError::io(&written, err))?;
+
+    Ok((vec![format!("{}{}", flag, written.display())], path))
+}
+
+/// Options controlling a build.
+///
+/// # Fields
+///
+/// * 'quiet' - Suppresses progress output and captures the output of the
+///   compiler into the report instead of printing it, for tools that
+///   drive builds programmatically.
+/// * 'targets' - The targets to build, every target except the examples and
+///   tests when empty.
+/// * 'explain' - Prints why every recompiled source and relinked artifact
+///   was out of date.
+/// * 'max_errors' - How many errors of the compiler are shown at most, the
+///   rest are only counted.
+/// * 'output_order' - The order the output of parallel compiles is printed
+///   in.
+/// * 'output_format' - How the diagnostics of the compiler are printed.
+/// * 'compile_commands' - Also writes every compile of the build, up to date
+///   or not, to compile_commands.json in the out directory.
+/// * 'emit' - Stops the build short of the artifacts, at what it emits
+///   instead.
+/// * 'file' - The only source to emit assembly or preprocessed output of,
+///   every source when None.
+/// * 'save_temps' - Keeps the intermediate files of every compile under
+///   build/temps/<source>/, with the object cache out of the way.
+/// * 'print_gc_sections' - Has the linker print the sections it drops, with
+///   gc_sections in the profile. Executables and shared libraries link
+///   again even when up to date.
+///
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    pub quiet: bool,
+    pub targets: Vec<TargetFilter>,
+    pub explain: bool,
+    pub max_errors: Option<usize>,
+    pub output_order: OutputOrder,
+    pub output_format: OutputFormat,
+    pub compile_commands: bool,
+    pub emit: Option<Emit>,
+    pub file: Option<PathBuf>,
+    pub save_temps: bool,
+    pub print_gc_sections: bool,
+}
+
+/// What a build stops at instead of linking the artifacts, set with --emit.
+///
+/// # Members
+///
+/// * 'Obj' - The object files of the targets, for a link outside of cedar.
+/// * 'Asm' - The assembly of the sources, .s files next to their objects.
+/// * 'Preprocessed' - The preprocessed sources, .i files next to their
+///   objects.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    Obj,
+    Asm,
+    Preprocessed,
+}
+
+impl Emit {
+    /// The flag stopping the compiler at what is emitted, and the extension
+    /// of the file every source compiles to.
+    fn step(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Obj => ("-c", "o"),
+            Self::Asm => ("-S", "s"),
+            Self::Preprocessed => ("-E", "i"),
+        }
+    }
+}
+
+impl FromStr for Emit {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "obj" => Ok(Self::Obj),
+            "asm" => Ok(Self::Asm),
+            "preprocessed" => Ok(Self::Preprocessed),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, cedar build emits obj, asm, or preprocessed.",
+                s
+            ))),
+        }
+    }
+}
+
+impl BuildOptions {
+    /// Whether the build includes the target.
+    pub fn selects(&self, target: &Target) -> bool {
+        if self.targets.is_empty() {
+            target.kind.is_default()
+        } else {
+            self.targets.iter().any(|filter| filter.matches(target))
+        }
+    }
+}
+
+/// The result of a successful build.
+///
+/// # Fields
+///
+/// * 'compiled' - The sources that were (re)compiled.
+/// * 'linked' - The artifacts that were (re)linked.
+/// * 'artifacts' - Every artifact of the project, whether it was up to date
+///   or not, or the files --emit asked for instead.
+/// * 'diagnostics' - Compiler output per source, only captured when quiet.
+/// * 'elapsed' - How long the build took.
+/// * 'cache' - How the object cache did, when it is enabled.
+/// * 'reasons' - Why each recompiled source and relinked artifact was out
+///   of date, empty with ninja.
+/// * 'timings' - When each compile and link ran and for how long, empty
+///   with ninja.
+/// * 'fixits' - The fix-it hints of the compiler, when it writes JSON
+///   diagnostics.
+///
+#[derive(Debug, Clone, Default)]
+pub struct BuildReport {
+    pub compiled: Vec<PathBuf>,
+    pub linked: Vec<PathBuf>,
+    pub artifacts: Vec<PathBuf>,
+    pub diagnostics: Vec<(PathBuf, String)>,
+    pub elapsed: Duration,
+    pub cache: Option<CacheStats>,
+    pub reasons: Vec<(PathBuf, Dirty)>,
+    pub timings: Vec<Unit>,
+    pub fixits: Vec<FixIt>,
+}
+
+/// Compiles every out of date translation unit of the project and relinks
+/// the artifacts that depend on them.
+///
+/// # Arguments
+///
+/// * 'project' - The project to build.
+/// * 'options' - How to build.
+///
+pub fn build(project: &Project, options: &BuildOptions) -> Result<BuildReport, CedarError> {
     let now = Instant::now();
+    let mut report = BuildReport::default();
+
+    let (root, manifest) = (&project.root, &project.manifest);
+    let build_path = &project.build_dir;
 
-    let path = path.as_ref();
-    let manifest_path = path.join("cedar.toml");
-    let src_path = path.join("src/");
-    let include_path = path.join("include/");
-    let build_path = path.join("build/");
+    // Objects, dependencies and artifacts go to build/<target>/<profile>/,
+    // the rest of build/ is shared by every build.
+    let out_path = &project.out_dir();
+    fs::create_dir_all(out_path).map_err(|err| CedarError::io(out_path, err))?;
 
-    for path in [&manifest_path, &src_path, &include_path, &build_path] {
+    // Header-only projects need no src/.
+    let src_dir = Some(&project.src_dir).filter(|_| !manifest.meta.kind.is_header_only());
+
+    for path in src_dir
+        .into_iter()
+        .chain([&project.include_dir, build_path])
+    {
         if !path.exists() {
-            return Err(Box::new(BuildError::InvalidDirectory));
+            return Err(BuildError::InvalidDirectory.into());
         }
     }
 
-    let manifest_str = fs::read_to_string(&manifest_path)?;
-    let manifest = Manifest::parse(&manifest_str)?;
-
-    println!(
-        "\n\t\x1b[1;32mCompiling \x1b[0m{} v{} ({:?})\n",
-        manifest.meta.name, manifest.meta.version, &path
+    let _build = trace::span("build", &manifest.meta.name);
+    let mut log = BuildLog::create(
+        build_path,
+        &format!(
+            "build of {} v{} ({})",
+            manifest.meta.name,
+            manifest.meta.version,
+            root.display()
+        ),
     );
 
-    let mut compiler_args: Vec<String> = Vec::new();
+    // A cycle compiles or not depending on the order of includes, so it is
+    // looked for before anything compiles.
+    if !manifest.build.include_cycles.is_ignore() {
+        let cycles: Vec<String> = check::include_cycles(project)?
+            .iter()
+            .map(|cycle| check::cycle_path(cycle, root))
+            .collect();
 
-    let mut src_files = recursive_file_search(src_path)?;
-    let include_files = recursive_file_search(include_path)?;
+        match manifest.build.include_cycles {
+            IncludeCycles::Error if !cycles.is_empty() => {
+                return Err(BuildError::IncludeCycles(cycles).into());
+            }
+            _ if !options.quiet => {
+                for cycle in &cycles {
+                    println!("\t{}include cycle {}", style::bold("Warning "), cycle);
+                }
+            }
+            _ => {}
+        }
+    }
 
-    src_files.extend_from_slice(&include_files);
+    let toolchain = Toolchain::from_compiler(&manifest.build.compiler)?;
+    let kind = manifest.meta.kind;
 
-    for file in src_files {
-        compiler_args.push(file);
+    // The flags of the profile apply to the dependencies as well.
+    let profile = manifest.profile(&project.profile).ok_or_else(|| {
+        BuildError::UnknownProfile(project.profile.clone(), manifest.profile_names())
+    })?;
+
+    // With the ninja backend every compile and link becomes an edge of
+    // build.ninja and ninja decides what is out of date.
+    let use_ninja = manifest.build.backend == Backend::Ninja;
+    if use_ninja && !manifest.hooks.build.is_empty() {
+        return Err(BuildError::HooksWithNinja.into());
     }
+    let mut edges = Vec::new();
 
-    let output_path = build_path.join(manifest.meta.name);
-    let output_str = output_path.to_str().unwrap();
+    for filter in &options.targets {
+        filter.validate(&project.targets)?;
+    }
 
-    compiler_args.extend_from_slice(&manifest.build.cflags);
+    // Examples and tests are only built when asked for.
+    let targets: Vec<&Target> = project
+        .targets
+        .iter()
+        .filter(|target| options.selects(target))
+        .collect();
 
-    process::Command::new(match manifest.build.compiler.as_str() {
-        "GCC" | "gcc" => "gcc",
-        "CLANG" | "clang" | "Clang" => todo!(),
-        _ => return Err(Box::new(BuildError::InvalidDirectory)),
-    })
-    .args(compiler_args)
-    .args(["-o", output_str])
-    .spawn()
-    .expect("Error: Failed to start compiler.")
-    .wait()?;
+    let object_path = out_path.join("obj");
+
+    // Dependencies compile with their own flags into deps/obj/<name>/,
+    // and every source can include their headers.
+    let resolve = trace::span("resolve", &manifest.meta.name);
+    let packages = deps::resolve(project, options.quiet)?;
+    drop(resolve);
+
+    let plan = trace::span("plan", &manifest.meta.name);
+
+    // The project and its dependencies, along with where their objects go
+    // under an out directory.
+    let units_in = |out_path: &Path| -> Vec<(&Project, PathBuf)> {
+        let dependency_object_path = out_path.join("deps").join("obj");
+
+        std::iter::once((project, out_path.join("obj")))
+            .chain(
+                packages
+                    .iter()
+                    .map(|package| (&package.project, dependency_object_path.join(&package.name))),
+            )
+            .collect()
+    };
+    let units = units_in(out_path);
+
+    for name in manifest.targets.keys() {
+        if !project.targets.iter().any(|target| &target.name == name) {
+            return Err(BuildError::UnknownTargetTable(
+                name.clone(),
+                project
+                    .targets
+                    .iter()
+                    .map(|target| target.name.clone())
+                    .collect(),
+            )
+            .into());
+        }
+    }
+
+    // Targets with a compiler or flags of their own in [targets] compile in
+    // a group of their own, along with the library sources they link, so
+    // every object is compiled by the toolchain that links it.
+    let mut groups: Vec<Group> = Vec::new();
+    let mut target_builds = Vec::new();
+    let mut target_groups = Vec::new();
+
+    for target in &targets {
+        let settings = manifest.target_build(&target.name);
+
+        let group = match groups
+            .iter()
+            .position(|group| compiles_alike(&group.build, &settings))
+        {
+            Some(group) => group,
+            None => {
+                groups.push(Group::new(settings.clone(), out_path, &manifest.build)?);
+                groups.len() - 1
+            }
+        };
+
+        target_builds.push(settings);
+        target_groups.push(group);
+    }
+
+    // Without targets the library sources still compile as [build] says.
+    if groups.is_empty() {
+        groups.push(Group::new(
+            manifest.build.clone(),
+            out_path,
+            &manifest.build,
+        )?);
+    }
+
+    // System libraries are checked up front, a missing one would otherwise
+    // only show up as a failed compile or link much later.
+    let system_deps = units
+        .iter()
+        .flat_map(|(unit, _)| {
+            unit.manifest
+                .system_deps
+                .iter()
+                .filter(|(name, dependency)| unit.uses(name, dependency.is_optional()))
+        })
+        .map(|(name, dependency)| (name.clone(), dependency.clone()))
+        .collect();
+
+    // Conan packages and vcpkg ports are prebuilt libraries too, just
+    // installed by a package manager.
+    let mut installed = Vec::new();
+    let conan_requires = units
+        .iter()
+        .flat_map(|(unit, _)| &unit.manifest.dependencies.conan)
+        .map(|(name, version)| (name.clone(), version.clone()))
+        .collect();
+    installed.extend(conan::install(&conan_requires, build_path, options.quiet)?);
+
+    let mut vcpkg_ports: Vec<String> = units
+        .iter()
+        .flat_map(|(unit, _)| unit.manifest.dependencies.vcpkg.iter().cloned())
+        .collect();
+    vcpkg_ports.sort();
+    vcpkg_ports.dedup();
+    installed.extend(vcpkg::install(&vcpkg_ports, build_path, options.quiet)?);
+
+    // Every compiler of the groups probes the system libraries and builds
+    // the CMake dependencies on its own, what the host compiler finds a cross
+    // compiler may not link. The default compiler does so in build/, the
+    // others in the out directory of their group. Dependencies built with
+    // CMake are installed before anything includes their headers.
+    let mut probed: Vec<(&str, Vec<SystemLibrary>)> = Vec::new();
+
+    for group in &groups {
+        let cc = group.toolchain.cc.as_str();
+
+        if probed.iter().any(|(probed, _)| *probed == cc) {
+            continue;
+        }
+
+        let path = match cc == toolchain.cc {
+            true => build_path,
+            false => &group.out_path,
+        };
+        let mut libraries = sysdeps::probe(&system_deps, path, cc)?;
+        libraries.extend(installed.iter().cloned());
+
+        for package in packages.iter().filter(|package| package.cmake.is_some()) {
+            libraries.push(cmake::build(package, path, cc, options.quiet)?);
+        }
+
+        probed.push((cc, libraries));
+    }
+
+    // The system libraries of every group.
+    let group_libraries: Vec<&Vec<SystemLibrary>> = groups
+        .iter()
+        .map(|group| {
+            probed
+                .iter()
+                .find(|(cc, _)| *cc == group.toolchain.cc)
+                .map(|(_, libraries)| libraries)
+                .expect("every compiler is probed")
+        })
+        .collect();
+
+    let dependency_includes = |libraries: &[SystemLibrary]| -> Vec<String> {
+        packages
+            .iter()
+            .map(|package| format!("-I{}", package.project.include_dir.to_str().unwrap()))
+            .chain(
+                libraries
+                    .iter()
+                    .flat_map(|library| library.cflags.iter().cloned()),
+            )
+            .collect()
+    };
+
+    // The build script runs before anything compiles, what it adds to the
+    // build only applies to the project itself.
+    let script = buildscript::run(project, &toolchain.cc, options.quiet)?.unwrap_or_default();
 
-    let elapsed = now.elapsed();
-    println!("\t\x1b[1;32mFinished\x1b[0m in {:.2?}\n", elapsed);
+    // The library sources of the project, along with the license notices of
+    // the dependencies and the sources of the build script.
+    let mut library_sources = project.library_sources.clone();
+    library_sources.extend(script.sources.iter().cloned());
+
+    // The headers compiled alone, which nothing links.
+    let header_checks = match manifest.build.check_headers {
+        true => header_checks(project, out_path)?,
+        false => Vec::new(),
+    };
+
+    if manifest.licenses.embed_notices {
+        library_sources.push(embed_notices(build_path, &packages)?);
+    }
+
+    let harness_arg = format!("-I{}", build_path.join("include").to_str().unwrap());
+
+    // What every artifact links of the system libraries, all of a library
+    // when its dependency asks for the whole archive.
+    let whole_archive = |name: &str| {
+        units.iter().any(|(unit, _)| {
+            unit.manifest
+                .system_deps
+                .get(name)
+                .is_some_and(SystemDependency::whole_archive)
+                || unit
+                    .manifest
+                    .dependencies
+                    .packages
+                    .get(name)
+                    .is_some_and(|dependency| dependency.whole_archive)
+        })
+    };
+    let system_libs = |libraries: &[SystemLibrary]| -> Vec<String> {
+        libraries
+            .iter()
+            .flat_map(|library| match whole_archive(&library.name) {
+                true => flags::whole_archives(&library.libs.iter().collect::<Vec<_>>()),
+                false => library.libs.clone(),
+            })
+            .collect()
+    };
+
+    let mut jobs = Vec::new();
+    let mut compiled_objects = Vec::new();
+    let mut database = Vec::new();
+
+    // The sources only each target uses, tests also get the bundled harness
+    // (and the include path of its header).
+    let mut target_sources = Vec::new();
+
+    for target in &targets {
+        let mut sources = target.sources.clone();
+
+        if target.kind == TargetKind::Test {
+            sources.extend(test::harness(project, target)?);
+        }
+
+        target_sources.push(sources);
+    }
+
+    // What the sources compile to, and which of them do with --emit asm
+    // or preprocessed.
+    let (step, extension) = options.emit.map_or(("-c", "o"), Emit::step);
+    let wanted = |source: &Path| match &options.file {
+        Some(file) => source.canonicalize().is_ok_and(|source| source == *file),
+        None => true,
+    };
+    let mut emitted: Vec<PathBuf> = Vec::new();
+
+    for (index, group) in groups.iter().enumerate() {
+        // gcc can write its diagnostics as JSON, which are rendered with the
+        // option of every warning and collect the fix-it hints. ninja prints
+        // the output of the compiler as it is.
+        let json = !use_ninja && diagnostics::json_supported(&group.toolchain.cc);
+        let dependency_includes = dependency_includes(group_libraries[index]);
+
+        let units = units_in(&group.out_path);
+        let (main_unit, dependency_units) = units.split_first().expect("the project is a unit");
+
+        let sources = library_sources
+            .iter()
+            .map(|source| (source, main_unit, &group.build, kind.is_shared(), false))
+            .chain(
+                header_checks
+                    .iter()
+                    .filter(|_| index == 0)
+                    .map(|source| (source, main_unit, &group.build, false, false)),
+            )
+            .chain(
+                targets
+                    .iter()
+                    .zip(&target_sources)
+                    .zip(&target_groups)
+                    .filter(|(_, group)| **group == index)
+                    .flat_map(|((target, sources), _)| {
+                        let harness = target.kind == TargetKind::Test;
+                        sources
+                            .iter()
+                            .map(move |source| (source, main_unit, &group.build, false, harness))
+                    }),
+            )
+            .chain(dependency_units.iter().flat_map(|unit| {
+                unit.0.library_sources.iter().map(move |source| {
+                    (
+                        source,
+                        unit,
+                        &unit.0.manifest.build,
+                        kind.is_shared(),
+                        false,
+                    )
+                })
+            }));
+
+        // Plan a compile for every translation unit whose object is out of
+        // date, only the library sources of shared library projects need
+        // -fPIC. Dependencies keep their own flags, only the compiler is the
+        // one of the group.
+        for ((source, lang), (unit, unit_object_path), build, pic, harness) in sources {
+            if extension != "o" && !wanted(source) {
+                continue;
+            }
+
+            let relative = source.strip_prefix(&unit.root).unwrap_or(source);
+            let (mut object, mut depfile) = object_files(unit_object_path, relative);
+            let mut fingerprint =
+                unit_object_path.join(format!("{}.cmd", relative.to_str().unwrap()));
+
+            // Assembly and preprocessed output goes next to the object, e.g.
+            // obj/src/main.c.s, and is emitted whether it changed or not.
+            if extension != "o" {
+                depfile = object.with_extension(format!("{}.d", extension));
+                fingerprint = fingerprint.with_extension(format!("{}.cmd", extension));
+                object.set_extension(extension);
+
+                if !emitted.contains(&object) {
+                    emitted.push(object.clone());
+                }
+            }
+
+            let toolchain = &group.toolchain;
+            let (driver, flags, profile_flags) = match lang {
+                Language::C => (&toolchain.cc, &build.cflags, &profile.cflags),
+                Language::Cxx => (&toolchain.cxx, &build.cxxflags, &profile.cxxflags),
+                // The driver runs the preprocessor for .S files by itself.
+                Language::Asm | Language::AsmCpp => {
+                    (&toolchain.cc, &build.asflags, &profile.asflags)
+                }
+            };
+
+            let system: Vec<String> =
+                std::iter::once(format!("-I{}", unit.include_dir.to_str().unwrap()))
+                    .chain(unit.feature_defines())
+                    .chain(
+                        (unit.root == *root)
+                            .then_some(&script.defines)
+                            .into_iter()
+                            .flatten()
+                            .cloned(),
+                    )
+                    .chain(dependency_includes.iter().cloned())
+                    .chain(harness.then(|| harness_arg.clone()))
+                    .collect();
+            let file_flags = build
+                .files
+                .iter()
+                .find(|(path, _)| Path::new(path) == relative)
+                .map_or(&[][..], |(_, flags)| flags);
+
+            // The temporaries of dependencies go under their own name, as
+            // their sources may have the same paths as the project's.
+            let temps = match unit.root == *root {
+                true => build_path.join("temps").join(relative),
+                false => build_path
+                    .join("temps")
+                    .join("deps")
+                    .join(&unit.manifest.meta.name)
+                    .join(relative),
+            };
+
+            if options.save_temps {
+                fs::create_dir_all(&temps).map_err(|err| CedarError::io(&temps, err))?;
+            }
+
+            let mut command = process::Command::new(driver);
+            command
+                .arg(step)
+                .arg(source)
+                .arg("-o")
+                .arg(&object)
+                .args(flags::order(&system, profile_flags, file_flags, flags))
+                .args(pic.then_some("-fPIC"))
+                .args(
+                    (pic && build.visibility == Visibility::Hidden)
+                        .then_some("-fvisibility=hidden"),
+                )
+                .args(
+                    profile
+                        .gc_sections
+                        .then_some(["-ffunction-sections", "-fdata-sections"])
+                        .into_iter()
+                        .flatten(),
+                )
+                .arg("-MMD")
+                .arg("-MF")
+                .arg(&depfile)
+                .args(json.then_some("-fdiagnostics-format=json"));
+
+            if options.save_temps {
+                command
+                    .arg("-save-temps=obj")
+                    .arg("-dumpdir")
+                    .arg(format!("{}/", temps.display()));
+            }
+
+            if options.compile_commands && extension == "o" {
+                database.push(CompileCommand {
+                    directory: root.clone(),
+                    file: source.clone(),
+                    arguments: std::iter::once(command.get_program())
+                        .chain(command.get_args())
+                        .filter(|arg| *arg != "-fdiagnostics-format=json")
+                        .map(|arg| arg.to_string_lossy().into_owned())
+                        .collect(),
+                    output: Some(object.clone()),
+                });
+            }
+
+            if use_ninja {
+                edges.push(Edge {
+                    output: object,
+                    inputs: vec![source.clone()],
+                    depfile: Some(depfile),
+                    command: ninja::shell(&command),
+                });
+                continue;
+            }
+
+            let inputs = fingerprint::dependencies(source, &depfile);
+
+            if let Some(reason) = fingerprint::dirty(&object, &inputs, &command, &fingerprint) {
+                report.reasons.push((source.clone(), reason));
+                jobs.push((source.clone(), object.clone(), command, fingerprint));
+                compiled_objects.push(object);
+            }
+        }
+    }
+
+    if options.compile_commands {
+        intercept::write_database(out_path, &database)?;
+    }
+
+    if let Some(file) = options.file.as_ref().filter(|_| emitted.is_empty()) {
+        return Err(BuildError::UnknownSource(file.clone()).into());
+    }
+
+    // The library objects every target of a group links.
+    let library_objects: Vec<Vec<PathBuf>> = groups
+        .iter()
+        .map(|group| {
+            units_in(&group.out_path)
+                .iter()
+                .enumerate()
+                .flat_map(|(index, (unit, unit_object_path))| match index {
+                    0 => objects(root, unit_object_path, &library_sources),
+                    _ => objects(&unit.root, unit_object_path, &unit.library_sources),
+                })
+                .collect()
+        })
+        .collect();
+
+    // What a shared library exports, when the manifest narrows it down.
+    let (export_flags, export_inputs) = match &manifest.build.exports {
+        Some(exports) if kind.is_shared() => {
+            let (flags, path) = export_flags(root, exports, &object_path)?;
+            (flags, vec![path])
+        }
+        _ => (Vec::new(), Vec::new()),
+    };
+
+    let mut links = Vec::new();
+    let mut all_artifacts = Vec::new();
+
+    for (((target, sources), build), group) in targets
+        .iter()
+        .zip(&target_sources)
+        .zip(&target_builds)
+        .zip(&target_groups)
+    {
+        let toolchain = &groups[*group].toolchain;
+        let system_libs = system_libs(group_libraries[*group]);
+
+        let mut target_objects = library_objects[*group].clone();
+        target_objects.extend(objects(root, &groups[*group].out_path.join("obj"), sources));
+
+        // Only the objects are wanted, nothing links.
+        match options.emit {
+            Some(Emit::Obj) => {
+                for object in target_objects {
+                    if !emitted.contains(&object) {
+                        emitted.push(object);
+                    }
+                }
+                continue;
+            }
+            Some(_) => continue,
+            None => {}
+        }
+
+        // Dependencies may need libraries of their own.
+        let ldflags: Vec<&String> = build
+            .ldflags
+            .iter()
+            .chain(
+                units
+                    .iter()
+                    .skip(1)
+                    .flat_map(|(unit, _)| &unit.manifest.build.ldflags),
+            )
+            .chain(&profile.ldflags)
+            .chain(&system_libs)
+            .chain(&script.libs)
+            .collect();
+
+        // Archives referring to each other are searched until nothing new
+        // resolves, Apple's linker always does so by itself.
+        let mut ldflags = match build.link_group && !cfg!(target_os = "macos") {
+            true => flags::group_archives(&ldflags),
+            false => ldflags.into_iter().cloned().collect(),
+        };
+
+        // Apple's linker has no list of what it strips.
+        if profile.gc_sections {
+            ldflags.extend(flags::gc_sections(options.print_gc_sections));
+        }
+
+        // C++ objects need the C++ runtime, which only the C++ driver links.
+        let linker = if units
+            .iter()
+            .flat_map(|(unit, _)| &unit.library_sources)
+            .chain(sources.iter())
+            .any(|(_, lang)| *lang == Language::Cxx)
+        {
+            &toolchain.cxx
+        } else {
+            &toolchain.cc
+        };
+
+        let output_dir = out_path.join(target.output_dir());
+
+        // Every artifact of a target is produced from the same objects, so a
+        // library project gets both its archive and shared object out of a
+        // single compile.
+        for artifact in target.artifacts(manifest)? {
+            let output = output_dir.join(artifact.name());
+
+            let command = match &artifact {
+                Artifact::Executable(_) => {
+                    let mut link = process::Command::new(linker);
+                    link.args(&target_objects)
+                        .arg("-o")
+                        .arg(&output)
+                        .args(&ldflags);
+                    link
+                }
+                Artifact::StaticLib(_) => {
+                    let mut archive = process::Command::new(&toolchain.ar);
+                    archive.arg("rcs").arg(&output).args(&target_objects);
+                    archive
+                }
+                Artifact::SharedLib { soname, .. } => {
+                    let mut link = process::Command::new(linker);
+                    link.arg("-shared")
+                        .args(
+                            soname
+                                .iter()
+                                .map(|soname| format!("-Wl,-soname,{}", soname)),
+                        )
+                        .args(&target_objects)
+                        .arg("-o")
+                        .arg(&output)
+                        .args(&export_flags)
+                        .args(&ldflags);
+                    link
+                }
+            };
+
+            let mut inputs = target_objects.clone();
+            if let Artifact::SharedLib { .. } = artifact {
+                inputs.extend(export_inputs.iter().cloned());
+            }
+
+            // The linker only prints the sections it drops when it runs, so
+            // asking for them links again even when up to date.
+            let forced = options.print_gc_sections
+                && profile.gc_sections
+                && !cfg!(target_os = "macos")
+                && !matches!(artifact, Artifact::StaticLib(_));
+
+            all_artifacts.push((output_dir.clone(), artifact));
+
+            if use_ninja {
+                // Ninja links again what is missing.
+                if forced {
+                    let _ = fs::remove_file(&output);
+                }
+
+                // ar only replaces members, see below.
+                let command = match command.get_program() == toolchain.ar.as_str() {
+                    true => ninja::replacing(&output, &command),
+                    false => ninja::shell(&command),
+                };
+
+                edges.push(Edge {
+                    output,
+                    inputs,
+                    depfile: None,
+                    command,
+                });
+                continue;
+            }
+
+            let relative = output.strip_prefix(out_path).unwrap_or(&output);
+            let fingerprint = object_path.join(format!("{}.link.cmd", relative.to_str().unwrap()));
+
+            let stale = match target_objects
+                .iter()
+                .find(|object| compiled_objects.contains(object))
+            {
+                Some(object) => Some(Dirty::ChangedInput(object.clone())),
+                None => fingerprint::dirty(&output, &inputs, &command, &fingerprint)
+                    .or(forced.then_some(Dirty::Forced)),
+            };
+
+            if let Some(reason) = stale {
+                report.reasons.push((output.clone(), reason));
+                links.push((output, command, fingerprint, inputs));
+            }
+        }
+    }
+
+    let relink = !links.is_empty();
+    drop(plan);
+
+    if use_ninja {
+        if !options.quiet {
+            println!(
+                "\n\t{}{} v{} ({:?}) with ninja\n",
+                style::header("Building "),
+                manifest.meta.name,
+                manifest.meta.version,
+                root
+            );
+        }
+
+        // Without links the objects are what ninja builds.
+        let outputs: Vec<PathBuf> = edges
+            .iter()
+            .filter(|edge| edge.depfile.is_none() || options.emit.is_some())
+            .map(|edge| edge.output.clone())
+            .collect();
+        log.line(&format!("ninja with {} edge(s)", edges.len()));
+
+        let _ninja = trace::span("ninja", out_path.display());
+        let output = ninja::run(out_path, &edges, &outputs, options.quiet, options.explain)
+            .inspect_err(|err| log.line(&format!("failed: {}", err).replace('\n', " ")))?;
+
+        for line in output.lines() {
+            log.line(&format!("    {}", line));
+        }
+
+        if !output.is_empty() {
+            report
+                .diagnostics
+                .push((out_path.join("build.ninja"), output));
+        }
+    } else if !options.quiet {
+        if !jobs.is_empty() {
+            println!(
+                "\n\t{}{} v{} ({:?})\n",
+                style::header("Compiling "),
+                manifest.meta.name,
+                manifest.meta.version,
+                root
+            );
+        } else if relink {
+            println!(
+                "\n\t{}{} v{} ({:?})\n",
+                style::header("Linking "),
+                manifest.meta.name,
+                manifest.meta.version,
+                root
+            );
+        } else {
+            println!();
+        }
+
+        if options.explain {
+            for (path, reason) in &report.reasons {
+                println!(
+                    "\t{}{}, {}",
+                    style::header("Dirty "),
+                    path.strip_prefix(root).unwrap_or(path).display(),
+                    reason.explain(path, root)
+                );
+            }
+
+            if !report.reasons.is_empty() {
+                println!();
+            }
+        }
+    }
+
+    for (path, reason) in &report.reasons {
+        log.line(&format!(
+            "dirty {}, {}",
+            path.strip_prefix(root).unwrap_or(path).display(),
+            reason.explain(path, root)
+        ));
+    }
+
+    // Objects the cache has are copied instead of compiled.
+    let cache = if use_ninja || options.save_temps {
+        None
+    } else {
+        Cache::open()?
+    };
+    let mut keys = Vec::new();
+    let mut cached = Vec::new();
+
+    if let Some(cache) = &cache {
+        let fetch = trace::span("cache", "fetch");
+        let (hits, misses) = cache.fetch(root, &mut jobs)?;
+        drop(fetch);
+
+        for (source, _, command, fingerprint) in hits {
+            log.line(&format!(
+                "cache hit {}",
+                source.strip_prefix(root).unwrap_or(&source).display()
+            ));
+            fingerprint::record(&command, &fingerprint)
+                .map_err(|err| CedarError::io(&fingerprint, err))?;
+            report.compiled.push(source);
+        }
+
+        keys = misses;
+    }
+
+    let hits = report.compiled.len();
+    let mut compile_units = Vec::new();
+
+    // With workers in the config the compiles run in parallel, spread over
+    // them. The hooks of [hooks.build] keep them on this machine.
+    let diagnostics = Diagnostics::new(root, options);
+    let distributed = match manifest.hooks.build.is_empty() {
+        true => dist::compile(&mut jobs, &diagnostics)?,
+        false => None,
+    };
+
+    for (index, (source, object, mut command, fingerprint)) in jobs.into_iter().enumerate() {
+        let started = Instant::now();
+        let ((success, output), started, elapsed, lane) = match &distributed {
+            Some(outcomes) => match &outcomes[index] {
+                Some(outcome) => (
+                    (outcome.success, outcome.output.clone()),
+                    outcome.started,
+                    outcome.elapsed,
+                    outcome.slot,
+                ),
+                None => continue,
+            },
+            None => {
+                if let Some(parent) = object.parent() {
+                    fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+                }
+
+                let _compile = trace::span("compile", source.display());
+                hooks::before_compile(project, &source, &object)?;
+
+                // The captured diagnostics keep their colors when they are
+                // printed to a terminal, unless they come as JSON.
+                let color = !options.quiet
+                    && options.output_format == OutputFormat::Human
+                    && style::enabled()
+                    && io::stderr().is_terminal()
+                    && !command
+                        .get_args()
+                        .any(|arg| arg == "-fdiagnostics-format=json");
+
+                let ticket = diagnostics.start();
+                let (success, output) = run_step(&mut command, color)?;
+                // A diagnostics hook filters them as text, rendered from JSON.
+                let output = hooks::filter_diagnostics(
+                    project,
+                    &source,
+                    &object,
+                    diagnostics.to_text(&output),
+                )?;
+                let output = diagnostics.finish(ticket, &source, &output);
+
+                hooks::after_compile(project, &source, &object, success)?;
+
+                ((success, output), started, started.elapsed(), 0)
+            }
+        };
+        let relative = source.strip_prefix(root).unwrap_or(&source);
+
+        log.step(
+            &format!("compile {}", relative.display()),
+            &command,
+            Some(elapsed),
+            success,
+            &output,
+        );
+
+        report.timings.push(Unit {
+            name: relative.display().to_string(),
+            kind: UnitKind::Compile,
+            start: started.duration_since(now),
+            duration: elapsed,
+            lane,
+            inputs: Vec::new(),
+        });
+        compile_units.push((object.clone(), report.timings.len() - 1));
+
+        // The terminal already has the output unless quiet.
+        let output = match options.quiet {
+            true => output,
+            false => String::new(),
+        };
+
+        if !success {
+            diagnostics.summary();
+            log.failed(options.quiet);
+
+            if let Some(cache) = &cache {
+                cache.store(&cached);
+            }
+
+            return Err(BuildError::CompilationFailed(
+                relative.to_str().unwrap().to_owned(),
+                output,
+            )
+            .into());
+        }
+
+        fingerprint::record(&command, &fingerprint)
+            .map_err(|err| CedarError::io(&fingerprint, err))?;
+
+        if let Some(Some(key)) = keys.get(index) {
+            cached.push((key.clone(), object));
+        }
+
+        if !output.is_empty() {
+            report.diagnostics.push((source.clone(), output));
+        }
+        report.compiled.push(source);
+    }
+
+    if let Some(cache) = &cache {
+        cache.store(&cached);
+        report.cache = Some(cache.stats(hits, report.compiled.len() - hits));
+    }
+
+    let mut previous_link = None;
+
+    for (artifact, mut command, fingerprint, inputs) in links {
+        for parent in [fingerprint.parent(), artifact.parent()]
+            .into_iter()
+            .flatten()
+        {
+            fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+        }
+
+        // ar only replaces members, so objects of deleted sources would linger
+        // in an archive that is updated in place.
+        if artifact.exists() {
+            fs::remove_file(&artifact).map_err(|err| CedarError::io(&artifact, err))?;
+        }
+
+        let started = Instant::now();
+        let link = trace::span("link", artifact.display());
+        let ticket = diagnostics.start();
+        let (success, output) = run_step(&mut command, false)?;
+        drop(link);
+        let output = diagnostics.finish(ticket, &artifact, &output);
+        let relative = artifact.strip_prefix(root).unwrap_or(&artifact);
+
+        log.step(
+            &format!("link {}", relative.display()),
+            &command,
+            Some(started.elapsed()),
+            success,
+            &output,
+        );
+
+        // Links run one after the other, after the compiles of their objects.
+        report.timings.push(Unit {
+            name: relative.display().to_string(),
+            kind: UnitKind::Link,
+            start: started.duration_since(now),
+            duration: started.elapsed(),
+            lane: 0,
+            inputs: compile_units
+                .iter()
+                .filter(|(object, _)| inputs.contains(object))
+                .map(|(_, index)| *index)
+                .chain(previous_link)
+                .collect(),
+        });
+        previous_link = Some(report.timings.len() - 1);
+
+        if !success {
+            diagnostics.summary();
+            log.failed(options.quiet);
+
+            return Err(BuildError::LinkingFailed(match options.quiet {
+                true => output,
+                false => String::new(),
+            })
+            .into());
+        }
+
+        fingerprint::record(&command, &fingerprint)
+            .map_err(|err| CedarError::io(&fingerprint, err))?;
+        report.linked.push(artifact);
+    }
+
+    for (output_dir, artifact) in &all_artifacts {
+        if let Artifact::SharedLib {
+            name,
+            soname: Some(soname),
+        } = artifact
+        {
+            symlink(output_dir, name, soname).map_err(|err| CedarError::io(output_dir, err))?;
+            symlink(output_dir, soname, &format!("lib{}.so", manifest.meta.name))
+                .map_err(|err| CedarError::io(output_dir, err))?;
+        }
+    }
+
+    // An in tree pkg-config file lets other projects consume the library
+    // straight from its build directory via PKG_CONFIG_PATH.
+    if options.emit.is_none() && targets.iter().any(|target| target.kind == TargetKind::Lib) {
+        let pkgconfig = out_path.join("pkgconfig");
+        let pc = pkgconfig.join(format!("{}.pc", manifest.meta.name));
+        let prefix = root
+            .canonicalize()
+            .map_err(|err| CedarError::io(root, err))?;
+        let libdir = out_path.strip_prefix(root).unwrap_or(out_path);
+
+        fs::create_dir_all(&pkgconfig).map_err(|err| CedarError::io(&pkgconfig, err))?;
+        fs::write(
+            &pc,
+            pkg_config(manifest, &prefix, &libdir.to_string_lossy()),
+        )
+        .map_err(|err| CedarError::io(&pc, err))?;
+    }
+
+    report.artifacts = match options.emit {
+        Some(_) => emitted,
+        None => all_artifacts
+            .iter()
+            .map(|(output_dir, artifact)| output_dir.join(artifact.name()))
+            .collect(),
+    };
+    report.elapsed = now.elapsed();
+    report.fixits = diagnostics.fixits();
+
+    if let Some(stats) = &report.cache {
+        log.line(&format!(
+            "cache {} hit(s) ({} remote), {} miss(es)",
+            stats.hits, stats.remote_hits, stats.misses
+        ));
+    }
+    log.line(&format!("finished in {:.2?}", report.elapsed));
+
+    // The history of the timings is kept for every build, for --timings.
+    if let Err(err) = timings::record(build_path, &project.profile, &report) {
+        log.line(&format!("timings not recorded: {}", err));
+    }
+
+    if !options.quiet {
+        diagnostics.summary();
+
+        if let Some(stats) = report
+            .cache
+            .as_ref()
+            .filter(|stats| stats.hits + stats.misses > 0)
+        {
+            stats.print();
+        }
+
+        // The dev profile is the usual build and goes unmentioned.
+        let profile = match project.profile.as_str() {
+            Profile::DEFAULT => String::new(),
+            name => format!("{} profile ", name),
+        };
+
+        println!(
+            "\t{} {}in {:.2?}\n",
+            style::header("Finished"),
+            profile,
+            report.elapsed
+        );
+    }
+
+    Ok(report)
+}
+
+/// Targets of a build that compile with the same compiler and flags.
+///
+/// # Fields
+///
+/// * 'build' - The [build] settings of the targets, see
+///   Manifest::target_build.
+/// * 'toolchain' - The toolchain of their compiler.
+/// * 'out_path' - Where their objects go, in obj/ and deps/obj/. The out
+///   directory itself for the settings of [build], and
+///   groups/<compiler>-<hash of the flags>/ in it otherwise.
+///
+struct Group {
+    build: Build,
+    toolchain: Toolchain,
+    out_path: PathBuf,
+}
+
+impl Group {
+    fn new(build: Build, out_path: &Path, default: &Build) -> Result<Self, BuildError> {
+        let toolchain = Toolchain::from_compiler(&build.compiler)?;
+
+        let out_path = match compiles_alike(&build, default) {
+            true => out_path.to_path_buf(),
+            false => {
+                let flags = [&build.cflags, &build.cxxflags, &build.asflags]
+                    .map(|flags| flags.join(" "))
+                    .join("\n");
+                let compiler: String = build
+                    .compiler
+                    .chars()
+                    .map(
+                        |ch| match ch.is_ascii_alphanumeric() || "-_.".contains(ch) {
+                            true => ch,
+                            false => '_',
+                        },
+                    )
+                    .collect();
+
+                out_path.join("groups").join(format!(
+                    "{}-{}",
+                    compiler,
+                    &sha256::digest(flags.as_bytes())[..8]
+                ))
+            }
+        };
+
+        Ok(Self {
+            build,
+            toolchain,
+            out_path,
+        })
+    }
+}
+
+/// Whether two sets of [build] settings compile every source alike.
+fn compiles_alike(a: &Build, b: &Build) -> bool {
+    a.compiler == b.compiler
+        && a.cflags == b.cflags
+        && a.cxxflags == b.cxxflags
+        && a.asflags == b.asflags
+}
+
+/// Where embed_notices writes the license notices, build/notices/notices.c.
+pub(crate) fn notices_path(build_path: &Path) -> PathBuf {
+    build_path.join("notices").join("notices.c")
+}
+
+/// Writes build/notices/notices.c defining the license notices of the
+/// dependencies, only touching it when they changed so it is not recompiled
+/// every build.
+pub(crate) fn embed_notices(
+    build_path: &Path,
+    packages: &[Package],
+) -> Result<(PathBuf, Language), CedarError> {
+    let path = notices_path(build_path);
+    let source = license::notices_source(&license::notices(packages));
+
+    if fs::read_to_string(&path).ok().as_deref() != Some(source.as_str()) {
+        let dir = build_path.join("notices");
+        fs::create_dir_all(&dir).map_err(|err| CedarError::io(&dir, err))?;
+        fs::write(&path, source).map_err(|err| CedarError::io(&path, err))?;
+    }
+
+    Ok((path, Language::C))
+}
+
+/// Writes a translation unit including just the header for every header in
+/// include/, under header-check/ in the out directory, only touching those
+/// that changed. C++ headers (.hpp, .hh, .hxx) get a C++ unit.
+fn header_checks(
+    project: &Project,
+    out_path: &Path,
+) -> Result<Vec<(PathBuf, Language)>, CedarError> {
+    let dir = out_path.join("header-check");
+    let headers = files_with_extensions(
+        &project.include_dir,
+        &["h", "hh", "hpp", "hxx"],
+        project.manifest.build.symlinks,
+    )?;
+
+    let mut sources = Vec::new();
+
+    for header in headers {
+        let relative = header.strip_prefix(&project.include_dir).unwrap_or(&header);
+        let (extension, language) = match header.extension().and_then(|ext| ext.to_str()) {
+            Some("h") => ("c", Language::C),
+            _ => ("cpp", Language::Cxx),
+        };
+
+        let path = dir.join(format!("{}.{}", relative.display(), extension));
+        let source = format!("#include \"{}\"\n", relative.display());
+
+        if fs::read_to_string(&path).ok().as_deref() != Some(source.as_str()) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+            }
+            fs::write(&path, source).map_err(|err| CedarError::io(&path, err))?;
+        }
+
+        sources.push((path, language));
+    }
+
+    Ok(sources)
+}
+
+/// The objects of the given sources.
+fn objects(root: &Path, object_path: &Path, sources: &[(PathBuf, Language)]) -> Vec<PathBuf> {
+    sources
+        .iter()
+        .map(|(source, _)| object_files(object_path, source.strip_prefix(root).unwrap_or(source)).0)
+        .collect()
+}
+
+/// Runs a compile or link step, capturing its output for the build log and
+/// the diagnostics.
+///
+/// # Arguments
+///
+/// * 'command' - The compile or link.
+/// * 'color' - Whether the compiler colors its diagnostics although they
+///   are captured. The flag is not part of the command, so it changes
+///   neither its fingerprint nor its cache key.
+///
+/// # Returns
+///
+/// * Whether the step succeeded and its captured stdout and stderr.
+///
+fn run_step(command: &mut process::Command, color: bool) -> Result<(bool, String), CedarError> {
+    let output = match color {
+        true => {
+            let mut colored = process::Command::new(command.get_program());
+            colored
+                .args(command.get_args())
+                .arg("-fdiagnostics-color=always");
+
+            if let Some(dir) = command.get_current_dir() {
+                colored.current_dir(dir);
+            }
+
+            for (key, value) in command.get_envs() {
+                match value {
+                    Some(value) => colored.env(key, value),
+                    None => colored.env_remove(key),
+                };
+            }
+
+            colored.output()
+        }
+        false => command.output(),
+    }
+    .map_err(|err| CedarError::command(command, err))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    Ok((output.status.success(), text))
+}
+
+/// Points `dir/link` at `target` (relative to dir), replacing whatever was
+/// there. Hosts without symlinks get a copy of the target instead.
+pub(crate) fn symlink(dir: &Path, target: &str, link: &str) -> Result<(), std::io::Error> {
+    let link_path = dir.join(link);
+
+    if link_path.symlink_metadata().is_ok() {
+        fs::remove_file(&link_path)?;
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, &link_path)?;
+
+    #[cfg(not(unix))]
+    fs::copy(dir.join(target), &link_path).map(|_| ())?;
 
     Ok(())
 }
 
-fn recursive_file_search<P: AsRef<Path>>(path: P) -> Result<Vec<String>, std::io::Error> {
+pub(crate) fn recursive_file_search<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<String>, std::io::Error> {
     let mut result = Vec::new();
     for file in fs::read_dir(path)? {
         let file_path = file?.path();
@@ -86,12 +1815,15 @@ fn recursive_file_search<P: AsRef<Path>>(path: P) -> Result<Vec<String>, std::io
         if file_path.is_dir() {
             result.extend_from_slice(&recursive_file_search(file_path)?);
         } else {
-            result.push(
-                file_path
-                    .to_str()
-                    .expect("Error: Failed to convert path to string.")
-                    .to_owned(),
-            )
+            match file_path.to_str() {
+                Some(file) => result.push(file.to_owned()),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{:?} is not valid UTF-8", file_path),
+                    ))
+                }
+            }
         }
     }
 
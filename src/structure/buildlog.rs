@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::style;
+
+use super::{ninja, sbom::format_timestamp};
+
+/// How many logs build/log/ keeps, the oldest are removed first.
+const KEEP: usize = 20;
+
+/// The log of a build in build/log/, named after the time it started. It
+/// holds the full command line, duration, and output of every step whatever
+/// the terminal shows, so a failed build can be diagnosed after the fact.
+///
+/// Logging never fails the build: a log that cannot be written is dropped.
+///
+/// # Fields
+///
+/// * 'file' - The open log, None once writing failed.
+/// * 'path' - Where the log is.
+/// * 'started' - When the build started, steps are logged relative to it.
+///
+pub(crate) struct BuildLog {
+    file: Option<fs::File>,
+    path: PathBuf,
+    started: Instant,
+}
+
+impl BuildLog {
+    /// Starts the log of a build, removing the oldest logs past KEEP.
+    ///
+    /// # Arguments
+    ///
+    /// * 'build_path' - The build directory.
+    /// * 'title' - What is built, the first line of the log.
+    ///
+    pub fn create(build_path: &Path, title: &str) -> Self {
+        let dir = build_path.join("log");
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let timestamp = format_timestamp(seconds);
+
+        // Colons are not allowed in file names on Windows.
+        let path = dir.join(format!(
+            "{}-{}.log",
+            timestamp.replace(':', "-"),
+            process::id()
+        ));
+
+        let file = fs::create_dir_all(&dir)
+            .and_then(|_| fs::File::create(&path))
+            .ok();
+
+        rotate(&dir);
+
+        let mut log = Self {
+            file,
+            path,
+            started: Instant::now(),
+        };
+        log.line(&format!(
+            "cedar {} {} at {}",
+            env!("CARGO_PKG_VERSION"),
+            title,
+            timestamp
+        ));
+
+        log
+    }
+    /// Appends a line, prefixed with the time since the build started.
+    pub fn line(&mut self, text: &str) {
+        let line = format!(
+            "[{:>9.3}s] {}\n",
+            self.started.elapsed().as_secs_f64(),
+            text
+        );
+
+        if let Some(file) = &mut self.file {
+            if file.write_all(line.as_bytes()).is_err() {
+                self.file = None;
+            }
+        }
+    }
+    /// Ends the log of a failed build, pointing at it unless quiet.
+    pub fn failed(&mut self, quiet: bool) {
+        self.line("failed");
+
+        if !quiet && self.file.is_some() {
+            println!("\n\t{}{}", style::header("Logged "), self.path.display());
+        }
+    }
+    /// Logs a step that ran: what, how long it took, its command line, and
+    /// its output indented below.
+    ///
+    /// # Arguments
+    ///
+    /// * 'what' - e.g. "compile src/main.c".
+    /// * 'command' - The command it ran.
+    /// * 'elapsed' - How long it ran, None when unknown (e.g. compiled on
+    ///   a worker alongside others).
+    /// * 'success' - Whether it succeeded.
+    /// * 'output' - What it printed.
+    ///
+    pub fn step(
+        &mut self,
+        what: &str,
+        command: &process::Command,
+        elapsed: Option<Duration>,
+        success: bool,
+        output: &str,
+    ) {
+        self.line(&format!(
+            "{}{}{}: {}",
+            what,
+            elapsed.map_or(String::new(), |elapsed| format!(
+                " ({:.3}s)",
+                elapsed.as_secs_f64()
+            )),
+            if success { "" } else { " FAILED" },
+            ninja::shell(command)
+        ));
+
+        for line in output.lines() {
+            self.line(&format!("    {}", style::strip(line)));
+        }
+    }
+}
+
+/// Removes the oldest logs of a directory past KEEP, the names sort by age.
+fn rotate(dir: &Path) {
+    let mut logs: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "log"))
+            .collect(),
+        Err(_) => return,
+    };
+
+    logs.sort();
+
+    for old in logs.iter().take(logs.len().saturating_sub(KEEP)) {
+        let _ = fs::remove_file(old);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_logs() {
+        let dir = std::env::temp_dir().join(format!("cedar-log-{}", process::id()));
+
+        for index in 0..KEEP + 3 {
+            let mut log = BuildLog::create(&dir, "test");
+            log.step(
+                "compile src/main.c",
+                process::Command::new("cc").arg("-c"),
+                Some(Duration::from_millis(1500)),
+                false,
+                "main.c:1: error",
+            );
+            // Logs started in the same second share a name.
+            fs::rename(&log.path, dir.join("log").join(format!("{:03}.log", index))).unwrap();
+        }
+
+        BuildLog::create(&dir, "test");
+
+        let logs = fs::read_dir(dir.join("log")).unwrap().count();
+        assert_eq!(logs, KEEP);
+
+        let contents = fs::read_to_string(dir.join("log/022.log")).unwrap();
+        assert!(contents.contains("] compile src/main.c (1.500s) FAILED: cc -c\n"));
+        assert!(contents.contains("]     main.c:1: error\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
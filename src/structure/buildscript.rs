@@ -0,0 +1,296 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    build::{host_compiler, target_triple, BuildError, Language, Toolchain},
+    fingerprint,
+    project::{Project, ProjectError},
+};
+
+/// What the build script of a project adds to its build, from the
+/// directives it prints on stdout:
+///
+/// ```text
+/// cedar:define=HAVE_EPOLL
+/// cedar:define=PAGE_SIZE=4096
+/// cedar:source=build/gen/tables.c
+/// cedar:link-lib=rt
+/// cedar:rerun-if-changed=data/tables.csv
+/// ```
+///
+/// # Fields
+///
+/// * 'defines' - The macros every library and target source of the project
+///   compiles with, as -D flags.
+/// * 'sources' - Sources compiled into the library part of the project,
+///   e.g. ones the script generates.
+/// * 'libs' - Libraries every artifact links, as -l flags.
+/// * 'rerun_if_changed' - The files the script reads, it runs again when
+///   any of them changes. The script only runs again when it changes
+///   itself otherwise.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptOutput {
+    pub defines: Vec<String>,
+    pub sources: Vec<(PathBuf, Language)>,
+    pub libs: Vec<String>,
+    pub rerun_if_changed: Vec<PathBuf>,
+}
+
+/// Compiles the build script of the project, build.c at its root, for the
+/// host and runs it, unless neither the script nor what it reads changed
+/// since it last ran. The script runs in the root with CEDAR_ROOT,
+/// CEDAR_OUT_DIR, CEDAR_PROFILE, CEDAR_TARGET, CEDAR_CC, and CEDAR_FEATURES
+/// set, and what it prints on stderr is shown unless quiet or it fails.
+///
+/// # Arguments
+///
+/// * 'project' - The project being built.
+/// * 'cc' - The C compiler of the build, what CEDAR_CC names.
+/// * 'quiet' - Suppresses the progress output and what the script prints
+///   on stderr when it succeeds.
+///
+/// # Returns
+///
+/// * What the script adds to the build, None without a build.c.
+///
+pub fn run(project: &Project, cc: &str, quiet: bool) -> Result<Option<ScriptOutput>, CedarError> {
+    let script = project.root.join("build.c");
+
+    if !script.is_file() {
+        return Ok(None);
+    }
+
+    let script_path = project.out_dir().join("build-script");
+    fs::create_dir_all(&script_path).map_err(|err| CedarError::io(&script_path, err))?;
+
+    // The script runs where cedar does, whatever the build targets: it is
+    // compiled by the compiler of the manifest unless that is a cross
+    // compiler, by the one cedar finds on the host then.
+    let toolchain = Toolchain::from_compiler(&project.manifest.build.compiler)?;
+    let host = match toolchain.cc.as_str() {
+        "gcc" | "clang" => toolchain.cc,
+        _ => host_compiler().ok_or(ProjectError::MissingTool("gcc"))?,
+    };
+    let program = script_path.join("build-script");
+    let depfile = script_path.join("build-script.d");
+
+    let mut compile = process::Command::new(&host);
+    compile
+        .arg(&script)
+        .arg("-o")
+        .arg(&program)
+        .args(["-MMD", "-MF"])
+        .arg(&depfile);
+
+    let compile_fingerprint = script_path.join("build-script.cmd");
+    let inputs = fingerprint::dependencies(&script, &depfile);
+
+    if fingerprint::dirty(&program, &inputs, &compile, &compile_fingerprint).is_some() {
+        let output = compile
+            .output()
+            .map_err(|err| CedarError::command(&compile, err))?;
+
+        if !output.status.success() {
+            return Err(BuildError::CompilationFailed(
+                String::from("build.c"),
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            )
+            .into());
+        }
+
+        fingerprint::record(&compile, &compile_fingerprint)
+            .map_err(|err| CedarError::io(&compile_fingerprint, err))?;
+    }
+
+    let mut command = process::Command::new(&program);
+    command
+        .current_dir(&project.root)
+        .env("CEDAR_ROOT", &project.root)
+        .env("CEDAR_OUT_DIR", project.out_dir())
+        .env("CEDAR_PROFILE", &project.profile)
+        .env(
+            "CEDAR_TARGET",
+            target_triple(&project.manifest.build.compiler),
+        )
+        .env("CEDAR_CC", cc)
+        .env("CEDAR_FEATURES", project.features.join(","));
+
+    // The directives of the last run stand in for the script as long as
+    // nothing it reads changed.
+    let saved = script_path.join("output");
+    let run_fingerprint = script_path.join("output.cmd");
+
+    if let Ok(text) = fs::read_to_string(&saved) {
+        let last = parse(&text, &project.root)?;
+        let inputs: Vec<PathBuf> = std::iter::once(program.clone())
+            .chain(last.rerun_if_changed.iter().cloned())
+            .collect();
+
+        if fingerprint::dirty(&saved, &inputs, &command, &run_fingerprint).is_none() {
+            return Ok(Some(last));
+        }
+    }
+
+    if !quiet {
+        println!("\t{}build.c", style::header("Running "));
+    }
+
+    let output = command
+        .output()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !quiet || !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    if !output.status.success() {
+        return Err(BuildError::BuildScriptFailed(
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+        )
+        .into());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let parsed = parse(&text, &project.root)?;
+
+    fs::write(&saved, text.as_bytes()).map_err(|err| CedarError::io(&saved, err))?;
+    fingerprint::record(&command, &run_fingerprint)
+        .map_err(|err| CedarError::io(&run_fingerprint, err))?;
+
+    Ok(Some(parsed))
+}
+
+/// Reads the directives among the output of a build script, lines without
+/// the cedar: prefix are left alone. Paths are from the root of the project
+/// unless absolute.
+fn parse(output: &str, root: &Path) -> Result<ScriptOutput, BuildError> {
+    let mut parsed = ScriptOutput::default();
+
+    for line in output.lines() {
+        let Some(directive) = line.trim_end().strip_prefix("cedar:") else {
+            continue;
+        };
+
+        match directive.split_once('=') {
+            Some(("define", define)) if !define.is_empty() => {
+                parsed.defines.push(format!("-D{}", define));
+            }
+            Some(("source", source)) if !source.is_empty() => {
+                let path = root.join(source);
+                let language = Language::from_path(&path)
+                    .ok_or_else(|| BuildError::InvalidBuildScriptDirective(line.to_owned()))?;
+
+                parsed.sources.push((path, language));
+            }
+            Some(("link-lib", lib)) if !lib.is_empty() => {
+                parsed.libs.push(format!("-l{}", lib));
+            }
+            Some(("rerun-if-changed", path)) if !path.is_empty() => {
+                parsed.rerun_if_changed.push(root.join(path));
+            }
+            _ => return Err(BuildError::InvalidBuildScriptDirective(line.to_owned())),
+        }
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn reads_directives() {
+        let parsed = parse(
+            "checking for epoll... yes\ncedar:define=HAVE_EPOLL\ncedar:define=PAGE_SIZE=4096\ncedar:source=build/gen/tables.c\ncedar:link-lib=rt\ncedar:rerun-if-changed=data/tables.csv\n",
+            Path::new("/p"),
+        )
+        .unwrap();
+
+        assert_eq!(parsed.defines, ["-DHAVE_EPOLL", "-DPAGE_SIZE=4096"]);
+        assert_eq!(
+            parsed.sources,
+            [(PathBuf::from("/p/build/gen/tables.c"), Language::C)]
+        );
+        assert_eq!(parsed.libs, ["-lrt"]);
+        assert_eq!(
+            parsed.rerun_if_changed,
+            [PathBuf::from("/p/data/tables.csv")]
+        );
+
+        assert!(parse("cedar:source=tables.h\n", Path::new("/p")).is_err());
+        assert!(parse("cedar:warning=old\n", Path::new("/p")).is_err());
+    }
+
+    #[test]
+    fn reruns_only_on_change() {
+        let root = env::temp_dir().join(format!("cedar-buildscript-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("cedar.toml"),
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n",
+        )
+        .unwrap();
+        fs::write(root.join("data.txt"), "1\n").unwrap();
+
+        let project = Project::load(&root).unwrap();
+        assert_eq!(run(&project, "gcc", true).unwrap(), None);
+
+        // Every run leaves a line in runs.txt.
+        let script = |define: &str| {
+            format!(
+                "#include <stdio.h>\nint main(void) {{\n    FILE *runs = fopen(\"runs.txt\", \"a\");\n    fputs(\"run\\n\", runs);\n    fclose(runs);\n    puts(\"cedar:define={}\");\n    puts(\"cedar:rerun-if-changed=data.txt\");\n    return 0;\n}}\n",
+                define
+            )
+        };
+        let runs = || {
+            fs::read_to_string(root.join("runs.txt"))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        let touch = |path: PathBuf| {
+            let later = fs::metadata(&path).unwrap().modified().unwrap() + Duration::from_secs(10);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(later)
+                .unwrap();
+        };
+
+        fs::write(root.join("build.c"), script("A")).unwrap();
+        let output = run(&project, "gcc", true).unwrap().unwrap();
+        assert_eq!(output.defines, ["-DA"]);
+        assert_eq!(output.rerun_if_changed, [root.join("data.txt")]);
+        assert_eq!(runs(), 1);
+
+        // Nothing changed, the saved directives stand in for the script.
+        assert_eq!(run(&project, "gcc", true).unwrap().unwrap(), output);
+        assert_eq!(runs(), 1);
+
+        // What the script reads changed.
+        touch(root.join("data.txt"));
+        assert_eq!(run(&project, "gcc", true).unwrap().unwrap(), output);
+        assert_eq!(runs(), 2);
+
+        // The script itself changed, it is compiled again.
+        fs::write(root.join("build.c"), script("B")).unwrap();
+        touch(root.join("build.c"));
+        assert_eq!(
+            run(&project, "gcc", true).unwrap().unwrap().defines,
+            ["-DB"]
+        );
+        assert_eq!(runs(), 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
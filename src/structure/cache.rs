@@ -0,0 +1,451 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    build::Language,
+    config::{CacheConfig, Config},
+    dist::{self, Job},
+    probe,
+    project::ProjectError,
+    sha256::Sha256,
+    sysdeps,
+};
+
+/// The cache settings from the global config.
+static CACHE: OnceLock<CacheConfig> = OnceLock::new();
+
+/// Sets where compiled objects are cached, only the first call has an
+/// effect.
+pub fn set_config(cache: CacheConfig) {
+    let _ = CACHE.set(cache);
+}
+
+/// How the cache did during a build.
+///
+/// # Fields
+///
+/// * 'hits' - The objects copied from the cache instead of compiled.
+/// * 'remote_hits' - The hits fetched from the remote cache.
+/// * 'misses' - The objects compiled.
+/// * 'failed_uploads' - The objects the remote cache did not accept.
+///
+#[derive(Debug, Clone, Default)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub remote_hits: usize,
+    pub misses: usize,
+    pub failed_uploads: usize,
+}
+
+impl CacheStats {
+    /// Prints the statistics as a line of the build output.
+    pub fn print(&self) {
+        println!(
+            "\t{}{} hit(s) ({} remote), {} miss(es){}",
+            style::header("Cache "),
+            self.hits,
+            self.remote_hits,
+            self.misses,
+            match self.failed_uploads {
+                0 => String::new(),
+                failed => format!(", {} upload(s) failed", failed),
+            }
+        );
+    }
+}
+
+/// The cache of compiled objects, keyed by the SHA-256 of the compiler, its
+/// flags, and the preprocessed source. When the flags map the project root
+/// away with -ffile-prefix-map or -fdebug-prefix-map, paths under it are
+/// left out of the key, so checkouts at different paths share objects.
+/// Otherwise the objects hold the root in __FILE__ and their debug
+/// information, and each checkout has its own.
+///
+/// # Fields
+///
+/// * 'dir' - The local cache, objects are kept at <dir>/<ab>/<key>.o.
+/// * 'config' - The settings of the remote cache.
+/// * 'versions' - The version of each compiler, as part of the keys.
+/// * 'remote_hits' - The hits fetched from the remote cache.
+/// * 'failed_uploads' - The objects the remote cache did not accept.
+///
+pub(crate) struct Cache {
+    dir: PathBuf,
+    config: &'static CacheConfig,
+    versions: Mutex<HashMap<String, String>>,
+    remote_hits: AtomicUsize,
+    failed_uploads: AtomicUsize,
+}
+
+impl Cache {
+    /// The cache of the global config, None when it is not enabled.
+    pub(crate) fn open() -> Result<Option<Self>, CedarError> {
+        let Some(config) = CACHE
+            .get()
+            .filter(|config| config.enabled || config.url.is_some())
+        else {
+            return Ok(None);
+        };
+
+        if config.url.is_some() && !sysdeps::on_path("curl") {
+            return Err(ProjectError::MissingTool("curl").into());
+        }
+
+        let dir = Config::data_dir()
+            .ok_or(ProjectError::InvalidPath(String::from(
+                "No home directory to keep the cache in.",
+            )))?
+            .join("cache");
+
+        Ok(Some(Self {
+            dir,
+            config,
+            versions: Mutex::new(HashMap::new()),
+            remote_hits: AtomicUsize::new(0),
+            failed_uploads: AtomicUsize::new(0),
+        }))
+    }
+    /// Copies the objects the cache has in place of compiling them, taking
+    /// their compiles out of the jobs.
+    ///
+    /// # Arguments
+    ///
+    /// * 'root' - The root of the project.
+    /// * 'jobs' - The compiles of the build, only the misses are left.
+    ///
+    /// # Returns
+    ///
+    /// * The compiles of the objects copied, and the key of each compile
+    ///   left (None when the source did not preprocess, the compile
+    ///   then reports why).
+    ///
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn fetch(
+        &self,
+        root: &Path,
+        jobs: &mut Vec<Job>,
+    ) -> Result<(Vec<Job>, Vec<Option<String>>), CedarError> {
+        let lookups = parallel(jobs, |(source, object, command, _)| {
+            let key = self.key(root, source, object, command)?;
+            let hit = self.get(&key, object);
+            Some((key, hit))
+        });
+
+        let mut hits = Vec::new();
+        let mut keys = Vec::new();
+        let mut misses = Vec::new();
+
+        for (job, lookup) in jobs.drain(..).zip(lookups) {
+            match lookup {
+                Some((_, true)) => hits.push(job),
+                lookup => {
+                    keys.push(lookup.map(|(key, _)| key));
+                    misses.push(job);
+                }
+            }
+        }
+
+        *jobs = misses;
+
+        Ok((hits, keys))
+    }
+    /// Stores compiled objects in the cache, and in the remote cache unless
+    /// it is read only. A failure to store an object only costs a future
+    /// hit, so it is counted instead of failing the build.
+    pub(crate) fn store(&self, objects: &[(String, PathBuf)]) {
+        parallel(objects, |(key, object)| {
+            let path = self.path(key);
+            let _ = copy(object, &path);
+
+            if let (Some(url), false) = (&self.config.url, self.config.read_only) {
+                if !self.curl(url, key, &["-T"], object) {
+                    self.failed_uploads.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+    /// The statistics of the build, given how many compiles there were.
+    pub(crate) fn stats(&self, hits: usize, misses: usize) -> CacheStats {
+        CacheStats {
+            hits,
+            remote_hits: self.remote_hits.load(Ordering::Relaxed),
+            misses,
+            failed_uploads: self.failed_uploads.load(Ordering::Relaxed),
+        }
+    }
+    /// The key of a compile, preprocessing the source (which also writes its
+    /// depfile). None for assembly, and when the source does not preprocess.
+    fn key(
+        &self,
+        root: &Path,
+        source: &Path,
+        object: &Path,
+        command: &process::Command,
+    ) -> Option<String> {
+        if !matches!(
+            Language::from_path(source),
+            Some(Language::C | Language::Cxx)
+        ) {
+            return None;
+        }
+
+        fs::create_dir_all(object.parent()?).ok()?;
+
+        let (mut preprocess, compiler, flags) = dist::split(command, object);
+        let output = preprocess.output().ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let root = root.to_string_lossy();
+        let relocatable = maps_root(&flags, &root);
+        let relative = |text: &str| match relocatable {
+            true => text.replace(root.as_ref(), ""),
+            false => text.to_owned(),
+        };
+        let mut hasher = Sha256::new();
+
+        hasher.update(self.version(&compiler).as_bytes());
+        for flag in &flags {
+            hasher.update(b"\0");
+            hasher.update(relative(flag).as_bytes());
+        }
+        hasher.update(b"\0");
+        hasher.update(relative(&String::from_utf8_lossy(&output.stdout)).as_bytes());
+
+        Some(hasher.finish())
+    }
+    /// The version of a compiler, so an upgrade does not hit objects of the
+    /// previous version.
+    fn version(&self, compiler: &str) -> String {
+        let mut versions = self.versions.lock().unwrap();
+
+        versions
+            .entry(compiler.to_owned())
+            .or_insert_with(|| probe::version(compiler).unwrap_or_else(|| compiler.to_owned()))
+            .clone()
+    }
+    /// Copies the object of a key from the cache, fetching it from the remote
+    /// cache when it is not kept locally.
+    fn get(&self, key: &str, object: &Path) -> bool {
+        let path = self.path(key);
+
+        if !path.is_file() {
+            let Some(url) = &self.config.url else {
+                return false;
+            };
+
+            let download = path.with_extension(format!("{}.tmp", process::id()));
+            let fetched = path
+                .parent()
+                .is_some_and(|dir| fs::create_dir_all(dir).is_ok())
+                && self.curl(url, key, &["-o"], &download)
+                && fs::rename(&download, &path).is_ok();
+
+            if !fetched {
+                let _ = fs::remove_file(&download);
+                return false;
+            }
+
+            self.remote_hits.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fs::copy(&path, object).is_ok()
+    }
+    /// Where the object of a key is kept locally.
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(format!("{}.o", key))
+    }
+    /// Transfers the object of a key to (-T) or from (-o) the remote cache.
+    fn curl(&self, url: &str, key: &str, args: &[&str], file: &Path) -> bool {
+        let mut command = process::Command::new("curl");
+        command
+            .arg("-fsS")
+            .args(args)
+            .arg(file)
+            .arg(format!("{}/{}.o", url.trim_end_matches('/'), key))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        // The credentials are passed as a config on stdin so they do not
+        // show up in the process list.
+        let mut credentials = String::new();
+        if let Some(region) = &self.config.s3_region {
+            command
+                .arg("--aws-sigv4")
+                .arg(format!("aws:amz:{}:s3", region));
+
+            if let (Ok(id), Ok(secret)) = (
+                env::var("AWS_ACCESS_KEY_ID"),
+                env::var("AWS_SECRET_ACCESS_KEY"),
+            ) {
+                command.arg("-K").arg("-");
+                credentials = format!("user = \"{}:{}\"\n", id, secret);
+
+                if let Ok(token) = env::var("AWS_SESSION_TOKEN") {
+                    credentials
+                        .push_str(&format!("header = \"x-amz-security-token: {}\"\n", token));
+                }
+            }
+        }
+
+        let Ok(mut child) = command.spawn() else {
+            return false;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(credentials.as_bytes());
+        }
+
+        child.wait().is_ok_and(|status| status.success())
+    }
+}
+
+/// Whether the flags of a compile map the root, or a directory above it,
+/// out of what the compiler writes into objects.
+fn maps_root(flags: &[String], root: &str) -> bool {
+    flags.iter().any(|flag| {
+        ["-ffile-prefix-map=", "-fdebug-prefix-map="]
+            .iter()
+            .filter_map(|option| flag.strip_prefix(option))
+            .filter_map(|map| map.split_once('='))
+            .any(|(old, _)| !old.is_empty() && Path::new(root).starts_with(old))
+    })
+}
+
+/// Copies a file into the cache, through a temporary file so concurrent
+/// builds never see a partial object.
+fn copy(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let temporary = to.with_extension(format!("{}.tmp", process::id()));
+    fs::copy(from, &temporary)?;
+    fs::rename(&temporary, to)
+}
+
+/// Runs a function on every item using every core, returning the results in
+/// order.
+pub fn parallel<T: Sync, R: Send>(items: &[T], function: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let jobs = thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+        .clamp(1, items.len().max(1));
+    let queue = Mutex::new(items.iter().enumerate());
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let Some((index, item)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+
+                let result = function(item);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_in_parallel_in_order() {
+        let items: Vec<usize> = (0..100).collect();
+
+        assert_eq!(
+            parallel(&items, |item| *item * 2),
+            (0..100).map(|item| item * 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn caches_objects_by_key() {
+        let dir = env::temp_dir().join(format!("cedar-cache-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let cache = Cache {
+            dir: dir.join("cache"),
+            config: Box::leak(Box::default()),
+            versions: Mutex::new(HashMap::from([(String::from("gcc"), String::from("gcc"))])),
+            remote_hits: AtomicUsize::new(0),
+            failed_uploads: AtomicUsize::new(0),
+        };
+
+        // The same source in two checkouts.
+        let checkout = |name: &str| {
+            let root = dir.join(name);
+            fs::create_dir_all(root.join("src")).unwrap();
+            fs::write(root.join("src/a.c"), "int a(void) { return 1; }\n").unwrap();
+            root
+        };
+        let (a, b) = (checkout("a"), checkout("b"));
+
+        let job = |root: &Path, flags: &[String]| -> Job {
+            let source = root.join("src/a.c");
+            let object = root.join("obj/a.c.o");
+            let mut command = process::Command::new("gcc");
+            command
+                .args(flags)
+                .arg("-c")
+                .arg(&source)
+                .arg("-o")
+                .arg(&object)
+                .args(["-MMD", "-MF"])
+                .arg(root.join("obj/a.c.d"));
+            (source, object, command, root.join("obj/a.c.o.cmd"))
+        };
+        let key = |root: &Path, flags: &[String]| {
+            let (source, object, command, _) = job(root, flags);
+            cache.key(root, &source, &object, &command).unwrap()
+        };
+        let map = |root: &Path| vec![format!("-ffile-prefix-map={}=.", root.display())];
+
+        assert_ne!(key(&a, &[]), key(&b, &[]));
+        assert_eq!(key(&a, &map(&a)), key(&b, &map(&b)));
+
+        // A miss is compiled and stored, the next build copies it instead.
+        let mut jobs = vec![job(&a, &[])];
+        let (hits, keys) = cache.fetch(&a, &mut jobs).unwrap();
+        assert!(hits.is_empty());
+        assert!(jobs[0].2.status().unwrap().success());
+
+        cache.store(&[(keys[0].clone().unwrap(), jobs[0].1.clone())]);
+        fs::remove_file(a.join("obj/a.c.o")).unwrap();
+
+        let mut jobs = vec![job(&a, &[])];
+        let (hits, _) = cache.fetch(&a, &mut jobs).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(jobs.is_empty());
+        assert!(a.join("obj/a.c.o").is_file());
+
+        // The other checkout compiles its own.
+        let mut jobs = vec![job(&b, &[])];
+        let (hits, _) = cache.fetch(&b, &mut jobs).unwrap();
+        assert!(hits.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use std::{fs, path::Path};
+
+use crate::error::CedarError;
+
+use super::{release::git, sbom::timestamp};
+
+/// The groups of a changelog with conventional commits, by the type prefix
+/// of the commits, in the order they are listed. Other types are listed
+/// under "Other changes".
+const GROUPS: &[(&str, &str)] = &[
+    ("feat", "Features"),
+    ("fix", "Bug fixes"),
+    ("perf", "Performance"),
+    ("docs", "Documentation"),
+];
+
+/// A commit of the history, its abbreviated hash and the subject line.
+pub type Commit = (String, String);
+
+/// The most recent tag reachable from HEAD, None when there is none.
+pub fn last_tag(root: &Path) -> Option<String> {
+    git(root, &["describe", "--tags", "--abbrev=0"])
+        .ok()
+        .map(|tag| tag.trim().to_owned())
+        .filter(|tag| !tag.is_empty())
+}
+
+/// The commits since a tag, every commit without one, newest first and
+/// without merges.
+pub fn commits(root: &Path, since: Option<&str>) -> Result<Vec<Commit>, CedarError> {
+    let range = since.map_or_else(|| String::from("HEAD"), |tag| format!("{}..HEAD", tag));
+    let log = git(root, &["log", "--no-merges", "--format=%h%x09%s", &range])?;
+
+    Ok(log
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(hash, subject)| (hash.to_owned(), subject.to_owned()))
+        .collect())
+}
+
+/// The section of a version in the changelog: a heading with the version
+/// and date, then a line per commit.
+///
+/// # Arguments
+///
+/// * 'version' - The version the commits are released in.
+/// * 'commits' - The commits, newest first.
+/// * 'conventional' - Whether to group the commits by their conventional
+///   commit type (feat:, fix:, ...), breaking changes (feat!:) first.
+///
+pub fn section(version: &str, commits: &[Commit], conventional: bool) -> String {
+    let date = &timestamp()[..10];
+    let mut section = format!("## [{}] - {}\n\n", version, date);
+
+    if !conventional {
+        for (hash, subject) in commits {
+            section.push_str(&format!("- {} ({})\n", subject, hash));
+        }
+        section.push('\n');
+        return section;
+    }
+
+    let mut groups: Vec<(&str, Vec<String>)> = std::iter::once("Breaking changes")
+        .chain(GROUPS.iter().map(|(_, title)| *title))
+        .chain(std::iter::once("Other changes"))
+        .map(|title| (title, Vec::new()))
+        .collect();
+
+    for (hash, subject) in commits {
+        let (group, entry) = match parse_conventional(subject) {
+            Some((kind, scope, breaking, description)) => {
+                let group = match breaking {
+                    true => "Breaking changes",
+                    false => GROUPS
+                        .iter()
+                        .find(|(prefix, _)| *prefix == kind)
+                        .map_or("Other changes", |(_, title)| title),
+                };
+                let entry = match scope {
+                    Some(scope) => format!("**{}:** {}", scope, description),
+                    None => description.to_owned(),
+                };
+                (group, entry)
+            }
+            None => ("Other changes", subject.clone()),
+        };
+
+        if let Some((_, entries)) = groups.iter_mut().find(|(title, _)| *title == group) {
+            entries.push(format!("- {} ({})\n", entry, hash));
+        }
+    }
+
+    for (title, entries) in groups.iter().filter(|(_, entries)| !entries.is_empty()) {
+        section.push_str(&format!("### {}\n\n{}\n", title, entries.concat()));
+    }
+
+    section
+}
+
+/// Splits the subject of a conventional commit, "type(scope)!: description".
+///
+/// # Returns
+///
+/// * The type, scope, whether it is a breaking change, and the description,
+///   None when the subject does not follow the convention.
+///
+fn parse_conventional(subject: &str) -> Option<(&str, Option<&str>, bool, &str)> {
+    let (prefix, description) = subject.split_once(": ")?;
+    let (prefix, breaking) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+
+    if kind.is_empty() || !kind.chars().all(|ch| ch.is_ascii_lowercase()) {
+        return None;
+    }
+
+    Some((kind, scope, breaking, description.trim()))
+}
+
+/// Puts the section of a version into the text of a changelog, replacing the
+/// section of the same version if there is one, otherwise above the newest
+/// one. An empty changelog gets a # Changelog heading.
+pub fn insert(changelog: &str, version: &str, section: &str) -> String {
+    if changelog.trim().is_empty() {
+        return format!("# Changelog\n\n{}", section);
+    }
+
+    let heading = format!("## [{}]", version);
+    let starts: Vec<usize> = changelog
+        .match_indices("## ")
+        .map(|(start, _)| start)
+        .filter(|start| *start == 0 || changelog[..*start].ends_with('\n'))
+        .collect();
+
+    match starts
+        .iter()
+        .position(|start| changelog[*start..].starts_with(&heading))
+    {
+        Some(index) => {
+            let end = starts.get(index + 1).copied().unwrap_or(changelog.len());
+            format!(
+                "{}{}{}",
+                &changelog[..starts[index]],
+                section,
+                &changelog[end..]
+            )
+        }
+        None => match starts.first() {
+            Some(start) => format!(
+                "{}{}{}",
+                &changelog[..*start],
+                section,
+                &changelog[*start..]
+            ),
+            None => format!("{}\n\n{}", changelog.trim_end(), section),
+        },
+    }
+}
+
+/// Whether a changelog has the section of a version.
+pub fn has_section(changelog: &str, version: &str) -> bool {
+    let heading = format!("## [{}]", version);
+    changelog.lines().any(|line| line.starts_with(&heading))
+}
+
+/// The changelog of a project with the section of a version for the commits
+/// since the last tag, see section.
+///
+/// # Returns
+///
+/// * The new text of CHANGELOG.md and how many commits it lists.
+///
+pub fn update(
+    root: &Path,
+    version: &str,
+    conventional: bool,
+) -> Result<(String, usize), CedarError> {
+    let path = root.join("CHANGELOG.md");
+    let changelog = match path.exists() {
+        true => fs::read_to_string(&path).map_err(|err| CedarError::io(&path, err))?,
+        false => String::new(),
+    };
+
+    let commits = commits(root, last_tag(root).as_deref())?;
+    let section = section(version, &commits, conventional);
+
+    Ok((insert(&changelog, version, &section), commits.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_conventional_commits() {
+        let commits = [
+            (String::from("a1"), String::from("feat(cli): add --quiet")),
+            (
+                String::from("b2"),
+                String::from("fix: crash on empty input"),
+            ),
+            (
+                String::from("c3"),
+                String::from("refactor!: drop the v1 API"),
+            ),
+            (String::from("d4"), String::from("Update the README")),
+        ];
+
+        let section = section("1.2.0", &commits, true);
+        let body = section.split_once("\n\n").unwrap().1;
+
+        assert_eq!(
+            body,
+            "### Breaking changes\n\n- drop the v1 API (c3)\n\n### Features\n\n- **cli:** add --quiet (a1)\n\n### Bug fixes\n\n- crash on empty input (b2)\n\n### Other changes\n\n- Update the README (d4)\n\n"
+        );
+    }
+
+    #[test]
+    fn inserts_sections() {
+        let changelog = "# Changelog\n\nNotes.\n\n## [1.0.0] - 2024-01-01\n\n- first\n";
+
+        let added = insert(
+            changelog,
+            "1.1.0",
+            "## [1.1.0] - 2024-02-01\n\n- second\n\n",
+        );
+        assert_eq!(
+            added,
+            "# Changelog\n\nNotes.\n\n## [1.1.0] - 2024-02-01\n\n- second\n\n## [1.0.0] - 2024-01-01\n\n- first\n"
+        );
+
+        let replaced = insert(&added, "1.1.0", "## [1.1.0] - 2024-02-02\n\n- third\n\n");
+        assert!(replaced.contains("- third") && !replaced.contains("- second"));
+        assert!(has_section(&replaced, "1.0.0"));
+    }
+}
@@ -0,0 +1,475 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde::Deserialize;
+
+use crate::error::CedarError;
+
+use super::{
+    amalgamate::{files_with_extensions, local_include, resolve},
+    build::{BuildError, BuildOptions},
+    cache::parallel,
+    intercept::CompileCommand,
+    project::{Project, ProjectError},
+    target::TargetFilter,
+};
+
+/// An issue found by gcc's static analyzer.
+///
+/// # Fields
+///
+/// * 'option' - The warning option of the kind of issue, e.g.
+///   -Wanalyzer-double-free.
+/// * 'cwe' - The CWE the issue is an instance of, when gcc knows it.
+/// * 'location' - Where it happens, file:line:column from the root.
+/// * 'message' - What happens.
+/// * 'path' - The events leading up to it, each where it happens and what
+///   happens there.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub option: String,
+    pub cwe: Option<u32>,
+    pub location: String,
+    pub message: String,
+    pub path: Vec<(String, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonDiagnostic {
+    message: String,
+    #[serde(default)]
+    option: Option<String>,
+    #[serde(default)]
+    locations: Vec<JsonLocation>,
+    #[serde(default)]
+    path: Vec<JsonEvent>,
+    #[serde(default)]
+    metadata: Option<JsonMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonLocation {
+    caret: JsonPosition,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonPosition {
+    file: String,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonEvent {
+    location: JsonPosition,
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonMetadata {
+    #[serde(default)]
+    cwe: Option<u32>,
+}
+
+impl JsonPosition {
+    /// file:line:column, the file from the root when it is in the project.
+    fn display(&self, root: &Path) -> String {
+        let file = Path::new(&self.file);
+
+        format!(
+            "{}:{}:{}",
+            file.strip_prefix(root).unwrap_or(file).display(),
+            self.line,
+            self.column
+        )
+    }
+}
+
+/// Runs gcc's static analyzer, -fanalyzer, over every translation unit of
+/// the project, tests and examples included, in a pass of its own after a
+/// build. Each source is compiled again exactly as the build compiles it,
+/// with the analyzer on and the object thrown away.
+///
+/// The analyzer follows the paths through each translation unit for leaks,
+/// double frees, use after free, null dereferences, and the like, with no
+/// other tool than gcc 10 or later.
+///
+/// # Returns
+///
+/// * The issues found, once each even when a header has them in several
+///   translation units.
+///
+pub fn analyze(project: &Project) -> Result<Vec<Finding>, CedarError> {
+    let commands = compiles(project)?;
+
+    let outputs = parallel(&commands, |command| {
+        let (program, args) = command.arguments.split_first()?;
+
+        process::Command::new(program)
+            .args(analyzer_args(args))
+            .current_dir(&command.directory)
+            .output()
+            .ok()
+    });
+
+    let root = project.root.canonicalize()?;
+    let mut found: Vec<Finding> = Vec::new();
+
+    for (command, output) in commands.iter().zip(outputs) {
+        let output = output.ok_or(ProjectError::MissingTool("gcc"))?;
+        let text = String::from_utf8_lossy(&output.stderr);
+        let issues = findings(&text, &root);
+
+        // The build compiled the same source, so a failure without findings
+        // is most likely a gcc without -fanalyzer.
+        if !output.status.success() && issues.is_empty() {
+            return Err(BuildError::CompilationFailed(
+                format!("{} with -fanalyzer", command.file.display()),
+                text.into_owned(),
+            )
+            .into());
+        }
+
+        for finding in issues {
+            if !found.contains(&finding) {
+                found.push(finding);
+            }
+        }
+    }
+
+    found.sort_by(|a, b| (&a.option, &a.location).cmp(&(&b.option, &b.location)));
+
+    Ok(found)
+}
+
+/// The include cycles among the headers of the project, in include/ and
+/// src/, following the quoted includes the way the compiler resolves them.
+/// A header in a cycle only compiles when its include guard happens to cut
+/// the cycle at the right place, which breaks with the order of includes.
+///
+/// # Returns
+///
+/// * Every cycle as the headers along it, the first one again last, e.g.
+///   a.h, b.h, a.h.
+///
+pub fn include_cycles(project: &Project) -> Result<Vec<Vec<PathBuf>>, ProjectError> {
+    let roots = [project.include_dir.as_path(), project.src_dir.as_path()];
+    let symlinks = project.manifest.build.symlinks;
+
+    let mut headers = Vec::new();
+    for root in roots {
+        headers.extend(files_with_extensions(
+            root,
+            &["h", "hh", "hpp", "hxx"],
+            symlinks,
+        )?);
+    }
+
+    let mut includes: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+
+    for header in &headers {
+        let contents = fs::read_to_string(header)?;
+        let header = header.canonicalize()?;
+
+        let included = contents
+            .lines()
+            .filter_map(|line| resolve(&header, local_include(line)?, &roots))
+            .filter_map(|included| included.canonicalize().ok())
+            .collect();
+
+        includes.insert(header, included);
+    }
+
+    Ok(cycles(&includes))
+}
+
+/// A cycle of includes as a.h -> b.h -> a.h, each header from the root.
+pub fn cycle_path(cycle: &[PathBuf], root: &Path) -> String {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+    cycle
+        .iter()
+        .map(|header| {
+            header
+                .strip_prefix(&root)
+                .unwrap_or(header)
+                .display()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// The elementary cycles of a graph, by Johnson's algorithm. Each is given
+/// once, starting at its least node.
+fn cycles(edges: &BTreeMap<PathBuf, Vec<PathBuf>>) -> Vec<Vec<PathBuf>> {
+    // The cycles through each node in turn among it and the nodes after it.
+    // A node that did not lead back to the start stays blocked until one
+    // it leads to does.
+    fn circuit<'a>(
+        node: &'a PathBuf,
+        start: &'a PathBuf,
+        edges: &'a BTreeMap<PathBuf, Vec<PathBuf>>,
+        stack: &mut Vec<&'a PathBuf>,
+        blocked: &mut BTreeSet<&'a PathBuf>,
+        blocks: &mut BTreeMap<&'a PathBuf, BTreeSet<&'a PathBuf>>,
+        found: &mut BTreeSet<Vec<PathBuf>>,
+    ) -> bool {
+        let mut closed = false;
+        let nexts = || {
+            edges
+                .get(node)
+                .into_iter()
+                .flatten()
+                .filter(|next| *next >= start)
+        };
+
+        stack.push(node);
+        blocked.insert(node);
+
+        for next in nexts() {
+            if next == start {
+                let mut cycle: Vec<PathBuf> = stack.iter().map(|&node| node.clone()).collect();
+                cycle.push(start.clone());
+                found.insert(cycle);
+                closed = true;
+            } else if !blocked.contains(next)
+                && circuit(next, start, edges, stack, blocked, blocks, found)
+            {
+                closed = true;
+            }
+        }
+
+        if closed {
+            unblock(node, blocked, blocks);
+        } else {
+            for next in nexts() {
+                blocks.entry(next).or_default().insert(node);
+            }
+        }
+
+        stack.pop();
+        closed
+    }
+
+    fn unblock<'a>(
+        node: &'a PathBuf,
+        blocked: &mut BTreeSet<&'a PathBuf>,
+        blocks: &mut BTreeMap<&'a PathBuf, BTreeSet<&'a PathBuf>>,
+    ) {
+        blocked.remove(node);
+
+        for other in blocks.remove(node).unwrap_or_default() {
+            if blocked.contains(other) {
+                unblock(other, blocked, blocks);
+            }
+        }
+    }
+
+    let mut found = BTreeSet::new();
+
+    for start in edges.keys() {
+        circuit(
+            start,
+            start,
+            edges,
+            &mut Vec::new(),
+            &mut BTreeSet::new(),
+            &mut BTreeMap::new(),
+            &mut found,
+        );
+    }
+
+    found.into_iter().collect()
+}
+
+/// The compiles of the translation units of the project, tests and
+/// examples included, as its build runs them. The build writes them down.
+pub fn compiles(project: &Project) -> Result<Vec<CompileCommand>, CedarError> {
+    project.build(&BuildOptions {
+        quiet: true,
+        targets: vec![TargetFilter::All],
+        compile_commands: true,
+        ..Default::default()
+    })?;
+
+    let database = project.out_dir().join("compile_commands.json");
+    let text = fs::read_to_string(&database).map_err(|err| CedarError::io(&database, err))?;
+    let commands: Vec<CompileCommand> = serde_json::from_str(&text)
+        .map_err(|err| CedarError::io(&database, io::Error::from(err)))?;
+
+    // Dependencies are not the project's to fix.
+    Ok(commands
+        .into_iter()
+        .filter(|command| !command.file.starts_with(&project.build_dir))
+        .filter(|command| command.file.starts_with(&project.root))
+        .collect())
+}
+
+/// The arguments of a compile without its depfile, and with its object
+/// thrown away, to run it again for its diagnostics alone.
+pub fn without_outputs(arguments: &[String]) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arguments = arguments.iter();
+
+    while let Some(arg) = arguments.next() {
+        match arg.as_str() {
+            "-MMD" => {}
+            "-MF" | "-o" => {
+                arguments.next();
+            }
+            _ => args.push(arg.clone()),
+        }
+    }
+
+    let null = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    args.extend(["-o", null].map(String::from));
+    args
+}
+
+/// The arguments of a compile run again with the analyzer on, writing its
+/// diagnostics as JSON.
+fn analyzer_args(arguments: &[String]) -> Vec<String> {
+    let mut args = without_outputs(arguments);
+    args.extend(["-fanalyzer", "-fdiagnostics-format=json"].map(String::from));
+    args
+}
+
+/// The issues of the analyzer among the JSON diagnostics of a compile, the
+/// other warnings are the build's.
+fn findings(output: &str, root: &Path) -> Vec<Finding> {
+    output
+        .lines()
+        .filter(|line| line.starts_with('['))
+        .filter_map(|line| serde_json::from_str::<Vec<JsonDiagnostic>>(line).ok())
+        .flatten()
+        .filter_map(|diagnostic| {
+            let option = diagnostic
+                .option
+                .filter(|option| option.starts_with("-Wanalyzer"))?;
+
+            Some(Finding {
+                option,
+                cwe: diagnostic.metadata.and_then(|metadata| metadata.cwe),
+                location: diagnostic.locations.first()?.caret.display(root),
+                message: diagnostic.message,
+                path: diagnostic
+                    .path
+                    .iter()
+                    .map(|event| (event.location.display(root), event.description.clone()))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_include_cycles() {
+        let found = |edges: &[(&str, Vec<&str>)]| -> Vec<Vec<String>> {
+            let edges: BTreeMap<PathBuf, Vec<PathBuf>> = edges
+                .iter()
+                .map(|(node, next)| {
+                    (
+                        PathBuf::from(node),
+                        next.iter().map(PathBuf::from).collect(),
+                    )
+                })
+                .collect();
+
+            cycles(&edges)
+                .iter()
+                .map(|cycle| {
+                    cycle
+                        .iter()
+                        .map(|node| node.display().to_string())
+                        .collect()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            found(&[
+                ("a.h", vec!["b.h"]),
+                ("b.h", vec!["c.h", "d.h"]),
+                ("c.h", vec!["a.h"]),
+                ("d.h", vec!["d.h"]),
+                ("e.h", vec!["a.h"]),
+            ]),
+            [vec!["a.h", "b.h", "c.h", "a.h"], vec!["d.h", "d.h"]]
+        );
+
+        // Cycles sharing their headers are each given.
+        assert_eq!(
+            found(&[
+                ("a.h", vec!["b.h", "c.h"]),
+                ("b.h", vec!["a.h"]),
+                ("c.h", vec!["b.h"]),
+            ]),
+            [vec!["a.h", "b.h", "a.h"], vec!["a.h", "c.h", "b.h", "a.h"]]
+        );
+        assert_eq!(
+            found(&[
+                ("a.h", vec!["b.h"]),
+                ("b.h", vec!["a.h", "c.h"]),
+                ("c.h", vec!["b.h", "a.h"]),
+            ]),
+            [
+                vec!["a.h", "b.h", "a.h"],
+                vec!["a.h", "b.h", "c.h", "a.h"],
+                vec!["b.h", "c.h", "b.h"],
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_analyzer_findings() {
+        let args = analyzer_args(
+            &[
+                "-c",
+                "/p/src/a.c",
+                "-o",
+                "a.c.o",
+                "-Wall",
+                "-MMD",
+                "-MF",
+                "a.c.d",
+            ]
+            .map(String::from),
+        );
+        assert_eq!(args[..5], ["-c", "/p/src/a.c", "-Wall", "-o", "/dev/null"]);
+        assert_eq!(args[5..], ["-fanalyzer", "-fdiagnostics-format=json"]);
+
+        let output = concat!(
+            "[{\"kind\": \"warning\", \"message\": \"double-'free' of 'p'\", ",
+            "\"option\": \"-Wanalyzer-double-free\", \"metadata\": {\"cwe\": 415}, ",
+            "\"locations\": [{\"caret\": {\"file\": \"/p/src/a.c\", \"line\": 6, \"column\": 3}}], ",
+            "\"path\": [{\"location\": {\"file\": \"/p/src/a.c\", \"line\": 5, \"column\": 5}, ",
+            "\"description\": \"first 'free' here\"}]}, ",
+            "{\"kind\": \"warning\", \"message\": \"unused variable 'x'\", ",
+            "\"option\": \"-Wunused-variable\", \"locations\": []}]\n"
+        );
+
+        assert_eq!(
+            findings(output, Path::new("/p")),
+            [Finding {
+                option: String::from("-Wanalyzer-double-free"),
+                cwe: Some(415),
+                location: String::from("src/a.c:6:3"),
+                message: String::from("double-'free' of 'p'"),
+                path: vec![(
+                    String::from("src/a.c:5:5"),
+                    String::from("first 'free' here")
+                )],
+            }]
+        );
+    }
+}
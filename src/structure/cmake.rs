@@ -0,0 +1,198 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    build::BuildError,
+    deps::Package,
+    manifest::{Manifest, Profile, ProjectKind},
+    project::{Project, ProjectError},
+    sysdeps::{self, SystemLibrary},
+};
+
+/// The prefix a CMake dependency is installed into,
+/// build/deps/cmake/<name>/prefix/.
+pub fn prefix(build_path: &Path, name: &str) -> PathBuf {
+    build_path
+        .join("deps")
+        .join("cmake")
+        .join(name)
+        .join("prefix")
+}
+
+/// Loads a dependency built with CMake as a project without sources of its
+/// own, its headers being the ones it installs. It is versioned by the
+/// project() call of its CMakeLists.txt.
+///
+/// # Arguments
+///
+/// * 'root' - The directory holding the CMakeLists.txt.
+/// * 'name' - The name of the dependency.
+/// * 'prefix' - The prefix it is installed into, see prefix().
+///
+pub fn project(root: &Path, name: &str, prefix: &Path) -> Result<Project, ProjectError> {
+    let cmakelists = root.join("CMakeLists.txt");
+
+    if !cmakelists.is_file() {
+        return Err(ProjectError::InvalidDependency(format!(
+            "Dependency {} has build = \"cmake\" but no CMakeLists.txt in {:?}.",
+            name, root
+        )));
+    }
+
+    let mut manifest = Manifest::new();
+    manifest.meta.name = name.to_owned();
+    manifest.meta.version =
+        project_version(&fs::read_to_string(&cmakelists)?).unwrap_or_else(|| String::from("0.0.0"));
+    manifest.meta.kind = ProjectKind::StaticLib;
+    manifest.build.cflags.clear();
+
+    Ok(Project {
+        root: root.to_path_buf(),
+        manifest,
+        src_dir: root.join("src"),
+        include_dir: prefix.join("include"),
+        build_dir: root.join("build"),
+        profile: String::from(Profile::DEFAULT),
+        targets: Vec::new(),
+        library_sources: Vec::new(),
+        features: Vec::new(),
+    })
+}
+
+/// Configures, builds, and installs a CMake dependency in
+/// build/deps/cmake/<name>/, as static, position independent libraries
+/// built with the compiler of the project. CMake is configured again only
+/// when its arguments change, and builds incrementally after that.
+///
+/// # Arguments
+///
+/// * 'package' - The dependency, built with CMake.
+/// * 'build_path' - The build directory of the project.
+/// * 'cc' - The C compiler to build with.
+/// * 'quiet' - Suppresses progress output.
+///
+/// # Returns
+///
+/// * The libraries it installed.
+///
+pub fn build(
+    package: &Package,
+    build_path: &Path,
+    cc: &str,
+    quiet: bool,
+) -> Result<SystemLibrary, CedarError> {
+    let prefix = prefix(build_path, &package.name);
+    let dir = prefix.parent().expect("the prefix is in a directory");
+    let binary = dir.join("build");
+
+    let mut configure = process::Command::new("cmake");
+    configure
+        .arg("-S")
+        .arg(&package.project.root)
+        .arg("-B")
+        .arg(&binary)
+        .args(
+            sysdeps::on_path("ninja")
+                .then_some(["-G", "Ninja"])
+                .into_iter()
+                .flatten(),
+        )
+        .arg("-DCMAKE_BUILD_TYPE=Release")
+        .arg(format!("-DCMAKE_INSTALL_PREFIX={}", prefix.display()))
+        .arg("-DCMAKE_INSTALL_LIBDIR=lib")
+        .arg("-DBUILD_SHARED_LIBS=OFF")
+        .arg("-DCMAKE_POSITION_INDEPENDENT_CODE=ON")
+        .arg(format!("-DCMAKE_C_COMPILER={}", cc))
+        .args(package.cmake.iter().flatten());
+
+    // The configure command is kept to notice changed arguments.
+    let configured_path = dir.join("configure.cmd");
+    let configured = format!("{:?}", configure);
+
+    if !binary.join("CMakeCache.txt").is_file()
+        || fs::read_to_string(&configured_path).ok().as_ref() != Some(&configured)
+    {
+        if !quiet {
+            println!(
+                "\t{}{} v{} (cmake)",
+                style::header("Configuring "),
+                package.name,
+                package.version
+            );
+        }
+
+        fs::create_dir_all(&binary).map_err(|err| CedarError::io(&binary, err))?;
+        run(&mut configure, &package.name)?;
+        fs::write(&configured_path, configured)
+            .map_err(|err| CedarError::io(&configured_path, err))?;
+    }
+
+    run(
+        process::Command::new("cmake")
+            .arg("--build")
+            .arg(&binary)
+            .arg("--parallel"),
+        &package.name,
+    )?;
+    run(
+        process::Command::new("cmake").arg("--install").arg(&binary),
+        &package.name,
+    )?;
+
+    sysdeps::prefix_library(&package.name, &prefix).map_err(|err| CedarError::io(&prefix, err))
+}
+
+/// Runs a step of a CMake build, failing with its output.
+fn run(command: &mut process::Command, name: &str) -> Result<(), CedarError> {
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(ProjectError::MissingTool("cmake").into())
+        }
+        Err(err) => return Err(CedarError::command(command, err)),
+    };
+
+    if !output.status.success() {
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+
+        return Err(
+            BuildError::CompilationFailed(format!("dependency {} with cmake", name), text).into(),
+        );
+    }
+
+    Ok(())
+}
+
+/// The VERSION of the project() call in a CMakeLists.txt, if it has one.
+fn project_version(cmakelists: &str) -> Option<String> {
+    let lower = cmakelists.to_ascii_lowercase();
+    let start = lower.find("project(").or_else(|| lower.find("project ("))?;
+    let call = &cmakelists[start..];
+    let call = &call[call.find('(')? + 1..call.find(')')?];
+
+    let mut words = call.split_whitespace();
+    words.find(|word| *word == "VERSION")?;
+    words
+        .next()
+        .map(|version| version.trim_matches('"').to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_project_versions() {
+        assert_eq!(
+            project_version("cmake_minimum_required(VERSION 3.10)\nproject(zlib\n  VERSION 1.3.1\n  LANGUAGES C)\n"),
+            Some(String::from("1.3.1"))
+        );
+        assert_eq!(project_version("project(foo C)"), None);
+    }
+}
@@ -0,0 +1,237 @@
+use std::{collections::BTreeMap, fs, io, path::Path, process};
+
+use serde_json::Value;
+
+use crate::{error::CedarError, style};
+
+use super::{
+    deps,
+    project::ProjectError,
+    sysdeps::{Installed, SystemLibrary},
+};
+
+/// Installs the Conan packages in [dependencies.conan], building any that
+/// have no prebuilt binary, and reads how to compile and link with them
+/// from the dependency graph conan reports. conan only runs again once the
+/// requirements change.
+///
+/// # Arguments
+///
+/// * 'requires' - The packages by name and version.
+/// * 'build_path' - The build directory, conan works in build/conan/.
+/// * 'quiet' - Suppresses the progress output of conan.
+///
+/// # Returns
+///
+/// * Every package in the graph, transitive requirements included, with
+///   the flags to compile and link with it.
+///
+pub fn install(
+    requires: &BTreeMap<String, String>,
+    build_path: &Path,
+    quiet: bool,
+) -> Result<Vec<SystemLibrary>, CedarError> {
+    if requires.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conan_path = build_path.join("conan");
+    let installed_path = conan_path.join("libraries.json");
+
+    let conanfile = requires.iter().fold(
+        String::from("[requires]\n"),
+        |conanfile, (name, version)| conanfile + &format!("{}/{}\n", name, version),
+    );
+
+    if let Some(libraries) = Installed::load(&installed_path, &conanfile) {
+        return Ok(libraries);
+    }
+
+    fs::create_dir_all(&conan_path).map_err(|err| CedarError::io(&conan_path, err))?;
+
+    let conanfile_path = conan_path.join("conanfile.txt");
+    fs::write(&conanfile_path, &conanfile).map_err(|err| CedarError::io(&conanfile_path, err))?;
+
+    if !quiet {
+        println!(
+            "\t{}{}",
+            style::header("Installing "),
+            requires
+                .iter()
+                .map(|(name, version)| format!("{}/{}", name, version))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let mut command = process::Command::new("conan");
+    command
+        .arg("install")
+        .arg(".")
+        .arg("--build=missing")
+        .arg("--format=json")
+        .args(deps::is_offline().then_some("--no-remote"))
+        .current_dir(&conan_path)
+        .stdout(process::Stdio::piped());
+
+    if quiet {
+        command.stderr(process::Stdio::null());
+    }
+
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(ProjectError::MissingTool("conan").into())
+        }
+        Err(err) => return Err(CedarError::command(&command, err)),
+    };
+
+    if !output.status.success() {
+        return Err(ProjectError::ToolFailed("conan").into());
+    }
+
+    let graph: Value = serde_json::from_slice(&output.stdout).map_err(|_| {
+        ProjectError::InvalidDependency(String::from(
+            "conan install printed no dependency graph, cedar needs Conan 2.",
+        ))
+    })?;
+
+    let installed = Installed {
+        key: conanfile,
+        libraries: libraries(&graph),
+    };
+    installed.save(&installed_path)?;
+
+    Ok(installed.libraries)
+}
+
+/// The packages in the graph conan install --format=json prints, build
+/// tools left out.
+fn libraries(graph: &Value) -> Vec<SystemLibrary> {
+    // Nodes are numbered dependents first, which is the order static
+    // libraries link in.
+    let mut nodes: Vec<(u64, &Value)> = graph["graph"]["nodes"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(id, node)| (id.parse().unwrap_or(u64::MAX), node))
+        .collect();
+    nodes.sort_by_key(|(id, _)| *id);
+
+    let strings = |value: &Value| -> Vec<String> {
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_str().map(str::to_owned))
+            .collect()
+    };
+
+    let mut libraries = Vec::new();
+
+    for (_, node) in nodes {
+        if node["context"] == "build" {
+            continue;
+        }
+
+        // References look like zlib/1.3.1#<revision>, the conanfile itself
+        // has none.
+        let reference = node["ref"].as_str().unwrap_or_default();
+        let (name, version) = match reference.split('#').next().and_then(|r| r.split_once('/')) {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        let mut library = SystemLibrary {
+            name: name.to_owned(),
+            version: Some(version.split('@').next().unwrap_or(version).to_owned()),
+            cflags: Vec::new(),
+            libs: Vec::new(),
+        };
+
+        for component in node["cpp_info"]
+            .as_object()
+            .into_iter()
+            .flat_map(|info| info.values())
+        {
+            let cflags = &mut library.cflags;
+            cflags.extend(
+                strings(&component["includedirs"])
+                    .iter()
+                    .map(|dir| format!("-I{}", dir)),
+            );
+            cflags.extend(
+                strings(&component["defines"])
+                    .iter()
+                    .map(|define| format!("-D{}", define)),
+            );
+            cflags.extend(strings(&component["cflags"]));
+
+            let libs = &mut library.libs;
+            for dir in strings(&component["libdirs"]) {
+                libs.push(format!("-L{}", dir));
+                libs.push(format!("-Wl,-rpath,{}", dir));
+            }
+            libs.extend(
+                strings(&component["libs"])
+                    .iter()
+                    .map(|lib| format!("-l{}", lib)),
+            );
+            libs.extend(
+                strings(&component["system_libs"])
+                    .iter()
+                    .map(|lib| format!("-l{}", lib)),
+            );
+            libs.extend(strings(&component["exelinkflags"]));
+        }
+
+        libraries.push(library);
+    }
+
+    libraries
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reads_the_dependency_graph() {
+        let graph = json!({
+            "graph": { "nodes": {
+                "0": { "ref": "conanfile", "context": "host", "cpp_info": {} },
+                "1": {
+                    "ref": "zlib/1.3.1#f52e03ae3d251dec704634230cd806a2",
+                    "context": "host",
+                    "cpp_info": { "root": {
+                        "includedirs": ["/conan/zlib/include"],
+                        "libdirs": ["/conan/zlib/lib"],
+                        "libs": ["z"],
+                        "system_libs": [],
+                        "defines": ["ZLIB_CONST"],
+                    } },
+                },
+                "2": { "ref": "cmake/3.28.1#1", "context": "build", "cpp_info": {} },
+            } },
+        });
+
+        assert_eq!(
+            libraries(&graph),
+            [SystemLibrary {
+                name: String::from("zlib"),
+                version: Some(String::from("1.3.1")),
+                cflags: vec![
+                    String::from("-I/conan/zlib/include"),
+                    String::from("-DZLIB_CONST")
+                ],
+                libs: vec![
+                    String::from("-L/conan/zlib/lib"),
+                    String::from("-Wl,-rpath,/conan/zlib/lib"),
+                    String::from("-lz")
+                ],
+            }]
+        );
+    }
+}
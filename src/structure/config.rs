@@ -0,0 +1,326 @@
+use std::{collections::BTreeMap, env, fs, path::PathBuf, process};
+
+use serde::{Deserialize, Serialize};
+
+use super::project::ProjectError;
+
+/// The user wide configuration, read from `$XDG_CONFIG_HOME/cedar/config.toml`
+/// (falling back to `~/.config/cedar/config.toml`). Every field is optional,
+/// a missing file is the same as an empty one.
+///
+/// # Fields
+///
+/// * 'new' - Defaults used when creating projects with new or init.
+/// * 'offline' - Forbids network access as if every command was given
+///   --offline.
+/// * 'net' - How cedar reaches the network.
+/// * 'remote' - The machines builds can run on with build --remote, by name.
+/// * 'dist' - The machines compiles are distributed to.
+/// * 'cache' - Where compiled objects are cached.
+/// * 'sign' - How packages are signed.
+/// * 'notify' - Sends a desktop notification when a build or test run
+///   finishes, with whether it succeeded and how long it took.
+/// * 'alias' - Commands of their own, by name, expanding to a command line
+///   of cedar. [alias] of a project's cedar.toml takes precedence.
+///
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub new: NewConfig,
+    pub offline: bool,
+    pub net: NetConfig,
+    pub remote: BTreeMap<String, RemoteConfig>,
+    pub dist: DistConfig,
+    pub cache: CacheConfig,
+    pub sign: SignConfig,
+    pub notify: bool,
+    pub alias: BTreeMap<String, Alias>,
+}
+
+/// A command alias, an entry of [alias]:
+///
+/// ```toml
+/// [alias]
+/// b = "build --release --timings"
+/// t = ["test", "--skip", "slow test"]
+/// ```
+///
+/// # Members
+///
+/// * 'Line' - The arguments separated by whitespace.
+/// * 'Args' - The arguments, for ones containing whitespace.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Alias {
+    Line(String),
+    Args(Vec<String>),
+}
+
+impl Alias {
+    /// The arguments the alias expands to.
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            Self::Line(line) => line.split_whitespace().map(String::from).collect(),
+            Self::Args(args) => args.clone(),
+        }
+    }
+}
+
+/// Defaults for newly created projects.
+///
+/// # Fields
+///
+/// * 'license' - The SPDX identifier of the license to scaffold.
+/// * 'author' - The copyright holder written into the license.
+///
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct NewConfig {
+    pub license: Option<String>,
+    pub author: Option<String>,
+}
+
+/// Network settings for fetching dependencies, for networks that only allow
+/// traffic through a proxy or to internal mirrors:
+///
+/// ```toml
+/// [net]
+/// proxy = "socks5://proxy.example.com:1080"
+/// no_proxy = "localhost,.example.com"
+///
+/// [net.mirrors]
+/// "https://github.com/" = "https://git.example.com/github/"
+/// ```
+///
+/// # Fields
+///
+/// * 'proxy' - The proxy for every connection, http://, https://, or
+///   socks5:// (the standard proxy environment variables apply when
+///   it is not set).
+/// * 'no_proxy' - Comma separated hosts and domains reached directly.
+/// * 'mirrors' - URL prefixes replaced by a mirror, the longest matching
+///   prefix wins. cedar.lock keeps the original URLs.
+///
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct NetConfig {
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub mirrors: BTreeMap<String, String>,
+}
+
+/// A machine to build on over ssh:
+///
+/// ```toml
+/// [remote.box]
+/// host = "me@build.example.com"
+/// path = "~/builds/app"
+/// exclude = ["*.mp4", "/data/"]
+/// ```
+///
+/// # Fields
+///
+/// * 'host' - The host to ssh to, anything ssh accepts (including hosts of
+///   ~/.ssh/config).
+/// * 'path' - The directory of the copy of the project on the host.
+/// * 'exclude' - rsync patterns of files not sent to the host, the build
+///   directory never is.
+/// * 'cedar' - The cedar to run on the host, cedar on its PATH by default.
+///
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RemoteConfig {
+    pub host: String,
+    pub path: String,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    pub cedar: Option<String>,
+}
+
+/// Machines that compile translation units of every build, reached over ssh:
+///
+/// ```toml
+/// [dist]
+/// jobs = 4
+///
+/// [[dist.workers]]
+/// host = "me@box1"
+/// jobs = 16
+/// ```
+///
+/// # Fields
+///
+/// * 'workers' - The machines to compile on, builds stay local without any.
+/// * 'jobs' - How many compiles run on this machine at once, as many as it
+///   has cores by default.
+///
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct DistConfig {
+    pub workers: Vec<WorkerConfig>,
+    pub jobs: Option<usize>,
+}
+
+/// A machine compiles are distributed to, it only needs the compilers (the
+/// sources are preprocessed before they are sent).
+///
+/// # Fields
+///
+/// * 'host' - The host to ssh to.
+/// * 'jobs' - How many compiles it runs at once.
+///
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WorkerConfig {
+    pub host: String,
+    #[serde(default = "WorkerConfig::default_jobs")]
+    pub jobs: usize,
+}
+
+impl WorkerConfig {
+    fn default_jobs() -> usize {
+        4
+    }
+}
+
+/// The cache of compiled objects, shared by every project (and, with a
+/// remote, every machine) compiling the same preprocessed sources with the
+/// same compiler and flags:
+///
+/// ```toml
+/// [cache]
+/// url = "https://cache.example.com/cedar/"
+/// read_only = true
+/// ```
+///
+/// # Fields
+///
+/// * 'enabled' - Caches objects in the data directory of cedar, implied by
+///   url.
+/// * 'url' - A remote cache objects are fetched from with GET and stored to
+///   with PUT, e.g. a plain HTTP server or an S3 bucket.
+/// * 's3_region' - Signs the requests for S3 (or a compatible store) in the
+///   region, with the credentials of AWS_ACCESS_KEY_ID and
+///   AWS_SECRET_ACCESS_KEY.
+/// * 'read_only' - Only fetches from the remote cache, e.g. on machines that
+///   must not publish their objects.
+///
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub url: Option<String>,
+    pub s3_region: Option<String>,
+    pub read_only: bool,
+}
+
+/// The key cedar package --sign signs with:
+///
+/// ```toml
+/// [sign]
+/// key = "~/.ssh/id_ed25519"
+/// ```
+///
+/// # Fields
+///
+/// * 'key' - An OpenSSH private key (signed with ssh-keygen -Y) or a
+///   minisign secret key.
+///
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SignConfig {
+    pub key: Option<PathBuf>,
+}
+
+impl NetConfig {
+    /// The URL to fetch from, the mirror of the URL if one is configured.
+    pub fn rewrite(&self, url: &str) -> String {
+        self.mirrors
+            .iter()
+            .filter(|(prefix, _)| url.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or_else(
+                || url.to_owned(),
+                |(prefix, mirror)| format!("{}{}", mirror, &url[prefix.len()..]),
+            )
+    }
+    /// Sets up a command that reaches the network (git) to use the proxy.
+    pub fn apply(&self, command: &mut process::Command) {
+        if let Some(proxy) = &self.proxy {
+            for var in ["http_proxy", "https_proxy", "HTTPS_PROXY", "ALL_PROXY"] {
+                command.env(var, proxy);
+            }
+        }
+
+        if let Some(no_proxy) = &self.no_proxy {
+            command.env("no_proxy", no_proxy).env("NO_PROXY", no_proxy);
+        }
+    }
+}
+
+impl Config {
+    /// Returns the path of the global config file, if a home can be found.
+    pub fn path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+
+        Some(base.join("cedar").join("config.toml"))
+    }
+    /// Returns the directory cedar keeps tools it downloads in,
+    /// `$XDG_DATA_HOME/cedar` or `~/.local/share/cedar`.
+    pub fn data_dir() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?)
+                .join(".local")
+                .join("share"),
+        };
+
+        Some(base.join("cedar"))
+    }
+    /// Loads the global config, returning the default config if it does not
+    /// exist.
+    pub fn load() -> Result<Self, ProjectError> {
+        let path = match Self::path() {
+            Some(path) if path.is_file() => path,
+            _ => return Ok(Self::default()),
+        };
+
+        let config_str = fs::read_to_string(&path)?;
+
+        match toml::from_str(&config_str) {
+            Ok(config) => Ok(config),
+            Err(e) => Err(ProjectError::InvalidConfig(format!("{:?}: {}", path, e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_mirrored_urls() {
+        let net = NetConfig {
+            mirrors: BTreeMap::from([
+                ("https://github.com/".into(), "https://mirror/gh/".into()),
+                ("https://github.com/corp/".into(), "ssh://git.corp/".into()),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            net.rewrite("https://github.com/madler/zlib"),
+            "https://mirror/gh/madler/zlib"
+        );
+        assert_eq!(
+            net.rewrite("https://github.com/corp/util"),
+            "ssh://git.corp/util"
+        );
+        assert_eq!(
+            net.rewrite("https://gitlab.com/a/b"),
+            "https://gitlab.com/a/b"
+        );
+    }
+}
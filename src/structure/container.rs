@@ -0,0 +1,149 @@
+use std::{
+    env,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::error::CedarError;
+
+use super::{config::Config, project::ProjectError, sysdeps};
+
+/// The container engines builds can run with, the first one on the PATH is
+/// used.
+const ENGINES: &[&str] = &["docker", "podman"];
+
+/// Runs cedar with the given arguments inside a container of the image, so
+/// the build uses the toolchain of the image instead of the one of the host.
+///
+/// The project is mounted at its path on the host and the command runs as
+/// the owner of the project, so the build directory stays usable from both
+/// sides. The cedar binary itself is mounted into the container, which needs
+/// an image with glibc, as are the config and downloaded tools of cedar.
+///
+/// # Arguments
+///
+/// * 'root' - The root of the project.
+/// * 'image' - The image to run the build in.
+/// * 'args' - The arguments of cedar inside the container.
+///
+pub fn run(root: &Path, image: &str, args: &[String]) -> Result<(), CedarError> {
+    let engine = ENGINES
+        .iter()
+        .find(|engine| sysdeps::on_path(engine))
+        .ok_or(ProjectError::MissingTool("docker or podman"))?;
+
+    let cwd = env::current_dir()?;
+    let cedar = env::current_exe()?;
+
+    let mut command = process::Command::new(engine);
+    command.arg("run").arg("--rm").arg("--init");
+
+    if std::io::stdout().is_terminal() {
+        command.arg("-t");
+    }
+
+    command
+        .arg("-v")
+        .arg(mount(root, root, false))
+        .arg("-v")
+        .arg(mount(&cedar, Path::new("/usr/local/bin/cedar"), true))
+        .arg("-w")
+        .arg(&cwd)
+        .arg("-e")
+        .arg("HOME=/tmp");
+
+    // The config and tools of cedar are kept where cedar looks for them.
+    let config_dir = Config::path().and_then(|path| path.parent().map(Path::to_path_buf));
+    let shared: [(Option<PathBuf>, &str, bool); 2] = [
+        (config_dir, "XDG_CONFIG_HOME", true),
+        (Config::data_dir(), "XDG_DATA_HOME", false),
+    ];
+
+    for (dir, variable, read_only) in shared {
+        let Some(dir) = dir.filter(|dir| dir.is_dir()) else {
+            continue;
+        };
+
+        command.arg("-v").arg(mount(&dir, &dir, read_only));
+        if let Some(base) = dir.parent() {
+            command
+                .arg("-e")
+                .arg(format!("{}={}", variable, base.display()));
+        }
+    }
+
+    // Rootless podman maps the user of the host into the container itself.
+    match *engine {
+        "podman" => {
+            command.arg("--userns=keep-id");
+        }
+        _ => {
+            if let Some(user) = owner(root) {
+                command.arg("--user").arg(user);
+            }
+        }
+    }
+
+    command.arg(image).arg("cedar").args(args);
+
+    let status = command
+        .status()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed("The build in the container").into());
+    }
+
+    Ok(())
+}
+
+/// A bind mount of a path of the host to a path in the container.
+fn mount(host: &Path, container: &Path, read_only: bool) -> String {
+    format!(
+        "{}:{}{}",
+        host.display(),
+        container.display(),
+        if read_only { ":ro" } else { "" }
+    )
+}
+
+/// The user and group owning the project, as uid:gid.
+#[cfg(unix)]
+fn owner(root: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = root.metadata().ok()?;
+
+    Some(format!("{}:{}", metadata.uid(), metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn owner(_root: &Path) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_mounts() {
+        assert_eq!(
+            mount(
+                Path::new("/home/me/proj"),
+                Path::new("/home/me/proj"),
+                false
+            ),
+            "/home/me/proj:/home/me/proj"
+        );
+        assert_eq!(
+            mount(
+                Path::new("/opt/cedar"),
+                Path::new("/usr/local/bin/cedar"),
+                true
+            ),
+            "/opt/cedar:/usr/local/bin/cedar:ro"
+        );
+    }
+}
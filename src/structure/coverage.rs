@@ -0,0 +1,179 @@
+use std::{fs, path::PathBuf};
+
+use crate::error::CedarError;
+
+use super::{
+    build::{object_files, Language, Toolchain},
+    project::{Project, ProjectError},
+    test::{test, TestOptions, TestReport},
+};
+
+/// The line coverage of a source file.
+///
+/// # Fields
+///
+/// * 'path' - The source, relative to the project root.
+/// * 'lines' - How many executable lines it has.
+/// * 'covered' - How many of them the tests ran.
+///
+#[derive(Debug, Clone)]
+pub struct FileCoverage {
+    pub path: PathBuf,
+    pub lines: usize,
+    pub covered: usize,
+}
+
+/// The line coverage of the library sources after running the tests.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    /// The percentage of executable lines covered across every file, 100
+    /// when there are none.
+    pub fn percent(&self) -> f64 {
+        let lines: usize = self.files.iter().map(|file| file.lines).sum();
+        let covered: usize = self.files.iter().map(|file| file.covered).sum();
+
+        percent(covered, lines)
+    }
+}
+
+impl FileCoverage {
+    pub fn percent(&self) -> f64 {
+        percent(self.covered, self.lines)
+    }
+}
+
+fn percent(covered: usize, lines: usize) -> f64 {
+    match lines {
+        0 => 100.0,
+        _ => covered as f64 * 100.0 / lines as f64,
+    }
+}
+
+/// Builds the tests with coverage instrumentation into build/coverage/, runs
+/// them, and measures the line coverage of the library sources with gcov.
+///
+/// # Arguments
+///
+/// * 'project' - The project to measure.
+/// * 'options' - Which tests to run and how.
+///
+/// # Returns
+///
+/// * The coverage along with the results of the tests, failing tests do not
+///   make this an error.
+///
+pub fn coverage(
+    project: &Project,
+    options: &TestOptions,
+) -> Result<(CoverageReport, TestReport), CedarError> {
+    let toolchain = Toolchain::from_compiler(&project.manifest.build.compiler)?;
+
+    // Instrumented objects live apart from the regular ones so switching
+    // between cedar build and cedar cov does not rebuild everything.
+    let mut instrumented = project.clone();
+    instrumented.build_dir = project.build_dir.join("coverage");
+
+    let build = &mut instrumented.manifest.build;
+    for flags in [&mut build.cflags, &mut build.cxxflags] {
+        flags.extend(["--coverage", "-O0"].map(String::from));
+    }
+    build.ldflags.push(String::from("--coverage"));
+
+    let object_path = instrumented.out_dir().join("obj");
+
+    // Counters accumulate across runs, only the ones of this run count.
+    for (source, _) in &project.library_sources {
+        let relative = source.strip_prefix(&project.root).unwrap_or(source);
+        let (object, _) = object_files(&object_path, relative);
+        let counters = object.with_extension("gcda");
+
+        if counters.exists() {
+            fs::remove_file(&counters).map_err(|err| CedarError::io(&counters, err))?;
+        }
+    }
+
+    fs::create_dir_all(&instrumented.build_dir)
+        .map_err(|err| CedarError::io(&instrumented.build_dir, err))?;
+
+    let tests = test(&instrumented, options)?;
+    let mut report = CoverageReport::default();
+
+    for (source, lang) in &project.library_sources {
+        let relative = source.strip_prefix(&project.root).unwrap_or(source);
+        let (object, _) = object_files(&object_path, relative);
+
+        // Assembly has no line information for gcov, and sources no test
+        // links against were never compiled.
+        if !matches!(lang, Language::C | Language::Cxx) || !object.exists() {
+            continue;
+        }
+
+        let mut command = toolchain.gcov_command();
+        command
+            .arg("-n")
+            .arg("-o")
+            .arg(&object)
+            .arg(source)
+            .current_dir(&project.root);
+
+        let output = command
+            .output()
+            .map_err(|err| CedarError::command(&command, err))?;
+
+        if !output.status.success() {
+            return Err(ProjectError::ToolFailed("gcov").into());
+        }
+
+        let (lines, covered) = parse_gcov(&String::from_utf8_lossy(&output.stdout));
+
+        report.files.push(FileCoverage {
+            path: relative.to_path_buf(),
+            lines,
+            covered,
+        });
+    }
+
+    Ok((report, tests))
+}
+
+/// Parses the "Lines executed:85.00% of 20" summary gcov prints for the
+/// source itself, the first one, as headers it includes follow it.
+///
+/// # Returns
+///
+/// * How many lines there are and how many of them were executed.
+///
+fn parse_gcov(output: &str) -> (usize, usize) {
+    let Some(summary) = output
+        .lines()
+        .find_map(|line| line.strip_prefix("Lines executed:"))
+    else {
+        return (0, 0);
+    };
+
+    let Some((percent, lines)) = summary.split_once("% of ") else {
+        return (0, 0);
+    };
+
+    let percent: f64 = percent.trim().parse().unwrap_or(0.0);
+    let lines: usize = lines.trim().parse().unwrap_or(0);
+
+    (lines, (percent * lines as f64 / 100.0).round() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_gcov;
+
+    #[test]
+    fn parses_gcov_summary() {
+        let output = "File 'src/util.c'\nLines executed:75.00% of 8\nFile '/usr/include/stdio.h'\nLines executed:0.00% of 2\n";
+
+        assert_eq!(parse_gcov(output), (8, 6));
+        assert_eq!(parse_gcov("src/util.c:cannot open source file\n"), (0, 0));
+    }
+}
@@ -0,0 +1,1030 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    cmake,
+    config::NetConfig,
+    lockfile::{LockedPackage, Lockfile},
+    manifest::{add_dependency, reserved_dependency, Dependency, RESERVED_DEPENDENCIES},
+    project::{Project, ProjectError},
+    sha256,
+    version::{Version, VersionReq},
+};
+
+/// Whether cedar may not touch the network, set once by --offline or the
+/// global config.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Whether cedar.lock may not change, set by --locked.
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Forbids (or allows) changes to cedar.lock: resolving must then reproduce
+/// it exactly, and the sources of git dependencies are verified against
+/// their locked checksums even when nothing was fetched.
+pub fn set_locked(locked: bool) {
+    LOCKED.store(locked, Ordering::Relaxed);
+}
+
+/// Whether cedar.lock may not change.
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::Relaxed)
+}
+
+/// Forbids (or allows) network access when resolving dependencies: git
+/// dependencies must then already be checked out under build/deps/src/.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Whether network access is forbidden.
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// The proxy and mirrors from the global config.
+static NETWORK: OnceLock<NetConfig> = OnceLock::new();
+
+/// Sets the proxy and mirrors git uses to fetch dependencies, only the first
+/// call has an effect.
+pub fn set_network(net: NetConfig) {
+    let _ = NETWORK.set(net);
+}
+
+/// The proxy and mirror settings of the global config.
+pub(crate) fn network() -> &'static NetConfig {
+    NETWORK.get_or_init(NetConfig::default)
+}
+
+/// A resolved dependency, ready to build.
+///
+/// # Fields
+///
+/// * 'name' - The name of the dependency in [dependencies].
+/// * 'version' - The version from its manifest.
+/// * 'source' - Where it came from, as written to cedar.lock.
+/// * 'checksum' - The SHA-256 of the sources of git dependencies.
+/// * 'project' - The dependency itself, for git dependencies rooted in its
+///   checkout under build/deps/src/.
+/// * 'cmake' - The extra arguments to configure a dependency built with
+///   CMake with, None for dependencies cedar builds itself.
+///
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    pub checksum: Option<String>,
+    pub project: Project,
+    pub cmake: Option<Vec<String>>,
+}
+
+impl Package {
+    /// The entry of the package in cedar.lock.
+    pub fn locked(&self) -> LockedPackage {
+        LockedPackage {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            source: self.source.clone(),
+            checksum: self.checksum.clone(),
+        }
+    }
+}
+
+/// Resolves the dependencies of a project, and theirs, fetching git
+/// dependencies into build/deps/src/ and updating cedar.lock.
+///
+/// Git dependencies already in cedar.lock stay at their locked commit as
+/// long as their entry in the manifest is unchanged.
+///
+/// # Arguments
+///
+/// * 'project' - The project whose dependencies to resolve.
+/// * 'quiet' - Suppresses the progress output.
+///
+/// # Returns
+///
+/// * Every dependency, sorted by name.
+///
+pub fn resolve(project: &Project, quiet: bool) -> Result<Vec<Package>, CedarError> {
+    if project.manifest.dependencies.packages.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (packages, resolved) = resolve_locked(project, &Lockfile::load(project)?, quiet)?;
+    resolved.save(project)?;
+
+    Ok(packages)
+}
+
+/// A dependency whose locked entry changed in an update.
+///
+/// # Fields
+///
+/// * 'name' - The name of the dependency.
+/// * 'old' - Its previous entry, None if it was added.
+/// * 'new' - Its new entry, None if it was removed.
+///
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub name: String,
+    pub old: Option<LockedPackage>,
+    pub new: Option<LockedPackage>,
+}
+
+/// Re-resolves dependencies ignoring their locked commits, so git
+/// dependencies move to the newest commit their branch, or the newest tag
+/// their version requirement allows. cedar.lock is left alone, the caller
+/// saves the new lockfile once the updates are confirmed.
+///
+/// # Arguments
+///
+/// * 'project' - The project whose dependencies to update.
+/// * 'names' - The dependencies to update, every one when empty.
+/// * 'quiet' - Suppresses the progress output.
+///
+/// # Returns
+///
+/// * The entries of cedar.lock that changed, and the lockfile with them.
+///
+pub fn update(
+    project: &Project,
+    names: &[String],
+    quiet: bool,
+) -> Result<(Vec<Update>, Lockfile), CedarError> {
+    let old = Lockfile::load(project)?;
+
+    for name in names {
+        if old.find(name).is_none() && !project.manifest.dependencies.packages.contains_key(name) {
+            return Err(ProjectError::InvalidDependency(format!(
+                "There is no dependency named {:?}.",
+                name
+            ))
+            .into());
+        }
+    }
+
+    let kept = Lockfile {
+        packages: old
+            .packages
+            .iter()
+            .filter(|package| !names.is_empty() && !names.contains(&package.name))
+            .cloned()
+            .collect(),
+    };
+
+    let (new, resolved) = match project.manifest.dependencies.packages.is_empty() {
+        true => (Vec::new(), kept),
+        false => resolve_locked(project, &kept, quiet)?,
+    };
+
+    let mut updates: Vec<Update> = old
+        .packages
+        .iter()
+        .map(|old| Update {
+            name: old.name.clone(),
+            old: Some(old.clone()),
+            new: new
+                .iter()
+                .find(|package| package.name == old.name)
+                .map(Package::locked),
+        })
+        .chain(
+            new.iter()
+                .filter(|package| old.find(&package.name).is_none())
+                .map(|package| Update {
+                    name: package.name.clone(),
+                    old: None,
+                    new: Some(package.locked()),
+                }),
+        )
+        .filter(|update| update.old != update.new)
+        .collect();
+
+    updates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok((updates, resolved))
+}
+
+/// A git dependency with newer versions than the locked one.
+///
+/// # Fields
+///
+/// * 'name' - The name of the dependency.
+/// * 'current' - The locked version.
+/// * 'compatible' - The newest version the manifest allows, None when that
+///   is the locked one or the dependency is pinned to a rev or tag.
+/// * 'latest' - The newest version tagged in the repository, when it is
+///   newer than the compatible one (it needs the manifest changed).
+///
+#[derive(Debug, Clone)]
+pub struct Outdated {
+    pub name: String,
+    pub current: Version,
+    pub compatible: Option<Version>,
+    pub latest: Option<Version>,
+}
+
+/// Lists the direct git dependencies that have newer versions than the
+/// locked ones, asking their remotes for their version tags.
+pub fn outdated(project: &Project) -> Result<Vec<Outdated>, CedarError> {
+    if is_offline() {
+        return Err(ProjectError::InvalidDependency(String::from(
+            "cedar outdated asks the remotes of the dependencies for their versions, which offline mode forbids.",
+        ))
+        .into());
+    }
+
+    let packages = resolve(project, true)?;
+    let mut outdated = Vec::new();
+
+    for (name, dependency) in &project.manifest.dependencies.packages {
+        let (Some(url), Some(package)) = (
+            &dependency.git,
+            packages.iter().find(|package| &package.name == name),
+        ) else {
+            continue;
+        };
+
+        let current: Version = package.version.parse()?;
+
+        let remote = network().rewrite(url);
+
+        let tags =
+            git(&project.root, &["ls-remote", "--tags", "--refs", &remote]).map_err(|_| {
+                ProjectError::InvalidDependency(format!(
+                    "Failed to list the tags of {} at {}.",
+                    name, url
+                ))
+            })?;
+
+        let versions = tag_versions(&tags);
+        let requirement = match &dependency.version {
+            Some(requirement) => Some(requirement.parse::<VersionReq>()?),
+            None => None,
+        };
+
+        let pinned = dependency.rev.is_some() || dependency.tag.is_some();
+
+        let compatible = versions
+            .iter()
+            .filter(|version| requirement.as_ref().is_none_or(|req| req.matches(version)))
+            .max()
+            .filter(|version| !pinned && **version > current)
+            .cloned();
+
+        let newest = compatible.as_ref().unwrap_or(&current);
+        let latest = versions
+            .iter()
+            .max()
+            .filter(|version| *version > newest)
+            .cloned();
+
+        if compatible.is_some() || latest.is_some() {
+            outdated.push(Outdated {
+                name: name.clone(),
+                current,
+                compatible,
+                latest,
+            });
+        }
+    }
+
+    Ok(outdated)
+}
+
+/// The versions of the tags in the output of git ls-remote --tags, tags
+/// that are not versions (and pre-releases) are left out.
+fn tag_versions(ls_remote: &str) -> Vec<Version> {
+    ls_remote
+        .lines()
+        .filter_map(|line| line.split_once("refs/tags/"))
+        .filter_map(|(_, tag)| tag.parse::<Version>().ok())
+        .filter(|version| version.pre.is_none())
+        .collect()
+}
+
+/// Resolves the dependencies of a project with the given lockfile.
+///
+/// # Returns
+///
+/// * Every dependency, sorted by name, and the lockfile locking them, not
+///   saved yet.
+///
+fn resolve_locked(
+    project: &Project,
+    lockfile: &Lockfile,
+    quiet: bool,
+) -> Result<(Vec<Package>, Lockfile), CedarError> {
+    let mut packages: Vec<Package> = Vec::new();
+
+    // The version requirements on every dependency, along with who declared
+    // them, checked together once everything is resolved.
+    let mut requirements: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+
+    // Optional dependencies no feature turns on, whose locked entries stay
+    // so that switching features does not churn cedar.lock.
+    let mut skipped: Vec<String> = unused_optional(project);
+
+    // Dependencies along with the project declaring them, whose root their
+    // paths are relative to.
+    let mut queue: VecDeque<(String, Dependency, PathBuf, String)> = project
+        .manifest
+        .dependencies
+        .packages
+        .iter()
+        .filter(|(name, dependency)| project.uses(name, dependency.optional))
+        .map(|(name, dependency)| {
+            (
+                name.clone(),
+                dependency.clone(),
+                project.root.clone(),
+                project.name().to_owned(),
+            )
+        })
+        .collect();
+
+    while let Some((name, dependency, base, declared_by)) = queue.pop_front() {
+        if let Some(requirement) = &dependency.version {
+            requirements
+                .entry(name.clone())
+                .or_default()
+                .push((declared_by, requirement.clone()));
+        }
+
+        let spec = source_spec(project, &name, &dependency, &base)?;
+
+        if let Some(existing) = packages.iter().find(|package| package.name == name) {
+            let same =
+                existing.source == spec || existing.source.starts_with(&format!("{}#", spec));
+
+            if !same {
+                return Err(ProjectError::InvalidDependency(format!(
+                    "Dependency {} is required from two sources:\n {}\n {}",
+                    name, existing.source, spec
+                ))
+                .into());
+            }
+
+            continue;
+        }
+
+        let locked = lockfile.find(&name);
+        let package = resolve_one(project, &name, &dependency, &spec, &base, locked, quiet)?;
+
+        skipped.extend(unused_optional(&package.project));
+
+        let dependencies = &package.project.manifest.dependencies.packages;
+        queue.extend(
+            dependencies
+                .iter()
+                .filter(|(name, dependency)| package.project.uses(name, dependency.optional))
+                .map(|(dependency_name, dependency)| {
+                    (
+                        dependency_name.clone(),
+                        dependency.clone(),
+                        package.project.root.clone(),
+                        package.name.clone(),
+                    )
+                }),
+        );
+
+        packages.push(package);
+    }
+
+    for package in &packages {
+        if let Some(requirements) = requirements.get(&package.name) {
+            check_requirements(package, requirements)?;
+        }
+    }
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut resolved = Lockfile {
+        packages: packages.iter().map(Package::locked).collect(),
+    };
+
+    resolved.packages.extend(
+        lockfile
+            .packages
+            .iter()
+            .filter(|locked| skipped.contains(&locked.name))
+            .filter(|locked| resolved.find(&locked.name).is_none())
+            .cloned()
+            .collect::<Vec<_>>(),
+    );
+    resolved.packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if is_locked() && resolved != *lockfile {
+        let changed: Vec<&str> = resolved
+            .packages
+            .iter()
+            .filter(|package| lockfile.find(&package.name) != Some(package))
+            .map(|package| package.name.as_str())
+            .chain(
+                lockfile
+                    .packages
+                    .iter()
+                    .filter(|package| resolved.find(&package.name).is_none())
+                    .map(|package| package.name.as_str()),
+            )
+            .collect();
+
+        return Err(ProjectError::InvalidDependency(format!(
+            "cedar.lock is out of date ({}) and --locked forbids updating it.",
+            changed.join(", ")
+        ))
+        .into());
+    }
+
+    Ok((packages, resolved))
+}
+
+/// The optional dependencies of a project that none of its features on
+/// pulls in.
+fn unused_optional(project: &Project) -> Vec<String> {
+    project
+        .manifest
+        .dependencies
+        .packages
+        .iter()
+        .filter(|(name, dependency)| !project.uses(name, dependency.optional))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Checks that a resolved dependency meets every requirement on it. When it
+/// does not, the error names the two requirements that cannot both hold, or
+/// the one the resolved version misses.
+fn check_requirements(
+    package: &Package,
+    requirements: &[(String, String)],
+) -> Result<(), CedarError> {
+    let version: Version = package.version.parse()?;
+
+    let parsed = requirements
+        .iter()
+        .map(|(declared_by, requirement)| {
+            Ok((declared_by, requirement, requirement.parse::<VersionReq>()?))
+        })
+        .collect::<Result<Vec<_>, ProjectError>>()?;
+
+    for (i, (a, a_text, a_req)) in parsed.iter().enumerate() {
+        for (b, b_text, b_req) in &parsed[i + 1..] {
+            if !a_req.intersects(b_req) {
+                return Err(ProjectError::InvalidDependency(format!(
+                    "Dependency {} has incompatible requirements, {} requires {:?} and {} requires {:?}, no version meets both.",
+                    package.name, a, a_text, b, b_text
+                ))
+                .into());
+            }
+        }
+    }
+
+    match parsed.iter().find(|(_, _, req)| !req.matches(&version)) {
+        Some((declared_by, text, _)) => Err(ProjectError::InvalidDependency(format!(
+            "Dependency {} is version {}, which does not meet the requirement {:?} of {}.",
+            package.name, version, text, declared_by
+        ))
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// Where a dependency comes from, without the commit of git dependencies:
+/// "path+<path relative to the project>" or "git+<url>[?<ref>]".
+fn source_spec(
+    project: &Project,
+    name: &str,
+    dependency: &Dependency,
+    base: &Path,
+) -> Result<String, CedarError> {
+    match (&dependency.path, &dependency.git) {
+        (Some(path), None) => {
+            let root = base.join(path);
+            let shown = root.strip_prefix(&project.root).unwrap_or(&root);
+
+            Ok(format!("path+{}", shown.display()))
+        }
+        (None, Some(url)) => Ok(git_spec(url, dependency)),
+        _ => Err(ProjectError::InvalidDependency(format!(
+            "Dependency {} needs exactly one of path or git.",
+            name
+        ))
+        .into()),
+    }
+}
+
+/// Resolves a single dependency.
+fn resolve_one(
+    project: &Project,
+    name: &str,
+    dependency: &Dependency,
+    spec: &str,
+    base: &Path,
+    locked: Option<&LockedPackage>,
+    quiet: bool,
+) -> Result<Package, CedarError> {
+    let (root, source, checksum) = match (&dependency.path, &dependency.git) {
+        (Some(path), _) => {
+            let root = base.join(path);
+            init_submodule(project, name, &root, quiet)?;
+
+            (root, spec.to_owned(), None)
+        }
+        (_, Some(url)) => {
+            let checkout = project.build_dir.join("deps").join("src").join(name);
+
+            // The locked commit only holds while the manifest asks for the
+            // same thing it was resolved from.
+            let locked = locked.filter(|locked| locked.source.starts_with(&format!("{}#", spec)));
+
+            let (commit, fetched) = fetch_git(
+                name,
+                url,
+                dependency,
+                &checkout,
+                locked.and_then(LockedPackage::commit),
+                quiet,
+            )?;
+
+            let source = format!("{}#{}", spec, commit);
+            let expected = locked
+                .filter(|locked| locked.source == source)
+                .and_then(|locked| locked.checksum.clone());
+
+            // Hashing every build would be slow, the checkout is verified
+            // whenever it was downloaded to, and on every --locked build.
+            let checksum = match expected {
+                Some(expected) if !fetched && !is_locked() => expected,
+                expected => {
+                    let actual = sha256::hash_dir(&checkout)
+                        .map_err(|err| CedarError::io(&checkout, err))?;
+
+                    if let Some(expected) = expected.filter(|expected| *expected != actual) {
+                        return Err(ProjectError::ChecksumMismatch {
+                            name: name.to_owned(),
+                            expected,
+                            actual,
+                        }
+                        .into());
+                    }
+
+                    actual
+                }
+            };
+
+            (checkout, source, Some(checksum))
+        }
+        (None, None) => unreachable!("source_spec rejects dependencies without a source"),
+    };
+
+    let cmake = match dependency.build.as_deref() {
+        Some("cmake") => Some(dependency.cmake_args.clone()),
+        Some(build) => {
+            return Err(ProjectError::InvalidDependency(format!(
+                "Dependency {} has build = {:?}, the only other build cedar knows is \"cmake\".",
+                name, build
+            ))
+            .into())
+        }
+        None => None,
+    };
+
+    let dependency_project = if cmake.is_some() {
+        cmake::project(&root, name, &cmake::prefix(&project.build_dir, name))
+    } else if dependency.is_vendored() && !root.join("cedar.toml").is_file() {
+        Project::vendored(
+            &root,
+            name,
+            dependency.include.as_deref().unwrap_or("include"),
+            &dependency.sources,
+        )
+    } else {
+        Project::load(&root)
+    }
+    .map_err(|err| match err {
+        ProjectError::NoManifest(_) => CedarError::from(ProjectError::InvalidDependency(format!(
+            "Dependency {} has no cedar.toml in {:?}, set its include and sources to build it as a plain library.",
+            name, root
+        ))),
+        err => err.into(),
+    })?;
+
+    Ok(Package {
+        name: name.to_owned(),
+        version: dependency_project.manifest.meta.version.clone(),
+        source,
+        checksum,
+        project: dependency_project,
+        cmake,
+    })
+}
+
+/// Checks out a path dependency that is a git submodule of the project but
+/// was never initialized, e.g. in a fresh clone.
+fn init_submodule(
+    project: &Project,
+    name: &str,
+    root: &Path,
+    quiet: bool,
+) -> Result<(), CedarError> {
+    let relative = match root.strip_prefix(&project.root) {
+        Ok(relative) if project.root.join(".gitmodules").is_file() => relative,
+        _ => return Ok(()),
+    };
+
+    let initialized = root
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some());
+
+    if initialized
+        || git(
+            &project.root,
+            &["config", "--file", ".gitmodules", "--get-regexp", "path"],
+        )
+        .map_or(true, |paths| {
+            !paths
+                .lines()
+                .any(|line| line.split_whitespace().nth(1) == relative.to_str())
+        })
+    {
+        return Ok(());
+    }
+
+    if is_offline() {
+        return Err(ProjectError::InvalidDependency(format!(
+            "Dependency {} is an uninitialized submodule and offline mode forbids fetching it, run git submodule update --init.",
+            name
+        ))
+        .into());
+    }
+
+    if !quiet {
+        println!(
+            "\t{}{} ({})",
+            style::header("Fetching "),
+            name,
+            relative.display()
+        );
+    }
+
+    git(
+        &project.root,
+        &[
+            "submodule",
+            "update",
+            "--init",
+            "--",
+            relative.to_str().unwrap(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Adds a git submodule to the project and registers it in [dependencies],
+/// so a vendored library builds as part of the project. A submodule without
+/// a cedar.toml is registered as a plain library with its include/ (or the
+/// submodule itself) on the include path and the sources in its src/ (or
+/// the submodule itself). The entry is added to cedar.toml in place, and a
+/// name that is already taken leaves the repository as it was.
+///
+/// # Arguments
+///
+/// * 'project' - The project to add the submodule to, which must be in a
+///   git repository.
+/// * 'url' - The repository of the submodule.
+/// * 'path' - Where to check it out, relative to the project root.
+///
+/// # Returns
+///
+/// * The name the dependency was registered under, and its entry.
+///
+pub fn add_submodule(
+    project: &Project,
+    url: &str,
+    path: &str,
+) -> Result<(String, Dependency), CedarError> {
+    let path = path.trim_end_matches('/');
+    let root = project.root.join(path);
+
+    if is_offline() {
+        return Err(ProjectError::InvalidDependency(format!(
+            "Adding submodule {} needs the network, which offline mode forbids.",
+            url
+        ))
+        .into());
+    }
+
+    if root
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        return Err(ProjectError::NonEmptyPath(format!("{:?}", root)).into());
+    }
+
+    git(&project.root, &["rev-parse", "--show-toplevel"]).map_err(|_| {
+        ProjectError::InvalidDependency(format!(
+            "{:?} is not in a git repository, submodules need one (git init).",
+            project.root
+        ))
+    })?;
+
+    // A submodule without a cedar.toml is named after its directory, which
+    // is checked before anything is cloned.
+    let directory = root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_owned());
+    let exists = |name: &str| {
+        ProjectError::InvalidDependency(format!(
+            "Dependency {} already exists, remove it from cedar.toml or check the submodule out elsewhere.",
+            name
+        ))
+    };
+
+    if project
+        .manifest
+        .dependencies
+        .packages
+        .contains_key(&directory)
+    {
+        return Err(exists(&directory).into());
+    }
+
+    if RESERVED_DEPENDENCIES.contains(&directory.as_str()) {
+        return Err(reserved_dependency(&directory).into());
+    }
+
+    let gitmodules = project.root.join(".gitmodules");
+    let had_gitmodules = gitmodules.is_file();
+
+    git(
+        &project.root,
+        &["submodule", "--quiet", "add", "--", url, path],
+    )?;
+
+    let mut dependency = Dependency {
+        path: Some(path.to_owned()),
+        ..Default::default()
+    };
+
+    let name = match Project::load(&root) {
+        Ok(submodule) => submodule.name().to_owned(),
+        Err(_) => {
+            if !root.join("include").is_dir() {
+                dependency.include = Some(String::from("."));
+            }
+
+            dependency.sources = vec![match root.join("src").is_dir() {
+                true => String::from("src"),
+                false => String::from("."),
+            }];
+
+            directory
+        }
+    };
+
+    // The name of a cedar project is only known once it is checked out, the
+    // submodule is removed again when it is taken.
+    if project.manifest.dependencies.packages.contains_key(&name) {
+        remove_submodule(project, path, had_gitmodules)?;
+        return Err(exists(&name).into());
+    }
+
+    if RESERVED_DEPENDENCIES.contains(&name.as_str()) {
+        remove_submodule(project, path, had_gitmodules)?;
+        return Err(reserved_dependency(&name).into());
+    }
+
+    let manifest_path = project.manifest_path();
+    let text = std::fs::read_to_string(&manifest_path)
+        .map_err(|err| CedarError::io(&manifest_path, err))?;
+    std::fs::write(&manifest_path, add_dependency(&text, &name, &dependency)?)
+        .map_err(|err| CedarError::io(&manifest_path, err))?;
+
+    Ok((name, dependency))
+}
+
+/// Undoes git submodule add: the checkout, its entry in .gitmodules (the
+/// file too when the submodule was the first), its config, and its clone
+/// in .git/modules/.
+fn remove_submodule(project: &Project, path: &str, had_gitmodules: bool) -> Result<(), CedarError> {
+    let modules = git(
+        &project.root,
+        &["rev-parse", "--git-path", &format!("modules/{}", path)],
+    )?;
+
+    git(
+        &project.root,
+        &["submodule", "--quiet", "deinit", "--force", "--", path],
+    )?;
+    git(&project.root, &["rm", "--quiet", "--force", "--", path])?;
+
+    if !had_gitmodules {
+        git(
+            &project.root,
+            &["rm", "--quiet", "--force", "--", ".gitmodules"],
+        )?;
+    }
+
+    let modules = project.root.join(modules.trim());
+    if modules.is_dir() {
+        std::fs::remove_dir_all(&modules).map_err(|err| CedarError::io(&modules, err))?;
+    }
+
+    Ok(())
+}
+
+/// The source of a git dependency without its commit, e.g.
+/// "git+https://example.com/util.git?tag=v1.0".
+fn git_spec(url: &str, dependency: &Dependency) -> String {
+    let reference = [
+        ("rev", &dependency.rev),
+        ("tag", &dependency.tag),
+        ("branch", &dependency.branch),
+    ]
+    .into_iter()
+    .find_map(|(kind, value)| value.as_ref().map(|value| format!("?{}={}", kind, value)));
+
+    format!("git+{}{}", url, reference.unwrap_or_default())
+}
+
+/// Clones or updates the checkout of a git dependency and checks out the
+/// locked commit, or else the requested rev, tag, or branch.
+///
+/// # Returns
+///
+/// * The commit that is checked out, and whether anything was downloaded.
+///
+fn fetch_git(
+    name: &str,
+    url: &str,
+    dependency: &Dependency,
+    checkout: &Path,
+    locked: Option<&str>,
+    quiet: bool,
+) -> Result<(String, bool), CedarError> {
+    let cloned = checkout.join(".git").exists();
+
+    // A locked commit that is already there needs no network at all.
+    if let Some(commit) = locked {
+        if cloned && git(checkout, &["checkout", "--quiet", commit]).is_ok() {
+            return Ok((git(checkout, &["rev-parse", "HEAD"])?, false));
+        }
+    }
+
+    if is_offline() {
+        // The checkout is the only copy there is, as of its last fetch.
+        if !cloned {
+            return Err(ProjectError::InvalidDependency(format!(
+                "Dependency {} ({}) has not been downloaded and offline mode forbids fetching it, build once without --offline.",
+                name, url
+            ))
+            .into());
+        }
+    } else {
+        if !quiet {
+            println!("\t{}{} ({})", style::header("Fetching "), name, url);
+        }
+
+        // Fetching from the mirror, should the config have changed since the
+        // clone.
+        let remote = network().rewrite(url);
+
+        if cloned {
+            git(checkout, &["remote", "set-url", "origin", &remote])?;
+            git(checkout, &["fetch", "--quiet", "--tags", "origin"])?;
+        } else {
+            clone(name, &remote, checkout)?;
+        }
+    }
+
+    let reference = match (locked, &dependency.rev, &dependency.tag, &dependency.branch) {
+        (Some(commit), ..) => commit.to_owned(),
+        (None, Some(rev), ..) => rev.clone(),
+        (None, None, Some(tag), _) => format!("tags/{}", tag),
+        (None, None, None, Some(branch)) => format!("origin/{}", branch),
+        (None, None, None, None) => match &dependency.version {
+            Some(requirement) => newest_tag(checkout, &requirement.parse()?)?.map_or_else(
+                || String::from("origin/HEAD"),
+                |tag| format!("tags/{}", tag),
+            ),
+            None => String::from("origin/HEAD"),
+        },
+    };
+
+    git(checkout, &["checkout", "--quiet", &reference]).map_err(|_| {
+        ProjectError::InvalidDependency(match is_offline() {
+            true => format!(
+                "Dependency {} has no {} in its checkout as of the last fetch, and offline mode forbids fetching {}.",
+                name, reference, url
+            ),
+            false => format!("Dependency {} has no {} in {}.", name, reference, url),
+        })
+    })?;
+
+    Ok((git(checkout, &["rev-parse", "HEAD"])?, !is_offline()))
+}
+
+/// Clones a git dependency into its checkout.
+pub(crate) fn clone(name: &str, url: &str, checkout: &Path) -> Result<(), CedarError> {
+    if let Some(parent) = checkout.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+    }
+
+    let mut clone = process::Command::new("git");
+    clone
+        .args(["clone", "--quiet", url])
+        .arg(checkout)
+        .stdout(process::Stdio::null());
+    network().apply(&mut clone);
+
+    let output = clone
+        .output()
+        .map_err(|err| CedarError::command(&clone, err))?;
+
+    if !output.status.success() {
+        return Err(ProjectError::InvalidDependency(format!(
+            "Failed to clone {} from {}.\n {}",
+            name,
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// The tag naming the newest version that meets a requirement, e.g. "v1.4.2"
+/// for ">=1.2, <2". Tags that are not versions are ignored.
+fn newest_tag(checkout: &Path, requirement: &VersionReq) -> Result<Option<String>, CedarError> {
+    let tags = git(checkout, &["tag", "--list"])?;
+
+    Ok(tags
+        .lines()
+        .filter_map(|tag| Some((tag.parse::<Version>().ok()?, tag)))
+        .filter(|(version, _)| requirement.matches(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag.to_owned()))
+}
+
+/// Runs git in a checkout, returning its trimmed stdout.
+fn git(checkout: &Path, args: &[&str]) -> Result<String, CedarError> {
+    let mut command = process::Command::new("git");
+    command.arg("-C").arg(checkout).args(args);
+    network().apply(&mut command);
+
+    let output = command
+        .output()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !output.status.success() {
+        return Err(ProjectError::ToolFailed("git").into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_remote_tags() {
+        let output = "0123\trefs/tags/v1.2.0\n4567\trefs/tags/1.10.1\n89ab\trefs/tags/nightly\ncdef\trefs/tags/v2.0.0-rc.1\n";
+
+        assert_eq!(
+            tag_versions(output),
+            vec!["1.2.0".parse().unwrap(), "1.10.1".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn git_specs() {
+        let tagged = Dependency {
+            git: Some("https://example.com/util.git".into()),
+            tag: Some("v1.0".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            git_spec("https://example.com/util.git", &tagged),
+            "git+https://example.com/util.git?tag=v1.0"
+        );
+        assert_eq!(
+            git_spec("https://example.com/util.git", &Dependency::default()),
+            "git+https://example.com/util.git"
+        );
+    }
+}
@@ -0,0 +1,787 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use serde::Deserialize;
+
+use crate::{cli::CliError, style};
+
+use super::{build::BuildOptions, probe};
+
+/// The order the output of the steps of a parallel build is printed in.
+///
+/// # Members
+///
+/// * 'Finish' - As each step finishes.
+/// * 'Start' - In the order the steps started, the output of a step that
+///   finished early waits for the steps started before it.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputOrder {
+    #[default]
+    Finish,
+    Start,
+}
+
+impl FromStr for OutputOrder {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "finish" => Ok(Self::Finish),
+            "start" => Ok(Self::Start),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, the output order is finish or start.",
+                s
+            ))),
+        }
+    }
+}
+
+/// How diagnostics are printed.
+///
+/// # Members
+///
+/// * 'Human' - As the compiler prints them, with the source lines.
+/// * 'Quickfix' - One file:line:col: severity: message line per diagnostic,
+///   for the quickfix lists of vim and emacs.
+/// * 'Github' - Workflow commands (::error file=...,line=...::message) that
+///   GitHub Actions shows as annotations of the pull request.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Quickfix,
+    Github,
+}
+
+impl FromStr for OutputFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "quickfix" => Ok(Self::Quickfix),
+            "github" => Ok(Self::Github),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, the output format is human, quickfix, or github.",
+                s
+            ))),
+        }
+    }
+}
+
+/// Prints the output of the compiler and linker as the steps of a build
+/// finish, each diagnostic once: a header included by many translation units
+/// reports the same warning for each of them, only the first is shown.
+///
+/// The output of a step is printed at once under the name of its file, so
+/// the output of steps running in parallel does not interleave.
+///
+/// Past --max-errors errors, the errors are counted but not shown.
+///
+/// # Fields
+///
+/// * 'root' - The root of the project, steps are named relative to it.
+/// * 'quiet' - Whether nothing is printed, the output is only counted.
+/// * 'max_errors' - How many errors are shown at most.
+/// * 'order' - The order the output of the steps is printed in.
+/// * 'format' - How diagnostics are printed, the quickfix and github formats
+///   go to stdout.
+/// * 'state' - What was shown so far, steps may finish on several threads.
+///
+pub(crate) struct Diagnostics {
+    root: PathBuf,
+    quiet: bool,
+    max_errors: Option<usize>,
+    order: OutputOrder,
+    format: OutputFormat,
+    state: Mutex<State>,
+}
+
+/// What the diagnostics of a build are at.
+///
+/// # Fields
+///
+/// * 'seen' - The diagnostics shown so far.
+/// * 'started' - How many steps started.
+/// * 'printed' - How many steps were printed, in start order.
+/// * 'pending' - The name and output of the steps that finished before a
+///   step started earlier, by the order they started in.
+/// * 'fixits' - The fix-it hints of the JSON diagnostics.
+///
+#[derive(Debug, Default)]
+struct State {
+    seen: HashSet<String>,
+    errors: usize,
+    warnings: usize,
+    duplicates: usize,
+    shown_errors: usize,
+    hidden_errors: usize,
+    started: usize,
+    printed: usize,
+    pending: BTreeMap<usize, (String, String)>,
+    fixits: Vec<FixIt>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+/// A diagnostic of the output: the lines saying where it was included from,
+/// its header line, and the lines after it (source, caret, and notes).
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    severity: Option<Severity>,
+    context: Vec<String>,
+    lines: Vec<String>,
+}
+
+impl Diagnostics {
+    pub fn new(root: &Path, options: &BuildOptions) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            quiet: options.quiet,
+            max_errors: options.max_errors,
+            order: options.output_order,
+            format: options.output_format,
+            state: Mutex::new(State::default()),
+        }
+    }
+    /// Notes that a step started.
+    ///
+    /// # Returns
+    ///
+    /// * The ticket of the step, which finish takes.
+    ///
+    pub fn start(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.started += 1;
+        state.started - 1
+    }
+    /// Renders the JSON diagnostics in the output of a step as text,
+    /// collecting their fix-it hints, ahead of finish.
+    pub fn to_text(&self, output: &str) -> String {
+        from_json(output, &mut self.state.lock().unwrap().fixits)
+    }
+    /// Prints the output of a finished step, or holds on to it until the
+    /// steps started before it finished when the output is in start order.
+    /// Every step started must finish, failed ones with what they printed.
+    ///
+    /// # Arguments
+    ///
+    /// * 'ticket' - The ticket start gave the step.
+    /// * 'name' - The file it compiled or linked.
+    /// * 'output' - What it printed, JSON diagnostics are rendered as text.
+    ///
+    /// # Returns
+    ///
+    /// * The output as text.
+    ///
+    pub fn finish(&self, ticket: usize, name: &Path, output: &str) -> String {
+        let name = name.strip_prefix(&self.root).unwrap_or(name).display();
+        let name = name.to_string();
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let mut text = String::new();
+
+        let output = from_json(output, &mut state.fixits);
+
+        match self.order {
+            OutputOrder::Finish => text.push_str(&self.render(state, &name, &output)),
+            OutputOrder::Start => {
+                state.pending.insert(ticket, (name, output.clone()));
+
+                while let Some((name, output)) = state.pending.remove(&state.printed) {
+                    state.printed += 1;
+                    text.push_str(&self.render(state, &name, &output));
+                }
+            }
+        }
+
+        if !self.quiet {
+            let _ = match self.format {
+                OutputFormat::Human => io::stderr().write_all(text.as_bytes()),
+                _ => io::stdout().write_all(text.as_bytes()),
+            };
+        }
+
+        output
+    }
+    /// The fix-it hints of the diagnostics so far.
+    pub fn fixits(&self) -> Vec<FixIt> {
+        self.state.lock().unwrap().fixits.clone()
+    }
+    /// Counts the diagnostics of the output of a step.
+    ///
+    /// # Returns
+    ///
+    /// * Those not shown yet, under the name of the step, empty when there
+    ///   are none.
+    ///
+    fn render(&self, state: &mut State, name: &str, output: &str) -> String {
+        let mut text = String::new();
+
+        for block in split(output) {
+            match block.severity {
+                Some(Severity::Error) => state.errors += 1,
+                Some(Severity::Warning) => state.warnings += 1,
+                None => {}
+            }
+
+            // The include chain differs between translation units, the
+            // diagnostic itself does not.
+            if !state.seen.insert(block.lines.join("\n")) {
+                state.duplicates += 1;
+                continue;
+            }
+
+            if block.severity == Some(Severity::Error) {
+                if self
+                    .max_errors
+                    .is_some_and(|max_errors| state.shown_errors >= max_errors)
+                {
+                    state.hidden_errors += 1;
+                    continue;
+                }
+
+                state.shown_errors += 1;
+            }
+
+            // Output that is not a diagnostic, e.g. of the linker, is
+            // printed as it is whatever the format.
+            let lines: Vec<String> = match (self.format, block.severity) {
+                (OutputFormat::Human, _) | (_, None) => {
+                    block.context.iter().chain(&block.lines).cloned().collect()
+                }
+                (OutputFormat::Quickfix, _) => block
+                    .lines
+                    .iter()
+                    .filter_map(|line| Header::parse(line))
+                    .map(|header| header.quickfix())
+                    .collect(),
+                (OutputFormat::Github, _) => block
+                    .lines
+                    .iter()
+                    .filter_map(|line| Header::parse(line))
+                    .map(|header| header.github())
+                    .collect(),
+            };
+
+            for line in lines {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+
+        match text.is_empty() || self.format != OutputFormat::Human {
+            true => text,
+            false => format!("\t{}{}\n{}", style::header("Output "), name, text),
+        }
+    }
+    /// Prints the totals of the build, what was hidden included, when there
+    /// were diagnostics.
+    pub fn summary(&self) {
+        let state = self.state.lock().unwrap();
+
+        if self.quiet || state.errors + state.warnings == 0 {
+            return;
+        }
+
+        let mut summary = format!("{} error(s), {} warning(s)", state.errors, state.warnings);
+
+        if state.duplicates > 0 {
+            summary.push_str(&format!(", {} duplicate(s) hidden", state.duplicates));
+        }
+
+        if state.hidden_errors > 0 {
+            summary.push_str(&format!(
+                ", {} error(s) past --max-errors hidden",
+                state.hidden_errors
+            ));
+        }
+
+        println!("\t{}{}", style::header("Diagnostics "), summary);
+    }
+}
+
+/// Whether the compiler writes its diagnostics as JSON with
+/// -fdiagnostics-format=json (gcc 9 and later), probed once per compiler.
+pub(crate) fn json_supported(cc: &str) -> bool {
+    let supported = probe::probe(cc, "json-diagnostics", |cc| {
+        let status = process::Command::new(cc)
+            .args(["-fdiagnostics-format=json", "-E", "-x", "c", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+
+        status
+            .is_ok_and(|status| status.success())
+            .then(|| String::from("yes"))
+    });
+
+    supported.is_some()
+}
+
+/// A fix-it hint of the compiler: replacing a range of a file, an insertion
+/// when the range is empty. Kept for applying them later, e.g. with --fix.
+///
+/// # Fields
+///
+/// * 'file' - The file to change.
+/// * 'start' - The line and byte column (from 1) of the start of the range.
+/// * 'end' - The line and byte column just past the end of the range.
+/// * 'replacement' - What replaces the range.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixIt {
+    pub file: PathBuf,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub replacement: String,
+}
+
+/// A diagnostic of -fdiagnostics-format=json, notes are its children.
+#[derive(Debug, Deserialize)]
+struct JsonDiagnostic {
+    kind: String,
+    message: String,
+    #[serde(default)]
+    option: Option<String>,
+    #[serde(default)]
+    locations: Vec<JsonLocation>,
+    #[serde(default)]
+    fixits: Vec<JsonFixIt>,
+    #[serde(default)]
+    children: Vec<JsonDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonLocation {
+    caret: JsonPosition,
+    #[serde(default)]
+    finish: Option<JsonPosition>,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFixIt {
+    start: JsonPosition,
+    next: JsonPosition,
+    string: String,
+}
+
+/// A position of the JSON diagnostics, gcc before 11 only gives the column.
+#[derive(Debug, Clone, Deserialize)]
+struct JsonPosition {
+    file: String,
+    line: usize,
+    column: usize,
+    #[serde(rename = "display-column", default)]
+    display_column: Option<usize>,
+    #[serde(rename = "byte-column", default)]
+    byte_column: Option<usize>,
+}
+
+impl JsonPosition {
+    /// The column on screen, from 1.
+    fn display(&self) -> usize {
+        self.display_column.unwrap_or(self.column)
+    }
+}
+
+/// Renders the JSON diagnostics in the output of a compile like gcc renders
+/// them, with the -W option of each warning, the ranges underlined, and the
+/// fix-it hints below, collecting the fix-it hints. Other lines are kept.
+fn from_json(output: &str, fixits: &mut Vec<FixIt>) -> String {
+    let mut text = String::new();
+    let mut sources = HashMap::new();
+
+    for line in output.lines() {
+        match serde_json::from_str::<Vec<JsonDiagnostic>>(line) {
+            Ok(diagnostics) if line.starts_with('[') => {
+                for diagnostic in &diagnostics {
+                    render_json(diagnostic, &mut sources, fixits, &mut text);
+                }
+            }
+            _ => {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+
+    text
+}
+
+fn render_json(
+    diagnostic: &JsonDiagnostic,
+    sources: &mut HashMap<String, Vec<String>>,
+    fixits: &mut Vec<FixIt>,
+    text: &mut String,
+) {
+    let option = diagnostic
+        .option
+        .as_ref()
+        .map_or(String::new(), |option| format!(" [{}]", option));
+
+    match diagnostic.locations.first() {
+        Some(location) => text.push_str(&format!(
+            "{}:{}:{}: {}: {}{}\n",
+            location.caret.file,
+            location.caret.line,
+            location.caret.display(),
+            diagnostic.kind,
+            diagnostic.message,
+            option
+        )),
+        None => text.push_str(&format!(
+            "cc1: {}: {}{}\n",
+            diagnostic.kind, diagnostic.message, option
+        )),
+    }
+
+    // The source line of the caret, its ranges underlined, then the fix-it
+    // hints on the same line.
+    if let Some(caret) = diagnostic.locations.first().map(|location| &location.caret) {
+        let lines = sources.entry(caret.file.clone()).or_insert_with(|| {
+            fs::read_to_string(&caret.file)
+                .map(|contents| contents.lines().map(str::to_owned).collect())
+                .unwrap_or_default()
+        });
+
+        if let Some(source) = lines.get(caret.line.wrapping_sub(1)) {
+            let mut underline = vec![' '; source.chars().count() + 1];
+
+            for location in diagnostic
+                .locations
+                .iter()
+                .filter(|location| location.caret.file == caret.file)
+                .filter(|location| location.caret.line == caret.line)
+            {
+                let finish = location
+                    .finish
+                    .as_ref()
+                    .filter(|finish| finish.line == caret.line)
+                    .map_or(location.caret.display(), |finish| finish.display());
+
+                for column in location.caret.display()..=finish {
+                    if let Some(mark) = underline.get_mut(column - 1) {
+                        if *mark == ' ' {
+                            *mark = '~';
+                        }
+                    }
+                }
+            }
+
+            if let Some(mark) = underline.get_mut(caret.display().saturating_sub(1)) {
+                *mark = '^';
+            }
+
+            let underline: String = underline.into_iter().collect();
+            let label = diagnostic
+                .locations
+                .first()
+                .and_then(|location| location.label.as_ref())
+                .map_or(String::new(), |label| format!(" {}", label));
+
+            text.push_str(&format!("{:>5} | {}\n", caret.line, source));
+            text.push_str(&format!("{:>5} | {}{}\n", "", underline.trim_end(), label));
+
+            for fixit in diagnostic
+                .fixits
+                .iter()
+                .filter(|fixit| fixit.start.line == caret.line && fixit.start.file == caret.file)
+            {
+                text.push_str(&format!(
+                    "{:>5} | {}{}\n",
+                    "",
+                    " ".repeat(fixit.start.display().saturating_sub(1)),
+                    fixit.string
+                ));
+            }
+        }
+    }
+
+    for fixit in &diagnostic.fixits {
+        let byte = |position: &JsonPosition| {
+            (
+                position.line,
+                position.byte_column.unwrap_or(position.column),
+            )
+        };
+
+        fixits.push(FixIt {
+            file: PathBuf::from(&fixit.start.file),
+            start: byte(&fixit.start),
+            end: byte(&fixit.next),
+            replacement: fixit.string.clone(),
+        });
+    }
+
+    for child in &diagnostic.children {
+        render_json(child, sources, fixits, text);
+    }
+}
+
+/// The first line of a diagnostic, or of a note: file:line:col: severity:
+/// message, the line and column may be missing.
+#[derive(Debug, Clone, PartialEq)]
+struct Header<'a> {
+    file: &'a str,
+    line: Option<usize>,
+    column: Option<usize>,
+    severity: &'a str,
+    message: &'a str,
+}
+
+impl<'a> Header<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let (at, severity) = ["fatal error", "error", "warning", "note"]
+            .into_iter()
+            .filter_map(|severity| {
+                line.find(&format!(": {}: ", severity))
+                    .map(|at| (at, severity))
+            })
+            .min()?;
+
+        let message = &line[at + severity.len() + 4..];
+        let mut location = &line[..at];
+        let mut numbers = Vec::new();
+
+        // The line and column are the numbers at the end of the location.
+        while numbers.len() < 2 {
+            match location.rsplit_once(':') {
+                Some((rest, number)) if number.parse::<usize>().is_ok() => {
+                    numbers.insert(0, number.parse().unwrap());
+                    location = rest;
+                }
+                _ => break,
+            }
+        }
+
+        Some(Self {
+            file: location,
+            line: numbers.first().copied(),
+            column: numbers.get(1).copied(),
+            severity,
+            message,
+        })
+    }
+    fn quickfix(&self) -> String {
+        let mut line = self.file.to_owned();
+
+        for number in self.line.iter().chain(&self.column) {
+            line.push_str(&format!(":{}", number));
+        }
+
+        format!("{}: {}: {}", line, self.severity, self.message)
+    }
+    /// The diagnostic as a workflow command of GitHub Actions, the file
+    /// relative to the workspace.
+    fn github(&self) -> String {
+        let level = match self.severity {
+            "warning" => "warning",
+            "note" => "notice",
+            _ => "error",
+        };
+
+        let workspace = env::var_os("GITHUB_WORKSPACE")
+            .map(PathBuf::from)
+            .or_else(|| env::current_dir().ok())
+            .unwrap_or_default();
+        let file = Path::new(self.file);
+        let file = file.strip_prefix(&workspace).unwrap_or(file);
+
+        let mut properties = format!(
+            "file={}",
+            escape_property(&file.to_string_lossy().replace('\\', "/"))
+        );
+
+        if let Some(line) = self.line {
+            properties.push_str(&format!(",line={}", line));
+        }
+
+        if let Some(column) = self.column {
+            properties.push_str(&format!(",col={}", column));
+        }
+
+        format!("::{} {}::{}", level, properties, escape_data(self.message))
+    }
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Splits the output of gcc or clang into diagnostics. The closing counts
+/// ("2 warnings generated.") are left out, the summary has the totals.
+fn split(output: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut context = Vec::new();
+
+    for line in output.lines() {
+        if is_trailer(line) {
+            continue;
+        }
+
+        // "In file included from a.h:1," and "a.c: In function 'f':" lead
+        // the diagnostic after them.
+        if line.starts_with("In file included from ")
+            || line.starts_with("                 from ")
+            || line.ends_with("':") && line.contains(": In ")
+            || line.ends_with(": At top level:")
+        {
+            context.push(line.to_owned());
+            continue;
+        }
+
+        let severity = if line.contains(": error: ") || line.contains(": fatal error: ") {
+            Some(Severity::Error)
+        } else if line.contains(": warning: ") {
+            Some(Severity::Warning)
+        } else {
+            None
+        };
+
+        match blocks.last_mut() {
+            Some(Block { lines, .. }) if severity.is_none() && context.is_empty() => {
+                lines.push(line.to_owned())
+            }
+            _ => blocks.push(Block {
+                severity,
+                context: std::mem::take(&mut context),
+                lines: vec![line.to_owned()],
+            }),
+        }
+    }
+
+    if !context.is_empty() {
+        blocks.push(Block {
+            severity: None,
+            context: Vec::new(),
+            lines: context,
+        });
+    }
+
+    blocks
+}
+
+/// Whether the line is the count of diagnostics clang prints last, or the
+/// line gcc ends a fatal error with.
+fn is_trailer(line: &str) -> bool {
+    line == "compilation terminated."
+        || line.ends_with(" generated.")
+            && line
+                .split_whitespace()
+                .next()
+                .is_some_and(|count| count.chars().all(|ch| ch.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deduplicates_across_units() {
+        let first = "In file included from src/a.c:1:\ninclude/util.h:3:5: warning: unused variable 'x' [-Wunused-variable]\n    3 |     int x;\n      |         ^\nsrc/a.c:9:1: error: expected ';' before '}' token\n1 warning and 1 error generated.\n";
+        let second = "In file included from src/b.c:2:\ninclude/util.h:3:5: warning: unused variable 'x' [-Wunused-variable]\n    3 |     int x;\n      |         ^\nsrc/b.c:4:1: error: unknown type name 'foo'\nsrc/b.c:5:1: error: unknown type name 'bar'\n";
+
+        let blocks = split(first);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].context, vec!["In file included from src/a.c:1:"]);
+        assert_eq!(blocks[0].lines.len(), 3);
+
+        let options = BuildOptions {
+            quiet: true,
+            max_errors: Some(2),
+            ..Default::default()
+        };
+        let diagnostics = Diagnostics::new(Path::new("/p"), &options);
+        for output in [first, second] {
+            let ticket = diagnostics.start();
+            diagnostics.finish(ticket, Path::new("/p/src/a.c"), output);
+        }
+
+        let state = diagnostics.state.lock().unwrap();
+        assert_eq!((state.errors, state.warnings), (3, 2));
+        assert_eq!(state.duplicates, 1);
+        assert_eq!((state.shown_errors, state.hidden_errors), (2, 1));
+    }
+
+    #[test]
+    fn renders_json_diagnostics() {
+        let output = r#"[{"kind": "warning", "locations": [{"caret": {"byte-column": 19, "display-column": 19, "line": 2, "file": "/nonexistent/j.c", "column": 19}}], "option": "-Wunused-variable", "children": [], "fixits": [{"start": {"column": 9, "line": 3, "file": "/nonexistent/j.c"}, "next": {"column": 9, "line": 3, "file": "/nonexistent/j.c"}, "string": ";"}], "message": "unused variable 'x'"}]
+cc1: all warnings being treated as errors"#;
+
+        let mut fixits = Vec::new();
+        assert_eq!(
+            from_json(output, &mut fixits),
+            "/nonexistent/j.c:2:19: warning: unused variable 'x' [-Wunused-variable]\ncc1: all warnings being treated as errors\n"
+        );
+        assert_eq!(fixits[0].start, (3, 9));
+        assert_eq!(fixits[0].replacement, ";");
+    }
+
+    #[test]
+    fn formats_for_editors_and_ci() {
+        let header = Header::parse("src/a.c:3:5: warning: unused variable 'x' [-Wunused]").unwrap();
+        assert_eq!(
+            header.quickfix(),
+            "src/a.c:3:5: warning: unused variable 'x' [-Wunused]"
+        );
+        assert_eq!(
+            header.github(),
+            "::warning file=src/a.c,line=3,col=5::unused variable 'x' [-Wunused]"
+        );
+
+        let header = Header::parse("cc1: fatal error: a.h: No such file").unwrap();
+        assert_eq!((header.file, header.line), ("cc1", None));
+        assert_eq!(header.severity, "fatal error");
+        assert_eq!(header.message, "a.h: No such file");
+    }
+
+    #[test]
+    fn holds_output_in_start_order() {
+        let options = BuildOptions {
+            quiet: true,
+            output_order: OutputOrder::Start,
+            ..Default::default()
+        };
+        let diagnostics = Diagnostics::new(Path::new("/p"), &options);
+        let (a, b) = (diagnostics.start(), diagnostics.start());
+
+        diagnostics.finish(b, Path::new("/p/src/b.c"), "src/b.c:1:1: warning: b\n");
+        assert_eq!(
+            diagnostics.state.lock().unwrap().pending.get(&b),
+            Some(&(
+                String::from("src/b.c"),
+                String::from("src/b.c:1:1: warning: b\n")
+            ))
+        );
+
+        diagnostics.finish(a, Path::new("/p/src/a.c"), "");
+        let state = diagnostics.state.lock().unwrap();
+        assert!(state.pending.is_empty());
+        assert_eq!((state.printed, state.warnings), (2, 1));
+    }
+}
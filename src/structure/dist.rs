@@ -0,0 +1,426 @@
+use std::{
+    collections::VecDeque,
+    ffi::OsStr,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{error::CedarError, style};
+
+use super::{build::Language, config::DistConfig, diagnostics::Diagnostics, ninja::quote, trace};
+
+/// Options of the compiler only the preprocessor uses, taking their value as
+/// the next argument. They are left out of the compile on the worker.
+const PREPROCESSOR_OPTIONS: &[&str] = &[
+    "-I",
+    "-D",
+    "-U",
+    "-include",
+    "-imacros",
+    "-isystem",
+    "-iquote",
+    "-idirafter",
+    "-MF",
+    "-MT",
+    "-MQ",
+];
+
+/// The exit status of ssh when it could not reach the host, and of the shell
+/// when the compiler is missing there.
+const UNREACHABLE: &[i32] = &[255, 127];
+
+/// The workers from the global config.
+static DIST: OnceLock<DistConfig> = OnceLock::new();
+
+/// Sets the workers compiles are distributed to, only the first call has an
+/// effect.
+pub fn set_config(dist: DistConfig) {
+    let _ = DIST.set(dist);
+}
+
+/// A compile planned by the build: the source, its object, the command and
+/// its fingerprint.
+pub(crate) type Job = (PathBuf, PathBuf, process::Command, PathBuf);
+
+/// How a compile ran.
+///
+/// # Fields
+///
+/// * 'success' - Whether it succeeded.
+/// * 'output' - What the compiler printed.
+/// * 'started' - When it started.
+/// * 'elapsed' - How long it ran, preprocessing and transfers included.
+/// * 'slot' - The slot it ran in, local slots first.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct Outcome {
+    pub success: bool,
+    pub output: String,
+    pub started: Instant,
+    pub elapsed: Duration,
+    pub slot: usize,
+}
+
+/// Runs the compiles of a build in parallel, spread over this machine and
+/// the workers of the config. Each source sent to a worker is preprocessed
+/// here (writing its depfile as usual), so the worker only needs the
+/// compiler, and the object comes back over the same ssh connection.
+///
+/// The largest sources are sent first, to the workers, while this machine
+/// works through the smallest ones, which are not worth the round trip. A
+/// worker that cannot be reached gets no more compiles for the rest of the
+/// build, the one it failed is compiled here.
+///
+/// Once a compile fails no new ones are started.
+///
+/// # Arguments
+///
+/// * 'jobs' - The compiles to run.
+/// * 'diagnostics' - Where the output of the compiler is printed, it is
+///   captured as well.
+///
+/// # Returns
+///
+/// * None without workers, the build then compiles as usual. Otherwise
+///   how each compile ran, None for compiles skipped after a failure.
+///
+pub(crate) fn compile(
+    jobs: &mut [Job],
+    diagnostics: &Diagnostics,
+) -> Result<Option<Vec<Option<Outcome>>>, CedarError> {
+    let Some(dist) = DIST.get().filter(|dist| !dist.workers.is_empty()) else {
+        return Ok(None);
+    };
+
+    for (_, object, _, _) in jobs.iter() {
+        if let Some(parent) = object.parent() {
+            fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+        }
+    }
+
+    let mut queue: Vec<(usize, &mut Job)> = jobs.iter_mut().enumerate().collect();
+    queue.sort_by_key(|(_, (source, ..))| {
+        std::cmp::Reverse(fs::metadata(source).map_or(0, |metadata| metadata.len()))
+    });
+
+    let count = queue.len();
+    let queue = Mutex::new(VecDeque::from(queue));
+    let results = Mutex::new((0..count).map(|_| None).collect::<Vec<_>>());
+    let failed = AtomicBool::new(false);
+    let local = dist
+        .jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1);
+
+    // Every slot is a thread, local slots take compiles off the back of the
+    // queue and worker slots off the front.
+    let mut slots: Vec<Option<usize>> = vec![None; local.max(1)];
+    for (index, worker) in dist.workers.iter().enumerate() {
+        slots.extend(std::iter::repeat_n(Some(index), worker.jobs));
+    }
+    let down: Vec<AtomicBool> = dist
+        .workers
+        .iter()
+        .map(|_| AtomicBool::new(false))
+        .collect();
+
+    thread::scope(|scope| {
+        for (number, slot) in slots.into_iter().enumerate() {
+            let (queue, results, failed, down) = (&queue, &results, &failed, &down);
+
+            scope.spawn(move || loop {
+                if failed.load(Ordering::Relaxed)
+                    || slot.is_some_and(|worker| down[worker].load(Ordering::Relaxed))
+                {
+                    break;
+                }
+
+                // The ticket is taken along with the compile, so tickets
+                // follow the order compiles start in.
+                let next = {
+                    let mut queue = queue.lock().unwrap();
+                    let next = match slot {
+                        Some(_) => queue.pop_front(),
+                        None => queue.pop_back(),
+                    };
+                    next.map(|job| (diagnostics.start(), job))
+                };
+                let Some((ticket, (index, (source, object, command, _)))) = next else {
+                    break;
+                };
+
+                let remote = slot
+                    .filter(|_| {
+                        matches!(
+                            Language::from_path(&*source),
+                            Some(Language::C | Language::Cxx)
+                        )
+                    })
+                    .map(|worker| &dist.workers[worker].host);
+
+                let started = Instant::now();
+                let span = trace::span(
+                    "compile",
+                    format!(
+                        "{} on {}",
+                        source.display(),
+                        remote.map_or("localhost", |host| host)
+                    ),
+                );
+                let mut result = match remote {
+                    Some(host) => compile_remote(host, object, command),
+                    None => run_local(command),
+                };
+                drop(span);
+
+                if let (Err(None), Some(worker)) = (&result, slot) {
+                    if !down[worker].swap(true, Ordering::Relaxed) {
+                        println!(
+                            "\t{}{} is unreachable, compiling locally",
+                            style::header("Skipping "),
+                            dist.workers[worker].host
+                        );
+                    }
+                    result = run_local(command);
+                }
+
+                let result = match result {
+                    Ok((success, output)) => {
+                        if !success {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+
+                        Ok(Outcome {
+                            success,
+                            output: diagnostics.finish(ticket, source, &output),
+                            started,
+                            elapsed: started.elapsed(),
+                            slot: number,
+                        })
+                    }
+                    Err(Some(err)) => {
+                        failed.store(true, Ordering::Relaxed);
+                        diagnostics.finish(ticket, source, "");
+                        Err(err)
+                    }
+                    Err(None) => unreachable!("local compiles do not go through ssh"),
+                };
+
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(Option::transpose)
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Runs a compile on this machine, capturing its output.
+fn run_local(command: &mut process::Command) -> Result<(bool, String), Option<CedarError>> {
+    let output = command
+        .output()
+        .map_err(|err| Some(CedarError::command(command, err)))?;
+
+    Ok((
+        output.status.success(),
+        captured(&output.stdout, &output.stderr),
+    ))
+}
+
+/// Preprocesses a source here and compiles it on a worker.
+///
+/// # Returns
+///
+/// * Whether the compile succeeded and the output of the compiler, or None
+///   as the error when the worker could not be reached.
+///
+fn compile_remote(
+    host: &str,
+    object: &Path,
+    command: &process::Command,
+) -> Result<(bool, String), Option<CedarError>> {
+    let (mut preprocess, compiler, flags) = split(command, object);
+    let remote = format!(
+        "t=$(mktemp -d) && {} {} -c - -o \"$t/o\" && cat \"$t/o\"; s=$?; rm -rf \"$t\"; exit $s",
+        quote(&compiler),
+        flags
+            .iter()
+            .map(|flag| quote(flag))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+
+    let output = preprocess
+        .output()
+        .map_err(|err| Some(CedarError::command(&preprocess, err)))?;
+
+    if !output.status.success() {
+        return Ok((false, captured(&output.stdout, &output.stderr)));
+    }
+
+    let mut ssh = process::Command::new("ssh");
+    ssh.arg("-o")
+        .arg("BatchMode=yes")
+        .arg(host)
+        .arg(remote)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let Ok(mut child) = ssh.spawn() else {
+        return Err(None);
+    };
+
+    // The preprocessed source is written from another thread so a large
+    // object coming back cannot block it.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let preprocessed = output.stdout;
+    let writer = thread::spawn(move || stdin.write_all(&preprocessed));
+
+    let result = child
+        .wait_with_output()
+        .map_err(|err| Some(CedarError::command(&ssh, err)))?;
+    let _ = writer.join();
+
+    match result.status.code() {
+        Some(code) if UNREACHABLE.contains(&code) => Err(None),
+        _ if result.status.success() => {
+            fs::write(object, &result.stdout).map_err(|err| Some(CedarError::io(object, err)))?;
+            Ok((true, captured(&[], &result.stderr)))
+        }
+        _ => {
+            // A partial object must not be mistaken for a compiled one.
+            let _ = fs::remove_file(object);
+            Ok((false, captured(&[], &result.stderr)))
+        }
+    }
+}
+
+/// Splits a compile into the command preprocessing the source, which writes
+/// the preprocessed source to stdout along with the depfile, and the
+/// compiler and flags that compile the preprocessed source.
+pub(crate) fn split(
+    command: &process::Command,
+    object: &Path,
+) -> (process::Command, String, Vec<String>) {
+    let program = command.get_program();
+    let args: Vec<&OsStr> = command.get_args().collect();
+    let cxx = args
+        .iter()
+        .any(|arg| Language::from_path(arg) == Some(Language::Cxx));
+
+    let mut preprocess = process::Command::new(program);
+    let mut flags = vec![
+        String::from("-x"),
+        String::from(if cxx { "c++-cpp-output" } else { "cpp-output" }),
+    ];
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let text = arg.to_string_lossy();
+
+        match text.as_ref() {
+            "-c" => {
+                preprocess.arg("-E");
+            }
+            "-o" => {
+                args.next();
+                preprocess.arg("-MT").arg(object).arg("-o").arg("-");
+            }
+            option if PREPROCESSOR_OPTIONS.contains(&option) => {
+                preprocess.arg(arg).args(args.next());
+            }
+            option
+                if ["-I", "-D", "-U", "-MMD", "-MD"]
+                    .iter()
+                    .any(|prefix| option.starts_with(prefix)) =>
+            {
+                preprocess.arg(arg);
+            }
+            option if option.starts_with('-') => {
+                preprocess.arg(arg);
+                flags.push(option.to_owned());
+            }
+            _ => {
+                preprocess.arg(arg);
+            }
+        }
+    }
+
+    // Only the name of the compiler is kept, the worker has its own.
+    let compiler = Path::new(program)
+        .file_name()
+        .unwrap_or(program)
+        .to_string_lossy()
+        .into_owned();
+
+    (preprocess, compiler, flags)
+}
+
+/// The stdout and stderr of a step as one text.
+fn captured(stdout: &[u8], stderr: &[u8]) -> String {
+    let mut text = String::from_utf8_lossy(stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(stderr));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_compiles() {
+        let mut command = process::Command::new("/usr/bin/gcc");
+        command.args([
+            "-c",
+            "src/a.c",
+            "-o",
+            "build/obj/src/a.o",
+            "-Iinclude",
+            "-isystem",
+            "vendor",
+            "-O2",
+            "-DNDEBUG",
+            "-MMD",
+            "-MF",
+            "build/obj/src/a.d",
+        ]);
+
+        let (preprocess, compiler, flags) = split(&command, Path::new("build/obj/src/a.o"));
+
+        assert_eq!(
+            preprocess.get_args().collect::<Vec<_>>(),
+            [
+                "-E",
+                "src/a.c",
+                "-MT",
+                "build/obj/src/a.o",
+                "-o",
+                "-",
+                "-Iinclude",
+                "-isystem",
+                "vendor",
+                "-O2",
+                "-DNDEBUG",
+                "-MMD",
+                "-MF",
+                "build/obj/src/a.d"
+            ]
+        );
+        assert_eq!(compiler, "gcc");
+        assert_eq!(flags, ["-x", "cpp-output", "-O2"]);
+    }
+}
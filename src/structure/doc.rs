@@ -0,0 +1,374 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    process,
+};
+
+use super::{
+    ignore,
+    manifest::Manifest,
+    project::{Project, ProjectError},
+};
+
+/// Generates HTML documentation for the project with Doxygen, into
+/// build/doc/html/.
+///
+/// If the project has its own Doxyfile it is used as is, except that the
+/// output directory is pointed at build/doc/. Otherwise a Doxyfile is
+/// generated from the manifest (and written to build/doc/Doxyfile so it can
+/// be inspected or copied into the project as a starting point).
+///
+/// # Arguments
+///
+/// * 'project' - The project to document.
+///
+/// # Returns
+///
+/// * The path of the generated index.html.
+///
+pub fn doxygen(project: &Project) -> Result<PathBuf, ProjectError> {
+    let path = &project.root;
+    let doc_path = project.build_dir.join("doc");
+
+    fs::create_dir_all(&doc_path)?;
+
+    let user_doxyfile = path.join("Doxyfile");
+
+    let mut doxyfile = if user_doxyfile.is_file() {
+        fs::read_to_string(&user_doxyfile)?
+    } else {
+        let generated = generate_doxyfile(&project.manifest);
+
+        fs::write(doc_path.join("Doxyfile"), &generated)?;
+        generated
+    };
+
+    // Later assignments win, so appending keeps the output in build/doc.
+    doxyfile.push_str("\nOUTPUT_DIRECTORY = build/doc\nHTML_OUTPUT = html\n");
+
+    let mut child = match process::Command::new("doxygen")
+        .arg("-")
+        .current_dir(path)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(ProjectError::MissingTool("doxygen"))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    child
+        .stdin
+        .take()
+        .ok_or(ProjectError::ToolFailed("doxygen"))?
+        .write_all(doxyfile.as_bytes())?;
+
+    if !child.wait()?.success() {
+        return Err(ProjectError::ToolFailed("doxygen"));
+    }
+
+    Ok(doc_path.join("html").join("index.html"))
+}
+
+/// Creates a Doxyfile documenting src/ and include/ from the manifest
+/// metadata.
+pub fn generate_doxyfile(manifest: &Manifest) -> String {
+    let meta = &manifest.meta;
+
+    format!(
+        "PROJECT_NAME           = \"{}\"
+PROJECT_NUMBER         = \"{}\"
+PROJECT_BRIEF          = \"{}\"
+INPUT                  = src include
+RECURSIVE              = YES
+FILE_PATTERNS          = *.c *.h *.cpp *.hpp
+OPTIMIZE_OUTPUT_FOR_C  = YES
+EXTRACT_ALL            = YES
+EXTRACT_STATIC         = YES
+JAVADOC_AUTOBRIEF      = YES
+GENERATE_HTML          = YES
+GENERATE_LATEX         = NO
+QUIET                  = YES
+",
+        meta.name,
+        meta.version,
+        meta.description.as_deref().unwrap_or_default()
+    )
+}
+
+/// A documented declaration found in a header.
+///
+/// # Fields
+///
+/// * 'name' - The name of the function (or the declaration itself when it
+///   is not a function).
+/// * 'declaration' - The declaration, whitespace collapsed and without the
+///   trailing semicolon or body.
+/// * 'doc' - The text of the `///` comments above the declaration.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocItem {
+    pub name: String,
+    pub declaration: String,
+    pub doc: String,
+}
+
+/// Generates a Markdown and HTML API reference from the `///` comments in the
+/// headers in include/, without any external tools. The reference is written
+/// to build/doc/api.md and build/doc/html/index.html.
+///
+/// # Arguments
+///
+/// * 'project' - The project to document.
+///
+/// # Returns
+///
+/// * The path of the generated index.html.
+///
+pub fn builtin(project: &Project) -> Result<PathBuf, ProjectError> {
+    let include_path = &project.include_dir;
+    let doc_path = project.build_dir.join("doc");
+    let manifest = &project.manifest;
+
+    // A project without include/ has no public headers to document.
+    let files = match include_path.is_dir() {
+        true => ignore::files(include_path, manifest.build.symlinks)?,
+        false => Vec::new(),
+    };
+    let mut headers: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| {
+            file.extension()
+                .is_some_and(|ext| ext == "h" || ext == "hpp")
+        })
+        .collect();
+    headers.sort();
+
+    let title = format!("{} v{}", manifest.meta.name, manifest.meta.version);
+    let mut markdown = format!("# {}\n", title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>body{{font-family:sans-serif;max-width:50em;margin:auto}}\
+         pre{{background:#f4f4f4;padding:.5em}}</style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape(&title)
+    );
+
+    if let Some(description) = &manifest.meta.description {
+        markdown.push_str(&format!("\n{}\n", escape_markdown(description)));
+        html.push_str(&format!("<p>{}</p>\n", escape(description)));
+    }
+
+    for header in headers {
+        let items = extract(&fs::read_to_string(&header)?);
+
+        if items.is_empty() {
+            continue;
+        }
+
+        let relative = header
+            .strip_prefix(include_path)
+            .unwrap_or(&header)
+            .display();
+
+        markdown.push_str(&format!("\n## {}\n", relative));
+        html.push_str(&format!("<h2>{}</h2>\n", escape(&relative.to_string())));
+
+        for item in items {
+            markdown.push_str(&format!(
+                "\n### {}\n\n```c\n{}\n```\n\n{}\n",
+                item.name,
+                item.declaration,
+                escape_markdown(&item.doc)
+            ));
+            html.push_str(&format!(
+                "<h3 id=\"{0}\">{0}</h3>\n<pre><code>{1}</code></pre>\n",
+                escape(&item.name),
+                escape(&item.declaration)
+            ));
+
+            for paragraph in item.doc.split("\n\n") {
+                html.push_str(&format!("<p>{}</p>\n", escape(paragraph)));
+            }
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+
+    let html_path = doc_path.join("html");
+    fs::create_dir_all(&html_path)?;
+
+    fs::write(doc_path.join("api.md"), markdown)?;
+    fs::write(html_path.join("index.html"), html)?;
+
+    Ok(html_path.join("index.html"))
+}
+
+/// Extracts the declarations preceded by `///` comments from a header.
+pub fn extract(contents: &str) -> Vec<DocItem> {
+    let mut items = Vec::new();
+    let mut doc: Vec<&str> = Vec::new();
+    let mut declaration = String::new();
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+
+        if declaration.is_empty() {
+            if let Some(comment) = trimmed.strip_prefix("///") {
+                doc.push(comment.strip_prefix(' ').unwrap_or(comment));
+                continue;
+            }
+
+            // Undocumented code (or a blank line) separates a comment from
+            // what comes after it.
+            if doc.is_empty() || trimmed.is_empty() {
+                doc.clear();
+                continue;
+            }
+        }
+
+        declaration.push(' ');
+        declaration.push_str(trimmed);
+
+        // Declarations may span several lines, they end at a semicolon, the
+        // start of a body, or (for macros) the end of the line.
+        if trimmed.ends_with(';') || trimmed.contains('{') || trimmed.starts_with('#') {
+            let declaration = std::mem::take(&mut declaration);
+            let declaration = declaration
+                .split('{')
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(';')
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ");
+
+            items.push(DocItem {
+                name: item_name(&declaration),
+                declaration,
+                doc: doc.join("\n").trim().to_owned(),
+            });
+
+            doc.clear();
+        }
+    }
+
+    items
+}
+
+/// The identifier before the parameter list of a function declaration, the
+/// macro name of a #define, or the whole declaration otherwise.
+fn item_name(declaration: &str) -> String {
+    if let Some(define) = declaration.strip_prefix("#define ") {
+        return define
+            .split(|ch: char| ch == '(' || ch.is_whitespace())
+            .next()
+            .unwrap_or(define)
+            .to_owned();
+    }
+
+    match declaration.find('(') {
+        Some(index) => declaration[..index]
+            .trim_end()
+            .rsplit(|ch: char| ch.is_whitespace() || ch == '*')
+            .next()
+            .unwrap_or(declaration)
+            .to_owned(),
+        None => declaration.to_owned(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes the text of Markdown outside code, where renderers take <, >,
+/// and & as HTML. Code spans are left alone, they show text as it is.
+fn escape_markdown(text: &str) -> String {
+    text.split('`')
+        .enumerate()
+        .map(|(index, part)| match index % 2 {
+            0 => part
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            _ => part.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("`")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let header = "#include <stddef.h>
+
+/// Adds two numbers.
+///
+/// Overflow is undefined.
+int add(int a,
+        int b);
+
+int undocumented(void);
+
+/// The largest value.
+#define MAX_VALUE 100
+";
+
+        let items = extract(header);
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "add");
+        assert_eq!(items[0].declaration, "int add(int a, int b)");
+        assert_eq!(items[0].doc, "Adds two numbers.\n\nOverflow is undefined.");
+        assert_eq!(items[1].name, "MAX_VALUE");
+    }
+
+    #[test]
+    fn escapes_the_reference() {
+        assert_eq!(
+            escape_markdown("Returns a < b && `a < b` <b>"),
+            "Returns a &lt; b &amp;&amp; `a < b` &lt;b&gt;"
+        );
+
+        let root = env::temp_dir().join(format!("cedar-doc-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("cedar.toml"),
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\ntype = \"headeronly\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n",
+        )
+        .unwrap();
+
+        // Without include/ there is nothing to document.
+        let project = Project::load(&root).unwrap();
+        let index = builtin(&project).unwrap();
+        assert!(!fs::read_to_string(&index).unwrap().contains("<h2>"));
+
+        fs::create_dir_all(root.join("include")).unwrap();
+        fs::write(
+            root.join("include").join("a.h"),
+            "/// Whether a < b & b > 0.\nint less(int a, int b);\n",
+        )
+        .unwrap();
+
+        builtin(&project).unwrap();
+        let html = fs::read_to_string(&index).unwrap();
+        assert!(html.contains("<p>Whether a &lt; b &amp; b &gt; 0.</p>"));
+        let markdown = fs::read_to_string(root.join("build").join("doc").join("api.md")).unwrap();
+        assert!(markdown.contains("int less(int a, int b)"));
+        assert!(markdown.contains("Whether a &lt; b &amp; b &gt; 0."));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+use std::{fs, path::Path};
+
+use crate::error::CedarError;
+
+use super::{
+    build::{BuildOptions, Emit},
+    project::Project,
+    target::TargetFilter,
+};
+
+/// Runs the preprocessor over a single source of the project, with the
+/// defines, include directories, and other flags it compiles with in the
+/// profile of the project, the same compile as cedar build --emit
+/// preprocessed.
+///
+/// # Arguments
+///
+/// * 'project' - The project the source belongs to.
+/// * 'file' - The path of the source.
+/// * 'own_lines' - Keeps only the lines that come from the source itself,
+///   leaving out those of the headers it includes.
+///
+/// # Returns
+///
+/// * The preprocessed source.
+///
+pub fn expand(project: &Project, file: &Path, own_lines: bool) -> Result<String, CedarError> {
+    let file = file
+        .canonicalize()
+        .map_err(|err| CedarError::io(file, err))?;
+
+    // Tests and examples are sources too.
+    let report = project.build(&BuildOptions {
+        quiet: true,
+        targets: vec![TargetFilter::All],
+        emit: Some(Emit::Preprocessed),
+        file: Some(file.clone()),
+        ..Default::default()
+    })?;
+
+    let path = report
+        .artifacts
+        .first()
+        .expect("a build with a file emits it");
+    let text = fs::read_to_string(path).map_err(|err| CedarError::io(path, err))?;
+
+    match own_lines {
+        true => Ok(lines_of(&text, |name| {
+            Path::new(name)
+                .canonicalize()
+                .is_ok_and(|name| name == file)
+        })),
+        false => Ok(text),
+    }
+}
+
+/// The lines of preprocessed output that come from a file, as the line
+/// markers of the preprocessor (# <line> "<file>" <flags>) tell, without the
+/// markers themselves.
+///
+/// # Arguments
+///
+/// * 'text' - The preprocessed output.
+/// * 'is_file' - Whether the file a line marker names is the one to keep.
+///
+fn lines_of(text: &str, is_file: impl Fn(&str) -> bool) -> String {
+    let mut lines = String::new();
+    let mut current = false;
+    let mut last_name = "";
+
+    for line in text.lines() {
+        let marker = line
+            .strip_prefix("# ")
+            .and_then(|rest| rest.split_once(" \""))
+            .filter(|(number, _)| number.chars().all(|ch| ch.is_ascii_digit()))
+            .and_then(|(_, rest)| rest.split_once('"'));
+
+        match marker {
+            // Markers name the same file over and over, only a change of
+            // file needs a look.
+            Some((name, _)) if name != last_name => {
+                current = is_file(name);
+                last_name = name;
+            }
+            Some(_) => {}
+            None if current => {
+                lines.push_str(line);
+                lines.push('\n');
+            }
+            None => {}
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_lines_of_file() {
+        let text = "# 0 \"src/parser.c\"\n# 1 \"src/parser.c\"\n# 1 \"include/parser.h\" 1\nint parse(void);\n# 2 \"src/parser.c\" 2\n\nint parse(void) { return ((1)*2); }\n";
+
+        assert_eq!(
+            lines_of(text, |name| name == "src/parser.c"),
+            "\nint parse(void) { return ((1)*2); }\n"
+        );
+        assert_eq!(
+            lines_of(text, |name| name == "include/parser.h"),
+            "int parse(void);\n"
+        );
+    }
+}
@@ -0,0 +1,1799 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+};
+
+use crate::{cli::CliError, error::CedarError};
+
+use super::{
+    build::{self, BuildError, Language, Toolchain},
+    cmake,
+    deps::{self, Package},
+    license,
+    manifest::{Manifest, SystemDependency},
+    ninja,
+    pkgconfig::pkg_config,
+    probe,
+    project::{Project, ProjectError},
+    sysdeps,
+    target::{Artifact, Sources, Target, TargetKind},
+    test,
+};
+
+/// The build systems a project can be exported to.
+///
+/// # Members
+///
+/// * 'Cmake' - A CMakeLists.txt.
+/// * 'Make' - A Makefile that needs nothing but make and the compiler.
+/// * 'Nix' - A flake.nix with a derivation building the project.
+/// * 'Docker' - A Dockerfile building the executables of the project into an
+///   image.
+/// * 'Ci' - A CI pipeline building, testing, and linting the project, for the
+///   CI service.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Cmake,
+    Make,
+    Nix,
+    Docker,
+    Ci(CiProvider),
+}
+
+/// The CI services cedar export ci writes pipelines for.
+///
+/// # Members
+///
+/// * 'Github' - A GitHub Actions workflow, .github/workflows/ci.yml.
+/// * 'Gitlab' - A GitLab CI/CD pipeline, .gitlab-ci.yml.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CiProvider {
+    #[default]
+    Github,
+    Gitlab,
+}
+
+impl FromStr for CiProvider {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "github" => Ok(Self::Github),
+            "gitlab" => Ok(Self::Gitlab),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, cedar can export ci for github or gitlab.",
+                s
+            ))),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cmake" => Ok(Self::Cmake),
+            "make" => Ok(Self::Make),
+            "nix" => Ok(Self::Nix),
+            "docker" => Ok(Self::Docker),
+            "ci" => Ok(Self::Ci(CiProvider::default())),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, cedar can export cmake, make, nix, docker, or ci.",
+                s
+            ))),
+        }
+    }
+}
+
+/// The first line of every exported file, files without it are never
+/// overwritten.
+const GENERATED: &str = "# Generated by cedar export";
+
+/// The nixpkgs branch of exported flakes, nix pins it to a commit in
+/// flake.lock on the first build.
+const NIXPKGS: &str = "github:NixOS/nixpkgs/nixos-25.05";
+
+/// The images of exported Dockerfiles, unless [package.docker] names others.
+const DOCKER_IMAGE: &str = "debian:bookworm";
+const DOCKER_RUNTIME: &str = "debian:bookworm-slim";
+
+/// Libraries of the C library, which every toolchain has.
+const LIBC: &[&str] = &["c", "m", "pthread", "dl", "rt"];
+
+/// Writes a build definition for another build system next to cedar.toml,
+/// mirroring the targets, sources, flags, and dependencies of the project.
+///
+/// # Arguments
+///
+/// * 'project' - The project to export.
+/// * 'format' - The build system to export to.
+///
+/// # Returns
+///
+/// * The path of the written file.
+///
+pub fn export(project: &Project, format: ExportFormat) -> Result<PathBuf, CedarError> {
+    let packages = deps::resolve(project, false)?;
+
+    let (path, contents) = match format {
+        ExportFormat::Cmake => (
+            project.root.join("CMakeLists.txt"),
+            cmake_lists(project, &packages)?,
+        ),
+        ExportFormat::Make => (project.root.join("Makefile"), makefile(project, &packages)?),
+        ExportFormat::Nix => (
+            project.root.join("flake.nix"),
+            flake(
+                project,
+                &packages,
+                &nix_stdenv(&project.manifest.build.compiler),
+            )?,
+        ),
+        ExportFormat::Docker => (
+            project.root.join("Dockerfile"),
+            dockerfile(project, &packages)?,
+        ),
+        ExportFormat::Ci(provider) => {
+            let plan = ci_plan(project, &packages)?;
+            match provider {
+                CiProvider::Github => (
+                    project
+                        .root
+                        .join(".github")
+                        .join("workflows")
+                        .join("ci.yml"),
+                    github_workflow(project, &plan),
+                ),
+                CiProvider::Gitlab => (
+                    project.root.join(".gitlab-ci.yml"),
+                    gitlab_ci(project, &plan),
+                ),
+            }
+        }
+    };
+
+    if fs::read_to_string(&path).is_ok_and(|existing| !existing.starts_with(GENERATED)) {
+        return Err(ProjectError::AlreadyExists(format!("{:?}", path)).into());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+    }
+    fs::write(&path, contents).map_err(|err| CedarError::io(&path, err))?;
+
+    Ok(path)
+}
+
+/// Generates a CMakeLists.txt building the project like cedar does: the
+/// library sources once into an object library every target links, each
+/// dependency as a static library, and every target of the project.
+fn cmake_lists(project: &Project, packages: &[Package]) -> Result<String, CedarError> {
+    let manifest = &project.manifest;
+    let meta = &manifest.meta;
+    let root = &project.root;
+
+    let mut languages = vec!["C"];
+    let all_sources = project
+        .library_sources
+        .iter()
+        .chain(project.targets.iter().flat_map(|target| &target.sources))
+        .chain(
+            packages
+                .iter()
+                .flat_map(|package| &package.project.library_sources),
+        );
+
+    for (_, lang) in all_sources {
+        let name = match lang {
+            Language::C => "C",
+            Language::Cxx => "CXX",
+            Language::Asm | Language::AsmCpp => "ASM",
+        };
+
+        if !languages.contains(&name) {
+            languages.push(name);
+        }
+    }
+
+    let version = meta
+        .version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or(&meta.version);
+
+    let mut cmake = format!(
+        "{} cmake from cedar.toml, do not edit.\n\ncmake_minimum_required(VERSION 3.16)\nproject({} VERSION {} LANGUAGES {})\n",
+        GENERATED,
+        quote(&meta.name),
+        version,
+        languages.join(" ")
+    );
+
+    if project
+        .targets
+        .iter()
+        .any(|target| target.kind == TargetKind::Test)
+    {
+        cmake.push_str("enable_testing()\n");
+    }
+
+    // Dependencies.
+    for package in packages {
+        let name = &package.name;
+        let dependency_root = &package.project.root;
+
+        cmake.push('\n');
+
+        if package.cmake.is_some() {
+            cmake.push_str(&format!(
+                "# {} builds with its own CMakeLists.txt, which is expected to define a target of that name.\n",
+                name
+            ));
+            for arg in package.cmake.iter().flatten() {
+                if let Some((variable, value)) =
+                    arg.strip_prefix("-D").and_then(|arg| arg.split_once('='))
+                {
+                    let variable = variable.split(':').next().unwrap_or(variable);
+                    cmake.push_str(&format!(
+                        "set({} {} CACHE INTERNAL \"\")\n",
+                        variable,
+                        quote(value)
+                    ));
+                }
+            }
+            cmake.push_str(&format!(
+                "add_subdirectory({} ${{CMAKE_BINARY_DIR}}/deps/{} EXCLUDE_FROM_ALL)\n",
+                path(root, dependency_root),
+                name
+            ));
+            continue;
+        }
+
+        cmake.push_str(&format!("add_library({} STATIC\n", quote(name)));
+        for (source, _) in &package.project.library_sources {
+            cmake.push_str(&format!("    {}\n", path(root, source)));
+        }
+        cmake.push_str(")\n");
+        cmake.push_str(&format!(
+            "target_include_directories({} PUBLIC {})\n",
+            quote(name),
+            path(root, &package.project.include_dir)
+        ));
+        cmake.push_str(&flags(name, "PRIVATE", &package.project.manifest));
+        cmake.push_str(&links(
+            name,
+            "PUBLIC",
+            &package.project.manifest,
+            packages,
+            &package
+                .project
+                .manifest
+                .dependencies
+                .packages
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+        ));
+    }
+
+    // System libraries.
+    let mut system_targets = Vec::new();
+
+    if !manifest.system_deps.is_empty() {
+        cmake.push('\n');
+
+        if manifest
+            .system_deps
+            .iter()
+            .any(|(name, dependency)| pkg_config_module(name, dependency).is_some())
+        {
+            cmake.push_str("find_package(PkgConfig REQUIRED)\n");
+        }
+
+        for (name, dependency) in &manifest.system_deps {
+            match (pkg_config_module(name, dependency), dependency) {
+                (Some(module), _) => {
+                    let prefix = name
+                        .to_ascii_uppercase()
+                        .replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+                    let requirement = dependency
+                        .version()
+                        .filter(|version| {
+                            !version.contains(',') && version.starts_with(['<', '>', '='])
+                        })
+                        .unwrap_or_default();
+
+                    cmake.push_str(&format!(
+                        "pkg_check_modules({} REQUIRED IMPORTED_TARGET {})\n",
+                        prefix,
+                        quote(&format!("{}{}", module, requirement.replace(' ', "")))
+                    ));
+                    system_targets.push(format!("PkgConfig::{}", prefix));
+                }
+                (None, SystemDependency::Detailed { lib: Some(lib), .. }) => {
+                    system_targets.push(lib.clone())
+                }
+                (None, _) => {}
+            }
+        }
+    }
+
+    if !manifest.dependencies.conan.is_empty() || !manifest.dependencies.vcpkg.is_empty() {
+        cmake.push_str("\n# Conan packages and vcpkg ports are not exported, provide them through find_package.\n");
+    }
+
+    // The library sources of the project, compiled once. A project without
+    // any only passes on its include directory and flags.
+    let objects = format!("{}-objects", meta.name);
+    let dependency_names: Vec<String> = manifest.dependencies.packages.keys().cloned().collect();
+    let library_sources = library_sources(project);
+    let (type_, scope) = match library_sources.is_empty() {
+        true => ("INTERFACE", "INTERFACE"),
+        false => ("OBJECT", "PUBLIC"),
+    };
+
+    // What cedar generates into build/ goes to the binary directory instead,
+    // written when configuring and only touched when it changed.
+    let generated = generated_sources(project, packages)?;
+
+    if !generated.is_empty() {
+        cmake.push_str("\nfunction(cedar_generate output content)\n    file(WRITE \"${output}.in\" \"${content}\")\n    configure_file(\"${output}.in\" \"${output}\" COPYONLY)\nendfunction()\n");
+        for (file, contents) in &generated {
+            cmake.push_str(&format!(
+                "cedar_generate({} {})\n",
+                cmake_path(project, file),
+                bracket(contents)
+            ));
+        }
+    }
+
+    cmake.push_str(&format!("\nadd_library({} {}\n", quote(&objects), type_));
+    for (source, _) in &library_sources {
+        cmake.push_str(&format!("    {}\n", cmake_path(project, source)));
+    }
+    cmake.push_str(")\n");
+    cmake.push_str(&format!(
+        "target_include_directories({} {} {})\n",
+        quote(&objects),
+        scope,
+        path(root, &project.include_dir)
+    ));
+    cmake.push_str(&flags(&objects, scope, manifest));
+    cmake.push_str(&links(
+        &objects,
+        scope,
+        manifest,
+        packages,
+        &dependency_names,
+    ));
+
+    if !system_targets.is_empty() {
+        cmake.push_str(&format!(
+            "target_link_libraries({} {} {})\n",
+            quote(&objects),
+            scope,
+            system_targets.join(" ")
+        ));
+    }
+
+    if meta.kind.is_shared() && scope == "PUBLIC" {
+        cmake.push_str(&format!(
+            "set_target_properties({} PROPERTIES POSITION_INDEPENDENT_CODE ON)\n",
+            quote(&objects)
+        ));
+    }
+
+    // A header-only library has no library target, consumers link the
+    // interface library by the name of the project.
+    if meta.kind.is_header_only() {
+        cmake.push_str(&format!(
+            "add_library({} ALIAS {})\n",
+            quote(&meta.name),
+            quote(&objects)
+        ));
+    }
+
+    // The targets.
+
+    for target in &project.targets {
+        cmake.push('\n');
+
+        if target.kind == TargetKind::Lib {
+            cmake.push_str(&library_targets(target, manifest, &objects));
+            continue;
+        }
+
+        let sources = target_sources(project, target)?;
+
+        let exclude = match target.kind {
+            TargetKind::Example | TargetKind::Bench => " EXCLUDE_FROM_ALL",
+            _ => "",
+        };
+
+        cmake.push_str(&format!(
+            "add_executable({}{}\n",
+            quote(&target.name),
+            exclude
+        ));
+        for (source, _) in &sources {
+            cmake.push_str(&format!("    {}\n", cmake_path(project, source)));
+        }
+        cmake.push_str(")\n");
+        cmake.push_str(&format!(
+            "target_link_libraries({} PRIVATE {})\n",
+            quote(&target.name),
+            quote(&objects)
+        ));
+
+        if target.kind == TargetKind::Test {
+            cmake.push_str(&format!(
+                "target_include_directories({} PRIVATE {})\n",
+                quote(&target.name),
+                cmake_path(project, &project.build_dir.join("include"))
+            ));
+            cmake.push_str(&format!(
+                "add_test(NAME {0} COMMAND {0} WORKING_DIRECTORY ${{CMAKE_CURRENT_SOURCE_DIR}})\n",
+                quote(&target.name)
+            ));
+        }
+
+        if !target.output_dir().is_empty() {
+            cmake.push_str(&format!(
+                "set_target_properties({} PROPERTIES RUNTIME_OUTPUT_DIRECTORY ${{CMAKE_BINARY_DIR}}/{})\n",
+                quote(&target.name),
+                target.output_dir()
+            ));
+        }
+    }
+
+    Ok(cmake)
+}
+
+/// The static and shared libraries of a library project, built from the
+/// object library.
+fn library_targets(target: &Target, manifest: &Manifest, objects: &str) -> String {
+    let name = &target.name;
+    let kind = manifest.meta.kind;
+    let mut cmake = String::new();
+
+    let mut add = |library: &str, type_: &str| {
+        cmake.push_str(&format!("add_library({} {})\n", quote(library), type_));
+        cmake.push_str(&format!(
+            "target_link_libraries({} PUBLIC {})\n",
+            quote(library),
+            quote(objects)
+        ));
+        cmake.push_str(&format!(
+            "set_target_properties({} PROPERTIES OUTPUT_NAME {})\n",
+            quote(library),
+            quote(name)
+        ));
+    };
+
+    if kind.is_static() {
+        add(name, "STATIC");
+    }
+
+    if kind.is_shared() {
+        let shared = match kind.is_static() {
+            true => format!("{}-shared", name),
+            false => name.clone(),
+        };
+
+        add(&shared, "SHARED");
+
+        if let Some(abi_version) = &manifest.build.abi_version {
+            cmake.push_str(&format!(
+                "set_target_properties({} PROPERTIES VERSION {} SOVERSION {})\n",
+                quote(&shared),
+                abi_version,
+                abi_version.split('.').next().unwrap_or_default()
+            ));
+        }
+    }
+
+    cmake
+}
+
+/// The compile definitions and options of a manifest, per language.
+fn flags(target: &str, scope: &str, manifest: &Manifest) -> String {
+    let build = &manifest.build;
+    let mut cmake = String::new();
+
+    let definitions: Vec<&str> = build
+        .cflags
+        .iter()
+        .chain(&build.cxxflags)
+        .filter_map(|flag| flag.strip_prefix("-D"))
+        .collect();
+
+    if !definitions.is_empty() {
+        cmake.push_str(&format!(
+            "target_compile_definitions({} {} {})\n",
+            quote(target),
+            scope,
+            definitions
+                .iter()
+                .map(|definition| quote(definition))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+    }
+
+    let options: Vec<String> = [
+        ("C", &build.cflags),
+        ("CXX", &build.cxxflags),
+        ("ASM", &build.asflags),
+    ]
+    .into_iter()
+    .flat_map(|(lang, flags)| {
+        flags
+            .iter()
+            .filter(|flag| !flag.starts_with("-D"))
+            .map(move |flag| quote(&format!("$<$<COMPILE_LANGUAGE:{}>:{}>", lang, flag)))
+    })
+    .collect();
+
+    if !options.is_empty() {
+        cmake.push_str(&format!(
+            "target_compile_options({} {} {})\n",
+            quote(target),
+            scope,
+            options.join(" ")
+        ));
+    }
+
+    cmake
+}
+
+/// The libraries and link options of a manifest, and the dependencies it
+/// links with.
+fn links(
+    target: &str,
+    scope: &str,
+    manifest: &Manifest,
+    packages: &[Package],
+    dependencies: &[String],
+) -> String {
+    let mut libraries: Vec<String> = dependencies
+        .iter()
+        .filter(|name| packages.iter().any(|package| package.name == **name))
+        .map(|name| quote(name))
+        .collect();
+    let mut directories = Vec::new();
+    let mut options = Vec::new();
+
+    for flag in &manifest.build.ldflags {
+        if let Some(library) = flag.strip_prefix("-l") {
+            libraries.push(quote(library));
+        } else if let Some(directory) = flag.strip_prefix("-L") {
+            directories.push(quote(directory));
+        } else {
+            options.push(quote(flag));
+        }
+    }
+
+    let mut cmake = String::new();
+
+    for (command, values) in [
+        ("target_link_directories", directories),
+        ("target_link_libraries", libraries),
+        ("target_link_options", options),
+    ] {
+        if !values.is_empty() {
+            cmake.push_str(&format!(
+                "{}({} {} {})\n",
+                command,
+                quote(target),
+                scope,
+                values.join(" ")
+            ));
+        }
+    }
+
+    cmake
+}
+
+/// Generates a Makefile following the build plan of cedar: a rule per
+/// translation unit producing the same object and depfile, the library
+/// sources of the project and of its dependencies linked into every target,
+/// and phony targets for each kind of target.
+fn makefile(project: &Project, packages: &[Package]) -> Result<String, CedarError> {
+    let manifest = &project.manifest;
+    let meta = &manifest.meta;
+    let root = &project.root;
+    let build_path = &project.build_dir;
+    let out_path = &project.out_dir();
+    let toolchain = Toolchain::from_compiler(&manifest.build.compiler)?;
+
+    let units: Vec<(&Project, PathBuf)> = std::iter::once((project, out_path.join("obj")))
+        .chain(packages.iter().map(|package| {
+            (
+                &package.project,
+                out_path.join("deps").join("obj").join(&package.name),
+            )
+        }))
+        .collect();
+
+    // System libraries are found with pkg-config when make runs, libraries
+    // that are only probed for are linked by name.
+    let mut modules = Vec::new();
+    let mut system_libs = Vec::new();
+
+    for (name, dependency) in units.iter().flat_map(|(unit, _)| {
+        unit.manifest
+            .system_deps
+            .iter()
+            .filter(|(name, dependency)| unit.uses(name, dependency.is_optional()))
+    }) {
+        match (pkg_config_module(name, dependency), dependency) {
+            (Some(module), _) => modules.push(module),
+            (None, SystemDependency::Detailed { lib: Some(lib), .. }) => {
+                system_libs.push(format!("-l{}", lib))
+            }
+            (None, _) => {}
+        }
+    }
+
+    modules.sort();
+    modules.dedup();
+
+    let mut system_cflags = Vec::new();
+
+    if !modules.is_empty() {
+        system_cflags.push(format!(
+            "$(shell pkg-config --cflags {})",
+            modules.join(" ")
+        ));
+        system_libs.insert(
+            0,
+            format!("$(shell pkg-config --libs {})", modules.join(" ")),
+        );
+    }
+
+    // Dependencies built with CMake are installed into their prefix before
+    // anything is compiled, and linked with what they installed.
+    let mut stamps = Vec::new();
+    let mut cmake_rules = String::new();
+
+    for package in packages.iter().filter(|package| package.cmake.is_some()) {
+        let prefix = cmake::prefix(build_path, &package.name);
+        let binary = relative(root, &prefix.with_file_name("build"));
+        let stamp = format!("{}/.installed", relative(root, &prefix));
+
+        cmake_rules.push_str(&format!(
+            "\n{}:\n\tcmake -S {} -B {} -DCMAKE_BUILD_TYPE=Release -DCMAKE_INSTALL_PREFIX=$(abspath {}) -DCMAKE_INSTALL_LIBDIR=lib -DBUILD_SHARED_LIBS=OFF -DCMAKE_POSITION_INDEPENDENT_CODE=ON -DCMAKE_C_COMPILER=$(CC){}\n\tcmake --build {} --parallel\n\tcmake --install {}\n\ttouch $@\n",
+            stamp,
+            relative(root, &package.project.root),
+            binary,
+            relative(root, &prefix),
+            package
+                .cmake
+                .iter()
+                .flatten()
+                .map(|arg| format!(" {}", arg))
+                .collect::<String>(),
+            binary,
+            binary
+        ));
+
+        system_libs.push(format!("-L{}/lib", relative(root, &prefix)));
+        if let Ok(library) = sysdeps::prefix_library(&package.name, &prefix) {
+            system_libs.extend(
+                library
+                    .libs
+                    .into_iter()
+                    .filter(|lib| !lib.starts_with("-L")),
+            );
+        }
+
+        stamps.push(stamp);
+    }
+
+    let dependency_includes: Vec<String> = packages
+        .iter()
+        .map(|package| format!("-I{}", relative(root, &package.project.include_dir)))
+        .collect();
+
+    let ldflags: Vec<&String> = units
+        .iter()
+        .flat_map(|(unit, _)| &unit.manifest.build.ldflags)
+        .collect();
+
+    // The translation units, as in build::build(): the library sources of
+    // the project and of every dependency, then the sources of each target.
+    let library_sources = library_sources(project);
+    let target_sources = project
+        .targets
+        .iter()
+        .map(|target| target_sources(project, target))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // What cedar generates into build/ is written by rules of its own, so
+    // the Makefile builds from the sources alone.
+    let harness_header = relative(
+        root,
+        &build_path.join("include").join("cedar").join("test.h"),
+    );
+    let mut generate_rules = String::new();
+
+    for (file, contents) in generated_sources(project, packages)? {
+        generate_rules.push_str(&format!(
+            "\n{}: Makefile\n\t@mkdir -p $(@D)\n\t@rm -f $@\n",
+            relative(root, &file)
+        ));
+        for line in contents.lines() {
+            generate_rules.push_str(&format!(
+                "\t@printf '%s\\n' '{}' >> $@\n",
+                line.replace('\'', r"'\''").replace('$', "$$")
+            ));
+        }
+    }
+
+    let (main_unit, dependency_units) = units.split_first().expect("the project is a unit");
+    let pic = meta.kind.is_shared();
+
+    let sources = library_sources
+        .iter()
+        .map(|source| (source, main_unit, pic, false))
+        .chain(dependency_units.iter().flat_map(|unit| {
+            unit.0
+                .library_sources
+                .iter()
+                .map(move |source| (source, unit, pic, false))
+        }))
+        .chain(
+            project
+                .targets
+                .iter()
+                .zip(&target_sources)
+                .flat_map(|(target, sources)| {
+                    let harness = target.kind == TargetKind::Test;
+                    sources
+                        .iter()
+                        .map(move |source| (source, main_unit, false, harness))
+                }),
+        );
+
+    let mut objects = Vec::new();
+    let mut compile_rules = String::new();
+
+    for ((source, lang), (unit, unit_object_path), pic, harness) in sources {
+        let (object, depfile) = build::object_files(
+            unit_object_path,
+            source.strip_prefix(&unit.root).unwrap_or(source),
+        );
+        let object = relative(root, &object);
+
+        // The project's own flags can be overridden on the command line.
+        let main = unit.root == *root;
+        let (driver, flags) = match lang {
+            Language::C => ("$(CC)", ("$(CFLAGS)", &unit.manifest.build.cflags)),
+            Language::Cxx => ("$(CXX)", ("$(CXXFLAGS)", &unit.manifest.build.cxxflags)),
+            Language::Asm | Language::AsmCpp => {
+                ("$(CC)", ("$(ASFLAGS)", &unit.manifest.build.asflags))
+            }
+        };
+        let flags = match main {
+            true => flags.0.to_owned(),
+            false => flags.1.join(" "),
+        };
+
+        let mut command = format!(
+            "{} -c $< -o $@ -I{} $(DEPENDENCY_INCLUDES) $(SYSTEM_CFLAGS)",
+            driver,
+            relative(root, &unit.include_dir)
+        );
+        if harness {
+            command.push_str(&format!(
+                " -I{}",
+                relative(root, &build_path.join("include"))
+            ));
+        }
+        if !flags.is_empty() {
+            command.push_str(&format!(" {}", flags));
+        }
+        if pic {
+            command.push_str(" -fPIC");
+        }
+        command.push_str(&format!(" -MMD -MP -MF {}", relative(root, &depfile)));
+
+        compile_rules.push_str(&format!(
+            "\n{}: {}{}{}\n\t@mkdir -p $(@D)\n\t{}\n",
+            object,
+            relative(root, source),
+            match harness {
+                true => format!(" {}", harness_header),
+                false => String::new(),
+            },
+            match stamps.is_empty() {
+                true => String::new(),
+                false => format!(" | {}", stamps.join(" ")),
+            },
+            command
+        ));
+
+        objects.push(object);
+    }
+
+    let library_objects: Vec<String> = objects
+        .iter()
+        .take(
+            library_sources.len()
+                + dependency_units
+                    .iter()
+                    .map(|(unit, _)| unit.library_sources.len())
+                    .sum::<usize>(),
+        )
+        .cloned()
+        .collect();
+    let mut target_objects = objects[library_objects.len()..].iter();
+
+    // C++ objects need the C++ runtime, which only the C++ driver links.
+    let library_cxx = units
+        .iter()
+        .flat_map(|(unit, _)| &unit.library_sources)
+        .any(|(_, lang)| *lang == Language::Cxx);
+
+    let mut groups: [(&str, Vec<String>); 4] = [
+        ("all", Vec::new()),
+        ("examples", Vec::new()),
+        ("tests", Vec::new()),
+        ("benches", Vec::new()),
+    ];
+    let mut link_rules = String::new();
+
+    for (target, sources) in project.targets.iter().zip(&target_sources) {
+        let own: Vec<&String> = target_objects.by_ref().take(sources.len()).collect();
+
+        let linker = match library_cxx || sources.iter().any(|(_, lang)| *lang == Language::Cxx) {
+            true => "$(CXX)",
+            false => "$(CC)",
+        };
+
+        let group = match target.kind {
+            TargetKind::Bin | TargetKind::Lib => 0,
+            TargetKind::Example => 1,
+            TargetKind::Test => 2,
+            TargetKind::Bench => 3,
+        };
+
+        for artifact in target.artifacts(manifest)? {
+            let output = relative(
+                root,
+                &out_path.join(target.output_dir()).join(artifact.name()),
+            );
+
+            let recipe = match &artifact {
+                Artifact::Executable(_) => {
+                    format!("{} $^ -o $@ $(LDFLAGS) $(SYSTEM_LIBS)", linker)
+                }
+                Artifact::StaticLib(_) => String::from("rm -f $@\n\t$(AR) rcs $@ $^"),
+                Artifact::SharedLib { name, soname } => {
+                    let mut recipe = format!(
+                        "{} -shared{} $^ -o $@ $(LDFLAGS) $(SYSTEM_LIBS)",
+                        linker,
+                        soname
+                            .iter()
+                            .map(|soname| format!(" -Wl,-soname,{}", soname))
+                            .collect::<String>()
+                    );
+                    if let Some(soname) = soname {
+                        recipe.push_str(&format!(
+                            "\n\tln -sf {} $(@D)/{}\n\tln -sf {} $(@D)/lib{}.so",
+                            name, soname, soname, meta.name
+                        ));
+                    }
+                    recipe
+                }
+            };
+
+            link_rules.push_str(&format!(
+                "\n{}: $(LIBRARY_OBJECTS){}\n\t@mkdir -p $(@D)\n\t{}\n",
+                output,
+                own.iter()
+                    .map(|object| format!(" {}", object))
+                    .collect::<String>(),
+                recipe
+            ));
+
+            groups[group].1.push(output);
+        }
+    }
+
+    let mut make = format!(
+        "{} make from cedar.toml, do not edit.\n#\n# Builds {} v{} without cedar, override the tools and flags on the\n# command line, e.g. make CC=clang CFLAGS=-O2.\n\n",
+        GENERATED, meta.name, meta.version
+    );
+
+    make.push_str(&format!(
+        "CC = {}\nCXX = {}\nAR = {}\n\n",
+        toolchain.cc, toolchain.cxx, toolchain.ar
+    ));
+    make.push_str(&variable("CFLAGS", &manifest.build.cflags));
+    make.push_str(&variable("CXXFLAGS", &manifest.build.cxxflags));
+    make.push_str(&variable("ASFLAGS", &manifest.build.asflags));
+    make.push_str(&variable("LDFLAGS", &ldflags));
+
+    if !manifest.dependencies.conan.is_empty() || !manifest.dependencies.vcpkg.is_empty() {
+        make.push_str("\n# Conan packages and vcpkg ports are not exported, add their flags to\n# SYSTEM_CFLAGS and SYSTEM_LIBS.\n");
+    }
+
+    make.push('\n');
+    make.push_str(&variable("DEPENDENCY_INCLUDES", &dependency_includes));
+    make.push_str(&variable("SYSTEM_CFLAGS", &system_cflags));
+    make.push_str(&variable("SYSTEM_LIBS", &system_libs));
+
+    make.push('\n');
+    make.push_str(&list("LIBRARY_OBJECTS", &library_objects));
+    make.push_str(&list("OBJECTS", &objects));
+
+    make.push_str("\n.PHONY: all examples tests benches check clean\n");
+
+    for (group, outputs) in &groups {
+        make.push_str(&format!(
+            "\n{}:{}\n",
+            group,
+            outputs
+                .iter()
+                .map(|output| format!(" {}", output))
+                .collect::<String>()
+        ));
+    }
+
+    make.push_str("\ncheck: tests\n");
+    for test in &groups[2].1 {
+        make.push_str(&format!("\t./{}\n", test));
+    }
+
+    make.push_str(&format!(
+        "\nclean:\n\trm -f $(OBJECTS) $(OBJECTS:.o=.d){}\n",
+        groups
+            .iter()
+            .flat_map(|(_, outputs)| outputs)
+            .map(|output| format!(" {}", output))
+            .collect::<String>()
+    ));
+
+    make.push_str(&cmake_rules);
+    make.push_str(&generate_rules);
+    make.push_str(&compile_rules);
+    make.push_str(&link_rules);
+    make.push_str("\n-include $(OBJECTS:.o=.d)\n");
+
+    Ok(make)
+}
+
+/// Generates a flake.nix whose default package builds the project with the
+/// Makefile of makefile(): with the stdenv of the compiler, the system
+/// dependencies from nixpkgs, and git dependencies fetched at their locked
+/// commits. Binaries, libraries, headers, and a pkg-config file for libraries
+/// are installed like cedar install does.
+fn flake(project: &Project, packages: &[Package], stdenv: &str) -> Result<String, CedarError> {
+    let manifest = &project.manifest;
+    let meta = &manifest.meta;
+    let root = &project.root;
+    let build_path = &project.build_dir;
+    let out_path = &project.out_dir();
+
+    // System dependencies.
+    let (libraries, pkg_config_used) = system_packages(project, packages, sysdeps::nix_package);
+    let build_inputs: Vec<String> = libraries
+        .iter()
+        .map(|library| format!("pkgs.{}", library))
+        .collect();
+    let mut native_build_inputs = Vec::new();
+
+    if pkg_config_used {
+        native_build_inputs.push(String::from("pkgs.pkg-config"));
+    }
+    if packages.iter().any(|package| package.cmake.is_some()) {
+        native_build_inputs.push(String::from("pkgs.cmake"));
+    }
+
+    // Git dependencies are not part of the sources, they are fetched where
+    // cedar would have checked them out.
+    let mut fetches = String::new();
+    let mut comments = String::new();
+
+    for package in packages {
+        let dir = ninja::quote(&relative(root, &package.project.root));
+
+        match package
+            .source
+            .strip_prefix("git+")
+            .and_then(|source| source.rsplit_once('#'))
+        {
+            Some((url, commit)) => {
+                let url = url.split_once('?').map_or(url, |(url, _)| url);
+                fetches.push_str(&format!(
+                    "            mkdir -p $(dirname {dir})\n            cp -r ${{builtins.fetchGit {{ url = {}; rev = {}; allRefs = true; }}}} {dir}\n            chmod -R u+w {dir}\n",
+                    nix_string(url),
+                    nix_string(commit),
+                    dir = indented(&dir)
+                ));
+            }
+            None if package.project.root.starts_with(root) => {}
+            None => comments.push_str(&format!(
+                "# The path dependency {} is outside the project, copy it into the\n# project for the derivation to build.\n",
+                package.name
+            )),
+        }
+    }
+
+    if !manifest.dependencies.conan.is_empty() || !manifest.dependencies.vcpkg.is_empty() {
+        comments.push_str(
+            "# Conan packages and vcpkg ports are not exported, add their nixpkgs\n# attributes to buildInputs.\n",
+        );
+    }
+
+    // The artifacts installed, as in install::install().
+    let mut install = vec![String::from("mkdir -p $out")];
+
+    for target in project
+        .targets
+        .iter()
+        .filter(|target| target.kind.is_default())
+    {
+        for artifact in target.artifacts(manifest)? {
+            let output = indented(&ninja::quote(&relative(
+                root,
+                &out_path.join(target.output_dir()).join(artifact.name()),
+            )));
+
+            match artifact {
+                Artifact::Executable(_) => {
+                    install.push(format!("install -Dm755 {} -t $out/bin", output))
+                }
+                Artifact::StaticLib(_) => {
+                    install.push(format!("install -Dm644 {} -t $out/lib", output))
+                }
+                Artifact::SharedLib { name, soname } => {
+                    install.push(format!("install -Dm755 {} -t $out/lib", output));
+                    if let Some(soname) = soname {
+                        install.push(format!("ln -s {} $out/lib/{}", name, soname));
+                        install.push(format!("ln -s {} $out/lib/lib{}.so", soname, meta.name));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut files = format!(
+        "      # The Makefile of cedar export make.\n      makefile = builtins.toFile \"Makefile\" ''\n{}'';\n",
+        indented(&makefile(project, packages)?)
+    );
+
+    if !meta.kind.is_bin() {
+        if project.include_dir.is_dir() {
+            install.push(format!(
+                "mkdir -p $out/include && cp -r {}/. $out/include/",
+                indented(&ninja::quote(&relative(root, &project.include_dir)))
+            ));
+        }
+
+        install.push(format!(
+            "mkdir -p $out/lib/pkgconfig && sed \"s|@out@|$out|\" ${{pc}} > $out/lib/pkgconfig/{}.pc",
+            meta.name
+        ));
+        files.push_str(&format!(
+            "      pc = builtins.toFile \"{}.pc\" ''\n{}'';\n",
+            meta.name,
+            indented(&pkg_config(manifest, Path::new("@out@"), "lib"))
+        ));
+    }
+
+    let mut nix = format!(
+        "{} nix from cedar.toml, do not edit.\n#\n# nix build builds {} v{} without cedar, nix develop opens a shell with\n# its compiler and dependencies.\n{}{{\n  description = {};\n\n  inputs.nixpkgs.url = {};\n\n  outputs = {{ self, nixpkgs }}:\n    let\n      systems = [ \"x86_64-linux\" \"aarch64-linux\" \"x86_64-darwin\" \"aarch64-darwin\" ];\n      forAllSystems = f: nixpkgs.lib.genAttrs systems (system: f nixpkgs.legacyPackages.${{system}});\n\n{}    in\n    {{\n",
+        GENERATED,
+        meta.name,
+        meta.version,
+        match comments.is_empty() {
+            true => String::new(),
+            false => format!("#\n{}", comments),
+        },
+        nix_string(meta.description.as_deref().unwrap_or(&meta.name)),
+        nix_string(NIXPKGS),
+        files
+    );
+
+    nix.push_str(&format!(
+        "      packages = forAllSystems (pkgs: {{\n        default = pkgs.{}.mkDerivation {{\n          pname = {};\n          version = {};\n          src = self;\n\n          nativeBuildInputs = [{}];\n          buildInputs = [{}];\n\n",
+        stdenv,
+        nix_string(&meta.name),
+        nix_string(&meta.version),
+        native_build_inputs
+            .iter()
+            .map(|input| format!(" {}", input))
+            .collect::<String>()
+            + if native_build_inputs.is_empty() { "" } else { " " },
+        build_inputs
+            .iter()
+            .map(|input| format!(" {}", input))
+            .collect::<String>()
+            + if build_inputs.is_empty() { "" } else { " " },
+    ));
+
+    // Leftovers of builds outside nix would look up to date to make.
+    nix.push_str(&format!(
+        "          buildPhase = ''\n            runHook preBuild\n            rm -rf {}\n{}            make -f ${{makefile}} -j$NIX_BUILD_CORES CC=cc CXX=c++ AR=ar all\n            runHook postBuild\n          '';\n\n",
+        indented(&ninja::quote(&relative(root, build_path))),
+        fetches
+    ));
+
+    nix.push_str(&format!(
+        "          installPhase = ''\n            runHook preInstall\n{}            runHook postInstall\n          '';\n",
+        install
+            .iter()
+            .map(|line| format!("            {}\n", line))
+            .collect::<String>()
+    ));
+
+    nix.push_str("        };\n      });\n\n      devShells = forAllSystems (pkgs: {\n        default = pkgs.mkShell {\n          inputsFrom = [ self.packages.${pkgs.system}.default ];\n        };\n      });\n    };\n}\n");
+
+    Ok(nix)
+}
+
+/// Generates a multi-stage Dockerfile: the project is built with the
+/// Makefile of makefile() in a Debian image with the packages of its system
+/// dependencies, then its executables and the shared libraries they load
+/// are copied into a slim runtime image.
+fn dockerfile(project: &Project, packages: &[Package]) -> Result<String, CedarError> {
+    let manifest = &project.manifest;
+    let meta = &manifest.meta;
+    let docker = &manifest.package.docker;
+    let root = &project.root;
+    let build_path = &project.build_dir;
+    let out_path = &project.out_dir();
+
+    let bins: Vec<&Target> = project
+        .targets
+        .iter()
+        .filter(|target| target.kind == TargetKind::Bin)
+        .collect();
+    let entrypoint = match project.runnable(None) {
+        Ok(target) => target,
+        Err(BuildError::AmbiguousExecutable(_)) => bins[0],
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut executables = Vec::new();
+    let mut command = String::new();
+
+    for target in &bins {
+        for artifact in target.artifacts(manifest)? {
+            if let Artifact::Executable(name) = artifact {
+                let output = relative(root, &out_path.join(target.output_dir()).join(&name));
+
+                if target.name == entrypoint.name {
+                    command = format!("/usr/local/bin/{}", name);
+                }
+                executables.push(output);
+            }
+        }
+    }
+
+    let (libraries, pkg_config_used) = system_packages(project, packages, sysdeps::apt_package);
+    let mut apt = vec![String::from("build-essential")];
+
+    if manifest.build.compiler.eq_ignore_ascii_case("clang") {
+        apt.push(String::from("clang"));
+    }
+    if pkg_config_used {
+        apt.push(String::from("pkg-config"));
+    }
+    if packages.iter().any(|package| package.cmake.is_some()) {
+        apt.push(String::from("cmake"));
+    }
+    apt.extend(libraries);
+
+    let image = docker.image.as_deref().unwrap_or(DOCKER_IMAGE);
+    let runtime = docker.runtime.as_deref().unwrap_or(DOCKER_RUNTIME);
+
+    let mut file = format!(
+        "{} docker from cedar.toml, do not edit.\n#\n# Builds {} v{} in {} and copies its executables into\n# {}: docker build -t {} .\n",
+        GENERATED, meta.name, meta.version, image, runtime, meta.name
+    );
+
+    for package in packages {
+        if !package.project.root.starts_with(root) {
+            file.push_str(&format!(
+                "#\n# The path dependency {} is outside the project, copy it into the\n# project for the image to build.\n",
+                package.name
+            ));
+        }
+    }
+
+    if !manifest.dependencies.conan.is_empty() || !manifest.dependencies.vcpkg.is_empty() {
+        file.push_str("#\n# Conan packages and vcpkg ports are not exported, install their Debian\n# packages in the build stage.\n");
+    }
+
+    file.push_str(&format!(
+        "\nFROM {} AS build\n\nRUN apt-get update \\\n    && apt-get install -y --no-install-recommends {} \\\n    && rm -rf /var/lib/apt/lists/*\n",
+        image,
+        apt.join(" ")
+    ));
+
+    file.push_str(&format!(
+        "\n# The Makefile of cedar export make.\nCOPY <<\"CEDAR_MAKEFILE\" /cedar.mk\n{}CEDAR_MAKEFILE\n",
+        makefile(project, packages)?
+    ));
+
+    // Objects of builds outside the image would look up to date to make.
+    file.push_str(&format!(
+        "\nWORKDIR /src\nCOPY . .\n\nRUN rm -rf {} \\\n    && make -f /cedar.mk -j\"$(nproc)\" all\n",
+        [
+            out_path.join("obj"),
+            out_path.join("deps").join("obj"),
+            build_path.join("deps").join("cmake"),
+        ]
+        .iter()
+        .map(|dir| ninja::quote(&relative(root, dir)))
+        .collect::<Vec<_>>()
+        .join(" ")
+    ));
+
+    file.push_str(&format!(
+        "\n# The shared libraries the executables load, besides the ones of libc.\nRUN mkdir -p /runtime \\\n    && ldd {} | awk '/=> \\// {{ print $3 }}' \\\n    | grep -Ev '/(libc|libm|libdl|libpthread|librt)\\.so|/ld-linux' \\\n    | sort -u | xargs -r cp -L -t /runtime\n",
+        executables
+            .iter()
+            .map(|executable| ninja::quote(executable))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+
+    file.push_str(&format!("\nFROM {}\n\n", runtime));
+
+    let mut labels = vec![
+        ("title", meta.name.as_str()),
+        ("version", meta.version.as_str()),
+    ];
+    labels.extend(
+        meta.description
+            .as_deref()
+            .map(|value| ("description", value)),
+    );
+    labels.extend(meta.license.as_deref().map(|value| ("licenses", value)));
+
+    file.push_str(&format!(
+        "LABEL {}\n",
+        labels
+            .iter()
+            .map(|(key, value)| format!("org.opencontainers.image.{}={}", key, json_string(value)))
+            .collect::<Vec<_>>()
+            .join(" \\\n      ")
+    ));
+
+    file.push_str(&format!(
+        "\nCOPY --from=build /runtime/ /usr/local/lib/\nRUN ldconfig\nCOPY --from=build [{}, \"/usr/local/bin/\"]\n",
+        executables
+            .iter()
+            .map(|executable| json_string(&format!("/src/{}", executable)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+
+    if !docker.expose.is_empty() {
+        file.push_str(&format!(
+            "\nEXPOSE {}\n",
+            docker
+                .expose
+                .iter()
+                .map(u16::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+    }
+
+    file.push_str(&format!("\nENTRYPOINT [{}]\n", json_string(&command)));
+
+    Ok(file)
+}
+
+/// What the CI pipeline of a project runs, derived from the manifest and
+/// rendered by github_workflow() and gitlab_ci().
+///
+/// # Fields
+///
+/// * 'compilers' - The compilers of the matrix.
+/// * 'profiles' - The profiles of the matrix.
+/// * 'packages' - The Debian packages the build needs.
+/// * 'build' - The arguments of cedar build, after a space.
+/// * 'tests' - Whether the project has tests to run.
+/// * 'format' - Whether to check the formatting with clang-format.
+/// * 'tidy' - Whether to run clang-tidy.
+///
+struct CiPlan {
+    compilers: Vec<String>,
+    profiles: Vec<String>,
+    packages: Vec<String>,
+    build: &'static str,
+    tests: bool,
+    format: bool,
+    tidy: bool,
+}
+
+/// The C and C++ sources and headers checked by clang-format.
+const FORMATTED: &str = "'*.c' '*.h' '*.cc' '*.cpp' '*.cxx' '*.hh' '*.hpp'";
+
+/// Works out the CI pipeline of a project: the compilers and profiles of
+/// [ci], what to build from its targets, whether it has tests, and the
+/// lints its .clang-format and .clang-tidy ask for.
+fn ci_plan(project: &Project, packages: &[Package]) -> Result<CiPlan, CedarError> {
+    let manifest = &project.manifest;
+
+    let compilers = match manifest.ci.compilers.is_empty() {
+        true => vec![manifest.build.compiler.to_ascii_lowercase()],
+        false => manifest.ci.compilers.clone(),
+    };
+    let profiles = match manifest.ci.profiles.is_empty() {
+        true => vec![String::from("dev"), String::from("release")],
+        false => manifest.ci.profiles.clone(),
+    };
+
+    if let Some(unknown) = profiles
+        .iter()
+        .find(|profile| manifest.profile(profile).is_none())
+    {
+        return Err(BuildError::UnknownProfile(unknown.clone(), manifest.profile_names()).into());
+    }
+
+    let (libraries, pkg_config_used) = system_packages(project, packages, sysdeps::apt_package);
+    let mut apt = Vec::new();
+
+    if compilers
+        .iter()
+        .any(|compiler| compiler.eq_ignore_ascii_case("clang"))
+    {
+        apt.push(String::from("clang"));
+    }
+    if pkg_config_used {
+        apt.push(String::from("pkg-config"));
+    }
+    if packages.iter().any(|package| package.cmake.is_some()) {
+        apt.push(String::from("cmake"));
+    }
+    apt.extend(libraries);
+
+    let has = |kind: TargetKind| project.targets.iter().any(|target| target.kind == kind);
+
+    Ok(CiPlan {
+        compilers,
+        profiles,
+        packages: apt,
+        build: match has(TargetKind::Example) || has(TargetKind::Bench) {
+            true => " --all-targets",
+            false => "",
+        },
+        tests: has(TargetKind::Test),
+        format: project.root.join(".clang-format").exists(),
+        tidy: project.root.join(".clang-tidy").exists(),
+    })
+}
+
+/// The shell command installing cedar, from the repository it is built
+/// from.
+fn install_cedar() -> String {
+    format!(
+        "cargo install --locked --git {} cedar",
+        env!("CARGO_PKG_REPOSITORY")
+    )
+}
+
+/// The shell command pointing [build] compiler of cedar.toml at another
+/// compiler, as cedar builds with the compiler of the manifest.
+fn select_compiler(compiler: &str) -> String {
+    format!(
+        "sed -i \"s/^compiler *=.*/compiler = '{}'/\" cedar.toml",
+        compiler
+    )
+}
+
+/// A YAML flow sequence of strings, [a, b].
+fn yaml_list(values: &[String]) -> String {
+    format!(
+        "[{}]",
+        values
+            .iter()
+            .map(|value| json_string(value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Generates a GitHub Actions workflow building and testing the project
+/// with every compiler and profile of the matrix, and a lint job when the
+/// project has a .clang-format or .clang-tidy.
+fn github_workflow(project: &Project, plan: &CiPlan) -> String {
+    let meta = &project.manifest.meta;
+    let mut file = format!(
+        "{} ci from cedar.toml, do not edit.\n#\n# Builds and tests {} with {} in the profiles {}.\n\nname: CI\n\non:\n  push:\n  pull_request:\n\njobs:\n",
+        GENERATED,
+        meta.name,
+        plan.compilers.join(", "),
+        plan.profiles.join(", ")
+    );
+
+    let setup = |packages: &[String]| {
+        let mut steps = String::from(
+            "      - uses: actions/checkout@v4\n        with:\n          submodules: recursive\n",
+        );
+        if !packages.is_empty() {
+            steps.push_str(&format!(
+                "      - name: Install packages\n        run: sudo apt-get update && sudo apt-get install -y --no-install-recommends {}\n",
+                packages.join(" ")
+            ));
+        }
+        steps.push_str(&format!(
+            "      - name: Install cedar\n        run: {}\n",
+            install_cedar()
+        ));
+        steps
+    };
+
+    file.push_str(&format!(
+        "  build:\n    name: ${{{{ matrix.compiler }}}} ${{{{ matrix.profile }}}}\n    runs-on: ubuntu-latest\n    strategy:\n      fail-fast: false\n      matrix:\n        compiler: {}\n        profile: {}\n    steps:\n{}      - name: Select compiler\n        run: {}\n      - name: Build\n        run: cedar build{} --profile ${{{{ matrix.profile }}}}\n",
+        yaml_list(&plan.compilers),
+        yaml_list(&plan.profiles),
+        setup(&plan.packages),
+        select_compiler("${{ matrix.compiler }}"),
+        plan.build,
+    ));
+
+    if plan.tests {
+        file.push_str(
+            "      - name: Test\n        run: cedar test --profile ${{ matrix.profile }}\n",
+        );
+    }
+
+    if plan.format || plan.tidy {
+        let mut packages = plan.packages.clone();
+        packages.extend(plan.format.then(|| String::from("clang-format")));
+        packages.extend(plan.tidy.then(|| String::from("clang-tidy")));
+
+        file.push_str(&format!(
+            "\n  lint:\n    runs-on: ubuntu-latest\n    steps:\n{}",
+            setup(&packages)
+        ));
+        if plan.format {
+            file.push_str(&format!(
+                "      - name: Check formatting\n        run: git ls-files -z {} | xargs -0 -r clang-format --dry-run --Werror\n",
+                FORMATTED
+            ));
+        }
+        if plan.tidy {
+            file.push_str(&format!(
+                "      - name: clang-tidy\n        run: |\n          cedar intercept -- cedar build{}\n          run-clang-tidy -quiet -p .\n",
+                plan.build
+            ));
+        }
+    }
+
+    file
+}
+
+/// Generates a .gitlab-ci.yml building and testing the project with every
+/// compiler and profile of the matrix in parallel jobs, and a lint job when
+/// the project has a .clang-format or .clang-tidy.
+fn gitlab_ci(project: &Project, plan: &CiPlan) -> String {
+    let meta = &project.manifest.meta;
+    let mut file = format!(
+        "{} ci from cedar.toml, do not edit.\n#\n# Builds and tests {} with {} in the profiles {}.\n\nvariables:\n  GIT_SUBMODULE_STRATEGY: recursive\n\n.cedar:\n  image: rust:bookworm\n  before_script:\n",
+        GENERATED,
+        meta.name,
+        plan.compilers.join(", "),
+        plan.profiles.join(", ")
+    );
+
+    let mut packages = plan.packages.clone();
+    packages.extend(plan.format.then(|| String::from("clang-format")));
+    packages.extend(plan.tidy.then(|| String::from("clang-tidy")));
+    if !packages.is_empty() {
+        file.push_str(&format!(
+            "    - apt-get update && apt-get install -y --no-install-recommends {}\n",
+            packages.join(" ")
+        ));
+    }
+    file.push_str(&format!("    - {}\n", install_cedar()));
+
+    file.push_str(&format!(
+        "\nbuild:\n  extends: .cedar\n  parallel:\n    matrix:\n      - COMPILER: {}\n        PROFILE: {}\n  script:\n    - {}\n    - cedar build{} --profile \"$PROFILE\"\n",
+        yaml_list(&plan.compilers),
+        yaml_list(&plan.profiles),
+        select_compiler("$COMPILER"),
+        plan.build
+    ));
+
+    if plan.tests {
+        file.push_str("    - cedar test --profile \"$PROFILE\"\n");
+    }
+
+    if plan.format || plan.tidy {
+        file.push_str("\nlint:\n  extends: .cedar\n  script:\n");
+        if plan.format {
+            file.push_str(&format!(
+                "    - git ls-files -z {} | xargs -0 -r clang-format --dry-run --Werror\n",
+                FORMATTED
+            ));
+        }
+        if plan.tidy {
+            file.push_str(&format!(
+                "    - cedar intercept -- cedar build{}\n    - run-clang-tidy -quiet -p .\n",
+                plan.build
+            ));
+        }
+    }
+
+    file
+}
+
+/// The packages of the system dependencies of the project and of its
+/// dependencies, named by the given package manager, and whether any is found
+/// with pkg-config. The libraries of libc need no package.
+fn system_packages(
+    project: &Project,
+    packages: &[Package],
+    package: fn(&str) -> String,
+) -> (Vec<String>, bool) {
+    let mut names = Vec::new();
+    let mut pkg_config_used = false;
+
+    for (name, dependency) in std::iter::once(project)
+        .chain(packages.iter().map(|package| &package.project))
+        .flat_map(|unit| {
+            unit.manifest
+                .system_deps
+                .iter()
+                .filter(|(name, dependency)| unit.uses(name, dependency.is_optional()))
+        })
+    {
+        let (library, module) = match (pkg_config_module(name, dependency), dependency) {
+            (Some(module), _) => (module, true),
+            (None, SystemDependency::Detailed { lib: Some(lib), .. }) => (lib.as_str(), false),
+            (None, _) => (name.as_str(), false),
+        };
+
+        if LIBC.contains(&library) {
+            continue;
+        }
+
+        pkg_config_used |= module;
+        names.push(package(library));
+    }
+
+    names.sort();
+    names.dedup();
+
+    (names, pkg_config_used)
+}
+
+/// A double quoted string, as in JSON and the instructions of a Dockerfile.
+fn json_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+/// The stdenv of nixpkgs with the compiler of the manifest at the major
+/// version installed here, or the default one of the compiler when nixpkgs
+/// has no stdenv for that version.
+fn nix_stdenv(compiler: &str) -> String {
+    let clang = compiler.eq_ignore_ascii_case("clang");
+
+    let version = probe::probe(if clang { "clang" } else { "gcc" }, "dumpversion", |cc| {
+        process::Command::new(cc)
+            .arg("-dumpversion")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    });
+    let major = version.and_then(|version| version.split('.').next()?.parse::<u32>().ok());
+
+    match (clang, major) {
+        (false, Some(major @ 12..=14)) => format!("gcc{}Stdenv", major),
+        (false, _) => String::from("stdenv"),
+        (true, Some(major @ 16..=19)) => format!("llvmPackages_{}.stdenv", major),
+        (true, _) => String::from("clangStdenv"),
+    }
+}
+
+/// A Nix string.
+fn nix_string(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace("${", "\\${")
+    )
+}
+
+/// Escapes text for an indented Nix string, ''...''.
+fn indented(text: &str) -> String {
+    text.replace("''", "'''").replace("${", "''${")
+}
+
+/// A Makefile variable.
+fn variable<S: AsRef<str>>(name: &str, values: &[S]) -> String {
+    values
+        .iter()
+        .fold(format!("{} =", name), |variable, value| {
+            variable + " " + value.as_ref()
+        })
+        + "\n"
+}
+
+/// A Makefile variable holding a list, one value per line.
+fn list(name: &str, values: &[String]) -> String {
+    values.iter().fold(format!("{} =", name), |list, value| {
+        list + " \\\n\t" + value
+    }) + "\n"
+}
+
+/// The library sources of the project, with the license notices of the
+/// dependencies when the build embeds them.
+fn library_sources(project: &Project) -> Sources {
+    let mut sources = project.library_sources.clone();
+
+    if project.manifest.licenses.embed_notices {
+        sources.push((build::notices_path(&project.build_dir), Language::C));
+    }
+
+    sources
+}
+
+/// The sources of a target, with the generated main of the harness for a
+/// test with TEST cases.
+fn target_sources(project: &Project, target: &Target) -> Result<Sources, CedarError> {
+    let mut sources = target.sources.clone();
+
+    if target.kind == TargetKind::Test && test::harness_main(target)?.is_some() {
+        let (_, main) = test::harness_paths(project, target);
+        sources.push((main, Language::C));
+    }
+
+    Ok(sources)
+}
+
+/// The sources a build of cedar writes into build/, the license notices and
+/// the test harness, with what they hold. build/ is not part of the sources
+/// of the project, so the exported builds write them themselves.
+fn generated_sources(
+    project: &Project,
+    packages: &[Package],
+) -> Result<Vec<(PathBuf, String)>, CedarError> {
+    let mut generated = Vec::new();
+
+    if project.manifest.licenses.embed_notices {
+        generated.push((
+            build::notices_path(&project.build_dir),
+            license::notices_source(&license::notices(packages)),
+        ));
+    }
+
+    for target in project
+        .targets
+        .iter()
+        .filter(|target| target.kind == TargetKind::Test)
+    {
+        let (header, main) = test::harness_paths(project, target);
+
+        if !generated.iter().any(|(path, _)| *path == header) {
+            generated.push((header, test::HARNESS.to_owned()));
+        }
+        if let Some(source) = test::harness_main(target)? {
+            generated.push((main, source));
+        }
+    }
+
+    Ok(generated)
+}
+
+/// The pkg-config module of a system dependency, None for ones that are
+/// only probed for.
+fn pkg_config_module<'a>(name: &'a str, dependency: &'a SystemDependency) -> Option<&'a str> {
+    match dependency {
+        SystemDependency::Version(_) => Some(name),
+        SystemDependency::Detailed {
+            pkg_config: Some(module),
+            ..
+        } => Some(module),
+        SystemDependency::Detailed {
+            header: None,
+            lib: None,
+            ..
+        } => Some(name),
+        SystemDependency::Detailed { .. } => None,
+    }
+}
+
+/// A path for a CMakeLists.txt in the root, quoted when needed.
+fn path(root: &Path, path: &Path) -> String {
+    quote(&relative(root, path))
+}
+
+/// A path in the CMakeLists.txt, in the binary directory for the sources
+/// cedar generates into build/, see generated_sources.
+fn cmake_path(project: &Project, file: &Path) -> String {
+    match file.strip_prefix(&project.build_dir) {
+        Ok(generated) => format!(
+            "${{CMAKE_BINARY_DIR}}/cedar/{}",
+            relative(Path::new(""), generated)
+        ),
+        Err(_) => path(&project.root, file),
+    }
+}
+
+/// A CMake bracket argument holding the text as it is, with enough = that
+/// the text cannot close it.
+fn bracket(text: &str) -> String {
+    let equals = (0..)
+        .map(|count| "=".repeat(count))
+        .find(|equals| !text.contains(&format!("]{}]", equals)))
+        .unwrap_or_default();
+
+    format!("[{0}[\n{1}]{0}]", equals, text)
+}
+
+/// A path relative to the root when it is inside it, with forward slashes.
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Quotes a CMake argument when it has characters CMake would split on or
+/// expand.
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', ';', '"', '(', ')', '#', '\\']) {
+        return arg.to_owned();
+    }
+
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_cmake_and_make_values() {
+        assert_eq!(quote("src/main.c"), "src/main.c");
+        assert_eq!(quote("my file.c"), "\"my file.c\"");
+        assert_eq!(quote("NAME=\"x\""), "\"NAME=\\\"x\\\"\"");
+        assert_eq!(path(Path::new("/p"), Path::new("/p/src/a.c")), "src/a.c");
+        assert_eq!(bracket("a[0] < b"), "[[\na[0] < b]]");
+        assert_eq!(bracket("x[y[0]]]"), "[=[\nx[y[0]]]]=]");
+
+        assert_eq!(variable::<&str>("ASFLAGS", &[]), "ASFLAGS =\n");
+        assert_eq!(
+            list("OBJECTS", &[String::from("a.c.o"), String::from("b.c.o")]),
+            "OBJECTS = \\\n\ta.c.o \\\n\tb.c.o\n"
+        );
+
+        assert_eq!(nix_string("say \"${x}\""), "\"say \\\"\\${x}\\\"\"");
+        assert_eq!(indented("''${x}''"), "'''''${x}'''");
+        assert_eq!(json_string("a \"b\""), "\"a \\\"b\\\"\"");
+    }
+
+    #[test]
+    fn formats_ci_values() {
+        assert_eq!(
+            yaml_list(&[String::from("gcc"), String::from("clang")]),
+            "[\"gcc\", \"clang\"]"
+        );
+        assert_eq!(
+            select_compiler("$COMPILER"),
+            "sed -i \"s/^compiler *=.*/compiler = '$COMPILER'/\" cedar.toml"
+        );
+        assert_eq!(
+            "ci".parse::<ExportFormat>().unwrap(),
+            ExportFormat::Ci(CiProvider::Github)
+        );
+    }
+}
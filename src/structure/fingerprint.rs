@@ -0,0 +1,179 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::SystemTime,
+};
+
+/// The reason a build step has to run again.
+///
+/// # Members
+///
+/// * 'MissingOutput' - The output of the step does not exist.
+/// * 'ChangedInput' - An input (source, header, or object) is newer than the
+///   output. Holds the path of the input.
+/// * 'ChangedCommand' - The command line differs from the one used last time,
+///   e.g. because flags in the manifest changed.
+/// * 'Forced' - An option needs the step to run, e.g. --print-gc-sections
+///   has links run to print what they drop.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dirty {
+    MissingOutput,
+    ChangedInput(PathBuf),
+    ChangedCommand,
+    Forced,
+}
+
+impl Dirty {
+    /// Why a step reran, for cedar build --explain.
+    ///
+    /// # Arguments
+    ///
+    /// * 'step' - The source compiled or the artifact linked.
+    /// * 'root' - The project root, which paths are shown relative to.
+    ///
+    pub fn explain(&self, step: &Path, root: &Path) -> String {
+        match self {
+            Self::MissingOutput => String::from("it was never built"),
+            Self::ChangedInput(input) if input == step => String::from("the source changed"),
+            Self::ChangedInput(input) => format!(
+                "{} changed",
+                input.strip_prefix(root).unwrap_or(input).display()
+            ),
+            Self::ChangedCommand => {
+                String::from("its command changed (compiler, flags, or options in cedar.toml)")
+            }
+            Self::Forced => String::from("--print-gc-sections asked for it"),
+        }
+    }
+}
+
+/// Checks whether a build step is out of date.
+///
+/// # Arguments
+///
+/// * 'output' - The file produced by the step.
+/// * 'inputs' - Every file the output depends on.
+/// * 'command' - The command that produces the output.
+/// * 'fingerprint' - Where the command of the last successful run is stored.
+///
+/// # Returns
+///
+/// * None when the output is up to date, otherwise the first reason found.
+///
+pub fn dirty(
+    output: &Path,
+    inputs: &[PathBuf],
+    command: &process::Command,
+    fingerprint: &Path,
+) -> Option<Dirty> {
+    let built = match modified(output) {
+        Some(time) => time,
+        None => return Some(Dirty::MissingOutput),
+    };
+
+    match fs::read_to_string(fingerprint) {
+        Ok(previous) if previous == command_string(command) => {}
+        _ => return Some(Dirty::ChangedCommand),
+    }
+
+    for input in inputs {
+        match modified(input) {
+            Some(time) if time <= built => {}
+            _ => return Some(Dirty::ChangedInput(input.clone())),
+        }
+    }
+
+    None
+}
+
+/// Stores the command of a successful build step, see dirty.
+pub fn record(command: &process::Command, fingerprint: &Path) -> Result<(), io::Error> {
+    fs::write(fingerprint, command_string(command))
+}
+
+/// Gets the files an object depends on from its source and the depfile
+/// written by the compiler (-MMD), a missing depfile yields just the source.
+pub fn dependencies(source: &Path, depfile: &Path) -> Vec<PathBuf> {
+    let mut inputs = vec![source.to_path_buf()];
+
+    if let Ok(contents) = fs::read_to_string(depfile) {
+        inputs.extend(parse_depfile(&contents));
+    }
+
+    inputs
+}
+
+/// Parses a make style depfile (`target: dep dep \` ...) into its
+/// prerequisites, handling line continuations and escaped spaces.
+pub fn parse_depfile(contents: &str) -> Vec<PathBuf> {
+    let joined = contents.replace("\\\r\n", " ").replace("\\\n", " ");
+
+    let mut result = Vec::new();
+
+    for line in joined.lines() {
+        // The target ends at the first colon followed by whitespace, which
+        // skips drive letters in Windows paths.
+        let deps = match line
+            .find(": ")
+            .or_else(|| line.strip_suffix(':').map(|l| l.len()))
+        {
+            Some(index) => &line[index + 1..],
+            None => continue,
+        };
+
+        let mut current = String::new();
+        let mut chars = deps.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' if chars.peek() == Some(&' ') => {
+                    current.push(' ');
+                    chars.next();
+                }
+                ' ' | '\t' => {
+                    if !current.is_empty() {
+                        result.push(PathBuf::from(std::mem::take(&mut current)));
+                    }
+                }
+                _ => current.push(ch),
+            }
+        }
+
+        if !current.is_empty() {
+            result.push(PathBuf::from(current));
+        }
+    }
+
+    result
+}
+
+fn command_string(command: &process::Command) -> String {
+    format!("{:?}", command)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::parse_depfile;
+
+    #[test]
+    fn test_parse_depfile() {
+        let depfile = "build/obj/main.c.o: src/main.c include/a.h \\\n include/my\\ file.h\n";
+
+        assert_eq!(
+            parse_depfile(depfile),
+            vec![
+                PathBuf::from("src/main.c"),
+                PathBuf::from("include/a.h"),
+                PathBuf::from("include/my file.h"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+/// Flags taking their value as the next argument, e.g. -I dir.
+const SEPARATE: &[&str] = &[
+    "-I",
+    "-D",
+    "-U",
+    "-include",
+    "-imacros",
+    "-isystem",
+    "-iquote",
+    "-idirafter",
+    "-x",
+    "--param",
+    "-Xpreprocessor",
+    "-Xassembler",
+    "-Xlinker",
+];
+
+/// Where a flag goes on the command line of a compile.
+///
+/// # Members
+///
+/// * 'Std' - The language standard, -std=.
+/// * 'Warning' - -W, -w and -pedantic flags.
+/// * 'Define' - Macro definitions, -D and -U.
+/// * 'Include' - Include directories and forced includes.
+/// * 'Other' - Everything else, left where its source puts it.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Std,
+    Warning,
+    Define,
+    Include,
+    Other,
+}
+
+impl Kind {
+    fn of(flag: &str) -> Self {
+        let is_warning = (flag.starts_with("-W")
+            && !["-Wl,", "-Wa,", "-Wp,"]
+                .iter()
+                .any(|prefix| flag.starts_with(prefix)))
+            || flag == "-w"
+            || flag.starts_with("-pedantic");
+
+        match flag {
+            _ if flag.starts_with("-std=") => Self::Std,
+            _ if is_warning => Self::Warning,
+            _ if flag.starts_with("-D") || flag.starts_with("-U") => Self::Define,
+            _ if [
+                "-I",
+                "-isystem",
+                "-iquote",
+                "-idirafter",
+                "-include",
+                "-imacros",
+            ]
+            .iter()
+            .any(|prefix| flag.starts_with(prefix)) =>
+            {
+                Self::Include
+            }
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Splits flags into single options, a flag taking a separate value stays
+/// together with it.
+fn options(flags: &[String]) -> Vec<&[String]> {
+    let mut options = Vec::new();
+    let mut index = 0;
+
+    while index < flags.len() {
+        let len = match SEPARATE.contains(&flags[index].as_str()) {
+            true => 2.min(flags.len() - index),
+            false => 1,
+        };
+
+        options.push(&flags[index..index + len]);
+        index += len;
+    }
+
+    options
+}
+
+/// Drops repeated options, keeping the first or last of each.
+fn dedup(options: Vec<&[String]>, keep_last: bool) -> Vec<&[String]> {
+    options
+        .iter()
+        .enumerate()
+        .filter(|(index, option)| match keep_last {
+            true => !options[index + 1..].contains(option),
+            false => !options[..*index].contains(option),
+        })
+        .map(|(_, option)| *option)
+        .collect()
+}
+
+/// Puts the flags of a compile in their documented order, the same whatever
+/// order they were given in across the manifest:
+///
+/// 1. The language standard, only the last -std= given.
+/// 2. Warnings.
+/// 3. Macro definitions.
+/// 4. Include directories, the project's own before those of dependencies.
+/// 5. The other flags of the profile.
+/// 6. The other per-file flags of [build.files].
+/// 7. The other flags of [build].
+///
+/// Repeated flags are dropped. An include directory keeps its first place,
+/// as the first one found wins, anything else its last, as the last one
+/// given wins.
+///
+/// # Arguments
+///
+/// * 'system' - The flags cedar adds: include directories of the project,
+///   its dependencies and system libraries.
+/// * 'profile' - The flags of the profile.
+/// * 'file' - The flags of the source in [build.files].
+/// * 'user' - The flags of [build].
+///
+pub fn order(
+    system: &[String],
+    profile: &[String],
+    file: &[String],
+    user: &[String],
+) -> Vec<String> {
+    let mut kinds: [Vec<&[String]>; 4] = Default::default();
+    let mut rest: [Vec<&[String]>; 4] = Default::default();
+
+    for (index, flags) in [system, profile, file, user].into_iter().enumerate() {
+        for option in options(flags) {
+            match Kind::of(&option[0]) {
+                Kind::Std => kinds[0].push(option),
+                Kind::Warning => kinds[1].push(option),
+                Kind::Define => kinds[2].push(option),
+                Kind::Include => kinds[3].push(option),
+                Kind::Other => rest[index].push(option),
+            }
+        }
+    }
+
+    let [std, warnings, defines, includes] = kinds;
+    let [system, profile, file, user] = rest;
+
+    // Other flags keep their source's position, a repeat later on is the
+    // one that counts.
+    let others = dedup(
+        system
+            .into_iter()
+            .chain(profile)
+            .chain(file)
+            .chain(user)
+            .collect(),
+        true,
+    );
+
+    std.last()
+        .into_iter()
+        .copied()
+        .chain(dedup(warnings, true))
+        .chain(dedup(defines, true))
+        .chain(dedup(includes, false))
+        .chain(others)
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// Whether a flag of a link names a library, as -l<name> or as the path of
+/// an archive.
+fn is_library(flag: &str) -> bool {
+    flag.starts_with("-l") || (!flag.starts_with('-') && flag.ends_with(".a"))
+}
+
+/// Puts flags before the first and after the last library among the flags
+/// of a link.
+fn around_libraries(ldflags: &[&String], start: &str, end: &str) -> Vec<String> {
+    let first = ldflags.iter().position(|flag| is_library(flag));
+    let last = ldflags.iter().rposition(|flag| is_library(flag));
+
+    let mut wrapped = Vec::new();
+
+    for (index, flag) in ldflags.iter().enumerate() {
+        if Some(index) == first {
+            wrapped.push(String::from(start));
+        }
+
+        wrapped.push(flag.to_string());
+
+        if Some(index) == last {
+            wrapped.push(String::from(end));
+        }
+    }
+
+    wrapped
+}
+
+/// Wraps the static libraries among the flags of a link in a group, from
+/// the first library to the last, so the linker searches them over and over
+/// until no more symbols resolve and circular references between them link.
+pub fn group_archives(ldflags: &[&String]) -> Vec<String> {
+    around_libraries(ldflags, "-Wl,--start-group", "-Wl,--end-group")
+}
+
+/// Makes the linker take every object of the static libraries among the
+/// flags of a link, not only the ones resolving a symbol. Apple's linker
+/// only does so for archives given by path, with -force_load.
+pub fn whole_archives(ldflags: &[&String]) -> Vec<String> {
+    if cfg!(target_os = "macos") {
+        return ldflags
+            .iter()
+            .map(|flag| match is_library(flag) && !flag.starts_with("-l") {
+                true => format!("-Wl,-force_load,{}", flag),
+                false => flag.to_string(),
+            })
+            .collect();
+    }
+
+    around_libraries(ldflags, "-Wl,--whole-archive", "-Wl,--no-whole-archive")
+}
+
+/// The flags of a link dropping the sections nothing references, of objects
+/// compiled with -ffunction-sections -fdata-sections, and printing each one
+/// it drops when asked to.
+pub fn gc_sections(print: bool) -> Vec<String> {
+    linker_gc_sections(cfg!(target_os = "macos"), print)
+}
+
+/// The flags of gc_sections for Apple's linker or a GNU one.
+fn linker_gc_sections(apple: bool, print: bool) -> Vec<String> {
+    let flags: &[&str] = match (apple, print) {
+        (true, _) => &["-Wl,-dead_strip"],
+        (false, false) => &["-Wl,--gc-sections"],
+        (false, true) => &["-Wl,--gc-sections", "-Wl,--print-gc-sections"],
+    };
+
+    flags.iter().map(|flag| flag.to_string()).collect()
+}
+
+/// The symbol names of an exports list, one per line, # starts a comment.
+pub fn symbols(list: &str) -> impl Iterator<Item = &str> {
+    list.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+}
+
+/// Whether an exports file is a GNU version script rather than a list of
+/// symbols.
+pub fn is_version_script(exports: &str) -> bool {
+    exports.contains('{')
+}
+
+/// The version script of an exports list, exporting its symbols and hiding
+/// every other one.
+pub fn version_script(list: &str) -> String {
+    let mut script = String::from("{\n  global:\n");
+
+    for symbol in symbols(list) {
+        script.push_str(&format!("    {};\n", symbol));
+    }
+
+    script.push_str("  local:\n    *;\n};\n");
+    script
+}
+
+/// The exported symbols list of an exports list for Apple's linker, which
+/// names C symbols with a leading underscore.
+pub fn exported_symbols_list(list: &str) -> String {
+    symbols(list)
+        .map(|symbol| format!("_{}\n", symbol))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_flags() {
+        let strings =
+            |flags: &[&str]| -> Vec<String> { flags.iter().map(|flag| flag.to_string()).collect() };
+
+        let ordered = order(
+            &strings(&["-Iinclude", "-I", "deps/include", "-pthread"]),
+            &strings(&["-O2", "-DNDEBUG"]),
+            &strings(&["-O3", "-Wno-unused"]),
+            &strings(&[
+                "-Wall",
+                "-std=c99",
+                "-Iinclude",
+                "-g",
+                "-std=c11",
+                "-Wall",
+                "-Wl,-z,now",
+            ]),
+        );
+
+        assert_eq!(
+            ordered,
+            [
+                "-std=c11",
+                "-Wno-unused",
+                "-Wall",
+                "-DNDEBUG",
+                "-Iinclude",
+                "-I",
+                "deps/include",
+                "-pthread",
+                "-O2",
+                "-O3",
+                "-g",
+                "-Wl,-z,now"
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_archives() {
+        let flags: Vec<String> = [
+            "-Llib",
+            "-lparser",
+            "lib/liblexer.a",
+            "-lparser",
+            "-pthread",
+        ]
+        .map(String::from)
+        .into();
+
+        assert_eq!(
+            group_archives(&flags.iter().collect::<Vec<_>>()),
+            [
+                "-Llib",
+                "-Wl,--start-group",
+                "-lparser",
+                "lib/liblexer.a",
+                "-lparser",
+                "-Wl,--end-group",
+                "-pthread"
+            ]
+        );
+        assert_eq!(group_archives(&[&String::from("-pthread")]), ["-pthread"]);
+
+        if !cfg!(target_os = "macos") {
+            assert_eq!(
+                whole_archives(&flags[..3].iter().collect::<Vec<_>>()),
+                [
+                    "-Llib",
+                    "-Wl,--whole-archive",
+                    "-lparser",
+                    "lib/liblexer.a",
+                    "-Wl,--no-whole-archive"
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn writes_export_lists() {
+        let list = "# The public API.\nparser_new\nparser_free # frees\n\n";
+
+        assert!(!is_version_script(list));
+        assert!(is_version_script("{ global: parser_new; local: *; };"));
+        assert_eq!(
+            version_script(list),
+            "{\n  global:\n    parser_new;\n    parser_free;\n  local:\n    *;\n};\n"
+        );
+        assert_eq!(exported_symbols_list(list), "_parser_new\n_parser_free\n");
+    }
+
+    #[test]
+    fn picks_gc_sections_flags() {
+        assert_eq!(linker_gc_sections(false, false), ["-Wl,--gc-sections"]);
+        assert_eq!(
+            linker_gc_sections(false, true),
+            ["-Wl,--gc-sections", "-Wl,--print-gc-sections"]
+        );
+        assert_eq!(linker_gc_sections(true, false), ["-Wl,-dead_strip"]);
+        assert_eq!(linker_gc_sections(true, true), ["-Wl,-dead_strip"]);
+        assert_eq!(
+            gc_sections(true),
+            linker_gc_sections(cfg!(target_os = "macos"), true)
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::{fs, path::PathBuf};
+
+use super::project::{Project, ProjectError};
+
+/// Creates a module in the project, a source file in src/ and a matching
+/// header in include/ under the same relative path, e.g. `net/socket` creates
+/// `src/net/socket.c` and `include/net/socket.h`.
+///
+/// # Arguments
+///
+/// * 'project' - The project to add the module to.
+/// * 'module' - The module path relative to src/ and include/, without an
+///   extension.
+/// * 'test' - Whether to also create a test stub in tests/.
+///
+/// # Returns
+///
+/// * The paths of the created files.
+///
+pub fn generate_module(
+    project: &Project,
+    module: &str,
+    test: bool,
+) -> Result<Vec<PathBuf>, ProjectError> {
+    let components: Vec<&str> = module.trim_matches('/').split('/').collect();
+
+    // Every component must be usable as both a file name and part of a macro.
+    if components
+        .iter()
+        .any(|c| c.is_empty() || !c.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_'))
+    {
+        return Err(ProjectError::InvalidPath(module.to_owned()));
+    }
+
+    let module = components.join("/");
+    let guard = format!("{}_{}_H", project.name(), components.join("_"))
+        .to_ascii_uppercase()
+        .replace(|ch: char| !ch.is_ascii_alphanumeric(), "_");
+
+    let source = project.src_dir.join(format!("{}.c", module));
+    let header = project.include_dir.join(format!("{}.h", module));
+
+    let mut files = vec![
+        (source, format!("#include \"{}.h\"\n", module)),
+        (
+            header,
+            format!("#ifndef {0}\n#define {0}\n\n#endif /* {0} */\n", guard),
+        ),
+    ];
+
+    if test {
+        files.push((
+            project
+                .root
+                .join("tests")
+                .join(format!("{}.c", components.join("_"))),
+            format!(
+                "#include <cedar/test.h>\n#include \"{}.h\"\n\nTEST({}) {{\n\tASSERT(1);\n}}\n",
+                module,
+                components.join("_")
+            ),
+        ));
+    }
+
+    // Check everything first so a clash never leaves a half generated module.
+    for (file, _) in &files {
+        if file.exists() {
+            return Err(ProjectError::AlreadyExists(format!("{:?}", file)));
+        }
+    }
+
+    for (file, contents) in &files {
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(file, contents)?;
+    }
+
+    Ok(files.into_iter().map(|(file, _)| file).collect())
+}
@@ -0,0 +1,345 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{cli::CliError, error::CedarError};
+
+use super::{
+    build::Language,
+    deps,
+    project::Project,
+    target::{Target, TargetKind},
+};
+
+/// The formats the build graph can be written in.
+///
+/// # Members
+///
+/// * 'Dot' - A Graphviz digraph.
+/// * 'Json' - The nodes and edges with their metadata, for scripts.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Json,
+}
+
+impl FromStr for GraphFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, the graph format is dot or json.",
+                s
+            ))),
+        }
+    }
+}
+
+/// The build graph of a project: the project and its dependencies, the
+/// targets of the project, and the translation units they compile.
+///
+/// # Fields
+///
+/// * 'nodes' - Every package, target, and translation unit.
+/// * 'edges' - How they relate, from the dependent to the dependency.
+///
+#[derive(Debug, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// A node of the graph, its id is <kind>:<name> for packages and targets
+/// and source:<path> for translation units.
+///
+/// # Fields
+///
+/// * 'id' - The unique id edges refer to it by.
+/// * 'kind' - What it is.
+/// * 'name' - The name of the package or target, the path of the source
+///   relative to the project root.
+/// * 'version' - The version of a package.
+/// * 'source' - Where a dependency comes from, as in cedar.lock.
+/// * 'target_kind' - The kind of a target.
+/// * 'language' - The language of a translation unit.
+///
+#[derive(Debug, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_kind: Option<TargetKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Package,
+    Target,
+    Source,
+}
+
+/// An edge of the graph.
+///
+/// # Fields
+///
+/// * 'from' - The id of the dependent node.
+/// * 'to' - The id of the node it depends on.
+/// * 'kind' - How: a package depends on a package, defines its targets,
+///   and a target compiles translation units and links packages.
+///
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    Depends,
+    Defines,
+    Compiles,
+    Links,
+}
+
+impl Graph {
+    /// Collects the graph of a project, resolving (and fetching) its
+    /// dependencies.
+    pub fn load(project: &Project) -> Result<Self, CedarError> {
+        let manifest = &project.manifest;
+        let package_id = |name: &str| format!("package:{}", name);
+
+        let mut nodes = vec![Node {
+            id: package_id(&manifest.meta.name),
+            kind: NodeKind::Package,
+            name: manifest.meta.name.clone(),
+            version: Some(manifest.meta.version.clone()),
+            source: None,
+            target_kind: None,
+            language: None,
+        }];
+        let mut edges = Vec::new();
+
+        let packages = deps::resolve(project, true)?;
+
+        for package in &packages {
+            nodes.push(Node {
+                id: package_id(&package.name),
+                kind: NodeKind::Package,
+                name: package.name.clone(),
+                version: Some(package.version.clone()),
+                source: Some(package.source.clone()),
+                target_kind: None,
+                language: None,
+            });
+        }
+
+        // Optional dependencies no enabled feature asks for are not resolved,
+        // and get no edge.
+        for (name, unit) in std::iter::once((&manifest.meta.name, project)).chain(
+            packages
+                .iter()
+                .map(|package| (&package.name, &package.project)),
+        ) {
+            for dependency in unit
+                .manifest
+                .dependencies
+                .packages
+                .iter()
+                .filter(|(dependency, details)| unit.uses(dependency, details.optional))
+                .map(|(dependency, _)| dependency)
+            {
+                edges.push(Edge {
+                    from: package_id(name),
+                    to: package_id(dependency),
+                    kind: EdgeKind::Depends,
+                });
+            }
+        }
+
+        let mut sources: Vec<(PathBuf, Language)> = Vec::new();
+
+        for target in &project.targets {
+            let id = target_id(target);
+
+            nodes.push(Node {
+                id: id.clone(),
+                kind: NodeKind::Target,
+                name: target.name.clone(),
+                version: None,
+                source: None,
+                target_kind: Some(target.kind),
+                language: None,
+            });
+            edges.push(Edge {
+                from: package_id(&manifest.meta.name),
+                to: id.clone(),
+                kind: EdgeKind::Defines,
+            });
+
+            // The library sources are linked into every target.
+            for (source, language) in project.library_sources.iter().chain(&target.sources) {
+                let relative = source.strip_prefix(&project.root).unwrap_or(source);
+
+                if !sources.iter().any(|(known, _)| known == relative) {
+                    sources.push((relative.to_path_buf(), *language));
+                }
+
+                edges.push(Edge {
+                    from: id.clone(),
+                    to: source_id(relative),
+                    kind: EdgeKind::Compiles,
+                });
+            }
+
+            for dependency in manifest.dependencies.packages.keys() {
+                edges.push(Edge {
+                    from: id.clone(),
+                    to: package_id(dependency),
+                    kind: EdgeKind::Links,
+                });
+            }
+        }
+
+        sources.sort();
+
+        for (source, language) in sources {
+            nodes.push(Node {
+                id: source_id(&source),
+                kind: NodeKind::Source,
+                name: source.to_string_lossy().into_owned(),
+                version: None,
+                source: None,
+                target_kind: None,
+                language: Some(language),
+            });
+        }
+
+        Ok(Self { nodes, edges })
+    }
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+    /// The graph as a Graphviz digraph, packages as boxes, targets as
+    /// ellipses, and translation units as notes.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cedar {\n    rankdir=LR;\n");
+
+        for node in &self.nodes {
+            let (shape, label) = match node.kind {
+                NodeKind::Package => (
+                    "box",
+                    match &node.version {
+                        Some(version) => format!("{} v{}", node.name, version),
+                        None => node.name.clone(),
+                    },
+                ),
+                NodeKind::Target => (
+                    "ellipse",
+                    format!(
+                        "{} ({})",
+                        node.name,
+                        node.target_kind.map_or("", |kind| kind.as_str())
+                    ),
+                ),
+                NodeKind::Source => ("note", node.name.clone()),
+            };
+
+            dot.push_str(&format!(
+                "    {} [label={}, shape={}];\n",
+                quote(&node.id),
+                quote(&label),
+                shape
+            ));
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Depends | EdgeKind::Links => "solid",
+                EdgeKind::Defines | EdgeKind::Compiles => "dashed",
+            };
+
+            dot.push_str(&format!(
+                "    {} -> {} [style={}];\n",
+                quote(&edge.from),
+                quote(&edge.to),
+                style
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn target_id(target: &Target) -> String {
+    format!("{}:{}", target.kind.as_str(), target.name)
+}
+
+fn source_id(source: &Path) -> String {
+    format!("source:{}", source.to_string_lossy().replace('\\', "/"))
+}
+
+/// Quotes an id or label for dot.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_dot() {
+        let graph = Graph {
+            nodes: vec![
+                Node {
+                    id: String::from("package:app"),
+                    kind: NodeKind::Package,
+                    name: String::from("app"),
+                    version: Some(String::from("1.0.0")),
+                    source: None,
+                    target_kind: None,
+                    language: None,
+                },
+                Node {
+                    id: String::from("source:src/main.c"),
+                    kind: NodeKind::Source,
+                    name: String::from("src/main.c"),
+                    version: None,
+                    source: None,
+                    target_kind: None,
+                    language: Some(Language::C),
+                },
+            ],
+            edges: vec![Edge {
+                from: String::from("package:app"),
+                to: String::from("source:src/main.c"),
+                kind: EdgeKind::Compiles,
+            }],
+        };
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph cedar {\n    rankdir=LR;\n    \"package:app\" [label=\"app v1.0.0\", shape=box];\n    \"source:src/main.c\" [label=\"src/main.c\", shape=note];\n    \"package:app\" -> \"source:src/main.c\" [style=dashed];\n}\n"
+        );
+        assert!(graph.to_json().unwrap().contains("\"language\": \"c\""));
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * cedar/test.h - the unit test harness bundled with cedar.
+ *
+ * Write test cases with TEST and the ASSERT macros, cedar test finds them
+ * and generates the main function that runs them:
+ *
+ *     #include <cedar/test.h>
+ *
+ *     TEST(addition) {
+ *         ASSERT_EQ(add(1, 2), 3);
+ *     }
+ *
+ * A failing assertion prints where it failed and ends the test case, the
+ * other cases still run.
+ */
+#ifndef CEDAR_TEST_H
+#define CEDAR_TEST_H
+
+#include <stdio.h>
+#include <string.h>
+
+struct cedar_test {
+	const char *name;
+	void (*run)(void);
+};
+
+/* Set by a failing assertion, reset before every test case. */
+#ifdef __cplusplus
+extern "C" int cedar_test_failed;
+#else
+extern int cedar_test_failed;
+#endif
+
+#ifdef __cplusplus
+#define TEST(name) extern "C" void cedar_test_##name(void)
+#else
+#define TEST(name) void cedar_test_##name(void)
+#endif
+
+#define CEDAR_FAIL_(...)                                                       \
+	do {                                                                       \
+		fprintf(stdout, "\t  %s:%d: ", __FILE__, __LINE__);                    \
+		fprintf(stdout, __VA_ARGS__);                                          \
+		fputc('\n', stdout);                                                   \
+		cedar_test_failed = 1;                                                 \
+		return;                                                                \
+	} while (0)
+
+#define ASSERT(cond)                                                           \
+	do {                                                                       \
+		if (!(cond))                                                           \
+			CEDAR_FAIL_("assertion failed: %s", #cond);                        \
+	} while (0)
+
+#define ASSERT_TRUE(cond) ASSERT(cond)
+#define ASSERT_FALSE(cond) ASSERT(!(cond))
+
+#define ASSERT_EQ(a, b)                                                        \
+	do {                                                                       \
+		long long cedar_a_ = (long long)(a), cedar_b_ = (long long)(b);        \
+		if (cedar_a_ != cedar_b_)                                              \
+			CEDAR_FAIL_("%s == %s (%lld != %lld)", #a, #b, cedar_a_,           \
+			            cedar_b_);                                             \
+	} while (0)
+
+#define ASSERT_NE(a, b)                                                        \
+	do {                                                                       \
+		long long cedar_a_ = (long long)(a), cedar_b_ = (long long)(b);        \
+		if (cedar_a_ == cedar_b_)                                              \
+			CEDAR_FAIL_("%s != %s (both %lld)", #a, #b, cedar_a_);             \
+	} while (0)
+
+#define ASSERT_STR_EQ(a, b)                                                    \
+	do {                                                                       \
+		const char *cedar_a_ = (a), *cedar_b_ = (b);                           \
+		if (strcmp(cedar_a_, cedar_b_) != 0)                                   \
+			CEDAR_FAIL_("%s == %s (\"%s\" != \"%s\")", #a, #b, cedar_a_,       \
+			            cedar_b_);                                             \
+	} while (0)
+
+#ifdef CEDAR_TEST_MAIN
+
+int cedar_test_failed;
+
+/*
+ * Whether a test case is selected by the arguments, which are patterns the
+ * name has to contain (any of them), or --skip followed by a pattern it must
+ * not contain.
+ */
+static int cedar_test_selected(const char *name, int argc, char **argv) {
+	int filtered = 0, matched = 0;
+
+	for (int i = 1; i < argc; i++) {
+		if (strcmp(argv[i], "--skip") == 0 && i + 1 < argc) {
+			if (strstr(name, argv[++i]))
+				return 0;
+		} else {
+			filtered = 1;
+			matched |= strstr(name, argv[i]) != NULL;
+		}
+	}
+
+	return !filtered || matched;
+}
+
+/* Runs the selected test cases, used by the main function cedar generates. */
+static int cedar_test_run(const struct cedar_test *tests, int count, int argc,
+                          char **argv) {
+	int failed = 0, ran = 0;
+
+	for (int i = 0; i < count; i++) {
+		if (!cedar_test_selected(tests[i].name, argc, argv))
+			continue;
+
+		cedar_test_failed = 0;
+		tests[i].run();
+
+		printf("\t  %s ... %s\n", tests[i].name,
+		       cedar_test_failed ? "FAILED" : "ok");
+		failed += cedar_test_failed;
+		ran++;
+	}
+
+	printf("\t  %d passed, %d failed, %d filtered out\n", ran - failed, failed,
+	       count - ran);
+	return failed ? 1 : 0;
+}
+
+#endif /* CEDAR_TEST_MAIN */
+
+#endif /* CEDAR_TEST_H */
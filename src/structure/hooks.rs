@@ -0,0 +1,323 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+    thread,
+};
+
+use crate::error::CedarError;
+
+use super::{
+    build::BuildError,
+    project::{Project, ProjectError},
+    release::git,
+    task::shell,
+};
+
+/// The git hooks [hooks.git] can install, the client side hooks that run
+/// before or after a commit, push, or checkout.
+pub const GIT_HOOKS: &[&str] = &[
+    "pre-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+];
+
+/// The line marking a hook as written by cedar, so installing again replaces
+/// it without asking.
+const MARKER: &str = "# Installed by cedar hooks install";
+
+/// The directory git runs hooks from, .git/hooks unless core.hooksPath says
+/// otherwise.
+pub fn hooks_dir(root: &Path) -> Result<PathBuf, CedarError> {
+    let dir = git(root, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(root.join(dir.trim()))
+}
+
+/// The shell script of a hook, running the command lines in the project root
+/// and stopping at the first that fails.
+pub fn script(root: &Path, commands: &[String]) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n{} from [hooks.git] of cedar.toml.\nset -e\ncd '{}'\n",
+        MARKER,
+        root.display().to_string().replace('\'', r"'\''")
+    );
+
+    for command in commands {
+        script.push_str(command);
+        script.push('\n');
+    }
+
+    script
+}
+
+/// Whether a hook was written by cedar hooks install.
+pub fn is_installed(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|text| {
+        text.lines()
+            .nth(1)
+            .is_some_and(|line| line.starts_with(MARKER))
+    })
+}
+
+/// Writes the hooks of [hooks.git] into the hooks directory of the git
+/// repository of a project.
+///
+/// # Arguments
+///
+/// * 'root' - The root of the project.
+/// * 'hooks' - The command lines of each hook, by the name of the hook.
+/// * 'overwrite' - Asked before replacing a hook not written by cedar, an
+///   error keeps the hook and stops the install.
+///
+/// # Returns
+///
+/// * The paths of the hooks written.
+///
+pub fn install(
+    root: &Path,
+    hooks: &BTreeMap<String, Vec<String>>,
+    mut overwrite: impl FnMut(&Path) -> Result<(), CedarError>,
+) -> Result<Vec<PathBuf>, CedarError> {
+    if let Some(name) = hooks
+        .keys()
+        .find(|name| !GIT_HOOKS.contains(&name.as_str()))
+    {
+        return Err(ProjectError::UnknownHook(name.clone()).into());
+    }
+
+    let dir = hooks_dir(root)?;
+    fs::create_dir_all(&dir).map_err(|err| CedarError::io(&dir, err))?;
+
+    let mut written = Vec::new();
+    for (name, commands) in hooks {
+        let path = dir.join(name);
+        if path.exists() && !is_installed(&path) {
+            overwrite(&path)?;
+        }
+
+        fs::write(&path, script(root, commands)).map_err(|err| CedarError::io(&path, err))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                .map_err(|err| CedarError::io(&path, err))?;
+        }
+
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Runs the before-compile hook of [hooks.build], if there is one, for a
+/// translation unit about to be compiled.
+pub fn before_compile(project: &Project, source: &Path, object: &Path) -> Result<(), CedarError> {
+    match &project.manifest.hooks.build.before_compile {
+        Some(command) => {
+            let output = run_build_hook(
+                project,
+                "before-compile",
+                command,
+                (source, object),
+                None,
+                None,
+            )?;
+            print!("{}", output);
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Runs the after-compile hook of [hooks.build], if there is one, for a
+/// translation unit that was compiled, successfully or not.
+pub fn after_compile(
+    project: &Project,
+    source: &Path,
+    object: &Path,
+    success: bool,
+) -> Result<(), CedarError> {
+    match &project.manifest.hooks.build.after_compile {
+        Some(command) => {
+            let output = run_build_hook(
+                project,
+                "after-compile",
+                command,
+                (source, object),
+                Some(success),
+                None,
+            )?;
+            print!("{}", output);
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Passes the output of the compiler through the diagnostics hook of
+/// [hooks.build], if there is one.
+///
+/// # Returns
+///
+/// * What the hook printed, the output as it is without a hook or output.
+///
+pub fn filter_diagnostics(
+    project: &Project,
+    source: &Path,
+    object: &Path,
+    output: String,
+) -> Result<String, CedarError> {
+    match &project.manifest.hooks.build.diagnostics {
+        Some(command) if !output.is_empty() => run_build_hook(
+            project,
+            "diagnostics",
+            command,
+            (source, object),
+            None,
+            Some(&output),
+        ),
+        _ => Ok(output),
+    }
+}
+
+/// Runs a command of [hooks.build] in the project root, with the project
+/// and the translation unit in its environment.
+///
+/// # Arguments
+///
+/// * 'project' - The project being built.
+/// * 'hook' - The extension point, for CEDAR_HOOK and errors.
+/// * 'command' - The command line.
+/// * 'unit' - The source and object of the translation unit.
+/// * 'status' - Whether the compile succeeded, once it ran.
+/// * 'input' - Written to the stdin of the command.
+///
+/// # Returns
+///
+/// * What the command printed on stdout, BuildError::HookFailed with all of
+///   its output when it fails.
+///
+fn run_build_hook(
+    project: &Project,
+    hook: &str,
+    command: &str,
+    (source, object): (&Path, &Path),
+    status: Option<bool>,
+    input: Option<&str>,
+) -> Result<String, CedarError> {
+    let metadata = serde_json::to_string(&project.manifest.metadata).unwrap_or_default();
+
+    let mut shell = shell(command);
+    shell
+        .current_dir(&project.root)
+        .env("CEDAR_HOOK", hook)
+        .env("CEDAR_ROOT", &project.root)
+        .env("CEDAR_MANIFEST", project.manifest_path())
+        .env("CEDAR_OUT_DIR", project.out_dir())
+        .env("CEDAR_PROFILE", &project.profile)
+        .env("CEDAR_METADATA", metadata)
+        .env("CEDAR_SOURCE", source)
+        .env("CEDAR_OBJECT", object)
+        .stdin(match input {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(success) = status {
+        shell.env(
+            "CEDAR_STATUS",
+            match success {
+                true => "success",
+                false => "failure",
+            },
+        );
+    }
+
+    let mut child = shell
+        .spawn()
+        .map_err(|err| CedarError::command(&shell, err))?;
+
+    // The input is written while the output is read, a hook printing as it
+    // reads would otherwise block on a full pipe.
+    let output = thread::scope(|scope| {
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            scope.spawn(move || stdin.write_all(input.as_bytes()));
+        }
+        child.wait_with_output()
+    })
+    .map_err(|err| CedarError::command(&shell, err))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+
+    if !output.status.success() {
+        return Err(BuildError::HookFailed(
+            hook.to_owned(),
+            format!("{}{}", stdout, String::from_utf8_lossy(&output.stderr)),
+        )
+        .into());
+    }
+
+    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_hook_scripts() {
+        let script = script(
+            Path::new("/work/it's"),
+            &[String::from("cedar build"), String::from("cedar test")],
+        );
+
+        assert_eq!(
+            script,
+            "#!/bin/sh\n# Installed by cedar hooks install from [hooks.git] of cedar.toml.\nset -e\ncd '/work/it'\\''s'\ncedar build\ncedar test\n"
+        );
+    }
+
+    #[test]
+    fn reinstalls_own_hooks() {
+        let root = std::env::temp_dir().join(format!("cedar-hooks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        git(&root, &["init", "-q"]).unwrap();
+
+        let hooks = BTreeMap::from([(
+            String::from("pre-commit"),
+            vec![String::from("cedar build")],
+        )]);
+        let written = install(&root, &hooks, |_| panic!("nothing to overwrite")).unwrap();
+        assert!(is_installed(&written[0]));
+
+        // Installing again replaces the hook cedar wrote without asking.
+        install(&root, &hooks, |_| panic!("the hook is cedar's")).unwrap();
+
+        // A hook of someone else is only replaced when allowed.
+        fs::write(&written[0], "#!/bin/sh\nmake lint\n").unwrap();
+        assert!(!is_installed(&written[0]));
+        let mut asked = false;
+        install(&root, &hooks, |_| {
+            asked = true;
+            Ok(())
+        })
+        .unwrap();
+        assert!(asked);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde_json::{json, Value};
+
+use crate::{cli::CliError, error::CedarError};
+
+use super::{
+    build::{build, BuildOptions},
+    project::{Project, ProjectError},
+    target::{TargetFilter, TargetKind},
+};
+
+/// The editors cedar ide sets a project up for.
+///
+/// # Members
+///
+/// * 'Vscode' - VS Code, a .vscode/tasks.json running cedar and a
+///   .vscode/launch.json debugging the executables.
+/// * 'Clangd' - clangd, a .clangd pointing at the compile database of the
+///   project.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Editor {
+    Vscode,
+    Clangd,
+}
+
+impl FromStr for Editor {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vscode" => Ok(Self::Vscode),
+            "clangd" => Ok(Self::Clangd),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, cedar ide supports vscode or clangd.",
+                s
+            ))),
+        }
+    }
+}
+
+/// The first line of the files cedar ide writes, after the comment marker
+/// of the format. Files without it are never overwritten.
+const GENERATED: &str = "Generated by cedar ide";
+
+/// Writes the editor configuration of a project.
+///
+/// For clangd, the project is built with every target first, writing
+/// compile_commands.json to the out directory the .clangd points at.
+///
+/// # Arguments
+///
+/// * 'project' - The project to set up.
+/// * 'editor' - The editor to set it up for.
+///
+/// # Returns
+///
+/// * The paths of the written files.
+///
+pub fn ide(project: &Project, editor: Editor) -> Result<Vec<PathBuf>, CedarError> {
+    let files = match editor {
+        Editor::Clangd => {
+            build(
+                project,
+                &BuildOptions {
+                    targets: vec![TargetFilter::All],
+                    compile_commands: true,
+                    ..Default::default()
+                },
+            )?;
+
+            vec![(project.root.join(".clangd"), clangd(project))]
+        }
+        Editor::Vscode => {
+            let dir = project.root.join(".vscode");
+            vec![
+                (dir.join("tasks.json"), tasks(project)),
+                (dir.join("launch.json"), launch(project)),
+            ]
+        }
+    };
+
+    for (path, _) in &files {
+        if fs::read_to_string(path).is_ok_and(|existing| {
+            !existing
+                .lines()
+                .next()
+                .is_some_and(|line| line.contains(GENERATED))
+        }) {
+            return Err(ProjectError::AlreadyExists(format!("{:?}", path)).into());
+        }
+    }
+
+    let mut written = Vec::new();
+    for (path, contents) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| CedarError::io(parent, err))?;
+        }
+        fs::write(&path, contents).map_err(|err| CedarError::io(&path, err))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// The .clangd of a project, reading the compile database of its dev
+/// build.
+fn clangd(project: &Project) -> String {
+    format!(
+        "# {} clangd, rerun it after adding sources or changing flags.\nCompileFlags:\n  CompilationDatabase: {}\n",
+        GENERATED,
+        relative(&project.root, &project.out_dir())
+    )
+}
+
+/// The .vscode/tasks.json of a project: building, testing, and running it
+/// with cedar, with the errors of the compiler in the problems panel.
+fn tasks(project: &Project) -> String {
+    let has = |kind: TargetKind| project.targets.iter().any(|target| target.kind == kind);
+
+    let task = |args: &[&str], group: Option<(&str, bool)>| {
+        let mut task = json!({
+            "label": format!("cedar {}", args.join(" ")),
+            "type": "shell",
+            "command": "cedar",
+            "args": args,
+            "problemMatcher": ["$gcc"],
+        });
+        if let Some((kind, default)) = group {
+            task["group"] = json!({ "kind": kind, "isDefault": default });
+        }
+        task
+    };
+
+    let mut tasks = vec![
+        task(&["build"], Some(("build", true))),
+        task(&["build", "--all-targets"], Some(("build", false))),
+        task(&["build", "--release"], Some(("build", false))),
+    ];
+    if has(TargetKind::Test) {
+        tasks.push(task(&["test"], Some(("test", true))));
+    }
+    if has(TargetKind::Bin) {
+        tasks.push(task(&["run"], None));
+    }
+
+    jsonc("vscode", &json!({ "version": "2.0.0", "tasks": tasks }))
+}
+
+/// The .vscode/launch.json of a project, a configuration debugging each
+/// binary, example, and test with gdb, or lldb where it is the debugger of
+/// the toolchain. The executable is built before it starts.
+fn launch(project: &Project) -> String {
+    let debugger = match cfg!(target_os = "macos")
+        || project
+            .manifest
+            .build
+            .compiler
+            .eq_ignore_ascii_case("clang")
+    {
+        true => "lldb",
+        false => "gdb",
+    };
+
+    let configurations: Vec<Value> = project
+        .targets
+        .iter()
+        .filter(|target| {
+            matches!(
+                target.kind,
+                TargetKind::Bin | TargetKind::Example | TargetKind::Test
+            )
+        })
+        .map(|target| {
+            json!({
+                "name": format!("Debug {}", target.name),
+                "type": "cppdbg",
+                "request": "launch",
+                "program": format!(
+                    "${{workspaceFolder}}/{}",
+                    relative(&project.root, &project.output(target))
+                ),
+                "args": [],
+                "cwd": "${workspaceFolder}",
+                "MIMode": debugger,
+                "preLaunchTask": match target.kind {
+                    TargetKind::Bin => "cedar build",
+                    _ => "cedar build --all-targets",
+                },
+            })
+        })
+        .collect();
+
+    jsonc(
+        "vscode",
+        &json!({ "version": "0.2.0", "configurations": configurations }),
+    )
+}
+
+/// A JSON file of VS Code, which allows comments, marked as generated.
+fn jsonc(editor: &str, value: &Value) -> String {
+    format!(
+        "// {} {}, do not edit.\n{}\n",
+        GENERATED,
+        editor,
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    )
+}
+
+/// A path relative to the project root, with forward slashes.
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_generated_json() {
+        let text = jsonc("vscode", &json!({ "version": "2.0.0" }));
+
+        assert_eq!(
+            text,
+            "// Generated by cedar ide vscode, do not edit.\n{\n  \"version\": \"2.0.0\"\n}\n"
+        );
+        assert_eq!(
+            relative(Path::new("/p"), Path::new("/p/build/x/dev/app")),
+            "build/x/dev/app"
+        );
+    }
+}
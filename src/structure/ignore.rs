@@ -0,0 +1,353 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::manifest::Symlinks;
+
+/// The ignore files read in every directory, the rules of later files win.
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".cedarignore"];
+
+/// A rule of an ignore file.
+///
+/// # Fields
+///
+/// * 'base' - The directory of the ignore file, anchored patterns are
+///   relative to it.
+/// * 'pattern' - The glob, without its leading / or ! and trailing /.
+/// * 'negated' - Whether the rule re-includes what an earlier rule ignored.
+/// * 'directory' - Whether the rule only matches directories.
+/// * 'anchored' - Whether the pattern matches the path relative to the base,
+///   rather than the name of the file at any depth.
+///
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    base: PathBuf,
+    pattern: String,
+    negated: bool,
+    directory: bool,
+    anchored: bool,
+}
+
+/// The rules of the .gitignore and .cedarignore files that apply to a
+/// directory, with the gitignore syntax: globs with *, ?, [...], and **, a
+/// leading ! to re-include, a trailing / to only match directories, and a
+/// / elsewhere to anchor the pattern to the directory of the file.
+#[derive(Debug, Clone, Default)]
+pub struct Ignore {
+    rules: Vec<Rule>,
+}
+
+impl Ignore {
+    /// The rules of the ignore files in a directory and its parents, up to
+    /// the root of the project (or repository) it is in.
+    pub fn for_dir(dir: &Path) -> Self {
+        let mut ignore = Self::default();
+
+        let ancestors: Vec<&Path> = match dir.ancestors().position(|ancestor| {
+            ancestor.join("cedar.toml").is_file() || ancestor.join(".git").exists()
+        }) {
+            Some(root) => dir.ancestors().take(root + 1).collect(),
+            None => vec![dir],
+        };
+
+        for ancestor in ancestors.into_iter().rev() {
+            ignore.read(ancestor);
+        }
+
+        ignore
+    }
+    /// Adds the rules of the ignore files of a directory.
+    pub fn read(&mut self, dir: &Path) {
+        for name in IGNORE_FILES {
+            if let Ok(contents) = fs::read_to_string(dir.join(name)) {
+                self.parse(dir, &contents);
+            }
+        }
+    }
+    /// Adds the rules of an ignore file in a directory.
+    pub fn parse(&mut self, base: &Path, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim_end();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (directory, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let pattern = line.trim_start_matches('/');
+
+            if pattern.is_empty() {
+                continue;
+            }
+
+            self.rules.push(Rule {
+                base: base.to_path_buf(),
+                pattern: pattern.to_owned(),
+                negated,
+                directory,
+                anchored,
+            });
+        }
+    }
+    /// Whether a file or directory is ignored, the last rule matching it
+    /// decides.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                if rule.directory && !is_dir {
+                    return false;
+                }
+
+                match rule.anchored {
+                    true => path.strip_prefix(&rule.base).is_ok_and(|relative| {
+                        glob(
+                            rule.pattern.as_bytes(),
+                            relative.to_string_lossy().replace('\\', "/").as_bytes(),
+                        )
+                    }),
+                    false => {
+                        path.starts_with(&rule.base)
+                            && glob(rule.pattern.as_bytes(), name.as_bytes())
+                    }
+                }
+            })
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+/// Every file under a directory that is not ignored, sorted. The ignore
+/// files of the directories walked are read along the way, and ignored
+/// directories are not walked at all, nor are subprojects, directories with
+/// a cedar.toml of their own.
+///
+/// # Arguments
+///
+/// * 'dir' - The directory to walk.
+/// * 'symlinks' - What to do with the symlinks found under it.
+///
+pub fn files(dir: &Path, symlinks: Symlinks) -> Result<Vec<PathBuf>, io::Error> {
+    let mut files = Vec::new();
+    let mut walking = Vec::new();
+    walk(
+        dir,
+        Ignore::for_dir(dir),
+        symlinks,
+        &mut walking,
+        &mut files,
+    )?;
+    files.sort();
+
+    Ok(files)
+}
+
+/// Whether to scan an entry according to the symlink policy, a symlink is
+/// an error under Symlinks::Error.
+pub fn keep(path: &Path, symlinks: Symlinks) -> Result<bool, io::Error> {
+    if !fs::symlink_metadata(path)?.is_symlink() {
+        return Ok(true);
+    }
+
+    match symlinks {
+        Symlinks::Follow => Ok(true),
+        Symlinks::Skip => Ok(false),
+        Symlinks::Error => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is a symlink, which symlinks = \"error\" in [build] forbids",
+                path.display()
+            ),
+        )),
+    }
+}
+
+/// Walks a directory, walking holding the canonical paths of the
+/// directories it is in so a symlink back to one of them is not followed.
+fn walk(
+    dir: &Path,
+    mut ignore: Ignore,
+    symlinks: Symlinks,
+    walking: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), io::Error> {
+    // The files of the starting directory were read by for_dir.
+    if !ignore.rules.iter().any(|rule| rule.base == dir) {
+        ignore.read(dir);
+    }
+
+    let canonical = fs::canonicalize(dir)?;
+
+    if walking.contains(&canonical) {
+        return Ok(());
+    }
+
+    walking.push(canonical);
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_dir = path.is_dir();
+
+        if path.file_name().is_some_and(|name| name == ".git")
+            || ignore.is_ignored(&path, is_dir)
+            || !keep(&path, symlinks)?
+        {
+            continue;
+        }
+
+        if is_dir && path.join("cedar.toml").is_file() {
+            continue;
+        }
+
+        if is_dir {
+            walk(&path, ignore.clone(), symlinks, walking, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    walking.pop();
+
+    Ok(())
+}
+
+/// Matches a path against a glob, * and ? never match a /, ** matches
+/// anything, and **/ also matches no directory at all.
+pub(crate) fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            if let [b'/', after @ ..] = rest {
+                if glob(after, text) {
+                    return true;
+                }
+            }
+
+            (0..=text.len()).any(|index| glob(rest, &text[index..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&index| index == 0 || text[index - 1] != b'/')
+            .any(|index| glob(rest, &text[index..])),
+        [b'?', rest @ ..] => match text {
+            [ch, text @ ..] if *ch != b'/' => glob(rest, text),
+            _ => false,
+        },
+        [b'[', class @ ..] => match (class_end(class), text) {
+            (Some(end), [ch, text @ ..]) => {
+                in_class(&class[..end], *ch) && glob(&class[end + 1..], text)
+            }
+            // Without a closing ] the [ is literal.
+            (None, [b'[', text @ ..]) => glob(class, text),
+            _ => false,
+        },
+        [b'\\', escaped, rest @ ..] => match text {
+            [ch, text @ ..] if ch == escaped => glob(rest, text),
+            _ => false,
+        },
+        [literal, rest @ ..] => match text {
+            [ch, text @ ..] if ch == literal => glob(rest, text),
+            _ => false,
+        },
+    }
+}
+
+/// Where the ] closing a class is, a ] right at the start is a member.
+fn class_end(class: &[u8]) -> Option<usize> {
+    let start = match class.first() {
+        Some(b'!' | b'^') => 1,
+        _ => 0,
+    };
+
+    class
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, ch)| **ch == b']')
+        .map(|(index, _)| index)
+}
+
+/// Whether a character is a member of a class like a-z_ or !0-9.
+fn in_class(class: &[u8], ch: u8) -> bool {
+    let (negated, mut members) = match class {
+        [b'!' | b'^', rest @ ..] => (true, rest),
+        _ => (false, class),
+    };
+
+    let mut found = false;
+
+    while let Some(&first) = members.first() {
+        match members {
+            [low, b'-', high, rest @ ..] => {
+                found |= (*low..=*high).contains(&ch);
+                members = rest;
+            }
+            [_, rest @ ..] => {
+                found |= first == ch;
+                members = rest;
+            }
+            [] => break,
+        }
+    }
+
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_like_git() {
+        assert!(glob(b"*.c~", b"main.c~"));
+        assert!(!glob(b"*.c", b"src/main.c"));
+        assert!(glob(b"**/gen/*.c", b"gen/a.c"));
+        assert!(glob(b"src/**/tmp", b"src/a/b/tmp"));
+        assert!(glob(b"[a-c]?.[ch]", b"b1.h"));
+        assert!(!glob(b"[!a-c]*", b"abc"));
+
+        let root = Path::new("/p");
+        let mut ignore = Ignore::default();
+        ignore.parse(
+            root,
+            "# editor backups\n*~\n/src/generated/\nbuild/\n*.c\n!keep.c\nsrc/**/*.tmp.cpp\n",
+        );
+
+        assert!(ignore.is_ignored(Path::new("/p/src/main.c~"), false));
+        assert!(ignore.is_ignored(Path::new("/p/src/generated"), true));
+        assert!(!ignore.is_ignored(Path::new("/p/lib/src/generated"), true));
+        assert!(ignore.is_ignored(Path::new("/p/deps/build"), true));
+        assert!(!ignore.is_ignored(Path::new("/p/build"), false));
+        assert!(ignore.is_ignored(Path::new("/p/src/a.c"), false));
+        assert!(!ignore.is_ignored(Path::new("/p/src/keep.c"), false));
+        assert!(ignore.is_ignored(Path::new("/p/src/x/y.tmp.cpp"), false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_at_symlink_loops() {
+        let dir = std::env::temp_dir().join(format!("cedar-symlinks-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::write(dir.join("src/nested/a.c"), "").unwrap();
+        std::os::unix::fs::symlink("..", dir.join("src/nested/up")).unwrap();
+
+        let found = files(&dir.join("src"), Symlinks::Follow).unwrap();
+        assert_eq!(found, vec![dir.join("src/nested/a.c")]);
+        assert!(files(&dir.join("src"), Symlinks::Error).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+
+use crate::error::CedarError;
+
+use super::{
+    build::{compiler_version, target_triple, Toolchain},
+    manifest::{Manifest, ProjectKind},
+    project::Project,
+    target::TargetKind,
+    timings::{self, Record},
+};
+
+/// An overview of a project, what cedar info prints.
+///
+/// # Fields
+///
+/// * 'name', 'version', 'kind' - From [meta].
+/// * 'compiler' - The compiler of [build], and the first line of its
+///   --version output when it runs.
+/// * 'targets' - Every target, by name.
+/// * 'dependencies' - Every declared dependency and where it comes from,
+///   e.g. path ../util, system, or conan 1.3.1.
+/// * 'sources' - The number of sources of the library part of the project.
+/// * 'translation_units' - The number of sources of every target, the
+///   library part included.
+/// * 'last_build' - The newest build in the timings history, None before
+///   the first build.
+/// * 'artifacts' - Where the artifacts of the default targets go, from the
+///   root, and whether they are there yet.
+///
+#[derive(Debug, Clone)]
+pub struct Info {
+    pub name: String,
+    pub version: String,
+    pub kind: ProjectKind,
+    pub compiler: (String, Option<String>),
+    pub targets: Vec<(String, TargetKind)>,
+    pub dependencies: Vec<(String, String)>,
+    pub sources: usize,
+    pub translation_units: usize,
+    pub last_build: Option<Record>,
+    pub artifacts: Vec<(PathBuf, bool)>,
+}
+
+impl Info {
+    /// Collects the overview of a project without building it.
+    pub fn load(project: &Project) -> Result<Self, CedarError> {
+        let manifest = &project.manifest;
+        let compiler = &manifest.build.compiler;
+
+        let version = Toolchain::from_compiler(compiler)
+            .ok()
+            .and_then(|toolchain| compiler_version(&toolchain.cc));
+
+        let target_sources: usize = project
+            .targets
+            .iter()
+            .map(|target| target.sources.len())
+            .sum();
+
+        let last_build = timings::history(&project.build_dir).into_iter().next();
+
+        // The artifacts are those of the last build, or of the profile of
+        // the project before the first one.
+        let out_dir = match &last_build {
+            Some(record) if !record.profile.is_empty() => project
+                .build_dir
+                .join(target_triple(compiler))
+                .join(&record.profile),
+            _ => project.out_dir(),
+        };
+
+        let mut artifacts = Vec::new();
+        for target in project
+            .targets
+            .iter()
+            .filter(|target| target.kind.is_default())
+        {
+            for artifact in target.artifacts(manifest)? {
+                let path = out_dir.join(target.output_dir()).join(artifact.name());
+                let exists = path.is_file();
+
+                artifacts.push((
+                    path.strip_prefix(&project.root)
+                        .map(PathBuf::from)
+                        .unwrap_or(path),
+                    exists,
+                ));
+            }
+        }
+
+        Ok(Self {
+            name: manifest.meta.name.clone(),
+            version: manifest.meta.version.clone(),
+            kind: manifest.meta.kind,
+            compiler: (compiler.clone(), version),
+            targets: project
+                .targets
+                .iter()
+                .map(|target| (target.name.clone(), target.kind))
+                .collect(),
+            dependencies: dependencies(manifest),
+            sources: project.library_sources.len(),
+            translation_units: project.library_sources.len() + target_sources,
+            last_build,
+            artifacts,
+        })
+    }
+}
+
+/// The declared dependencies of a manifest and where each comes from.
+fn dependencies(manifest: &Manifest) -> Vec<(String, String)> {
+    let optional = |source: &str, optional: bool| match optional {
+        true => format!("{}, optional", source),
+        false => source.to_owned(),
+    };
+
+    let packages = manifest
+        .dependencies
+        .packages
+        .iter()
+        .map(|(name, dependency)| {
+            let source = match (&dependency.path, &dependency.git) {
+                (Some(path), _) => format!("path {}", path),
+                (None, Some(url)) => format!("git {}", url),
+                (None, None) => String::from("no source"),
+            };
+            (name.clone(), optional(&source, dependency.optional))
+        });
+    let system = manifest
+        .system_deps
+        .iter()
+        .map(|(name, dependency)| (name.clone(), optional("system", dependency.is_optional())));
+    let conan = manifest
+        .dependencies
+        .conan
+        .iter()
+        .map(|(name, version)| (name.clone(), format!("conan {}", version)));
+    let vcpkg = manifest
+        .dependencies
+        .vcpkg
+        .iter()
+        .map(|name| (name.clone(), String::from("vcpkg")));
+
+    packages.chain(system).chain(conan).chain(vcpkg).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_dependencies() {
+        let manifest = Manifest::parse(
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"GCC\"\ncflags = []\n\n[dependencies]\nutil = { path = \"../util\" }\n\n[dependencies.conan]\nzlib = \"1.3.1\"\n\n[system-deps]\ntls = { pkg = \"openssl\", optional = true }\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            dependencies(&manifest),
+            [
+                (String::from("util"), String::from("path ../util")),
+                (String::from("tls"), String::from("system, optional")),
+                (String::from("zlib"), String::from("conan 1.3.1")),
+            ]
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::{fs, path::Path};
 
 use crate::structure::project::ProjectError;
 
-use super::manifest::Manifest;
+use super::manifest::{Manifest, ProjectKind};
 
 /// Ensures the current path is empty, then creates the default manifest,
 /// the src, include, and build folders, and initializes a git repository.
@@ -10,9 +10,17 @@ use super::manifest::Manifest;
 /// # Arguments
 ///
 /// * 'path' - The empty path to initialize as a project, any type that can be
-///         coerced into a path.
-///         
-pub fn init<P: AsRef<Path>>(path: P) -> Result<(), ProjectError> {
+///   coerced into a path.
+/// * 'non_empty' - Allows the path to already contain files, which are kept.
+///   The hello world main.c is then only written when src/ is new.
+/// * 'kind' - The kind of project. A header-only project gets no src/, but
+///   a header named after it in include/ and check_headers on.
+///
+pub fn init<P: AsRef<Path>>(
+    path: P,
+    non_empty: bool,
+    kind: ProjectKind,
+) -> Result<(), ProjectError> {
     let path = path.as_ref();
 
     // Ensure the path is an existing directory.
@@ -21,26 +29,18 @@ pub fn init<P: AsRef<Path>>(path: P) -> Result<(), ProjectError> {
     }
 
     // Ensure the path is empty.
-    if path.read_dir()?.next().is_some() {
+    if !non_empty && path.read_dir()?.next().is_some() {
         return Err(ProjectError::NonEmptyPath(format!("{:?}", path)));
     }
 
-    let (src, include, build) = (
-        path.join("src/"),
-        path.join("include/"),
-        path.join("build/"),
-    );
-
-    // Create src, include, build, and .cedar directories.
-    fs::create_dir(&src)?;
-    fs::create_dir(&include)?;
-    fs::create_dir(&build)?;
-
-    // Create default main.c file in src.
-    let hello_world =
-        "#include <stdio.h>\n\nint main() {\n\tprintf(\"Hello World!\");\n\treturn 0;\n}";
+    if path.join("cedar.toml").exists() {
+        return Err(ProjectError::AlreadyExists(format!(
+            "{:?}",
+            path.join("cedar.toml")
+        )));
+    }
 
-    fs::write(src.join("main.c"), hello_world)?;
+    let (src, include, build) = (path.join("src"), path.join("include"), path.join("build"));
 
     let mut manifest = Manifest::default();
 
@@ -50,8 +50,54 @@ pub fn init<P: AsRef<Path>>(path: P) -> Result<(), ProjectError> {
             return Err(ProjectError::InvalidPath(format!("{:?}", path)));
         }
     };
+    manifest.meta.kind = kind;
+
+    // Create include and build directories, and src unless the project is
+    // headers alone.
+    fs::create_dir_all(&include)?;
+    fs::create_dir_all(&build)?;
+
+    if kind.is_header_only() {
+        manifest.build.check_headers = true;
+
+        let header = include.join(format!("{}.h", manifest.meta.name));
+
+        if !header.exists() {
+            fs::write(&header, header_only(&manifest.meta.name))?;
+        }
+    } else {
+        let new_src = !src.exists();
+        fs::create_dir_all(&src)?;
+
+        // Create default main.c file in src.
+        let hello_world =
+            "#include <stdio.h>\n\nint main() {\n\tprintf(\"Hello World!\");\n\treturn 0;\n}";
+
+        if new_src {
+            fs::write(src.join("main.c"), hello_world)?;
+        }
+    }
 
     fs::write(path.join("cedar.toml"), manifest.as_string()?)?;
 
     Ok(())
 }
+
+/// The header of a new header-only project, a static inline function
+/// behind an include guard.
+fn header_only(name: &str) -> String {
+    let identifier: String = name
+        .chars()
+        .map(|ch| match ch.is_ascii_alphanumeric() {
+            true => ch,
+            false => '_',
+        })
+        .collect();
+    let guard = format!("{}_H", identifier.to_ascii_uppercase());
+
+    format!(
+        "#ifndef {guard}\n#define {guard}\n\nstatic inline int {name}_answer(void) {{\n\treturn 42;\n}}\n\n#endif /* {guard} */\n",
+        guard = guard,
+        name = identifier.to_ascii_lowercase()
+    )
+}
@@ -0,0 +1,113 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::CedarError;
+
+use super::{
+    build::symlink,
+    ignore,
+    pkgconfig::pkg_config,
+    project::{Project, ProjectError},
+    target::Artifact,
+};
+
+/// Installs the artifacts of a built project under a prefix: executables into
+/// bin/, libraries (with their soname links) into lib/, headers from include/
+/// into include/, and a pkg-config file into lib/pkgconfig/ for libraries.
+///
+/// # Arguments
+///
+/// * 'project' - The project, which must already be built.
+/// * 'prefix' - The directory to install into, e.g. /usr/local.
+///
+/// # Returns
+///
+/// * The paths of the installed files.
+///
+pub fn install<P: AsRef<Path>>(project: &Project, prefix: P) -> Result<Vec<PathBuf>, CedarError> {
+    let prefix = prefix.as_ref();
+
+    let manifest = &project.manifest;
+    let name = &manifest.meta.name;
+
+    let build_path = &project.out_dir();
+    let (bin, lib, include) = (
+        prefix.join("bin"),
+        prefix.join("lib"),
+        prefix.join("include"),
+    );
+
+    let mut installed = Vec::new();
+
+    let targets = project
+        .targets
+        .iter()
+        .filter(|target| target.kind.is_default());
+
+    for artifact in targets
+        .map(|target| target.artifacts(manifest))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+    {
+        match artifact {
+            Artifact::Executable(file) => installed.push(copy(&build_path.join(file), &bin)?),
+            Artifact::StaticLib(file) => installed.push(copy(&build_path.join(file), &lib)?),
+            Artifact::SharedLib { name: file, soname } => {
+                installed.push(copy(&build_path.join(&file), &lib)?);
+
+                if let Some(soname) = soname {
+                    let plain = format!("lib{}.so", name);
+                    symlink(&lib, &file, &soname).map_err(|err| CedarError::io(&lib, err))?;
+                    symlink(&lib, &soname, &plain).map_err(|err| CedarError::io(&lib, err))?;
+                    installed.push(lib.join(soname));
+                    installed.push(lib.join(plain));
+                }
+            }
+        }
+    }
+
+    if manifest.meta.kind.is_bin() {
+        return Ok(installed);
+    }
+
+    let include_path = &project.include_dir;
+
+    if include_path.is_dir() {
+        let headers = ignore::files(include_path, project.manifest.build.symlinks)
+            .map_err(|err| CedarError::io(include_path, err))?;
+
+        for header in headers {
+            let relative = header.strip_prefix(include_path).unwrap_or(&header);
+            let dir = include.join(relative.parent().unwrap_or(Path::new("")));
+
+            installed.push(copy(&header, &dir)?);
+        }
+    }
+
+    let pkgconfig = lib.join("pkgconfig");
+    fs::create_dir_all(&pkgconfig).map_err(|err| CedarError::io(&pkgconfig, err))?;
+
+    let pc_path = pkgconfig.join(format!("{}.pc", name));
+    fs::write(&pc_path, pkg_config(manifest, prefix, "lib"))
+        .map_err(|err| CedarError::io(&pc_path, err))?;
+    installed.push(pc_path);
+
+    Ok(installed)
+}
+
+/// Copies a file into a directory, creating the directory if needed.
+fn copy(file: &Path, dir: &Path) -> Result<PathBuf, CedarError> {
+    if !file.exists() {
+        return Err(ProjectError::InvalidPath(format!("{:?}", file)).into());
+    }
+
+    fs::create_dir_all(dir).map_err(|err| CedarError::io(dir, err))?;
+
+    let destination = dir.join(file.file_name().unwrap_or_default());
+    fs::copy(file, &destination).map_err(|err| CedarError::io(&destination, err))?;
+
+    Ok(destination)
+}
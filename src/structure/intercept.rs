@@ -0,0 +1,324 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CedarError;
+
+use super::{build::Language, ninja::quote, project::ProjectError, sysdeps};
+
+/// The compiler drivers intercept puts wrappers in front of.
+const COMPILERS: &[&str] = &["cc", "gcc", "clang", "c++", "g++", "clang++"];
+
+/// Options of the compiler taking their value as the next argument.
+const VALUE_OPTIONS: &[&str] = &[
+    "-o",
+    "-I",
+    "-D",
+    "-U",
+    "-MF",
+    "-MT",
+    "-MQ",
+    "-include",
+    "-imacros",
+    "-isystem",
+    "-iquote",
+    "-idirafter",
+    "-x",
+    "-L",
+    "-l",
+    "-Xlinker",
+    "-arch",
+    "--sysroot",
+    "-target",
+];
+
+/// An entry of compile_commands.json, the compile of a source.
+///
+/// # Fields
+///
+/// * 'directory' - The directory the compiler ran in.
+/// * 'file' - The source compiled.
+/// * 'arguments' - The command line, the compiler first.
+/// * 'output' - The file it wrote, if it was given with -o.
+///
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompileCommand {
+    pub directory: PathBuf,
+    pub file: PathBuf,
+    pub arguments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+}
+
+/// Runs a build command with wrappers in front of the compilers on the
+/// PATH, and of those $CC and $CXX name, recording every compile it runs,
+/// and writes them to compile_commands.json in the given directory. A
+/// compiler the command calls by its path otherwise is not seen.
+///
+/// The database is written even when the command fails, with the compiles
+/// that ran before it did.
+///
+/// # Arguments
+///
+/// * 'root' - The directory to run the command in and write the database to.
+/// * 'command_line' - The command to run and its arguments.
+///
+/// # Returns
+///
+/// * The path of the database and how many compiles it holds.
+///
+pub fn intercept(root: &Path, command_line: &[String]) -> Result<(PathBuf, usize), CedarError> {
+    let Some((program, args)) = command_line.split_first() else {
+        return Err(ProjectError::InvalidPath(String::from("No command to intercept.")).into());
+    };
+
+    let overrides: Vec<(&str, String)> = ["CC", "CXX"]
+        .into_iter()
+        .filter_map(|variable| Some((variable, env::var(variable).ok()?)))
+        .collect();
+
+    let dir = env::temp_dir().join(format!("cedar-intercept-{}", process::id()));
+    let result = run(&dir, root, program, args, &overrides);
+    let _ = fs::remove_dir_all(&dir);
+    let (success, commands) = result?;
+
+    let path = write_database(root, &commands)?;
+
+    if !success {
+        return Err(ProjectError::ToolFailed("The intercepted command").into());
+    }
+
+    Ok((path, commands.len()))
+}
+
+/// Writes the compiles to compile_commands.json in a directory, returning
+/// its path.
+pub fn write_database(dir: &Path, commands: &[CompileCommand]) -> Result<PathBuf, CedarError> {
+    let path = dir.join("compile_commands.json");
+    let contents = serde_json::to_string_pretty(commands).map_err(std::io::Error::from)?;
+    fs::write(&path, contents + "\n").map_err(|err| CedarError::io(&path, err))?;
+
+    Ok(path)
+}
+
+/// Writes the wrappers to dir/bin/, runs the command with them first on the
+/// PATH, and reads the compiles they recorded in dir/records/.
+///
+/// # Arguments
+///
+/// * 'overrides' - The variables naming a compiler, $CC and $CXX, and
+///   their values. Each gets a wrapper of its own when it names a single
+///   program, which make and configure scripts call then.
+///
+fn run(
+    dir: &Path,
+    root: &Path,
+    program: &str,
+    args: &[String],
+    overrides: &[(&str, String)],
+) -> Result<(bool, Vec<CompileCommand>), CedarError> {
+    let bin = dir.join("bin");
+    let records = dir.join("records");
+    fs::create_dir_all(&bin).map_err(|err| CedarError::io(&bin, err))?;
+    fs::create_dir_all(&records).map_err(|err| CedarError::io(&records, err))?;
+
+    let write_wrapper = |name: &str, real: &Path| {
+        let wrapper = bin.join(name);
+        fs::write(&wrapper, wrapper_script(&records, real))
+            .map_err(|err| CedarError::io(&wrapper, err))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(&wrapper, fs::Permissions::from_mode(0o755))
+                .map_err(|err| CedarError::io(&wrapper, err))?;
+        }
+
+        Ok::<_, CedarError>(wrapper)
+    };
+
+    for compiler in COMPILERS {
+        if let Some(real) = sysdeps::which(compiler) {
+            write_wrapper(compiler, &real)?;
+        }
+    }
+
+    let mut variables = Vec::new();
+
+    for (variable, compiler) in overrides {
+        // A compiler with arguments, e.g. "ccache gcc", is left alone.
+        let real = match compiler.contains(['/', '\\']) {
+            true => Some(PathBuf::from(compiler)).filter(|path| path.is_file()),
+            false if compiler.contains(char::is_whitespace) => None,
+            false => sysdeps::which(compiler),
+        };
+
+        if let Some(real) = real {
+            let wrapper = write_wrapper(&format!("cedar-{}", variable.to_lowercase()), &real)?;
+            variables.push((variable, wrapper));
+        }
+    }
+
+    let path = env::join_paths(
+        std::iter::once(bin.clone()).chain(
+            env::var_os("PATH")
+                .map(|path| env::split_paths(&path).collect::<Vec<_>>())
+                .unwrap_or_default(),
+        ),
+    )
+    .map_err(|err| CedarError::io(&bin, std::io::Error::other(err)))?;
+
+    let mut command = process::Command::new(program);
+    command
+        .args(args)
+        .current_dir(root)
+        .env("PATH", path)
+        .envs(variables);
+
+    let status = match command.status() {
+        Ok(status) => status,
+        Err(err) => return Err(CedarError::command(&command, err)),
+    };
+
+    let mut entries = fs::read_dir(&records)
+        .map_err(|err| CedarError::io(&records, err))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    let mut commands: Vec<CompileCommand> = Vec::new();
+
+    for (_, record) in entries {
+        let contents = fs::read(&record).map_err(|err| CedarError::io(&record, err))?;
+        let mut fields = contents
+            .split(|byte| *byte == 0)
+            .map(|field| String::from_utf8_lossy(field).into_owned())
+            .collect::<Vec<_>>();
+        fields.pop();
+
+        if fields.len() < 2 {
+            continue;
+        }
+
+        let directory = PathBuf::from(fields.remove(0));
+
+        // A source compiled again replaces its earlier compile.
+        for command in compile_commands(&directory, fields) {
+            commands.retain(|other| other.file != command.file || other.output != command.output);
+            commands.push(command);
+        }
+    }
+
+    Ok((status.success(), commands))
+}
+
+/// The wrapper of a compiler: it records the directory and command line to
+/// a file of its own, NUL separated, then runs the real compiler.
+fn wrapper_script(records: &Path, real: &Path) -> String {
+    let real = quote(&real.to_string_lossy());
+
+    format!(
+        "#!/bin/sh\nrecord=$(mktemp {}) && printf '%s\\0' \"$PWD\" {} \"$@\" > \"$record\"\nexec {} \"$@\"\n",
+        quote(&format!("{}/XXXXXXXX", records.to_string_lossy())),
+        real,
+        real
+    )
+}
+
+/// The compiles of a compiler command line, one per source it names. Commands
+/// that only preprocess or print dependencies compile nothing.
+fn compile_commands(directory: &Path, arguments: Vec<String>) -> Vec<CompileCommand> {
+    let mut sources = Vec::new();
+    let mut output = None;
+    let mut args = arguments.iter().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-E" | "-M" | "-MM" => return Vec::new(),
+            "-o" => output = args.next().map(|output| directory.join(output)),
+            arg if VALUE_OPTIONS.contains(&arg) => {
+                args.next();
+            }
+            arg if arg.starts_with('-') => {}
+            arg if Language::from_path(arg).is_some() => sources.push(directory.join(arg)),
+            _ => {}
+        }
+    }
+
+    // With several sources -o names what they are linked into.
+    let output = output.filter(|_| sources.len() == 1);
+
+    sources
+        .into_iter()
+        .map(|file| CompileCommand {
+            directory: directory.to_path_buf(),
+            file,
+            arguments: arguments.clone(),
+            output: output.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_compiles_from_command_lines() {
+        let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+        let dir = Path::new("/p");
+
+        let commands = compile_commands(dir, args("gcc -Iinc -o obj/a.o -c src/a.c"));
+        assert_eq!(
+            commands,
+            [CompileCommand {
+                directory: PathBuf::from("/p"),
+                file: PathBuf::from("/p/src/a.c"),
+                arguments: args("gcc -Iinc -o obj/a.o -c src/a.c"),
+                output: Some(PathBuf::from("/p/obj/a.o")),
+            }]
+        );
+
+        let commands = compile_commands(dir, args("cc -x c -o tool a.c b.cpp -lm"));
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[1].output, None);
+
+        assert!(compile_commands(dir, args("gcc -MM src/a.c")).is_empty());
+        assert!(compile_commands(dir, args("gcc -o tool a.o -L lib")).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wraps_the_compiler_of_cc() {
+        let Some(gcc) = sysdeps::which("gcc") else {
+            return;
+        };
+
+        let root = env::temp_dir().join(format!("cedar-intercept-cc-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.c"), "int a;\n").unwrap();
+
+        // Called by its path, the compiler is only seen through $CC.
+        let (success, commands) = run(
+            &root.join("wrappers"),
+            &root,
+            "sh",
+            &[String::from("-c"), String::from("\"$CC\" -c a.c -o a.o")],
+            &[("CC", gcc.display().to_string())],
+        )
+        .unwrap();
+
+        assert!(success);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].file, root.join("a.c"));
+        assert_eq!(commands[0].arguments[0], gcc.display().to_string());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
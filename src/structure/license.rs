@@ -0,0 +1,355 @@
+use std::{
+    fs,
+    path::Path,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::CedarError;
+
+use super::{
+    deps::{self, Package},
+    manifest::Manifest,
+    project::{Project, ProjectError},
+};
+
+/// The built in license templates, keyed by SPDX identifier. Templates use
+/// `{year}` and `{holder}` as placeholders.
+const TEMPLATES: [(&str, &str); 8] = [
+    ("MIT", include_str!("licenses/MIT.txt")),
+    ("Apache-2.0", include_str!("licenses/Apache-2.0.txt")),
+    ("BSD-2-Clause", include_str!("licenses/BSD-2-Clause.txt")),
+    ("BSD-3-Clause", include_str!("licenses/BSD-3-Clause.txt")),
+    ("ISC", include_str!("licenses/ISC.txt")),
+    ("0BSD", include_str!("licenses/0BSD.txt")),
+    ("Zlib", include_str!("licenses/Zlib.txt")),
+    ("Unlicense", include_str!("licenses/Unlicense.txt")),
+];
+
+/// Returns the SPDX identifiers cedar has templates for.
+pub fn supported() -> Vec<&'static str> {
+    TEMPLATES.iter().map(|(id, _)| *id).collect()
+}
+
+/// Finds the canonical SPDX identifier and template for the given id, the
+/// comparison is case insensitive so `mit` and `MIT` are both accepted.
+fn template(id: &str) -> Option<(&'static str, &'static str)> {
+    TEMPLATES
+        .iter()
+        .find(|(spdx, _)| spdx.eq_ignore_ascii_case(id))
+        .copied()
+}
+
+/// Writes a LICENSE file into the project and records the license in the
+/// manifest.
+///
+/// # Arguments
+///
+/// * 'path' - The root of the project, which must already contain a manifest.
+/// * 'id' - The SPDX identifier of the license.
+/// * 'holder' - The copyright holder, when None the git user name is used,
+///   falling back to the project name.
+///
+pub fn scaffold_license<P: AsRef<Path>>(
+    path: P,
+    id: &str,
+    holder: Option<&str>,
+) -> Result<(), ProjectError> {
+    let path = path.as_ref();
+
+    let (spdx, text) = match template(id) {
+        Some(template) => template,
+        None => return Err(ProjectError::UnknownLicense(id.to_owned())),
+    };
+
+    let manifest_path = path.join("cedar.toml");
+    let mut manifest = Manifest::parse(&fs::read_to_string(&manifest_path)?)?;
+
+    let holder = match holder {
+        Some(holder) => holder.to_owned(),
+        None => git_user_name().unwrap_or_else(|| manifest.meta.name.clone()),
+    };
+
+    let license = text
+        .replace("{year}", &current_year().to_string())
+        .replace("{holder}", &holder);
+
+    fs::write(path.join("LICENSE"), license)?;
+
+    manifest.meta.license = Some(spdx.to_owned());
+    fs::write(manifest_path, manifest.as_string()?)?;
+
+    Ok(())
+}
+
+/// The names license files are conventionally given, in the order they are
+/// looked for.
+const LICENSE_FILES: [&str; 8] = [
+    "LICENSE",
+    "LICENSE.txt",
+    "LICENSE.md",
+    "LICENCE",
+    "COPYING",
+    "COPYING.txt",
+    "COPYING.LIB",
+    "COPYING.md",
+];
+
+/// Phrases identifying well known license texts, checked in order so the
+/// more specific GNU licenses come before the plain GPL.
+const FINGERPRINTS: [(&str, &[&str]); 14] = [
+    ("AGPL-3.0", &["GNU AFFERO GENERAL PUBLIC LICENSE"]),
+    (
+        "LGPL-3.0",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    (
+        "LGPL-2.1",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"],
+    ),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("MPL-2.0", &["Mozilla Public License", "Version 2.0"]),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("BSL-1.0", &["Boost Software License"]),
+    (
+        "Unlicense",
+        &["free and unencumbered software released into the public domain"],
+    ),
+    ("Zlib", &["Altered source versions must be plainly marked"]),
+    (
+        "BSD-3-Clause",
+        &[
+            "Redistribution and use in source and binary forms",
+            "endorse or promote",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &["Redistribution and use in source and binary forms"],
+    ),
+    (
+        "ISC",
+        &[
+            "Permission to use, copy, modify, and",
+            "provided that the above copyright notice",
+        ],
+    ),
+    ("0BSD", &["Permission to use, copy, modify, and"]),
+];
+
+/// Guesses the SPDX identifier of a license text.
+pub fn detect(text: &str) -> Option<&'static str> {
+    // Line breaks fall anywhere in license texts.
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if text.contains("Permission is hereby granted, free of charge") {
+        return Some("MIT");
+    }
+
+    FINGERPRINTS
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        .map(|(id, _)| *id)
+}
+
+/// Whether a license is denied. In an SPDX expression every alternative of
+/// an OR must be denied, but any part of an AND is enough.
+pub fn is_denied(license: &str, deny: &[String]) -> bool {
+    let denied = |id: &str| {
+        deny.iter().any(|denied| {
+            let id = id.trim().trim_matches(|c| c == '(' || c == ')');
+
+            id.eq_ignore_ascii_case(denied)
+                || id
+                    .to_ascii_lowercase()
+                    .strip_prefix(&denied.to_ascii_lowercase())
+                    .is_some_and(|rest| rest.starts_with('-') || rest == "+")
+        })
+    };
+
+    license
+        .split(" OR ")
+        .all(|alternative| alternative.split(" AND ").any(denied))
+}
+
+/// The license of a dependency.
+///
+/// # Fields
+///
+/// * 'name' - The name of the dependency.
+/// * 'version' - Its version.
+/// * 'license' - Its SPDX identifier (or expression), None when it has none.
+/// * 'detected' - Whether the license was guessed from a license file
+///   because its manifest has none.
+/// * 'denied' - Whether the license is on the deny list.
+///
+#[derive(Debug, Clone)]
+pub struct DependencyLicense {
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    pub detected: bool,
+    pub denied: bool,
+}
+
+/// Collects the licenses of every dependency of the project, from their
+/// manifests or else their license files, and checks them against the deny
+/// list in [licenses].
+pub fn report(project: &Project) -> Result<Vec<DependencyLicense>, CedarError> {
+    let deny = &project.manifest.licenses.deny;
+    let mut licenses = Vec::new();
+
+    for package in deps::resolve(project, false)? {
+        let (license, detected) = license_of(&package.project);
+
+        licenses.push(DependencyLicense {
+            denied: license
+                .as_deref()
+                .is_some_and(|license| is_denied(license, deny)),
+            name: package.name,
+            version: package.version,
+            license,
+            detected,
+        });
+    }
+
+    Ok(licenses)
+}
+
+/// The license of a project, from its manifest or else detected from its
+/// license file, along with whether it was detected.
+pub fn license_of(project: &Project) -> (Option<String>, bool) {
+    match &project.manifest.meta.license {
+        Some(license) => (Some(license.clone()), false),
+        None => (license_file(&project.root).map(String::from), true),
+    }
+}
+
+/// Detects the license of the first license file in a directory.
+fn license_file(root: &Path) -> Option<&'static str> {
+    LICENSE_FILES
+        .iter()
+        .filter_map(|name| fs::read_to_string(root.join(name)).ok())
+        .find_map(|text| detect(&text))
+}
+
+/// The attribution notices of the dependencies: for each one its name,
+/// version, and license followed by the text of its license file and, if
+/// it has one, its NOTICE file.
+pub fn notices(packages: &[Package]) -> String {
+    let mut notices = String::from(
+        "This software includes the following third party components, which\nare distributed under the terms of their own licenses.\n",
+    );
+
+    for package in packages {
+        let root = &package.project.root;
+        let (license, _) = license_of(&package.project);
+
+        notices.push_str(&format!(
+            "\n{}\n\n{} {} ({})\n\n",
+            "=".repeat(72),
+            package.name,
+            package.version,
+            license.as_deref().unwrap_or("unknown license")
+        ));
+
+        let texts: Vec<String> = LICENSE_FILES
+            .iter()
+            .chain(&["NOTICE", "NOTICE.txt", "NOTICE.md"])
+            .filter_map(|name| fs::read_to_string(root.join(name)).ok())
+            .collect();
+
+        if texts.is_empty() {
+            notices.push_str("No license file was found.\n");
+        }
+
+        for text in texts {
+            notices.push_str(text.trim_end());
+            notices.push('\n');
+        }
+    }
+
+    notices
+}
+
+/// A C source defining the notices as `const char cedar_notices[]`, a
+/// NUL terminated string, so a program can print them (e.g. for --licenses).
+pub fn notices_source(notices: &str) -> String {
+    let mut source = String::from(
+        "/* Generated by cedar from the licenses of the dependencies, do not edit. */\n\nconst char cedar_notices[] = {",
+    );
+
+    for (i, byte) in notices.bytes().chain([0]).enumerate() {
+        source.push_str(if i % 16 == 0 { "\n    " } else { " " });
+        source.push_str(&format!("0x{:02x},", byte));
+    }
+
+    source.push_str("\n};\n");
+    source
+}
+
+/// Reads the configured git user name, if git is installed and it is set.
+fn git_user_name() -> Option<String> {
+    let output = process::Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .ok()?;
+
+    let name = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// Gets the current year (UTC) from the system clock.
+fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64 / 86_400)
+        .unwrap_or(0);
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+    yoe + era * 400 + i64::from(month <= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, is_denied, notices_source, template, TEMPLATES};
+
+    #[test]
+    fn test_template_lookup() {
+        assert_eq!(template("mit").unwrap().0, "MIT");
+        assert_eq!(template("apache-2.0").unwrap().0, "Apache-2.0");
+        assert!(template("GPL-3.0").is_none());
+    }
+
+    #[test]
+    fn detects_and_denies_licenses() {
+        for (id, text) in TEMPLATES {
+            assert_eq!(detect(text), Some(id));
+        }
+
+        let deny = vec![String::from("GPL-3.0")];
+        assert!(is_denied("GPL-3.0-or-later", &deny));
+        assert!(is_denied("MIT AND GPL-3.0-only", &deny));
+        assert!(!is_denied("MIT OR GPL-3.0", &deny));
+        assert!(!is_denied("LGPL-3.0", &deny));
+    }
+
+    #[test]
+    fn embeds_notices() {
+        assert!(notices_source("hi").contains("\n    0x68, 0x69, 0x00,\n};"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::{ffi::OsStr, path::Path, process};
+
+use crate::error::CedarError;
+
+use super::{manifest::Limits, project::ProjectError, timeout};
+
+/// Parses a size in bytes, "512K", "64M", "2G" or "1T" in powers of 1024, a
+/// bare number is in bytes.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().ok()?;
+
+    let shift = match unit
+        .trim()
+        .trim_end_matches("iB")
+        .trim_end_matches('B')
+        .to_ascii_uppercase()
+        .as_str()
+    {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return None,
+    };
+
+    number.checked_mul(1 << shift)
+}
+
+/// The ulimit commands applying the limits, in the units sh takes them in:
+/// KiB for the address space, 512 byte blocks for core dumps, and whole
+/// seconds of CPU time.
+fn ulimits(limits: &Limits) -> Result<Vec<String>, CedarError> {
+    let size = |text: &String| {
+        parse_size(text).ok_or_else(|| CedarError::from(ProjectError::InvalidSize(text.clone())))
+    };
+
+    let mut ulimits = Vec::new();
+
+    if let Some(bytes) = limits.address_space.as_ref().map(size).transpose()? {
+        ulimits.push(format!("ulimit -v {}", bytes.div_ceil(1024)));
+    }
+    if let Some(bytes) = limits.core.as_ref().map(size).transpose()? {
+        ulimits.push(format!("ulimit -c {}", bytes.div_ceil(512)));
+    }
+    if let Some(files) = limits.open_files {
+        ulimits.push(format!("ulimit -n {}", files));
+    }
+    if let Some(text) = &limits.cpu_time {
+        let time =
+            timeout::parse(text).ok_or_else(|| ProjectError::InvalidDuration(text.clone()))?;
+        ulimits.push(format!("ulimit -t {}", time.as_secs_f64().ceil()));
+    }
+
+    Ok(ulimits)
+}
+
+/// A command running a built program, prefixed by a wrapper and under the
+/// resource limits of [run.limits]. The limits are set by sh, which then
+/// execs the wrapper or program in its place, the arguments are added to the
+/// command as usual.
+///
+/// Other platforms than unix run the program without limits.
+///
+/// # Arguments
+///
+/// * 'program' - The path of the program.
+/// * 'wrapper' - A command the program is run with, as in "strace -f" or
+///   "rr record", split on whitespace. Empty runs the program alone.
+/// * 'limits' - The resource limits.
+///
+pub fn command(
+    program: &Path,
+    wrapper: &str,
+    limits: &Limits,
+) -> Result<process::Command, CedarError> {
+    let ulimits = ulimits(limits)?;
+
+    let mut line: Vec<&OsStr> = wrapper.split_whitespace().map(OsStr::new).collect();
+    line.push(program.as_os_str());
+
+    let mut command = match ulimits.is_empty() || cfg!(not(unix)) {
+        true => process::Command::new(line.remove(0)),
+        false => {
+            let mut command = process::Command::new("sh");
+            command
+                .arg("-c")
+                .arg(format!("{} && exec \"$0\" \"$@\"", ulimits.join(" && ")));
+            command
+        }
+    };
+
+    command.args(line);
+
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_limits() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("64K"), Some(64 << 10));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("512MiB"), Some(512 << 20));
+        assert_eq!(parse_size("1.5G"), None);
+
+        let limits = Limits {
+            address_space: Some(String::from("1G")),
+            core: Some(String::from("0")),
+            open_files: Some(64),
+            cpu_time: Some(String::from("1500ms")),
+        };
+
+        assert_eq!(
+            ulimits(&limits).unwrap(),
+            [
+                "ulimit -v 1048576",
+                "ulimit -c 0",
+                "ulimit -n 64",
+                "ulimit -t 2"
+            ]
+        );
+    }
+}
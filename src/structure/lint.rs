@@ -0,0 +1,481 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::PathBuf,
+    process,
+    str::FromStr,
+};
+
+use crate::{cli::CliError, error::CedarError};
+
+use super::{
+    amalgamate::files_with_extensions,
+    build::Toolchain,
+    cache::parallel,
+    check::{compiles, without_outputs},
+    fingerprint, flags,
+    project::{Project, ProjectError},
+};
+
+/// A tool of cedar lint.
+///
+/// # Members
+///
+/// * 'Iwyu' - include-what-you-use, which finds the includes a file is
+///   missing and the ones it does not need.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintTool {
+    Iwyu,
+}
+
+impl FromStr for LintTool {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "iwyu" | "include-what-you-use" => Ok(Self::Iwyu),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, the lint tool is iwyu.",
+                s
+            ))),
+        }
+    }
+}
+
+/// What include-what-you-use suggests for a file.
+///
+/// # Fields
+///
+/// * 'file' - The file, a source or the header of the same name.
+/// * 'add' - The lines to add, includes and forward declarations.
+/// * 'remove' - The lines to remove, with the line of the file they are on.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub file: PathBuf,
+    pub add: Vec<String>,
+    pub remove: Vec<(String, usize)>,
+}
+
+/// Runs include-what-you-use over every translation unit of the project,
+/// with the flags of the build, and collects what it suggests for each file.
+///
+/// # Returns
+///
+/// * The suggestions, one per file with includes to add or remove.
+///
+pub fn iwyu(project: &Project) -> Result<Vec<Suggestion>, CedarError> {
+    let commands = compiles(project)?;
+
+    // iwyu takes the command line of a compile without the compiler.
+    let outputs = parallel(&commands, |command| {
+        process::Command::new("include-what-you-use")
+            .args(without_outputs(&command.arguments[1..]))
+            .current_dir(&command.directory)
+            .output()
+    });
+
+    let mut suggestions: Vec<Suggestion> = Vec::new();
+
+    for output in outputs {
+        let output = match output {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(ProjectError::MissingTool("include-what-you-use").into())
+            }
+            Err(source) => {
+                return Err(CedarError::Command {
+                    program: String::from("include-what-you-use"),
+                    source,
+                })
+            }
+        };
+
+        // A header is seen by every source including it, the first
+        // suggestion for it is kept.
+        for suggestion in parse(&String::from_utf8_lossy(&output.stderr)) {
+            if suggestion.file.starts_with(&project.root)
+                && !suggestions
+                    .iter()
+                    .any(|other| other.file == suggestion.file)
+            {
+                suggestions.push(suggestion);
+            }
+        }
+    }
+
+    suggestions.sort_by(|a, b| a.file.cmp(&b.file));
+
+    Ok(suggestions)
+}
+
+/// What cedar lint --unused finds no use of.
+///
+/// # Fields
+///
+/// * 'sources' - Sources defining no main and no symbol any other source
+///   references.
+/// * 'headers' - Headers in include/ and src/ that no source includes.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Unused {
+    pub sources: Vec<PathBuf>,
+    pub headers: Vec<PathBuf>,
+}
+
+/// Looks for the dead files of the project after a build of every target,
+/// tests and examples included: the headers missing from the depfiles of
+/// every compile, and the sources whose objects define no symbol, as nm
+/// lists them, that the objects of the other sources reference.
+///
+/// The symbols named by the exports file of a library are used, the rest
+/// of its API only when a test or an example calls it.
+///
+/// # Returns
+///
+/// * The unused sources and headers, from the root.
+///
+pub fn unused(project: &Project) -> Result<Unused, CedarError> {
+    let commands = compiles(project)?;
+    let toolchain = Toolchain::from_compiler(&project.manifest.build.compiler)?;
+    let root = project.root.canonicalize()?;
+
+    let mut included = BTreeSet::new();
+    let mut defined: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+    let mut referenced: BTreeMap<String, BTreeSet<PathBuf>> = BTreeMap::new();
+
+    for command in &commands {
+        let depfile = command
+            .arguments
+            .iter()
+            .position(|arg| arg == "-MF")
+            .and_then(|index| command.arguments.get(index + 1));
+
+        if let Some(depfile) = depfile {
+            included.extend(
+                fingerprint::dependencies(&command.file, &command.directory.join(depfile))
+                    .iter()
+                    .filter_map(|path| command.directory.join(path).canonicalize().ok()),
+            );
+        }
+    }
+
+    // The same source compiles once per target table and flavour, its
+    // symbols are those of every object.
+    let objects: Vec<_> = commands
+        .iter()
+        .filter_map(|command| Some((&command.file, command.output.as_ref()?)))
+        .collect();
+
+    let listings = parallel(&objects, |(_, object)| {
+        let mut nm = process::Command::new(&toolchain.nm);
+        nm.args(["-g", "-P"]).arg(object);
+
+        nm.output().map_err(|err| CedarError::command(&nm, err))
+    });
+
+    for ((source, _), listing) in objects.iter().zip(listings) {
+        let listing = listing?;
+
+        if !listing.status.success() {
+            return Err(ProjectError::ToolFailed("nm").into());
+        }
+
+        let (defines, references) = symbols(&String::from_utf8_lossy(&listing.stdout));
+        let source = source.canonicalize()?;
+
+        for symbol in references {
+            referenced.entry(symbol).or_default().insert(source.clone());
+        }
+        defined.entry(source).or_default().extend(defines);
+    }
+
+    let mut exported = BTreeSet::new();
+
+    if let Some(exports) = &project.manifest.build.exports {
+        let path = project.root.join(exports);
+        let list = fs::read_to_string(&path).map_err(|err| CedarError::io(&path, err))?;
+
+        if !flags::is_version_script(&list) {
+            exported.extend(flags::symbols(&list).map(String::from));
+        }
+    }
+
+    let is_used = |source: &PathBuf, symbol: &String| {
+        symbol == "main"
+            || exported.contains(symbol)
+            || referenced
+                .get(symbol)
+                .is_some_and(|users| users.iter().any(|user| user != source))
+    };
+
+    let from_root = |path: &PathBuf| path.strip_prefix(&root).unwrap_or(path).to_path_buf();
+    let mut unused = Unused::default();
+
+    for (source, symbols) in &defined {
+        if !symbols.iter().any(|symbol| is_used(source, symbol)) {
+            unused.sources.push(from_root(source));
+        }
+    }
+
+    for directory in [&project.include_dir, &project.src_dir] {
+        for header in files_with_extensions(
+            directory,
+            &["h", "hh", "hpp", "hxx"],
+            project.manifest.build.symlinks,
+        )? {
+            let header = header.canonicalize()?;
+
+            if !included.contains(&header) {
+                unused.headers.push(from_root(&header));
+            }
+        }
+    }
+
+    unused.headers.sort();
+
+    Ok(unused)
+}
+
+/// The symbols an object defines and those it references, from the POSIX
+/// output of nm -g, name type [value size] per line.
+fn symbols(listing: &str) -> (Vec<String>, Vec<String>) {
+    let mut defines = Vec::new();
+    let mut references = Vec::new();
+
+    for line in listing.lines() {
+        let mut fields = line.split_whitespace();
+
+        let (Some(name), Some(kind)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        // Weak references are lowercase w and v without a value.
+        match kind {
+            "U" => references.push(name.to_owned()),
+            "w" | "v" if fields.next().is_none() => references.push(name.to_owned()),
+            _ => defines.push(name.to_owned()),
+        }
+    }
+
+    (defines, references)
+}
+
+/// Reads the suggestions of include-what-you-use from its output, files
+/// whose includes are right are left out.
+fn parse(output: &str) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = Vec::new();
+    let mut section: Option<(PathBuf, bool)> = None;
+
+    for line in output.lines() {
+        if let Some(file) = line.strip_suffix(" should add these lines:") {
+            section = Some((PathBuf::from(file), true));
+            continue;
+        }
+        if let Some(file) = line.strip_suffix(" should remove these lines:") {
+            section = Some((PathBuf::from(file), false));
+            continue;
+        }
+        if line.trim().is_empty() || line.starts_with("The full include-list for ") {
+            section = None;
+            continue;
+        }
+
+        let Some((file, add)) = &section else {
+            continue;
+        };
+
+        let index = match suggestions.iter().position(|other| &other.file == file) {
+            Some(index) => index,
+            None => {
+                suggestions.push(Suggestion {
+                    file: file.clone(),
+                    add: Vec::new(),
+                    remove: Vec::new(),
+                });
+                suggestions.len() - 1
+            }
+        };
+
+        // Lines end in a comment of why, "// for printf" or "// lines 3-3".
+        let (text, comment) = line.split_once("  // ").unwrap_or((line, ""));
+
+        match add {
+            true => suggestions[index].add.push(text.trim().to_owned()),
+            false => {
+                let number = comment
+                    .strip_prefix("lines ")
+                    .and_then(|lines| lines.split('-').next())
+                    .and_then(|number| number.parse().ok());
+
+                if let (Some(text), Some(number)) = (text.strip_prefix("- "), number) {
+                    suggestions[index]
+                        .remove
+                        .push((text.trim().to_owned(), number));
+                }
+            }
+        }
+    }
+
+    suggestions.retain(|suggestion| !suggestion.add.is_empty() || !suggestion.remove.is_empty());
+    suggestions
+}
+
+/// Applies a suggestion of include-what-you-use to its file.
+///
+/// A line is only removed when it still reads as include-what-you-use saw
+/// it, so a file edited since is never broken. Added lines go after the
+/// last include, or after the include guard of a header without includes,
+/// and are skipped when the file has them already.
+///
+/// # Returns
+///
+/// * Whether the file changed.
+///
+pub fn fix(suggestion: &Suggestion) -> Result<bool, CedarError> {
+    let path = &suggestion.file;
+    let contents = fs::read_to_string(path).map_err(|err| CedarError::io(path, err))?;
+    let fixed = apply(&contents, suggestion);
+
+    if fixed == contents {
+        return Ok(false);
+    }
+
+    fs::write(path, fixed).map_err(|err| CedarError::io(path, err))?;
+
+    Ok(true)
+}
+
+/// The contents of a file with a suggestion applied, see fix.
+fn apply(contents: &str, suggestion: &Suggestion) -> String {
+    let lines: Vec<&str> = contents.lines().collect();
+
+    let removed: Vec<usize> = suggestion
+        .remove
+        .iter()
+        .filter(|(text, number)| {
+            lines
+                .get(number.wrapping_sub(1))
+                .is_some_and(|line| line.split("//").next().unwrap_or_default().trim() == text)
+        })
+        .map(|(_, number)| number - 1)
+        .collect();
+
+    let added: Vec<&String> = suggestion
+        .add
+        .iter()
+        .filter(|text| !lines.iter().any(|line| line.trim() == text.as_str()))
+        .collect();
+
+    // After the last include, else after the guard of a header, else first.
+    let guard = lines
+        .iter()
+        .position(|line| line.trim() == "#pragma once")
+        .or_else(|| {
+            lines
+                .iter()
+                .position(|line| line.trim_start().starts_with("#ifndef"))
+                .filter(|index| {
+                    lines
+                        .get(index + 1)
+                        .is_some_and(|line| line.trim_start().starts_with("#define"))
+                })
+                .map(|index| index + 1)
+        });
+    let at = lines
+        .iter()
+        .rposition(|line| line.trim_start().starts_with("#include"))
+        .or(guard)
+        .map_or(0, |index| index + 1);
+
+    let mut fixed = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if index == at {
+            added
+                .iter()
+                .for_each(|text| fixed.push_str(&format!("{}\n", text)));
+        }
+        if !removed.contains(&index) {
+            fixed.push_str(line);
+            fixed.push('\n');
+        }
+    }
+
+    if at >= lines.len() {
+        added
+            .iter()
+            .for_each(|text| fixed.push_str(&format!("{}\n", text)));
+    }
+
+    // The file keeps its last line as it was, with or without a newline.
+    if !contents.ends_with('\n') && fixed.ends_with('\n') && !contents.is_empty() {
+        fixed.pop();
+    }
+
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_nm_symbols() {
+        let listing = "main T 0000000000000000 0000000000000020\nparse U\nlexer_next U\n__gmon_start__ w\ntable D 0000000000000000 0000000000000010\n";
+
+        assert_eq!(
+            symbols(listing),
+            (
+                vec![String::from("main"), String::from("table")],
+                vec![
+                    String::from("parse"),
+                    String::from("lexer_next"),
+                    String::from("__gmon_start__")
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn applies_iwyu_suggestions() {
+        let output = "\
+/p/src/a.c should add these lines:
+#include <string.h>  // for strlen
+
+/p/src/a.c should remove these lines:
+- #include <stdlib.h>  // lines 2-2
+
+The full include-list for /p/src/a.c:
+#include <stdio.h>  // for printf
+#include <string.h>  // for strlen
+---
+
+(/p/src/a.h has correct #includes/fwd-decls)
+";
+
+        let suggestions = parse(output);
+        assert_eq!(
+            suggestions,
+            [Suggestion {
+                file: PathBuf::from("/p/src/a.c"),
+                add: vec![String::from("#include <string.h>")],
+                remove: vec![(String::from("#include <stdlib.h>"), 2)],
+            }]
+        );
+
+        let source = "#include <stdio.h>\n#include <stdlib.h>\n\nint main(void) { return 0; }\n";
+        assert_eq!(
+            apply(source, &suggestions[0]),
+            "#include <stdio.h>\n#include <string.h>\n\nint main(void) { return 0; }\n"
+        );
+
+        // An edited file keeps the line that moved.
+        let edited = "#include <stdio.h>\n\n#include <stdlib.h>\n";
+        assert_eq!(
+            apply(edited, &suggestions[0]),
+            "#include <stdio.h>\n\n#include <stdlib.h>\n#include <string.h>\n"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CedarError;
+
+use super::project::{Project, ProjectError};
+
+/// The resolved dependencies of a project, cedar.lock next to cedar.toml.
+/// Checked in, it pins git dependencies to the exact commits they resolved
+/// to so every checkout builds the same sources.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "package", skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A resolved dependency.
+///
+/// # Fields
+///
+/// * 'name' - The name of the dependency in [dependencies].
+/// * 'version' - The version from its manifest.
+/// * 'source' - Where it came from, "path+<path>" or
+///   "git+<url>[?tag=|?branch=|?rev=<ref>]#<commit>".
+/// * 'checksum' - The SHA-256 of the sources of a git dependency (see
+///   sha256::hash_dir), which every download of the commit must match.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+impl LockedPackage {
+    /// The commit a git dependency is pinned to.
+    pub fn commit(&self) -> Option<&str> {
+        self.source
+            .strip_prefix("git+")
+            .and_then(|source| source.rsplit_once('#'))
+            .map(|(_, commit)| commit)
+    }
+}
+
+impl Lockfile {
+    pub fn path(project: &Project) -> PathBuf {
+        project.root.join("cedar.lock")
+    }
+    /// Loads the lockfile of a project, an empty one if there is none yet.
+    pub fn load(project: &Project) -> Result<Self, CedarError> {
+        let path = Self::path(project);
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(CedarError::io(&path, err)),
+        };
+
+        toml::from_str(&contents).map_err(|err| {
+            ProjectError::InvalidDependency(format!("Invalid cedar.lock: {}", err)).into()
+        })
+    }
+    /// Writes the lockfile, unless it is unchanged.
+    pub fn save(&self, project: &Project) -> Result<(), CedarError> {
+        if Self::load(project).is_ok_and(|current| current == *self) {
+            return Ok(());
+        }
+
+        let path = Self::path(project);
+        let contents = toml::to_string(self).map_err(|_| ProjectError::InvalidManifest)?;
+
+        fs::write(
+            &path,
+            format!("# Generated by cedar, do not edit.\n\n{}", contents),
+        )
+        .map_err(|err| CedarError::io(&path, err))
+    }
+    /// The locked package with the given name.
+    pub fn find(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+}
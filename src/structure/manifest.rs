@@ -1,12 +1,50 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use toml::{self};
 
-use super::project::ProjectError;
+use super::{config::Alias, project::ProjectError};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Manifest {
     pub meta: Meta,
     pub build: Build,
+    #[serde(default, skip_serializing_if = "Test::is_empty")]
+    pub test: Test,
+    #[serde(default, skip_serializing_if = "Run::is_empty")]
+    pub run: Run,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, TargetBuild>,
+    #[serde(default, skip_serializing_if = "Dependencies::is_empty")]
+    pub dependencies: Dependencies,
+    #[serde(
+        rename = "system-deps",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub system_deps: BTreeMap<String, SystemDependency>,
+    #[serde(default, skip_serializing_if = "Licenses::is_empty")]
+    pub licenses: Licenses,
+    #[serde(default, skip_serializing_if = "Package::is_empty")]
+    pub package: Package,
+    #[serde(default, skip_serializing_if = "Subprojects::is_empty")]
+    pub subprojects: Subprojects,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, Profile>,
+    #[serde(default, skip_serializing_if = "Features::is_empty")]
+    pub features: Features,
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+    #[serde(default, skip_serializing_if = "Ci::is_empty")]
+    pub ci: Ci,
+    #[serde(default, skip_serializing_if = "Matrix::is_empty")]
+    pub matrix: Matrix,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, Task>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub alias: BTreeMap<String, Alias>,
+    #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+    pub metadata: toml::Table,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -14,17 +52,1119 @@ pub struct Meta {
     pub name: String,
     pub version: String,
     pub description: Option<String>,
+    pub license: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "ProjectKind::is_bin")]
+    pub kind: ProjectKind,
+}
+
+/// The kind of artifact a project produces.
+///
+/// # Members
+///
+/// * 'Bin' - An executable, build/<name>.
+/// * 'StaticLib' - A static library, build/lib<name>.a.
+/// * 'SharedLib' - A shared library, build/lib<name>.so.
+/// * 'Lib' - Both a static and a shared library from the same objects.
+/// * 'HeaderOnly' - A library of headers in include/ alone, nothing of it
+///   links and projects depending on it only get its include path.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectKind {
+    #[default]
+    Bin,
+    StaticLib,
+    SharedLib,
+    Lib,
+    HeaderOnly,
+}
+
+impl ProjectKind {
+    pub fn is_bin(&self) -> bool {
+        *self == Self::Bin
+    }
+    /// The name of the kind, as the type of [meta] spells it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bin => "bin",
+            Self::StaticLib => "staticlib",
+            Self::SharedLib => "sharedlib",
+            Self::Lib => "lib",
+            Self::HeaderOnly => "headeronly",
+        }
+    }
+    /// Whether a static library is produced.
+    pub fn is_static(&self) -> bool {
+        matches!(self, Self::StaticLib | Self::Lib)
+    }
+    /// Whether a shared library is produced, which requires -fPIC objects.
+    pub fn is_shared(&self) -> bool {
+        matches!(self, Self::SharedLib | Self::Lib)
+    }
+    /// Whether the project is headers alone, without sources in src/.
+    pub fn is_header_only(&self) -> bool {
+        *self == Self::HeaderOnly
+    }
 }
 
+/// The [build] table, how the project compiles and links.
+///
+/// # Fields
+///
+/// * 'compiler' - The compiler, gcc or a gcc cross compiler.
+/// * 'cflags', 'cxxflags', 'asflags' - The flags of C, C++ and assembly
+///   sources, see flags::order for where they go.
+/// * 'ldflags' - The flags of links.
+/// * 'abi_version' - The version a shared library is named with.
+/// * 'backend' - What runs the compiles and links.
+/// * 'container' - The image the build runs in, see cedar build
+///   --in-container.
+/// * 'symlinks' - What scanning the sources does with symlinks.
+/// * 'artifact_name' - The template the artifacts are named by.
+/// * 'files' - Extra flags of single sources, by their path from the root.
+/// * 'link_group' - Links the static libraries among the ldflags as a group,
+///   for libraries with circular references to each other.
+/// * 'visibility' - The default visibility of the symbols of a shared
+///   library.
+/// * 'exports' - A file, relative to the root, naming what a shared library
+///   exports: a GNU version script, or the symbols one per line.
+/// * 'include_cycles' - What a build does with headers including each
+///   other in a cycle.
+/// * 'check_headers' - Compiles every header in include/ alone, through a
+///   generated translation unit including just it, so a header
+///   missing an include of its own fails the build. Nothing links the
+///   objects.
+///
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Build {
     pub compiler: String,
     pub cflags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cxxflags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asflags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ldflags: Vec<String>,
+    pub abi_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Backend::is_cedar")]
+    pub backend: Backend,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    #[serde(default, skip_serializing_if = "Symlinks::is_follow")]
+    pub symlinks: Symlinks,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_name: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub link_group: bool,
+    #[serde(default, skip_serializing_if = "Visibility::is_default")]
+    pub visibility: Visibility,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exports: Option<String>,
+    #[serde(default, skip_serializing_if = "IncludeCycles::is_ignore")]
+    pub include_cycles: IncludeCycles,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub check_headers: bool,
+}
+
+/// A [targets.<name>] table, the compiler and flags of a single target in
+/// place of those of [build]. The library sources the target links are
+/// compiled again with them, so a firmware binary built by a cross compiler
+/// can live next to host tools built by gcc.
+///
+/// ```toml
+/// [targets.firmware]
+/// compiler = "arm-none-eabi-gcc"
+/// cflags = ["-mcpu=cortex-m4", "-Os"]
+/// ldflags = ["-specs=nosys.specs"]
+/// ```
+///
+/// # Fields
+///
+/// * 'compiler' - The compiler, gcc or a gcc cross compiler.
+/// * 'cflags', 'cxxflags', 'asflags', 'ldflags' - The flags, replacing those
+///   of [build].
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct TargetBuild {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cflags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cxxflags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asflags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ldflags: Option<Vec<String>>,
+}
+
+/// What runs the compiles and links of a build.
+///
+/// # Members
+///
+/// * 'Cedar' - cedar runs them itself.
+/// * 'Ninja' - cedar writes them to build/build.ninja and runs ninja, which
+///   schedules them and skips the up to date ones.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Cedar,
+    Ninja,
+}
+
+impl Backend {
+    pub fn is_cedar(&self) -> bool {
+        *self == Self::Cedar
+    }
+}
+
+/// What scanning src/ and include/ does with the symlinks it finds, set with
+/// symlinks in [build]. A symlink to a directory it is already in is never
+/// followed, so a looped symlink cannot hang the scan.
+///
+/// # Members
+///
+/// * 'Follow' - Symlinks are scanned like the files and directories they
+///   point to.
+/// * 'Skip' - Symlinks are left out.
+/// * 'Error' - Any symlink fails the build.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Symlinks {
+    #[default]
+    Follow,
+    Skip,
+    Error,
+}
+
+impl Symlinks {
+    pub fn is_follow(&self) -> bool {
+        *self == Self::Follow
+    }
+}
+
+/// The visibility of the symbols of a shared library, set with visibility
+/// in [build].
+///
+/// # Members
+///
+/// * 'Default' - Every symbol with external linkage is exported.
+/// * 'Hidden' - The library sources compile with -fvisibility=hidden, only
+///   symbols marked __attribute__((visibility("default"))) are
+///   exported.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    #[default]
+    Default,
+    Hidden,
+}
+
+impl Visibility {
+    pub fn is_default(&self) -> bool {
+        *self == Self::Default
+    }
+}
+
+/// What a build does with headers of the project including each other in a
+/// cycle, set with include_cycles in [build]. cedar check --include-cycles
+/// reports them either way.
+///
+/// # Members
+///
+/// * 'Ignore' - Cycles are not looked for.
+/// * 'Warn' - Each cycle is printed as a warning.
+/// * 'Error' - Any cycle fails the build.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IncludeCycles {
+    #[default]
+    Ignore,
+    Warn,
+    Error,
+}
+
+impl IncludeCycles {
+    pub fn is_ignore(&self) -> bool {
+        *self == Self::Ignore
+    }
+}
+
+/// The [test] table, configuring cedar test.
+///
+/// # Fields
+///
+/// * 'fixtures' - Files and directories, relative to the project root,
+///   copied into the temporary directory of every test.
+/// * 'min_coverage' - The line coverage in percent below which cedar cov
+///   fails.
+/// * 'timeout' - How long a test may run before it is killed and failed,
+///   "30s", "500ms", "2m", no limit by default.
+/// * 'timeouts' - The timeout of single tests by name, overriding both the
+///   timeout and --timeout.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Test {
+    #[serde(default)]
+    pub fixtures: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_coverage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub timeouts: BTreeMap<String, String>,
+}
+
+impl Test {
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty()
+            && self.min_coverage.is_none()
+            && self.timeout.is_none()
+            && self.timeouts.is_empty()
+    }
+}
+
+/// The [run] table, configuring how cedar runs the programs it builds.
+///
+/// # Fields
+///
+/// * 'wrapper' - A command cedar run and every test run the program with,
+///   such as "rr record" or "taskset -c 0", --wrap takes precedence.
+/// * 'limits' - The resource limits of cedar run and of every test.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Run {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapper: Option<String>,
+    #[serde(default, skip_serializing_if = "Limits::is_empty")]
+    pub limits: Limits,
+}
+
+impl Run {
+    pub fn is_empty(&self) -> bool {
+        self.wrapper.is_none() && self.limits.is_empty()
+    }
+    /// The wrapper to run programs with, the one given on the command line
+    /// over the one of the manifest.
+    pub fn wrapper<'a>(&'a self, wrap: Option<&'a str>) -> &'a str {
+        wrap.or(self.wrapper.as_deref()).unwrap_or_default()
+    }
+}
+
+/// The [run.limits] table, resource limits set on the programs cedar runs,
+/// so a runaway test fails fast instead of taking the machine down.
+///
+/// ```toml
+/// [run.limits]
+/// address_space = "2G"
+/// core = "0"
+/// open_files = 256
+/// cpu_time = "30s"
+/// ```
+///
+/// # Fields
+///
+/// * 'address_space' - The most virtual memory a program may map, "512M",
+///   "2G". Sanitizers reserve far more than they use, so this is best
+///   left unset for asan builds.
+/// * 'core' - The largest core dump a program may write, "0" for none.
+/// * 'open_files' - How many files a program may have open at once.
+/// * 'cpu_time' - How much CPU time a program may use before it is killed,
+///   "30s", "2m".
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct Limits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_space: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub core: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_files: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_time: Option<String>,
+}
+
+impl Limits {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The [licenses] table, configuring cedar license.
+///
+/// # Fields
+///
+/// * 'deny' - SPDX identifiers no dependency may be licensed under, e.g.
+///   "GPL-3.0" (which also covers GPL-3.0-only and GPL-3.0-or-later).
+/// * 'embed_notices' - Compiles the license notices of the dependencies
+///   into every target as `extern const char cedar_notices[];`.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Licenses {
+    #[serde(default)]
+    pub deny: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub embed_notices: bool,
+}
+
+impl Licenses {
+    pub fn is_empty(&self) -> bool {
+        self.deny.is_empty() && !self.embed_notices
+    }
+}
+
+/// The [package.docker] table, configuring cedar export docker.
+///
+/// # Fields
+///
+/// * 'expose' - The ports the program listens on.
+/// * 'image' - The Debian based image the project is built in,
+///   debian:bookworm by default.
+/// * 'runtime' - The image the executables are copied into,
+///   debian:bookworm-slim by default.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Docker {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expose: Vec<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<String>,
+}
+
+impl Docker {
+    pub fn is_empty(&self) -> bool {
+        self.expose.is_empty() && self.image.is_none() && self.runtime.is_none()
+    }
+}
+
+/// The [package] table, configuring cedar package.
+///
+/// # Fields
+///
+/// * 'public_key' - The public key packages are signed with, relative to
+///   the project root, which cedar verify checks signatures against.
+/// * 'artifact_name' - The name of the archive (without .tar.gz) and of
+///   the directory in it, {name}-{version} by default. Takes the same
+///   placeholders as artifact_name in [build].
+/// * 'docker' - The [package.docker] table.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Package {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Docker::is_empty")]
+    pub docker: Docker,
+}
+
+impl Package {
+    pub fn is_empty(&self) -> bool {
+        self.public_key.is_none() && self.artifact_name.is_none() && self.docker.is_empty()
+    }
+}
+
+/// The [subprojects] table, the cedar projects in subdirectories of the
+/// project that cedar build --recursive builds before it.
+///
+/// ```toml
+/// [subprojects]
+/// members = ["tools/gen", "libs/core"]
+/// ```
+///
+/// # Fields
+///
+/// * 'members' - The directories of subprojects, relative to the project
+///   root, in the order they are built. Subprojects found without
+///   being listed are built after them, sorted by path.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Subprojects {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<String>,
+}
+
+impl Subprojects {
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// The [ci] table, configuring cedar export ci.
+///
+/// ```toml
+/// [ci]
+/// compilers = ["gcc", "clang"]
+/// profiles = ["dev", "release", "asan"]
+/// ```
+///
+/// # Fields
+///
+/// * 'compilers' - The compilers every profile is built and tested with,
+///   the compiler of [build] by default.
+/// * 'profiles' - The profiles built and tested, dev and release by
+///   default.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Ci {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compilers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+}
+
+impl Ci {
+    pub fn is_empty(&self) -> bool {
+        self.compilers.is_empty() && self.profiles.is_empty()
+    }
+}
+
+/// The [matrix] table, the combinations cedar build --matrix builds, every
+/// profile with every compiler and every selection of targets.
+///
+/// ```toml
+/// [matrix]
+/// profiles = ["dev", "release"]
+/// compilers = ["gcc", "arm-none-eabi-gcc"]
+/// targets = ["lib", "all"]
+/// ```
+///
+/// # Fields
+///
+/// * 'profiles' - The profiles, the one picked with --profile by default.
+/// * 'compilers' - The compilers, the compiler of [build] by default.
+/// * 'targets' - The targets built together, default for those cedar build
+///   builds, lib, examples, tests, all, bin:<name>, or example:<name>,
+///   default by default.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Matrix {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compilers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+}
+
+impl Matrix {
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty() && self.compilers.is_empty() && self.targets.is_empty()
+    }
+}
+
+/// The [hooks] table, commands run by git and at extension points of the
+/// build.
+///
+/// ```toml
+/// [hooks.git]
+/// pre-commit = ["cedar build", "cedar test"]
+/// pre-push = ["cedar test --release"]
+///
+/// [hooks.build]
+/// before-compile = "python scripts/check_unit.py"
+/// diagnostics = "grep -v 'note: '"
+/// ```
+///
+/// # Fields
+///
+/// * 'git' - The command lines of each git hook, by the name of the hook,
+///   run in order in the project root until one fails, installed by
+///   cedar hooks install.
+/// * 'build' - The commands run around each compile.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Hooks {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub git: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "BuildHooks::is_empty")]
+    pub build: BuildHooks,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.git.is_empty() && self.build.is_empty()
+    }
+}
+
+/// The [hooks.build] table, shell commands run in the project root at the
+/// extension points of every compile. They get the project in CEDAR_ROOT,
+/// CEDAR_MANIFEST, CEDAR_OUT_DIR, and CEDAR_PROFILE, the [metadata] table
+/// as JSON in CEDAR_METADATA, and the translation unit in CEDAR_SOURCE and
+/// CEDAR_OBJECT.
+///
+/// The hooks are programs rather than scripts cedar interprets, a Lua
+/// script runs with lua, a WASM module with a runtime like wasmtime. The
+/// keys of [metadata] configure them.
+///
+/// Compiles with hooks run on this machine, not on [dist] workers, and the
+/// ninja backend refuses them.
+///
+/// # Fields
+///
+/// * 'before_compile' - Run before a source is compiled, failing fails the
+///   compile.
+/// * 'after_compile' - Run after a source is compiled, with CEDAR_STATUS set
+///   to success or failure, failing fails the build.
+/// * 'diagnostics' - Filters the output of the compiler, read on stdin as
+///   text, JSON diagnostics rendered, what it prints replaces it.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildHooks {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_compile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_compile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<String>,
+}
+
+impl BuildHooks {
+    pub fn is_empty(&self) -> bool {
+        self.before_compile.is_none() && self.after_compile.is_none() && self.diagnostics.is_none()
+    }
+}
+
+/// A build profile, [profile.<name>]: flags added to those of [build] (and
+/// of the dependencies) when building with --profile <name>.
+///
+/// ```toml
+/// [profile.fuzz]
+/// cflags = ["-O1", "-g", "-fsanitize=fuzzer-no-link,address"]
+/// ldflags = ["-fsanitize=address"]
+/// ```
+///
+/// dev, the default, adds nothing. release and asan are built in, a table
+/// of the same name replaces them.
+///
+/// # Fields
+///
+/// * 'cflags' - Added to the flags of C sources.
+/// * 'cxxflags' - Added to the flags of C++ sources.
+/// * 'asflags' - Added to the flags of assembly sources.
+/// * 'ldflags' - Added to the flags of links.
+/// * 'gc_sections' - Puts every function and variable in a section of its
+///   own and has the linker drop those nothing references, -dead_strip
+///   with Apple's linker.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cflags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cxxflags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asflags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ldflags: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub gc_sections: bool,
+}
+
+impl Profile {
+    /// The profile used without --profile.
+    pub const DEFAULT: &'static str = "dev";
+
+    /// The built-in profiles: dev, release, and asan.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let flags = |flags: &[&str]| flags.iter().map(|flag| flag.to_string()).collect();
+
+        match name {
+            "dev" => Some(Self::default()),
+            "release" => Some(Self {
+                cflags: flags(&["-O2", "-DNDEBUG"]),
+                cxxflags: flags(&["-O2", "-DNDEBUG"]),
+                ..Default::default()
+            }),
+            "asan" => {
+                let sanitize = flags(&["-fsanitize=address", "-fno-omit-frame-pointer", "-g"]);
+
+                Some(Self {
+                    cflags: sanitize.clone(),
+                    cxxflags: sanitize,
+                    asflags: Vec::new(),
+                    ldflags: flags(&["-fsanitize=address"]),
+                    gc_sections: false,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The [features] table, optional parts of the project picked with
+/// --features, each defining macros and adding sources when on.
+///
+/// ```toml
+/// [features]
+/// default = ["metrics"]
+/// tls = { defines = ["USE_TLS"], sources = ["src/tls.c"], dependencies = ["openssl"] }
+/// metrics = { defines = ["METRICS_LEVEL=2"] }
+/// ```
+///
+/// # Fields
+///
+/// * 'default' - The features on without --features, unless
+///   --no-default-features.
+/// * 'features' - The features by name. An optional dependency is also a
+///   feature of its own name.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Features {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default: Vec<String>,
+    #[serde(flatten)]
+    pub features: BTreeMap<String, Feature>,
+}
+
+impl Features {
+    pub fn is_empty(&self) -> bool {
+        self.default.is_empty() && self.features.is_empty()
+    }
+}
+
+/// A feature of [features].
+///
+/// # Fields
+///
+/// * 'defines' - The macros every source of the project is compiled with
+///   when the feature is on, NAME or NAME=VALUE.
+/// * 'sources' - Sources, relative to the root, only compiled into the
+///   library when the feature is on.
+/// * 'dependencies' - The optional dependencies of [dependencies] and
+///   [system-deps] the feature pulls in.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct Feature {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub defines: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+/// The [dependencies] table.
+///
+/// # Fields
+///
+/// * 'conan' - Conan packages by name and version, [dependencies.conan],
+///   installed with conan and linked prebuilt.
+/// * 'vcpkg' - vcpkg ports, installed for the triplet of the host.
+/// * 'packages' - The cedar projects and vendored libraries by name, which
+///   cannot be named conan or vcpkg.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Dependencies {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conan: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vcpkg: Vec<String>,
+    #[serde(flatten)]
+    pub packages: BTreeMap<String, Dependency>,
+}
+
+impl Dependencies {
+    pub fn is_empty(&self) -> bool {
+        self.conan.is_empty() && self.vcpkg.is_empty() && self.packages.is_empty()
+    }
+}
+
+/// The names of [dependencies] taken by the Conan packages and vcpkg ports.
+pub const RESERVED_DEPENDENCIES: &[&str] = &["conan", "vcpkg"];
+
+/// The error for a dependency named like one of RESERVED_DEPENDENCIES.
+pub fn reserved_dependency(name: &str) -> ProjectError {
+    ProjectError::InvalidDependency(format!(
+        "A dependency cannot be named {}, [dependencies.{}] holds the {} packages. Give it another name.",
+        name,
+        name,
+        match name {
+            "conan" => "Conan",
+            _ => "vcpkg",
+        }
+    ))
+}
+
+/// Finds a dependency named conan or vcpkg in a manifest. The packages of
+/// [dependencies] are flattened, so serde would hand such a dependency to
+/// the Conan or vcpkg table: a table of versions and a list of ports.
+fn find_reserved_dependency(manifest: &str) -> Option<&'static str> {
+    let table: toml::Table = toml::from_str(manifest).ok()?;
+    let dependencies = table.get("dependencies")?.as_table()?;
+
+    let conan = dependencies.get("conan").is_some_and(|conan| {
+        conan.as_table().is_none_or(|packages| {
+            packages.contains_key("path")
+                || packages.contains_key("git")
+                || packages.values().any(|version| !version.is_str())
+        })
+    });
+    let vcpkg = dependencies.get("vcpkg").is_some_and(|vcpkg| {
+        vcpkg
+            .as_array()
+            .is_none_or(|ports| ports.iter().any(|port| !port.is_str()))
+    });
+
+    match (conan, vcpkg) {
+        (true, _) => Some("conan"),
+        (_, true) => Some("vcpkg"),
+        _ => None,
+    }
+}
+
+/// A dependency on another cedar project, an entry of [dependencies]:
+///
+/// ```toml
+/// [dependencies]
+/// util = { path = "../util" }
+/// parser = { git = "https://example.com/parser.git", tag = "v1.2.0" }
+/// ```
+///
+/// The sources of a dependency (except its src/main.c) are compiled and
+/// linked into every target, and its include/ directory is on the include
+/// path.
+///
+/// # Fields
+///
+/// * 'path' - A directory holding the dependency, relative to the project.
+/// * 'git' - A git repository holding the dependency.
+/// * 'rev' - The commit to check out (git).
+/// * 'tag' - The tag to check out (git).
+/// * 'branch' - The branch to check out (git), the default branch if no
+///   rev, tag, or branch is given.
+/// * 'version' - A requirement the version of the dependency must meet,
+///   e.g. "^1.2" or ">=1.2, <1.5", see VersionReq.
+/// * 'include' - The include directory of a library without a cedar.toml,
+///   relative to it, include/ by default.
+/// * 'sources' - The directories or files holding the sources of a library
+///   without a cedar.toml, src/ by default. Setting either this or
+///   include builds such a library as a static library.
+/// * 'build' - "cmake" to build the dependency with its CMakeLists.txt
+///   instead, installing it into build/deps/cmake/<name>/ and linking
+///   the libraries it installs.
+/// * 'cmake_args' - Extra arguments to configure a CMake dependency with,
+///   e.g. "-DZLIB_BUILD_EXAMPLES=OFF".
+/// * 'whole_archive' - Links every object of the libraries a CMake
+///   dependency installs, not only those resolving a symbol, for
+///   code that registers itself from constructors. Other dependencies
+///   always link every object.
+/// * 'optional' - Only pulls the dependency in when a feature of the same
+///   name, or one listing it in its dependencies, is on.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct Dependency {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cmake_args: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub whole_archive: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+impl Dependency {
+    /// Whether the dependency is a plain library rather than a cedar project.
+    pub fn is_vendored(&self) -> bool {
+        self.include.is_some() || !self.sources.is_empty()
+    }
+}
+
+/// A library the system must provide, an entry of [system-deps]:
+///
+/// ```toml
+/// [system-deps]
+/// openssl = ">=3"
+/// readline = { header = "readline/readline.h", lib = "readline" }
+/// tls = { pkg = "openssl", optional = true }
+/// ```
+///
+/// # Members
+///
+/// * 'Version' - A version requirement checked against pkg-config.
+/// * 'Detailed' - How to find a library:
+///   * 'version' - A version requirement checked against pkg-config.
+///   * 'pkg_config' - The pkg-config module, the name by default, set
+///     with pkg-config or pkg.
+///   * 'header' - A header to probe for when pkg-config does not know
+///     the library.
+///   * 'lib' - The library to link (-l<lib>) when probing.
+///   * 'whole_archive' - Links every object of the static library,
+///     see Dependency.
+///   * 'optional' - Only links the library when a feature turns it
+///     on, see Dependency.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum SystemDependency {
+    Version(String),
+    Detailed {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        #[serde(
+            rename = "pkg-config",
+            alias = "pkg",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pkg_config: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        header: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lib: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        whole_archive: bool,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        optional: bool,
+    },
+}
+
+impl SystemDependency {
+    /// The version requirement, if there is one.
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            Self::Version(version) => Some(version),
+            Self::Detailed { version, .. } => version.as_deref(),
+        }
+    }
+    /// Whether every object of the library is linked.
+    pub fn whole_archive(&self) -> bool {
+        matches!(
+            self,
+            Self::Detailed {
+                whole_archive: true,
+                ..
+            }
+        )
+    }
+    /// Whether the library is only linked when a feature turns it on.
+    pub fn is_optional(&self) -> bool {
+        matches!(self, Self::Detailed { optional: true, .. })
+    }
+}
+
+/// A chore of the project, an entry of [tasks] run with cedar task:
+///
+/// ```toml
+/// [tasks]
+/// gen = "python scripts/gen.py"
+/// check = ["cedar build", "cedar test"]
+/// bundle = { run = ["./scripts/bundle.sh"], depends = ["gen"], build = true }
+/// ```
+///
+/// # Members
+///
+/// * 'Command' - A shell command line.
+/// * 'Commands' - Shell command lines, run in order until one fails.
+/// * 'Detailed' - A task with prerequisites:
+///   * 'run' - The shell command lines.
+///   * 'depends' - The tasks run before it.
+///   * 'build' - Whether the project is built before it, for tasks
+///     using its artifacts.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Task {
+    Command(String),
+    Commands(Vec<String>),
+    Detailed {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        run: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        depends: Vec<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        build: bool,
+    },
+}
+
+impl Task {
+    /// The shell command lines of the task.
+    pub fn commands(&self) -> &[String] {
+        match self {
+            Self::Command(command) => std::slice::from_ref(command),
+            Self::Commands(commands) | Self::Detailed { run: commands, .. } => commands,
+        }
+    }
+    /// The tasks run before it.
+    pub fn depends(&self) -> &[String] {
+        match self {
+            Self::Detailed { depends, .. } => depends,
+            _ => &[],
+        }
+    }
+    /// Whether the project is built before it.
+    pub fn builds(&self) -> bool {
+        matches!(self, Self::Detailed { build: true, .. })
+    }
+}
+
+/// Sets the version in the [meta] table of the text of a manifest, leaving
+/// the rest of the text, comments and formatting included, as it is.
+///
+/// # Returns
+///
+/// * The new text, None when the table has no version.
+///
+pub fn set_version(text: &str, version: &str) -> Option<String> {
+    let mut table = String::new();
+
+    for (offset, line) in text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+    }) {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') {
+            table = trimmed
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .filter(|ch| !ch.is_whitespace())
+                .collect();
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        if table != "[meta" || key.trim() != "version" {
+            continue;
+        }
+
+        let quote = value
+            .trim_start()
+            .chars()
+            .next()
+            .filter(|ch| matches!(ch, '"' | '\''))?;
+        let open = offset + key.len() + 1 + value.find(quote)? + 1;
+        let close = open + text[open..].find(quote)?;
+
+        return Some(format!("{}{}{}", &text[..open], version, &text[close..]));
+    }
+
+    None
+}
+
+/// Adds an entry to the [dependencies] table of the text of a manifest,
+/// after its last line, leaving the rest of the text, comments and
+/// formatting included, as it is. The table is appended when there is none.
+pub fn add_dependency(
+    text: &str,
+    name: &str,
+    dependency: &Dependency,
+) -> Result<String, ProjectError> {
+    if RESERVED_DEPENDENCIES.contains(&name) {
+        return Err(reserved_dependency(name));
+    }
+
+    // The fields of a dependency are plain values, one per line, which make
+    // an inline table in the order of the struct.
+    let fields: Vec<String> = toml::to_string(dependency)
+        .map_err(|_| ProjectError::InvalidManifest)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect();
+    let value = match fields.is_empty() {
+        true => String::from("{}"),
+        false => format!("{{ {} }}", fields.join(", ")),
+    };
+    let key = match name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+    {
+        true => name.to_owned(),
+        false => toml::Value::String(name.to_owned()).to_string(),
+    };
+    let entry = format!("{} = {}\n", key, value);
+
+    let mut table = String::new();
+    let mut end = None;
+
+    for (offset, line) in text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+    }) {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') {
+            table = trimmed
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .filter(|ch| !ch.is_whitespace())
+                .collect();
+        } else if trimmed.is_empty() {
+            continue;
+        }
+
+        if table == "[dependencies" {
+            end = Some(offset + line.len());
+        }
+    }
+
+    let (before, after) = match end {
+        Some(end) => text.split_at(end),
+        None => (text, ""),
+    };
+    let mut edited = before.to_owned();
+
+    if !edited.is_empty() && !edited.ends_with('\n') {
+        edited.push('\n');
+    }
+
+    if end.is_none() {
+        if !edited.is_empty() {
+            edited.push('\n');
+        }
+        edited.push_str("[dependencies]\n");
+    }
+
+    edited.push_str(&entry);
+    edited.push_str(after);
+
+    Ok(edited)
 }
 
 impl Manifest {
     #[inline]
     pub fn parse(manifest: &str) -> Result<Self, ProjectError> {
+        if let Some(name) = find_reserved_dependency(manifest) {
+            return Err(reserved_dependency(name));
+        }
+
         let parsed = match toml::from_str(manifest) {
             Ok(manifest) => manifest,
             Err(_) => return Err(ProjectError::InvalidManifest),
@@ -47,12 +1187,104 @@ impl Manifest {
                 name: String::new(),
                 version: String::from("0.1.0"),
                 description: None,
+                license: None,
+                kind: ProjectKind::Bin,
             },
             build: Build {
                 compiler: String::from("GCC"),
                 cflags: vec![String::from("-Wall"), String::from("-Wextra")],
+                cxxflags: Vec::new(),
+                asflags: Vec::new(),
+                ldflags: Vec::new(),
+                abi_version: None,
+                backend: Backend::Cedar,
+                container: None,
+                symlinks: Symlinks::Follow,
+                artifact_name: None,
+                files: BTreeMap::new(),
+                link_group: false,
+                visibility: Visibility::Default,
+                exports: None,
+                include_cycles: IncludeCycles::Ignore,
+                check_headers: false,
             },
+            test: Test::default(),
+            run: Run::default(),
+            targets: BTreeMap::new(),
+            dependencies: Dependencies::default(),
+            system_deps: BTreeMap::new(),
+            licenses: Licenses::default(),
+            package: Package::default(),
+            subprojects: Subprojects::default(),
+            profile: BTreeMap::new(),
+            features: Features::default(),
+            hooks: Hooks::default(),
+            ci: Ci::default(),
+            matrix: Matrix::default(),
+            tasks: BTreeMap::new(),
+            alias: BTreeMap::new(),
+            metadata: toml::Table::new(),
+        }
+    }
+    /// The names of the optional dependencies, of [dependencies] and
+    /// [system-deps].
+    pub fn optional_dependencies(&self) -> Vec<&String> {
+        self.dependencies
+            .packages
+            .iter()
+            .filter(|(_, dependency)| dependency.optional)
+            .map(|(name, _)| name)
+            .chain(
+                self.system_deps
+                    .iter()
+                    .filter(|(_, dependency)| dependency.is_optional())
+                    .map(|(name, _)| name),
+            )
+            .collect()
+    }
+    /// The profile with the given name, from [profile.<name>] or built in.
+    pub fn profile(&self, name: &str) -> Option<Profile> {
+        self.profile
+            .get(name)
+            .cloned()
+            .or_else(|| Profile::builtin(name))
+    }
+    /// The [build] settings of a target, with those of its [targets.<name>]
+    /// table in place.
+    pub fn target_build(&self, name: &str) -> Build {
+        let mut build = self.build.clone();
+
+        let Some(target) = self.targets.get(name) else {
+            return build;
+        };
+
+        if let Some(compiler) = &target.compiler {
+            build.compiler = compiler.clone();
+        }
+
+        for (flags, replacement) in [
+            (&mut build.cflags, &target.cflags),
+            (&mut build.cxxflags, &target.cxxflags),
+            (&mut build.asflags, &target.asflags),
+            (&mut build.ldflags, &target.ldflags),
+        ] {
+            if let Some(replacement) = replacement {
+                flags.clone_from(replacement);
+            }
         }
+
+        build
+    }
+    /// The names of every profile, the built-in ones included.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = ["dev", "release", "asan"]
+            .into_iter()
+            .map(String::from)
+            .chain(self.profile.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
     }
 }
 
@@ -66,7 +1298,8 @@ impl Default for Manifest {
 mod tests {
     use std::fs;
 
-    use super::Manifest;
+    use super::{add_dependency, set_version, Dependency, Manifest};
+    use crate::structure::project::ProjectError;
 
     #[test]
     fn test_deserialize() {
@@ -76,4 +1309,154 @@ mod tests {
 
         println!("{:?}", parsed);
     }
+
+    #[test]
+    fn round_trips_dependencies() {
+        let manifest = Manifest::parse(
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"GCC\"\ncflags = []\n\n[dependencies]\nutil = { path = \"../util\" }\n\n[dependencies.conan]\nzlib = \"1.3.1\"\n",
+        )
+        .unwrap();
+
+        assert!(manifest.dependencies.packages.contains_key("util"));
+        assert_eq!(manifest.dependencies.conan["zlib"], "1.3.1");
+
+        let reparsed = Manifest::parse(&manifest.as_string().unwrap()).unwrap();
+        assert_eq!(reparsed.dependencies.packages.len(), 1);
+        assert_eq!(reparsed.dependencies.conan.len(), 1);
+    }
+
+    #[test]
+    fn sets_version_in_place() {
+        let text = "# The app.\n[meta]\nname = \"a\"\nversion   = '0.1.0' # bumped by cedar version\n\n[dependencies]\nversion = \"1\"\n";
+
+        assert_eq!(
+            set_version(text, "0.2.0").unwrap(),
+            "# The app.\n[meta]\nname = \"a\"\nversion   = '0.2.0' # bumped by cedar version\n\n[dependencies]\nversion = \"1\"\n"
+        );
+        assert!(set_version("[meta]\nname = \"a\"\n", "0.2.0").is_none());
+    }
+
+    #[test]
+    fn adds_dependencies_in_place() {
+        let dependency = Dependency {
+            path: Some(String::from("vendor/zlib")),
+            include: Some(String::from(".")),
+            sources: vec![String::from(".")],
+            ..Default::default()
+        };
+        let text = "[meta]\nname = \"a\" # the app\nversion = \"0.1.0\"\n\n[dependencies]\n# Vendored.\nutil = { path = \"../util\" }\n\n[dependencies.conan]\nfmt = \"10.2.1\"\n";
+
+        let edited = add_dependency(text, "zlib", &dependency).unwrap();
+        assert_eq!(
+            edited,
+            "[meta]\nname = \"a\" # the app\nversion = \"0.1.0\"\n\n[dependencies]\n# Vendored.\nutil = { path = \"../util\" }\nzlib = { path = \"vendor/zlib\", include = \".\", sources = [\".\"] }\n\n[dependencies.conan]\nfmt = \"10.2.1\"\n"
+        );
+        assert_eq!(
+            Manifest::parse(&format!(
+                "{}[build]\ncompiler = \"gcc\"\ncflags = []\n",
+                edited
+            ))
+            .unwrap()
+            .dependencies
+            .packages["zlib"],
+            dependency
+        );
+
+        assert_eq!(
+            add_dependency("[meta]\nname = \"a\"", "my.lib", &Dependency::default()).unwrap(),
+            "[meta]\nname = \"a\"\n\n[dependencies]\n\"my.lib\" = {}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_reserved_dependency_names() {
+        let manifest = |dependencies: &str| {
+            Manifest::parse(&format!(
+                "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n\n[dependencies]\n{}\n",
+                dependencies
+            ))
+        };
+
+        assert!(matches!(
+            manifest("conan = { path = \"../conan\" }"),
+            Err(ProjectError::InvalidDependency(_))
+        ));
+        assert!(matches!(
+            manifest("conan = { git = \"https://example.com/conan.git\", tag = \"v1\" }"),
+            Err(ProjectError::InvalidDependency(_))
+        ));
+        assert!(matches!(
+            manifest("vcpkg = { path = \"../vcpkg\" }"),
+            Err(ProjectError::InvalidDependency(_))
+        ));
+        assert!(matches!(
+            add_dependency("", "vcpkg", &Dependency::default()),
+            Err(ProjectError::InvalidDependency(_))
+        ));
+
+        let packages = manifest("vcpkg = [\"fmt\"]\nconan = { zlib = \"1.3.1\" }").unwrap();
+        assert_eq!(packages.dependencies.vcpkg, ["fmt"]);
+        assert_eq!(packages.dependencies.conan["zlib"], "1.3.1");
+    }
+
+    #[test]
+    fn reads_package_docker() {
+        let manifest = Manifest::parse(
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n\n[package.docker]\nexpose = [8080]\nruntime = \"debian:bookworm\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package.docker.expose, [8080]);
+        assert_eq!(
+            manifest.package.docker.runtime.as_deref(),
+            Some("debian:bookworm")
+        );
+        assert!(manifest.as_string().unwrap().contains("[package.docker]"));
+    }
+
+    #[test]
+    fn overrides_builtin_profiles() {
+        let manifest = Manifest::parse(
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"GCC\"\ncflags = []\n\n[profile.release]\ncflags = [\"-O3\"]\n\n[profile.fuzz]\nldflags = [\"-fsanitize=fuzzer\"]\n\n[profile.small]\ncflags = [\"-Os\"]\ngc_sections = true\n",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.profile("release").unwrap().cflags, ["-O3"]);
+        assert_eq!(
+            manifest.profile("asan").unwrap().ldflags,
+            ["-fsanitize=address"]
+        );
+        assert!(manifest.profile("missing").is_none());
+        assert!(manifest.profile("small").unwrap().gc_sections);
+        assert!(!manifest.profile("release").unwrap().gc_sections);
+        assert_eq!(
+            manifest.profile_names(),
+            ["asan", "dev", "fuzz", "release", "small"]
+        );
+    }
+
+    #[test]
+    fn applies_target_builds() {
+        let manifest = Manifest::parse(
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"GCC\"\ncflags = [\"-Wall\"]\nldflags = [\"-lm\"]\n\n[targets.firmware]\ncompiler = \"arm-none-eabi-gcc\"\ncflags = [\"-Os\"]\n",
+        )
+        .unwrap();
+
+        let firmware = manifest.target_build("firmware");
+        assert_eq!(firmware.compiler, "arm-none-eabi-gcc");
+        assert_eq!(firmware.cflags, ["-Os"]);
+        assert_eq!(firmware.ldflags, ["-lm"]);
+        assert_eq!(manifest.target_build("tool").cflags, ["-Wall"]);
+    }
+
+    #[test]
+    fn reads_optional_dependencies() {
+        let manifest = Manifest::parse(
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"GCC\"\ncflags = []\n\n[dependencies]\nutil = { path = \"../util\", optional = true }\n\n[system-deps]\nm = \"*\"\ntls = { pkg = \"openssl\", optional = true }\n\n[features]\nnet = { dependencies = [\"tls\"] }\n",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.optional_dependencies(), ["util", "tls"]);
+        assert_eq!(manifest.features.features["net"].dependencies, ["tls"]);
+    }
 }
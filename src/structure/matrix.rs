@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use crate::error::CedarError;
+
+use super::{
+    build::{BuildError, BuildOptions},
+    project::Project,
+    target::TargetFilter,
+};
+
+/// A combination of the [matrix] and how its build went.
+///
+/// # Fields
+///
+/// * 'profile' - The profile built with.
+/// * 'compiler' - The compiler built with.
+/// * 'targets' - The selection of targets built, as [matrix] names it.
+/// * 'result' - How long the build took, or the first line of its error.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub profile: String,
+    pub compiler: String,
+    pub targets: String,
+    pub result: Result<Duration, String>,
+}
+
+/// Builds every combination of the [matrix] of the project, one after the
+/// other, each into the out directory of its compiler and profile. A build
+/// failing does not stop the others.
+///
+/// # Returns
+///
+/// * Every combination, by profile, then compiler, then targets.
+///
+pub fn matrix(project: &Project) -> Result<Vec<Cell>, CedarError> {
+    let matrix = &project.manifest.matrix;
+
+    let or = |values: &Vec<String>, default: &str| match values.is_empty() {
+        true => vec![default.to_owned()],
+        false => values.clone(),
+    };
+    let profiles = or(&matrix.profiles, &project.profile);
+    let compilers = or(&matrix.compilers, &project.manifest.build.compiler);
+    let targets = or(&matrix.targets, "default");
+
+    // A typo fails before anything builds.
+    let filters = targets
+        .iter()
+        .map(|selection| Ok((selection, filters(selection)?)))
+        .collect::<Result<Vec<_>, BuildError>>()?;
+
+    let mut cells = Vec::new();
+
+    for profile in &profiles {
+        for compiler in &compilers {
+            for (selection, filters) in &filters {
+                let mut cell_project = project.clone();
+                cell_project.manifest.build.compiler = compiler.clone();
+
+                let result = cell_project
+                    .with_profile(profile)
+                    .map_err(CedarError::from)
+                    .and_then(|cell_project| {
+                        cell_project.build(&BuildOptions {
+                            quiet: true,
+                            targets: filters.clone(),
+                            ..Default::default()
+                        })
+                    });
+
+                cells.push(Cell {
+                    profile: profile.clone(),
+                    compiler: compiler.clone(),
+                    targets: selection.to_string(),
+                    result: result.map(|report| report.elapsed).map_err(|err| {
+                        let message = err.to_string();
+                        let line = message.lines().next().unwrap_or_default();
+                        line.trim_start_matches("Error: ").to_owned()
+                    }),
+                });
+            }
+        }
+    }
+
+    Ok(cells)
+}
+
+/// The target filters of a selection of targets of [matrix].
+fn filters(selection: &str) -> Result<Vec<TargetFilter>, BuildError> {
+    match selection.split_once(':') {
+        Some(("bin", name)) => Ok(vec![TargetFilter::Bin(name.to_owned())]),
+        Some(("example", name)) => Ok(vec![TargetFilter::Example(name.to_owned())]),
+        Some(_) => Err(BuildError::InvalidMatrixTargets(selection.to_owned())),
+        None => match selection {
+            "default" => Ok(Vec::new()),
+            "lib" => Ok(vec![TargetFilter::Lib]),
+            "examples" => Ok(vec![TargetFilter::Examples]),
+            "tests" => Ok(vec![TargetFilter::Tests]),
+            "all" => Ok(vec![TargetFilter::All]),
+            _ => Err(BuildError::InvalidMatrixTargets(selection.to_owned())),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_target_selections() {
+        assert_eq!(filters("default").unwrap(), []);
+        assert_eq!(filters("all").unwrap(), [TargetFilter::All]);
+        assert_eq!(
+            filters("bin:tool").unwrap(),
+            [TargetFilter::Bin(String::from("tool"))]
+        );
+        assert!(filters("bench:parse").is_err());
+        assert!(filters("everything").is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::error::CedarError;
+
+use super::{
+    build::{object_files, Language},
+    fingerprint,
+    manifest::Manifest,
+    project::Project,
+    target::TargetKind,
+};
+
+/// A machine readable description of a project, for editor plugins and
+/// scripts that should not have to parse the manifest or guess paths.
+///
+/// # Fields
+///
+/// * 'root' - The absolute path of the project.
+/// * 'manifest_path' - The absolute path of cedar.toml.
+/// * 'manifest' - The parsed manifest.
+/// * 'targets' - What the project builds, the sources only the target uses,
+///   and where the artifacts end up.
+/// * 'sources' - Every translation unit, its object, and the files it
+///   depends on (known once the project has been built).
+///
+#[derive(Debug, Serialize)]
+pub struct Metadata {
+    pub root: PathBuf,
+    pub manifest_path: PathBuf,
+    pub manifest: Manifest,
+    pub targets: Vec<TargetMetadata>,
+    pub sources: Vec<SourceMetadata>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetMetadata {
+    pub name: String,
+    pub kind: TargetKind,
+    pub sources: Vec<PathBuf>,
+    pub artifacts: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceMetadata {
+    pub path: PathBuf,
+    pub language: Language,
+    pub object: PathBuf,
+    pub dependencies: Vec<PathBuf>,
+}
+
+impl Metadata {
+    /// Collects the metadata of a project.
+    pub fn load(project: &Project) -> Result<Self, CedarError> {
+        let root = project
+            .root
+            .canonicalize()
+            .map_err(|err| CedarError::io(&project.root, err))?;
+        let manifest_path = root.join("cedar.toml");
+        let out_dir = project.out_dir();
+        let build_path = root.join(out_dir.strip_prefix(&project.root).unwrap_or(&out_dir));
+        let object_path = build_path.join("obj");
+
+        let mut targets = Vec::new();
+
+        for target in &project.targets {
+            let output_dir = build_path.join(target.output_dir());
+
+            targets.push(TargetMetadata {
+                name: target.name.clone(),
+                kind: target.kind,
+                sources: target
+                    .sources
+                    .iter()
+                    .map(|(source, _)| {
+                        root.join(source.strip_prefix(&project.root).unwrap_or(source))
+                    })
+                    .collect(),
+                artifacts: target
+                    .artifacts(&project.manifest)?
+                    .iter()
+                    .map(|artifact| output_dir.join(artifact.name()))
+                    .collect(),
+            });
+        }
+
+        let all_sources = project.library_sources.iter().chain(
+            project
+                .targets
+                .iter()
+                .flat_map(|target| target.sources.iter()),
+        );
+
+        let mut sources = Vec::new();
+
+        for (source, language) in all_sources {
+            let relative = source.strip_prefix(&project.root).unwrap_or(source);
+            let (object, depfile) = object_files(&object_path, relative);
+
+            let dependencies = fingerprint::dependencies(source, &depfile)
+                .into_iter()
+                .filter(|dependency| dependency != source)
+                .collect();
+
+            sources.push(SourceMetadata {
+                path: root.join(relative),
+                language: *language,
+                object,
+                dependencies,
+            });
+        }
+
+        Ok(Self {
+            root,
+            manifest_path,
+            manifest: project.manifest.clone(),
+            targets,
+            sources,
+        })
+    }
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn describes_a_project() {
+        let root = env::temp_dir().join(format!("cedar-metadata-{}", process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("cedar.toml"),
+            "[meta]\nname = \"app\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src").join("main.c"),
+            "int main(void) { return 0; }\n",
+        )
+        .unwrap();
+        fs::write(root.join("src").join("util.c"), "int util;\n").unwrap();
+
+        let project = Project::load(&root).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&Metadata::load(&project).unwrap().to_json().unwrap()).unwrap();
+
+        let root = root.canonicalize().unwrap();
+        let out_dir = root.join(project.out_dir().strip_prefix(&project.root).unwrap());
+        let path = |path: PathBuf| path.display().to_string();
+        let source = |name: &str| {
+            serde_json::json!({
+                "path": path(root.join("src").join(name)),
+                "language": "c",
+                "object": path(out_dir.join("obj").join("src").join(format!("{}.o", name))),
+                "dependencies": [],
+            })
+        };
+
+        assert_eq!(json["root"], path(root.clone()));
+        assert_eq!(json["manifest_path"], path(root.join("cedar.toml")));
+        assert_eq!(json["manifest"]["meta"]["name"], "app");
+        assert_eq!(json["manifest"]["build"]["compiler"], "gcc");
+        assert_eq!(
+            json["targets"],
+            serde_json::json!([{
+                "name": "app",
+                "kind": "bin",
+                "sources": [path(root.join("src").join("main.c"))],
+                "artifacts": [path(out_dir.join("app"))],
+            }])
+        );
+        assert_eq!(
+            json["sources"],
+            serde_json::json!([source("util.c"), source("main.c")])
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
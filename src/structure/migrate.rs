@@ -0,0 +1,1340 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs, io,
+    path::{Component, Path, PathBuf},
+    process,
+    str::FromStr,
+};
+
+use crate::{cli::CliError, error::CedarError};
+
+use super::{
+    build::{recursive_file_search, Language},
+    manifest::{Manifest, ProjectKind, SystemDependency},
+    project::ProjectError,
+};
+
+/// The build descriptions a project can be migrated from.
+///
+/// # Members
+///
+/// * 'Cmake' - A CMakeLists.txt.
+/// * 'Make' - The commands a Makefile runs, from make -n or a build log.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateSource {
+    Cmake,
+    Make,
+}
+
+impl FromStr for MigrateSource {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cmake" => Ok(Self::Cmake),
+            "make" => Ok(Self::Make),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, cedar can migrate from cmake or make.",
+                s
+            ))),
+        }
+    }
+}
+
+impl MigrateSource {
+    /// The build description found in the directory, if any.
+    pub fn detect(root: &Path) -> Option<Self> {
+        if root.join("CMakeLists.txt").is_file() {
+            return Some(Self::Cmake);
+        }
+
+        ["GNUmakefile", "makefile", "Makefile"]
+            .iter()
+            .any(|makefile| root.join(makefile).is_file())
+            .then_some(Self::Make)
+    }
+}
+
+/// The result of a migration.
+///
+/// # Fields
+///
+/// * 'manifest' - The cedar.toml that was written.
+/// * 'suggestions' - Files to move so the layout matches what cedar expects,
+///   as (from, to) relative to the project root.
+/// * 'notes' - What could not be carried over and needs a look.
+///
+pub struct Migration {
+    pub manifest: Manifest,
+    pub suggestions: Vec<(PathBuf, PathBuf)>,
+    pub notes: Vec<String>,
+}
+
+/// Writes a cedar.toml for a project built by another build system, from
+/// what its build description says about targets, sources, flags, and
+/// libraries. Sources are left where they are, the migration suggests where
+/// to move them instead.
+///
+/// # Arguments
+///
+/// * 'root' - The project directory.
+/// * 'source' - The build description to read.
+/// * 'log' - A log of the commands of a build to read instead of running
+///   make -n (make).
+///
+pub fn migrate(
+    root: &Path,
+    source: MigrateSource,
+    log: Option<&Path>,
+) -> Result<Migration, CedarError> {
+    let manifest_path = root.join("cedar.toml");
+
+    if manifest_path.exists() {
+        return Err(ProjectError::AlreadyExists(format!("{:?}", manifest_path)).into());
+    }
+
+    let imported = match source {
+        MigrateSource::Cmake => {
+            let mut reader = CmakeReader::new(root);
+            reader.read_file(root)?;
+            reader.finish()
+        }
+        MigrateSource::Make => {
+            let log = match log {
+                Some(log) => fs::read_to_string(log).map_err(|err| CedarError::io(log, err))?,
+                None => dry_run(root)?,
+            };
+            read_log(root, &log)
+        }
+    };
+
+    let migration = imported.migration(root);
+
+    // cedar expects these to exist, the sources are moved in by hand.
+    for dir in ["src", "include", "build"] {
+        let path = root.join(dir);
+        fs::create_dir_all(&path).map_err(|err| CedarError::io(&path, err))?;
+    }
+
+    fs::write(&manifest_path, migration.manifest.as_string()?)
+        .map_err(|err| CedarError::io(&manifest_path, err))?;
+
+    Ok(migration)
+}
+
+/// What a build description says about a project, relative to its root.
+#[derive(Debug, Default)]
+struct Imported {
+    name: Option<String>,
+    version: Option<String>,
+    executables: Vec<(String, Vec<PathBuf>)>,
+    libraries: Vec<(String, ProjectKind, Vec<PathBuf>)>,
+    tests: BTreeSet<String>,
+    include_dirs: Vec<PathBuf>,
+    cflags: Vec<String>,
+    cxxflags: Vec<String>,
+    ldflags: Vec<String>,
+    system_deps: BTreeMap<String, SystemDependency>,
+    notes: Vec<String>,
+}
+
+impl Imported {
+    /// The manifest and layout of the project in cedar terms: one library of
+    /// every non-main source, a binary per executable, and a test per test.
+    fn migration(self, root: &Path) -> Migration {
+        let mut manifest = Manifest::new();
+        let mut notes = self.notes;
+
+        manifest.meta.name = self
+            .name
+            .or_else(|| {
+                root.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_default();
+        if let Some(version) = self.version {
+            manifest.meta.version = version;
+        }
+
+        if self.executables.is_empty() {
+            let shared = self
+                .libraries
+                .iter()
+                .any(|(_, kind, _)| *kind == ProjectKind::SharedLib);
+            let static_ = self
+                .libraries
+                .iter()
+                .any(|(_, kind, _)| *kind == ProjectKind::StaticLib);
+
+            manifest.meta.kind = match (static_, shared) {
+                (true, true) => ProjectKind::Lib,
+                (false, true) => ProjectKind::SharedLib,
+                _ => ProjectKind::StaticLib,
+            };
+        }
+
+        if self.libraries.len() > 1 {
+            notes.push(format!(
+                "The libraries {} are merged into one, cedar builds a single library per project.",
+                self.libraries
+                    .iter()
+                    .map(|(name, _, _)| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        let sources: Vec<&PathBuf> = self
+            .executables
+            .iter()
+            .flat_map(|(_, sources)| sources)
+            .chain(self.libraries.iter().flat_map(|(_, _, sources)| sources))
+            .collect();
+        let cxx = sources
+            .iter()
+            .any(|source| Language::from_path(source) == Some(Language::Cxx));
+
+        // Flags.
+        manifest.build.cflags = self
+            .include_dirs
+            .iter()
+            .filter(|dir| !dir.as_os_str().is_empty() && **dir != Path::new("include"))
+            .map(|dir| format!("-I{}", dir.display()))
+            .chain(self.cflags.iter().cloned())
+            .collect();
+        dedup(&mut manifest.build.cflags);
+
+        if cxx {
+            manifest.build.cxxflags = self
+                .include_dirs
+                .iter()
+                .filter(|dir| !dir.as_os_str().is_empty() && **dir != Path::new("include"))
+                .map(|dir| format!("-I{}", dir.display()))
+                .chain(
+                    self.cflags
+                        .iter()
+                        .filter(|flag| !flag.starts_with("-std="))
+                        .cloned(),
+                )
+                .chain(self.cxxflags.iter().cloned())
+                .collect();
+            dedup(&mut manifest.build.cxxflags);
+        }
+
+        manifest.build.ldflags = self.ldflags;
+        dedup(&mut manifest.build.ldflags);
+        manifest.system_deps = self.system_deps;
+
+        // Layout.
+        let mut suggestions = Vec::new();
+        let mut mains = BTreeSet::new();
+        let primary = self
+            .executables
+            .iter()
+            .position(|(name, _)| *name == manifest.meta.name)
+            .unwrap_or_default();
+
+        for (index, (name, sources)) in self.executables.iter().enumerate() {
+            let main = match sources
+                .iter()
+                .find(|source| defines_main(&root.join(source)))
+            {
+                Some(main) => main,
+                None => match sources.first() {
+                    Some(first) => first,
+                    None => continue,
+                },
+            };
+            let extension = main
+                .extension()
+                .map(|ext| ext.to_string_lossy().into_owned())
+                .unwrap_or_else(|| String::from("c"));
+
+            let to = if self.tests.contains(name) {
+                PathBuf::from("tests").join(format!("{}.{}", name, extension))
+            } else if index == primary {
+                PathBuf::from("src").join(format!("main.{}", extension))
+            } else {
+                PathBuf::from("src")
+                    .join("bin")
+                    .join(format!("{}.{}", name, extension))
+            };
+
+            mains.insert(main.clone());
+            if *main != to {
+                suggestions.push((main.clone(), to));
+            }
+        }
+
+        if let Some((name, _)) = self.executables.get(primary) {
+            if *name != manifest.meta.name && !self.tests.contains(name) {
+                notes.push(format!(
+                    "The executable {} is built as {}, cedar names it after the project.",
+                    name, manifest.meta.name
+                ));
+            }
+        }
+
+        for source in sources {
+            if mains.contains(source) || source.starts_with("src") {
+                continue;
+            }
+
+            let to = PathBuf::from("src").join(source);
+            if !suggestions.iter().any(|(from, _)| from == source) {
+                suggestions.push((source.clone(), to));
+            }
+        }
+
+        for dir in &self.include_dirs {
+            if dir.as_os_str().is_empty() || dir == Path::new("include") {
+                continue;
+            }
+
+            notes.push(format!(
+                "Headers in {}/ are found through -I{}, they could move to include/ instead.",
+                dir.display(),
+                dir.display()
+            ));
+        }
+
+        // Sources cedar would pick up that no target used.
+        let known: BTreeSet<PathBuf> = suggestions.iter().map(|(_, to)| to.clone()).collect();
+        let used: BTreeSet<&PathBuf> = self
+            .executables
+            .iter()
+            .flat_map(|(_, sources)| sources)
+            .chain(self.libraries.iter().flat_map(|(_, _, sources)| sources))
+            .collect();
+
+        for file in recursive_file_search(root.join("src")).unwrap_or_default() {
+            let path = PathBuf::from(&file);
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+
+            if Language::from_path(&relative).is_some()
+                && !used.contains(&relative)
+                && !known.contains(&relative)
+            {
+                notes.push(format!(
+                    "{} is not part of any target, but cedar compiles every source in src/.",
+                    relative.display()
+                ));
+            }
+        }
+
+        Migration {
+            manifest,
+            suggestions,
+            notes,
+        }
+    }
+}
+
+/// Whether a source defines main(), to tell the entry point of an executable
+/// from the sources it shares.
+fn defines_main(path: &Path) -> bool {
+    let Ok(source) = fs::read_to_string(path) else {
+        return false;
+    };
+
+    source.lines().any(|line| {
+        let line = line.trim_start();
+        !line.starts_with("//")
+            && !line.starts_with('*')
+            && line.find("main").is_some_and(|at| {
+                let before = &line[..at];
+                let after = line[at + 4..].trim_start();
+                (before.is_empty() || before.ends_with([' ', '\t', '*']))
+                    && after.starts_with('(')
+                    && !line.trim_end().ends_with(';')
+            })
+    })
+}
+
+fn dedup(flags: &mut Vec<String>) {
+    let mut seen = BTreeSet::new();
+    flags.retain(|flag| seen.insert(flag.clone()));
+}
+
+/// Lexically normalizes a path, resolving . and .. without touching the
+/// file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// The imported targets of the packages find_package() is commonly used
+/// with, by the system dependency cedar knows them as.
+const PACKAGES: &[(&str, &str, &[&str])] = &[
+    ("ZLIB", "zlib", &["ZLIB::ZLIB"]),
+    ("OpenSSL", "openssl", &["OpenSSL::SSL", "OpenSSL::Crypto"]),
+    ("CURL", "libcurl", &["CURL::libcurl"]),
+    ("PNG", "libpng", &["PNG::PNG"]),
+    ("SQLite3", "sqlite3", &["SQLite::SQLite3"]),
+    ("LibXml2", "libxml-2.0", &["LibXml2::LibXml2"]),
+];
+
+/// Reads the commands of a CMakeLists.txt, and of the directories it adds,
+/// that describe the targets, expanding variables as it goes.
+struct CmakeReader {
+    root: PathBuf,
+    variables: HashMap<String, String>,
+    imported: Imported,
+    targets: BTreeSet<String>,
+    pkg_config_prefixes: BTreeSet<String>,
+    ignored: BTreeSet<String>,
+    conditional: bool,
+}
+
+impl CmakeReader {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            variables: HashMap::new(),
+            imported: Imported::default(),
+            targets: BTreeSet::new(),
+            pkg_config_prefixes: BTreeSet::new(),
+            ignored: BTreeSet::new(),
+            conditional: false,
+        }
+    }
+
+    /// Reads the CMakeLists.txt in a directory.
+    fn read_file(&mut self, dir: &Path) -> Result<(), CedarError> {
+        let path = dir.join("CMakeLists.txt");
+        let text = fs::read_to_string(&path).map_err(|err| CedarError::io(&path, err))?;
+
+        self.read(dir, &text)
+    }
+
+    /// Reads the commands of a CMakeLists.txt in the directory.
+    fn read(&mut self, dir: &Path, text: &str) -> Result<(), CedarError> {
+        let dir_string = dir.to_string_lossy().into_owned();
+        self.variables
+            .insert(String::from("CMAKE_CURRENT_SOURCE_DIR"), dir_string.clone());
+        self.variables
+            .insert(String::from("CMAKE_CURRENT_LIST_DIR"), dir_string);
+
+        for (command, raw) in commands(text) {
+            let args = self.expand(&raw);
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+            match (command.as_str(), args.as_slice()) {
+                ("project", [name, rest @ ..]) => {
+                    let root = self.root.to_string_lossy().into_owned();
+                    self.set("PROJECT_NAME", name);
+                    self.set("PROJECT_SOURCE_DIR", &root);
+                    self.set("CMAKE_SOURCE_DIR", &root);
+                    self.imported.name.get_or_insert_with(|| name.to_string());
+
+                    if let Some(version) = value_of(rest, "VERSION") {
+                        self.set("PROJECT_VERSION", version);
+                        self.imported
+                            .version
+                            .get_or_insert_with(|| version.to_owned());
+                    }
+                }
+                ("set", [name, values @ ..]) => {
+                    let values: Vec<&str> = values
+                        .iter()
+                        .take_while(|value| !matches!(**value, "CACHE" | "PARENT_SCOPE"))
+                        .copied()
+                        .collect();
+
+                    match *name {
+                        "CMAKE_C_STANDARD" => self
+                            .imported
+                            .cflags
+                            .extend(values.first().map(|std| format!("-std=c{}", std))),
+                        "CMAKE_CXX_STANDARD" => self
+                            .imported
+                            .cxxflags
+                            .extend(values.first().map(|std| format!("-std=c++{}", std))),
+                        "CMAKE_C_FLAGS" => self.imported.cflags.extend(flags(&values)),
+                        "CMAKE_CXX_FLAGS" => self.imported.cxxflags.extend(flags(&values)),
+                        _ => {}
+                    }
+
+                    self.set(name, &values.join(";"));
+                }
+                ("list", ["APPEND", name, values @ ..]) => {
+                    let mut list: Vec<String> = self
+                        .variables
+                        .get(*name)
+                        .filter(|list| !list.is_empty())
+                        .map(|list| list.split(';').map(str::to_owned).collect())
+                        .unwrap_or_default();
+                    list.extend(values.iter().map(|value| value.to_string()));
+                    self.set(name, &list.join(";"));
+                }
+                ("file", [mode @ ("GLOB" | "GLOB_RECURSE"), name, patterns @ ..]) => {
+                    let files = glob(dir, patterns, *mode == "GLOB_RECURSE");
+                    self.set(name, &files.join(";"));
+                }
+                ("add_executable", [name, rest @ ..]) => {
+                    if !is_imported(rest) {
+                        let sources = self.sources(
+                            dir,
+                            &skip_keywords(rest, &["WIN32", "MACOSX_BUNDLE", "EXCLUDE_FROM_ALL"]),
+                        );
+                        self.targets.insert(name.to_string());
+                        self.imported.executables.push((name.to_string(), sources));
+                    }
+                }
+                ("add_library", [name, rest @ ..]) => {
+                    if !is_imported(rest) {
+                        let kind = match rest.first() {
+                            Some(&"SHARED") | Some(&"MODULE") => ProjectKind::SharedLib,
+                            _ => ProjectKind::StaticLib,
+                        };
+                        let sources = self.sources(
+                            dir,
+                            &skip_keywords(
+                                rest,
+                                &[
+                                    "STATIC",
+                                    "SHARED",
+                                    "MODULE",
+                                    "OBJECT",
+                                    "INTERFACE",
+                                    "EXCLUDE_FROM_ALL",
+                                ],
+                            ),
+                        );
+                        self.targets.insert(name.to_string());
+                        self.imported
+                            .libraries
+                            .push((name.to_string(), kind, sources));
+                    }
+                }
+                ("target_sources", [name, rest @ ..]) => {
+                    let sources = self.sources(dir, &skip_keywords(rest, SCOPES));
+                    self.target_sources(name, sources);
+                }
+                ("include_directories", dirs) => {
+                    let dirs = skip_keywords(dirs, &["AFTER", "BEFORE", "SYSTEM"]);
+                    self.include_dirs(dir, &dirs);
+                }
+                ("target_include_directories", [_, dirs @ ..]) => {
+                    let dirs = skip_keywords(
+                        dirs,
+                        &[
+                            "AFTER",
+                            "BEFORE",
+                            "SYSTEM",
+                            "PUBLIC",
+                            "PRIVATE",
+                            "INTERFACE",
+                        ],
+                    );
+                    self.include_dirs(dir, &dirs);
+                }
+                ("add_definitions", definitions) => {
+                    self.imported.cflags.extend(flags(definitions));
+                }
+                ("add_compile_definitions", definitions)
+                | ("target_compile_definitions", [_, definitions @ ..]) => {
+                    self.imported.cflags.extend(
+                        skip_keywords(definitions, SCOPES)
+                            .iter()
+                            .filter(|definition| !definition.contains("$<"))
+                            .map(|definition| format!("-D{}", definition.trim_start_matches("-D"))),
+                    );
+                }
+                ("add_compile_options", options)
+                | ("target_compile_options", [_, options @ ..]) => {
+                    self.imported.cflags.extend(flags(options));
+                }
+                ("link_libraries", libraries) | ("target_link_libraries", [_, libraries @ ..]) => {
+                    self.link(&skip_keywords(
+                        libraries,
+                        &[
+                            "PUBLIC",
+                            "PRIVATE",
+                            "INTERFACE",
+                            "debug",
+                            "optimized",
+                            "general",
+                        ],
+                    ));
+                }
+                ("find_package", [package, ..]) => {
+                    if *package == "Threads" {
+                        self.set("CMAKE_THREAD_LIBS_INIT", "-pthread");
+                    } else if let Some((name, _, targets)) =
+                        PACKAGES.iter().find(|(name, _, _)| name == package)
+                    {
+                        self.set(
+                            &format!("{}_LIBRARIES", name.to_ascii_uppercase()),
+                            &targets.join(";"),
+                        );
+                        self.set(&format!("{}_LIBRARIES", name), &targets.join(";"));
+                    } else if *package != "PkgConfig" {
+                        self.imported.notes.push(format!(
+                            "find_package({}) has no cedar equivalent, add it under [system-deps] or [dependencies].",
+                            package
+                        ));
+                    }
+                }
+                ("pkg_check_modules" | "pkg_search_module", [prefix, modules @ ..]) => {
+                    let modules = skip_keywords(
+                        modules,
+                        &[
+                            "REQUIRED",
+                            "QUIET",
+                            "IMPORTED_TARGET",
+                            "GLOBAL",
+                            "NO_CMAKE_PATH",
+                            "NO_CMAKE_ENVIRONMENT_PATH",
+                        ],
+                    );
+                    for module in &modules {
+                        let (name, version) = match module.find(['<', '>', '=']) {
+                            Some(at) => (&module[..at], &module[at..]),
+                            None => (*module, "*"),
+                        };
+                        self.imported.system_deps.insert(
+                            name.to_owned(),
+                            SystemDependency::Version(version.to_owned()),
+                        );
+                    }
+                    self.pkg_config_prefixes.insert(prefix.to_string());
+                    self.set(&format!("{}_LIBRARIES", prefix), "");
+                    self.set(&format!("{}_INCLUDE_DIRS", prefix), "");
+                }
+                ("add_test", args) => {
+                    let executable = match value_of(args, "COMMAND") {
+                        Some(command) => Some(command),
+                        None => args.get(1).copied(),
+                    };
+                    if let Some(executable) = executable {
+                        self.imported.tests.insert(executable.to_owned());
+                    }
+                }
+                ("add_subdirectory", [subdir, ..]) => {
+                    let subdir = dir.join(subdir);
+                    if subdir.join("CMakeLists.txt").is_file() {
+                        self.read_file(&subdir)?;
+                        let dir_string = dir.to_string_lossy().into_owned();
+                        self.set("CMAKE_CURRENT_SOURCE_DIR", &dir_string);
+                        self.set("CMAKE_CURRENT_LIST_DIR", &dir_string);
+                    }
+                }
+                ("if" | "elseif" | "else", _) => self.conditional = true,
+                (
+                    "cmake_minimum_required"
+                    | "endif"
+                    | "enable_testing"
+                    | "message"
+                    | "include"
+                    | "option"
+                    | "set_property"
+                    | "set_target_properties",
+                    _,
+                ) => {}
+                (command, _) => {
+                    self.ignored.insert(command.to_owned());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The notes about what was not migrated, then what was read.
+    fn finish(mut self) -> Imported {
+        if self.conditional {
+            self.imported.notes.push(String::from(
+                "Conditional blocks were read as if every condition held, check the flags.",
+            ));
+        }
+
+        if !self.ignored.is_empty() {
+            self.imported.notes.push(format!(
+                "Ignored CMake commands: {}.",
+                self.ignored.iter().cloned().collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        self.imported
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        self.variables.insert(name.to_owned(), value.to_owned());
+    }
+
+    /// Expands the variables in the arguments of a command, unquoted
+    /// arguments holding a list become one argument per item.
+    fn expand(&self, args: &[(String, bool)]) -> Vec<String> {
+        let mut expanded = Vec::new();
+
+        for (arg, quoted) in args {
+            let mut value = arg.clone();
+
+            // Innermost references first, so ${${name}_SOURCES} works.
+            while let Some(start) = value.rfind("${") {
+                let Some(length) = value[start..].find('}') else {
+                    break;
+                };
+                let name = &value[start + 2..start + length];
+                let replacement = self.variables.get(name).cloned().unwrap_or_default();
+                value.replace_range(start..start + length + 1, &replacement);
+            }
+
+            match quoted {
+                true => expanded.push(value),
+                false => expanded.extend(
+                    value
+                        .split(';')
+                        .filter(|item| !item.is_empty())
+                        .map(str::to_owned),
+                ),
+            }
+        }
+
+        expanded
+    }
+
+    /// The sources among the arguments, relative to the root.
+    fn sources(&self, dir: &Path, args: &[&str]) -> Vec<PathBuf> {
+        args.iter()
+            .filter(|arg| !arg.contains("$<"))
+            .map(|arg| self.relative(dir, arg))
+            .filter(|source| Language::from_path(source).is_some())
+            .collect()
+    }
+
+    fn target_sources(&mut self, name: &str, sources: Vec<PathBuf>) {
+        if let Some((_, existing)) = self
+            .imported
+            .executables
+            .iter_mut()
+            .find(|(target, _)| target == name)
+        {
+            existing.extend(sources);
+        } else if let Some((_, _, existing)) = self
+            .imported
+            .libraries
+            .iter_mut()
+            .find(|(target, _, _)| target == name)
+        {
+            existing.extend(sources);
+        }
+    }
+
+    fn include_dirs(&mut self, dir: &Path, dirs: &[&str]) {
+        for include in dirs.iter().filter(|include| !include.contains("$<")) {
+            let include = self.relative(dir, include);
+            if !self.imported.include_dirs.contains(&include) {
+                self.imported.include_dirs.push(include);
+            }
+        }
+    }
+
+    /// Turns what a target links into ldflags and system dependencies, other
+    /// targets of the project need nothing as cedar links everything into
+    /// every target.
+    fn link(&mut self, libraries: &[&str]) {
+        for library in libraries {
+            if self.targets.contains(*library) || library.contains("$<") {
+                continue;
+            }
+
+            if library.starts_with('-') {
+                self.imported.ldflags.push(library.to_string());
+            } else if *library == "Threads::Threads" {
+                self.imported.ldflags.push(String::from("-pthread"));
+            } else if let Some(prefix) = library.strip_prefix("PkgConfig::") {
+                if !self.pkg_config_prefixes.contains(prefix) {
+                    self.imported
+                        .notes
+                        .push(format!("Link to {} was not migrated.", library));
+                }
+            } else if let Some((_, name, _)) = PACKAGES
+                .iter()
+                .find(|(_, _, targets)| targets.contains(library))
+            {
+                self.imported
+                    .system_deps
+                    .entry(name.to_string())
+                    .or_insert_with(|| SystemDependency::Version(String::from("*")));
+            } else if library.contains("::") {
+                self.imported
+                    .notes
+                    .push(format!("Link to {} was not migrated.", library));
+            } else if library.ends_with(".a") || library.contains(".so") {
+                self.imported.ldflags.push(library.to_string());
+            } else {
+                self.imported.ldflags.push(format!("-l{}", library));
+            }
+        }
+    }
+
+    /// A path argument relative to the project root.
+    fn relative(&self, dir: &Path, path: &str) -> PathBuf {
+        let path = normalize(&dir.join(path));
+        path.strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+            .unwrap_or(path)
+    }
+}
+
+const SCOPES: &[&str] = &["PUBLIC", "PRIVATE", "INTERFACE", "BEFORE"];
+
+/// Splits the commands out of a CMakeLists.txt, as their lowercase names and
+/// their arguments, each marked as quoted or not.
+fn commands(text: &str) -> Vec<(String, Vec<(String, bool)>)> {
+    let mut commands = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '#' {
+            chars.by_ref().find(|ch| *ch == '\n');
+            continue;
+        }
+
+        if !(ch.is_ascii_alphabetic() || ch == '_') {
+            continue;
+        }
+
+        let mut name = String::from(ch);
+        while let Some(ch) = chars.next_if(|ch| ch.is_ascii_alphanumeric() || *ch == '_') {
+            name.push(ch);
+        }
+        while chars.next_if(|ch| *ch == ' ' || *ch == '\t').is_some() {}
+
+        if chars.next_if_eq(&'(').is_none() {
+            continue;
+        }
+
+        let mut args = Vec::new();
+        let mut arg = String::new();
+        let mut depth = 0;
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => {
+                    let mut quoted = String::new();
+                    while let Some(ch) = chars.next() {
+                        match ch {
+                            '"' => break,
+                            '\\' => quoted.extend(chars.next()),
+                            ch => quoted.push(ch),
+                        }
+                    }
+                    args.push((quoted, true));
+                }
+                '#' => {
+                    chars.by_ref().find(|ch| *ch == '\n');
+                }
+                '(' => {
+                    depth += 1;
+                    arg.push(ch);
+                }
+                ')' if depth == 0 => break,
+                ')' => {
+                    depth -= 1;
+                    arg.push(ch);
+                }
+                ch if ch.is_whitespace() => {
+                    if !arg.is_empty() {
+                        args.push((std::mem::take(&mut arg), false));
+                    }
+                }
+                ch => arg.push(ch),
+            }
+        }
+
+        if !arg.is_empty() {
+            args.push((arg, false));
+        }
+
+        commands.push((name.to_ascii_lowercase(), args));
+    }
+
+    commands
+}
+
+/// The value after a keyword argument, e.g. VERSION 1.2.0.
+fn value_of<'a>(args: &[&'a str], keyword: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| *arg == keyword)
+        .and_then(|at| args.get(at + 1))
+        .copied()
+}
+
+fn is_imported(args: &[&str]) -> bool {
+    args.iter().any(|arg| matches!(*arg, "IMPORTED" | "ALIAS"))
+}
+
+fn skip_keywords<'a>(args: &[&'a str], keywords: &[&str]) -> Vec<&'a str> {
+    args.iter()
+        .filter(|arg| !keywords.contains(arg))
+        .copied()
+        .collect()
+}
+
+/// Compiler flags from arguments that may each hold several.
+fn flags(args: &[&str]) -> Vec<String> {
+    args.iter()
+        .filter(|arg| !arg.contains("$<") && !SCOPES.contains(arg))
+        .flat_map(|arg| arg.split_whitespace())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Expands file(GLOB) patterns of the form dir/*.ext.
+fn glob(dir: &Path, patterns: &[&str], recurse: bool) -> Vec<String> {
+    let mut files = Vec::new();
+
+    for pattern in patterns
+        .iter()
+        .filter(|pattern| **pattern != "CONFIGURE_DEPENDS")
+    {
+        let pattern = dir.join(pattern);
+        let (Some(parent), Some(name)) = (pattern.parent(), pattern.file_name()) else {
+            continue;
+        };
+        let name = name.to_string_lossy();
+        let (prefix, suffix) = name.split_once('*').unwrap_or((&name, ""));
+
+        let candidates: Vec<PathBuf> = match recurse {
+            true => recursive_file_search(parent)
+                .unwrap_or_default()
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+            false => fs::read_dir(parent)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect(),
+        };
+
+        files.extend(
+            candidates
+                .into_iter()
+                .filter(|file| {
+                    file.file_name().is_some_and(|file| {
+                        let file = file.to_string_lossy();
+                        file.starts_with(prefix) && file.ends_with(suffix)
+                    })
+                })
+                .map(|file| file.to_string_lossy().into_owned()),
+        );
+    }
+
+    files.sort();
+    files
+}
+
+/// Runs make -n -B in the root, printing every command a full build would
+/// run without running any of them.
+fn dry_run(root: &Path) -> Result<String, CedarError> {
+    let mut command = process::Command::new("make");
+    command
+        .args(["-n", "-B", "-w"])
+        .current_dir(root)
+        .env("LC_ALL", "C");
+
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(ProjectError::MissingTool("make").into())
+        }
+        Err(err) => return Err(CedarError::command(&command, err)),
+    };
+
+    if !output.status.success() {
+        return Err(ProjectError::ToolFailed("make -n").into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Recovers the targets and flags of a project from the commands of a
+/// build: what each compile gets, which objects each link combines, and
+/// which objects are archived into libraries.
+///
+/// # Arguments
+///
+/// * 'root' - The project directory, where the build started.
+/// * 'log' - The commands, as printed by make -n or in a build log.
+///
+fn read_log(root: &Path, log: &str) -> Imported {
+    let mut imported = Imported::default();
+    let mut objects: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    let mut flag_sets = BTreeSet::new();
+    let mut linked = BTreeSet::new();
+    let mut compiled = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    // Commands continue on the next line after a backslash.
+    let log = log.replace("\\\n", " ");
+
+    for line in log.lines() {
+        // make -w reports the directories recursive makes run in.
+        if let Some(dir) = line
+            .split_once("Entering directory")
+            .map(|(_, dir)| dir.trim().trim_matches(['\'', '`', '"']))
+        {
+            dirs.push(PathBuf::from(dir));
+            continue;
+        }
+        if line.contains("Leaving directory") {
+            if dirs.len() > 1 {
+                dirs.pop();
+            }
+            continue;
+        }
+
+        let mut cwd = dirs.last().cloned().unwrap_or_else(|| root.to_path_buf());
+
+        for words in shell_commands(line) {
+            let words: Vec<&str> = words.iter().map(String::as_str).collect();
+            let Some((program, args)) = words.split_first() else {
+                continue;
+            };
+            let program = program.rsplit('/').next().unwrap_or(program);
+
+            if program == "cd" {
+                if let Some(dir) = args.first() {
+                    cwd = normalize(&cwd.join(dir));
+                }
+                continue;
+            }
+
+            let relative = |path: &str| {
+                let path = normalize(&cwd.join(path));
+                path.strip_prefix(root)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(path)
+            };
+
+            if is_archiver(program) {
+                // ar rcs libname.a objects...
+                let mut paths = args.iter().skip(1).filter(|arg| !arg.starts_with('-'));
+                let Some(archive) = paths.next() else {
+                    continue;
+                };
+                let sources = paths
+                    .flat_map(|object| objects.get(&relative(object)).cloned().unwrap_or_default())
+                    .collect::<Vec<_>>();
+                linked.extend(sources.iter().cloned());
+                imported
+                    .libraries
+                    .push((library_name(archive), ProjectKind::StaticLib, sources));
+                continue;
+            }
+
+            let Some(cxx) = compiler(program) else {
+                continue;
+            };
+
+            let mut output = None;
+            let mut inputs = Vec::new();
+            let mut flags = Vec::new();
+            let mut link_flags = Vec::new();
+            let (mut compile, mut shared) = (false, false);
+            let mut args = args.iter();
+
+            while let Some(arg) = args.next() {
+                match *arg {
+                    "-c" => compile = true,
+                    "-shared" => shared = true,
+                    "-o" => output = args.next().map(|output| relative(output)),
+                    "-MF" | "-MT" | "-MQ" => {
+                        args.next();
+                    }
+                    "-I" | "-isystem" | "-iquote" => {
+                        if let Some(dir) = args.next() {
+                            imported.include_dirs.push(relative(dir));
+                        }
+                    }
+                    "-include" | "-x" => {
+                        flags.push(arg.to_string());
+                        flags.extend(args.next().map(|value| value.to_string()));
+                    }
+                    "-pthread" => {
+                        flags.push(arg.to_string());
+                        link_flags.push(arg.to_string());
+                    }
+                    arg if arg.starts_with("-M") => {}
+                    arg if arg.starts_with("-I") => imported.include_dirs.push(relative(&arg[2..])),
+                    // Only the libraries of the project itself are in its root.
+                    arg if arg.starts_with("-L") => match relative(&arg[2..]) {
+                        dir if dir.as_os_str().is_empty() => {}
+                        dir => link_flags.push(format!("-L{}", dir.display())),
+                    },
+                    arg if arg.starts_with("-l") || arg.starts_with("-Wl,") => {
+                        link_flags.push(arg.to_owned())
+                    }
+                    arg if arg.starts_with('-') => flags.push(arg.to_owned()),
+                    arg => inputs.push(relative(arg)),
+                }
+            }
+
+            let sources: Vec<PathBuf> = inputs
+                .iter()
+                .filter(|input| Language::from_path(input).is_some())
+                .cloned()
+                .collect();
+            let cxx = cxx
+                || sources
+                    .iter()
+                    .any(|source| Language::from_path(source) == Some(Language::Cxx));
+
+            if !sources.is_empty() {
+                match cxx {
+                    true => imported.cxxflags.extend(flags.iter().cloned()),
+                    false => imported.cflags.extend(flags.iter().cloned()),
+                }
+                flag_sets.insert(flags.clone());
+                compiled.extend(sources.iter().cloned());
+            }
+
+            if compile {
+                if let (Some(output), [source]) = (&output, sources.as_slice()) {
+                    objects.insert(output.clone(), vec![source.clone()]);
+                } else {
+                    for source in &sources {
+                        objects.insert(source.with_extension("o"), vec![source.clone()]);
+                    }
+                }
+                continue;
+            }
+
+            // A link, of objects or straight from sources.
+            let target_sources: Vec<PathBuf> = inputs
+                .iter()
+                .flat_map(|input| match Language::from_path(input) {
+                    Some(_) => vec![input.clone()],
+                    None => objects.get(input).cloned().unwrap_or_default(),
+                })
+                .collect();
+            let name = output
+                .as_ref()
+                .and_then(|output| output.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| String::from("a.out"));
+
+            imported.ldflags.extend(link_flags);
+            imported.ldflags.extend(
+                inputs
+                    .iter()
+                    .filter(|input| {
+                        input
+                            .extension()
+                            .is_some_and(|ext| ext == "a" || ext == "so")
+                            && !imported.libraries.iter().any(|(library, _, _)| {
+                                *library == library_name(&input.to_string_lossy())
+                            })
+                    })
+                    .map(|input| input.display().to_string()),
+            );
+            linked.extend(target_sources.iter().cloned());
+
+            if shared {
+                imported.libraries.push((
+                    library_name(&name),
+                    ProjectKind::SharedLib,
+                    target_sources,
+                ));
+            } else {
+                if name.starts_with("test") || name.ends_with("_test") {
+                    imported.tests.insert(name.clone());
+                }
+                imported.executables.push((name, target_sources));
+            }
+        }
+    }
+
+    // Libraries of the project that executables link are part of it.
+    let libraries: Vec<String> = imported
+        .libraries
+        .iter()
+        .map(|(name, _, _)| format!("-l{}", name))
+        .collect();
+    imported.ldflags.retain(|flag| !libraries.contains(flag));
+
+    let mut seen = BTreeSet::new();
+    imported.include_dirs.retain(|dir| seen.insert(dir.clone()));
+
+    // Sources compiled but never linked still belong to the project.
+    let unlinked: Vec<PathBuf> = compiled
+        .into_iter()
+        .filter(|source| !linked.contains(source))
+        .collect();
+    if !unlinked.is_empty() {
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        imported
+            .libraries
+            .push((name, ProjectKind::StaticLib, unlinked));
+    }
+
+    if flag_sets.len() > 1 {
+        imported.notes.push(String::from(
+            "Sources were compiled with different flags, cedar.toml has all of them.",
+        ));
+    }
+
+    imported
+}
+
+/// Whether a program is a C or C++ compiler driver, e.g. gcc, clang++, or
+/// x86_64-linux-gnu-gcc-12, and if so whether it is the C++ one.
+fn compiler(program: &str) -> Option<bool> {
+    let name = program.trim_end_matches(|ch: char| ch.is_ascii_digit() || ch == '.' || ch == '-');
+    let name = name.rsplit('-').next().unwrap_or(name);
+
+    match name {
+        "cc" | "gcc" | "clang" => Some(false),
+        "c++" | "g++" | "clang++" => Some(true),
+        _ => None,
+    }
+}
+
+/// Whether a program is an archiver, e.g. ar, gcc-ar, or llvm-ar.
+fn is_archiver(program: &str) -> bool {
+    program.rsplit('-').next() == Some("ar")
+}
+
+/// The name of a library from its file, libfoo.a is foo.
+fn library_name(file: &str) -> String {
+    let file = file.rsplit('/').next().unwrap_or(file);
+    let file = file.strip_prefix("lib").unwrap_or(file);
+
+    file.split(".so")
+        .next()
+        .unwrap_or(file)
+        .trim_end_matches(".a")
+        .to_owned()
+}
+
+/// Splits a line of shell into its commands, at &&, ||, and ;, and each
+/// command into its words, with quotes removed.
+fn shell_commands(line: &str) -> Vec<Vec<String>> {
+    let mut commands = vec![Vec::new()];
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    let end_word = |word: &mut String, quoted: &mut bool, commands: &mut Vec<Vec<String>>| {
+        if !word.is_empty() || *quoted {
+            commands
+                .last_mut()
+                .expect("there is a command")
+                .push(std::mem::take(word));
+        }
+        *quoted = false;
+    };
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' => {
+                quoted = true;
+                word.extend(chars.by_ref().take_while(|ch| *ch != '\''));
+            }
+            '"' => {
+                quoted = true;
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        ch => word.push(ch),
+                    }
+                }
+            }
+            '\\' => word.extend(chars.next()),
+            ';' | '&' | '|' => {
+                end_word(&mut word, &mut quoted, &mut commands);
+                chars.next_if(|next| *next == ch);
+                commands.push(Vec::new());
+            }
+            ch if ch.is_whitespace() => end_word(&mut word, &mut quoted, &mut commands),
+            ch => word.push(ch),
+        }
+    }
+
+    end_word(&mut word, &mut quoted, &mut commands);
+    commands.retain(|command| !command.is_empty());
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_cmake_targets() {
+        let mut reader = CmakeReader::new(Path::new("/p"));
+        reader
+            .read(
+                Path::new("/p"),
+                "cmake_minimum_required(VERSION 3.10)
+                 project(calc VERSION 1.4.0 LANGUAGES C) # a comment
+                 set(CMAKE_C_STANDARD 11)
+                 set(SOURCES lib/parse.c \"lib/eval.c\")
+                 add_library(calccore STATIC ${SOURCES})
+                 target_include_directories(calccore PUBLIC ${CMAKE_CURRENT_SOURCE_DIR}/headers)
+                 target_compile_definitions(calccore PRIVATE USE_FAST_MATH=1)
+                 find_package(ZLIB REQUIRED)
+                 add_executable(calc app/main.c)
+                 target_link_libraries(calc PRIVATE calccore m ${ZLIB_LIBRARIES})",
+            )
+            .unwrap();
+        let imported = reader.finish();
+
+        assert_eq!(imported.name.as_deref(), Some("calc"));
+        assert_eq!(imported.version.as_deref(), Some("1.4.0"));
+        assert_eq!(
+            imported.libraries[0].2,
+            [PathBuf::from("lib/parse.c"), PathBuf::from("lib/eval.c")]
+        );
+        assert_eq!(imported.include_dirs, [PathBuf::from("headers")]);
+        assert_eq!(imported.cflags, ["-std=c11", "-DUSE_FAST_MATH=1"]);
+        assert_eq!(imported.ldflags, ["-lm"]);
+        assert!(imported.system_deps.contains_key("zlib"));
+    }
+
+    #[test]
+    fn reads_make_logs() {
+        let imported = read_log(
+            Path::new("/p"),
+            "make: Entering directory '/p'
+gcc -O2 -Iinc -DNAME=\\\"x\\\" -MMD -c main.c -o main.o
+cd lib && gcc -O2 -Iinc \\
+  -c a.c -o a.o
+ar rcs lib/libcore.a lib/a.o
+gcc -o tool main.o -L. -Llib -lcore -lm",
+        );
+
+        assert_eq!(imported.executables[0].0, "tool");
+        assert_eq!(imported.executables[0].1, [PathBuf::from("main.c")]);
+        assert_eq!(imported.libraries[0].2, [PathBuf::from("lib/a.c")]);
+        assert_eq!(
+            imported.include_dirs,
+            [PathBuf::from("inc"), PathBuf::from("lib/inc")]
+        );
+        assert_eq!(imported.cflags[..2], ["-O2", "-DNAME=\"x\""]);
+        assert_eq!(imported.ldflags, ["-Llib", "-lm"]);
+    }
+}
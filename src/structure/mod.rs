@@ -1,4 +1,65 @@
+pub mod abi;
+pub mod amalgamate;
+pub mod audit;
+pub mod bench;
 pub mod build;
+pub mod buildlog;
+pub mod buildscript;
+pub mod cache;
+pub mod changelog;
+pub mod check;
+pub mod cmake;
+pub mod conan;
+pub mod config;
+pub mod container;
+pub mod coverage;
+pub mod deps;
+pub mod diagnostics;
+pub mod dist;
+pub mod doc;
+pub mod expand;
+pub mod export;
+pub mod fingerprint;
+pub mod flags;
+pub mod generate;
+pub mod graph;
+pub mod hooks;
+pub mod ide;
+pub mod ignore;
+pub mod info;
 pub mod init;
+pub mod install;
+pub mod intercept;
+pub mod license;
+pub mod limits;
+pub mod lint;
+pub mod lockfile;
 pub mod manifest;
+pub mod matrix;
+pub mod metadata;
+pub mod migrate;
+pub mod ninja;
+pub mod notify;
+pub mod package;
+pub mod pkgconfig;
+pub mod probe;
+pub mod profile;
 pub mod project;
+pub mod release;
+pub mod remote;
+pub mod sbom;
+pub mod sha256;
+pub mod sign;
+pub mod snapshot;
+pub mod stats;
+pub mod subproject;
+pub mod sysdeps;
+pub mod target;
+pub mod task;
+pub mod test;
+pub mod timeout;
+pub mod timings;
+pub mod tooling;
+pub mod trace;
+pub mod vcpkg;
+pub mod version;
@@ -0,0 +1,242 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::error::CedarError;
+
+use super::{build::BuildError, project::ProjectError};
+
+/// A compile or link of the build, as an edge of build.ninja.
+///
+/// # Fields
+///
+/// * 'output' - The file it produces.
+/// * 'inputs' - The files it reads, which make it rerun when they change.
+/// * 'depfile' - The depfile the compiler writes, listing the headers the
+///   source includes. None for links.
+/// * 'command' - What to run.
+///
+pub struct Edge {
+    pub output: PathBuf,
+    pub inputs: Vec<PathBuf>,
+    pub depfile: Option<PathBuf>,
+    pub command: String,
+}
+
+/// Writes the edges to build/build.ninja and runs ninja on the requested
+/// outputs. ninja keeps its log in the build directory, so it only reruns
+/// edges whose inputs, included headers, or commands changed.
+///
+/// # Arguments
+///
+/// * 'build_path' - The build directory.
+/// * 'edges' - Every compile and link of the build.
+/// * 'outputs' - The outputs to bring up to date.
+/// * 'quiet' - Captures the output of ninja instead of printing it.
+/// * 'explain' - Has ninja print why it reruns every edge.
+///
+/// # Returns
+///
+/// * The captured output, empty when not quiet.
+///
+pub fn run(
+    build_path: &Path,
+    edges: &[Edge],
+    outputs: &[PathBuf],
+    quiet: bool,
+    explain: bool,
+) -> Result<String, CedarError> {
+    let path = build_path.join("build.ninja");
+    let contents = generate(build_path, edges, outputs);
+
+    // Rewriting an unchanged file would make ninja reload it for nothing.
+    if fs::read_to_string(&path).ok().as_deref() != Some(contents.as_str()) {
+        fs::write(&path, contents).map_err(|err| CedarError::io(&path, err))?;
+    }
+
+    let mut command = process::Command::new("ninja");
+    command.arg("-f").arg(&path).current_dir(build_path);
+
+    if explain {
+        command.args(["-d", "explain"]);
+    }
+
+    let (success, output) = match quiet {
+        true => match command.output() {
+            Ok(output) => {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                (output.status.success(), text)
+            }
+            Err(err) => return Err(spawn_error(&command, err)),
+        },
+        false => match command.status() {
+            Ok(status) => (status.success(), String::new()),
+            Err(err) => return Err(spawn_error(&command, err)),
+        },
+    };
+
+    if !success {
+        return Err(
+            BuildError::CompilationFailed(String::from("the project with ninja"), output).into(),
+        );
+    }
+
+    Ok(output)
+}
+
+fn spawn_error(command: &process::Command, err: io::Error) -> CedarError {
+    match err.kind() {
+        io::ErrorKind::NotFound => ProjectError::MissingTool("ninja").into(),
+        _ => CedarError::command(command, err),
+    }
+}
+
+/// The build.ninja of the edges, with the outputs as its defaults.
+fn generate(build_path: &Path, edges: &[Edge], outputs: &[PathBuf]) -> String {
+    let mut ninja = format!(
+        "# Generated by cedar from cedar.toml, do not edit.\n\nninja_required_version = 1.3\nbuilddir = {}\n\nrule cc\n  command = $cmd\n  description = CC $in\n  depfile = $dep\n  deps = gcc\n\nrule link\n  command = $cmd\n  description = LINK $out\n",
+        build_path.to_string_lossy().replace('$', "$$")
+    );
+
+    for edge in edges {
+        let rule = match edge.depfile {
+            Some(_) => "cc",
+            None => "link",
+        };
+
+        ninja.push_str(&format!(
+            "\nbuild {}: {}{}\n  cmd = {}\n",
+            escape_path(&edge.output),
+            rule,
+            edge.inputs
+                .iter()
+                .map(|input| format!(" {}", escape_path(input)))
+                .collect::<String>(),
+            edge.command.replace('$', "$$")
+        ));
+
+        if let Some(depfile) = &edge.depfile {
+            ninja.push_str(&format!("  dep = {}\n", escape_path(depfile)));
+        }
+    }
+
+    ninja.push_str(&format!(
+        "\ndefault{}\n",
+        outputs
+            .iter()
+            .map(|output| format!(" {}", escape_path(output)))
+            .collect::<String>()
+    ));
+
+    ninja
+}
+
+/// A command line for the shell ninja runs commands with. On Windows ninja
+/// runs commands without a shell, the arguments are quoted the way programs
+/// split their command line there.
+pub fn shell(command: &process::Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| quote_arg(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A command line removing a file before running the command, for archives
+/// ar would otherwise update in place.
+pub fn replacing(output: &Path, command: &process::Command) -> String {
+    let output = quote_arg(&output.to_string_lossy());
+
+    match cfg!(windows) {
+        true => format!(
+            "cmd /c if exist {0} del /f /q {0} & {1}",
+            output,
+            shell(command)
+        ),
+        false => format!("rm -f {} && {}", output, shell(command)),
+    }
+}
+
+/// Quotes an argument of a command line ninja runs.
+fn quote_arg(arg: &str) -> String {
+    match cfg!(windows) {
+        true => quote_windows(arg),
+        false => quote(arg),
+    }
+}
+
+/// Quotes an argument for the shell when it has characters the shell would
+/// interpret.
+pub fn quote(arg: &str) -> String {
+    let plain = |ch: char| ch.is_ascii_alphanumeric() || "_-+=/.,:@%".contains(ch);
+
+    if !arg.is_empty() && arg.chars().all(plain) {
+        return arg.to_owned();
+    }
+
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Quotes an argument for a Windows command line, where backslashes are only
+/// special before a double quote.
+fn quote_windows(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_owned();
+    }
+
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+
+    for ch in arg.chars() {
+        match ch {
+            '\\' => backslashes += 1,
+            '"' => {
+                // The backslashes before a quote are doubled, and the quote
+                // itself escaped.
+                quoted.push_str(&"\\".repeat(backslashes + 1));
+                backslashes = 0;
+            }
+            _ => backslashes = 0,
+        }
+        quoted.push(ch);
+    }
+
+    // So are the ones before the closing quote.
+    quoted.push_str(&"\\".repeat(backslashes));
+    quoted.push('"');
+    quoted
+}
+
+fn escape_path(path: &Path) -> String {
+    escape(&path.to_string_lossy())
+}
+
+/// Escapes a path in a build line, where spaces and colons separate paths.
+fn escape(path: &str) -> String {
+    path.replace('$', "$$")
+        .replace(' ', "$ ")
+        .replace(':', "$:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_paths_and_commands() {
+        let mut command = process::Command::new("gcc");
+        command.args(["-c", "my file.c", "-DNAME='x'"]);
+
+        if cfg!(windows) {
+            assert_eq!(shell(&command), "gcc -c \"my file.c\" -DNAME='x'");
+        } else {
+            assert_eq!(shell(&command), "gcc -c 'my file.c' '-DNAME='\\''x'\\'''");
+        }
+        assert_eq!(quote_windows(r"C:\my dir\"), r#""C:\my dir\\""#);
+        assert_eq!(quote_windows(r#"-DNAME="x\""#), r#""-DNAME=\"x\\\"""#);
+        assert_eq!(escape("C:/my dir/$x"), "C$:/my$ dir/$$x");
+    }
+}
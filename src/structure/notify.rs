@@ -0,0 +1,66 @@
+use std::process::{self, Stdio};
+
+/// Sends a desktop notification with notify-send, osascript on macOS, or a
+/// toast through PowerShell on Windows. Notifications are best effort, a
+/// missing or failing tool is ignored.
+///
+/// # Arguments
+///
+/// * 'title' - The summary line.
+/// * 'body' - The text below it.
+///
+pub fn send(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = process::Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ));
+        command
+    } else if cfg!(windows) {
+        let mut command = process::Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command", TOAST])
+            .env("CEDAR_NOTIFY_TITLE", title)
+            .env("CEDAR_NOTIFY_BODY", body);
+        command
+    } else {
+        let mut command = process::Command::new("notify-send");
+        command.args(["--app-name", "cedar", title, body]);
+        command
+    };
+
+    let _ = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Shows a toast with the title and body passed in the environment, which
+/// avoids quoting them into the script.
+const TOAST: &str = "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+$xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+$text = $xml.GetElementsByTagName('text'); \
+$text.Item(0).AppendChild($xml.CreateTextNode($env:CEDAR_NOTIFY_TITLE)) > $null; \
+$text.Item(1).AppendChild($xml.CreateTextNode($env:CEDAR_NOTIFY_BODY)) > $null; \
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('cedar').Show([Windows.UI.Notifications.ToastNotification]::new($xml))";
+
+/// Quotes a string for AppleScript.
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_applescript() {
+        assert_eq!(
+            applescript_string(r#"built "app" in C:\x"#),
+            r#""built \"app\" in C:\\x""#
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+};
+
+use crate::error::CedarError;
+
+use super::{
+    build::{recursive_file_search, BuildError},
+    install::install,
+    pkgconfig::pkg_config,
+    project::{Project, ProjectError},
+    sha256,
+    target::artifact_name,
+};
+
+/// Packages a built project into build/package/<name>-<version>.tar.gz (or
+/// as artifact_name in [package] names it), holding what install would
+/// install under a prefix, in a <name>-<version>/ directory. The pkg-config file of a library locates the
+/// package wherever it is unpacked.
+///
+/// The archive only depends on the files packaged: entries are sorted, owned
+/// by root, with normalized permissions, and dated SOURCE_DATE_EPOCH (or the
+/// epoch), and gzip leaves out the name and time, so the same tree always
+/// packages to the same bytes.
+///
+/// # Arguments
+///
+/// * 'project' - The project, which must already be built.
+///
+/// # Returns
+///
+/// * The path of the archive and its SHA-256.
+///
+pub fn package(project: &Project) -> Result<(PathBuf, String), CedarError> {
+    let manifest = &project.manifest;
+    let template = manifest
+        .package
+        .artifact_name
+        .as_deref()
+        .unwrap_or("{name}-{version}");
+    let name = artifact_name(template, &manifest.meta.name, manifest)
+        .map_err(|placeholder| BuildError::InvalidArtifactName(template.to_owned(), placeholder))?;
+
+    let dir = project.build_dir.join("package");
+    let stage = dir.join(&name);
+
+    if stage.exists() {
+        fs::remove_dir_all(&stage).map_err(|err| CedarError::io(&stage, err))?;
+    }
+
+    let installed = install(project, &stage)?;
+
+    if let Some(pc) = installed
+        .iter()
+        .find(|file| file.extension().is_some_and(|extension| extension == "pc"))
+    {
+        fs::write(
+            pc,
+            pkg_config(&project.manifest, "${pcfiledir}/../..".as_ref(), "lib"),
+        )
+        .map_err(|err| CedarError::io(pc, err))?;
+    }
+
+    let mtime = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .unwrap_or(0);
+    let tar = tar(&dir, &name, mtime).map_err(|err| CedarError::io(&stage, err))?;
+
+    let archive = dir.join(format!("{}.tar.gz", name));
+    let file = fs::File::create(&archive).map_err(|err| CedarError::io(&archive, err))?;
+
+    let mut gzip = process::Command::new("gzip");
+    gzip.arg("-n").arg("-9").stdin(Stdio::piped()).stdout(file);
+
+    let mut child = gzip.spawn().map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => ProjectError::MissingTool("gzip").into(),
+        _ => CedarError::command(&gzip, err),
+    })?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(&tar)?;
+
+    if !child.wait()?.success() {
+        return Err(ProjectError::ToolFailed("gzip").into());
+    }
+
+    let contents = fs::read(&archive).map_err(|err| CedarError::io(&archive, err))?;
+
+    Ok((archive, sha256::digest(&contents)))
+}
+
+/// Writes a ustar archive of dir/name, with the entries in path order.
+fn tar(dir: &Path, name: &str, mtime: u64) -> Result<Vec<u8>, io::Error> {
+    let root = dir.join(name);
+    let mut paths: Vec<PathBuf> = recursive_file_search(&root)?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+    // Directories get entries of their own, so they unpack with the same
+    // permissions everywhere.
+    let mut directories: Vec<PathBuf> = paths
+        .iter()
+        .flat_map(|path| path.ancestors().skip(1))
+        .filter(|ancestor| ancestor.starts_with(&root))
+        .map(Path::to_path_buf)
+        .collect();
+    paths.append(&mut directories);
+    paths.sort();
+    paths.dedup();
+
+    let mut archive = Vec::new();
+
+    for path in paths {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        let entry_name = relative.to_string_lossy().replace('\\', "/");
+        let metadata = fs::symlink_metadata(&path)?;
+
+        if metadata.is_dir() {
+            archive.extend(header(
+                &format!("{}/", entry_name),
+                b'5',
+                0o755,
+                0,
+                "",
+                mtime,
+            )?);
+        } else if metadata.is_symlink() {
+            let target = fs::read_link(&path)?;
+            archive.extend(header(
+                &entry_name,
+                b'2',
+                0o777,
+                0,
+                &target.to_string_lossy(),
+                mtime,
+            )?);
+        } else {
+            let contents = fs::read(&path)?;
+            let mode = match executable(&metadata) {
+                true => 0o755,
+                false => 0o644,
+            };
+
+            archive.extend(header(
+                &entry_name,
+                b'0',
+                mode,
+                contents.len() as u64,
+                "",
+                mtime,
+            )?);
+            archive.extend(&contents);
+            archive.resize(archive.len().next_multiple_of(512), 0);
+        }
+    }
+
+    // The end of the archive is marked by two empty blocks.
+    archive.resize(archive.len() + 1024, 0);
+
+    Ok(archive)
+}
+
+/// The 512 byte ustar header of an entry, owned by uid and gid 0 without
+/// user or group names.
+fn header(
+    name: &str,
+    kind: u8,
+    mode: u32,
+    size: u64,
+    link: &str,
+    mtime: u64,
+) -> Result<[u8; 512], io::Error> {
+    let mut header = [0u8; 512];
+
+    // Names over 100 bytes are split at a / into a prefix and a name.
+    let (prefix, name) = match name.len() {
+        0..=100 => ("", name),
+        _ => name
+            .char_indices()
+            .filter(|(index, ch)| *ch == '/' && *index <= 155 && name.len() - index - 1 <= 100)
+            .map(|(index, _)| (&name[..index], &name[index + 1..]))
+            .find(|(_, rest)| !rest.is_empty())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is too long to archive", name),
+                )
+            })?,
+    };
+
+    if link.len() > 100 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("the link {} is too long to archive", link),
+        ));
+    }
+
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut header[100..108], mode as u64);
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], size);
+    octal(&mut header[136..148], mtime);
+    header[156] = kind;
+    header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    octal(&mut header[329..337], 0);
+    octal(&mut header[337..345], 0);
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // The checksum is computed with its own field as spaces.
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|byte| *byte as u64).sum();
+    octal(&mut header[148..155], checksum);
+
+    Ok(header)
+}
+
+/// Writes a number as zero padded octal, NUL terminated, filling the field.
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+#[cfg(unix)]
+fn executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_ustar_headers() {
+        let entry = header("app-1.0/bin/app", b'0', 0o755, 1234, "", 0).unwrap();
+
+        assert_eq!(&entry[..16], b"app-1.0/bin/app\0");
+        assert_eq!(&entry[100..108], b"0000755\0");
+        assert_eq!(&entry[124..136], b"00000002322\0");
+        assert_eq!(&entry[257..265], b"ustar\x0000");
+
+        let checksum: u64 = entry
+            .iter()
+            .enumerate()
+            .map(|(index, byte)| match index {
+                148..156 => b' ' as u64,
+                _ => *byte as u64,
+            })
+            .sum();
+        assert_eq!(&entry[148..155], format!("{:06o}\0", checksum).as_bytes());
+
+        let long = format!("app-1.0/include/{}/deep.h", "nested".repeat(20));
+        let entry = header(&long, b'0', 0o644, 0, "", 0).unwrap();
+        assert_eq!(&entry[..7], b"deep.h\0");
+        assert!(entry[345..].starts_with(long.trim_end_matches("/deep.h").as_bytes()));
+    }
+}
@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use super::manifest::Manifest;
+
+/// Renders a pkg-config file for a library project.
+///
+/// # Arguments
+///
+/// * 'manifest' - The manifest of the project, used for the name, version,
+///   description, and private link flags.
+/// * 'prefix' - The prefix the library is found under.
+/// * 'libdir' - The directory holding the libraries, relative to the prefix.
+///
+pub fn pkg_config(manifest: &Manifest, prefix: &Path, libdir: &str) -> String {
+    let meta = &manifest.meta;
+
+    // Flags the library itself links with are only needed by consumers that
+    // link it statically.
+    let private: Vec<&str> = manifest
+        .build
+        .ldflags
+        .iter()
+        .map(String::as_str)
+        .filter(|flag| flag.starts_with("-l") || flag.starts_with("-L"))
+        .collect();
+
+    let mut pc = format!(
+        "prefix={}\nexec_prefix=${{prefix}}\nlibdir=${{exec_prefix}}/{}\nincludedir=${{prefix}}/include\n\n",
+        prefix.display(),
+        libdir
+    );
+
+    pc.push_str(&format!("Name: {}\n", meta.name));
+    pc.push_str(&format!(
+        "Description: {}\n",
+        meta.description.as_deref().unwrap_or(&meta.name)
+    ));
+    pc.push_str(&format!("Version: {}\n", meta.version));
+    pc.push_str("Cflags: -I${includedir}\n");
+
+    // A header-only library has nothing to link of its own.
+    if !meta.kind.is_header_only() {
+        pc.push_str(&format!("Libs: -L${{libdir}} -l{}\n", meta.name));
+    }
+
+    if !private.is_empty() {
+        pc.push_str(&format!("Libs.private: {}\n", private.join(" ")));
+    }
+
+    pc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::pkg_config;
+    use crate::structure::manifest::{Manifest, ProjectKind};
+
+    #[test]
+    fn test_pkg_config() {
+        let mut manifest = Manifest::new();
+        manifest.meta.name = String::from("foo");
+        manifest.build.ldflags = vec![String::from("-lm"), String::from("-pthread")];
+
+        let pc = pkg_config(&manifest, Path::new("/usr/local"), "lib");
+
+        assert!(pc.starts_with("prefix=/usr/local\n"));
+        assert!(pc.contains("Libs: -L${libdir} -lfoo\n"));
+        assert!(pc.contains("Libs.private: -lm\n"));
+    }
+
+    #[test]
+    fn header_only_links_nothing() {
+        let mut manifest = Manifest::new();
+        manifest.meta.name = String::from("foo");
+        manifest.meta.kind = ProjectKind::HeaderOnly;
+        manifest.build.ldflags = vec![String::from("-lm")];
+
+        let pc = pkg_config(&manifest, Path::new("/usr/local"), "lib");
+
+        assert!(pc.contains("Cflags: -I${includedir}\n"));
+        assert!(!pc.contains("Libs:"));
+        assert!(pc.contains("Libs.private: -lm\n"));
+    }
+}
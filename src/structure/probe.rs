@@ -0,0 +1,224 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{config::Config, sysdeps};
+
+/// What probing a compiler found, by the name of the probe, along with
+/// the modification time of the compiler when it was probed.
+///
+/// # Fields
+///
+/// * 'modified' - The modification time of the compiler, in nanoseconds
+///   since the epoch. An upgrade changes it, and the results no
+///   longer hold.
+/// * 'results' - The output of each probe, None when it failed.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct CompilerProbes {
+    modified: u64,
+    results: BTreeMap<String, Option<String>>,
+}
+
+/// The probes of every compiler by its path, loaded from
+/// <data dir>/probes.json on first use.
+static PROBES: Mutex<Option<BTreeMap<String, CompilerProbes>>> = Mutex::new(None);
+
+/// Probes a compiler once, e.g. for its version or whether it takes a flag,
+/// and keeps the result in the user data directory, so later builds reuse
+/// it until the compiler changes. A program not on the PATH is probed
+/// every time.
+///
+/// # Arguments
+///
+/// * 'program' - The compiler, by name or path.
+/// * 'name' - The name of the probe, unique among the probes.
+/// * 'run' - Runs the probe on the compiler.
+///
+/// # Returns
+///
+/// * What the probe found, None when it failed.
+///
+pub fn probe(
+    program: &str,
+    name: &str,
+    run: impl FnOnce(&Path) -> Option<String>,
+) -> Option<String> {
+    let cache = cache_path();
+    let mut probes = PROBES.lock().unwrap_or_else(|err| err.into_inner());
+    let probes = probes.get_or_insert_with(|| load(cache.as_deref()));
+
+    cached(probes, cache.as_deref(), program, name, run)
+}
+
+/// Probes a compiler unless the probes, kept in the cache file, hold what
+/// it found since it last changed.
+fn cached(
+    probes: &mut BTreeMap<String, CompilerProbes>,
+    cache: Option<&Path>,
+    program: &str,
+    name: &str,
+    run: impl FnOnce(&Path) -> Option<String>,
+) -> Option<String> {
+    let Some((path, modified)) = resolve(program) else {
+        return run(Path::new(program));
+    };
+    let key = path.display().to_string();
+
+    if let Some(result) = probes
+        .get(&key)
+        .filter(|compiler| compiler.modified == modified)
+        .and_then(|compiler| compiler.results.get(name))
+    {
+        return result.clone();
+    }
+
+    // Run by the name it was given, gcc names itself after argv[0].
+    let result = run(Path::new(program));
+
+    let compiler = probes.entry(key).or_default();
+    if compiler.modified != modified {
+        *compiler = CompilerProbes {
+            modified,
+            results: BTreeMap::new(),
+        };
+    }
+    compiler.results.insert(name.to_owned(), result.clone());
+
+    // A cache that cannot be written only costs the next build a probe.
+    if let Some(cache) = cache {
+        let _ = save(probes, cache);
+    }
+
+    result
+}
+
+/// The --version output of a compiler.
+pub fn version(program: &str) -> Option<String> {
+    probe(program, "version", |cc| {
+        process::Command::new(cc)
+            .arg("--version")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+    })
+}
+
+/// The absolute path of a program, found on the PATH unless it is a path
+/// already, and the modification time of what it links to.
+fn resolve(program: &str) -> Option<(PathBuf, u64)> {
+    let path = match program.contains(['/', '\\']) {
+        true => Path::new(program).canonicalize().ok()?,
+        false => sysdeps::which(program)?,
+    };
+
+    let modified = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+
+    Some((path, u64::try_from(modified).ok()?))
+}
+
+fn cache_path() -> Option<PathBuf> {
+    Some(Config::data_dir()?.join("probes.json"))
+}
+
+/// The probes kept in the user data directory, none when there are none
+/// yet or they cannot be read.
+fn load(cache: Option<&Path>) -> BTreeMap<String, CompilerProbes> {
+    cache
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Writes the probes through a temporary file, so cedar running in parallel
+/// never reads half a file.
+fn save(probes: &BTreeMap<String, CompilerProbes>, path: &Path) -> Option<()> {
+    fs::create_dir_all(path.parent()?).ok()?;
+
+    let temporary = path.with_extension(format!("json.{}", process::id()));
+    fs::write(&temporary, serde_json::to_string_pretty(probes).ok()?).ok()?;
+    fs::rename(&temporary, path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, env, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn reads_cached_probes() {
+        let probes: BTreeMap<String, CompilerProbes> = serde_json::from_str(
+            r#"{"/usr/bin/gcc": {"modified": 1700000000000000000, "results": {"dumpmachine": "x86_64-linux-gnu", "json-diagnostics": null}}}"#,
+        )
+        .unwrap();
+
+        let gcc = &probes["/usr/bin/gcc"];
+        assert_eq!(gcc.modified, 1_700_000_000_000_000_000);
+        assert_eq!(
+            gcc.results["dumpmachine"].as_deref(),
+            Some("x86_64-linux-gnu")
+        );
+        assert_eq!(gcc.results["json-diagnostics"], None);
+        assert!(!gcc.results.contains_key("version"));
+    }
+
+    #[test]
+    fn reuses_probes_until_the_compiler_changes() {
+        let dir = env::temp_dir().join(format!("cedar-probes-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let compiler = dir.join("cc");
+        fs::write(&compiler, "").unwrap();
+        let cache = dir.join("data").join("probes.json");
+        let program = compiler.to_str().unwrap();
+
+        let runs = Cell::new(0);
+        let run = |_: &Path| {
+            runs.set(runs.get() + 1);
+            Some(String::from("x86_64-linux-gnu"))
+        };
+
+        let mut probes = load(Some(&cache));
+        assert_eq!(
+            cached(&mut probes, Some(&cache), program, "dumpmachine", run).as_deref(),
+            Some("x86_64-linux-gnu")
+        );
+        assert_eq!(runs.get(), 1);
+
+        // A later build reads what the first one found.
+        let mut probes = load(Some(&cache));
+        assert_eq!(
+            cached(&mut probes, Some(&cache), program, "dumpmachine", run).as_deref(),
+            Some("x86_64-linux-gnu")
+        );
+        assert_eq!(runs.get(), 1);
+
+        // An upgrade of the compiler changes its modification time.
+        let later = fs::metadata(&compiler).unwrap().modified().unwrap() + Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&compiler)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        let mut probes = load(Some(&cache));
+        cached(&mut probes, Some(&cache), program, "dumpmachine", run);
+        assert_eq!(runs.get(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,740 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, process, str::FromStr};
+
+use crate::{cli::CliError, error::CedarError, style};
+
+use super::{
+    build::{build, BuildOptions},
+    project::{Project, ProjectError},
+    target::{Target, TargetFilter, TargetKind},
+};
+
+/// The profilers cedar profile can run a program under.
+///
+/// # Members
+///
+/// * 'Perf' - Samples with perf record, rendered into a flamegraph. When
+///   perf is missing or not permitted to record, the program is built
+///   with -pg and sampled by gprof instead.
+/// * 'Callgrind' - Counts instructions per function with valgrind's
+///   callgrind.
+/// * 'Cachegrind' - Counts instructions and cache misses per function with
+///   valgrind's cachegrind.
+/// * 'Massif' - Measures the heap with valgrind's massif, reporting the peak
+///   and the allocation sites holding it.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfileTool {
+    #[default]
+    Perf,
+    Callgrind,
+    Cachegrind,
+    Massif,
+}
+
+impl ProfileTool {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Perf => "perf",
+            Self::Callgrind => "callgrind",
+            Self::Cachegrind => "cachegrind",
+            Self::Massif => "massif",
+        }
+    }
+}
+
+impl FromStr for ProfileTool {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "perf" => Ok(Self::Perf),
+            "callgrind" => Ok(Self::Callgrind),
+            "cachegrind" => Ok(Self::Cachegrind),
+            "massif" => Ok(Self::Massif),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, --tool expects perf, callgrind, cachegrind, or massif.",
+                s
+            ))),
+        }
+    }
+}
+
+/// The result of profiling a program.
+///
+/// # Fields
+///
+/// * 'data' - The raw profile the tool recorded.
+/// * 'flamegraph' - The flamegraph rendered from it, perf only.
+/// * 'hottest' - The functions the program spent the most time in (not
+///   counting the functions they call), with their share of the
+///   samples or instructions, hottest first. With massif, the
+///   allocation sites with their share of the peak heap.
+/// * 'peak_heap' - The peak heap usage in bytes, massif only.
+///
+#[derive(Debug, Clone)]
+pub struct ProfileReport {
+    pub data: PathBuf,
+    pub flamegraph: Option<PathBuf>,
+    pub hottest: Vec<(String, f64)>,
+    pub peak_heap: Option<u64>,
+}
+
+/// Builds a program with debug info and frame pointers into build/profile/
+/// and runs it under a profiler.
+///
+/// With perf the samples are rendered into build/profile/flamegraph.svg,
+/// gprof samples too when perf cannot record, with the valgrind tools the output is kept as build/profile/<tool>.out
+/// for tools like kcachegrind and massif-visualizer.
+///
+/// # Arguments
+///
+/// * 'project' - The project holding the program.
+/// * 'target' - The bin or example to profile.
+/// * 'tool' - The profiler to use.
+///
+pub fn profile(
+    project: &Project,
+    target: &Target,
+    tool: ProfileTool,
+) -> Result<ProfileReport, CedarError> {
+    let sampled = tool == ProfileTool::Perf && !perf_permitted();
+
+    if sampled {
+        println!(
+            "\t{}perf cannot record here, sampling with gprof instead",
+            style::bold("Warning ")
+        );
+    }
+
+    let flags: &[&str] = match sampled {
+        true => &["-g", "-fno-omit-frame-pointer", "-pg"],
+        false => &["-g", "-fno-omit-frame-pointer"],
+    };
+    let profiled = profile_build(project, target, flags)?;
+
+    match tool {
+        ProfileTool::Perf if sampled => gprof(project, &profiled, target),
+        ProfileTool::Perf => perf(project, &profiled, target),
+        ProfileTool::Callgrind | ProfileTool::Cachegrind | ProfileTool::Massif => {
+            valgrind(project, &profiled, target, tool)
+        }
+    }
+}
+
+/// Samples a program with perf and renders a flamegraph.
+fn perf(
+    project: &Project,
+    profiled: &Project,
+    target: &Target,
+) -> Result<ProfileReport, CedarError> {
+    let dir = &profiled.build_dir;
+    let data = dir.join("perf.data");
+
+    let mut record = process::Command::new("perf");
+    record
+        .args(["record", "-F", "999", "-g", "-o"])
+        .arg(&data)
+        .arg("--")
+        .arg(profiled.output(target))
+        .current_dir(&project.root);
+
+    let status = record
+        .status()
+        .map_err(|err| CedarError::command(&record, err))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed("perf").into());
+    }
+
+    let mut script = process::Command::new("perf");
+    script.arg("script").arg("-i").arg(&data);
+
+    let output = script
+        .output()
+        .map_err(|err| CedarError::command(&script, err))?;
+
+    if !output.status.success() {
+        return Err(ProjectError::ToolFailed("perf").into());
+    }
+
+    let stacks = collapse_perf(&String::from_utf8_lossy(&output.stdout));
+
+    let flamegraph = dir.join("flamegraph.svg");
+    fs::write(&flamegraph, flamegraph_svg(&stacks, &target.name))
+        .map_err(|err| CedarError::io(&flamegraph, err))?;
+
+    Ok(ProfileReport {
+        data,
+        flamegraph: Some(flamegraph),
+        hottest: hottest(&stacks),
+        peak_heap: None,
+    })
+}
+
+/// Whether perf is installed and allowed to record, which
+/// kernel.perf_event_paranoid may forbid.
+fn perf_permitted() -> bool {
+    process::Command::new("perf")
+        .args(["record", "-q", "-o", "/dev/null", "--", "true"])
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Samples a program with gprof, built with -pg, and renders a flamegraph.
+/// gprof only tells which function each sample hit, so the stacks of the
+/// flamegraph are the functions alone, without their callers.
+fn gprof(
+    project: &Project,
+    profiled: &Project,
+    target: &Target,
+) -> Result<ProfileReport, CedarError> {
+    let dir = &profiled.build_dir;
+    let program = profiled.output(target);
+
+    // The program writes its samples to gmon.<pid> when it exits.
+    let mut run = process::Command::new(&program);
+    run.env("GMON_OUT_PREFIX", dir.join("gmon"))
+        .current_dir(&project.root);
+
+    let mut child = run.spawn().map_err(|err| CedarError::command(&run, err))?;
+    let data = dir.join(format!("gmon.{}", child.id()));
+    child.wait().map_err(|err| CedarError::command(&run, err))?;
+
+    let mut report = process::Command::new("gprof");
+    report.args(["-b", "-p"]).arg(&program).arg(&data);
+
+    let output = report
+        .output()
+        .map_err(|err| CedarError::command(&report, err))?;
+
+    if !output.status.success() {
+        return Err(ProjectError::ToolFailed("gprof").into());
+    }
+
+    let stacks = gprof_samples(&String::from_utf8_lossy(&output.stdout));
+
+    let flamegraph = dir.join("flamegraph.svg");
+    fs::write(&flamegraph, flamegraph_svg(&stacks, &target.name))
+        .map_err(|err| CedarError::io(&flamegraph, err))?;
+
+    Ok(ProfileReport {
+        data,
+        flamegraph: Some(flamegraph),
+        hottest: hottest(&stacks),
+        peak_heap: None,
+    })
+}
+
+/// Runs a program under one of the valgrind tools.
+fn valgrind(
+    project: &Project,
+    profiled: &Project,
+    target: &Target,
+    tool: ProfileTool,
+) -> Result<ProfileReport, CedarError> {
+    let data = profiled.build_dir.join(format!("{}.out", tool.as_str()));
+
+    let mut command = process::Command::new("valgrind");
+    command
+        .arg(format!("--tool={}", tool.as_str()))
+        .arg(format!("--{}-out-file={}", tool.as_str(), data.display()))
+        .arg(profiled.output(target))
+        .current_dir(&project.root);
+
+    let status = command
+        .status()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed("valgrind").into());
+    }
+
+    let contents = fs::read_to_string(&data).map_err(|err| CedarError::io(&data, err))?;
+
+    if tool == ProfileTool::Massif {
+        let (peak, sites) = massif_peak(&contents);
+
+        return Ok(ProfileReport {
+            data,
+            flamegraph: None,
+            hottest: sites
+                .into_iter()
+                .map(|(site, bytes)| (site, bytes as f64 * 100.0 / peak.max(1) as f64))
+                .collect(),
+            peak_heap: Some(peak),
+        });
+    }
+
+    let costs = valgrind_costs(&contents);
+    let total: u64 = costs.values().sum();
+
+    let mut hottest: Vec<(String, f64)> = costs
+        .into_iter()
+        .map(|(function, cost)| (function, cost as f64 * 100.0 / total.max(1) as f64))
+        .collect();
+
+    hottest.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    Ok(ProfileReport {
+        data,
+        flamegraph: None,
+        hottest,
+        peak_heap: None,
+    })
+}
+
+/// Finds the peak snapshot of a massif output file.
+///
+/// # Returns
+///
+/// * The peak heap usage in bytes, and the allocation sites directly below
+///   the allocation functions with the bytes they held at the peak,
+///   largest first.
+///
+pub fn massif_peak(contents: &str) -> (u64, Vec<(String, u64)>) {
+    let mut peak = 0;
+    let mut sites = Vec::new();
+
+    // Snapshots start with snapshot=N, the peak one has heap_tree=peak
+    // followed by its allocation tree.
+    let mut heap = 0;
+    let mut in_peak = false;
+
+    for line in contents.lines() {
+        if line.starts_with("snapshot=") {
+            heap = 0;
+            in_peak = false;
+        } else if let Some(bytes) = line.strip_prefix("mem_heap_B=") {
+            heap = bytes.trim().parse().unwrap_or(0);
+        } else if line == "heap_tree=peak" {
+            peak = heap;
+            sites.clear();
+            in_peak = true;
+        } else if in_peak && line.starts_with(" n") {
+            // Direct children of the root are indented by one space:
+            // " n0: 600 0x10916F: make_buffer (app.c:5)".
+            if line.starts_with("  ") {
+                continue;
+            }
+
+            let Some((_, entry)) = line.trim_start().split_once(": ") else {
+                continue;
+            };
+
+            let Some((bytes, site)) = entry.split_once(' ') else {
+                continue;
+            };
+
+            let site = match site.split_once(": ") {
+                Some((address, site)) if address.starts_with("0x") => site,
+                _ => site,
+            };
+
+            sites.push((site.to_owned(), bytes.parse().unwrap_or(0)));
+        }
+    }
+
+    sites.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    (peak, sites)
+}
+
+/// Sums the self cost (of the first event, instructions by default) of
+/// every function in a callgrind or cachegrind output file.
+pub fn valgrind_costs(contents: &str) -> BTreeMap<String, u64> {
+    let mut costs = BTreeMap::new();
+
+    // Callgrind compresses repeated names to "(id)" after their first use.
+    let mut names: BTreeMap<String, String> = BTreeMap::new();
+    let mut function: Option<String> = None;
+    let mut after_calls = false;
+
+    for line in contents.lines() {
+        if let Some(name) = line.strip_prefix("fn=") {
+            function = Some(expand_name(name, &mut names));
+            continue;
+        }
+
+        // The line after calls= holds the inclusive cost of the call, which
+        // belongs to the callee.
+        if line.starts_with("calls=") {
+            after_calls = true;
+            continue;
+        }
+
+        let position = line.starts_with(|ch: char| ch.is_ascii_digit() || "+-*".contains(ch));
+
+        if !position {
+            // cfn= names compress like fn= names.
+            if let Some(name) = line.strip_prefix("cfn=") {
+                expand_name(name, &mut names);
+            }
+            continue;
+        }
+
+        if std::mem::take(&mut after_calls) {
+            continue;
+        }
+
+        let (Some(function), Some(cost)) = (&function, line.split_whitespace().nth(1)) else {
+            continue;
+        };
+
+        *costs.entry(function.clone()).or_insert(0) += cost.parse::<u64>().unwrap_or(0);
+    }
+
+    costs
+}
+
+/// Resolves a possibly compressed "(id) name" or "(id)" function name.
+fn expand_name(name: &str, names: &mut BTreeMap<String, String>) -> String {
+    let Some(rest) = name.strip_prefix('(') else {
+        return name.to_owned();
+    };
+
+    let Some((id, name)) = rest.split_once(')') else {
+        return name.to_owned();
+    };
+
+    let name = name.trim();
+
+    if name.is_empty() {
+        return names.get(id).cloned().unwrap_or_else(|| id.to_owned());
+    }
+
+    names.insert(id.to_owned(), name.to_owned());
+    name.to_owned()
+}
+
+/// Builds a target with extra compiler flags, which it also links with,
+/// into build/profile/, apart from the regular objects.
+///
+/// # Returns
+///
+/// * The project as built, its build_dir and output point to build/profile/.
+///
+fn profile_build(
+    project: &Project,
+    target: &Target,
+    flags: &[&str],
+) -> Result<Project, CedarError> {
+    let mut profiled = project.clone();
+    profiled.build_dir = project.build_dir.join("profile");
+
+    let build_flags = &mut profiled.manifest.build;
+    for list in [
+        &mut build_flags.cflags,
+        &mut build_flags.cxxflags,
+        &mut build_flags.ldflags,
+    ] {
+        list.extend(flags.iter().map(|flag| flag.to_string()));
+    }
+
+    fs::create_dir_all(&profiled.build_dir)
+        .map_err(|err| CedarError::io(&profiled.build_dir, err))?;
+
+    let options = BuildOptions {
+        targets: vec![match target.kind {
+            TargetKind::Example => TargetFilter::Example(target.name.clone()),
+            _ => TargetFilter::Bin(target.name.clone()),
+        }],
+        ..Default::default()
+    };
+
+    build(&profiled, &options)?;
+
+    Ok(profiled)
+}
+
+/// Folds the samples printed by `perf script` into stacks, outermost frame
+/// first and joined by ';', with how many samples had each stack.
+pub fn collapse_perf(script: &str) -> BTreeMap<String, usize> {
+    let mut stacks = BTreeMap::new();
+    let mut frames: Vec<String> = Vec::new();
+
+    // Samples are a header line followed by one line per frame, innermost
+    // first, and end with an empty line.
+    for line in script.lines().chain([""]) {
+        if line.trim().is_empty() {
+            if !frames.is_empty() {
+                frames.reverse();
+                *stacks.entry(frames.join(";")).or_insert(0) += 1;
+                frames.clear();
+            }
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        // "  55d4c3a1b149 util+0x9 (/path/to/binary)"
+        let Some((_, symbol)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+
+        let symbol = symbol
+            .rsplit_once(" (")
+            .map_or(symbol, |(symbol, _)| symbol);
+        let symbol = symbol
+            .rsplit_once("+0x")
+            .map_or(symbol, |(symbol, _)| symbol);
+
+        frames.push(symbol.trim().replace(';', ":"));
+    }
+
+    stacks
+}
+
+/// The samples of every function in the flat profile printed by `gprof -p`,
+/// from the seconds spent in the function itself.
+pub fn gprof_samples(flat: &str) -> BTreeMap<String, usize> {
+    let mut samples = BTreeMap::new();
+    let mut period = 0.01;
+
+    for line in flat.lines() {
+        // "Each sample counts as 0.01 seconds."
+        if let Some(rest) = line.trim().strip_prefix("Each sample counts as ") {
+            if let Some(seconds) = rest.split_whitespace().next() {
+                period = seconds.parse().unwrap_or(period);
+            }
+            continue;
+        }
+
+        // "%time cumulative self [calls self/call total/call] name", the
+        // calls are blank for functions not built with -pg.
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let numbers = fields
+            .iter()
+            .take(6)
+            .take_while(|field| field.parse::<f64>().is_ok())
+            .count();
+
+        if numbers < 3 || numbers == fields.len() {
+            continue;
+        }
+
+        let seconds: f64 = fields[2].parse().unwrap_or(0.0);
+        let count = (seconds / period).round() as usize;
+
+        if count > 0 {
+            *samples
+                .entry(fields[numbers..].join(" ").replace(';', ":"))
+                .or_insert(0) += count;
+        }
+    }
+
+    samples
+}
+
+/// The functions with the most samples at the top of the stack, with their
+/// share of all samples.
+pub fn hottest(stacks: &BTreeMap<String, usize>) -> Vec<(String, f64)> {
+    let total: usize = stacks.values().sum();
+    let mut functions: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for (stack, count) in stacks {
+        let leaf = stack.rsplit(';').next().unwrap_or(stack);
+        *functions.entry(leaf).or_insert(0) += count;
+    }
+
+    let mut hottest: Vec<(String, f64)> = functions
+        .into_iter()
+        .map(|(function, count)| (function.to_owned(), count as f64 * 100.0 / total as f64))
+        .collect();
+
+    hottest.sort_by(|a, b| b.1.total_cmp(&a.1));
+    hottest
+}
+
+/// A frame of the flamegraph and the frames called from it.
+#[derive(Default)]
+struct Frame {
+    samples: usize,
+    children: BTreeMap<String, Frame>,
+}
+
+const SVG_WIDTH: f64 = 1200.0;
+const FRAME_HEIGHT: f64 = 16.0;
+
+/// Renders folded stacks as a flamegraph: every frame is a box as wide as
+/// its share of the samples, stacked on top of its caller.
+pub fn flamegraph_svg(stacks: &BTreeMap<String, usize>, title: &str) -> String {
+    let mut root = Frame::default();
+
+    for (stack, count) in stacks {
+        root.samples += count;
+        let mut frame = &mut root;
+
+        for function in stack.split(';') {
+            frame = frame.children.entry(function.to_owned()).or_default();
+            frame.samples += count;
+        }
+    }
+
+    let depth = max_depth(&root);
+    let height = (depth + 2) as f64 * FRAME_HEIGHT + 20.0;
+
+    let mut svg = format!(
+        "<?xml version=\"1.0\" standalone=\"no\"?>\n<svg version=\"1.1\" width=\"{0}\" height=\"{1}\" xmlns=\"http://www.w3.org/2000/svg\" font-family=\"monospace\" font-size=\"11\">\n<rect width=\"100%\" height=\"100%\" fill=\"#f8f8f8\"/>\n<text x=\"{2}\" y=\"16\" text-anchor=\"middle\" font-size=\"14\">{3}</text>\n",
+        SVG_WIDTH,
+        height,
+        SVG_WIDTH / 2.0,
+        escape(&format!("Flamegraph of {}", title))
+    );
+
+    let scale = match root.samples {
+        0 => 0.0,
+        samples => SVG_WIDTH / samples as f64,
+    };
+
+    render_frame(
+        &mut svg,
+        "all",
+        &root,
+        0.0,
+        height - FRAME_HEIGHT,
+        scale,
+        root.samples,
+    );
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn max_depth(frame: &Frame) -> usize {
+    frame
+        .children
+        .values()
+        .map(|child| max_depth(child) + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Draws a frame at the given position and its children above it.
+fn render_frame(
+    svg: &mut String,
+    name: &str,
+    frame: &Frame,
+    x: f64,
+    y: f64,
+    scale: f64,
+    total: usize,
+) {
+    let width = frame.samples as f64 * scale;
+
+    // Frames narrower than a pixel are invisible anyway.
+    if width < 1.0 {
+        return;
+    }
+
+    let percent = frame.samples as f64 * 100.0 / total.max(1) as f64;
+    let label: String = name.chars().take((width / 7.0) as usize).collect();
+
+    svg.push_str(&format!(
+        "<g><title>{0} ({1} samples, {2:.2}%)</title><rect x=\"{3:.1}\" y=\"{4:.1}\" width=\"{5:.1}\" height=\"{6}\" fill=\"{7}\" rx=\"2\"/><text x=\"{8:.1}\" y=\"{9:.1}\">{10}</text></g>\n",
+        escape(name),
+        frame.samples,
+        percent,
+        x,
+        y,
+        width,
+        FRAME_HEIGHT - 1.0,
+        color(name),
+        x + 3.0,
+        y + FRAME_HEIGHT - 4.0,
+        escape(&label)
+    ));
+
+    let mut offset = x;
+
+    for (child_name, child) in &frame.children {
+        render_frame(
+            svg,
+            child_name,
+            child,
+            offset,
+            y - FRAME_HEIGHT,
+            scale,
+            total,
+        );
+        offset += child.samples as f64 * scale;
+    }
+}
+
+/// A warm color derived from the function name, so a function keeps its
+/// color across runs.
+fn color(name: &str) -> String {
+    let hash = name
+        .bytes()
+        .fold(5381u32, |hash, byte| hash.wrapping_mul(33) ^ byte as u32);
+
+    format!(
+        "rgb({},{},{})",
+        205 + hash % 50,
+        80 + (hash >> 8) % 130,
+        (hash >> 16) % 55
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_perf_script() {
+        let script = "app  4242 100.000001:     250000 cpu-clock:\n\t    1149 util+0x9 (/p/app)\n\t    1170 main+0x17 (/p/app)\n\napp  4242 100.000002:     250000 cpu-clock:\n\t    1170 main+0x20 (/p/app)\n\napp  4242 100.000003:     250000 cpu-clock:\n\t    1149 util+0x3 (/p/app)\n\t    1170 main+0x17 (/p/app)\n";
+
+        let stacks = collapse_perf(script);
+
+        assert_eq!(stacks.get("main;util"), Some(&2));
+        assert_eq!(stacks.get("main"), Some(&1));
+        assert_eq!(hottest(&stacks)[0].0, "util");
+    }
+
+    #[test]
+    fn reads_gprof_samples() {
+        let flat = "Flat profile:\n\nEach sample counts as 0.01 seconds.\n  %   cumulative   self              self     total           \n time   seconds   seconds    calls  ms/call  ms/call  name    \n 71.55      0.25     0.25        1   250.41   250.41  busy\n 28.62      0.35     0.10                             frame_dummy\n  0.00      0.35     0.00        1     0.00     0.00  idle\n";
+
+        let stacks = gprof_samples(flat);
+
+        assert_eq!(stacks.get("busy"), Some(&25));
+        assert_eq!(stacks.get("frame_dummy"), Some(&10));
+        assert_eq!(stacks.get("idle"), None);
+        assert_eq!(hottest(&stacks)[0].0, "busy");
+    }
+
+    #[test]
+    fn sums_callgrind_costs() {
+        let contents = "events: Ir\nfl=(1) app.c\nfn=(1) main\n3 10\ncfn=(2) util\ncalls=1 8\n4 500\n+1 5\nfn=(2)\n8 400\n";
+
+        let costs = valgrind_costs(contents);
+
+        assert_eq!(costs.get("main"), Some(&15));
+        assert_eq!(costs.get("util"), Some(&400));
+    }
+
+    #[test]
+    fn finds_massif_peak() {
+        let contents = "snapshot=0\nmem_heap_B=0\nheap_tree=empty\nsnapshot=1\nmem_heap_B=1000\nheap_tree=peak\nn2: 1000 (heap allocation functions) malloc/new/new[], --alloc-fns, etc.\n n1: 600 0x10916F: make_buffer (app.c:5)\n  n0: 600 0x1091A0: main (app.c:12)\n n0: 400 0x109180: make_table (app.c:8)\nsnapshot=2\nmem_heap_B=200\nheap_tree=empty\n";
+
+        let (peak, sites) = massif_peak(contents);
+
+        assert_eq!(peak, 1000);
+        assert_eq!(
+            sites,
+            vec![
+                ("make_buffer (app.c:5)".to_owned(), 600),
+                ("make_table (app.c:8)".to_owned(), 400)
+            ]
+        );
+    }
+}
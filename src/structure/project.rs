@@ -1,29 +1,94 @@
-use std::{error::Error, fmt::Display, io};
+use std::{
+    error::Error,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::error::CedarError;
+
+use super::{
+    bench::{bench, BenchOptions, BenchResult},
+    build::{build, find_sources, target_triple, BuildError, BuildOptions, BuildReport, Language},
+    coverage::{coverage, CoverageReport},
+    deps::{resolve, Package},
+    init::init,
+    manifest::{Manifest, Profile, ProjectKind, Symlinks},
+    target::{self, Target, TargetFilter, TargetKind},
+    test::{test, TestOptions, TestReport},
+    trace,
+};
 
 /// A custom error type for errors when building a project.
 ///
 /// # Members
 ///
 /// * 'NoManifest' - Used when there is no manifest in the directory given.
+/// * 'InvalidManifest' - Used when the manifest cannot be parsed or created.
 /// * 'InvalidPath' - Used when the given path does not exist. Holds a String
-///         primarily to give the path that caused the error, however, a String
-///         is used to give flexibility to the information passed.
+///   primarily to give the path that caused the error, however, a String
+///   is used to give flexibility to the information passed.
 /// * 'IoError' - A wrapper for std::io::Error to allow for error propogation
-///         within functions that return ProjectError without using a Box.
+///   within functions that return ProjectError without using a Box.
 /// * 'NonEmptyPath' - Used when the path given is not empty, this will likely
-///         be handled by asking the user to confirm overwriting the directory.
+///   be handled by asking the user to confirm overwriting the directory.
+/// * 'InvalidConfig' - Used when the global config file cannot be parsed.
+/// * 'UnknownLicense' - Used when no template exists for an SPDX identifier.
+/// * 'AlreadyExists' - Used when a file that would be generated already exists.
+/// * 'MissingTool' - Used when an external program cedar needs is not installed.
+/// * 'ToolFailed' - Used when an external program exits unsuccessfully.
+/// * 'InvalidVersion' - Used when a version or version requirement cannot be
+///   parsed.
+/// * 'InvalidDependency' - Used when a dependency cannot be resolved, holds
+///   what went wrong.
+/// * 'ChecksumMismatch' - Used when the sources of a dependency do not match
+///   the checksum in cedar.lock.
+/// * 'BadSignature' - Used when the signature of a file does not match it or
+///   the public key.
+/// * 'UnknownHook' - Used when [hooks.git] names a hook git does not run.
+/// * 'UnknownTask' - Used when a task, or a task depended on, is not in
+///   [tasks], holds its name and the tasks there are.
+/// * 'TaskCycle' - Used when tasks depend on each other, holds the cycle.
+/// * 'InvalidDuration' - Used when a timeout cannot be parsed, holds it.
+/// * 'InvalidSize' - Used when a size in bytes cannot be parsed, holds it.
+/// * 'UnknownFeature' - Used when a feature picked, or a default one, is not
+///   in [features], holds its name and the features there are.
+/// * 'NotALibrary' - Used when a command that only makes sense for a library
+///   runs on a bin project, holds the command.
 ///
 #[derive(Debug)]
 pub enum ProjectError {
+    NoManifest(String),
     InvalidManifest,
     InvalidPath(String),
     IoError(io::Error),
     NonEmptyPath(String),
+    InvalidConfig(String),
+    UnknownLicense(String),
+    AlreadyExists(String),
+    MissingTool(&'static str),
+    ToolFailed(&'static str),
+    InvalidVersion(String),
+    InvalidDependency(String),
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    BadSignature(String),
+    UnknownHook(String),
+    UnknownTask(String, Vec<String>),
+    TaskCycle(Vec<String>),
+    InvalidDuration(String),
+    InvalidSize(String),
+    UnknownFeature(String, Vec<String>),
+    NotALibrary(&'static str),
 }
 
 impl Display for ProjectError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::NoManifest(s) => writeln!(f, "Error: No cedar.toml found in {}.", s),
             Self::InvalidManifest => writeln!(
                 f,
                 "Error: Manifest is either invalid, failed to be created, or does not exist."
@@ -31,6 +96,70 @@ impl Display for ProjectError {
             Self::InvalidPath(s) => writeln!(f, "Error: Invalid path given. \n {:?}", s),
             Self::IoError(e) => writeln!(f, "Error: Project caused an std::io::Error. \n {}", e),
             Self::NonEmptyPath(s) => writeln!(f, "Error: Path given is not empty. \n {}", s),
+            Self::InvalidConfig(s) => writeln!(f, "Error: Invalid global config. \n {}", s),
+            Self::MissingTool(s) => writeln!(f, "Error: Failed to run {}, is it installed?", s),
+            Self::ToolFailed(s) => writeln!(f, "Error: {} exited unsuccessfully.", s),
+            Self::InvalidVersion(s) => writeln!(f, "Error: Invalid version {:?}.", s),
+            Self::InvalidDependency(s) => writeln!(f, "Error: {}", s),
+            Self::ChecksumMismatch {
+                name,
+                expected,
+                actual,
+            } => writeln!(
+                f,
+                "Error: The sources of dependency {} do not match cedar.lock, they may have been tampered with.\n expected sha256 {}\n found sha256    {}\n If the change is expected, run cedar update -p {} to lock the new sources.",
+                name, expected, actual, name
+            ),
+            Self::AlreadyExists(s) => writeln!(f, "Error: File already exists. \n {}", s),
+            Self::BadSignature(s) => writeln!(
+                f,
+                "Error: The signature of {} is not valid for the key, the file may have been tampered with.",
+                s
+            ),
+            Self::UnknownLicense(s) => writeln!(
+                f,
+                "Error: No license template for {:?}. \n Supported: {}",
+                s,
+                crate::structure::license::supported().join(", ")
+            ),
+            Self::UnknownTask(s, tasks) => writeln!(
+                f,
+                "Error: No task {:?} in [tasks]. \n Available: {}",
+                s,
+                tasks.join(", ")
+            ),
+            Self::TaskCycle(cycle) => writeln!(
+                f,
+                "Error: Tasks depend on each other: {}.",
+                cycle.join(" -> ")
+            ),
+            Self::InvalidDuration(s) => writeln!(
+                f,
+                "Error: Invalid duration {:?}, expected e.g. \"30s\", \"500ms\" or \"2m\".",
+                s
+            ),
+            Self::InvalidSize(s) => writeln!(
+                f,
+                "Error: Invalid size {:?}, expected e.g. \"64M\" or \"2G\".",
+                s
+            ),
+            Self::UnknownFeature(s, features) => writeln!(
+                f,
+                "Error: No feature {:?} in [features]. \n Available: {}",
+                s,
+                features.join(", ")
+            ),
+            Self::NotALibrary(s) => writeln!(
+                f,
+                "Error: {} only works on library projects. \n Set type in [meta] to \"lib\", \"staticlib\", \"sharedlib\" or \"headeronly\".",
+                s
+            ),
+            Self::UnknownHook(s) => writeln!(
+                f,
+                "Error: Unknown git hook {:?} in [hooks.git]. \n Supported: {}",
+                s,
+                crate::structure::hooks::GIT_HOOKS.join(", ")
+            ),
         }
     }
 }
@@ -42,3 +171,458 @@ impl From<io::Error> for ProjectError {
         ProjectError::IoError(err)
     }
 }
+
+/// A cedar project on disk, the entry point for driving cedar as a library.
+///
+/// # Fields
+///
+/// * 'root' - The directory holding cedar.toml.
+/// * 'manifest' - The parsed manifest.
+/// * 'src_dir' - The directory holding the sources, root/src.
+/// * 'include_dir' - The directory holding the public headers, root/include.
+/// * 'build_dir' - The directory holding everything cedar produces,
+///   root/build, see out_dir for where the artifacts go.
+/// * 'profile' - The name of the profile the project builds with.
+/// * 'targets' - The targets found in the project, the first is always the
+///   project itself.
+/// * 'library_sources' - The sources every target is linked with, those of
+///   the features that are off left out.
+/// * 'features' - The features of [features] that are on.
+///
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+    pub src_dir: PathBuf,
+    pub include_dir: PathBuf,
+    pub build_dir: PathBuf,
+    pub profile: String,
+    pub targets: Vec<Target>,
+    pub library_sources: Vec<(PathBuf, Language)>,
+    pub features: Vec<String>,
+}
+
+impl Project {
+    /// Loads the project rooted at the given directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ProjectError> {
+        let root = path.as_ref().to_path_buf();
+        let manifest_path = root.join("cedar.toml");
+
+        if !manifest_path.is_file() {
+            return Err(ProjectError::NoManifest(format!("{:?}", root)));
+        }
+
+        let manifest = Manifest::parse(&fs::read_to_string(manifest_path)?)?;
+
+        let (src_dir, include_dir, build_dir) =
+            (root.join("src"), root.join("include"), root.join("build"));
+
+        let (targets, library_sources) = if src_dir.is_dir() || manifest.meta.kind.is_header_only()
+        {
+            let _scan = trace::span("scan", root.display());
+            target::discover(&root, &manifest)?
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let project = Self {
+            root,
+            manifest,
+            src_dir,
+            include_dir,
+            build_dir,
+            profile: String::from(Profile::DEFAULT),
+            targets,
+            library_sources,
+            features: Vec::new(),
+        };
+
+        project.with_features(&[], true)
+    }
+    /// Loads a vendored library without a cedar.toml, such as a git
+    /// submodule, as a static library project named after its dependency.
+    ///
+    /// # Arguments
+    ///
+    /// * 'root' - The directory holding the library.
+    /// * 'name' - The name of the dependency.
+    /// * 'include' - Its include directory, relative to the root.
+    /// * 'sources' - The directories or files holding its library sources,
+    ///   relative to the root, src/ when empty.
+    ///
+    pub fn vendored<P: AsRef<Path>>(
+        root: P,
+        name: &str,
+        include: &str,
+        sources: &[String],
+    ) -> Result<Self, ProjectError> {
+        let root = root.as_ref().to_path_buf();
+
+        if !root.is_dir() {
+            return Err(ProjectError::InvalidPath(format!("{:?}", root)));
+        }
+
+        let mut manifest = Manifest::new();
+        manifest.meta.name = name.to_owned();
+        manifest.meta.version = String::from("0.0.0");
+        manifest.meta.kind = ProjectKind::StaticLib;
+        manifest.build.cflags.clear();
+
+        let mut library_sources = Vec::new();
+
+        let default = [String::from("src")];
+
+        for source in if sources.is_empty() {
+            &default
+        } else {
+            sources
+        } {
+            let path = root.join(source);
+
+            match Language::from_path(&path) {
+                Some(lang) if path.is_file() => library_sources.push((path, lang)),
+                _ => library_sources.extend(find_sources(&path, Symlinks::Follow)?),
+            }
+        }
+
+        Ok(Self {
+            src_dir: root.join("src"),
+            include_dir: root.join(include),
+            build_dir: root.join("build"),
+            profile: String::from(Profile::DEFAULT),
+            targets: Vec::new(),
+            library_sources,
+            features: Vec::new(),
+            root,
+            manifest,
+        })
+    }
+    /// The project built with a profile, which builds into its own
+    /// out_dir so switching between profiles does not rebuild everything.
+    pub fn with_profile(&self, name: &str) -> Result<Self, BuildError> {
+        if self.manifest.profile(name).is_none() {
+            return Err(BuildError::UnknownProfile(
+                name.to_owned(),
+                self.manifest.profile_names(),
+            ));
+        }
+
+        let mut profiled = self.clone();
+        profiled.profile = name.to_owned();
+
+        Ok(profiled)
+    }
+    /// The project built with features of [features] on, the sources of the
+    /// others left out of its library. A change of features changes the
+    /// compiles and links, so only what they touch is built again.
+    ///
+    /// # Arguments
+    ///
+    /// * 'names' - The features to turn on.
+    /// * 'defaults' - Also turns on the default features.
+    ///
+    pub fn with_features(&self, names: &[String], defaults: bool) -> Result<Self, ProjectError> {
+        let features = &self.manifest.features;
+        let optional = self.manifest.optional_dependencies();
+
+        for (name, feature) in &features.features {
+            if let Some(dependency) = feature
+                .dependencies
+                .iter()
+                .find(|dependency| !optional.contains(dependency))
+            {
+                return Err(ProjectError::InvalidDependency(format!(
+                    "Feature {} pulls in {}, which is no optional dependency.",
+                    name, dependency
+                )));
+            }
+        }
+
+        let mut enabled: Vec<String> = Vec::new();
+
+        for name in defaults
+            .then_some(&features.default)
+            .into_iter()
+            .flatten()
+            .chain(names)
+        {
+            if !features.features.contains_key(name) && !optional.contains(&name) {
+                return Err(ProjectError::UnknownFeature(
+                    name.clone(),
+                    features
+                        .features
+                        .keys()
+                        .chain(optional.iter().copied())
+                        .cloned()
+                        .collect(),
+                ));
+            }
+            if !enabled.contains(name) {
+                enabled.push(name.clone());
+            }
+        }
+
+        let optional: Vec<PathBuf> = features
+            .features
+            .values()
+            .flat_map(|feature| &feature.sources)
+            .map(|source| self.root.join(source))
+            .collect();
+
+        let mut featured = self.clone();
+        featured
+            .library_sources
+            .retain(|(source, _)| !optional.contains(source));
+
+        for source in enabled
+            .iter()
+            .filter_map(|name| features.features.get(name))
+            .flat_map(|feature| &feature.sources)
+        {
+            let path = self.root.join(source);
+
+            match Language::from_path(&path) {
+                Some(lang) if path.is_file() => featured.library_sources.push((path, lang)),
+                _ => return Err(ProjectError::InvalidPath(format!("{:?}", path))),
+            }
+        }
+
+        featured.features = enabled;
+
+        Ok(featured)
+    }
+    /// Whether a dependency is pulled in, always unless it is optional and no
+    /// feature that is on turns it on.
+    pub fn uses(&self, name: &str, optional: bool) -> bool {
+        !optional
+            || self.features.iter().any(|feature| {
+                feature == name
+                    || self
+                        .manifest
+                        .features
+                        .features
+                        .get(feature)
+                        .is_some_and(|feature| {
+                            feature.dependencies.iter().any(|other| other == name)
+                        })
+            })
+    }
+    /// The macros the features that are on define, as -D flags.
+    pub fn feature_defines(&self) -> Vec<String> {
+        self.features
+            .iter()
+            .filter_map(|name| self.manifest.features.features.get(name))
+            .flat_map(|feature| &feature.defines)
+            .map(|define| format!("-D{}", define))
+            .collect()
+    }
+    /// The directory the objects, dependencies, and artifacts of a build go
+    /// to, build/<target>/<profile>/, so builds for another target or with
+    /// another profile never overwrite them.
+    pub fn out_dir(&self) -> PathBuf {
+        self.build_dir
+            .join(target_triple(&self.manifest.build.compiler))
+            .join(&self.profile)
+    }
+    /// Loads the project containing the given directory, searching upwards
+    /// through its parents for a cedar.toml so commands work from anywhere
+    /// inside a project.
+    pub fn find<P: AsRef<Path>>(path: P) -> Result<Self, ProjectError> {
+        let path = path.as_ref();
+
+        match path
+            .ancestors()
+            .find(|dir| dir.join("cedar.toml").is_file())
+        {
+            Some(root) => Self::load(root),
+            None => Err(ProjectError::NoManifest(format!("{:?}", path))),
+        }
+    }
+    /// Initializes a new project in an existing empty directory and loads it.
+    pub fn init<P: AsRef<Path>>(path: P, kind: ProjectKind) -> Result<Self, ProjectError> {
+        init(&path, false, kind)?;
+        Self::load(path)
+    }
+    /// Initializes a project in a directory that already has files in it,
+    /// keeping them.
+    pub fn init_existing<P: AsRef<Path>>(path: P, kind: ProjectKind) -> Result<Self, ProjectError> {
+        init(&path, true, kind)?;
+        Self::load(path)
+    }
+    /// Removes everything in the build directory.
+    pub fn clean(&self) -> Result<(), ProjectError> {
+        if self.build_dir.exists() {
+            fs::remove_dir_all(&self.build_dir)?;
+        }
+
+        fs::create_dir_all(&self.build_dir)?;
+        Ok(())
+    }
+    /// Removes the objects, dependencies, and artifacts of the profile of the
+    /// project, keeping the rest of the build directory.
+    pub fn clean_profile(&self) -> Result<(), ProjectError> {
+        let out_dir = self.out_dir();
+
+        if out_dir.exists() {
+            fs::remove_dir_all(&out_dir)?;
+        }
+
+        Ok(())
+    }
+    /// Creates the directory (if needed) and initializes a new project in it.
+    pub fn create<P: AsRef<Path>>(path: P, kind: ProjectKind) -> Result<Self, ProjectError> {
+        if !path.as_ref().is_dir() {
+            fs::create_dir_all(&path)?;
+        }
+
+        Self::init(path, kind)
+    }
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join("cedar.toml")
+    }
+    pub fn name(&self) -> &str {
+        &self.manifest.meta.name
+    }
+    /// Finds a target by name and kind.
+    pub fn target(&self, name: &str, kind: TargetKind) -> Option<&Target> {
+        self.targets
+            .iter()
+            .find(|target| target.name == name && target.kind == kind)
+    }
+    /// The main executable of a bin project, None for library projects.
+    pub fn executable(&self) -> Option<PathBuf> {
+        self.targets
+            .first()
+            .filter(|target| target.kind == TargetKind::Bin)
+            .map(|target| self.output(target))
+    }
+    /// The path of the executable of a bin or example target.
+    pub fn output(&self, target: &Target) -> PathBuf {
+        self.out_dir()
+            .join(target.output_dir())
+            .join(target.executable(&self.manifest))
+    }
+    /// Picks the target to run: the one the filter names, otherwise the
+    /// project itself for bin projects, or the only binary of a library.
+    pub fn runnable(&self, filter: Option<&TargetFilter>) -> Result<&Target, BuildError> {
+        if let Some(filter) = filter {
+            filter.validate(&self.targets)?;
+
+            return Ok(self
+                .targets
+                .iter()
+                .find(|target| filter.matches(target))
+                .expect("validated filters match a target"));
+        }
+
+        let bins: Vec<&Target> = self
+            .targets
+            .iter()
+            .filter(|target| target.kind == TargetKind::Bin)
+            .collect();
+
+        match bins.as_slice() {
+            [] => Err(BuildError::NotExecutable),
+            [bin] => Ok(bin),
+            [first, ..] if self.manifest.meta.kind.is_bin() => Ok(first),
+            _ => Err(BuildError::AmbiguousExecutable(
+                bins.iter().map(|bin| bin.name.clone()).collect(),
+            )),
+        }
+    }
+    /// Builds the project, see structure::build::build.
+    pub fn build(&self, options: &BuildOptions) -> Result<BuildReport, CedarError> {
+        build(self, options)
+    }
+    /// Builds and runs the tests of the project, see structure::test::test.
+    pub fn test(&self, options: &TestOptions) -> Result<TestReport, CedarError> {
+        test(self, options)
+    }
+    /// Builds and times the benchmarks of the project, see
+    /// structure::bench::bench.
+    pub fn bench(&self, options: &BenchOptions) -> Result<Vec<BenchResult>, CedarError> {
+        bench(self, options)
+    }
+    /// Resolves the dependencies of the project, see structure::deps::resolve.
+    pub fn dependencies(&self, quiet: bool) -> Result<Vec<Package>, CedarError> {
+        resolve(self, quiet)
+    }
+    /// Measures the line coverage of the tests, see
+    /// structure::coverage::coverage.
+    pub fn coverage(
+        &self,
+        options: &TestOptions,
+    ) -> Result<(CoverageReport, TestReport), CedarError> {
+        coverage(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn selects_features() {
+        let root = env::temp_dir().join(format!("cedar-features-{}", process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("cedar.toml"),
+            "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n\n[system-deps]\nzlib = { pkg = \"zlib\", optional = true }\n\n[features]\ndefault = [\"fast\"]\nfast = { defines = [\"FAST\"], sources = [\"src/fast.c\"] }\ntls = { sources = [\"src/tls.c\"] }\n",
+        )
+        .unwrap();
+        for source in ["main.c", "lib.c", "fast.c", "tls.c"] {
+            fs::write(root.join("src").join(source), "int x;\n").unwrap();
+        }
+
+        let project = Project::load(&root).unwrap();
+        let sources = |project: &Project| {
+            let mut names: Vec<String> = project
+                .library_sources
+                .iter()
+                .map(|(source, _)| source.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // The sources of a feature are only compiled when it is on.
+        let defaults = project.with_features(&[], true).unwrap();
+        assert_eq!(defaults.features, ["fast"]);
+        assert_eq!(sources(&defaults), ["fast.c", "lib.c"]);
+
+        let none = project.with_features(&[], false).unwrap();
+        assert!(none.features.is_empty());
+        assert_eq!(sources(&none), ["lib.c"]);
+
+        let tls = project
+            .with_features(&[String::from("tls")], false)
+            .unwrap();
+        assert_eq!(tls.features, ["tls"]);
+        assert_eq!(sources(&tls), ["lib.c", "tls.c"]);
+
+        let all = project
+            .with_features(&[String::from("tls"), String::from("fast")], true)
+            .unwrap();
+        assert_eq!(all.features, ["fast", "tls"]);
+        assert_eq!(sources(&all), ["fast.c", "lib.c", "tls.c"]);
+
+        // An optional dependency is a feature of its own name.
+        let zlib = project
+            .with_features(&[String::from("zlib")], false)
+            .unwrap();
+        assert!(zlib.uses("zlib", true));
+        assert!(!none.uses("zlib", true));
+
+        match project.with_features(&[String::from("gui")], true) {
+            Err(ProjectError::UnknownFeature(name, features)) => {
+                assert_eq!(name, "gui");
+                assert_eq!(features, ["fast", "tls", "zlib"]);
+            }
+            other => panic!("expected UnknownFeature, got {:?}", other.map(|_| ())),
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
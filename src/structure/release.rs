@@ -0,0 +1,171 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::error::CedarError;
+
+use super::{project::ProjectError, version::Version};
+
+/// The files with uncommitted changes in the git repository of a project,
+/// untracked files included, as git status lists them.
+pub fn uncommitted(root: &Path) -> Result<Vec<String>, CedarError> {
+    let output = git(root, &["status", "--porcelain"])?;
+
+    Ok(output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().to_owned())
+        .collect())
+}
+
+/// Whether the git repository of a project has a tag.
+pub fn tag_exists(root: &Path, tag: &str) -> Result<bool, CedarError> {
+    let output = git(root, &["tag", "--list", tag])?;
+    Ok(output.lines().any(|line| line.trim() == tag))
+}
+
+/// The tag a version is released under, v1.2.3.
+pub fn tag_name(version: &Version) -> String {
+    format!("v{}", version)
+}
+
+/// Writes the files of a version bump and checks the release with them,
+/// putting the originals back when the check fails.
+///
+/// # Arguments
+///
+/// * 'edits' - The files to write and their new contents.
+/// * 'check' - Builds and tests the release.
+///
+pub fn with_edits<T>(
+    edits: &[(PathBuf, String)],
+    check: impl FnOnce() -> Result<T, CedarError>,
+) -> Result<T, CedarError> {
+    let mut originals = Vec::new();
+    for (path, contents) in edits {
+        originals.push((
+            path,
+            fs::read_to_string(path).map_err(|err| CedarError::io(path, err))?,
+        ));
+        fs::write(path, contents).map_err(|err| CedarError::io(path, err))?;
+    }
+
+    let checked = check();
+
+    if checked.is_err() {
+        for (path, contents) in originals {
+            fs::write(path, contents).map_err(|err| CedarError::io(path, err))?;
+        }
+    }
+
+    checked
+}
+
+/// Records a release in git: commits the files the version bump changed,
+/// if any, and tags the commit with an annotated tag.
+///
+/// # Arguments
+///
+/// * 'root' - The root of the project.
+/// * 'files' - The files the version bump changed.
+/// * 'version' - The version released.
+///
+/// # Returns
+///
+/// * The name of the tag.
+///
+pub fn commit_and_tag(
+    root: &Path,
+    files: &[PathBuf],
+    version: &Version,
+) -> Result<String, CedarError> {
+    let tag = tag_name(version);
+    let message = format!("Release {}", tag);
+
+    if !files.is_empty() {
+        let mut add = vec![String::from("add"), String::from("--")];
+        add.extend(files.iter().map(|file| file.to_string_lossy().into_owned()));
+        git(root, &add.iter().map(String::as_str).collect::<Vec<_>>())?;
+        git(root, &["commit", "--quiet", "-m", &message])?;
+    }
+
+    git(root, &["tag", "--annotate", &tag, "-m", &message])?;
+
+    Ok(tag)
+}
+
+/// Runs git in the root of a project.
+///
+/// # Returns
+///
+/// * What git printed on stdout.
+///
+pub(crate) fn git(root: &Path, args: &[&str]) -> Result<String, CedarError> {
+    let mut command = process::Command::new("git");
+    command.arg("-C").arg(root).args(args);
+
+    let output = command.output().map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => ProjectError::MissingTool("git").into(),
+        _ => CedarError::command(&command, err),
+    })?;
+
+    if !output.status.success() {
+        return Err(ProjectError::ToolFailed("git").into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::structure::{manifest::set_version, version::Bump};
+
+    #[test]
+    fn releases_or_rolls_back() {
+        let root = env::temp_dir().join(format!("cedar-release-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let manifest = root.join("cedar.toml");
+        let text = "[meta]\nname = \"a\"\nversion = \"0.1.0\"\n";
+        fs::write(&manifest, text).unwrap();
+
+        git(&root, &["init", "--quiet"]).unwrap();
+        git(&root, &["config", "user.name", "cedar"]).unwrap();
+        git(&root, &["config", "user.email", "cedar@example.com"]).unwrap();
+        git(&root, &["add", "--", "cedar.toml"]).unwrap();
+        git(&root, &["commit", "--quiet", "-m", "Start"]).unwrap();
+        assert!(uncommitted(&root).unwrap().is_empty());
+
+        let old: Version = "0.1.0".parse().unwrap();
+        let new = old.bumped(&Bump::Minor);
+        let bumped = set_version(text, &new.to_string()).unwrap();
+        let edits = vec![(manifest.clone(), bumped.clone())];
+
+        // A release failing its checks leaves the version alone.
+        let failed: Result<(), CedarError> =
+            with_edits(&edits, || Err(ProjectError::ToolFailed("make").into()));
+        assert!(failed.is_err());
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), text);
+        assert!(uncommitted(&root).unwrap().is_empty());
+
+        with_edits(&edits, || Ok(())).unwrap();
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), bumped);
+        assert_eq!(uncommitted(&root).unwrap(), ["M cedar.toml"]);
+
+        let tag = commit_and_tag(&root, &[manifest], &new).unwrap();
+        assert_eq!(tag, "v0.2.0");
+        assert!(tag_exists(&root, "v0.2.0").unwrap());
+        assert!(!tag_exists(&root, "v0.1.0").unwrap());
+        assert!(uncommitted(&root).unwrap().is_empty());
+        assert_eq!(
+            git(&root, &["log", "-1", "--format=%s"]).unwrap().trim(),
+            "Release v0.2.0"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process::{self, Stdio},
+};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    build::BuildOptions,
+    config::RemoteConfig,
+    ninja::quote,
+    project::{Project, ProjectError},
+    target::Artifact,
+};
+
+/// Builds the project on a remote machine: the sources are synced to it with
+/// rsync, cedar builds them there over ssh, and the artifacts of the build
+/// are copied back into the build directory.
+///
+/// The build directory of the remote copy is kept between builds, so they
+/// are incremental there too. Dependencies already checked out in
+/// build/deps/src/ are synced along with the sources.
+///
+/// # Arguments
+///
+/// * 'project' - The project to build.
+/// * 'name' - The name of the remote in the config.
+/// * 'remote' - Where and how to build.
+/// * 'options' - The targets to build, to know which artifacts to copy.
+/// * 'args' - The arguments of cedar on the remote.
+///
+/// # Returns
+///
+/// * The artifacts copied back.
+///
+pub fn build(
+    project: &Project,
+    name: &str,
+    remote: &RemoteConfig,
+    options: &BuildOptions,
+    args: &[String],
+) -> Result<Vec<PathBuf>, CedarError> {
+    let root = &project.root;
+    let destination = format!("{}:{}/", remote.host, remote.path.trim_end_matches('/'));
+
+    println!(
+        "\t{}{} to {} ({})",
+        style::header("Syncing "),
+        project.manifest.meta.name,
+        name,
+        destination
+    );
+
+    // Only the checked out dependencies of the build directory are sent,
+    // and the remote build directory is never deleted as it is excluded.
+    let mut sync = process::Command::new("rsync");
+    sync.arg("-az")
+        .arg("--delete")
+        .arg(format!(
+            "--rsync-path=mkdir -p {} && rsync",
+            shell_path(&remote.path)
+        ))
+        .args([
+            "--filter=+ /build/",
+            "--filter=+ /build/deps/",
+            "--filter=+ /build/deps/src/***",
+            "--filter=- /build/**",
+        ])
+        .args(
+            remote
+                .exclude
+                .iter()
+                .map(|exclude| format!("--exclude={}", exclude)),
+        )
+        .arg(format!("{}/", root.display()))
+        .arg(&destination);
+
+    run(&mut sync, "rsync")?;
+
+    println!("\t{}on {}", style::header("Building "), remote.host);
+
+    let mut ssh = process::Command::new("ssh");
+    if io::stdout().is_terminal() {
+        ssh.arg("-t");
+    }
+    ssh.arg(&remote.host).arg(format!(
+        "cd {} && {} {}",
+        shell_path(&remote.path),
+        remote.cedar.as_deref().unwrap_or("cedar"),
+        args.iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+
+    let status = ssh.status().map_err(|err| missing_or(&ssh, err, "ssh"))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed("The build on the remote").into());
+    }
+
+    // The artifacts of the targets built, and the links of shared libraries.
+    let mut artifacts = Vec::new();
+
+    for target in project
+        .targets
+        .iter()
+        .filter(|target| options.selects(target))
+    {
+        let dir = project.out_dir().join(target.output_dir());
+
+        for artifact in target.artifacts(&project.manifest)? {
+            if let Artifact::SharedLib {
+                soname: Some(soname),
+                ..
+            } = &artifact
+            {
+                artifacts.push(dir.join(soname));
+                artifacts.push(dir.join(format!("lib{}.so", project.manifest.meta.name)));
+            }
+            artifacts.push(dir.join(artifact.name()));
+        }
+    }
+
+    let files: String = artifacts
+        .iter()
+        .filter_map(|artifact| artifact.strip_prefix(root).ok())
+        .map(|artifact| format!("{}\n", artifact.display()))
+        .collect();
+
+    let mut copy = process::Command::new("rsync");
+    copy.arg("-az")
+        .arg("--files-from=-")
+        .arg(&destination)
+        .arg(format!("{}/", root.display()))
+        .stdin(Stdio::piped());
+
+    let mut child = copy
+        .spawn()
+        .map_err(|err| missing_or(&copy, err, "rsync"))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(files.as_bytes())?;
+
+    if !child.wait()?.success() {
+        return Err(ProjectError::ToolFailed("rsync").into());
+    }
+
+    Ok(artifacts)
+}
+
+/// Runs a command, failing when it is missing or exits unsuccessfully.
+fn run(command: &mut process::Command, tool: &'static str) -> Result<(), CedarError> {
+    let status = command
+        .status()
+        .map_err(|err| missing_or(command, err, tool))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed(tool).into());
+    }
+
+    Ok(())
+}
+
+fn missing_or(command: &process::Command, err: io::Error, tool: &'static str) -> CedarError {
+    match err.kind() {
+        io::ErrorKind::NotFound => ProjectError::MissingTool(tool).into(),
+        _ => CedarError::command(command, err),
+    }
+}
+
+/// A path for the remote shell, relative to the home directory when it
+/// starts with ~/.
+fn shell_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", quote(rest)),
+        None => quote(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_remote_paths() {
+        assert_eq!(shell_path("~/builds/my project"), "~/'builds/my project'");
+        assert_eq!(shell_path("/srv/build"), "/srv/build");
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    env,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+
+use crate::{cli::CliError, error::CedarError};
+
+use super::{
+    deps::{self, Package},
+    license::license_of,
+    project::Project,
+    sha256,
+};
+
+/// The formats a software bill of materials can be written in.
+///
+/// # Members
+///
+/// * 'CycloneDx' - CycloneDX 1.5 JSON.
+/// * 'Spdx' - SPDX 2.3 JSON.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SbomFormat {
+    #[default]
+    CycloneDx,
+    Spdx,
+}
+
+impl FromStr for SbomFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cyclonedx" => Ok(Self::CycloneDx),
+            "spdx" => Ok(Self::Spdx),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, the sbom format is cyclonedx or spdx.",
+                s
+            ))),
+        }
+    }
+}
+
+/// A dependency as it appears in the bill of materials.
+struct Component {
+    package: Package,
+    license: Option<String>,
+    hash: String,
+}
+
+/// Generates a software bill of materials for the project and its resolved
+/// dependencies, with their versions, licenses, where they come from, and
+/// the SHA-256 of their sources.
+///
+/// # Arguments
+///
+/// * 'project' - The project to describe.
+/// * 'format' - The format to write.
+///
+/// # Returns
+///
+/// * The bill of materials as pretty printed JSON.
+///
+pub fn sbom(project: &Project, format: SbomFormat) -> Result<String, CedarError> {
+    let mut components = Vec::new();
+
+    for package in deps::resolve(project, true)? {
+        let hash = sha256::hash_dir(&package.project.root)
+            .map_err(|err| CedarError::io(&package.project.root, err))?;
+
+        components.push(Component {
+            license: license_of(&package.project).0,
+            package,
+            hash,
+        });
+    }
+
+    let document = match format {
+        SbomFormat::CycloneDx => cyclonedx(project, &components),
+        SbomFormat::Spdx => spdx(project, &components),
+    };
+
+    Ok(serde_json::to_string_pretty(&document).map_err(std::io::Error::from)?)
+}
+
+fn cyclonedx(project: &Project, components: &[Component]) -> Value {
+    let meta = &project.manifest.meta;
+
+    let licenses = |license: &Option<String>| match license {
+        Some(license) if license.contains(' ') => json!([{ "expression": license }]),
+        Some(license) => json!([{ "license": { "id": license } }]),
+        None => json!([]),
+    };
+
+    let dependencies: Vec<Value> = std::iter::once(json!({
+        "ref": meta.name,
+        "dependsOn": project.manifest.dependencies.packages.keys().collect::<Vec<_>>(),
+    }))
+    .chain(components.iter().map(|component| {
+        json!({
+            "ref": component.package.name,
+            "dependsOn": component.package.project.manifest.dependencies.packages.keys().collect::<Vec<_>>(),
+        })
+    }))
+    .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp(),
+            "tools": [{ "name": "cedar", "version": env!("CARGO_PKG_VERSION") }],
+            "component": {
+                "type": if meta.kind.is_bin() { "application" } else { "library" },
+                "bom-ref": meta.name,
+                "name": meta.name,
+                "version": meta.version,
+                "licenses": licenses(&meta.license),
+            },
+        },
+        "components": components.iter().map(|component| {
+            let package = &component.package;
+            let mut value = json!({
+                "type": "library",
+                "bom-ref": package.name,
+                "name": package.name,
+                "version": package.version,
+                "hashes": [{ "alg": "SHA-256", "content": component.hash }],
+                "licenses": licenses(&component.license),
+            });
+
+            if let Some(url) = git_url(package) {
+                value["externalReferences"] = json!([{ "type": "vcs", "url": url }]);
+            }
+
+            value
+        }).collect::<Vec<_>>(),
+        "dependencies": dependencies,
+    })
+}
+
+fn spdx(project: &Project, components: &[Component]) -> Value {
+    let meta = &project.manifest.meta;
+    let id = |name: &str| format!("SPDXRef-Package-{}", spdx_id(name));
+
+    let mut packages = vec![json!({
+        "name": meta.name,
+        "SPDXID": id(&meta.name),
+        "versionInfo": meta.version,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": meta.license.as_deref().unwrap_or("NOASSERTION"),
+    })];
+
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": id(&meta.name),
+    })];
+
+    let depends_on = |from: &str, to: &str| {
+        json!({
+            "spdxElementId": id(from),
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": id(to),
+        })
+    };
+
+    relationships.extend(
+        project
+            .manifest
+            .dependencies
+            .packages
+            .keys()
+            .map(|name| depends_on(&meta.name, name)),
+    );
+
+    for component in components {
+        let package = &component.package;
+
+        packages.push(json!({
+            "name": package.name,
+            "SPDXID": id(&package.name),
+            "versionInfo": package.version,
+            "downloadLocation": git_url(package).unwrap_or_else(|| String::from("NOASSERTION")),
+            "filesAnalyzed": false,
+            "checksums": [{ "algorithm": "SHA256", "checksumValue": component.hash }],
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": component.license.as_deref().unwrap_or("NOASSERTION"),
+        }));
+
+        relationships.extend(
+            package
+                .project
+                .manifest
+                .dependencies
+                .packages
+                .keys()
+                .map(|name| depends_on(&package.name, name)),
+        );
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{}-{}", meta.name, meta.version),
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/{}-{}-{}",
+            meta.name,
+            meta.version,
+            &sha256::digest(project.root.to_string_lossy().as_bytes())[..16]
+        ),
+        "creationInfo": {
+            "created": timestamp(),
+            "creators": [format!("Tool: cedar-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+/// The repository a git dependency was fetched from, pinned to its commit.
+fn git_url(package: &Package) -> Option<String> {
+    let source = package.source.strip_prefix("git+")?;
+    let (url, commit) = source.rsplit_once('#')?;
+    let url = url.split_once('?').map_or(url, |(url, _)| url);
+
+    Some(format!("git+{}@{}", url, commit))
+}
+
+/// SPDX identifiers only allow letters, digits, '.', and '-'.
+fn spdx_id(name: &str) -> String {
+    name.chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '.' {
+            true => c,
+            false => '-',
+        })
+        .collect()
+}
+
+/// The current time as an RFC 3339 UTC timestamp, or SOURCE_DATE_EPOCH when
+/// it is set so the document can be reproduced.
+pub(crate) fn timestamp() -> String {
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0)
+        });
+
+    format_timestamp(seconds)
+}
+
+pub(crate) fn format_timestamp(seconds: i64) -> String {
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_timestamps_and_ids() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1_709_210_096), "2024-02-29T12:34:56Z");
+        assert_eq!(spdx_id("my_lib+"), "my-lib-");
+    }
+}
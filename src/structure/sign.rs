@@ -0,0 +1,287 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::CedarError;
+
+use super::project::ProjectError;
+
+/// The namespace of ssh signatures made by cedar, so a signature of a file
+/// cannot pass for a signature made for another purpose (e.g. a git commit).
+const NAMESPACE: &str = "cedar";
+
+/// The programs files are signed with, picked from the format of the key.
+///
+/// # Members
+///
+/// * 'Ssh' - ssh-keygen -Y, with an OpenSSH key (or the public key of one in
+///   the agent).
+/// * 'Minisign' - minisign, with a minisign key.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignTool {
+    Ssh,
+    Minisign,
+}
+
+impl SignTool {
+    /// The tool of a key file, minisign keys start with an untrusted comment.
+    pub fn of_key(contents: &str) -> Self {
+        match contents.starts_with("untrusted comment:") {
+            true => Self::Minisign,
+            false => Self::Ssh,
+        }
+    }
+}
+
+/// The signature of a file, <file>.sig.
+pub fn signature_path(file: &Path) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// Signs a file, writing its signature next to it.
+///
+/// # Arguments
+///
+/// * 'file' - The file to sign.
+/// * 'key' - The secret key, minisign may ask for its password.
+///
+/// # Returns
+///
+/// * The path of the signature.
+///
+pub fn sign(file: &Path, key: &Path) -> Result<PathBuf, CedarError> {
+    let key = expand(key);
+    let contents = fs::read_to_string(&key).map_err(|err| CedarError::io(&key, err))?;
+    let signature = signature_path(file);
+
+    // ssh-keygen refuses to overwrite a signature.
+    if signature.exists() {
+        fs::remove_file(&signature).map_err(|err| CedarError::io(&signature, err))?;
+    }
+
+    let (tool, mut command) = match SignTool::of_key(&contents) {
+        SignTool::Ssh => {
+            let mut command = process::Command::new("ssh-keygen");
+            command
+                .args(["-Y", "sign", "-n", NAMESPACE, "-f"])
+                .arg(&key)
+                .arg(file);
+            ("ssh-keygen", command)
+        }
+        SignTool::Minisign => {
+            let mut command = process::Command::new("minisign");
+            command
+                .arg("-S")
+                .arg("-s")
+                .arg(&key)
+                .arg("-m")
+                .arg(file)
+                .arg("-x")
+                .arg(&signature);
+            ("minisign", command)
+        }
+    };
+
+    run(&mut command, tool)?;
+
+    Ok(signature)
+}
+
+/// Checks the signature of a file, <file>.sig, against a public key.
+///
+/// # Arguments
+///
+/// * 'file' - The signed file.
+/// * 'public_key' - The public key of the signer, an OpenSSH public key or
+///   a minisign public key.
+///
+pub fn verify(file: &Path, public_key: &Path) -> Result<(), CedarError> {
+    let public_key = expand(public_key);
+    let contents =
+        fs::read_to_string(&public_key).map_err(|err| CedarError::io(&public_key, err))?;
+    let signature = signature_path(file);
+
+    if !signature.is_file() {
+        return Err(ProjectError::InvalidPath(format!(
+            "{}, no signature next to the file.",
+            signature.display()
+        ))
+        .into());
+    }
+
+    let verified = match SignTool::of_key(&contents) {
+        SignTool::Ssh => {
+            // ssh-keygen only takes keys from a list of allowed signers,
+            // written where no other user can swap it for their own.
+            let dir = private_dir().map_err(|err| CedarError::io(env::temp_dir(), err))?;
+            let allowed = dir.join("allowed_signers");
+            let written = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&allowed)
+                .and_then(|mut signers| signers.write_all(allowed_signers(&contents).as_bytes()));
+
+            if let Err(err) = written {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(CedarError::io(&allowed, err));
+            }
+
+            let mut command = process::Command::new("ssh-keygen");
+            command
+                .args(["-Y", "verify", "-I", NAMESPACE, "-n", NAMESPACE, "-f"])
+                .arg(&allowed)
+                .arg("-s")
+                .arg(&signature)
+                .stdin(fs::File::open(file).map_err(|err| CedarError::io(file, err))?)
+                .stdout(Stdio::null());
+
+            let status = command.status();
+            let _ = fs::remove_dir_all(&dir);
+            status_of(&command, status, "ssh-keygen")?
+        }
+        SignTool::Minisign => {
+            let mut command = process::Command::new("minisign");
+            command
+                .arg("-V")
+                .arg("-p")
+                .arg(&public_key)
+                .arg("-m")
+                .arg(file)
+                .arg("-x")
+                .arg(&signature)
+                .stdout(Stdio::null());
+
+            let status = command.status();
+            status_of(&command, status, "minisign")?
+        }
+    };
+
+    match verified {
+        true => Ok(()),
+        false => Err(ProjectError::BadSignature(file.display().to_string()).into()),
+    }
+}
+
+/// Creates a new directory in the temporary directory only the user can
+/// enter, failing rather than reusing one that is already there.
+fn private_dir() -> io::Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.subsec_nanos());
+
+    let mut attempt = 0;
+    loop {
+        let dir = env::temp_dir().join(format!(
+            "cedar-signers-{}-{}-{}",
+            process::id(),
+            nanos,
+            attempt
+        ));
+
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+
+            builder.mode(0o700);
+        }
+
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// The allowed signers file of ssh-keygen for the keys of a public key file,
+/// every key is allowed to sign as cedar.
+fn allowed_signers(public_keys: &str) -> String {
+    public_keys
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| format!("{} namespaces=\"{}\" {}\n", NAMESPACE, NAMESPACE, line))
+        .collect()
+}
+
+/// Expands a leading ~/ to the home directory, for keys from the config.
+fn expand(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn run(command: &mut process::Command, tool: &'static str) -> Result<(), CedarError> {
+    let status = command.status();
+
+    match status_of(command, status, tool)? {
+        true => Ok(()),
+        false => Err(ProjectError::ToolFailed(tool).into()),
+    }
+}
+
+/// Whether a tool succeeded, a missing tool is an error.
+fn status_of(
+    command: &process::Command,
+    status: std::io::Result<process::ExitStatus>,
+    tool: &'static str,
+) -> Result<bool, CedarError> {
+    match status {
+        Ok(status) => Ok(status.success()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Err(ProjectError::MissingTool(tool).into())
+        }
+        Err(err) => Err(CedarError::command(command, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_keys() {
+        assert_eq!(
+            SignTool::of_key("untrusted comment: minisign public key\nRWQ..."),
+            SignTool::Minisign
+        );
+        assert_eq!(SignTool::of_key("ssh-ed25519 AAAA me@host"), SignTool::Ssh);
+        assert_eq!(
+            allowed_signers("# release key\nssh-ed25519 AAAA me@host\n"),
+            "cedar namespaces=\"cedar\" ssh-ed25519 AAAA me@host\n"
+        );
+        assert_eq!(
+            signature_path(Path::new("build/package/app-1.0.tar.gz")),
+            Path::new("build/package/app-1.0.tar.gz.sig")
+        );
+    }
+
+    #[test]
+    fn creates_private_directories() {
+        let first = private_dir().unwrap();
+        let second = private_dir().unwrap();
+        assert_ne!(first, second);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        fs::remove_dir(&first).unwrap();
+        fs::remove_dir(&second).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::error::CedarError;
+
+use super::project::Project;
+
+/// The snapshot of a test's stdout, tests/snapshots/<name>.txt. A test opts
+/// into snapshot testing by having one, an empty file is enough to have
+/// `cedar test --update-snapshots` fill it in.
+pub fn snapshot_path(project: &Project, name: &str) -> PathBuf {
+    project
+        .root
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name))
+}
+
+/// Compares the stdout of a test against its snapshot, or rewrites the
+/// snapshot with it.
+///
+/// # Arguments
+///
+/// * 'path' - The snapshot.
+/// * 'stdout' - What the test printed.
+/// * 'update' - Rewrites the snapshot instead of comparing.
+///
+/// # Returns
+///
+/// * A diff of the snapshot against the output when they differ, nothing
+///   when they match, the snapshot was updated, or there is none.
+///
+pub fn check(path: &Path, stdout: &str, update: bool) -> Result<Option<String>, CedarError> {
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(CedarError::io(path, err)),
+    };
+
+    if expected == stdout {
+        return Ok(None);
+    }
+
+    if update {
+        fs::write(path, stdout).map_err(|err| CedarError::io(path, err))?;
+        return Ok(None);
+    }
+
+    Ok(Some(diff(&expected, stdout)))
+}
+
+/// A line diff of two texts, removed lines prefixed with '-' and added
+/// lines with '+'.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Lengths of the longest common subsequences of every pair of suffixes.
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push_str(&format!(" {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+
+    // Texts differing only in the trailing newline have no differing lines.
+    if expected.ends_with('\n') != actual.ends_with('\n') {
+        diff.push_str("\\ trailing newline differs\n");
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+
+    #[test]
+    fn diffs_lines() {
+        assert_eq!(
+            diff("usage\nverbose\ndone\n", "usage\nquiet\ndone\n"),
+            " usage\n-verbose\n+quiet\n done\n"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::CedarError;
+
+use super::{
+    ignore,
+    project::Project,
+    timings::{self, Record},
+};
+
+/// The extensions of the files counted as code, sources and headers.
+const CODE: &[&str] = &[
+    "c", "cc", "cpp", "cxx", "c++", "C", "s", "S", "sx", "h", "hh", "hpp", "hxx",
+];
+
+/// How many of the largest objects are listed.
+const LARGEST: usize = 10;
+
+/// What cedar stats reports about a project and its builds.
+///
+/// # Fields
+///
+/// * 'directories' - The files of code and their lines in each directory,
+///   from the root, of src/, include/, tests/, examples/, and benches/.
+/// * 'translation_units' - The sources of the library and every target.
+/// * 'headers' - The headers in include/ and src/.
+/// * 'builds' - The builds in the timings history, newest first.
+/// * 'logged' - The builds build/log/ still has a log of.
+/// * 'failed' - How many of the logged builds failed.
+/// * 'cache' - The hits and misses of the object cache in the logged builds,
+///   None when no build used it.
+/// * 'largest_objects' - The largest objects of the profile, from the root,
+///   and their sizes in bytes.
+///
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub directories: Vec<(PathBuf, usize, usize)>,
+    pub translation_units: usize,
+    pub headers: usize,
+    pub builds: Vec<Record>,
+    pub logged: usize,
+    pub failed: usize,
+    pub cache: Option<(usize, usize)>,
+    pub largest_objects: Vec<(PathBuf, u64)>,
+}
+
+impl Stats {
+    /// Collects the statistics of a project from its files and what its
+    /// past builds left in build/, without building.
+    pub fn load(project: &Project) -> Result<Self, CedarError> {
+        let root = &project.root;
+        let symlinks = project.manifest.build.symlinks;
+
+        let mut directories: BTreeMap<PathBuf, (usize, usize)> = BTreeMap::new();
+        let mut headers = 0;
+
+        for dir in ["src", "include", "tests", "examples", "benches"] {
+            let dir = root.join(dir);
+
+            if !dir.is_dir() {
+                continue;
+            }
+
+            for file in ignore::files(&dir, symlinks).map_err(|err| CedarError::io(&dir, err))? {
+                let extension = file.extension().and_then(|ext| ext.to_str());
+
+                if !extension.is_some_and(|extension| CODE.contains(&extension)) {
+                    continue;
+                }
+
+                if matches!(extension, Some("h" | "hh" | "hpp" | "hxx"))
+                    && (file.starts_with(&project.include_dir)
+                        || file.starts_with(&project.src_dir))
+                {
+                    headers += 1;
+                }
+
+                let lines = fs::read(&file)
+                    .map(|contents| contents.split(|byte| *byte == b'\n').count() - 1)
+                    .map_err(|err| CedarError::io(&file, err))?;
+
+                let parent = file.parent().unwrap_or(root);
+                let entry = directories
+                    .entry(parent.strip_prefix(root).unwrap_or(parent).to_path_buf())
+                    .or_default();
+                entry.0 += 1;
+                entry.1 += lines;
+            }
+        }
+
+        let translation_units = project.library_sources.len()
+            + project
+                .targets
+                .iter()
+                .map(|target| target.sources.len())
+                .sum::<usize>();
+
+        let mut logged = 0;
+        let mut failed = 0;
+        let mut cache: Option<(usize, usize)> = None;
+
+        let log_dir = project.build_dir.join("log");
+        for entry in fs::read_dir(&log_dir).into_iter().flatten().flatten() {
+            let Ok(text) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let (build_failed, build_cache) = summary(&text);
+
+            logged += 1;
+            failed += usize::from(build_failed);
+
+            if let Some((hits, misses)) = build_cache {
+                let total = cache.get_or_insert((0, 0));
+                total.0 += hits;
+                total.1 += misses;
+            }
+        }
+
+        let mut largest_objects: Vec<(PathBuf, u64)> =
+            ignore::files(&project.out_dir().join("obj"), symlinks)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|file| file.extension().is_some_and(|extension| extension == "o"))
+                .filter_map(|file| {
+                    let size = fs::metadata(&file).ok()?.len();
+                    Some((
+                        file.strip_prefix(root)
+                            .map(Path::to_path_buf)
+                            .unwrap_or(file),
+                        size,
+                    ))
+                })
+                .collect();
+
+        largest_objects.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        largest_objects.truncate(LARGEST);
+
+        Ok(Self {
+            directories: directories
+                .into_iter()
+                .map(|(dir, (files, lines))| (dir, files, lines))
+                .collect(),
+            translation_units,
+            headers,
+            builds: timings::history(&project.build_dir),
+            logged,
+            failed,
+            cache,
+            largest_objects,
+        })
+    }
+}
+
+/// Whether the build of a log failed, and the hits and misses of the object
+/// cache it logged.
+fn summary(log: &str) -> (bool, Option<(usize, usize)>) {
+    let mut failed = false;
+    let mut cache = None;
+
+    for line in log.lines() {
+        // Every line starts with the time since the build started.
+        let text = line.split_once("] ").map_or(line, |(_, text)| text);
+
+        if text == "failed" {
+            failed = true;
+        } else if let Some(totals) = cache_totals(text) {
+            cache = Some(totals);
+        }
+    }
+
+    (failed, cache)
+}
+
+/// The hits and misses of the line a build logs the object cache with,
+/// exactly `cache <hits> hit(s) (<remote> remote), <misses> miss(es)`.
+fn cache_totals(text: &str) -> Option<(usize, usize)> {
+    let (hits, rest) = text.strip_prefix("cache ")?.split_once(" hit(s) (")?;
+    let (remote, misses) = rest.split_once(" remote), ")?;
+    let misses = misses.strip_suffix(" miss(es)")?;
+    remote.parse::<usize>().ok()?;
+
+    Some((hits.parse().ok()?, misses.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_build_logs() {
+        let log = "[    0.000s] cedar 0.1.0 build of a v0.1.0 (/p) at 2026-01-01T00:00:00Z\n[    0.120s] compile src/main.c (0.100s): gcc -c src/main.c\n[    0.121s] cache hit src/a.c\n[    0.130s] cache 3 hit(s) (1 remote), 2 miss(es)\n[    0.131s] finished in 131.00ms\n";
+        assert_eq!(summary(log), (false, Some((3, 2))));
+
+        let log = "[    0.000s] cedar 0.1.0 build of a v0.1.0 (/p) at 2026-01-01T00:00:00Z\n[    0.040s] cache hit src/v1/2/3.c\n[    0.050s] compile src/main.c (0.050s) FAILED: gcc -c src/main.c\n[    0.051s]     cache 1 hit\n[    0.052s] failed\n";
+        assert_eq!(summary(log), (true, None));
+
+        assert_eq!(cache_totals("cache hit src/v1/2/3.c"), None);
+        assert_eq!(cache_totals("cache 1 hit(s) (x remote), 2 miss(es)"), None);
+    }
+}
@@ -0,0 +1,431 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    build::{BuildOptions, BuildReport},
+    ignore::{self, Ignore},
+    manifest::Symlinks,
+    project::{Project, ProjectError},
+};
+
+/// The roots of the subprojects of a project, in the order they build: the
+/// members of [subprojects] first, then the other directories with a
+/// cedar.toml under the root, sorted by path. The search skips build
+/// directories, hidden and ignored directories, and does not look inside
+/// subprojects, which find their own.
+pub fn members(project: &Project) -> Result<Vec<PathBuf>, ProjectError> {
+    let mut members = Vec::new();
+
+    for member in &project.manifest.subprojects.members {
+        let root = project.root.join(member);
+
+        if !root.join("cedar.toml").is_file() {
+            return Err(ProjectError::InvalidPath(format!(
+                "{}, the subproject {} has no cedar.toml.",
+                root.display(),
+                member
+            )));
+        }
+
+        members.push(root);
+    }
+
+    let mut found = Vec::new();
+    discover(
+        &project.root,
+        &project.build_dir,
+        Ignore::default(),
+        &mut found,
+    )?;
+    found.sort();
+
+    for root in found {
+        if !members.iter().any(|member| same_dir(member, &root)) {
+            members.push(root);
+        }
+    }
+
+    Ok(members)
+}
+
+/// What a recursive build compares against to find the projects affected
+/// by changes.
+///
+/// # Members
+///
+/// * 'LastBuild' - The files modified since the last recursive build of
+///   each project started.
+/// * 'Since' - The files git reports changed since a ref, committed or not,
+///   untracked files included.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Changes {
+    LastBuild,
+    Since(String),
+}
+
+/// Where a project records when its last recursive build started, in its
+/// out directory, so a build with another profile or for another target
+/// does not count as the last one.
+const STAMP: &str = "recursive.stamp";
+
+/// Builds the subprojects of a project, each after its own subprojects,
+/// then the project with the options. A subproject reachable twice (e.g.
+/// listed by two projects) is built once.
+///
+/// Only the projects affected by the changes are built: those with changed
+/// files of their own (outside of their subprojects and build directory),
+/// and those with a path dependency on an affected project.
+///
+/// # Arguments
+///
+/// * 'project' - The project at the top.
+/// * 'options' - The options of its build, subprojects build their default
+///   targets.
+/// * 'changes' - What the changed files are.
+///
+/// # Returns
+///
+/// * The report of every project built, the project last if it was.
+///
+pub fn build_recursive(
+    project: &Project,
+    options: &BuildOptions,
+    changes: &Changes,
+) -> Result<Vec<(PathBuf, BuildReport)>, CedarError> {
+    let mut subprojects = Vec::new();
+    let mut visited = vec![project.root.canonicalize()?];
+    collect(project, &mut visited, &mut subprojects)?;
+
+    let projects: Vec<&Project> = subprojects.iter().chain([project]).collect();
+
+    let roots: Vec<PathBuf> = projects
+        .iter()
+        .map(|project| project.root.canonicalize())
+        .collect::<Result<_, _>>()?;
+
+    let mut affected: Vec<bool> = match changes {
+        Changes::LastBuild => projects
+            .iter()
+            .map(|project| modified_since_stamp(project))
+            .collect::<Result<_, _>>()?,
+        Changes::Since(since) => {
+            let build_dirs: Vec<PathBuf> = roots.iter().map(|root| root.join("build")).collect();
+            let mut affected = vec![false; projects.len()];
+
+            for file in git_changed(&roots[roots.len() - 1], since)? {
+                if build_dirs.iter().any(|dir| file.starts_with(dir)) {
+                    continue;
+                }
+
+                // A file belongs to the innermost project it is in.
+                if let Some(owner) = (0..roots.len())
+                    .filter(|index| file.starts_with(&roots[*index]))
+                    .max_by_key(|index| roots[*index].components().count())
+                {
+                    affected[owner] = true;
+                }
+            }
+
+            affected
+        }
+    };
+
+    let dependencies: Vec<Vec<usize>> = projects
+        .iter()
+        .map(|project| {
+            project
+                .manifest
+                .dependencies
+                .packages
+                .values()
+                .filter_map(|dependency| dependency.path.as_ref())
+                .filter_map(|path| project.root.join(path).canonicalize().ok())
+                .filter_map(|path| roots.iter().position(|root| *root == path))
+                .collect()
+        })
+        .collect();
+
+    // Affected dependencies affect their dependents, however deep.
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for (index, dependencies) in dependencies.iter().enumerate() {
+            if !affected[index] && dependencies.iter().any(|dependency| affected[*dependency]) {
+                affected[index] = true;
+                changed = true;
+            }
+        }
+    }
+
+    let mut reports = Vec::new();
+    let last = projects.len() - 1;
+
+    for (index, project) in projects.iter().enumerate() {
+        if !affected[index] {
+            if !options.quiet {
+                println!(
+                    "\t{}{} v{} ({:?}), unaffected",
+                    style::header("Skipping "),
+                    project.manifest.meta.name,
+                    project.manifest.meta.version,
+                    project.root
+                );
+            }
+            continue;
+        }
+
+        // The stamp is dated when the build starts, so files edited during
+        // the build count as changed next time.
+        let out_dir = project.out_dir();
+        fs::create_dir_all(&out_dir).map_err(|err| CedarError::io(&out_dir, err))?;
+        let started = out_dir.join(format!("{}.tmp", STAMP));
+        fs::write(&started, "").map_err(|err| CedarError::io(&started, err))?;
+
+        let report = match index == last {
+            true => project.build(options)?,
+            false => project.build(&BuildOptions {
+                quiet: options.quiet,
+                ..Default::default()
+            })?,
+        };
+
+        let stamp = out_dir.join(STAMP);
+        fs::rename(&started, &stamp).map_err(|err| CedarError::io(&stamp, err))?;
+
+        reports.push((project.root.clone(), report));
+    }
+
+    Ok(reports)
+}
+
+/// Loads the subprojects of a project in build order, each after its own.
+fn collect(
+    project: &Project,
+    visited: &mut Vec<PathBuf>,
+    projects: &mut Vec<Project>,
+) -> Result<(), CedarError> {
+    for root in members(project)? {
+        let canonical = root.canonicalize()?;
+
+        if visited.contains(&canonical) {
+            continue;
+        }
+
+        visited.push(canonical);
+
+        let subproject = Project::load(&root)?;
+        collect(&subproject, visited, projects)?;
+        projects.push(subproject);
+    }
+
+    Ok(())
+}
+
+/// Whether a file of the project is newer than the stamp of its last
+/// recursive build, always true without one.
+fn modified_since_stamp(project: &Project) -> Result<bool, CedarError> {
+    let stamp = match fs::metadata(project.out_dir().join(STAMP)).and_then(|meta| meta.modified()) {
+        Ok(time) => time,
+        Err(_) => return Ok(true),
+    };
+
+    let newer = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .map_or(true, |time| time > stamp)
+    };
+
+    let ignore = Ignore::for_dir(&project.root);
+
+    for entry in project.root.read_dir()? {
+        let path = entry?.path();
+        let is_dir = path.is_dir();
+
+        if path == project.build_dir
+            || path.file_name().is_some_and(|name| name == ".git")
+            || ignore.is_ignored(&path, is_dir)
+        {
+            continue;
+        }
+
+        let changed = match is_dir {
+            true if path.join("cedar.toml").is_file() => false,
+            true => ignore::files(&path, Symlinks::Follow)?
+                .iter()
+                .any(|file| newer(file)),
+            false => newer(&path),
+        };
+
+        if changed {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// The files changed since a git ref, with git diff and the untracked
+/// files of git ls-files, under the root.
+fn git_changed(root: &Path, since: &str) -> Result<Vec<PathBuf>, CedarError> {
+    let mut files = Vec::new();
+
+    for args in [
+        &["diff", "--name-only", "--relative", since, "--"][..],
+        &["ls-files", "--others", "--exclude-standard"],
+    ] {
+        let mut command = process::Command::new("git");
+        command.arg("-C").arg(root).args(args);
+
+        let output = command.output().map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => ProjectError::MissingTool("git").into(),
+            _ => CedarError::command(&command, err),
+        })?;
+
+        if !output.status.success() {
+            return Err(ProjectError::ToolFailed("git").into());
+        }
+
+        files.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| root.join(line)),
+        );
+    }
+
+    Ok(files)
+}
+
+fn discover(
+    dir: &Path,
+    build_dir: &Path,
+    mut ignore: Ignore,
+    found: &mut Vec<PathBuf>,
+) -> Result<(), ProjectError> {
+    ignore.read(dir);
+
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+        if !path.is_dir()
+            || hidden
+            || path == build_dir
+            || path.is_symlink()
+            || ignore.is_ignored(&path, true)
+        {
+            continue;
+        }
+
+        match path.join("cedar.toml").is_file() {
+            true => found.push(path),
+            false => discover(&path, build_dir, ignore.clone(), found)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn orders_members_then_found() {
+        let root = env::temp_dir().join(format!("cedar-subprojects-{}", process::id()));
+        for dir in ["", "a", "libs/b", "libs/b/nested", "build/c", ".hidden"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(
+                root.join(dir).join("cedar.toml"),
+                "[meta]\nname = \"x\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n",
+            )
+            .unwrap();
+        }
+
+        let mut project = Project::load(&root).unwrap();
+        project.manifest.subprojects.members = vec![String::from("libs/b")];
+
+        assert_eq!(
+            members(&project).unwrap(),
+            vec![root.join("libs/b"), root.join("a")]
+        );
+
+        project.manifest.subprojects.members = vec![String::from("missing")];
+        assert!(members(&project).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rebuilds_changes_since_the_stamp() {
+        let root = env::temp_dir().join(format!("cedar-stamp-{}", process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("cedar.toml"),
+            "[meta]\nname = \"x\"\nversion = \"0.1.0\"\n\n[build]\ncompiler = \"gcc\"\ncflags = []\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src").join("main.c"),
+            "int main(void) { return 0; }\n",
+        )
+        .unwrap();
+
+        let mut project = Project::load(&root).unwrap();
+
+        // Never built recursively.
+        assert!(modified_since_stamp(&project).unwrap());
+
+        let stamp = project.out_dir().join(STAMP);
+        fs::create_dir_all(stamp.parent().unwrap()).unwrap();
+        fs::write(&stamp, "").unwrap();
+        let stamped = fs::metadata(&stamp).unwrap().modified().unwrap();
+        let set_modified = |path: &Path, time| {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(time)
+                .unwrap()
+        };
+        set_modified(&root.join("cedar.toml"), stamped - Duration::from_secs(10));
+        set_modified(
+            &root.join("src").join("main.c"),
+            stamped - Duration::from_secs(10),
+        );
+
+        assert!(!modified_since_stamp(&project).unwrap());
+
+        // Another profile has a stamp of its own.
+        let profile = std::mem::replace(&mut project.profile, String::from("release"));
+        assert!(modified_since_stamp(&project).unwrap());
+        project.profile = profile;
+
+        set_modified(
+            &root.join("src").join("main.c"),
+            stamped + Duration::from_secs(10),
+        );
+        assert!(modified_since_stamp(&project).unwrap());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,579 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CedarError;
+
+use super::{
+    build::BuildError,
+    manifest::SystemDependency,
+    version::{Version, VersionReq},
+};
+
+/// A system library that was found, and how to compile and link with it.
+///
+/// # Fields
+///
+/// * 'name' - The name of the library in [system-deps], or of the package.
+/// * 'version' - The version pkg-config reports, None when it was probed.
+/// * 'cflags' - Flags every source compiles with.
+/// * 'libs' - Flags every artifact links with.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemLibrary {
+    pub name: String,
+    pub version: Option<String>,
+    pub cflags: Vec<String>,
+    pub libs: Vec<String>,
+}
+
+/// Libraries a package manager installed, kept in the build directory so it
+/// only runs again when what was asked of it changes.
+///
+/// # Fields
+///
+/// * 'key' - What was installed, e.g. the conanfile.
+/// * 'libraries' - The libraries it resolved to.
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Installed {
+    pub key: String,
+    pub libraries: Vec<SystemLibrary>,
+}
+
+impl Installed {
+    /// The libraries installed for the key, None if something else (or
+    /// nothing) was installed.
+    pub fn load(path: &Path, key: &str) -> Option<Vec<SystemLibrary>> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Self>(&contents).ok())
+            .filter(|installed| installed.key == key)
+            .map(|installed| installed.libraries)
+    }
+    pub fn save(&self, path: &Path) -> Result<(), CedarError> {
+        let contents = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        fs::write(path, contents).map_err(|err| CedarError::io(path, err))
+    }
+}
+
+/// A system library that was not found, or is too old or new.
+///
+/// # Fields
+///
+/// * 'name' - The name of the library in [system-deps].
+/// * 'requirement' - The version requirement, if there is one.
+/// * 'found' - The version that is installed instead, if any.
+/// * 'hint' - The command installing it with the package manager of the
+///   system, if cedar knows one.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingLibrary {
+    pub name: String,
+    pub requirement: Option<String>,
+    pub found: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl Display for MissingLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, " {}", self.name)?;
+
+        if let Some(requirement) = &self.requirement {
+            write!(f, " {}", requirement)?;
+        }
+
+        match &self.found {
+            Some(found) => writeln!(f, " (found {})", found)?,
+            None => writeln!(f, " (not found)")?,
+        }
+
+        match &self.hint {
+            Some(hint) => writeln!(f, "   install it with `{}`", hint),
+            None => writeln!(
+                f,
+                "   install it, or point cedar at it with header and lib in [system-deps]"
+            ),
+        }
+    }
+}
+
+/// The package managers cedar gives install hints for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Brew,
+    Nix,
+}
+
+impl PackageManager {
+    /// The package manager of the system, the first one on the PATH.
+    fn detect() -> Option<Self> {
+        let managers = [
+            ("brew", Self::Brew),
+            ("apt-get", Self::Apt),
+            ("dnf", Self::Dnf),
+            ("pacman", Self::Pacman),
+            ("nix-shell", Self::Nix),
+        ];
+
+        // Homebrew is the one to use on macOS even when others are installed.
+        let managers = match cfg!(target_os = "macos") {
+            true => &managers[..1],
+            false => &managers[1..],
+        };
+
+        managers
+            .iter()
+            .find(|(program, _)| on_path(program))
+            .map(|(_, manager)| *manager)
+    }
+    fn install(&self, package: &str) -> String {
+        match self {
+            Self::Apt => format!("sudo apt install {}", package),
+            Self::Dnf => format!("sudo dnf install {}", package),
+            Self::Pacman => format!("sudo pacman -S {}", package),
+            Self::Brew => format!("brew install {}", package),
+            Self::Nix => format!("nix-shell -p {}", package),
+        }
+    }
+}
+
+/// The packages providing common libraries, for apt, dnf, pacman, brew, and
+/// the attributes of nixpkgs.
+const PACKAGES: &[(&str, [&str; 5])] = &[
+    (
+        "openssl",
+        [
+            "libssl-dev",
+            "openssl-devel",
+            "openssl",
+            "openssl",
+            "openssl",
+        ],
+    ),
+    (
+        "libssl",
+        [
+            "libssl-dev",
+            "openssl-devel",
+            "openssl",
+            "openssl",
+            "openssl",
+        ],
+    ),
+    (
+        "libcrypto",
+        [
+            "libssl-dev",
+            "openssl-devel",
+            "openssl",
+            "openssl",
+            "openssl",
+        ],
+    ),
+    ("zlib", ["zlib1g-dev", "zlib-devel", "zlib", "zlib", "zlib"]),
+    (
+        "libcurl",
+        [
+            "libcurl4-openssl-dev",
+            "libcurl-devel",
+            "curl",
+            "curl",
+            "curl",
+        ],
+    ),
+    (
+        "sqlite3",
+        [
+            "libsqlite3-dev",
+            "sqlite-devel",
+            "sqlite",
+            "sqlite",
+            "sqlite",
+        ],
+    ),
+    (
+        "libpng",
+        ["libpng-dev", "libpng-devel", "libpng", "libpng", "libpng"],
+    ),
+    (
+        "libxml-2.0",
+        [
+            "libxml2-dev",
+            "libxml2-devel",
+            "libxml2",
+            "libxml2",
+            "libxml2",
+        ],
+    ),
+    (
+        "libzstd",
+        ["libzstd-dev", "libzstd-devel", "zstd", "zstd", "zstd"],
+    ),
+    ("liblzma", ["liblzma-dev", "xz-devel", "xz", "xz", "xz"]),
+    (
+        "libffi",
+        ["libffi-dev", "libffi-devel", "libffi", "libffi", "libffi"],
+    ),
+    (
+        "ncurses",
+        [
+            "libncurses-dev",
+            "ncurses-devel",
+            "ncurses",
+            "ncurses",
+            "ncurses",
+        ],
+    ),
+    (
+        "readline",
+        [
+            "libreadline-dev",
+            "readline-devel",
+            "readline",
+            "readline",
+            "readline",
+        ],
+    ),
+    (
+        "libuv",
+        ["libuv1-dev", "libuv-devel", "libuv", "libuv", "libuv"],
+    ),
+    (
+        "sdl2",
+        ["libsdl2-dev", "SDL2-devel", "sdl2", "sdl2", "SDL2"],
+    ),
+    (
+        "glib-2.0",
+        ["libglib2.0-dev", "glib2-devel", "glib2", "glib", "glib"],
+    ),
+    (
+        "gtk+-3.0",
+        ["libgtk-3-dev", "gtk3-devel", "gtk3", "gtk+3", "gtk3"],
+    ),
+    (
+        "libsodium",
+        [
+            "libsodium-dev",
+            "libsodium-devel",
+            "libsodium",
+            "libsodium",
+            "libsodium",
+        ],
+    ),
+];
+
+/// The package providing a library, from PACKAGES or else a guess following
+/// the naming conventions of the package manager.
+fn package(manager: PackageManager, name: &str) -> String {
+    let index = match manager {
+        PackageManager::Apt => 0,
+        PackageManager::Dnf => 1,
+        PackageManager::Pacman => 2,
+        PackageManager::Brew => 3,
+        PackageManager::Nix => 4,
+    };
+
+    if let Some((_, packages)) = PACKAGES.iter().find(|(library, _)| *library == name) {
+        return packages[index].to_owned();
+    }
+
+    let bare = name.strip_prefix("lib").unwrap_or(name);
+
+    match manager {
+        PackageManager::Apt => format!("lib{}-dev", bare),
+        PackageManager::Dnf => format!("{}-devel", name),
+        PackageManager::Pacman | PackageManager::Brew | PackageManager::Nix => name.to_owned(),
+    }
+}
+
+/// The nixpkgs attribute providing a library, e.g. curl for libcurl.
+pub fn nix_package(name: &str) -> String {
+    package(PackageManager::Nix, name)
+}
+
+/// The Debian package providing a library, e.g. libcurl4-openssl-dev for
+/// libcurl.
+pub fn apt_package(name: &str) -> String {
+    package(PackageManager::Apt, name)
+}
+
+/// Finds every system library, checking the versions pkg-config reports
+/// against their requirements, or else probing for their header and
+/// library with the compiler.
+///
+/// # Arguments
+///
+/// * 'system_deps' - The [system-deps] of the project and its dependencies.
+/// * 'build_path' - The build directory, probes are compiled in
+///   build/sysdeps/.
+/// * 'cc' - The C compiler to probe with.
+///
+/// # Returns
+///
+/// * The libraries, or BuildError::MissingSystemDependencies listing every
+///   one that is missing along with how to install it.
+///
+pub fn probe(
+    system_deps: &BTreeMap<String, SystemDependency>,
+    build_path: &Path,
+    cc: &str,
+) -> Result<Vec<SystemLibrary>, CedarError> {
+    let mut libraries = Vec::new();
+    let mut missing = Vec::new();
+
+    for (name, dependency) in system_deps {
+        let requirement = match dependency.version() {
+            Some(version) => Some(version.parse::<VersionReq>()?),
+            None => None,
+        };
+
+        let (module, header, lib) = match dependency {
+            SystemDependency::Version(_) => (name.as_str(), None, None),
+            SystemDependency::Detailed {
+                pkg_config,
+                header,
+                lib,
+                ..
+            } => (
+                pkg_config.as_deref().unwrap_or(name),
+                header.as_deref(),
+                lib.as_deref(),
+            ),
+        };
+
+        let mut found = None;
+
+        if let Some(version) = pkg_config(&["--modversion", module]) {
+            let meets = match (&requirement, lenient_version(&version)) {
+                (Some(requirement), Some(parsed)) => requirement.matches(&parsed),
+                (Some(_), None) => false,
+                (None, _) => true,
+            };
+
+            if meets {
+                libraries.push(SystemLibrary {
+                    name: name.clone(),
+                    cflags: split(pkg_config(&["--cflags", module])),
+                    libs: split(pkg_config(&["--libs", module])),
+                    version: Some(version),
+                });
+                continue;
+            }
+
+            found = Some(version);
+        } else if header.is_some() || lib.is_some() {
+            let probe_path = build_path.join("sysdeps");
+
+            if probe_library(&probe_path, name, header, lib, cc)? {
+                libraries.push(SystemLibrary {
+                    name: name.clone(),
+                    version: None,
+                    cflags: Vec::new(),
+                    libs: lib.map(|lib| format!("-l{}", lib)).into_iter().collect(),
+                });
+                continue;
+            }
+        }
+
+        missing.push(MissingLibrary {
+            name: name.clone(),
+            requirement: dependency.version().map(str::to_owned),
+            found,
+            hint: PackageManager::detect()
+                .map(|manager| manager.install(&package(manager, module))),
+        });
+    }
+
+    match missing.is_empty() {
+        true => Ok(libraries),
+        false => Err(BuildError::MissingSystemDependencies(missing).into()),
+    }
+}
+
+/// Runs pkg-config, None when it is not installed or does not know the
+/// module.
+fn pkg_config(args: &[&str]) -> Option<String> {
+    let output = process::Command::new("pkg-config")
+        .args(args)
+        .output()
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+fn split(flags: Option<String>) -> Vec<String> {
+    flags
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Compiles and links a program including the header and linking the
+/// library, to see whether the system has them.
+fn probe_library(
+    probe_path: &Path,
+    name: &str,
+    header: Option<&str>,
+    lib: Option<&str>,
+    cc: &str,
+) -> Result<bool, CedarError> {
+    fs::create_dir_all(probe_path).map_err(|err| CedarError::io(probe_path, err))?;
+
+    let source = probe_path.join(format!("{}.c", name));
+    let program = header
+        .map(|header| format!("#include <{}>\n", header))
+        .unwrap_or_default()
+        + "int main(void) { return 0; }\n";
+
+    fs::write(&source, program).map_err(|err| CedarError::io(&source, err))?;
+
+    let status = process::Command::new(cc)
+        .arg(&source)
+        .arg("-o")
+        .arg(probe_path.join(name))
+        .args(lib.map(|lib| format!("-l{}", lib)))
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status();
+
+    Ok(status.is_ok_and(|status| status.success()))
+}
+
+/// The flags for the libraries installed into a prefix, by vcpkg or CMake:
+/// the headers in include/, and every library in lib/. The libraries are
+/// linked in the order pkg-config gives when they ship .pc files.
+pub fn prefix_library(name: &str, prefix: &Path) -> Result<SystemLibrary, io::Error> {
+    let lib = prefix.join("lib");
+    let pkgconfig = lib.join("pkgconfig");
+
+    let mut libs = vec![format!("-L{}", lib.display())];
+
+    let modules: Vec<String> = match pkgconfig.is_dir() {
+        true => fs::read_dir(&pkgconfig)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.extension()? == "pc")
+                    .then(|| path.file_stem()?.to_str().map(str::to_owned))?
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+
+    if !modules.is_empty() {
+        let output = process::Command::new("pkg-config")
+            .args(["--libs-only-l", "--static"])
+            .args(&modules)
+            .env("PKG_CONFIG_PATH", &pkgconfig)
+            .output();
+
+        if let Some(output) = output.ok().filter(|output| output.status.success()) {
+            libs.extend(
+                String::from_utf8_lossy(&output.stdout)
+                    .split_whitespace()
+                    .map(str::to_owned),
+            );
+        }
+    }
+
+    let mut files: Vec<PathBuf> = match lib.is_dir() {
+        true => fs::read_dir(&lib)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect(),
+        false => Vec::new(),
+    };
+    files.sort();
+
+    for file in files {
+        if let Some(name) = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(library_name)
+        {
+            let flag = format!("-l{}", name);
+
+            if !libs.contains(&flag) {
+                libs.push(flag);
+            }
+        }
+    }
+
+    Ok(SystemLibrary {
+        name: name.to_owned(),
+        version: None,
+        cflags: vec![format!("-I{}", prefix.join("include").display())],
+        libs,
+    })
+}
+
+/// The name to link a library file by, libz.a and z.lib both being z.
+fn library_name(file: &str) -> Option<&str> {
+    match file.strip_suffix(".a") {
+        Some(stem) => stem.strip_prefix("lib"),
+        None => file.strip_suffix(".lib"),
+    }
+}
+
+/// Parses versions such as "1.1.1f" by their leading numbers.
+fn lenient_version(version: &str) -> Option<Version> {
+    let numbers: String = version
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+
+    numbers.trim_end_matches('.').parse().ok()
+}
+
+/// Whether a program is on the PATH.
+pub fn on_path(program: &str) -> bool {
+    which(program).is_some()
+}
+
+/// The path of a program on the PATH, the first one found. On Windows the
+/// program may also be named with .exe.
+pub fn which(program: &str) -> Option<PathBuf> {
+    let names = [
+        program.to_owned(),
+        format!("{}{}", program, env::consts::EXE_SUFFIX),
+    ];
+
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_packages_and_parses_versions() {
+        assert_eq!(package(PackageManager::Apt, "openssl"), "libssl-dev");
+        assert_eq!(package(PackageManager::Dnf, "libfoo"), "libfoo-devel");
+        assert_eq!(package(PackageManager::Apt, "libfoo"), "libfoo-dev");
+        assert_eq!(package(PackageManager::Brew, "zlib"), "zlib");
+        assert_eq!(package(PackageManager::Nix, "libcurl"), "curl");
+
+        assert_eq!(lenient_version("1.1.1f"), Some(Version::new(1, 1, 1)));
+        assert_eq!(lenient_version("3.0"), Some(Version::new(3, 0, 0)));
+        assert_eq!(lenient_version("unknown"), None);
+
+        assert_eq!(library_name("libz.a"), Some("z"));
+        assert_eq!(library_name("sqlite3.lib"), Some("sqlite3"));
+        assert_eq!(library_name("libz.so"), None);
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use super::{
+    build::{find_sources, target_triple, BuildError, Language},
+    ignore::{self, Ignore},
+    manifest::{Manifest, ProjectKind, Symlinks},
+};
+
+/// The kinds of targets a project can contain.
+///
+/// # Members
+///
+/// * 'Bin' - An executable, either the project itself (from src/main.c) or
+///   an extra binary from src/bin/.
+/// * 'Lib' - The library of a library project, static, shared, or both as
+///   given by the project type.
+/// * 'Example' - An executable from examples/, only built on request.
+/// * 'Test' - A test executable from tests/, built and run by cedar test.
+/// * 'Bench' - A benchmark executable from benches/, built and timed by
+///   cedar bench.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    Bin,
+    Lib,
+    Example,
+    Test,
+    Bench,
+}
+
+/// Source files paired with their language, as returned by find_sources.
+pub type Sources = Vec<(PathBuf, Language)>;
+
+/// Something the project builds.
+///
+/// Every target is linked from the objects of the shared library sources
+/// (see Project::library_sources) plus its own sources, so extra binaries and
+/// examples can use everything in src/ without a separate library build.
+///
+/// # Fields
+///
+/// * 'name' - The name of the target, which is also the name of its output.
+/// * 'kind' - What kind of target it is.
+/// * 'sources' - The translation units only this target uses, e.g. the file
+///   holding its main function.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Target {
+    pub name: String,
+    pub kind: TargetKind,
+    pub sources: Sources,
+}
+
+/// Picks targets out of a project, e.g. for --bin and --example.
+///
+/// # Members
+///
+/// * 'Bin' - The bin target with the given name.
+/// * 'Lib' - The library of a library project.
+/// * 'Example' - The example with the given name.
+/// * 'Examples' - Every example.
+/// * 'Test' - The test with the given name.
+/// * 'Tests' - Every test.
+/// * 'Bench' - The benchmark with the given name.
+/// * 'All' - Every target, examples included.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetFilter {
+    Bin(String),
+    Lib,
+    Example(String),
+    Examples,
+    Test(String),
+    Tests,
+    Bench(String),
+    All,
+}
+
+impl TargetFilter {
+    /// Whether the filter picks the target.
+    pub fn matches(&self, target: &Target) -> bool {
+        match self {
+            Self::Bin(name) => target.kind == TargetKind::Bin && &target.name == name,
+            Self::Example(name) => target.kind == TargetKind::Example && &target.name == name,
+            Self::Lib => target.kind == TargetKind::Lib,
+            Self::Examples => target.kind == TargetKind::Example,
+            Self::Test(name) => target.kind == TargetKind::Test && &target.name == name,
+            Self::Tests => target.kind == TargetKind::Test,
+            Self::Bench(name) => target.kind == TargetKind::Bench && &target.name == name,
+            Self::All => true,
+        }
+    }
+    /// Checks that the filter picks a target of the project.
+    pub fn validate(&self, targets: &[Target]) -> Result<(), BuildError> {
+        if targets.iter().any(|target| self.matches(target)) {
+            return Ok(());
+        }
+
+        let (kind, name) = match self {
+            Self::Bin(name) => (TargetKind::Bin, name),
+            Self::Example(name) => (TargetKind::Example, name),
+            Self::Test(name) => (TargetKind::Test, name),
+            Self::Bench(name) => (TargetKind::Bench, name),
+            Self::Lib => (TargetKind::Lib, &targets[0].name),
+            // Selecting every example or target is fine when there are none.
+            Self::Examples | Self::Tests | Self::All => return Ok(()),
+        };
+
+        Err(BuildError::UnknownTarget {
+            name: name.clone(),
+            kind: kind.as_str(),
+            available: targets
+                .iter()
+                .filter(|target| target.kind == kind)
+                .map(|target| target.name.clone())
+                .collect(),
+        })
+    }
+}
+
+/// An output of the build, named by its file name.
+///
+/// # Members
+///
+/// * 'Executable' - The program of a bin or example target, with the
+///   executable suffix of the host (.exe on Windows).
+/// * 'StaticLib' - A static library archive, lib<name>.a.
+/// * 'SharedLib' - A shared library. With an abi_version the name is the
+///   fully versioned lib<name>.so.1.2.3, and soname holds lib<name>.so.1
+///   which (along with lib<name>.so) is created as a link to it.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Artifact {
+    Executable(String),
+    StaticLib(String),
+    SharedLib {
+        name: String,
+        soname: Option<String>,
+    },
+}
+
+impl Artifact {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Executable(name) | Self::StaticLib(name) => name,
+            Self::SharedLib { name, .. } => name,
+        }
+    }
+}
+
+impl TargetKind {
+    /// The lowercase name of the kind, as in the metadata.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bin => "bin",
+            Self::Lib => "lib",
+            Self::Example => "example",
+            Self::Test => "test",
+            Self::Bench => "bench",
+        }
+    }
+    /// Whether targets of the kind are built by default and installed,
+    /// examples, tests, and benchmarks are only built on request.
+    pub fn is_default(&self) -> bool {
+        matches!(self, Self::Bin | Self::Lib)
+    }
+}
+
+impl Target {
+    /// Gets the artifacts of the target, validating the abi_version of shared
+    /// libraries.
+    pub fn artifacts(&self, manifest: &Manifest) -> Result<Vec<Artifact>, BuildError> {
+        let name = &self.name;
+        let kind = manifest.meta.kind;
+
+        if self.kind != TargetKind::Lib {
+            if let (Some(template), TargetKind::Bin) = (&manifest.build.artifact_name, self.kind) {
+                artifact_name(template, name, manifest).map_err(|placeholder| {
+                    BuildError::InvalidArtifactName(template.clone(), placeholder)
+                })?;
+            }
+
+            return Ok(vec![Artifact::Executable(self.executable(manifest))]);
+        }
+
+        let mut artifacts = Vec::new();
+
+        if kind.is_static() {
+            artifacts.push(Artifact::StaticLib(format!("lib{}.a", name)));
+        }
+
+        if kind.is_shared() {
+            artifacts.push(match &manifest.build.abi_version {
+                Some(abi_version) => {
+                    let major = abi_version.split('.').next().unwrap_or_default();
+
+                    if major.is_empty() || !major.chars().all(|ch| ch.is_ascii_digit()) {
+                        return Err(BuildError::InvalidAbiVersion(abi_version.clone()));
+                    }
+
+                    Artifact::SharedLib {
+                        name: format!("lib{}.so.{}", name, abi_version),
+                        soname: Some(format!("lib{}.so.{}", name, major)),
+                    }
+                }
+                None => Artifact::SharedLib {
+                    name: format!("lib{}.so", name),
+                    soname: None,
+                },
+            });
+        }
+
+        Ok(artifacts)
+    }
+    /// The file name of the program of a bin, example, test, or bench
+    /// target on the host, <name>.exe on Windows. Binaries are named after
+    /// artifact_name in [build] when the manifest has one.
+    pub fn executable(&self, manifest: &Manifest) -> String {
+        let name = match &manifest.build.artifact_name {
+            Some(template) if self.kind == TargetKind::Bin => {
+                artifact_name(template, &self.name, manifest).unwrap_or_else(|_| self.name.clone())
+            }
+            _ => self.name.clone(),
+        };
+
+        // A template may already end in the suffix.
+        match name.ends_with(env::consts::EXE_SUFFIX) {
+            true => name,
+            false => format!("{}{}", name, env::consts::EXE_SUFFIX),
+        }
+    }
+    /// The directory (relative to the out_dir of the project) the artifacts
+    /// are written to, examples are kept apart so they cannot clash with
+    /// binaries.
+    pub fn output_dir(&self) -> &'static str {
+        match self.kind {
+            TargetKind::Example => "examples",
+            TargetKind::Test => "tests",
+            TargetKind::Bench => "benches",
+            _ => "",
+        }
+    }
+}
+
+/// Fills in an artifact_name template: {name} is the name of the target,
+/// {version} the version of the project, and {target} what the compiler
+/// builds for, e.g. x86_64-linux-gnu.
+///
+/// # Returns
+///
+/// * The name, or the unknown placeholder as the error.
+///
+pub fn artifact_name(template: &str, name: &str, manifest: &Manifest) -> Result<String, String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+
+        let Some(end) = rest[start..].find('}') else {
+            return Err(rest[start + 1..].to_owned());
+        };
+
+        match &rest[start + 1..start + end] {
+            "name" => rendered.push_str(name),
+            "version" => rendered.push_str(&manifest.meta.version),
+            "target" => rendered.push_str(target_triple(&manifest.build.compiler)),
+            placeholder => return Err(placeholder.to_owned()),
+        }
+
+        rest = &rest[start + end + 1..];
+    }
+
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Discovers the targets of a project.
+///
+/// * The project itself: a bin target named after the project whose own
+///   source is src/main.* for bin projects, or a lib target otherwise.
+///   Header-only projects have no sources of their own, src/ is not
+///   looked at.
+/// * src/bin/<name>.c or src/bin/<name>/ - extra binaries.
+/// * examples/<name>.c or examples/<name>/ - examples.
+/// * tests/<name>.c or tests/<name>/ - tests.
+/// * benches/<name>.c or benches/<name>/ - benchmarks.
+///
+/// # Returns
+///
+/// * The targets and the library sources shared by all of them.
+///
+pub fn discover(root: &Path, manifest: &Manifest) -> Result<(Vec<Target>, Sources), io::Error> {
+    let src = root.join("src");
+    let bin = src.join("bin");
+
+    let mut library = Vec::new();
+    let mut entry = Vec::new();
+
+    let symlinks = manifest.build.symlinks;
+
+    let sources = match manifest.meta.kind.is_header_only() {
+        true => Vec::new(),
+        false => find_sources(&src, symlinks)?,
+    };
+
+    for (source, lang) in sources {
+        if source.starts_with(&bin) {
+            continue;
+        }
+
+        let is_main = source.parent() == Some(src.as_path())
+            && source.file_stem().is_some_and(|stem| stem == "main");
+
+        if is_main && manifest.meta.kind == ProjectKind::Bin {
+            entry.push((source, lang));
+        } else {
+            library.push((source, lang));
+        }
+    }
+
+    let mut targets = vec![Target {
+        name: manifest.meta.name.clone(),
+        kind: if manifest.meta.kind.is_bin() {
+            TargetKind::Bin
+        } else {
+            TargetKind::Lib
+        },
+        sources: entry,
+    }];
+
+    if !manifest.meta.kind.is_header_only() {
+        targets.extend(discover_dir(&bin, TargetKind::Bin, symlinks)?);
+    }
+    targets.extend(discover_dir(
+        &root.join("examples"),
+        TargetKind::Example,
+        symlinks,
+    )?);
+    targets.extend(discover_dir(
+        &root.join("tests"),
+        TargetKind::Test,
+        symlinks,
+    )?);
+    targets.extend(discover_dir(
+        &root.join("benches"),
+        TargetKind::Bench,
+        symlinks,
+    )?);
+
+    Ok((targets, library))
+}
+
+/// Finds the single file and directory targets in a directory, sorted by
+/// name.
+fn discover_dir(
+    path: &Path,
+    kind: TargetKind,
+    symlinks: Symlinks,
+) -> Result<Vec<Target>, io::Error> {
+    if !path.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut targets = Vec::new();
+    let ignore = Ignore::for_dir(path);
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?.path();
+
+        if ignore.is_ignored(&entry, entry.is_dir()) || !ignore::keep(&entry, symlinks)? {
+            continue;
+        }
+
+        let sources = if entry.is_dir() {
+            find_sources(&entry, symlinks)?
+        } else {
+            match Language::from_path(&entry) {
+                Some(lang) => vec![(entry.clone(), lang)],
+                None => continue,
+            }
+        };
+
+        let name = match entry.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) if !sources.is_empty() => name.to_owned(),
+            _ => continue,
+        };
+
+        targets.push(Target {
+            name,
+            kind,
+            sources,
+        });
+    }
+
+    targets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        let target = |name: &str, kind| Target {
+            name: name.to_owned(),
+            kind,
+            sources: Vec::new(),
+        };
+        let targets = [
+            target("app", TargetKind::Bin),
+            target("tool", TargetKind::Bin),
+            target("demo", TargetKind::Example),
+        ];
+
+        assert!(TargetFilter::Bin("tool".into()).matches(&targets[1]));
+        assert!(!TargetFilter::Bin("demo".into()).matches(&targets[2]));
+        assert!(TargetFilter::Examples.matches(&targets[2]));
+        assert!(TargetFilter::Lib.validate(&targets).is_err());
+        assert!(TargetFilter::Examples.validate(&targets).is_ok());
+    }
+
+    #[test]
+    fn renders_artifact_names() {
+        let mut manifest = Manifest::new();
+        manifest.meta.version = String::from("1.2.0");
+
+        assert_eq!(
+            artifact_name("{name}-{version}", "app", &manifest).unwrap(),
+            "app-1.2.0"
+        );
+        assert_eq!(
+            artifact_name("{name}-{arch}", "app", &manifest).unwrap_err(),
+            "arch"
+        );
+
+        manifest.build.artifact_name = Some(String::from("{name}-v{version}"));
+        let bin = Target {
+            name: String::from("app"),
+            kind: TargetKind::Bin,
+            sources: Vec::new(),
+        };
+
+        assert_eq!(
+            bin.executable(&manifest),
+            format!("app-v1.2.0{}", env::consts::EXE_SUFFIX)
+        );
+    }
+}
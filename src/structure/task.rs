@@ -0,0 +1,161 @@
+use std::{collections::BTreeMap, process};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    build::{build, BuildOptions},
+    manifest::Task,
+    ninja,
+    project::{Project, ProjectError},
+};
+
+/// The tasks to run for a task, the tasks it depends on (and theirs) first,
+/// each once.
+///
+/// # Returns
+///
+/// * The names of the tasks in the order they run, the task itself last.
+///
+pub fn order<'a>(
+    tasks: &'a BTreeMap<String, Task>,
+    name: &'a str,
+) -> Result<Vec<&'a str>, ProjectError> {
+    fn visit<'a>(
+        tasks: &'a BTreeMap<String, Task>,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) -> Result<(), ProjectError> {
+        if order.contains(&name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|task| *task == name) {
+            let mut cycle: Vec<String> =
+                path[start..].iter().map(|task| task.to_string()).collect();
+            cycle.push(name.to_owned());
+            return Err(ProjectError::TaskCycle(cycle));
+        }
+
+        let task = tasks.get(name).ok_or_else(|| {
+            ProjectError::UnknownTask(name.to_owned(), tasks.keys().cloned().collect())
+        })?;
+
+        path.push(name);
+        for dependency in task.depends() {
+            visit(tasks, dependency, path, order)?;
+        }
+        path.pop();
+
+        order.push(name);
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(tasks, name, &mut Vec::new(), &mut order)?;
+
+    Ok(order)
+}
+
+/// Runs a task of [tasks] in the project root, after the tasks it depends
+/// on, and after building the project when any of them needs its artifacts.
+///
+/// The commands run in the shell with CEDAR_OUT_DIR set to the directory of
+/// the artifacts and CEDAR_PROFILE to the profile.
+///
+/// # Arguments
+///
+/// * 'project' - The project the task is in.
+/// * 'name' - The task to run.
+/// * 'args' - Appended to every command of the task itself, not to the
+///   tasks it depends on.
+///
+pub fn run(project: &Project, name: &str, args: &[String]) -> Result<(), CedarError> {
+    let tasks = &project.manifest.tasks;
+    let order = order(tasks, name)?;
+
+    if order.iter().any(|task| tasks[*task].builds()) {
+        build(project, &BuildOptions::default())?;
+    }
+
+    let extra: String = args
+        .iter()
+        .map(|arg| format!(" {}", ninja::quote(arg)))
+        .collect();
+
+    for task in order {
+        for command in tasks[task].commands() {
+            let line = match task == name {
+                true => format!("{}{}", command, extra),
+                false => command.clone(),
+            };
+
+            println!("\t{}{}: {}", style::header("Task "), task, line);
+
+            let mut shell = shell(&line);
+            shell
+                .current_dir(&project.root)
+                .env("CEDAR_OUT_DIR", project.out_dir())
+                .env("CEDAR_PROFILE", &project.profile);
+
+            let status = shell
+                .status()
+                .map_err(|err| CedarError::command(&shell, err))?;
+
+            if !status.success() {
+                return Err(CedarError::TaskFailed {
+                    task: task.to_owned(),
+                    command: line,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A command running a command line in the shell, sh or cmd on Windows.
+pub(crate) fn shell(line: &str) -> process::Command {
+    let mut shell = match cfg!(windows) {
+        true => {
+            let mut shell = process::Command::new("cmd");
+            shell.arg("/C");
+            shell
+        }
+        false => {
+            let mut shell = process::Command::new("sh");
+            shell.arg("-c");
+            shell
+        }
+    };
+    shell.arg(line);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_task_dependencies() {
+        let tasks: BTreeMap<String, Task> = toml::from_str(
+            r#"
+gen = "python gen.py"
+docs = { run = ["doxygen"], depends = ["gen"] }
+all = { depends = ["docs", "gen"] }
+a = { depends = ["b"] }
+b = { depends = ["a"] }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(order(&tasks, "all").unwrap(), vec!["gen", "docs", "all"]);
+        assert!(matches!(
+            order(&tasks, "a"),
+            Err(ProjectError::TaskCycle(cycle)) if cycle == ["a", "b", "a"]
+        ));
+        assert!(matches!(
+            order(&tasks, "lint"),
+            Err(ProjectError::UnknownTask(..))
+        ));
+    }
+}
@@ -0,0 +1,781 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{cli::CliError, error::CedarError, style};
+
+use super::{
+    build::{build, BuildOptions, Language},
+    limits,
+    project::{Project, ProjectError},
+    snapshot::{self, snapshot_path},
+    target::{Sources, Target, TargetFilter, TargetKind},
+    timeout, trace,
+};
+
+/// The bundled unit test harness, available to tests as <cedar/test.h>.
+pub const HARNESS: &str = include_str!("harness/test.h");
+
+/// The environment variable holding the temporary directory of a test.
+pub const TMPDIR_VAR: &str = "CEDAR_TEST_TMPDIR";
+
+/// Options controlling a test run.
+///
+/// # Fields
+///
+/// * 'quiet' - Suppresses the build and per test output, the results are
+///   only returned in the report.
+/// * 'filter' - Only runs the tests whose name contains the pattern. Test
+///   binaries using the bundled harness also run when some of their
+///   TEST cases match, running only those cases.
+/// * 'skip' - Skips the tests (and TEST cases) whose name contains any of
+///   the patterns.
+/// * 'jobs' - How many tests run at once, the number of cores by default.
+/// * 'nocapture' - Prints the output of every test as it finishes, not just
+///   the output of the failed ones afterwards.
+/// * 'update_snapshots' - Rewrites the snapshots in tests/snapshots/ with
+///   the stdout of their tests instead of comparing against them.
+/// * 'timeout' - How long a test may run before its process group is killed
+///   and it fails, overriding the timeout in [test].
+/// * 'wrap' - A command every test binary is run with, such as "valgrind -q",
+///   overriding the wrapper in [run].
+///
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    pub quiet: bool,
+    pub filter: Option<String>,
+    pub skip: Vec<String>,
+    pub jobs: Option<usize>,
+    pub nocapture: bool,
+    pub update_snapshots: bool,
+    pub timeout: Option<Duration>,
+    pub wrap: Option<String>,
+}
+
+impl TestOptions {
+    /// Whether a name is picked by the filter and not skipped.
+    fn selects(&self, name: &str) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| name.contains(filter.as_str()))
+            && !self.skip.iter().any(|skip| name.contains(skip.as_str()))
+    }
+}
+
+/// The outcome of a single test binary.
+///
+/// # Fields
+///
+/// * 'name' - The name of the test, its file or directory name in tests/.
+/// * 'passed' - Whether it exited successfully, and its stdout matched its
+///   snapshot if it has one.
+/// * 'output' - Its captured stdout and stderr.
+/// * 'elapsed' - How long it ran.
+///
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub passed: bool,
+    pub output: String,
+    pub elapsed: Duration,
+}
+
+/// The outcome of a test run.
+#[derive(Debug, Clone, Default)]
+pub struct TestReport {
+    pub results: Vec<TestResult>,
+}
+
+impl TestReport {
+    /// The results of the tests that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &TestResult> {
+        self.results.iter().filter(|result| !result.passed)
+    }
+    /// Renders the report in a machine readable format.
+    ///
+    /// # Arguments
+    ///
+    /// * 'format' - The format to render.
+    /// * 'suite' - The name of the test suite, the project name.
+    ///
+    pub fn render(&self, format: ReportFormat, suite: &str) -> String {
+        match format {
+            ReportFormat::Junit => self.junit(suite),
+            ReportFormat::Tap => self.tap(),
+        }
+    }
+    /// Renders the report as JUnit XML, as read by GitLab, Jenkins, and
+    /// most other CI systems.
+    fn junit(&self, suite: &str) -> String {
+        let failures = self.failures().count();
+        let time: Duration = self.results.iter().map(|result| result.elapsed).sum();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites tests=\"{0}\" failures=\"{1}\" time=\"{2:.3}\">\n  <testsuite name=\"{3}\" tests=\"{0}\" failures=\"{1}\" time=\"{2:.3}\">\n",
+            self.results.len(),
+            failures,
+            time.as_secs_f64(),
+            xml_escape(suite)
+        ));
+
+        for result in &self.results {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(&result.name),
+                xml_escape(suite),
+                result.elapsed.as_secs_f64()
+            ));
+
+            if !result.passed {
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    xml_escape(&failure_message(&result.output)),
+                    xml_escape(&result.output)
+                ));
+            }
+
+            // The output of failures is already the body of the failure.
+            if result.passed && !result.output.is_empty() {
+                xml.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    xml_escape(&result.output)
+                ));
+            }
+
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+    /// Renders the report as TAP version 13, with the duration and failure
+    /// message of each test in a YAML block.
+    fn tap(&self) -> String {
+        let mut tap = format!("TAP version 13\n1..{}\n", self.results.len());
+
+        for (index, result) in self.results.iter().enumerate() {
+            let status = if result.passed { "ok" } else { "not ok" };
+
+            tap.push_str(&format!("{} {} - {}\n", status, index + 1, result.name));
+            tap.push_str(&format!(
+                "  ---\n  duration_ms: {:.3}\n",
+                result.elapsed.as_secs_f64() * 1000.0
+            ));
+
+            if !result.passed {
+                tap.push_str(&format!(
+                    "  message: {:?}\n",
+                    failure_message(&result.output)
+                ));
+
+                if !result.output.is_empty() {
+                    tap.push_str("  output: |\n");
+
+                    for line in result.output.lines() {
+                        tap.push_str(&format!("    {}\n", line));
+                    }
+                }
+            }
+
+            tap.push_str("  ...\n");
+        }
+
+        tap
+    }
+}
+
+/// A machine readable test report format.
+///
+/// # Members
+///
+/// * 'Junit' - JUnit XML.
+/// * 'Tap' - The Test Anything Protocol.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Junit,
+    Tap,
+}
+
+impl FromStr for ReportFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "junit" => Ok(Self::Junit),
+            "tap" => Ok(Self::Tap),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, --report expects junit or tap.",
+                s
+            ))),
+        }
+    }
+}
+
+/// The message summarizing why a test failed: the first line of its output
+/// that is not the status of a passing TEST case.
+fn failure_message(output: &str) -> String {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.ends_with(" ... ok"))
+        .unwrap_or("exited with a failure status")
+        .to_owned()
+}
+
+/// Escapes text for use in XML attributes and content.
+fn xml_escape(text: &str) -> String {
+    text.chars()
+        .filter(|ch| !ch.is_control() || matches!(ch, '\n' | '\t' | '\r'))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Builds every test in tests/, each linked against the library sources of
+/// the project, then runs them in parallel.
+///
+/// # Arguments
+///
+/// * 'project' - The project to test.
+/// * 'options' - How to test.
+///
+/// # Returns
+///
+/// * The result of every test, failing tests do not make this an error so
+///   callers can report them.
+///
+pub fn test(project: &Project, options: &TestOptions) -> Result<TestReport, CedarError> {
+    let mut report = TestReport::default();
+    let tests = select_tests(project, options)?;
+
+    if tests.is_empty() {
+        return Ok(report);
+    }
+
+    build_tests(project, options, &tests)?;
+
+    let timeouts = tests
+        .iter()
+        .map(|(target, _)| test_timeout(project, options, &target.name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let jobs = options
+        .jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .clamp(1, tests.len());
+
+    // Workers take the next test off a shared index, each test's output is
+    // captured and printed as one block once it finishes so nothing
+    // interleaves.
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+
+                let Some((target, args)) = tests.get(index) else {
+                    break;
+                };
+
+                let result = run_test(
+                    project,
+                    target,
+                    args,
+                    options.wrap.as_deref(),
+                    timeouts[index],
+                    options.update_snapshots,
+                );
+
+                if let (Ok(result), false) = (&result, options.quiet) {
+                    print_result(result, options.nocapture);
+                }
+
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+
+    for (_, result) in results {
+        report.results.push(result?);
+    }
+
+    Ok(report)
+}
+
+/// How long a test may run: its entry in [test.timeouts], then --timeout,
+/// then the timeout in [test].
+fn test_timeout(
+    project: &Project,
+    options: &TestOptions,
+    name: &str,
+) -> Result<Option<Duration>, CedarError> {
+    let config = &project.manifest.test;
+    let parse = |text: &String| {
+        timeout::parse(text).ok_or_else(|| ProjectError::InvalidDuration(text.clone()))
+    };
+
+    Ok(match config.timeouts.get(name) {
+        Some(text) => Some(parse(text)?),
+        None => match options.timeout {
+            Some(timeout) => Some(timeout),
+            None => config.timeout.as_ref().map(parse).transpose()?,
+        },
+    })
+}
+
+/// Picks the tests to run, along with the arguments narrowing down the cases
+/// the harness runs when only some of them were picked.
+fn select_tests<'a>(
+    project: &'a Project,
+    options: &TestOptions,
+) -> Result<Vec<(&'a Target, Vec<String>)>, CedarError> {
+    let mut tests = Vec::new();
+
+    for target in &project.targets {
+        if target.kind != TargetKind::Test
+            || options
+                .skip
+                .iter()
+                .any(|skip| target.name.contains(skip.as_str()))
+        {
+            continue;
+        }
+
+        let cases = target_cases(target)?;
+        let mut args = Vec::new();
+
+        // Only binaries using the harness understand the arguments.
+        if !cases.is_empty() {
+            for skip in &options.skip {
+                args.extend([String::from("--skip"), skip.clone()]);
+            }
+        }
+
+        if !options.selects(&target.name) {
+            if !cases.iter().any(|case| options.selects(case)) {
+                continue;
+            }
+
+            args.extend(options.filter.clone());
+        }
+
+        tests.push((target, args));
+    }
+
+    Ok(tests)
+}
+
+/// Builds the selected tests.
+fn build_tests(
+    project: &Project,
+    options: &TestOptions,
+    tests: &[(&Target, Vec<String>)],
+) -> Result<(), CedarError> {
+    let build_options = BuildOptions {
+        quiet: options.quiet,
+        targets: tests
+            .iter()
+            .map(|(target, _)| TargetFilter::Test(target.name.clone()))
+            .collect(),
+        ..Default::default()
+    };
+
+    build(project, &build_options)?;
+    Ok(())
+}
+
+/// Builds the selected tests and writes build/CTestTestfile.cmake, which
+/// runs them with ctest (`ctest --test-dir build`). Snapshots and fixtures
+/// are only handled when cedar runs the tests.
+///
+/// # Returns
+///
+/// * The path of the written file.
+///
+pub fn export_ctest(project: &Project, options: &TestOptions) -> Result<PathBuf, CedarError> {
+    let tests = select_tests(project, options)?;
+
+    if !tests.is_empty() {
+        build_tests(project, options, &tests)?;
+    }
+
+    let timeouts = tests
+        .iter()
+        .map(|(target, _)| test_timeout(project, options, &target.name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let path = project.build_dir.join("CTestTestfile.cmake");
+    let contents = ctest_file(project, &tests, &timeouts);
+
+    fs::write(&path, contents).map_err(|err| CedarError::io(&path, err))?;
+
+    Ok(path)
+}
+
+/// Generates a CTestTestfile.cmake adding every test with its arguments, and
+/// its timeout when it has one.
+fn ctest_file(
+    project: &Project,
+    tests: &[(&Target, Vec<String>)],
+    timeouts: &[Option<Duration>],
+) -> String {
+    let mut file = String::from("# Generated by cedar test --export ctest, do not edit.\n\n");
+
+    for ((target, args), timeout) in tests.iter().zip(timeouts) {
+        let mut command = vec![project.output(target).to_string_lossy().into_owned()];
+        command.extend(args.iter().cloned());
+
+        let command: Vec<String> = command.iter().map(|arg| cmake_bracket(arg)).collect();
+
+        file.push_str(&format!(
+            "add_test({} {})\nset_tests_properties({} PROPERTIES WORKING_DIRECTORY {})\n",
+            cmake_bracket(&target.name),
+            command.join(" "),
+            cmake_bracket(&target.name),
+            cmake_bracket(&project.root.to_string_lossy())
+        ));
+
+        if let Some(timeout) = timeout {
+            file.push_str(&format!(
+                "set_tests_properties({} PROPERTIES TIMEOUT {})\n",
+                cmake_bracket(&target.name),
+                timeout.as_secs_f64()
+            ));
+        }
+    }
+
+    file
+}
+
+/// Quotes a CMake argument as a bracket argument, which needs no escaping.
+fn cmake_bracket(arg: &str) -> String {
+    let mut equals = String::new();
+
+    while arg.contains(&format!("]{}]", equals)) {
+        equals.push('=');
+    }
+
+    format!("[{0}[{1}]{0}]", equals, arg)
+}
+
+/// Runs a single test binary in a fresh temporary directory holding the
+/// fixtures, capturing its output and checking its stdout against its
+/// snapshot. The directory is removed afterwards unless the test failed, or
+/// it ran past its timeout and was killed.
+fn run_test(
+    project: &Project,
+    target: &Target,
+    args: &[String],
+    wrap: Option<&str>,
+    timeout: Option<Duration>,
+    update_snapshots: bool,
+) -> Result<TestResult, CedarError> {
+    let tmp = project.build_dir.join("tmp").join(&target.name);
+    prepare_tmp(project, &tmp)?;
+
+    let run = &project.manifest.run;
+    let mut command = limits::command(&project.output(target), run.wrapper(wrap), &run.limits)?;
+    command
+        .args(args)
+        .current_dir(&project.root)
+        .env(TMPDIR_VAR, &tmp);
+
+    let now = Instant::now();
+    let span = trace::span("test", &target.name);
+    let output =
+        timeout::output(&mut command, timeout).map_err(|err| CedarError::command(&command, err))?;
+    drop(span);
+
+    let elapsed = now.elapsed();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let snapshot = snapshot_path(project, &target.name);
+
+    // Only the full run of a test is snapshotted, not a filtered one or one
+    // cut short.
+    let mismatch = match args.is_empty() && output.status.is_some() {
+        true => snapshot::check(&snapshot, &stdout, update_snapshots)?,
+        false => None,
+    };
+
+    // The cause leads the output so it is what a report names.
+    let mut text = match (&output.status, &mismatch) {
+        (None, _) => format!(
+            "timed out after {}, killed\n",
+            timeout::display(timeout.unwrap_or_default())
+        ),
+        (Some(_), Some(diff)) => format!(
+            "stdout does not match {}, rerun with --update-snapshots if intended:\n{}",
+            snapshot.display(),
+            diff
+        ),
+        (Some(_), None) => String::new(),
+    };
+    text.push_str(&stdout);
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    let passed = output.status.is_some_and(|status| status.success()) && mismatch.is_none();
+
+    if passed {
+        fs::remove_dir_all(&tmp).map_err(|err| CedarError::io(&tmp, err))?;
+    } else {
+        text.push_str(&format!("temporary directory kept at {}\n", tmp.display()));
+    }
+
+    Ok(TestResult {
+        name: target.name.clone(),
+        passed,
+        output: text,
+        elapsed,
+    })
+}
+
+/// Recreates the temporary directory of a test with the fixtures declared in
+/// the manifest copied in.
+fn prepare_tmp(project: &Project, tmp: &Path) -> Result<(), CedarError> {
+    if tmp.exists() {
+        fs::remove_dir_all(tmp).map_err(|err| CedarError::io(tmp, err))?;
+    }
+
+    fs::create_dir_all(tmp).map_err(|err| CedarError::io(tmp, err))?;
+
+    for fixture in &project.manifest.test.fixtures {
+        let source = project.root.join(fixture);
+
+        let Some(name) = source.file_name() else {
+            continue;
+        };
+
+        copy_all(&source, &tmp.join(name))?;
+    }
+
+    Ok(())
+}
+
+/// Copies a file, or a directory and everything in it.
+fn copy_all(source: &Path, destination: &Path) -> Result<(), CedarError> {
+    if !source.is_dir() {
+        fs::copy(source, destination).map_err(|err| CedarError::io(source, err))?;
+        return Ok(());
+    }
+
+    fs::create_dir_all(destination).map_err(|err| CedarError::io(destination, err))?;
+
+    for entry in fs::read_dir(source).map_err(|err| CedarError::io(source, err))? {
+        let entry = entry.map_err(|err| CedarError::io(source, err))?;
+        copy_all(&entry.path(), &destination.join(entry.file_name()))?;
+    }
+
+    Ok(())
+}
+
+/// Prints the status of a finished test, along with its output when not
+/// captured.
+fn print_result(result: &TestResult, nocapture: bool) {
+    let status = match result.passed {
+        true => style::header("ok"),
+        false => style::red("FAILED"),
+    };
+
+    let mut block = format!("\ttest {} ... {}\n", result.name, status);
+
+    if nocapture && !result.output.is_empty() {
+        block.push_str(&result.output);
+    }
+
+    // One write per test keeps the blocks of concurrent tests apart.
+    print!("{}", block);
+}
+
+/// Prepares the bundled harness for a test target: writes cedar/test.h into
+/// build/include/ and, when the sources of the test define TEST cases,
+/// generates the main function that runs them.
+///
+/// # Returns
+///
+/// * The generated main as an extra source of the target, nothing when the
+///   test has no TEST cases (and so brings its own main).
+///
+pub(crate) fn harness(project: &Project, target: &Target) -> Result<Sources, CedarError> {
+    let (header, main) = harness_paths(project, target);
+    write_if_changed(&header, HARNESS).map_err(|err| CedarError::io(&header, err))?;
+
+    match harness_main(target)? {
+        Some(source) => {
+            write_if_changed(&main, &source).map_err(|err| CedarError::io(&main, err))?;
+            Ok(vec![(main, Language::C)])
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Where the harness of a test target goes: cedar/test.h under
+/// build/include/, and the generated main under build/tests/harness/.
+pub(crate) fn harness_paths(project: &Project, target: &Target) -> (PathBuf, PathBuf) {
+    let header = project
+        .build_dir
+        .join("include")
+        .join("cedar")
+        .join("test.h");
+    let main = project
+        .build_dir
+        .join("tests")
+        .join("harness")
+        .join(format!("{}.c", target.name));
+
+    (header, main)
+}
+
+/// The generated main of a test target, None when the test has no TEST
+/// cases.
+pub(crate) fn harness_main(target: &Target) -> Result<Option<String>, CedarError> {
+    let cases = target_cases(target)?;
+
+    Ok(Some(cases)
+        .filter(|cases| !cases.is_empty())
+        .map(|cases| generate_main(&cases)))
+}
+
+/// Finds the names of the TEST cases defined in the sources of a target.
+fn target_cases(target: &Target) -> Result<Vec<String>, CedarError> {
+    let mut cases = Vec::new();
+
+    for (source, _) in &target.sources {
+        let contents = fs::read_to_string(source).map_err(|err| CedarError::io(source, err))?;
+        cases.extend(test_cases(&contents));
+    }
+
+    Ok(cases)
+}
+
+/// Finds the names of the TEST cases defined in a source file.
+pub fn test_cases(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("TEST("))
+        .filter_map(|rest| rest.split_once(')'))
+        .map(|(name, _)| name.trim())
+        .filter(|name| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        })
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Generates the main function registering and running the given cases.
+fn generate_main(cases: &[String]) -> String {
+    let mut main =
+        String::from("/* Generated by cedar test, do not edit. */\n#define CEDAR_TEST_MAIN\n#include <cedar/test.h>\n\n");
+
+    for case in cases {
+        main.push_str(&format!("TEST({});\n", case));
+    }
+
+    main.push_str("\nstatic const struct cedar_test cedar_tests[] = {\n");
+
+    for case in cases {
+        main.push_str(&format!("\t{{\"{0}\", cedar_test_{0}}},\n", case));
+    }
+
+    main.push_str(
+        "};\n\nint main(int argc, char **argv) {\n\treturn cedar_test_run(cedar_tests, sizeof(cedar_tests) / sizeof(cedar_tests[0]), argc, argv);\n}\n",
+    );
+
+    main
+}
+
+/// Writes a generated file only when its contents changed, so its object is
+/// not rebuilt on every run.
+fn write_if_changed(path: &Path, contents: &str) -> Result<(), io::Error> {
+    if fs::read_to_string(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_test_cases() {
+        let contents = "#include <cedar/test.h>\n\nTEST(adds) {\n\tASSERT_EQ(1 + 1, 2);\n}\n\n  TEST( trims ) {}\n// TEST(commented)\nTEST(bad name) {}\n";
+
+        assert_eq!(test_cases(contents), vec!["adds", "trims"]);
+    }
+
+    #[test]
+    fn filters_names() {
+        let options = TestOptions {
+            filter: Some("parser_".into()),
+            skip: vec!["slow".into()],
+            ..Default::default()
+        };
+
+        assert!(options.selects("parser_tokens"));
+        assert!(!options.selects("parser_slow"));
+        assert!(!options.selects("lexer"));
+    }
+
+    #[test]
+    fn quotes_cmake_arguments() {
+        assert_eq!(cmake_bracket("unit"), "[[unit]]");
+        assert_eq!(cmake_bracket("a]]b"), "[=[a]]b]=]");
+    }
+
+    #[test]
+    fn renders_reports() {
+        let report = TestReport {
+            results: vec![
+                TestResult {
+                    name: "ok".into(),
+                    passed: true,
+                    output: String::new(),
+                    elapsed: Duration::from_millis(5),
+                },
+                TestResult {
+                    name: "unit".into(),
+                    passed: false,
+                    output: "\t  adds ... ok\n\t  tests/unit.c:9: a < b\n".into(),
+                    elapsed: Duration::from_millis(20),
+                },
+            ],
+        };
+
+        let junit = report.render(ReportFormat::Junit, "demo");
+        assert!(
+            junit.contains("<testsuite name=\"demo\" tests=\"2\" failures=\"1\" time=\"0.025\">")
+        );
+        assert!(junit.contains("<failure message=\"tests/unit.c:9: a &lt; b\">"));
+
+        let tap = report.render(ReportFormat::Tap, "demo");
+        assert!(tap.starts_with("TAP version 13\n1..2\nok 1 - ok\n"));
+        assert!(tap.contains("not ok 2 - unit\n"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    io::{self, Read},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+/// How often a child is polled while it runs under a timeout.
+const POLL: Duration = Duration::from_millis(10);
+
+/// The outcome of a command run under a timeout.
+///
+/// # Fields
+///
+/// * 'status' - How it exited, None when it timed out and was killed.
+/// * 'stdout' - What it printed on stdout, up to when it was killed.
+/// * 'stderr' - What it printed on stderr, up to when it was killed.
+///
+#[derive(Debug)]
+pub struct Output {
+    pub status: Option<process::ExitStatus>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Parses a duration, "30s", "500ms", "2m" or "1h", a bare number is in
+/// seconds.
+///
+/// # Returns
+///
+/// * The duration, None when it is malformed or zero.
+///
+pub fn parse(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let seconds = match unit.trim() {
+        "" | "s" => number,
+        "ms" => number / 1000.0,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+}
+
+/// Formats a duration in seconds, "30s" or "0.5s".
+pub fn display(duration: Duration) -> String {
+    format!("{}s", duration.as_secs_f64())
+}
+
+/// Puts a command in a process group of its own, so that it and everything
+/// it starts can be killed together when it times out.
+pub fn isolate(command: &mut process::Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// Waits for a child to exit, killing it and its process group once it has
+/// run longer than the timeout.
+///
+/// # Returns
+///
+/// * How it exited, None when it timed out.
+///
+pub fn wait(
+    child: &mut process::Child,
+    timeout: Option<Duration>,
+) -> io::Result<Option<process::ExitStatus>> {
+    let Some(timeout) = timeout else {
+        return child.wait().map(Some);
+    };
+
+    let start = Instant::now();
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        if start.elapsed() >= timeout {
+            kill(child);
+            return Ok(None);
+        }
+
+        thread::sleep(POLL);
+    }
+}
+
+/// Runs a command to completion with its stdout and stderr captured, like
+/// Command::output, in a process group of its own that is killed once it has
+/// run longer than the timeout.
+pub fn output(command: &mut process::Command, timeout: Option<Duration>) -> io::Result<Output> {
+    isolate(command);
+
+    let mut child = command
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()?;
+
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+
+    // Both pipes are drained while waiting so a chatty child cannot block on
+    // a full pipe and be mistaken for a hung one.
+    thread::scope(|scope| {
+        let drain = |pipe: Option<Box<dyn Read + Send>>| {
+            scope.spawn(move || {
+                let mut bytes = Vec::new();
+                if let Some(mut pipe) = pipe {
+                    let _ = pipe.read_to_end(&mut bytes);
+                }
+                bytes
+            })
+        };
+
+        let stdout = drain(stdout.map(|pipe| Box::new(pipe) as Box<dyn Read + Send>));
+        let stderr = drain(stderr.map(|pipe| Box::new(pipe) as Box<dyn Read + Send>));
+
+        let status = wait(&mut child, timeout)?;
+
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    })
+}
+
+/// Kills a child, and the process group it leads when it was isolated.
+fn kill(child: &mut process::Child) {
+    // std only signals the child itself, kill(1) reaches the whole group.
+    #[cfg(unix)]
+    let _ = process::Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", child.id())])
+        .stderr(process::Stdio::null())
+        .status();
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse("1.5m"), Some(Duration::from_secs(90)));
+        assert_eq!(parse("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse("0s"), None);
+        assert_eq!(parse("10 days"), None);
+        assert_eq!(parse("s"), None);
+        assert_eq!(display(Duration::from_millis(1500)), "1.5s");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn kills_what_runs_too_long() {
+        let start = Instant::now();
+        let timed_out = output(
+            process::Command::new("sh").args(["-c", "echo started; sleep 10 & sleep 10"]),
+            Some(Duration::from_millis(200)),
+        )
+        .unwrap();
+
+        assert!(timed_out.status.is_none());
+        assert_eq!(timed_out.stdout, b"started\n");
+        // The sleep in the background is killed along with the shell, or it
+        // would hold the pipes open.
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let exited = output(
+            process::Command::new("sh").args(["-c", "exit 3"]),
+            Some(Duration::from_secs(10)),
+        )
+        .unwrap();
+        assert_eq!(exited.status.and_then(|status| status.code()), Some(3));
+    }
+}
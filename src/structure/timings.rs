@@ -0,0 +1,471 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cli::CliError, style};
+
+use super::{build::BuildReport, project::Project, sbom::format_timestamp};
+
+/// How many builds the history of build/timings/ keeps.
+const HISTORY: usize = 10;
+
+/// The width of the chart of the HTML report, in pixels.
+const CHART_WIDTH: f64 = 960.0;
+const LABEL_WIDTH: f64 = 280.0;
+const ROW_HEIGHT: f64 = 18.0;
+
+/// The formats of --timings.
+///
+/// # Members
+///
+/// * 'Text' - The slowest steps, printed after the build.
+/// * 'Html' - A self-contained page in build/timings/ with a chart of the
+///   steps, the critical path, and the history of every file.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimingsFormat {
+    #[default]
+    Text,
+    Html,
+}
+
+impl FromStr for TimingsFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "html" => Ok(Self::Html),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, the timings format is text or html.",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitKind {
+    Compile,
+    Link,
+}
+
+/// A step that ran during a build.
+///
+/// # Fields
+///
+/// * 'name' - The source compiled or the artifact linked, relative to the
+///   project root.
+/// * 'kind' - What the step did.
+/// * 'start' - When it started, since the build started.
+/// * 'duration' - How long it ran.
+/// * 'lane' - The slot it ran in, compiles spread over workers run in
+///   parallel lanes, everything else runs in lane 0.
+/// * 'inputs' - The steps it waited for, by index, e.g. the compiles of
+///   the objects a link uses.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub name: String,
+    pub kind: UnitKind,
+    pub start: Duration,
+    pub duration: Duration,
+    pub lane: usize,
+    pub inputs: Vec<usize>,
+}
+
+impl Unit {
+    pub fn end(&self) -> Duration {
+        self.start + self.duration
+    }
+}
+
+/// A build in the history of build/timings/history.json.
+///
+/// # Fields
+///
+/// * 'started' - When it started, as an RFC 3339 timestamp.
+/// * 'profile' - The profile it built with, empty in older histories.
+/// * 'elapsed' - How long it took, in seconds.
+/// * 'units' - How long each step took, in seconds, by kind and name.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub started: String,
+    #[serde(default)]
+    pub profile: String,
+    pub elapsed: f64,
+    pub units: BTreeMap<String, f64>,
+}
+
+/// The steps on the critical path of a build: the step that finished last,
+/// then the step it waited for longest, and so on, first step first. A step
+/// waits for its inputs and for the step before it in its lane.
+pub fn critical_path(units: &[Unit]) -> Vec<usize> {
+    let latest = |indices: &mut dyn Iterator<Item = usize>| {
+        indices.max_by_key(|index| (units[*index].end(), std::cmp::Reverse(*index)))
+    };
+
+    let mut path = Vec::new();
+    let mut next = latest(&mut (0..units.len()));
+
+    while let Some(index) = next {
+        path.push(index);
+
+        let unit = &units[index];
+        let before = latest(&mut (0..units.len()).filter(|other| {
+            *other != index && units[*other].lane == unit.lane && units[*other].end() <= unit.start
+        }));
+
+        next = latest(&mut unit.inputs.iter().copied().chain(before));
+    }
+
+    path.reverse();
+    path
+}
+
+/// Adds a build to the history of build/timings/, keeping the last HISTORY
+/// builds. Builds that ran no step are not recorded.
+///
+/// # Returns
+///
+/// * The history, newest first.
+///
+pub fn record(
+    build_path: &Path,
+    profile: &str,
+    report: &BuildReport,
+) -> Result<Vec<Record>, io::Error> {
+    let path = build_path.join("timings").join("history.json");
+    let mut history = history(build_path);
+
+    if report.timings.is_empty() {
+        return Ok(history);
+    }
+
+    let seconds = SystemTime::now()
+        .checked_sub(report.elapsed)
+        .and_then(|started| started.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |started| started.as_secs() as i64);
+
+    history.insert(
+        0,
+        Record {
+            started: format_timestamp(seconds),
+            profile: profile.to_owned(),
+            elapsed: report.elapsed.as_secs_f64(),
+            units: report
+                .timings
+                .iter()
+                .map(|unit| (key(unit), unit.duration.as_secs_f64()))
+                .collect(),
+        },
+    );
+    history.truncate(HISTORY);
+
+    fs::create_dir_all(build_path.join("timings"))?;
+    fs::write(&path, serde_json::to_string_pretty(&history)?)?;
+
+    Ok(history)
+}
+
+/// Prints the slowest steps of a build, those on the critical path marked.
+pub fn print(report: &BuildReport) {
+    if report.timings.is_empty() {
+        println!("\t{}no step ran\n", style::header("Timings "));
+        return;
+    }
+
+    let critical = critical_path(&report.timings);
+    let total: Duration = critical
+        .iter()
+        .map(|index| report.timings[*index].duration)
+        .sum();
+
+    println!(
+        "\t{}{} step(s), critical path {:.2?} (*)\n",
+        style::header("Timings "),
+        report.timings.len(),
+        total
+    );
+
+    let mut slowest: Vec<usize> = (0..report.timings.len()).collect();
+    slowest.sort_by_key(|index| std::cmp::Reverse(report.timings[*index].duration));
+
+    for index in slowest.into_iter().take(20) {
+        let unit = &report.timings[index];
+
+        println!(
+            "\t{:>10.2?} {:<7} {}{}",
+            unit.duration,
+            kind(unit.kind),
+            unit.name,
+            if critical.contains(&index) { " *" } else { "" }
+        );
+    }
+
+    println!();
+}
+
+/// Writes build/timings/cedar-timing.html, the report of a build with its
+/// history.
+///
+/// # Returns
+///
+/// * The path of the report.
+///
+pub fn write_html(
+    project: &Project,
+    report: &BuildReport,
+    history: &[Record],
+) -> Result<PathBuf, io::Error> {
+    let dir = project.build_dir.join("timings");
+    let path = dir.join("cedar-timing.html");
+
+    fs::create_dir_all(&dir)?;
+    fs::write(&path, html(project, report, history))?;
+
+    Ok(path)
+}
+
+fn html(project: &Project, report: &BuildReport, history: &[Record]) -> String {
+    let manifest = &project.manifest;
+    let units = &report.timings;
+    let critical = critical_path(units);
+    let critical_total: Duration = critical.iter().map(|index| units[*index].duration).sum();
+    let busy: Duration = units.iter().map(|unit| unit.duration).sum();
+    let end = units
+        .iter()
+        .map(Unit::end)
+        .max()
+        .unwrap_or_default()
+        .max(Duration::from_millis(1));
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Build timings of {0}</title>\n\
+         <style>body{{font-family:sans-serif;margin:2em}}\
+         table{{border-collapse:collapse}}td,th{{padding:2px 10px;text-align:left}}\
+         td.n{{text-align:right;font-variant-numeric:tabular-nums}}\
+         tr:nth-child(even){{background:#f4f4f4}}\
+         .compile{{fill:#7aa6da}}.link{{fill:#b294bb}}.critical{{stroke:#c82829;stroke-width:2}}\
+         </style>\n</head>\n<body>\n<h1>Build timings of {0} v{1}</h1>\n",
+        escape(&manifest.meta.name),
+        escape(&manifest.meta.version)
+    );
+
+    html.push_str(&format!(
+        "<table>\n<tr><td>Started</td><td>{}</td></tr>\n\
+         <tr><td>Total time</td><td>{:.2?}</td></tr>\n\
+         <tr><td>Steps</td><td>{}</td></tr>\n\
+         <tr><td>Parallelism</td><td>{:.2}</td></tr>\n\
+         <tr><td>Critical path</td><td>{:.2?} over {} step(s)</td></tr>\n</table>\n",
+        // The history only has this build when a step ran.
+        history
+            .first()
+            .filter(|_| !units.is_empty())
+            .map_or(String::new(), |record| escape(&record.started)),
+        report.elapsed,
+        units.len(),
+        busy.as_secs_f64() / end.as_secs_f64(),
+        critical_total,
+        critical.len()
+    ));
+
+    html.push_str("<h2>Steps</h2>\n");
+    html.push_str(&chart(units, &critical, end));
+
+    html.push_str(
+        "<h2>Slowest steps</h2>\n<table>\n<tr><th></th><th>Step</th><th>Kind</th>\
+         <th>Start</th><th>Duration</th><th>Lane</th></tr>\n",
+    );
+
+    let mut slowest: Vec<usize> = (0..units.len()).collect();
+    slowest.sort_by_key(|index| std::cmp::Reverse(units[*index].duration));
+
+    for index in &slowest {
+        let unit = &units[*index];
+
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"n\">{:.2}s</td>\
+             <td class=\"n\">{:.2}s</td><td class=\"n\">{}</td></tr>\n",
+            if critical.contains(index) {
+                "&#9733;"
+            } else {
+                ""
+            },
+            escape(&unit.name),
+            kind(unit.kind),
+            unit.start.as_secs_f64(),
+            unit.duration.as_secs_f64(),
+            unit.lane
+        ));
+    }
+
+    html.push_str("</table>\n");
+
+    // The history of the steps of this build, newest build first.
+    html.push_str("<h2>History</h2>\n<table>\n<tr><th>Step</th>");
+    for record in history {
+        html.push_str(&format!("<th>{}</th>", escape(&record.started)));
+    }
+    html.push_str("</tr>\n<tr><td>Total</td>");
+    for record in history {
+        html.push_str(&format!("<td class=\"n\">{:.2}s</td>", record.elapsed));
+    }
+    html.push_str("</tr>\n");
+
+    for index in &slowest {
+        let key = key(&units[*index]);
+
+        html.push_str(&format!("<tr><td>{}</td>", escape(&key)));
+        for record in history {
+            match record.units.get(&key) {
+                Some(seconds) => html.push_str(&format!("<td class=\"n\">{:.2}s</td>", seconds)),
+                None => html.push_str("<td class=\"n\">-</td>"),
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// The chart of the steps as an SVG, one row per step in the order they
+/// started, the steps of the critical path outlined.
+fn chart(units: &[Unit], critical: &[usize], end: Duration) -> String {
+    let scale = CHART_WIDTH / end.as_secs_f64();
+    let height = ROW_HEIGHT * (units.len() + 1) as f64;
+
+    let mut order: Vec<usize> = (0..units.len()).collect();
+    order.sort_by_key(|index| (units[*index].start, *index));
+
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"{}\" font-size=\"12\">\n",
+        LABEL_WIDTH + CHART_WIDTH + 20.0,
+        height + 10.0
+    );
+
+    // A tick every step of a round length, about ten of them.
+    let tick = [
+        0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0,
+    ]
+    .into_iter()
+    .find(|tick| end.as_secs_f64() / tick <= 10.0)
+    .unwrap_or(600.0);
+    let mut at = 0.0;
+
+    while at <= end.as_secs_f64() {
+        let x = LABEL_WIDTH + at * scale;
+
+        svg.push_str(&format!(
+            "<line x1=\"{0:.1}\" y1=\"0\" x2=\"{0:.1}\" y2=\"{1:.1}\" stroke=\"#ddd\"/>\
+             <text x=\"{0:.1}\" y=\"{2:.1}\" fill=\"#666\">{3}s</text>\n",
+            x,
+            height - ROW_HEIGHT,
+            height,
+            at
+        ));
+        at += tick;
+    }
+
+    for (row, index) in order.iter().enumerate() {
+        let unit = &units[*index];
+        let y = row as f64 * ROW_HEIGHT;
+
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>\
+             <rect class=\"{}{}\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\">\
+             <title>{} {}: {:.2}s from {:.2}s, lane {}</title></rect>\n",
+            LABEL_WIDTH - 6.0,
+            y + ROW_HEIGHT - 5.0,
+            escape(&unit.name),
+            kind(unit.kind),
+            if critical.contains(index) {
+                " critical"
+            } else {
+                ""
+            },
+            LABEL_WIDTH + unit.start.as_secs_f64() * scale,
+            y + 2.0,
+            (unit.duration.as_secs_f64() * scale).max(1.0),
+            ROW_HEIGHT - 4.0,
+            kind(unit.kind),
+            escape(&unit.name),
+            unit.duration.as_secs_f64(),
+            unit.start.as_secs_f64(),
+            unit.lane
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The history of build/timings/, newest first, empty when there is none
+/// yet.
+pub fn history(build_path: &Path) -> Vec<Record> {
+    fs::read_to_string(build_path.join("timings").join("history.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// The name of a step in the history, e.g. "compile src/main.c".
+fn key(unit: &Unit) -> String {
+    format!("{} {}", kind(unit.kind), unit.name)
+}
+
+fn kind(kind: UnitKind) -> &'static str {
+    match kind {
+        UnitKind::Compile => "compile",
+        UnitKind::Link => "link",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_critical_path() {
+        let unit = |name: &str, kind, start, duration, inputs: Vec<usize>, lane| Unit {
+            name: name.to_owned(),
+            kind,
+            start: Duration::from_millis(start),
+            duration: Duration::from_millis(duration),
+            lane,
+            inputs,
+        };
+
+        let units = [
+            unit("src/a.c", UnitKind::Compile, 0, 300, vec![], 0),
+            unit("src/b.c", UnitKind::Compile, 0, 500, vec![], 1),
+            unit("src/main.c", UnitKind::Compile, 300, 100, vec![], 0),
+            unit("build/app", UnitKind::Link, 500, 50, vec![0, 1, 2], 0),
+        ];
+
+        assert_eq!(critical_path(&units), vec![1, 3]);
+
+        // Without b.c the link waits on main.c, which waited on a.c.
+        assert_eq!(
+            critical_path(&[units[0].clone(), units[2].clone()]),
+            vec![0, 1]
+        );
+        assert!(critical_path(&[]).is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use std::{fs, path::Path};
+
+use super::project::ProjectError;
+
+/// An enum for the tooling configuration files cedar can scaffold.
+///
+/// # Members
+///
+/// * 'ClangFormat' - A .clang-format file for clang-format.
+/// * 'ClangTidy' - A .clang-tidy file for clang-tidy.
+/// * 'EditorConfig' - An .editorconfig file for editors.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tooling {
+    ClangFormat,
+    ClangTidy,
+    EditorConfig,
+}
+
+impl Tooling {
+    /// Every tooling file, used by the --with-tooling flag.
+    pub const ALL: [Tooling; 3] = [Self::ClangFormat, Self::ClangTidy, Self::EditorConfig];
+
+    /// The name of the file written into the project root.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::ClangFormat => ".clang-format",
+            Self::ClangTidy => ".clang-tidy",
+            Self::EditorConfig => ".editorconfig",
+        }
+    }
+    /// The default contents of the file, matching the style of the files
+    /// cedar generates itself (tab indentation, LLVM based layout).
+    pub fn contents(&self) -> &'static str {
+        match self {
+            Self::ClangFormat => CLANG_FORMAT,
+            Self::ClangTidy => CLANG_TIDY,
+            Self::EditorConfig => EDITORCONFIG,
+        }
+    }
+}
+
+const CLANG_FORMAT: &str = "---
+BasedOnStyle: LLVM
+IndentWidth: 4
+TabWidth: 4
+UseTab: ForIndentation
+ColumnLimit: 80
+BreakBeforeBraces: Attach
+AllowShortFunctionsOnASingleLine: None
+AllowShortIfStatementsOnASingleLine: Never
+PointerAlignment: Right
+SortIncludes: true
+...
+";
+
+const CLANG_TIDY: &str = "---
+Checks: >
+  -*,
+  bugprone-*,
+  clang-analyzer-*,
+  performance-*,
+  portability-*,
+  readability-*,
+  -readability-magic-numbers,
+  -readability-identifier-length
+WarningsAsErrors: ''
+HeaderFilterRegex: 'include/.*'
+FormatStyle: file
+...
+";
+
+const EDITORCONFIG: &str = "root = true
+
+[*]
+end_of_line = lf
+insert_final_newline = true
+trim_trailing_whitespace = true
+charset = utf-8
+
+[*.{c,h}]
+indent_style = tab
+indent_size = 4
+
+[*.toml]
+indent_style = space
+indent_size = 4
+";
+
+/// Writes the given tooling configuration files into the project root.
+/// Existing files are left untouched so user configuration is never lost.
+///
+/// # Arguments
+///
+/// * 'path' - The root of the project, any type that can be coerced into a
+///   path.
+/// * 'tools' - The tooling files to generate.
+///
+pub fn scaffold_tooling<P: AsRef<Path>>(path: P, tools: &[Tooling]) -> Result<(), ProjectError> {
+    let path = path.as_ref();
+
+    if !path.is_dir() {
+        return Err(ProjectError::InvalidPath(format!("{:?}", path)));
+    }
+
+    for tool in tools {
+        let file = path.join(tool.file_name());
+
+        if !file.exists() {
+            fs::write(file, tool.contents())?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use std::{
+    cell::Cell,
+    fmt::Display,
+    fs, io,
+    path::Path,
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::Instant,
+};
+
+use serde_json::{json, Value};
+
+/// The spans recorded so far and when tracing started, set once tracing is
+/// enabled by --trace-output.
+static TRACE: OnceLock<(Instant, Mutex<Vec<Event>>)> = OnceLock::new();
+
+/// The next thread number, threads are numbered as they record their first
+/// span.
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A finished span, a complete ("X") event of the trace.
+///
+/// # Fields
+///
+/// * 'name' - What ran, e.g. compile.
+/// * 'detail' - What it ran on, e.g. the source compiled.
+/// * 'start' - When it started, in microseconds since tracing started.
+/// * 'duration' - How long it ran, in microseconds.
+/// * 'thread' - The number of the thread it ran on.
+///
+#[derive(Debug, Clone)]
+struct Event {
+    name: &'static str,
+    detail: String,
+    start: u64,
+    duration: u64,
+    thread: u64,
+}
+
+/// A running span, recorded when it is dropped. Without tracing it does
+/// nothing.
+#[must_use = "the span ends when it is dropped"]
+pub struct Span {
+    running: Option<(&'static str, String, Instant)>,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let (Some((name, detail, started)), Some((epoch, events))) =
+            (self.running.take(), TRACE.get())
+        else {
+            return;
+        };
+
+        let event = Event {
+            name,
+            detail,
+            start: started.duration_since(*epoch).as_micros() as u64,
+            duration: started.elapsed().as_micros() as u64,
+            thread: thread(),
+        };
+
+        if let Ok(mut events) = events.lock() {
+            events.push(event);
+        }
+    }
+}
+
+/// Starts recording spans, for the rest of the process.
+pub fn enable() {
+    let _ = TRACE.set((Instant::now(), Mutex::new(Vec::new())));
+}
+
+/// Starts a span of the build, which ends when the returned guard is
+/// dropped.
+///
+/// # Arguments
+///
+/// * 'name' - What runs: scan, resolve, plan, compile, link, ...
+/// * 'detail' - What it runs on, shown in the arguments of the event.
+///
+pub fn span(name: &'static str, detail: impl Display) -> Span {
+    Span {
+        running: TRACE
+            .get()
+            .map(|_| (name, detail.to_string(), Instant::now())),
+    }
+}
+
+/// Writes the spans recorded so far as a Chrome trace-event file.
+pub fn write(path: &Path) -> Result<(), io::Error> {
+    let events = match TRACE.get() {
+        Some((_, events)) => events
+            .lock()
+            .map(|events| events.clone())
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    fs::write(path, to_json(&events).to_string())
+}
+
+/// The number of the current thread in the trace, 1 for the first thread
+/// that records a span (usually the main thread).
+fn thread() -> u64 {
+    THREAD.with(|number| {
+        if number.get() == 0 {
+            number.set(NEXT_THREAD.fetch_add(1, Ordering::Relaxed));
+        }
+
+        number.get()
+    })
+}
+
+fn to_json(events: &[Event]) -> Value {
+    let pid = process::id();
+
+    let mut threads: Vec<u64> = events.iter().map(|event| event.thread).collect();
+    threads.sort();
+    threads.dedup();
+
+    let names = threads.into_iter().map(|thread| {
+        let name = match thread {
+            1 => String::from("cedar"),
+            _ => format!("worker {}", thread - 1),
+        };
+
+        json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": pid,
+            "tid": thread,
+            "args": { "name": name },
+        })
+    });
+
+    let spans = events.iter().map(|event| {
+        json!({
+            "name": event.name,
+            "cat": "cedar",
+            "ph": "X",
+            "ts": event.start,
+            "dur": event.duration,
+            "pid": pid,
+            "tid": event.thread,
+            "args": { "detail": event.detail },
+        })
+    });
+
+    json!({
+        "traceEvents": names.chain(spans).collect::<Vec<_>>(),
+        "displayTimeUnit": "ms",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_trace_events() {
+        let trace = to_json(&[Event {
+            name: "compile",
+            detail: String::from("src/main.c"),
+            start: 10,
+            duration: 2500,
+            thread: 1,
+        }]);
+
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events[0]["ph"], "M");
+        assert_eq!(events[0]["args"]["name"], "cedar");
+        assert_eq!(events[1]["ph"], "X");
+        assert_eq!(events[1]["dur"], 2500);
+        assert_eq!(events[1]["args"]["detail"], "src/main.c");
+    }
+}
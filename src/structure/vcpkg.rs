@@ -0,0 +1,296 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{error::CedarError, style};
+
+use super::{
+    config::Config,
+    deps,
+    project::ProjectError,
+    sysdeps::{self, Installed, SystemLibrary},
+};
+
+const VCPKG_URL: &str = "https://github.com/microsoft/vcpkg";
+
+const EXE: &str = if cfg!(windows) { "vcpkg.exe" } else { "vcpkg" };
+
+/// Installs the vcpkg ports in [dependencies] vcpkg for the triplet of the
+/// host ($VCPKG_DEFAULT_TRIPLET overrides it) into build/vcpkg/, and wires
+/// up their include and library directories. vcpkg is found through
+/// $VCPKG_ROOT or the PATH, or else cloned and bootstrapped once into the
+/// data directory of cedar.
+///
+/// # Arguments
+///
+/// * 'ports' - The ports to install.
+/// * 'build_path' - The build directory.
+/// * 'quiet' - Suppresses the progress output of vcpkg.
+///
+/// # Returns
+///
+/// * The installed ports as a single library, linking every library they
+///   installed.
+///
+pub fn install(
+    ports: &[String],
+    build_path: &Path,
+    quiet: bool,
+) -> Result<Vec<SystemLibrary>, CedarError> {
+    if ports.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let triplet = triplet(env::var("VCPKG_DEFAULT_TRIPLET").ok());
+    let key = format!("{} {}", triplet, ports.join(" "));
+
+    let vcpkg_path = build_path.join("vcpkg");
+    let installed_path = vcpkg_path.join("libraries.json");
+
+    if let Some(libraries) = Installed::load(&installed_path, &key) {
+        return Ok(libraries);
+    }
+
+    if deps::is_offline() {
+        return Err(ProjectError::InvalidDependency(format!(
+            "The vcpkg ports {} are not installed and offline mode forbids installing them.",
+            ports.join(", ")
+        ))
+        .into());
+    }
+
+    let vcpkg = match locate() {
+        Some(vcpkg) => vcpkg,
+        None => bootstrap(quiet)?,
+    };
+
+    fs::create_dir_all(&vcpkg_path).map_err(|err| CedarError::io(&vcpkg_path, err))?;
+
+    if !quiet {
+        println!(
+            "\t{}{} ({})",
+            style::header("Installing "),
+            ports.join(", "),
+            triplet
+        );
+    }
+
+    let install_root = vcpkg_path.join("installed");
+
+    let mut command = install_command(&vcpkg, ports, &triplet, &vcpkg_path);
+    deps::network().apply(&mut command);
+
+    if quiet {
+        command
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null());
+    }
+
+    let status = command
+        .status()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed("vcpkg").into());
+    }
+
+    let installed = Installed {
+        key,
+        libraries: vec![
+            sysdeps::prefix_library("vcpkg", &install_root.join(&triplet))
+                .map_err(|err| CedarError::io(&install_root, err))?,
+        ],
+    };
+    installed.save(&installed_path)?;
+
+    Ok(installed.libraries)
+}
+
+/// The vcpkg install of the ports for the triplet, into installed/ of the
+/// vcpkg directory of the build.
+fn install_command(
+    vcpkg: &Path,
+    ports: &[String],
+    triplet: &str,
+    vcpkg_path: &Path,
+) -> process::Command {
+    let mut command = process::Command::new(vcpkg);
+    command
+        .arg("install")
+        .args(ports)
+        .arg("--triplet")
+        .arg(triplet)
+        .arg(format!(
+            "--x-install-root={}",
+            vcpkg_path.join("installed").display()
+        ))
+        .current_dir(vcpkg_path);
+
+    command
+}
+
+/// The triplet ports are installed for, the one given unless empty, or the
+/// one of the host.
+fn triplet(given: Option<String>) -> String {
+    given
+        .filter(|triplet| !triplet.is_empty())
+        .unwrap_or_else(host_triplet)
+}
+
+/// The vcpkg triplet of the host, e.g. x64-linux or arm64-osx.
+fn host_triplet() -> String {
+    let arch = match env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        arch => arch,
+    };
+
+    let os = match env::consts::OS {
+        "macos" => "osx",
+        os => os,
+    };
+
+    format!("{}-{}", arch, os)
+}
+
+/// Finds an installed vcpkg, in $VCPKG_ROOT, on the PATH, or bootstrapped
+/// by cedar earlier.
+fn locate() -> Option<PathBuf> {
+    let path = env::var_os("PATH").unwrap_or_default();
+
+    env::var_os("VCPKG_ROOT")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(env::split_paths(&path))
+        .chain(Config::data_dir().map(|dir| dir.join("vcpkg")))
+        .map(|root| root.join(EXE))
+        .find(|vcpkg| vcpkg.is_file())
+}
+
+/// Clones vcpkg into the data directory of cedar and builds it.
+fn bootstrap(quiet: bool) -> Result<PathBuf, CedarError> {
+    let root = Config::data_dir()
+        .ok_or(ProjectError::MissingTool("vcpkg"))?
+        .join("vcpkg");
+
+    if !quiet {
+        println!("\t{}vcpkg ({:?})", style::header("Bootstrapping "), root);
+    }
+
+    if !root.join(".git").exists() {
+        deps::clone("vcpkg", &deps::network().rewrite(VCPKG_URL), &root)?;
+    }
+
+    let script = root.join(if cfg!(windows) {
+        "bootstrap-vcpkg.bat"
+    } else {
+        "bootstrap-vcpkg.sh"
+    });
+
+    let mut command = process::Command::new(&script);
+    command.arg("-disableMetrics").current_dir(&root);
+    deps::network().apply(&mut command);
+
+    if quiet {
+        command.stdout(process::Stdio::null());
+    }
+
+    let status = command
+        .status()
+        .map_err(|err| CedarError::command(&command, err))?;
+
+    if !status.success() {
+        return Err(ProjectError::ToolFailed("bootstrap-vcpkg").into());
+    }
+
+    Ok(root.join(EXE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_host_triplet() {
+        assert!(host_triplet().contains('-'));
+
+        if cfg!(all(target_arch = "x86_64", target_os = "linux")) {
+            assert_eq!(host_triplet(), "x64-linux");
+        }
+
+        assert_eq!(
+            triplet(Some(String::from("x64-windows-static"))),
+            "x64-windows-static"
+        );
+        assert_eq!(triplet(Some(String::new())), host_triplet());
+        assert_eq!(triplet(None), host_triplet());
+    }
+
+    #[test]
+    fn installs_ports_for_the_triplet() {
+        let ports = [String::from("zlib"), String::from("sqlite3")];
+        let command = install_command(
+            Path::new("/opt/vcpkg/vcpkg"),
+            &ports,
+            "arm64-osx",
+            Path::new("/p/build/vcpkg"),
+        );
+
+        assert_eq!(command.get_program(), "/opt/vcpkg/vcpkg");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            [
+                "install",
+                "zlib",
+                "sqlite3",
+                "--triplet",
+                "arm64-osx",
+                "--x-install-root=/p/build/vcpkg/installed"
+            ]
+        );
+        assert_eq!(command.get_current_dir(), Some(Path::new("/p/build/vcpkg")));
+    }
+
+    #[test]
+    fn links_the_installed_ports() {
+        let build = env::temp_dir().join(format!("cedar-vcpkg-{}", process::id()));
+        let prefix = build.join("vcpkg").join("installed").join("x64-linux");
+        fs::create_dir_all(prefix.join("include")).unwrap();
+        fs::create_dir_all(prefix.join("lib")).unwrap();
+        fs::write(prefix.join("lib").join("libz.a"), "").unwrap();
+        fs::write(prefix.join("lib").join("libsqlite3.a"), "").unwrap();
+
+        let library = sysdeps::prefix_library("vcpkg", &prefix).unwrap();
+        assert_eq!(
+            library.cflags,
+            [format!("-I{}", prefix.join("include").display())]
+        );
+        assert_eq!(
+            library.libs,
+            [
+                format!("-L{}", prefix.join("lib").display()),
+                String::from("-lsqlite3"),
+                String::from("-lz")
+            ]
+        );
+
+        // Installed ports are not installed again for the same triplet.
+        let ports = [String::from("zlib"), String::from("sqlite3")];
+        let key = format!(
+            "{} zlib sqlite3",
+            triplet(env::var("VCPKG_DEFAULT_TRIPLET").ok())
+        );
+        Installed {
+            key,
+            libraries: vec![library.clone()],
+        }
+        .save(&build.join("vcpkg").join("libraries.json"))
+        .unwrap();
+
+        assert_eq!(install(&ports, &build, true).unwrap(), [library]);
+
+        fs::remove_dir_all(&build).unwrap();
+    }
+}
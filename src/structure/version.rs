@@ -0,0 +1,441 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use super::project::ProjectError;
+
+/// A version number, major.minor.patch with an optional pre-release tag.
+/// Missing minor and patch numbers are zero, so "1.2" is 1.2.0.
+///
+/// # Fields
+///
+/// * 'major' - The major version.
+/// * 'minor' - The minor version.
+/// * 'patch' - The patch version.
+/// * 'pre' - The pre-release tag after '-', e.g. "rc.1". A pre-release is
+///   older than the release it leads up to.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl FromStr for Version {
+    type Err = ProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ProjectError::InvalidVersion(s.to_owned());
+
+        let s = s.trim().trim_start_matches('v');
+        // Build metadata never affects precedence.
+        let s = s.split_once('+').map_or(s, |(version, _)| version);
+
+        let (numbers, pre) = match s.split_once('-') {
+            Some((numbers, pre)) if !pre.is_empty() => (numbers, Some(pre.to_owned())),
+            Some(_) => return Err(invalid()),
+            None => (s, None),
+        };
+
+        let mut parts = numbers.split('.');
+        let mut next = |required: bool| match parts.next() {
+            Some(part) => part.parse::<u64>().map_err(|_| invalid()),
+            None if required => Err(invalid()),
+            None => Ok(0),
+        };
+
+        let version = Self {
+            major: next(true)?,
+            minor: next(false)?,
+            patch: next(false)?,
+            pre,
+        };
+
+        match parts.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(version),
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        match &self.pre {
+            Some(pre) => write!(f, "-{}", pre),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A comparison operator of a version requirement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+/// A version requirement, comparators separated by commas that must all
+/// hold, e.g. ">=1.2, <1.2.13". Like Cargo's:
+///
+/// * '^1.2.3' - Compatible versions, >=1.2.3, <2.0.0 (for 0.x versions the
+///   minor version is the breaking one, ^0.2.3 is <0.3.0).
+/// * '~1.2.3' - Patch updates only, >=1.2.3, <1.3.0 (~1 is <2.0.0).
+/// * '1.2.*' - Any version with the given prefix, * is any version.
+/// * '=1.2.3' - Exactly that version, or every version with the prefix when
+///   parts are left out. A bare version is the same, so "1.2.3" stays
+///   exactly 1.2.3 and compatible versions need the caret.
+///
+/// Every form is stored as plain comparisons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    pub comparators: Vec<(Op, Version)>,
+}
+
+impl FromStr for VersionReq {
+    type Err = ProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut comparators = Vec::new();
+
+        for comparator in s.split(',').map(str::trim) {
+            let (op, version) = [
+                (">=", ">="),
+                ("<=", "<="),
+                (">", ">"),
+                ("<", "<"),
+                ("=", "="),
+                ("^", "^"),
+                ("~", "~"),
+            ]
+            .into_iter()
+            .find_map(|(prefix, op)| comparator.strip_prefix(prefix).map(|rest| (op, rest)))
+            .unwrap_or(("", comparator));
+
+            let (version, parts) = partial(version, s)?;
+
+            let op = match op {
+                "" => "=",
+                op => op,
+            };
+
+            // The smallest version after everything the given parts match,
+            // e.g. 1.3.0 for 1.2 and 2.0.0 for 1.
+            let next = |parts: usize| match parts {
+                0 => None,
+                1 => Some(Version::new(version.major + 1, 0, 0)),
+                2 => Some(Version::new(version.major, version.minor + 1, 0)),
+                _ => Some(Version::new(
+                    version.major,
+                    version.minor,
+                    version.patch + 1,
+                )),
+            };
+
+            match op {
+                "=" if parts == 3 => comparators.push((Op::Exact, version)),
+                ">=" => comparators.push((Op::GreaterEq, version)),
+                "<" => comparators.push((Op::Less, version)),
+                ">" if parts == 3 => comparators.push((Op::Greater, version)),
+                "<=" if parts == 3 => comparators.push((Op::LessEq, version)),
+                ">" => comparators.extend(next(parts).map(|next| (Op::GreaterEq, next))),
+                "<=" => comparators.extend(next(parts).map(|next| (Op::Less, next))),
+                op => {
+                    // The first non-zero part (or the last given one) is the
+                    // one a caret requirement may not change.
+                    let fixed = match op {
+                        "^" if version.major > 0 || parts == 1 => 1,
+                        "^" if version.minor > 0 || parts == 2 => 2,
+                        "^" => 3,
+                        "~" => parts.clamp(1, 2),
+                        _ => parts,
+                    };
+
+                    if let Some(next) = next(fixed.min(parts)) {
+                        comparators.push((Op::GreaterEq, version));
+                        comparators.push((Op::Less, next));
+                    }
+                }
+            }
+        }
+
+        Ok(Self { comparators })
+    }
+}
+
+/// Parses a version that may leave parts out or use wildcards, e.g. "1.2"
+/// or "1.2.*", returning it and how many parts were given.
+fn partial(s: &str, requirement: &str) -> Result<(Version, usize), ProjectError> {
+    let invalid = || ProjectError::InvalidVersion(requirement.to_owned());
+    let is_wildcard = |part: &str| matches!(part, "*" | "x" | "X");
+
+    let s = s.trim();
+    let numbers = s.split(['-', '+']).next().unwrap_or_default();
+
+    let parts = numbers
+        .split('.')
+        .take_while(|part| !is_wildcard(part))
+        .count();
+    let wildcard = numbers.split('.').any(is_wildcard);
+
+    if wildcard && !numbers.split('.').skip(parts).all(is_wildcard) {
+        return Err(invalid());
+    }
+
+    let version = match parts {
+        0 => Version::new(0, 0, 0),
+        _ if wildcard => numbers
+            .split('.')
+            .take(parts)
+            .collect::<Vec<&str>>()
+            .join(".")
+            .parse()
+            .map_err(|_| invalid())?,
+        _ => s.parse().map_err(|_| invalid())?,
+    };
+
+    Ok((version, parts.min(3)))
+}
+
+impl VersionReq {
+    /// Whether a version satisfies every comparator.
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators.iter().all(|(op, bound)| match op {
+            Op::Exact => version == bound,
+            Op::Greater => version > bound,
+            Op::GreaterEq => version >= bound,
+            Op::Less => version < bound,
+            Op::LessEq => version <= bound,
+        })
+    }
+    /// Whether some version satisfies both requirements.
+    pub fn intersects(&self, other: &VersionReq) -> bool {
+        // The tightest lower and upper bounds, along with whether they are
+        // inclusive.
+        let mut lower: Option<(&Version, bool)> = None;
+        let mut upper: Option<(&Version, bool)> = None;
+
+        for (op, bound) in self.comparators.iter().chain(&other.comparators) {
+            let (is_lower, is_upper, inclusive) = match op {
+                Op::Exact => (true, true, true),
+                Op::Greater => (true, false, false),
+                Op::GreaterEq => (true, false, true),
+                Op::Less => (false, true, false),
+                Op::LessEq => (false, true, true),
+            };
+
+            if is_lower
+                && lower.is_none_or(|(current, current_inclusive)| {
+                    bound > current || (bound == current && current_inclusive && !inclusive)
+                })
+            {
+                lower = Some((bound, inclusive));
+            }
+
+            if is_upper
+                && upper.is_none_or(|(current, current_inclusive)| {
+                    bound < current || (bound == current && current_inclusive && !inclusive)
+                })
+            {
+                upper = Some((bound, inclusive));
+            }
+        }
+
+        match (lower, upper) {
+            (Some((lower, lower_inclusive)), Some((upper, upper_inclusive))) => {
+                lower < upper || (lower == upper && lower_inclusive && upper_inclusive)
+            }
+            _ => true,
+        }
+    }
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+    /// The version after a bump, bumping a part resets the ones after it
+    /// and drops the pre-release tag.
+    pub fn bumped(&self, bump: &Bump) -> Self {
+        match bump {
+            Bump::Major => Self::new(self.major + 1, 0, 0),
+            Bump::Minor => Self::new(self.major, self.minor + 1, 0),
+            Bump::Patch => Self::new(self.major, self.minor, self.patch + 1),
+            Bump::To(version) => version.clone(),
+        }
+    }
+}
+
+/// How cedar version changes the version of a project.
+///
+/// # Members
+///
+/// * 'Major' - The next major version, 1.4.2 to 2.0.0.
+/// * 'Minor' - The next minor version, 1.4.2 to 1.5.0.
+/// * 'Patch' - The next patch version, 1.4.2 to 1.4.3.
+/// * 'To' - An explicit version.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+    To(Version),
+}
+
+impl FromStr for Bump {
+    type Err = ProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "major" => Ok(Self::Major),
+            "minor" => Ok(Self::Minor),
+            "patch" => Ok(Self::Patch),
+            version => version.parse().map(Self::To),
+        }
+    }
+}
+
+/// Sets the version in the text of a C header, for headers defining the
+/// version of the project as macros:
+///
+/// * '#define <PREFIX>VERSION "1.4.2"' - the whole version, replaced when
+///   it is the old one.
+/// * '#define <PREFIX>VERSION_MAJOR 1' - and _MINOR and _PATCH, single
+///   parts of it.
+///
+/// # Returns
+///
+/// * The new text, None when the header defines no such macro.
+///
+pub fn set_header_version(text: &str, old: &Version, new: &Version) -> Option<String> {
+    let mut changed = false;
+    let mut rewritten = String::with_capacity(text.len());
+
+    for line in text.split_inclusive('\n') {
+        let mut words = line.split_whitespace();
+        let (Some("#define"), Some(name), Some(value)) = (words.next(), words.next(), words.next())
+        else {
+            rewritten.push_str(line);
+            continue;
+        };
+
+        let replacement = match name {
+            _ if name.ends_with("VERSION") && value == format!("\"{}\"", old) => {
+                format!("\"{}\"", new)
+            }
+            _ if name.ends_with("VERSION_MAJOR") => new.major.to_string(),
+            _ if name.ends_with("VERSION_MINOR") => new.minor.to_string(),
+            _ if name.ends_with("VERSION_PATCH") => new.patch.to_string(),
+            _ => {
+                rewritten.push_str(line);
+                continue;
+            }
+        };
+
+        // Only the value is replaced, the spacing and comments stay.
+        let start = line.find(name).unwrap_or(0) + name.len();
+        let start = start + line[start..].find(value).unwrap_or(0);
+        rewritten.push_str(&line[..start]);
+        rewritten.push_str(&replacement);
+        rewritten.push_str(&line[start + value.len()..]);
+        changed = true;
+    }
+
+    changed.then_some(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(req: &str, version: &str) -> bool {
+        req.parse::<VersionReq>()
+            .unwrap()
+            .matches(&version.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_and_matches() {
+        assert!(matches(">=1.2, <1.2.13", "1.2.12"));
+        assert!(!matches(">=1.2, <1.2.13", "1.2.13"));
+        assert!("2.0.0-rc.1".parse::<Version>().unwrap() < "2.0.0".parse().unwrap());
+        assert!("1.x".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn parses_semver_operators() {
+        assert!(matches("^1.2", "1.9.0") && !matches("^1.2", "2.0.0"));
+        assert!(!matches("^1.2", "1.1.9"));
+        assert!(matches("^0.2.3", "0.2.9") && !matches("^0.2.3", "0.3.0"));
+        assert!(matches("^0.0.3", "0.0.3") && !matches("^0.0.3", "0.0.4"));
+        assert!(matches("1.2.3", "1.2.3") && !matches("1.2.3", "1.2.4"));
+        assert!(matches("1.2", "1.2.5") && !matches("1.2", "1.4.0"));
+        assert!(matches("~1.2.3", "1.2.9") && !matches("~1.2.3", "1.3.0"));
+        assert!(matches("~1", "1.9.0") && !matches("~1", "2.0.0"));
+        assert!(matches("1.2.*", "1.2.7") && !matches("1.2.*", "1.3.0"));
+        assert!(matches("*", "7.0.0"));
+        assert!(matches("=1.2", "1.2.5") && !matches("=1.2.3", "1.2.4"));
+        assert!(!matches(">1.2", "1.2.5") && matches(">1.2", "1.3.0"));
+        assert!(matches("<=1.2", "1.2.5") && !matches("<=1.2", "1.3.0"));
+    }
+
+    #[test]
+    fn detects_conflicts() {
+        let req = |s: &str| s.parse::<VersionReq>().unwrap();
+
+        assert!(req("^1.2").intersects(&req(">=1.5")));
+        assert!(!req("^1.2").intersects(&req("^2")));
+        assert!(!req(">=0.4, <0.6").intersects(&req("=0.6.0")));
+        assert!(req("<=1.0.0").intersects(&req(">=1.0.0")));
+        assert!(!req("<1.0.0").intersects(&req(">=1.0.0")));
+    }
+
+    #[test]
+    fn bumps_versions() {
+        let version: Version = "1.4.2-rc.1".parse().unwrap();
+
+        assert_eq!(version.bumped(&Bump::Major).to_string(), "2.0.0");
+        assert_eq!(version.bumped(&Bump::Minor).to_string(), "1.5.0");
+        assert_eq!(version.bumped(&Bump::Patch).to_string(), "1.4.3");
+        assert_eq!(
+            "3.0.0".parse::<Bump>().unwrap(),
+            Bump::To(Version::new(3, 0, 0))
+        );
+
+        let header = "#define APP_VERSION \"1.4.2-rc.1\" /* kept */\n#define APP_VERSION_MINOR  4\n#define OTHER 1\n";
+        assert_eq!(
+            set_header_version(header, &version, &version.bumped(&Bump::Minor)).unwrap(),
+            "#define APP_VERSION \"1.5.0\" /* kept */\n#define APP_VERSION_MINOR  5\n#define OTHER 1\n"
+        );
+        assert!(set_header_version("#define OTHER 1\n", &version, &version).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::cli::CliError;
+
+/// When cedar colors its output.
+///
+/// # Members
+///
+/// * 'Auto' - Only when stdout is a terminal and NO_COLOR is not set.
+/// * 'Always' - Always, e.g. for CI logs that render ANSI escapes.
+/// * 'Never' - Never.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(CliError::InvalidArgument(format!(
+                "{:?}, --color expects auto, always, or never.",
+                s
+            ))),
+        }
+    }
+}
+
+const UNRESOLVED: u8 = 0;
+const ENABLED: u8 = 1;
+const DISABLED: u8 = 2;
+
+static COLOR: AtomicU8 = AtomicU8::new(UNRESOLVED);
+
+/// Sets when the output is colored, overriding the automatic detection.
+pub fn set_color(choice: ColorChoice) {
+    let state = match choice {
+        ColorChoice::Auto => UNRESOLVED,
+        ColorChoice::Always => ENABLED,
+        ColorChoice::Never => DISABLED,
+    };
+
+    COLOR.store(state, Ordering::Relaxed);
+}
+
+/// Whether the output is colored, detected on first use unless set_color
+/// was called.
+pub fn enabled() -> bool {
+    match COLOR.load(Ordering::Relaxed) {
+        ENABLED => true,
+        DISABLED => false,
+        _ => {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            let enabled = !no_color && io::stdout().is_terminal();
+
+            COLOR.store(if enabled { ENABLED } else { DISABLED }, Ordering::Relaxed);
+            enabled
+        }
+    }
+}
+
+/// Wraps text in an ANSI style if colors are enabled.
+fn paint(code: &str, text: &str) -> String {
+    if enabled() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Bold green, used for status verbs (Compiling, Finished) and help headings.
+pub fn header(text: &str) -> String {
+    paint("1;32", text)
+}
+
+/// Green.
+pub fn green(text: &str) -> String {
+    paint("32", text)
+}
+
+/// Bold red, used for failures.
+pub fn red(text: &str) -> String {
+    paint("1;31", text)
+}
+
+/// Bold.
+pub fn bold(text: &str) -> String {
+    paint("1", text)
+}
+
+/// Removes the ANSI escape sequences of text, e.g. the colors of compiler
+/// diagnostics before they are written to a log.
+pub fn strip(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\x1b' {
+            plain.push(ch);
+            continue;
+        }
+
+        // ESC [ <parameters> <final byte>
+        if chars.next() == Some('[') {
+            for ch in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&ch) {
+                    break;
+                }
+            }
+        }
+    }
+
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_escape_sequences() {
+        assert_eq!(
+            strip("a.c:1:1: \x1b[01;31m\x1b[Kerror: \x1b[m\x1b[Kexpected"),
+            "a.c:1:1: error: expected"
+        );
+        assert_eq!(strip("plain"), "plain");
+    }
+}